@@ -62,6 +62,14 @@ function getPoolVaultAuthorityPDA(programId: PublicKey): PublicKey {
   return pda;
 }
 
+function getReceiptMintPDA(programId: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("receipt_mint")],
+    programId
+  );
+  return pda;
+}
+
 function getUserStakePDA(
   programId: PublicKey,
   poolState: PublicKey,
@@ -172,6 +180,8 @@ class DepositMonitorService {
         poolVaultAuthority,
         true // allowOwnerOffCurve for PDA
       );
+      const receiptMint = getReceiptMintPDA(PROGRAM_ID);
+      const userReceiptAta = await getAssociatedTokenAddress(receiptMint, userPubkey);
 
       console.log("   User ATA:", userVoucherAta.toBase58());
       console.log("   Pool Vault:", poolVaultAta.toBase58());
@@ -185,6 +195,9 @@ class DepositMonitorService {
           userStakeRecord: userStakeRecord,
           userVoucherAta: userVoucherAta,
           poolVaultAta: poolVaultAta,
+          receiptMint: receiptMint,
+          userReceiptAta: userReceiptAta,
+          poolVaultAuthority: poolVaultAuthority,
           systemProgram: anchor.web3.SystemProgram.programId,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
@@ -0,0 +1,4090 @@
+//! Account lists for each instruction, one struct per `#[derive(Accounts)]` struct in the program
+//! Fields keep the program's order; `to_account_metas` appends the event CPI accounts where needed
+
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+
+use crate::pda::find_event_authority_pda;
+use crate::ID;
+
+/// Meta for an optional account, with the program id standing in for a missing one
+fn optional(key: Option<Pubkey>, is_writable: bool, is_signer: bool) -> AccountMeta {
+    match key {
+        Some(key) if is_writable => AccountMeta::new(key, is_signer),
+        Some(key) => AccountMeta::new_readonly(key, is_signer),
+        None => AccountMeta::new_readonly(ID, false),
+    }
+}
+
+/// The `event_authority` and `program` accounts every `#[event_cpi]` instruction takes last
+fn event_cpi_metas() -> [AccountMeta; 2] {
+    [
+        AccountMeta::new_readonly(find_event_authority_pda().0, false),
+        AccountMeta::new_readonly(ID, false),
+    ]
+}
+
+/// Accounts for `initialize_lokal_mint`
+#[derive(Clone, Copy, Debug)]
+pub struct InitializeLokalMint {
+    /// The authority that can update the mint configuration
+    /// This should be the program deployer or designated admin
+    pub update_authority: Pubkey,
+    /// The mint account for Lokal tokens
+    /// Created by the handler and owned by the token program, with the mint authority PDA
+    /// as mint and freeze authority; under Token-2022 the PDA is also the permanent
+    /// delegate, which lets a clawback move a blocked balance
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    /// This ensures only the program can mint new tokens
+    pub mint_authority: Pubkey,
+    /// Configuration account that stores mint metadata and settings
+    /// Uses PDA to ensure uniqueness and program ownership
+    pub config: Pubkey,
+    /// Registry that numbers merchants as they register
+    pub merchant_registry: Pubkey,
+    /// SPL Token program required for mint operations
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// Rent sysvar required for rent exemption calculations
+    pub rent: Pubkey,
+}
+
+impl InitializeLokalMint {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.update_authority, true),
+            AccountMeta::new(self.mint, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.merchant_registry, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.rent, false),
+        ]
+    }
+}
+
+/// Accounts for `mint_lokal_tokens`
+#[derive(Clone, Copy, Debug)]
+pub struct MintLokalTokens {
+    /// The authority that can mint tokens (must be the update authority)
+    pub authority: Pubkey,
+    /// The mint account for Lokal tokens
+    /// Must match the mint stored in config
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The token account that will receive the minted tokens
+    /// This should be the user's associated token account for Lokal tokens
+    pub destination: Pubkey,
+    /// SPL Token program for mint operations
+    pub token_program: Pubkey,
+    /// Second signer, required above the single-signature limit when a co-signer is set
+    pub cosigner: Option<Pubkey>,
+}
+
+impl MintLokalTokens {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.destination, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            optional(self.cosigner, false, true),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `reconcile_supply`
+#[derive(Clone, Copy, Debug)]
+pub struct ReconcileSupply {
+    /// Any wallet; must be the update authority to force a repair
+    pub caller: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The mint account for Lokal tokens
+    pub mint: Pubkey,
+}
+
+impl ReconcileSupply {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.caller, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.mint, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `register_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterMerchant {
+    /// The merchant's wallet that will own this merchant account
+    pub merchant_owner: Pubkey,
+    /// The merchant account to be created
+    /// Uses PDA for security and to prevent duplicate merchants
+    pub merchant_account: Pubkey,
+    /// Registry that numbers merchants as they register
+    /// Created here if the mint was initialized before the registry existed
+    pub merchant_registry: Pubkey,
+    /// Index entry pointing at the new merchant
+    pub merchant_index: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The franchise parent the merchant registers under, if any
+    pub parent_merchant: Option<Pubkey>,
+    /// The parent merchant's owner, co-signing registration under `parent_merchant`
+    pub parent_owner: Option<Pubkey>,
+    /// The token account redeemed tokens are paid to, normally the owner's associated
+    /// token account; when omitted, the first redemption locks in the account it pays
+    pub payout_token_account: Option<Pubkey>,
+    /// Configuration account containing mint settings
+    /// Writable so a high cashback alert can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl RegisterMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.merchant_registry, false),
+            AccountMeta::new(self.merchant_index, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.parent_merchant, true, false),
+            optional(self.parent_owner, false, true),
+            optional(self.payout_token_account, false, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `register_merchant_by_authority`
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterMerchantByAuthority {
+    /// The update authority of the mint configuration, paying for the new accounts
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the registration can take the next event sequence number
+    pub config: Pubkey,
+    /// The merchant account to be created, at the owner's usual address
+    pub merchant_account: Pubkey,
+    /// Registry that numbers merchants as they register
+    /// Created here if the mint was initialized before the registry existed
+    pub merchant_registry: Pubkey,
+    /// Index entry pointing at the new merchant
+    pub merchant_index: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl RegisterMerchantByAuthority {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.merchant_registry, false),
+            AccountMeta::new(self.merchant_index, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `accept_merchant_registration`
+#[derive(Clone, Copy, Debug)]
+pub struct AcceptMerchantRegistration {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to activate
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the acceptance can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl AcceptMerchantRegistration {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `process_purchase_with_redemption`, `process_purchase`, `pay_invoice`
+#[derive(Clone, Copy, Debug)]
+pub struct ProcessPurchase {
+    /// The customer making the purchase
+    pub customer: Pubkey,
+    /// The merchant account receiving the purchase
+    pub merchant_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The customer's token account that will receive the reward tokens and source for redemption
+    /// Either an existing Lokal token account, or the customer's associated token account,
+    /// created here on a first purchase when `associated_token_program` is passed
+    pub customer_token_account: Pubkey,
+    /// The merchant's token account (required when redeeming tokens, otherwise can be any account)
+    pub merchant_token_account: Pubkey,
+    /// Purchase transaction record for tracking, keyed by merchant and customer, or the
+    /// customer's hash in privacy mode
+    /// Omitted for merchants with compressed receipts, which extend their receipt chain instead
+    pub transaction_record: Option<Pubkey>,
+    /// SPL Token program for mint operations
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    pub stake_pool: Option<Pubkey>,
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Pubkey>,
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    pub activity_record: Option<Pubkey>,
+    /// Activity feed entry for the redeemed tokens, required only when activity recording
+    /// is enabled and the purchase redeems tokens
+    pub redemption_activity_record: Option<Pubkey>,
+    /// The configured treasury token account, required when a redemption owes a protocol fee
+    pub treasury_token_account: Option<Pubkey>,
+    /// The configured Pyth price account, required when the config sets a price feed
+    pub price_feed: Option<Pubkey>,
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    pub reward_escrow: Option<Pubkey>,
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    pub reward_lot: Option<Pubkey>,
+    /// The invoice being paid, only with `pay_invoice`
+    pub invoice: Option<Pubkey>,
+    /// The instructions sysvar, required when the merchant requires signed approvals
+    pub instructions_sysvar: Option<Pubkey>,
+    /// The POS key that approved the purchase, when it was not the merchant wallet
+    pub pos_key: Option<Pubkey>,
+    /// The merchant's franchise parent, required when the merchant has one
+    pub parent_merchant: Option<Pubkey>,
+    /// A sponsor's reward budget to pay the reward from instead of minting it
+    pub reward_budget: Option<Pubkey>,
+    /// The merchant's membership in `reward_budget`, checked in the handler
+    pub budget_membership: Option<Pubkey>,
+    /// Escrow holding sponsored budgets, required with `reward_budget`
+    pub reward_budget_escrow: Option<Pubkey>,
+    /// Associated Token program, required to create `customer_token_account` for a new customer
+    pub associated_token_program: Option<Pubkey>,
+    /// The operator that approved the purchase, when it was not the merchant wallet
+    pub merchant_operator: Option<Pubkey>,
+    /// The customer's passbook for the current month, created by their first purchase in it
+    pub customer_monthly_stats: Option<Pubkey>,
+    /// Slim purchase record, passed instead of `transaction_record` when the config sets
+    /// `slim_purchase_records`; keyed by the first 16 bytes of the transaction id
+    pub slim_transaction_record: Option<Pubkey>,
+    /// The protocol's boost for the merchant's category, adding to the reward while live
+    pub category_boost: Option<Pubkey>,
+    /// The customer's visits to the merchant, created by their first purchase there
+    /// Required when the merchant has a streak reward
+    pub customer_merchant_stats: Option<Pubkey>,
+    /// Durable stub of the purchase, kept when its record is closed
+    /// Required with either purchase record layout
+    pub transaction_stub: Option<Pubkey>,
+    /// Index from the purchase's reference code to its record, created with the record
+    /// Required with either purchase record layout; taken by an earlier purchase, it fails
+    /// this one with `ReferenceCodeCollision`
+    pub reference_index: Option<Pubkey>,
+}
+
+impl ProcessPurchase {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.merchant_token_account, false),
+            optional(self.transaction_record, true, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.stake_pool, false, false),
+            optional(self.customer_stake_record, false, false),
+            optional(self.activity_record, true, false),
+            optional(self.redemption_activity_record, true, false),
+            optional(self.treasury_token_account, true, false),
+            optional(self.price_feed, false, false),
+            optional(self.reward_escrow, true, false),
+            optional(self.reward_lot, true, false),
+            optional(self.invoice, true, false),
+            optional(self.instructions_sysvar, false, false),
+            optional(self.pos_key, false, false),
+            optional(self.parent_merchant, true, false),
+            optional(self.reward_budget, true, false),
+            optional(self.budget_membership, false, false),
+            optional(self.reward_budget_escrow, true, false),
+            optional(self.associated_token_program, false, false),
+            optional(self.merchant_operator, false, false),
+            optional(self.customer_monthly_stats, true, false),
+            optional(self.slim_transaction_record, true, false),
+            optional(self.category_boost, true, false),
+            optional(self.customer_merchant_stats, true, false),
+            optional(self.transaction_stub, true, false),
+            optional(self.reference_index, true, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_invoice`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateInvoice {
+    /// The merchant's owner wallet or one of its operators, paying for the invoice
+    pub merchant_owner: Pubkey,
+    /// The merchant the invoice is payable to
+    pub merchant_account: Pubkey,
+    /// The invoice to be created
+    pub invoice: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The signer's operator account, when the signer is not the merchant owner
+    pub merchant_operator: Option<Pubkey>,
+}
+
+impl CreateInvoice {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.invoice, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.merchant_operator, false, false),
+        ]
+    }
+}
+
+/// Accounts for `create_hold`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateHold {
+    /// The customer placing the hold, paying for the hold account
+    pub customer: Pubkey,
+    /// The merchant that may capture the hold
+    pub merchant_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The customer's token account the held tokens come from
+    pub customer_token_account: Pubkey,
+    /// Token account holding tokens until the hold is captured or released
+    pub hold_escrow: Pubkey,
+    /// The hold to be created
+    pub hold: Pubkey,
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateHold {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.hold_escrow, false),
+            AccountMeta::new(self.hold, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `capture_hold`
+#[derive(Clone, Copy, Debug)]
+pub struct CaptureHold {
+    /// The merchant's owner wallet, paying for the purchase record
+    pub merchant_owner: Pubkey,
+    /// The merchant capturing the hold
+    pub merchant_account: Pubkey,
+    /// The hold being captured, closed to the customer that paid for it
+    pub hold: Pubkey,
+    /// The customer that placed the hold
+    pub customer: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The customer's token account receiving the reward and the released remainder
+    pub customer_token_account: Pubkey,
+    /// The merchant's token account receiving the captured tokens
+    pub merchant_token_account: Pubkey,
+    /// Token account holding tokens until the hold is captured or released
+    pub hold_escrow: Pubkey,
+    /// Purchase transaction record for tracking
+    pub transaction_record: Pubkey,
+    /// SPL Token program for the transfers and reward mint
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    pub stake_pool: Option<Pubkey>,
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Pubkey>,
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    pub activity_record: Option<Pubkey>,
+    /// Activity feed entry for the captured tokens, required only when activity recording
+    /// is enabled and tokens are captured
+    pub redemption_activity_record: Option<Pubkey>,
+    /// The configured treasury token account, required when the capture owes a protocol fee
+    pub treasury_token_account: Option<Pubkey>,
+    /// The configured Pyth price account, required when the config sets a price feed
+    pub price_feed: Option<Pubkey>,
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    pub reward_escrow: Option<Pubkey>,
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    pub reward_lot: Option<Pubkey>,
+    /// The merchant's franchise parent, required when the merchant has one
+    pub parent_merchant: Option<Pubkey>,
+}
+
+impl CaptureHold {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.hold, false),
+            AccountMeta::new(self.customer, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.merchant_token_account, false),
+            AccountMeta::new(self.hold_escrow, false),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.stake_pool, false, false),
+            optional(self.customer_stake_record, false, false),
+            optional(self.activity_record, true, false),
+            optional(self.redemption_activity_record, true, false),
+            optional(self.treasury_token_account, true, false),
+            optional(self.price_feed, false, false),
+            optional(self.reward_escrow, true, false),
+            optional(self.reward_lot, true, false),
+            optional(self.parent_merchant, true, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `release_hold`
+#[derive(Clone, Copy, Debug)]
+pub struct ReleaseHold {
+    /// The customer that placed the hold
+    pub customer: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding tokens until the hold is captured or released
+    pub hold_escrow: Pubkey,
+    /// The customer's token account receiving the held tokens
+    pub customer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// The expired hold
+    pub hold: Pubkey,
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Pubkey,
+}
+
+impl ReleaseHold {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.hold_escrow, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.hold, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `open_dispute`
+#[derive(Clone, Copy, Debug)]
+pub struct OpenDispute {
+    /// The customer of the disputed purchase, paying for the dispute account
+    pub customer: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The disputed purchase, which must be the customer's own
+    /// Either seed scheme is accepted, so purchases recorded before records were keyed by
+    /// merchant can still be disputed
+    pub transaction_record: Pubkey,
+    /// The dispute to be created, at most one per purchase
+    pub dispute: Pubkey,
+    /// The purchase's stub, moved to disputed; created here for purchases recorded
+    /// before stubs existed
+    pub transaction_stub: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl OpenDispute {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new(self.dispute, false),
+            AccountMeta::new(self.transaction_stub, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `respond_dispute`
+#[derive(Clone, Copy, Debug)]
+pub struct RespondDispute {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant of the disputed purchase
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The dispute being answered
+    pub dispute: Pubkey,
+}
+
+impl RespondDispute {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.dispute, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `resolve_dispute`
+#[derive(Clone, Copy, Debug)]
+pub struct ResolveDispute {
+    /// The update authority of the mint configuration, paying for a missing stub
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The dispute being decided
+    pub dispute: Pubkey,
+    /// The disputed purchase
+    pub transaction_record: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// The customer's token account receiving any refund
+    pub customer_token_account: Pubkey,
+    /// SPL Token program for minting the refund
+    pub token_program: Pubkey,
+    /// The configured mint co-signer, required when the refund is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Pubkey>,
+    /// The purchase's stub, moved to refunded or resolved; created here for purchases recorded
+    /// before stubs existed
+    pub transaction_stub: Pubkey,
+    /// System program required for creating a missing stub
+    pub system_program: Pubkey,
+}
+
+impl ResolveDispute {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.dispute, false),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            optional(self.cosigner, false, true),
+            AccountMeta::new(self.transaction_stub, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `mark_fulfilled`
+#[derive(Clone, Copy, Debug)]
+pub struct MarkFulfilled {
+    /// The configured fulfillment authority, paying for a missing stub
+    pub fulfillment_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The fulfilled purchase
+    /// Records from before `PurchaseTransaction` outgrew 164 bytes must go through
+    /// `migrate_purchase_transaction` first
+    pub transaction_record: Pubkey,
+    /// The purchase's stub, moved to fulfilled; created here for purchases recorded
+    /// before stubs existed
+    pub transaction_stub: Pubkey,
+    /// System program required for creating a missing stub
+    pub system_program: Pubkey,
+}
+
+impl MarkFulfilled {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.fulfillment_authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new(self.transaction_stub, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `settle_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct SettleMerchant {
+    /// The merchant's owner wallet, paying for the settlement record
+    pub merchant_owner: Pubkey,
+    /// The merchant settling its redeemed tokens
+    pub merchant_account: Pubkey,
+    /// The merchant's token account the settled tokens are burned from
+    pub merchant_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Record of this settlement, at most one per merchant and period
+    pub settlement_record: Pubkey,
+    /// SPL Token program for the burn
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The configured Pyth price account, required when the config sets a price feed
+    pub price_feed: Option<Pubkey>,
+}
+
+impl SettleMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.merchant_token_account, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.settlement_record, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.price_feed, false, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_gift_card`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateGiftCard {
+    /// The wallet buying the gift card, paying for its accounts
+    pub issuer: Pubkey,
+    /// The issuer's token account the gift card is funded from
+    pub issuer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    pub gift_card_escrow: Pubkey,
+    /// The gift card to be created
+    pub gift_card: Pubkey,
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateGiftCard {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.issuer, true),
+            AccountMeta::new(self.issuer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.gift_card_escrow, false),
+            AccountMeta::new(self.gift_card, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `redeem_gift_card`
+#[derive(Clone, Copy, Debug)]
+pub struct RedeemGiftCard {
+    /// The wallet presenting the code
+    pub claimer: Pubkey,
+    /// The claimer's token account receiving the tokens
+    pub claimer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// The gift card's issuer, refunded the gift card's rent
+    pub issuer: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    pub gift_card_escrow: Pubkey,
+    /// The gift card, closed so its code cannot be replayed
+    pub gift_card: Pubkey,
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Pubkey,
+}
+
+impl RedeemGiftCard {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.claimer, true),
+            AccountMeta::new(self.claimer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.issuer, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.gift_card_escrow, false),
+            AccountMeta::new(self.gift_card, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `reclaim_gift_card`
+#[derive(Clone, Copy, Debug)]
+pub struct ReclaimGiftCard {
+    /// The wallet that bought the gift card
+    pub issuer: Pubkey,
+    /// The issuer's token account receiving the tokens
+    pub issuer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    pub gift_card_escrow: Pubkey,
+    /// The expired gift card
+    pub gift_card: Pubkey,
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Pubkey,
+}
+
+impl ReclaimGiftCard {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.issuer, true),
+            AccountMeta::new(self.issuer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.gift_card_escrow, false),
+            AccountMeta::new(self.gift_card, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_subscription`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateSubscription {
+    /// The subscribing customer, paying for the subscription account
+    pub customer: Pubkey,
+    /// The merchant being subscribed to
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The customer's token account charged each period
+    /// Token accounts hold a single delegate, so this replaces any earlier approval on it
+    pub customer_token_account: Pubkey,
+    /// The subscription to be created, at most one per customer and merchant
+    pub subscription: Pubkey,
+    /// SPL Token program for the delegate approval
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateSubscription {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.subscription, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `charge_subscription`
+#[derive(Clone, Copy, Debug)]
+pub struct ChargeSubscription {
+    /// The wallet cranking the charge, paying for the reward lot when one is needed
+    pub cranker: Pubkey,
+    /// The subscription being charged
+    pub subscription: Pubkey,
+    /// The merchant being paid
+    pub merchant_account: Pubkey,
+    /// The customer's token account the subscription was approved on
+    pub customer_token_account: Pubkey,
+    /// The merchant's token account receiving the charge
+    pub merchant_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// SPL Token program for the charge and reward mint
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The configured treasury token account, required when a rewarded charge owes a
+    /// protocol fee
+    pub treasury_token_account: Option<Pubkey>,
+    /// The configured Pyth price account, required for rewarded charges when the config
+    /// sets a price feed
+    pub price_feed: Option<Pubkey>,
+    /// Escrow receiving the reward, required for rewarded charges when expiring rewards
+    /// are enabled
+    pub reward_escrow: Option<Pubkey>,
+    /// Lot tracking the escrowed reward, required for rewarded charges when expiring
+    /// rewards are enabled
+    pub reward_lot: Option<Pubkey>,
+    /// The merchant's franchise parent, required for rewarded charges when the merchant
+    /// has one
+    pub parent_merchant: Option<Pubkey>,
+}
+
+impl ChargeSubscription {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.cranker, true),
+            AccountMeta::new(self.subscription, false),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.merchant_token_account, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.treasury_token_account, true, false),
+            optional(self.price_feed, false, false),
+            optional(self.reward_escrow, true, false),
+            optional(self.reward_lot, true, false),
+            optional(self.parent_merchant, true, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `cancel_subscription`
+#[derive(Clone, Copy, Debug)]
+pub struct CancelSubscription {
+    /// The subscribed customer
+    pub customer: Pubkey,
+    /// The subscription being cancelled, closed to the customer
+    pub subscription: Pubkey,
+    /// The customer's token account the subscription was approved on
+    pub customer_token_account: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// SPL Token program for revoking the approval
+    pub token_program: Pubkey,
+}
+
+impl CancelSubscription {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new(self.subscription, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_airdrop`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateAirdrop {
+    /// The update authority of the mint configuration, paying for the airdrop account
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The airdrop to be created
+    pub airdrop: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The configured mint co-signer, required when the budget is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Pubkey>,
+}
+
+impl CreateAirdrop {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.airdrop, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.cosigner, false, true),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `claim_airdrop`
+#[derive(Clone, Copy, Debug)]
+pub struct ClaimAirdrop {
+    /// The wallet claiming its allocation, paying for the claim marker
+    pub claimant: Pubkey,
+    /// The claimant's token account receiving the allocation
+    pub claimant_token_account: Pubkey,
+    /// The airdrop being claimed from
+    pub airdrop: Pubkey,
+    /// Marker created on the first claim, so each wallet can claim only once
+    pub airdrop_claim: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// SPL Token program for minting the allocation
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl ClaimAirdrop {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.claimant, true),
+            AccountMeta::new(self.claimant_token_account, false),
+            AccountMeta::new(self.airdrop, false),
+            AccountMeta::new(self.airdrop_claim, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `sweep_airdrop`
+#[derive(Clone, Copy, Debug)]
+pub struct SweepAirdrop {
+    /// The update authority of the mint configuration, refunded the airdrop's rent
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The expired airdrop
+    pub airdrop: Pubkey,
+}
+
+impl SweepAirdrop {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.airdrop, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_session_key`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateSessionKey {
+    /// The customer authorizing the session, paying for the session account
+    pub customer: Pubkey,
+    /// The customer's token account the allowance is escrowed from
+    pub customer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding session allowances until spent or revoked
+    pub session_escrow: Pubkey,
+    /// The session to be created
+    pub session_key: Pubkey,
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateSessionKey {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.session_escrow, false),
+            AccountMeta::new(self.session_key, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `process_purchase_with_session`
+#[derive(Clone, Copy, Debug)]
+pub struct ProcessPurchaseWithSession {
+    /// The session's ephemeral key, paying for the purchase records
+    pub session_signer: Pubkey,
+    /// The session the signer belongs to
+    pub session_key: Pubkey,
+    /// The merchant account receiving the purchase
+    pub merchant_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The customer's token account receiving the reward
+    pub customer_token_account: Pubkey,
+    /// The merchant's token account receiving redeemed tokens
+    pub merchant_token_account: Pubkey,
+    /// Token account holding session allowances until spent or revoked
+    pub session_escrow: Pubkey,
+    /// Purchase transaction record for tracking, keyed by merchant and customer like any purchase
+    pub transaction_record: Pubkey,
+    /// SPL Token program for the transfers and reward mint
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    pub stake_pool: Option<Pubkey>,
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Pubkey>,
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    pub activity_record: Option<Pubkey>,
+    /// Activity feed entry for the redeemed tokens, required only when activity recording
+    /// is enabled and the purchase redeems tokens
+    pub redemption_activity_record: Option<Pubkey>,
+    /// The configured treasury token account, required when a redemption owes a protocol fee
+    pub treasury_token_account: Option<Pubkey>,
+    /// The configured Pyth price account, required when the config sets a price feed
+    pub price_feed: Option<Pubkey>,
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    pub reward_escrow: Option<Pubkey>,
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    pub reward_lot: Option<Pubkey>,
+    /// The instructions sysvar, required when the merchant requires signed approvals
+    pub instructions_sysvar: Option<Pubkey>,
+    /// The POS key that approved the purchase, when it was not the merchant wallet
+    pub pos_key: Option<Pubkey>,
+    /// The merchant's franchise parent, required when the merchant has one
+    pub parent_merchant: Option<Pubkey>,
+    /// The operator that approved the purchase, when it was not the merchant wallet
+    pub merchant_operator: Option<Pubkey>,
+}
+
+impl ProcessPurchaseWithSession {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.session_signer, true),
+            AccountMeta::new(self.session_key, false),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new(self.merchant_token_account, false),
+            AccountMeta::new(self.session_escrow, false),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.stake_pool, false, false),
+            optional(self.customer_stake_record, false, false),
+            optional(self.activity_record, true, false),
+            optional(self.redemption_activity_record, true, false),
+            optional(self.treasury_token_account, true, false),
+            optional(self.price_feed, false, false),
+            optional(self.reward_escrow, true, false),
+            optional(self.reward_lot, true, false),
+            optional(self.instructions_sysvar, false, false),
+            optional(self.pos_key, false, false),
+            optional(self.parent_merchant, true, false),
+            optional(self.merchant_operator, false, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `revoke_session_key`
+#[derive(Clone, Copy, Debug)]
+pub struct RevokeSessionKey {
+    /// The customer that authorized the session
+    pub customer: Pubkey,
+    /// The customer's token account receiving the unspent allowance
+    pub customer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding session allowances until spent or revoked
+    pub session_escrow: Pubkey,
+    /// The session being revoked, closed to the customer
+    pub session_key: Pubkey,
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Pubkey,
+}
+
+impl RevokeSessionKey {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.session_escrow, false),
+            AccountMeta::new(self.session_key, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `update_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct UpdateMerchant {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to update
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    pub config: Pubkey,
+    /// The update authority, co-signing to lift the cashback rate cooldown and increase cap
+    pub update_authority: Option<Pubkey>,
+}
+
+impl UpdateMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+            optional(self.update_authority, false, true),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `register_pos_key`
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterPosKey {
+    /// The merchant's owner wallet, paying for the POS key account
+    pub merchant_owner: Pubkey,
+    /// The merchant the key approves purchases for
+    pub merchant_account: Pubkey,
+    /// The POS key to be registered
+    pub pos_key_account: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl RegisterPosKey {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.pos_key_account, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `remove_pos_key`
+#[derive(Clone, Copy, Debug)]
+pub struct RemovePosKey {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant the key approved purchases for
+    pub merchant_account: Pubkey,
+    /// The POS key to be removed, closed to the merchant owner
+    pub pos_key_account: Pubkey,
+}
+
+impl RemovePosKey {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.pos_key_account, false),
+        ]
+    }
+}
+
+/// Accounts for `add_merchant_operator`
+#[derive(Clone, Copy, Debug)]
+pub struct AddMerchantOperator {
+    /// The merchant's owner wallet, paying for the operator account
+    pub merchant_owner: Pubkey,
+    /// The merchant the operator acts for
+    pub merchant_account: Pubkey,
+    /// The operator to be added
+    pub merchant_operator: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl AddMerchantOperator {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.merchant_operator, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `remove_merchant_operator`
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveMerchantOperator {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant the operator acted for
+    pub merchant_account: Pubkey,
+    /// The operator to be removed, closed to the merchant owner
+    pub merchant_operator: Pubkey,
+}
+
+impl RemoveMerchantOperator {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.merchant_operator, false),
+        ]
+    }
+}
+
+/// Accounts for `create_parent_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateParentMerchant {
+    /// The brand's owner wallet, paying for the parent account
+    pub owner: Pubkey,
+    /// The parent merchant to be created
+    pub parent_merchant: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateParentMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.owner, true),
+            AccountMeta::new(self.parent_merchant, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `update_parent_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct UpdateParentMerchant {
+    /// The brand's owner wallet
+    pub owner: Pubkey,
+    /// The parent merchant to update
+    pub parent_merchant: Pubkey,
+}
+
+impl UpdateParentMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new(self.parent_merchant, false),
+        ]
+    }
+}
+
+/// Accounts for `cascade_cashback_rate`
+#[derive(Clone, Copy, Debug)]
+pub struct CascadeCashbackRate {
+    /// The brand's owner wallet
+    pub owner: Pubkey,
+    /// The parent merchant whose default rate is applied
+    pub parent_merchant: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so each outlet's change can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl CascadeCashbackRate {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(self.parent_merchant, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_reward_budget`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateRewardBudget {
+    /// The wallet sponsoring the budget, paying for its accounts
+    pub sponsor: Pubkey,
+    /// The sponsor's token account the budget is funded from
+    pub sponsor_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    pub reward_budget_escrow: Pubkey,
+    /// The reward budget to be created
+    pub reward_budget: Pubkey,
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl CreateRewardBudget {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.sponsor, true),
+            AccountMeta::new(self.sponsor_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.reward_budget_escrow, false),
+            AccountMeta::new(self.reward_budget, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `top_up_reward_budget`
+#[derive(Clone, Copy, Debug)]
+pub struct TopUpRewardBudget {
+    /// The budget's sponsor
+    pub sponsor: Pubkey,
+    /// The sponsor's token account the tokens come from
+    pub sponsor_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    pub reward_budget_escrow: Pubkey,
+    /// The reward budget being topped up
+    pub reward_budget: Pubkey,
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Pubkey,
+}
+
+impl TopUpRewardBudget {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.sponsor, true),
+            AccountMeta::new(self.sponsor_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.reward_budget_escrow, false),
+            AccountMeta::new(self.reward_budget, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `withdraw_reward_budget`
+#[derive(Clone, Copy, Debug)]
+pub struct WithdrawRewardBudget {
+    /// The budget's sponsor
+    pub sponsor: Pubkey,
+    /// The sponsor's token account receiving the tokens
+    pub sponsor_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    pub reward_budget_escrow: Pubkey,
+    /// The reward budget being withdrawn from
+    pub reward_budget: Pubkey,
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Pubkey,
+}
+
+impl WithdrawRewardBudget {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.sponsor, true),
+            AccountMeta::new(self.sponsor_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.reward_budget_escrow, false),
+            AccountMeta::new(self.reward_budget, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `add_budget_member`
+#[derive(Clone, Copy, Debug)]
+pub struct AddBudgetMember {
+    /// The budget's sponsor, paying for the membership
+    pub sponsor: Pubkey,
+    /// The reward budget the merchant joins
+    pub reward_budget: Pubkey,
+    /// The merchant being sponsored
+    pub merchant_account: Pubkey,
+    /// The membership to be created
+    pub budget_membership: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl AddBudgetMember {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.sponsor, true),
+            AccountMeta::new(self.reward_budget, false),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new(self.budget_membership, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `remove_budget_member`
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveBudgetMember {
+    /// The budget's sponsor, refunded the membership's rent
+    pub sponsor: Pubkey,
+    /// The reward budget the merchant leaves
+    pub reward_budget: Pubkey,
+    /// The membership to be removed
+    pub budget_membership: Pubkey,
+}
+
+impl RemoveBudgetMember {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.sponsor, true),
+            AccountMeta::new(self.reward_budget, false),
+            AccountMeta::new(self.budget_membership, false),
+        ]
+    }
+}
+
+/// Accounts for `set_category_boost`
+#[derive(Clone, Copy, Debug)]
+pub struct SetCategoryBoost {
+    /// The update authority of the mint configuration, pays for a new boost
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The category's boost (PDA), created on first use
+    pub category_boost: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl SetCategoryBoost {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.category_boost, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `close_category_boost`
+#[derive(Clone, Copy, Debug)]
+pub struct CloseCategoryBoost {
+    /// The update authority of the mint configuration, receives the rent
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The boost to remove
+    pub category_boost: Pubkey,
+}
+
+impl CloseCategoryBoost {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.category_boost, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_payout_account`
+#[derive(Clone, Copy, Debug)]
+pub struct SetPayoutAccount {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to update
+    pub merchant_account: Pubkey,
+    /// The new payout token account
+    pub payout_token_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl SetPayoutAccount {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new_readonly(self.payout_token_account, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `propose_merchant_owner`
+#[derive(Clone, Copy, Debug)]
+pub struct ProposeMerchantOwner {
+    /// The merchant's current owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to hand over
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the proposal can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl ProposeMerchantOwner {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `accept_merchant_ownership`
+#[derive(Clone, Copy, Debug)]
+pub struct AcceptMerchantOwnership {
+    /// The proposed owner's wallet
+    pub new_owner: Pubkey,
+    /// The merchant account to take over, which keeps its address
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the transfer can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl AcceptMerchantOwnership {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.new_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_streak_reward`
+#[derive(Clone, Copy, Debug)]
+pub struct SetStreakReward {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to update
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    pub config: Pubkey,
+}
+
+impl SetStreakReward {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `close_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct CloseMerchant {
+    /// The merchant's owner wallet
+    pub merchant_owner: Pubkey,
+    /// The merchant account to close
+    pub merchant_account: Pubkey,
+    /// The merchant's index entry, marked closed
+    pub merchant_index: Pubkey,
+}
+
+impl CloseMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.merchant_owner, true),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.merchant_index, false),
+        ]
+    }
+}
+
+/// Accounts for `set_staker_boost`
+#[derive(Clone, Copy, Debug)]
+pub struct SetStakerBoost {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetStakerBoost {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_mint_cosigner`
+#[derive(Clone, Copy, Debug)]
+pub struct SetMintCosigner {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetMintCosigner {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_limits`
+#[derive(Clone, Copy, Debug)]
+pub struct SetLimits {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetLimits {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_mint_caps`
+#[derive(Clone, Copy, Debug)]
+pub struct SetMintCaps {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetMintCaps {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_rate_change_policy`
+#[derive(Clone, Copy, Debug)]
+pub struct SetRateChangePolicy {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetRateChangePolicy {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_cashback_limits`
+#[derive(Clone, Copy, Debug)]
+pub struct SetCashbackLimits {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetCashbackLimits {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_price_feed`
+#[derive(Clone, Copy, Debug)]
+pub struct SetPriceFeed {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetPriceFeed {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_dispute_window`
+#[derive(Clone, Copy, Debug)]
+pub struct SetDisputeWindow {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetDisputeWindow {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_fulfillment_authority`
+#[derive(Clone, Copy, Debug)]
+pub struct SetFulfillmentAuthority {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetFulfillmentAuthority {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_reward_expiry`
+#[derive(Clone, Copy, Debug)]
+pub struct SetRewardExpiry {
+    /// The update authority of the mint configuration, paying for the escrow
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Token account holding rewards until they are withdrawn or expire
+    pub reward_escrow: Pubkey,
+    /// SPL Token program for creating the escrow
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl SetRewardExpiry {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.reward_escrow, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `expire_rewards`
+#[derive(Clone, Copy, Debug)]
+pub struct ExpireRewards {
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding rewards until they are withdrawn or expire
+    pub reward_escrow: Pubkey,
+    /// The expired reward lot
+    pub reward_lot: Pubkey,
+    /// SPL Token program for burning the expired rewards
+    pub token_program: Pubkey,
+}
+
+impl ExpireRewards {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.reward_escrow, false),
+            AccountMeta::new(self.reward_lot, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `withdraw_vested_rewards`
+#[derive(Clone, Copy, Debug)]
+pub struct WithdrawVestedRewards {
+    /// The customer that earned the reward
+    pub customer: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding rewards until they are withdrawn or expire
+    pub reward_escrow: Pubkey,
+    /// The customer's token account receiving the reward
+    pub customer_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// The reward lot to withdraw
+    pub reward_lot: Pubkey,
+    /// SPL Token program for the transfer
+    pub token_program: Pubkey,
+}
+
+impl WithdrawVestedRewards {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.customer, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.reward_escrow, false),
+            AccountMeta::new(self.customer_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.reward_lot, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `create_vesting_grant`
+#[derive(Clone, Copy, Debug)]
+pub struct CreateVestingGrant {
+    /// The update authority of the mint configuration, paying for the grant
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Token account holding granted tokens until they are claimed or revoked
+    pub vesting_escrow: Pubkey,
+    /// The grant record
+    pub vesting_grant: Pubkey,
+    /// SPL Token program for minting the grant
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// The configured mint co-signer, required when the grant is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Pubkey>,
+}
+
+impl CreateVestingGrant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.vesting_escrow, false),
+            AccountMeta::new(self.vesting_grant, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.cosigner, false, true),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `claim_vested`
+#[derive(Clone, Copy, Debug)]
+pub struct ClaimVested {
+    /// The grant's beneficiary
+    pub beneficiary: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding granted tokens until they are claimed or revoked
+    pub vesting_escrow: Pubkey,
+    /// The grant being claimed
+    pub vesting_grant: Pubkey,
+    /// The beneficiary's token account receiving the vested tokens
+    pub beneficiary_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// SPL Token program for the transfer
+    pub token_program: Pubkey,
+}
+
+impl ClaimVested {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.beneficiary, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.vesting_escrow, false),
+            AccountMeta::new(self.vesting_grant, false),
+            AccountMeta::new(self.beneficiary_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `revoke_vesting_grant`
+#[derive(Clone, Copy, Debug)]
+pub struct RevokeVestingGrant {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Token account holding granted tokens until they are claimed or revoked
+    pub vesting_escrow: Pubkey,
+    /// The grant being revoked
+    pub vesting_grant: Pubkey,
+    /// The configured treasury token account, receiving the unvested tokens
+    pub treasury_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// SPL Token program for the transfer
+    pub token_program: Pubkey,
+}
+
+impl RevokeVestingGrant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.vesting_escrow, false),
+            AccountMeta::new(self.vesting_grant, false),
+            AccountMeta::new(self.treasury_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_activity_recording`
+#[derive(Clone, Copy, Debug)]
+pub struct SetActivityRecording {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetActivityRecording {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_slim_purchase_records`
+#[derive(Clone, Copy, Debug)]
+pub struct SetSlimPurchaseRecords {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetSlimPurchaseRecords {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_customer_hashing`
+#[derive(Clone, Copy, Debug)]
+pub struct SetCustomerHashing {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetCustomerHashing {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_protocol_fee`
+#[derive(Clone, Copy, Debug)]
+pub struct SetProtocolFee {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// PDA that must own the treasury, so only `withdraw_treasury` can move fees out
+    pub treasury_authority: Pubkey,
+    /// Lokal token account that will receive protocol fees
+    pub treasury_token_account: Pubkey,
+}
+
+impl SetProtocolFee {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.treasury_authority, false),
+            AccountMeta::new_readonly(self.treasury_token_account, false),
+        ]
+    }
+}
+
+/// Accounts for `set_transfer_fee`
+#[derive(Clone, Copy, Debug)]
+pub struct SetTransferFee {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetTransferFee {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `set_treasury_controls`
+#[derive(Clone, Copy, Debug)]
+pub struct SetTreasuryControls {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetTreasuryControls {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `withdraw_treasury`
+#[derive(Clone, Copy, Debug)]
+pub struct WithdrawTreasury {
+    /// The update authority of the mint configuration, pays for the record
+    pub authority: Pubkey,
+    /// Second signer, required when the config sets a treasury co-signer
+    pub cosigner: Option<Pubkey>,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// PDA that owns the treasury token account
+    pub treasury_authority: Pubkey,
+    /// The configured treasury token account
+    pub treasury_token_account: Pubkey,
+    /// Token account receiving the withdrawn fees
+    pub destination: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Record of this withdrawal
+    pub withdrawal_record: Pubkey,
+    /// SPL Token program for transfer operations
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl WithdrawTreasury {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            optional(self.cosigner, false, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.treasury_authority, false),
+            AccountMeta::new(self.treasury_token_account, false),
+            AccountMeta::new(self.destination, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.withdrawal_record, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `restrict_account`
+#[derive(Clone, Copy, Debug)]
+pub struct RestrictAccount {
+    /// The update authority of the mint configuration, pays for the restriction
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The token account to block
+    pub token_account: Pubkey,
+    /// Restriction marking the account as blocked
+    pub restriction: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// PDA that is the mint's freeze authority
+    pub mint_authority: Pubkey,
+    /// SPL Token program for the freeze
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl RestrictAccount {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.token_account, false),
+            AccountMeta::new(self.restriction, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `lift_account_restriction`
+#[derive(Clone, Copy, Debug)]
+pub struct LiftAccountRestriction {
+    /// The update authority of the mint configuration, receives the restriction's rent
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The blocked token account
+    pub token_account: Pubkey,
+    /// Restriction to lift
+    pub restriction: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// PDA that is the mint's freeze authority
+    pub mint_authority: Pubkey,
+    /// SPL Token program for the thaw
+    pub token_program: Pubkey,
+}
+
+impl LiftAccountRestriction {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.token_account, false),
+            AccountMeta::new(self.restriction, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `clawback`
+#[derive(Clone, Copy, Debug)]
+pub struct Clawback {
+    /// The update authority of the mint configuration, pays for the record
+    pub authority: Pubkey,
+    /// The configured mint co-signer; always required for a clawback
+    pub cosigner: Option<Pubkey>,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The blocked token account
+    pub token_account: Pubkey,
+    /// Restriction the clawback relies on
+    pub restriction: Pubkey,
+    /// The configured treasury token account
+    pub treasury_token_account: Pubkey,
+    /// Record of this clawback
+    pub clawback_record: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// PDA that is the mint's freeze authority and permanent delegate
+    pub mint_authority: Pubkey,
+    /// Token-2022 program for the transfer
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl Clawback {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.authority, true),
+            optional(self.cosigner, false, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.token_account, false),
+            AccountMeta::new(self.restriction, false),
+            AccountMeta::new(self.treasury_token_account, false),
+            AccountMeta::new(self.clawback_record, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_param_timelock`
+#[derive(Clone, Copy, Debug)]
+pub struct SetParamTimelock {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetParamTimelock {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ]
+    }
+}
+
+/// Accounts for `queue_param_change`
+#[derive(Clone, Copy, Debug)]
+pub struct QueueParamChange {
+    /// The authority proposing the change, pays for the pending change account
+    pub proposer: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The pool whose configuration changes, only for `ParamChange::PoolConfig` and
+    /// `ParamChange::AdvanceLtv`
+    pub pool_state: Option<Pubkey>,
+    /// The queued change
+    pub pending_change: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl QueueParamChange {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.proposer, true),
+            AccountMeta::new(self.config, false),
+            optional(self.pool_state, false, false),
+            AccountMeta::new(self.pending_change, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `execute_param_change`
+#[derive(Clone, Copy, Debug)]
+pub struct ExecuteParamChange {
+    /// The proposer of the change, receives the pending change account's rent
+    pub proposer: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The pool whose configuration changes, only for `ParamChange::PoolConfig` and
+    /// `ParamChange::AdvanceLtv`
+    pub pool_state: Option<Pubkey>,
+    /// The queued change
+    pub pending_change: Pubkey,
+}
+
+impl ExecuteParamChange {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.proposer, false),
+            AccountMeta::new(self.config, false),
+            optional(self.pool_state, true, false),
+            AccountMeta::new(self.pending_change, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `cancel_param_change`
+#[derive(Clone, Copy, Debug)]
+pub struct CancelParamChange {
+    /// The authority that queued the change
+    pub proposer: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The queued change
+    pub pending_change: Pubkey,
+}
+
+impl CancelParamChange {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.proposer, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.pending_change, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_guardian`
+#[derive(Clone, Copy, Debug)]
+pub struct SetGuardian {
+    /// The update authority of the mint configuration
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetGuardian {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_global_pause`
+#[derive(Clone, Copy, Debug)]
+pub struct SetGlobalPause {
+    /// The update authority or guardian, checked in the handler
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+}
+
+impl SetGlobalPause {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_merchant_suspension`
+#[derive(Clone, Copy, Debug)]
+pub struct SetMerchantSuspension {
+    /// The update authority or guardian, checked in the handler
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The merchant to suspend or reinstate
+    pub merchant_account: Pubkey,
+}
+
+impl SetMerchantSuspension {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.merchant_account, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `preview_purchase_reward`
+#[derive(Clone, Copy, Debug)]
+pub struct PreviewPurchaseReward {
+    /// The customer the purchase would be made by
+    pub customer: Pubkey,
+    /// The merchant account the purchase would be made at
+    pub merchant_account: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    pub stake_pool: Option<Pubkey>,
+    /// The customer's stake record in `stake_pool`, to include the staker boost
+    pub customer_stake_record: Option<Pubkey>,
+    /// The configured Pyth price account, required when the config sets a price feed
+    pub price_feed: Option<Pubkey>,
+    /// The protocol's boost for the merchant's category, to include it while live
+    pub category_boost: Option<Pubkey>,
+}
+
+impl PreviewPurchaseReward {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.customer, false),
+            AccountMeta::new_readonly(self.merchant_account, false),
+            AccountMeta::new_readonly(self.config, false),
+            optional(self.stake_pool, false, false),
+            optional(self.customer_stake_record, false, false),
+            optional(self.price_feed, false, false),
+            optional(self.category_boost, false, false),
+        ]
+    }
+}
+
+/// Accounts for `get_program_status`
+#[derive(Clone, Copy, Debug)]
+pub struct GetProgramStatus {
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// The voucher pool to report on
+    pub pool_state: Pubkey,
+    /// The pool's vault
+    pub vault_ata: Pubkey,
+}
+
+impl GetProgramStatus {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.pool_state, false),
+            AccountMeta::new_readonly(self.vault_ata, false),
+        ]
+    }
+}
+
+/// Accounts for `transfer_tokens`
+#[derive(Clone, Copy, Debug)]
+pub struct TransferTokens {
+    /// The sender of the tokens
+    pub sender: Pubkey,
+    /// The sender's token account
+    pub sender_token_account: Pubkey,
+    /// The recipient's token account
+    pub recipient_token_account: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Configuration account containing mint settings
+    /// Writable so the transfer can take the next event sequence number
+    pub config: Pubkey,
+    /// Transfer record for tracking
+    pub transfer_record: Pubkey,
+    /// SPL Token program for transfer operations
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+    /// Activity feed entry for the sender, required only when activity recording is enabled
+    pub activity_record: Option<Pubkey>,
+    /// The sender's fee exemption, waiving the transfer fee
+    pub fee_exemption: Option<Pubkey>,
+    /// The configured treasury token account, required when a transfer fee is owed
+    pub treasury_token_account: Option<Pubkey>,
+}
+
+impl TransferTokens {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.sender, true),
+            AccountMeta::new(self.sender_token_account, false),
+            AccountMeta::new(self.recipient_token_account, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.transfer_record, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            optional(self.activity_record, true, false),
+            optional(self.fee_exemption, false, false),
+            optional(self.treasury_token_account, true, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `add_fee_exemption`
+#[derive(Clone, Copy, Debug)]
+pub struct AddFeeExemption {
+    /// The update authority of the mint configuration, pays for the exemption
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The wallet being exempted
+    pub wallet: Pubkey,
+    /// The exemption (PDA)
+    pub fee_exemption: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl AddFeeExemption {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new_readonly(self.wallet, false),
+            AccountMeta::new(self.fee_exemption, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `remove_fee_exemption`
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveFeeExemption {
+    /// The update authority of the mint configuration, receives the rent
+    pub authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The exemption to remove
+    pub fee_exemption: Pubkey,
+}
+
+impl RemoveFeeExemption {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.authority, true),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new(self.fee_exemption, false),
+        ]
+    }
+}
+
+/// Accounts for `initialize_pool`
+#[derive(Clone, Copy, Debug)]
+pub struct InitializePool {
+    /// The authority that manages the pool (admin)
+    pub pool_authority: Pubkey,
+    /// The delegate authority that can execute deposits on behalf of users
+    pub pool_delegate: Pubkey,
+    /// The voucher token mint (LOKAL token), part of every pool PDA's seeds
+    pub voucher_mint: Pubkey,
+    /// The pool state account (PDA)
+    pub pool_state: Pubkey,
+    /// The vault authority PDA (owns the vault_ata)
+    pub pool_vault_authority: Pubkey,
+    /// The vault token account that will hold staked voucher tokens
+    /// Created as the vault authority's associated token account if it doesn't exist yet
+    pub vault_ata: Pubkey,
+    /// The sLOKAL receipt mint (PDA), minted by the vault authority
+    pub receipt_mint: Pubkey,
+    /// System program for account creation
+    pub system_program: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+    /// Associated token program for creating the vault
+    pub associated_token_program: Pubkey,
+}
+
+impl InitializePool {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_authority, true),
+            AccountMeta::new_readonly(self.pool_delegate, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new(self.vault_ata, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `deposit_voucher`
+#[derive(Clone, Copy, Debug)]
+pub struct DepositVoucher {
+    /// The user whose tokens are being deposited
+    /// Must co-sign when the pool is configured to auto-revoke leftover delegation
+    pub user: Pubkey,
+    /// The delegate authority executing this instruction on behalf of the user
+    /// Must match the pool_delegate in pool_state
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record (created if doesn't exist)
+    pub user_stake_record: Pubkey,
+    /// The user's whitelist approval, required only when the pool is whitelist-gated
+    pub stake_whitelist_entry: Option<Pubkey>,
+    /// History entry for this deposit, required only when the pool records history
+    pub stake_action_record: Option<Pubkey>,
+    /// User's voucher token account (source)
+    /// Must have the pool delegate approved as its SPL delegate
+    pub user_voucher_ata: Pubkey,
+    /// Pool vault token account (destination)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// User's receipt token account (created if it doesn't exist)
+    pub user_receipt_ata: Pubkey,
+    /// Pool vault authority PDA (receipt mint authority)
+    pub pool_vault_authority: Pubkey,
+    /// System program for account creation
+    pub system_program: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Pubkey,
+}
+
+impl DepositVoucher {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.user, false),
+            AccountMeta::new(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            optional(self.stake_whitelist_entry, false, false),
+            optional(self.stake_action_record, true, false),
+            AccountMeta::new(self.user_voucher_ata, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `deposit_for_beneficiary`
+#[derive(Clone, Copy, Debug)]
+pub struct DepositForBeneficiary {
+    /// The wallet funding the deposit, which also pays for any accounts it creates
+    pub funder: Pubkey,
+    /// The wallet the stake belongs to
+    pub beneficiary: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Beneficiary's stake record (created if doesn't exist)
+    pub user_stake_record: Pubkey,
+    /// The beneficiary's whitelist approval, required only when the pool is whitelist-gated
+    pub stake_whitelist_entry: Option<Pubkey>,
+    /// History entry for this deposit, required only when the pool records history
+    pub stake_action_record: Option<Pubkey>,
+    /// Funder's voucher token account (source)
+    pub funder_voucher_ata: Pubkey,
+    /// Pool vault token account (destination)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// Beneficiary's receipt token account (created if it doesn't exist)
+    pub beneficiary_receipt_ata: Pubkey,
+    /// Pool vault authority PDA (receipt mint authority)
+    pub pool_vault_authority: Pubkey,
+    /// System program for account creation
+    pub system_program: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Pubkey,
+}
+
+impl DepositForBeneficiary {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.funder, true),
+            AccountMeta::new_readonly(self.beneficiary, false),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            optional(self.stake_whitelist_entry, false, false),
+            optional(self.stake_action_record, true, false),
+            AccountMeta::new(self.funder_voucher_ata, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.beneficiary_receipt_ata, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `record_yield`
+#[derive(Clone, Copy, Debug)]
+pub struct RecordYield {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Batch marker, initialization fails if this batch was already recorded
+    pub yield_batch: Pubkey,
+    pub system_program: Pubkey,
+}
+
+impl RecordYield {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.yield_batch, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `reset_external_sequence`
+#[derive(Clone, Copy, Debug)]
+pub struct ResetExternalSequence {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl ResetExternalSequence {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `configure_stake_pool`
+#[derive(Clone, Copy, Debug)]
+pub struct ConfigureStakePool {
+    /// The pool authority (admin), pays for the new accounts
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The pool's deployment record
+    pub stake_pool_deployment: Pubkey,
+    /// The SPL stake-pool program
+    pub stake_pool_program: Pubkey,
+    /// The stake pool to deposit into
+    pub stake_pool: Pubkey,
+    /// The stake pool's token mint
+    pub pool_token_mint: Pubkey,
+    /// Token account holding the pool tokens, owned by the deployment record
+    pub pool_token_account: Pubkey,
+    /// System account holding SOL waiting to be deployed
+    pub sol_vault: Pubkey,
+    /// Token program owning the stake pool's mint
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl ConfigureStakePool {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.stake_pool_deployment, false),
+            AccountMeta::new_readonly(self.stake_pool_program, false),
+            AccountMeta::new_readonly(self.stake_pool, false),
+            AccountMeta::new_readonly(self.pool_token_mint, false),
+            AccountMeta::new(self.pool_token_account, false),
+            AccountMeta::new_readonly(self.sol_vault, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `deploy_to_stake_pool`
+#[derive(Clone, Copy, Debug)]
+pub struct DeployToStakePool {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The pool's deployment record
+    pub stake_pool_deployment: Pubkey,
+    /// System account holding SOL waiting to be deployed, signs the deposit
+    pub sol_vault: Pubkey,
+    /// The SPL stake-pool program
+    pub stake_pool_program: Pubkey,
+    /// The stake pool
+    pub stake_pool: Pubkey,
+    /// The stake pool's withdraw authority
+    pub stake_pool_withdraw_authority: Pubkey,
+    /// The stake pool's reserve stake account
+    pub reserve_stake: Pubkey,
+    /// The stake pool manager's fee account
+    pub manager_fee_account: Pubkey,
+    /// The stake pool's token mint
+    pub pool_token_mint: Pubkey,
+    /// Token account holding the pool tokens
+    pub pool_token_account: Pubkey,
+    /// Token program owning the stake pool's mint
+    pub token_program: Pubkey,
+    /// System program, which moves the deposited lamports
+    pub system_program: Pubkey,
+}
+
+impl DeployToStakePool {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.stake_pool_deployment, false),
+            AccountMeta::new(self.sol_vault, false),
+            AccountMeta::new_readonly(self.stake_pool_program, false),
+            AccountMeta::new(self.stake_pool, false),
+            AccountMeta::new_readonly(self.stake_pool_withdraw_authority, false),
+            AccountMeta::new(self.reserve_stake, false),
+            AccountMeta::new(self.manager_fee_account, false),
+            AccountMeta::new(self.pool_token_mint, false),
+            AccountMeta::new(self.pool_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `unwind_from_stake_pool`
+#[derive(Clone, Copy, Debug)]
+pub struct UnwindFromStakePool {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The pool's deployment record, signs for the pool tokens it owns
+    pub stake_pool_deployment: Pubkey,
+    /// System account receiving the withdrawn SOL
+    pub sol_vault: Pubkey,
+    /// The SPL stake-pool program
+    pub stake_pool_program: Pubkey,
+    /// The stake pool
+    pub stake_pool: Pubkey,
+    /// The stake pool's withdraw authority
+    pub stake_pool_withdraw_authority: Pubkey,
+    /// The stake pool's reserve stake account
+    pub reserve_stake: Pubkey,
+    /// The stake pool manager's fee account
+    pub manager_fee_account: Pubkey,
+    /// The stake pool's token mint
+    pub pool_token_mint: Pubkey,
+    /// Token account holding the pool tokens
+    pub pool_token_account: Pubkey,
+    /// Clock sysvar, read by the stake program
+    pub clock: Pubkey,
+    /// Stake history sysvar, read by the stake program
+    pub stake_history: Pubkey,
+    /// The native stake program, which pays out of the reserve
+    pub stake_program: Pubkey,
+    /// Token program owning the stake pool's mint
+    pub token_program: Pubkey,
+}
+
+impl UnwindFromStakePool {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.stake_pool_deployment, false),
+            AccountMeta::new(self.sol_vault, false),
+            AccountMeta::new_readonly(self.stake_pool_program, false),
+            AccountMeta::new(self.stake_pool, false),
+            AccountMeta::new_readonly(self.stake_pool_withdraw_authority, false),
+            AccountMeta::new(self.reserve_stake, false),
+            AccountMeta::new(self.manager_fee_account, false),
+            AccountMeta::new(self.pool_token_mint, false),
+            AccountMeta::new(self.pool_token_account, false),
+            AccountMeta::new_readonly(self.clock, false),
+            AccountMeta::new_readonly(self.stake_history, false),
+            AccountMeta::new_readonly(self.stake_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `record_loss`
+#[derive(Clone, Copy, Debug)]
+pub struct RecordLoss {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl RecordLoss {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `redeem_voucher`, `redeem_all_vouchers`
+#[derive(Clone, Copy, Debug)]
+pub struct RedeemVoucher {
+    /// The user redeeming their stake
+    pub user: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record
+    pub user_stake_record: Pubkey,
+    /// History entry for this withdrawal, required only when the pool records history
+    pub stake_action_record: Option<Pubkey>,
+    /// User's voucher token account (destination)
+    pub user_voucher_ata: Pubkey,
+    /// Pool vault token account (source)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// Pool vault authority PDA (signer for transfer)
+    pub pool_vault_authority: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// User's receipt token account (receipts burned here)
+    pub user_receipt_ata: Pubkey,
+    /// System program for history entry creation
+    pub system_program: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl RedeemVoucher {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            optional(self.stake_action_record, true, false),
+            AccountMeta::new(self.user_voucher_ata, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `update_pool_config`
+#[derive(Clone, Copy, Debug)]
+pub struct UpdatePoolConfig {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The mint configuration, read for its parameter timelock
+    pub config: Pubkey,
+}
+
+impl UpdatePoolConfig {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_advance_ltv`
+#[derive(Clone, Copy, Debug)]
+pub struct SetAdvanceLtv {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The mint configuration, read for its parameter timelock
+    pub config: Pubkey,
+}
+
+impl SetAdvanceLtv {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `borrow_against_stake`
+#[derive(Clone, Copy, Debug)]
+pub struct BorrowAgainstStake {
+    /// The staker borrowing, pays for the advance account
+    pub user: Pubkey,
+    /// The pool the stake is in
+    pub pool_state: Pubkey,
+    /// User's stake record, its collateral lock raised by the advance
+    pub user_stake_record: Pubkey,
+    /// The user's advance in this pool, topped up by repeated borrows
+    pub token_advance: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Program Derived Address that acts as the mint authority
+    pub mint_authority: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The user's token account receiving the advance
+    pub user_token_account: Pubkey,
+    /// SPL Token program for the mint
+    pub token_program: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl BorrowAgainstStake {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.token_advance, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new_readonly(self.mint_authority, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.user_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `repay_advance`
+#[derive(Clone, Copy, Debug)]
+pub struct RepayAdvance {
+    /// The borrower, receives the advance account's rent once it is repaid
+    pub user: Pubkey,
+    /// The pool the stake is in
+    pub pool_state: Pubkey,
+    /// User's stake record, its collateral lock lowered by the repayment
+    pub user_stake_record: Pubkey,
+    /// The advance being repaid
+    pub token_advance: Pubkey,
+    /// The Lokal token mint
+    pub mint: Pubkey,
+    /// Configuration account containing mint settings
+    pub config: Pubkey,
+    /// The user's token account the repayment is burned from
+    pub user_token_account: Pubkey,
+    /// SPL Token program for the burn
+    pub token_program: Pubkey,
+}
+
+impl RepayAdvance {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.token_advance, false),
+            AccountMeta::new(self.mint, false),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new(self.user_token_account, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_deployment_destination`
+#[derive(Clone, Copy, Debug)]
+pub struct SetDeploymentDestination {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The token account that will receive deployed vouchers
+    pub destination: Pubkey,
+}
+
+impl SetDeploymentDestination {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.destination, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `withdraw_for_deployment`
+#[derive(Clone, Copy, Debug)]
+pub struct WithdrawForDeployment {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Pool vault token account (source)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// Pool vault authority PDA (signer for transfer)
+    pub pool_vault_authority: Pubkey,
+    /// The deployment destination registered by the pool authority
+    pub destination: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl WithdrawForDeployment {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new(self.destination, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `return_principal`
+#[derive(Clone, Copy, Debug)]
+pub struct ReturnPrincipal {
+    /// The pool delegate authority (backend service)
+    /// Must be the owner or approved delegate of the source account
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Token account holding the returned vouchers
+    pub source: Pubkey,
+    /// Pool vault token account (destination)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl ReturnPrincipal {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.source, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `close_stake_record`
+#[derive(Clone, Copy, Debug)]
+pub struct CloseStakeRecord {
+    /// The staker or the pool delegate
+    pub authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record to close
+    pub user_stake_record: Pubkey,
+    /// The account that paid the record's rent at initialization
+    pub rent_receiver: Pubkey,
+}
+
+impl CloseStakeRecord {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.rent_receiver, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `close_stake_records`
+#[derive(Clone, Copy, Debug)]
+pub struct CloseStakeRecords {
+    /// The pool delegate authority (backend service), which receives the rent
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl CloseStakeRecords {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `repair_stake_record`
+#[derive(Clone, Copy, Debug)]
+pub struct RepairStakeRecord {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The stake record to repair
+    pub user_stake_record: Pubkey,
+}
+
+impl RepairStakeRecord {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_pool_guardian`
+#[derive(Clone, Copy, Debug)]
+pub struct SetPoolGuardian {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl SetPoolGuardian {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_pool_pause`
+#[derive(Clone, Copy, Debug)]
+pub struct SetPoolPause {
+    /// The pool authority or a guardian, checked in the handler
+    pub authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The mint configuration, read for its guardian
+    pub config: Pubkey,
+}
+
+impl SetPoolPause {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.config, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `propose_pool_authority`
+#[derive(Clone, Copy, Debug)]
+pub struct ProposePoolAuthority {
+    /// The current pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl ProposePoolAuthority {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `accept_pool_authority`
+#[derive(Clone, Copy, Debug)]
+pub struct AcceptPoolAuthority {
+    /// The nominated authority
+    pub new_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl AcceptPoolAuthority {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.new_authority, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `set_auto_compound`
+#[derive(Clone, Copy, Debug)]
+pub struct SetAutoCompound {
+    /// The user who owns the stake
+    pub user: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record
+    pub user_stake_record: Pubkey,
+}
+
+impl SetAutoCompound {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `compound_user`
+#[derive(Clone, Copy, Debug)]
+pub struct CompoundUser {
+    /// The pool delegate authority (backend service)
+    /// Must be the owner or approved delegate of the source account
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The stake record being compounded
+    pub user_stake_record: Pubkey,
+    /// Token account holding the purchased vouchers
+    pub source: Pubkey,
+    /// Pool vault token account (destination)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// The user's receipt token account (credited with receipts for the compounded vouchers)
+    pub user_receipt_ata: Pubkey,
+    /// Pool vault authority PDA (receipt mint authority)
+    pub pool_vault_authority: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl CompoundUser {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.source, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `add_to_whitelist`
+#[derive(Clone, Copy, Debug)]
+pub struct AddToWhitelist {
+    /// The pool authority (admin), pays for the entry
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The wallet being approved
+    pub user: Pubkey,
+    /// The whitelist entry (PDA)
+    pub stake_whitelist_entry: Pubkey,
+    /// System program for account creation
+    pub system_program: Pubkey,
+}
+
+impl AddToWhitelist {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.user, false),
+            AccountMeta::new(self.stake_whitelist_entry, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `remove_from_whitelist`
+#[derive(Clone, Copy, Debug)]
+pub struct RemoveFromWhitelist {
+    /// The pool authority (admin), receives the entry's rent
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The whitelist entry to close
+    pub stake_whitelist_entry: Pubkey,
+}
+
+impl RemoveFromWhitelist {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.stake_whitelist_entry, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `settle_users`
+#[derive(Clone, Copy, Debug)]
+pub struct SettleUsers {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl SettleUsers {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_delegate, true),
+            AccountMeta::new(self.pool_state, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `accrue_minimum_yield`
+#[derive(Clone, Copy, Debug)]
+pub struct AccrueMinimumYield {
+    /// The pool state account
+    pub pool_state: Pubkey,
+}
+
+impl AccrueMinimumYield {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![AccountMeta::new(self.pool_state, false)];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `request_unstake`
+#[derive(Clone, Copy, Debug)]
+pub struct RequestUnstake {
+    /// The user requesting the withdrawal, pays for the request account
+    pub user: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record
+    pub user_stake_record: Pubkey,
+    /// The user's unstake request (PDA), topped up by repeated requests
+    pub unstake_request: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// User's receipt token account (receipts burned here)
+    pub user_receipt_ata: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+    /// System program for account creation
+    pub system_program: Pubkey,
+}
+
+impl RequestUnstake {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.unstake_request, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `cancel_unstake`
+#[derive(Clone, Copy, Debug)]
+pub struct CancelUnstake {
+    /// The user who made the request, receives the request account's rent
+    pub user: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// User's stake record
+    pub user_stake_record: Pubkey,
+    /// The request being cancelled
+    pub unstake_request: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// User's receipt token account (receipts re-minted here)
+    pub user_receipt_ata: Pubkey,
+    /// Pool vault authority PDA (receipt mint authority)
+    pub pool_vault_authority: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl CancelUnstake {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.user, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new(self.unstake_request, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `process_unstake`
+#[derive(Clone, Copy, Debug)]
+pub struct ProcessUnstake {
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The request being processed
+    pub unstake_request: Pubkey,
+    /// The requesting user, receives the request account's rent
+    pub user: Pubkey,
+    /// User's voucher token account (destination)
+    pub user_voucher_ata: Pubkey,
+    /// Pool vault token account (source)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// Pool vault authority PDA (signer for transfer)
+    pub pool_vault_authority: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl ProcessUnstake {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.unstake_request, false),
+            AccountMeta::new(self.user, false),
+            AccountMeta::new(self.user_voucher_ata, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `verify_pool_invariants`
+#[derive(Clone, Copy, Debug)]
+pub struct VerifyPoolInvariants {
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Pool vault token account
+    pub pool_vault_ata: Pubkey,
+}
+
+impl VerifyPoolInvariants {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.pool_vault_ata, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `draw_insurance`
+#[derive(Clone, Copy, Debug)]
+pub struct DrawInsurance {
+    /// The pool authority (admin)
+    pub pool_authority: Pubkey,
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// Pool vault token account (source)
+    pub pool_vault_ata: Pubkey,
+    /// The voucher token mint (LOKAL token)
+    pub voucher_mint: Pubkey,
+    /// Pool vault authority PDA (signer for transfer)
+    pub pool_vault_authority: Pubkey,
+    /// The deployment destination registered by the pool authority
+    pub destination: Pubkey,
+    /// Token program for SPL token operations
+    pub token_program: Pubkey,
+}
+
+impl DrawInsurance {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut metas = vec![
+            AccountMeta::new_readonly(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new(self.pool_vault_ata, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new(self.destination, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ];
+        metas.extend(event_cpi_metas());
+        metas
+    }
+}
+
+/// Accounts for `preview_claimable_yield`
+#[derive(Clone, Copy, Debug)]
+pub struct PreviewClaimableYield {
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The user's stake record
+    pub user_stake_record: Pubkey,
+}
+
+impl PreviewClaimableYield {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.pool_state, false),
+            AccountMeta::new_readonly(self.user_stake_record, false),
+        ]
+    }
+}
+
+/// Accounts for `sync_stake_view`
+#[derive(Clone, Copy, Debug)]
+pub struct SyncStakeView {
+    /// The pool state account
+    pub pool_state: Pubkey,
+    /// The stake record to refresh
+    pub user_stake_record: Pubkey,
+}
+
+impl SyncStakeView {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+        ]
+    }
+}
+
+/// Accounts for `write_stats_snapshot`
+#[derive(Clone, Copy, Debug)]
+pub struct WriteStatsSnapshot {
+    /// Pays for the snapshot account the first time it is written each day
+    pub payer: Pubkey,
+    /// Configuration account containing mint settings and purchase/transfer counters
+    pub config: Pubkey,
+    /// Registry holding the merchant count
+    pub merchant_registry: Pubkey,
+    /// Pool 0 of the Lokal mint (the original voucher pool)
+    pub pool_state: Pubkey,
+    /// Snapshot for `day_index`, which must be the current day
+    pub snapshot: Pubkey,
+    /// System program required for account creation
+    pub system_program: Pubkey,
+}
+
+impl WriteStatsSnapshot {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.config, false),
+            AccountMeta::new_readonly(self.merchant_registry, false),
+            AccountMeta::new_readonly(self.pool_state, false),
+            AccountMeta::new(self.snapshot, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `migrate_pool`
+#[derive(Clone, Copy, Debug)]
+pub struct MigratePool {
+    /// The pool authority recorded in the legacy pool; pays for the larger account
+    pub pool_authority: Pubkey,
+    /// The legacy pool state (PDA of pool 0)
+    pub pool_state: Pubkey,
+    /// The vault authority PDA, which mints and burns receipts
+    pub pool_vault_authority: Pubkey,
+    /// The voucher token mint the pool stakes
+    pub voucher_mint: Pubkey,
+    /// The sLOKAL receipt mint (PDA), minted by the vault authority
+    pub receipt_mint: Pubkey,
+    /// System program for the rent top-up and mint creation
+    pub system_program: Pubkey,
+    /// Token program for the receipt mint
+    pub token_program: Pubkey,
+}
+
+impl MigratePool {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.pool_authority, true),
+            AccountMeta::new(self.pool_state, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new_readonly(self.voucher_mint, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+        ]
+    }
+}
+
+/// Accounts for `migrate_stake_record`
+#[derive(Clone, Copy, Debug)]
+pub struct MigrateStakeRecord {
+    /// Pays for the larger record and the receipt account
+    pub payer: Pubkey,
+    /// The staker who owns the record
+    pub user: Pubkey,
+    /// The original pool, already migrated with `migrate_pool`
+    pub pool_state: Pubkey,
+    /// The legacy stake record (PDA)
+    pub user_stake_record: Pubkey,
+    /// Pool vault authority PDA (receipt mint authority)
+    pub pool_vault_authority: Pubkey,
+    /// The pool's sLOKAL receipt mint
+    pub receipt_mint: Pubkey,
+    /// User's receipt token account (created if it doesn't exist)
+    pub user_receipt_ata: Pubkey,
+    /// System program for the rent top-up and receipt account creation
+    pub system_program: Pubkey,
+    /// Token program for minting receipts
+    pub token_program: Pubkey,
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Pubkey,
+}
+
+impl MigrateStakeRecord {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.user, false),
+            AccountMeta::new_readonly(self.pool_state, false),
+            AccountMeta::new(self.user_stake_record, false),
+            AccountMeta::new_readonly(self.pool_vault_authority, false),
+            AccountMeta::new(self.receipt_mint, false),
+            AccountMeta::new(self.user_receipt_ata, false),
+            AccountMeta::new_readonly(self.system_program, false),
+            AccountMeta::new_readonly(self.token_program, false),
+            AccountMeta::new_readonly(self.associated_token_program, false),
+        ]
+    }
+}
+
+/// Accounts for `migrate_config`
+#[derive(Clone, Copy, Debug)]
+pub struct MigrateConfig {
+    /// The update authority recorded in the legacy config; pays for the larger account
+    pub update_authority: Pubkey,
+    /// The legacy configuration account (PDA)
+    pub config: Pubkey,
+    /// The Lokal token mint, whose decimals the legacy layout did not record
+    pub mint: Pubkey,
+    /// System program for the rent top-up
+    pub system_program: Pubkey,
+}
+
+impl MigrateConfig {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.update_authority, true),
+            AccountMeta::new(self.config, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `migrate_merchant`
+#[derive(Clone, Copy, Debug)]
+pub struct MigrateMerchant {
+    /// Pays for the larger account and the merchant's index entry
+    pub payer: Pubkey,
+    /// The wallet the merchant registered with
+    pub merchant_owner: Pubkey,
+    /// The legacy merchant account (PDA)
+    pub merchant_account: Pubkey,
+    /// Registry that numbers merchants
+    /// Created here if no merchant has registered since the registry was added
+    pub merchant_registry: Pubkey,
+    /// Index entry pointing at the migrated merchant
+    pub merchant_index: Pubkey,
+    /// System program for the rent top-up and index creation
+    pub system_program: Pubkey,
+}
+
+impl MigrateMerchant {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(self.merchant_owner, false),
+            AccountMeta::new(self.merchant_account, false),
+            AccountMeta::new(self.merchant_registry, false),
+            AccountMeta::new(self.merchant_index, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
+
+/// Accounts for `migrate_purchase_transaction`
+#[derive(Clone, Copy, Debug)]
+pub struct MigratePurchaseTransaction {
+    /// Pays for the larger account
+    pub payer: Pubkey,
+    /// The legacy purchase record, at either its merchant-scoped or customer-only address
+    pub transaction_record: Pubkey,
+    /// System program for the rent top-up
+    pub system_program: Pubkey,
+}
+
+impl MigratePurchaseTransaction {
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new(self.transaction_record, false),
+            AccountMeta::new_readonly(self.system_program, false),
+        ]
+    }
+}
@@ -0,0 +1,1932 @@
+//! Builders for every program instruction
+//! Each takes the instruction's account struct and its arguments in declaration order,
+//! and encodes them the way Anchor does: the 8-byte discriminator, then the Borsh arguments
+
+use borsh::BorshSerialize;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+
+use crate::accounts;
+use crate::state::*;
+use crate::ID;
+
+fn instruction(
+    accounts: Vec<AccountMeta>,
+    discriminator: [u8; 8],
+    args: impl BorshSerialize,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data)
+        .expect("serializing into a Vec cannot fail");
+    Instruction {
+        program_id: ID,
+        accounts,
+        data,
+    }
+}
+
+/// Discriminator of `initialize_lokal_mint`
+pub const INITIALIZE_LOKAL_MINT_DISCRIMINATOR: [u8; 8] = [191, 150, 196, 106, 175, 171, 200, 123];
+/// Discriminator of `mint_lokal_tokens`
+pub const MINT_LOKAL_TOKENS_DISCRIMINATOR: [u8; 8] = [30, 159, 99, 133, 157, 239, 64, 63];
+/// Discriminator of `reconcile_supply`
+pub const RECONCILE_SUPPLY_DISCRIMINATOR: [u8; 8] = [76, 3, 168, 164, 69, 196, 225, 250];
+/// Discriminator of `register_merchant`
+pub const REGISTER_MERCHANT_DISCRIMINATOR: [u8; 8] = [238, 245, 77, 132, 161, 88, 216, 248];
+/// Discriminator of `register_merchant_by_authority`
+pub const REGISTER_MERCHANT_BY_AUTHORITY_DISCRIMINATOR: [u8; 8] =
+    [73, 232, 104, 252, 236, 203, 107, 113];
+/// Discriminator of `accept_merchant_registration`
+pub const ACCEPT_MERCHANT_REGISTRATION_DISCRIMINATOR: [u8; 8] = [52, 54, 72, 223, 244, 99, 25, 230];
+/// Discriminator of `process_purchase_with_redemption`
+pub const PROCESS_PURCHASE_WITH_REDEMPTION_DISCRIMINATOR: [u8; 8] =
+    [59, 111, 73, 179, 251, 149, 152, 202];
+/// Discriminator of `process_purchase`
+pub const PROCESS_PURCHASE_DISCRIMINATOR: [u8; 8] = [38, 233, 48, 62, 162, 120, 177, 244];
+/// Discriminator of `create_invoice`
+pub const CREATE_INVOICE_DISCRIMINATOR: [u8; 8] = [154, 170, 31, 135, 134, 100, 156, 146];
+/// Discriminator of `pay_invoice`
+pub const PAY_INVOICE_DISCRIMINATOR: [u8; 8] = [104, 6, 62, 239, 197, 206, 208, 220];
+/// Discriminator of `create_hold`
+pub const CREATE_HOLD_DISCRIMINATOR: [u8; 8] = [5, 206, 229, 132, 223, 134, 166, 221];
+/// Discriminator of `capture_hold`
+pub const CAPTURE_HOLD_DISCRIMINATOR: [u8; 8] = [128, 110, 47, 105, 223, 150, 126, 138];
+/// Discriminator of `release_hold`
+pub const RELEASE_HOLD_DISCRIMINATOR: [u8; 8] = [106, 109, 70, 162, 197, 158, 92, 243];
+/// Discriminator of `open_dispute`
+pub const OPEN_DISPUTE_DISCRIMINATOR: [u8; 8] = [137, 25, 99, 119, 23, 223, 161, 42];
+/// Discriminator of `respond_dispute`
+pub const RESPOND_DISPUTE_DISCRIMINATOR: [u8; 8] = [71, 136, 87, 127, 213, 117, 241, 1];
+/// Discriminator of `resolve_dispute`
+pub const RESOLVE_DISPUTE_DISCRIMINATOR: [u8; 8] = [231, 6, 202, 6, 96, 103, 12, 230];
+/// Discriminator of `mark_fulfilled`
+pub const MARK_FULFILLED_DISCRIMINATOR: [u8; 8] = [40, 188, 159, 127, 20, 151, 228, 191];
+/// Discriminator of `settle_merchant`
+pub const SETTLE_MERCHANT_DISCRIMINATOR: [u8; 8] = [131, 245, 212, 153, 77, 234, 128, 205];
+/// Discriminator of `create_gift_card`
+pub const CREATE_GIFT_CARD_DISCRIMINATOR: [u8; 8] = [202, 141, 181, 51, 5, 224, 200, 17];
+/// Discriminator of `redeem_gift_card`
+pub const REDEEM_GIFT_CARD_DISCRIMINATOR: [u8; 8] = [213, 90, 9, 183, 232, 155, 243, 102];
+/// Discriminator of `reclaim_gift_card`
+pub const RECLAIM_GIFT_CARD_DISCRIMINATOR: [u8; 8] = [18, 26, 220, 189, 71, 90, 251, 237];
+/// Discriminator of `create_subscription`
+pub const CREATE_SUBSCRIPTION_DISCRIMINATOR: [u8; 8] = [65, 71, 10, 60, 249, 82, 197, 12];
+/// Discriminator of `charge_subscription`
+pub const CHARGE_SUBSCRIPTION_DISCRIMINATOR: [u8; 8] = [121, 52, 210, 23, 3, 68, 86, 194];
+/// Discriminator of `cancel_subscription`
+pub const CANCEL_SUBSCRIPTION_DISCRIMINATOR: [u8; 8] = [60, 139, 189, 242, 191, 208, 143, 18];
+/// Discriminator of `create_airdrop`
+pub const CREATE_AIRDROP_DISCRIMINATOR: [u8; 8] = [227, 135, 208, 66, 137, 177, 80, 94];
+/// Discriminator of `claim_airdrop`
+pub const CLAIM_AIRDROP_DISCRIMINATOR: [u8; 8] = [137, 50, 122, 111, 89, 254, 8, 20];
+/// Discriminator of `sweep_airdrop`
+pub const SWEEP_AIRDROP_DISCRIMINATOR: [u8; 8] = [81, 15, 206, 125, 8, 78, 179, 196];
+/// Discriminator of `create_session_key`
+pub const CREATE_SESSION_KEY_DISCRIMINATOR: [u8; 8] = [137, 204, 246, 242, 200, 143, 215, 56];
+/// Discriminator of `process_purchase_with_session`
+pub const PROCESS_PURCHASE_WITH_SESSION_DISCRIMINATOR: [u8; 8] =
+    [92, 130, 208, 56, 71, 218, 11, 139];
+/// Discriminator of `revoke_session_key`
+pub const REVOKE_SESSION_KEY_DISCRIMINATOR: [u8; 8] = [81, 192, 32, 110, 104, 116, 144, 151];
+/// Discriminator of `update_merchant`
+pub const UPDATE_MERCHANT_DISCRIMINATOR: [u8; 8] = [192, 114, 143, 220, 199, 50, 234, 165];
+/// Discriminator of `register_pos_key`
+pub const REGISTER_POS_KEY_DISCRIMINATOR: [u8; 8] = [70, 73, 20, 141, 216, 119, 166, 157];
+/// Discriminator of `remove_pos_key`
+pub const REMOVE_POS_KEY_DISCRIMINATOR: [u8; 8] = [126, 171, 212, 242, 154, 194, 179, 137];
+/// Discriminator of `add_merchant_operator`
+pub const ADD_MERCHANT_OPERATOR_DISCRIMINATOR: [u8; 8] = [160, 241, 116, 182, 141, 76, 74, 56];
+/// Discriminator of `remove_merchant_operator`
+pub const REMOVE_MERCHANT_OPERATOR_DISCRIMINATOR: [u8; 8] = [0, 71, 49, 53, 163, 109, 191, 6];
+/// Discriminator of `create_parent_merchant`
+pub const CREATE_PARENT_MERCHANT_DISCRIMINATOR: [u8; 8] = [74, 31, 212, 12, 34, 22, 196, 252];
+/// Discriminator of `update_parent_merchant`
+pub const UPDATE_PARENT_MERCHANT_DISCRIMINATOR: [u8; 8] = [23, 102, 19, 122, 245, 201, 73, 26];
+/// Discriminator of `cascade_cashback_rate`
+pub const CASCADE_CASHBACK_RATE_DISCRIMINATOR: [u8; 8] = [220, 121, 66, 217, 172, 182, 175, 44];
+/// Discriminator of `create_reward_budget`
+pub const CREATE_REWARD_BUDGET_DISCRIMINATOR: [u8; 8] = [236, 184, 224, 103, 80, 141, 167, 178];
+/// Discriminator of `top_up_reward_budget`
+pub const TOP_UP_REWARD_BUDGET_DISCRIMINATOR: [u8; 8] = [13, 235, 12, 165, 59, 203, 153, 219];
+/// Discriminator of `withdraw_reward_budget`
+pub const WITHDRAW_REWARD_BUDGET_DISCRIMINATOR: [u8; 8] = [0, 14, 210, 224, 251, 82, 77, 1];
+/// Discriminator of `add_budget_member`
+pub const ADD_BUDGET_MEMBER_DISCRIMINATOR: [u8; 8] = [251, 170, 231, 224, 27, 137, 243, 173];
+/// Discriminator of `remove_budget_member`
+pub const REMOVE_BUDGET_MEMBER_DISCRIMINATOR: [u8; 8] = [134, 160, 105, 131, 189, 134, 213, 248];
+/// Discriminator of `set_category_boost`
+pub const SET_CATEGORY_BOOST_DISCRIMINATOR: [u8; 8] = [104, 16, 43, 85, 20, 209, 65, 20];
+/// Discriminator of `close_category_boost`
+pub const CLOSE_CATEGORY_BOOST_DISCRIMINATOR: [u8; 8] = [152, 71, 152, 179, 227, 108, 121, 253];
+/// Discriminator of `set_payout_account`
+pub const SET_PAYOUT_ACCOUNT_DISCRIMINATOR: [u8; 8] = [242, 33, 233, 54, 165, 178, 249, 17];
+/// Discriminator of `propose_merchant_owner`
+pub const PROPOSE_MERCHANT_OWNER_DISCRIMINATOR: [u8; 8] = [190, 72, 66, 85, 76, 41, 75, 80];
+/// Discriminator of `accept_merchant_ownership`
+pub const ACCEPT_MERCHANT_OWNERSHIP_DISCRIMINATOR: [u8; 8] = [91, 23, 150, 186, 250, 67, 82, 19];
+/// Discriminator of `set_streak_reward`
+pub const SET_STREAK_REWARD_DISCRIMINATOR: [u8; 8] = [116, 34, 245, 6, 221, 152, 231, 70];
+/// Discriminator of `close_merchant`
+pub const CLOSE_MERCHANT_DISCRIMINATOR: [u8; 8] = [138, 96, 102, 11, 220, 136, 154, 11];
+/// Discriminator of `set_staker_boost`
+pub const SET_STAKER_BOOST_DISCRIMINATOR: [u8; 8] = [55, 87, 56, 140, 179, 10, 148, 3];
+/// Discriminator of `set_mint_cosigner`
+pub const SET_MINT_COSIGNER_DISCRIMINATOR: [u8; 8] = [203, 142, 210, 164, 28, 76, 214, 26];
+/// Discriminator of `set_limits`
+pub const SET_LIMITS_DISCRIMINATOR: [u8; 8] = [207, 50, 250, 67, 211, 33, 70, 91];
+/// Discriminator of `set_mint_caps`
+pub const SET_MINT_CAPS_DISCRIMINATOR: [u8; 8] = [153, 151, 45, 96, 181, 190, 134, 59];
+/// Discriminator of `set_rate_change_policy`
+pub const SET_RATE_CHANGE_POLICY_DISCRIMINATOR: [u8; 8] = [210, 125, 167, 110, 165, 123, 188, 238];
+/// Discriminator of `set_cashback_limits`
+pub const SET_CASHBACK_LIMITS_DISCRIMINATOR: [u8; 8] = [208, 248, 247, 183, 211, 2, 102, 113];
+/// Discriminator of `set_price_feed`
+pub const SET_PRICE_FEED_DISCRIMINATOR: [u8; 8] = [13, 15, 231, 129, 61, 7, 28, 122];
+/// Discriminator of `set_dispute_window`
+pub const SET_DISPUTE_WINDOW_DISCRIMINATOR: [u8; 8] = [125, 232, 78, 47, 233, 47, 47, 237];
+/// Discriminator of `set_fulfillment_authority`
+pub const SET_FULFILLMENT_AUTHORITY_DISCRIMINATOR: [u8; 8] = [233, 50, 58, 71, 145, 53, 21, 69];
+/// Discriminator of `set_reward_expiry`
+pub const SET_REWARD_EXPIRY_DISCRIMINATOR: [u8; 8] = [251, 191, 177, 12, 91, 173, 40, 253];
+/// Discriminator of `expire_rewards`
+pub const EXPIRE_REWARDS_DISCRIMINATOR: [u8; 8] = [249, 29, 95, 123, 170, 90, 76, 180];
+/// Discriminator of `withdraw_vested_rewards`
+pub const WITHDRAW_VESTED_REWARDS_DISCRIMINATOR: [u8; 8] = [41, 72, 66, 81, 69, 233, 41, 63];
+/// Discriminator of `create_vesting_grant`
+pub const CREATE_VESTING_GRANT_DISCRIMINATOR: [u8; 8] = [135, 251, 158, 237, 206, 18, 164, 244];
+/// Discriminator of `claim_vested`
+pub const CLAIM_VESTED_DISCRIMINATOR: [u8; 8] = [208, 190, 166, 114, 203, 225, 140, 208];
+/// Discriminator of `revoke_vesting_grant`
+pub const REVOKE_VESTING_GRANT_DISCRIMINATOR: [u8; 8] = [76, 48, 232, 206, 145, 169, 31, 21];
+/// Discriminator of `set_activity_recording`
+pub const SET_ACTIVITY_RECORDING_DISCRIMINATOR: [u8; 8] = [23, 244, 146, 133, 148, 32, 92, 28];
+/// Discriminator of `set_slim_purchase_records`
+pub const SET_SLIM_PURCHASE_RECORDS_DISCRIMINATOR: [u8; 8] = [219, 224, 99, 249, 200, 180, 2, 133];
+/// Discriminator of `set_customer_hashing`
+pub const SET_CUSTOMER_HASHING_DISCRIMINATOR: [u8; 8] = [121, 51, 111, 54, 153, 107, 165, 247];
+/// Discriminator of `set_protocol_fee`
+pub const SET_PROTOCOL_FEE_DISCRIMINATOR: [u8; 8] = [173, 239, 83, 242, 136, 43, 144, 217];
+/// Discriminator of `set_transfer_fee`
+pub const SET_TRANSFER_FEE_DISCRIMINATOR: [u8; 8] = [58, 149, 37, 3, 230, 78, 181, 180];
+/// Discriminator of `set_treasury_controls`
+pub const SET_TREASURY_CONTROLS_DISCRIMINATOR: [u8; 8] = [102, 182, 46, 101, 46, 71, 115, 134];
+/// Discriminator of `withdraw_treasury`
+pub const WITHDRAW_TREASURY_DISCRIMINATOR: [u8; 8] = [40, 63, 122, 158, 144, 216, 83, 96];
+/// Discriminator of `restrict_account`
+pub const RESTRICT_ACCOUNT_DISCRIMINATOR: [u8; 8] = [118, 217, 205, 220, 66, 199, 153, 80];
+/// Discriminator of `lift_account_restriction`
+pub const LIFT_ACCOUNT_RESTRICTION_DISCRIMINATOR: [u8; 8] = [254, 22, 60, 26, 250, 237, 252, 3];
+/// Discriminator of `clawback`
+pub const CLAWBACK_DISCRIMINATOR: [u8; 8] = [111, 92, 142, 79, 33, 234, 82, 27];
+/// Discriminator of `set_param_timelock`
+pub const SET_PARAM_TIMELOCK_DISCRIMINATOR: [u8; 8] = [91, 41, 200, 160, 185, 189, 194, 144];
+/// Discriminator of `queue_param_change`
+pub const QUEUE_PARAM_CHANGE_DISCRIMINATOR: [u8; 8] = [140, 242, 124, 63, 143, 237, 195, 231];
+/// Discriminator of `execute_param_change`
+pub const EXECUTE_PARAM_CHANGE_DISCRIMINATOR: [u8; 8] = [162, 166, 56, 243, 168, 135, 66, 175];
+/// Discriminator of `cancel_param_change`
+pub const CANCEL_PARAM_CHANGE_DISCRIMINATOR: [u8; 8] = [150, 147, 92, 108, 72, 160, 224, 55];
+/// Discriminator of `set_guardian`
+pub const SET_GUARDIAN_DISCRIMINATOR: [u8; 8] = [147, 243, 50, 121, 154, 164, 50, 30];
+/// Discriminator of `set_global_pause`
+pub const SET_GLOBAL_PAUSE_DISCRIMINATOR: [u8; 8] = [32, 234, 28, 216, 67, 76, 116, 231];
+/// Discriminator of `set_merchant_suspension`
+pub const SET_MERCHANT_SUSPENSION_DISCRIMINATOR: [u8; 8] = [237, 83, 156, 135, 114, 116, 21, 226];
+/// Discriminator of `preview_purchase_reward`
+pub const PREVIEW_PURCHASE_REWARD_DISCRIMINATOR: [u8; 8] = [148, 225, 147, 162, 216, 9, 40, 174];
+/// Discriminator of `get_program_status`
+pub const GET_PROGRAM_STATUS_DISCRIMINATOR: [u8; 8] = [255, 112, 98, 86, 104, 52, 168, 53];
+/// Discriminator of `transfer_tokens`
+pub const TRANSFER_TOKENS_DISCRIMINATOR: [u8; 8] = [54, 180, 238, 175, 74, 85, 126, 188];
+/// Discriminator of `add_fee_exemption`
+pub const ADD_FEE_EXEMPTION_DISCRIMINATOR: [u8; 8] = [189, 238, 101, 182, 238, 47, 93, 30];
+/// Discriminator of `remove_fee_exemption`
+pub const REMOVE_FEE_EXEMPTION_DISCRIMINATOR: [u8; 8] = [158, 59, 24, 139, 29, 141, 63, 15];
+/// Discriminator of `initialize_pool`
+pub const INITIALIZE_POOL_DISCRIMINATOR: [u8; 8] = [95, 180, 10, 172, 84, 174, 232, 40];
+/// Discriminator of `deposit_voucher`
+pub const DEPOSIT_VOUCHER_DISCRIMINATOR: [u8; 8] = [82, 178, 83, 142, 200, 70, 4, 73];
+/// Discriminator of `deposit_for_beneficiary`
+pub const DEPOSIT_FOR_BENEFICIARY_DISCRIMINATOR: [u8; 8] = [179, 230, 149, 41, 22, 226, 26, 102];
+/// Discriminator of `record_yield`
+pub const RECORD_YIELD_DISCRIMINATOR: [u8; 8] = [80, 136, 238, 204, 216, 161, 41, 88];
+/// Discriminator of `reset_external_sequence`
+pub const RESET_EXTERNAL_SEQUENCE_DISCRIMINATOR: [u8; 8] = [120, 230, 19, 222, 240, 69, 36, 136];
+/// Discriminator of `configure_stake_pool`
+pub const CONFIGURE_STAKE_POOL_DISCRIMINATOR: [u8; 8] = [141, 152, 6, 213, 245, 49, 181, 177];
+/// Discriminator of `deploy_to_stake_pool`
+pub const DEPLOY_TO_STAKE_POOL_DISCRIMINATOR: [u8; 8] = [5, 249, 32, 121, 140, 109, 38, 28];
+/// Discriminator of `unwind_from_stake_pool`
+pub const UNWIND_FROM_STAKE_POOL_DISCRIMINATOR: [u8; 8] = [70, 28, 46, 167, 105, 229, 174, 205];
+/// Discriminator of `record_loss`
+pub const RECORD_LOSS_DISCRIMINATOR: [u8; 8] = [112, 182, 48, 145, 171, 216, 247, 43];
+/// Discriminator of `redeem_voucher`
+pub const REDEEM_VOUCHER_DISCRIMINATOR: [u8; 8] = [50, 219, 8, 127, 45, 96, 161, 92];
+/// Discriminator of `redeem_all_vouchers`
+pub const REDEEM_ALL_VOUCHERS_DISCRIMINATOR: [u8; 8] = [246, 45, 119, 222, 50, 181, 33, 202];
+/// Discriminator of `update_pool_config`
+pub const UPDATE_POOL_CONFIG_DISCRIMINATOR: [u8; 8] = [68, 236, 203, 122, 179, 62, 234, 252];
+/// Discriminator of `set_advance_ltv`
+pub const SET_ADVANCE_LTV_DISCRIMINATOR: [u8; 8] = [159, 108, 186, 5, 86, 121, 248, 0];
+/// Discriminator of `borrow_against_stake`
+pub const BORROW_AGAINST_STAKE_DISCRIMINATOR: [u8; 8] = [174, 73, 160, 16, 63, 231, 200, 132];
+/// Discriminator of `repay_advance`
+pub const REPAY_ADVANCE_DISCRIMINATOR: [u8; 8] = [247, 119, 157, 136, 70, 66, 200, 20];
+/// Discriminator of `set_deployment_destination`
+pub const SET_DEPLOYMENT_DESTINATION_DISCRIMINATOR: [u8; 8] =
+    [159, 27, 155, 67, 222, 118, 235, 242];
+/// Discriminator of `withdraw_for_deployment`
+pub const WITHDRAW_FOR_DEPLOYMENT_DISCRIMINATOR: [u8; 8] = [237, 136, 76, 83, 90, 245, 183, 242];
+/// Discriminator of `return_principal`
+pub const RETURN_PRINCIPAL_DISCRIMINATOR: [u8; 8] = [27, 177, 124, 34, 3, 16, 96, 76];
+/// Discriminator of `close_stake_record`
+pub const CLOSE_STAKE_RECORD_DISCRIMINATOR: [u8; 8] = [68, 52, 109, 180, 36, 255, 137, 142];
+/// Discriminator of `close_stake_records`
+pub const CLOSE_STAKE_RECORDS_DISCRIMINATOR: [u8; 8] = [66, 129, 147, 191, 212, 132, 225, 76];
+/// Discriminator of `repair_stake_record`
+pub const REPAIR_STAKE_RECORD_DISCRIMINATOR: [u8; 8] = [131, 45, 239, 207, 158, 226, 253, 251];
+/// Discriminator of `set_pool_guardian`
+pub const SET_POOL_GUARDIAN_DISCRIMINATOR: [u8; 8] = [201, 254, 99, 122, 41, 66, 158, 74];
+/// Discriminator of `set_pool_pause`
+pub const SET_POOL_PAUSE_DISCRIMINATOR: [u8; 8] = [52, 171, 212, 208, 28, 209, 90, 15];
+/// Discriminator of `propose_pool_authority`
+pub const PROPOSE_POOL_AUTHORITY_DISCRIMINATOR: [u8; 8] = [101, 252, 82, 20, 147, 12, 170, 199];
+/// Discriminator of `accept_pool_authority`
+pub const ACCEPT_POOL_AUTHORITY_DISCRIMINATOR: [u8; 8] = [96, 70, 143, 180, 146, 69, 234, 113];
+/// Discriminator of `set_auto_compound`
+pub const SET_AUTO_COMPOUND_DISCRIMINATOR: [u8; 8] = [101, 22, 46, 161, 189, 86, 250, 244];
+/// Discriminator of `compound_user`
+pub const COMPOUND_USER_DISCRIMINATOR: [u8; 8] = [154, 108, 105, 95, 110, 90, 21, 0];
+/// Discriminator of `add_to_whitelist`
+pub const ADD_TO_WHITELIST_DISCRIMINATOR: [u8; 8] = [157, 211, 52, 54, 144, 81, 5, 55];
+/// Discriminator of `remove_from_whitelist`
+pub const REMOVE_FROM_WHITELIST_DISCRIMINATOR: [u8; 8] = [7, 144, 216, 239, 243, 236, 193, 235];
+/// Discriminator of `settle_users`
+pub const SETTLE_USERS_DISCRIMINATOR: [u8; 8] = [125, 30, 171, 102, 44, 45, 86, 25];
+/// Discriminator of `accrue_minimum_yield`
+pub const ACCRUE_MINIMUM_YIELD_DISCRIMINATOR: [u8; 8] = [195, 62, 207, 134, 145, 108, 231, 113];
+/// Discriminator of `request_unstake`
+pub const REQUEST_UNSTAKE_DISCRIMINATOR: [u8; 8] = [44, 154, 110, 253, 160, 202, 54, 34];
+/// Discriminator of `cancel_unstake`
+pub const CANCEL_UNSTAKE_DISCRIMINATOR: [u8; 8] = [64, 65, 53, 227, 125, 153, 3, 167];
+/// Discriminator of `process_unstake`
+pub const PROCESS_UNSTAKE_DISCRIMINATOR: [u8; 8] = [217, 160, 136, 174, 149, 62, 79, 133];
+/// Discriminator of `verify_pool_invariants`
+pub const VERIFY_POOL_INVARIANTS_DISCRIMINATOR: [u8; 8] = [28, 74, 75, 143, 203, 118, 193, 56];
+/// Discriminator of `draw_insurance`
+pub const DRAW_INSURANCE_DISCRIMINATOR: [u8; 8] = [177, 72, 230, 39, 177, 248, 175, 215];
+/// Discriminator of `preview_claimable_yield`
+pub const PREVIEW_CLAIMABLE_YIELD_DISCRIMINATOR: [u8; 8] = [38, 104, 85, 184, 19, 98, 90, 78];
+/// Discriminator of `sync_stake_view`
+pub const SYNC_STAKE_VIEW_DISCRIMINATOR: [u8; 8] = [247, 85, 78, 222, 215, 90, 218, 88];
+/// Discriminator of `write_stats_snapshot`
+pub const WRITE_STATS_SNAPSHOT_DISCRIMINATOR: [u8; 8] = [195, 96, 241, 198, 95, 45, 17, 10];
+/// Discriminator of `migrate_pool`
+pub const MIGRATE_POOL_DISCRIMINATOR: [u8; 8] = [55, 170, 171, 123, 210, 69, 39, 172];
+/// Discriminator of `migrate_stake_record`
+pub const MIGRATE_STAKE_RECORD_DISCRIMINATOR: [u8; 8] = [152, 58, 81, 16, 16, 9, 230, 55];
+/// Discriminator of `migrate_config`
+pub const MIGRATE_CONFIG_DISCRIMINATOR: [u8; 8] = [92, 131, 58, 105, 210, 154, 224, 193];
+/// Discriminator of `migrate_merchant`
+pub const MIGRATE_MERCHANT_DISCRIMINATOR: [u8; 8] = [74, 230, 161, 163, 14, 34, 101, 166];
+/// Discriminator of `migrate_purchase_transaction`
+pub const MIGRATE_PURCHASE_TRANSACTION_DISCRIMINATOR: [u8; 8] =
+    [73, 64, 196, 13, 249, 182, 28, 211];
+
+/// Initialize the Lokal token mint and program configuration
+/// This instruction sets up the SPL token mint with the program as authority
+pub fn initialize_lokal_mint(accounts: &accounts::InitializeLokalMint) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        INITIALIZE_LOKAL_MINT_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Mint Lokal tokens to a user's token account
+/// Used for reward distribution when users make purchases at merchants
+/// Mints above the single-signature limit also need the configured co-signer
+pub fn mint_lokal_tokens(accounts: &accounts::MintLokalTokens, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MINT_LOKAL_TOKENS_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Compare the Lokal mint's supply with `total_supply - total_burned` and emit the result
+/// Anyone can check; with `force` the update authority snaps the counters to the mint
+pub fn reconcile_supply(accounts: &accounts::ReconcileSupply, force: bool) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RECONCILE_SUPPLY_DISCRIMINATOR,
+        force,
+    )
+}
+
+/// Register a new merchant in the Carsa loyalty program
+/// This instruction creates a merchant account with specific cashback rates
+/// and gives it the next `MerchantIndex` in the merchant registry
+/// Franchise outlets pass their parent merchant, co-signed by the parent's owner
+pub fn register_merchant(
+    accounts: &accounts::RegisterMerchant,
+    name: &str,
+    category: &str,
+    cashback_rate: u16,
+    min_purchase_amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REGISTER_MERCHANT_DISCRIMINATOR,
+        (name, category, cashback_rate, min_purchase_amount),
+    )
+}
+
+/// Register a merchant on its owner's behalf, with the update authority signing and paying
+/// The merchant is created pending activation and takes no purchases until its owner
+/// signs `accept_merchant_registration`
+pub fn register_merchant_by_authority(
+    accounts: &accounts::RegisterMerchantByAuthority,
+    merchant_wallet: Pubkey,
+    name: &str,
+    category: &str,
+    cashback_rate: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REGISTER_MERCHANT_BY_AUTHORITY_DISCRIMINATOR,
+        (merchant_wallet, name, category, cashback_rate),
+    )
+}
+
+/// Accept a registration made by the update authority, allowing purchases
+/// Only the merchant owner can perform this operation
+pub fn accept_merchant_registration(
+    accounts: &accounts::AcceptMerchantRegistration,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ACCEPT_MERCHANT_REGISTRATION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Process a purchase transaction and distribute reward tokens with optional token redemption
+/// This is the core instruction that implements the loyalty program logic
+/// Customers who pass their voucher pool stake record may earn the staker cashback boost
+/// Redeemed tokens are valued with the merchant's redemption bonus and pay the protocol
+/// fee to the treasury; the merchant receives the rest
+pub fn process_purchase_with_redemption(
+    accounts: &accounts::ProcessPurchase,
+    fiat_amount: u64,
+    redemption: Option<RedemptionParams>,
+    transaction_id: &[u8; 32],
+    expected_nonce: Option<u64>,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROCESS_PURCHASE_WITH_REDEMPTION_DISCRIMINATOR,
+        (fiat_amount, redemption, transaction_id, expected_nonce),
+    )
+}
+
+/// Legacy purchase entry point taking a bare redemption amount
+/// Deprecated: use `process_purchase_with_redemption`; kept so existing clients don't break
+pub fn process_purchase(
+    accounts: &accounts::ProcessPurchase,
+    fiat_amount: u64,
+    redeem_token_amount: Option<u64>,
+    transaction_id: &[u8; 32],
+    expected_nonce: Option<u64>,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROCESS_PURCHASE_DISCRIMINATOR,
+        (
+            fiat_amount,
+            redeem_token_amount,
+            transaction_id,
+            expected_nonce,
+        ),
+    )
+}
+
+/// Create an invoice with a fixed IDR amount for a customer to pay by reference
+/// Only the merchant owner can perform this operation
+pub fn create_invoice(
+    accounts: &accounts::CreateInvoice,
+    reference_id: &[u8; 32],
+    fiat_amount: u64,
+    max_redeem_token_amount: Option<u64>,
+    expires_at: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_INVOICE_DISCRIMINATOR,
+        (
+            reference_id,
+            fiat_amount,
+            max_redeem_token_amount,
+            expires_at,
+        ),
+    )
+}
+
+/// Pay an invoice, distributing rewards as `process_purchase_with_redemption` does but
+/// with the amount taken from the invoice
+pub fn pay_invoice(
+    accounts: &accounts::ProcessPurchase,
+    invoice_amount: u64,
+    redemption: Option<RedemptionParams>,
+    transaction_id: &[u8; 32],
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PAY_INVOICE_DISCRIMINATOR,
+        (invoice_amount, redemption, transaction_id),
+    )
+}
+
+/// Hold customer tokens in escrow for a merchant to capture when the bill is settled
+pub fn create_hold(
+    accounts: &accounts::CreateHold,
+    hold_id: &[u8; 32],
+    amount: u64,
+    expires_at: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_HOLD_DISCRIMINATOR,
+        (hold_id, amount, expires_at),
+    )
+}
+
+/// Capture up to the held tokens as the redemption of a purchase, distributing rewards
+/// as `process_purchase_with_redemption` does and releasing the rest to the customer
+/// Only the merchant owner can perform this operation
+pub fn capture_hold(
+    accounts: &accounts::CaptureHold,
+    fiat_amount: u64,
+    capture_amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CAPTURE_HOLD_DISCRIMINATOR,
+        (fiat_amount, capture_amount),
+    )
+}
+
+/// Return an expired hold's tokens to the customer
+pub fn release_hold(accounts: &accounts::ReleaseHold) -> Instruction {
+    instruction(accounts.to_account_metas(), RELEASE_HOLD_DISCRIMINATOR, ())
+}
+
+/// Dispute one of the customer's purchases within the dispute window, freezing
+/// refunds on it until the dispute is resolved
+/// Moves the purchase's transaction stub to disputed
+pub fn open_dispute(accounts: &accounts::OpenDispute, reason_code: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        OPEN_DISPUTE_DISCRIMINATOR,
+        reason_code,
+    )
+}
+
+/// Attach the merchant's response to an open dispute
+/// Only the merchant owner can perform this operation
+pub fn respond_dispute(accounts: &accounts::RespondDispute, response_code: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RESPOND_DISPUTE_DISCRIMINATOR,
+        response_code,
+    )
+}
+
+/// Decide a dispute, refunding the customer's redeemed tokens when it is upheld
+/// Only the update authority can perform this operation; repeating a decision is a no-op
+/// Moves the purchase's transaction stub to refunded or resolved
+pub fn resolve_dispute(accounts: &accounts::ResolveDispute, uphold: bool) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RESOLVE_DISPUTE_DISCRIMINATOR,
+        uphold,
+    )
+}
+
+/// Record that a purchase's off-chain fulfillment has completed
+/// Only the fulfillment authority can perform this operation, once per purchase and
+/// within the fulfillment window, and not once the purchase is disputed
+pub fn mark_fulfilled(accounts: &accounts::MarkFulfilled) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MARK_FULFILLED_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Burn redeemed tokens the merchant holds and record the IDR owed for them, to be
+/// paid out off-chain
+/// Only the merchant owner can perform this operation
+pub fn settle_merchant(
+    accounts: &accounts::SettleMerchant,
+    period_id: u64,
+    token_amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SETTLE_MERCHANT_DISCRIMINATOR,
+        (period_id, token_amount),
+    )
+}
+
+/// Escrow tokens as a gift card claimable by whoever presents its secret code
+pub fn create_gift_card(
+    accounts: &accounts::CreateGiftCard,
+    amount: u64,
+    code_hash: &[u8; 32],
+    expires_at: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_GIFT_CARD_DISCRIMINATOR,
+        (amount, code_hash, expires_at),
+    )
+}
+
+/// Claim a gift card's tokens by presenting its secret code, closing the gift card
+pub fn redeem_gift_card(accounts: &accounts::RedeemGiftCard, code_preimage: &[u8]) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REDEEM_GIFT_CARD_DISCRIMINATOR,
+        code_preimage,
+    )
+}
+
+/// Return an expired gift card's tokens to its issuer
+pub fn reclaim_gift_card(accounts: &accounts::ReclaimGiftCard) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RECLAIM_GIFT_CARD_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Subscribe to a merchant, approving the subscription to charge the customer's
+/// token account a fixed amount every period
+/// The first period can be charged immediately
+pub fn create_subscription(
+    accounts: &accounts::CreateSubscription,
+    amount_per_period: u64,
+    period_seconds: i64,
+    max_periods: u32,
+    earn_rewards: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_SUBSCRIPTION_DISCRIMINATOR,
+        (amount_per_period, period_seconds, max_periods, earn_rewards),
+    )
+}
+
+/// Charge the next due period of a subscription
+/// Permissionless, so merchants or any crank can collect
+pub fn charge_subscription(accounts: &accounts::ChargeSubscription) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CHARGE_SUBSCRIPTION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Cancel a subscription, revoking its approval so no further periods are charged
+/// Only the subscribed customer can perform this operation
+pub fn cancel_subscription(accounts: &accounts::CancelSubscription) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CANCEL_SUBSCRIPTION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Create an airdrop from the merkle root of its (wallet, amount) allocations
+/// Only the update authority can perform this operation; budgets above the
+/// single-signature mint limit also need the mint co-signer
+pub fn create_airdrop(
+    accounts: &accounts::CreateAirdrop,
+    airdrop_id: u64,
+    merkle_root: &[u8; 32],
+    budget: u64,
+    expires_at: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_AIRDROP_DISCRIMINATOR,
+        (airdrop_id, merkle_root, budget, expires_at),
+    )
+}
+
+/// Claim the signer's airdrop allocation with a merkle proof, once per wallet
+pub fn claim_airdrop(
+    accounts: &accounts::ClaimAirdrop,
+    amount: u64,
+    proof: &[[u8; 32]],
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CLAIM_AIRDROP_DISCRIMINATOR,
+        (amount, proof),
+    )
+}
+
+/// Close an expired airdrop, retiring its unclaimed budget
+/// Only the update authority can perform this operation
+pub fn sweep_airdrop(accounts: &accounts::SweepAirdrop) -> Instruction {
+    instruction(accounts.to_account_metas(), SWEEP_AIRDROP_DISCRIMINATOR, ())
+}
+
+/// Authorize an ephemeral key to make purchases on the customer's behalf until it
+/// expires, escrowing up to `spend_limit` tokens for it to redeem
+pub fn create_session_key(
+    accounts: &accounts::CreateSessionKey,
+    session_signer: Pubkey,
+    spend_limit: u64,
+    expires_at: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_SESSION_KEY_DISCRIMINATOR,
+        (session_signer, spend_limit, expires_at),
+    )
+}
+
+/// Process a purchase signed by a session key instead of the customer
+/// Rewards are credited to the customer; redemptions draw on the session's allowance
+pub fn process_purchase_with_session(
+    accounts: &accounts::ProcessPurchaseWithSession,
+    fiat_amount: u64,
+    redemption: Option<RedemptionParams>,
+    transaction_id: &[u8; 32],
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROCESS_PURCHASE_WITH_SESSION_DISCRIMINATOR,
+        (fiat_amount, redemption, transaction_id),
+    )
+}
+
+/// Revoke a session key immediately, returning its unspent allowance
+/// Only the customer that authorized the session can perform this operation
+pub fn revoke_session_key(accounts: &accounts::RevokeSessionKey) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REVOKE_SESSION_KEY_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Update merchant settings such as cashback rate and active status
+/// Only the merchant owner can perform this operation
+pub fn update_merchant(
+    accounts: &accounts::UpdateMerchant,
+    new_cashback_rate: Option<u16>,
+    is_active: Option<bool>,
+    min_purchase_amount: Option<u64>,
+    redemption_bonus_bps: Option<u16>,
+    require_merchant_signature: Option<bool>,
+    compressed_receipts: Option<bool>,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        UPDATE_MERCHANT_DISCRIMINATOR,
+        (
+            new_cashback_rate,
+            is_active,
+            min_purchase_amount,
+            redemption_bonus_bps,
+            require_merchant_signature,
+            compressed_receipts,
+        ),
+    )
+}
+
+/// Register a point-of-sale key allowed to sign purchase approvals for the merchant
+/// Only the merchant owner can perform this operation
+pub fn register_pos_key(accounts: &accounts::RegisterPosKey, pos_key: Pubkey) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REGISTER_POS_KEY_DISCRIMINATOR,
+        pos_key,
+    )
+}
+
+/// Remove a point-of-sale key so its approvals are no longer accepted
+/// Only the merchant owner can perform this operation
+pub fn remove_pos_key(accounts: &accounts::RemovePosKey) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REMOVE_POS_KEY_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Add an operator wallet that may approve purchases and create invoices for the
+/// merchant, without access to its settings or funds
+/// Only the merchant owner can perform this operation
+pub fn add_merchant_operator(
+    accounts: &accounts::AddMerchantOperator,
+    operator: Pubkey,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ADD_MERCHANT_OPERATOR_DISCRIMINATOR,
+        operator,
+    )
+}
+
+/// Remove a merchant operator so it can no longer act for the merchant
+/// Only the merchant owner can perform this operation
+pub fn remove_merchant_operator(accounts: &accounts::RemoveMerchantOperator) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REMOVE_MERCHANT_OPERATOR_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Create the parent account of a franchise brand
+/// Outlets register under it with the owner's co-signature and their purchases
+/// roll up into its aggregate counters
+pub fn create_parent_merchant(
+    accounts: &accounts::CreateParentMerchant,
+    name: &str,
+    default_cashback_rate: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_PARENT_MERCHANT_DISCRIMINATOR,
+        (name, default_cashback_rate),
+    )
+}
+
+/// Update a parent merchant's default cashback rate
+/// Outlets are unchanged until the rate is cascaded to them
+/// Only the parent's owner can perform this operation
+pub fn update_parent_merchant(
+    accounts: &accounts::UpdateParentMerchant,
+    default_cashback_rate: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        UPDATE_PARENT_MERCHANT_DISCRIMINATOR,
+        default_cashback_rate,
+    )
+}
+
+/// Set a batch of outlets' cashback rate to their parent's default
+/// Outlet merchant accounts are passed as writable remaining accounts
+/// Only the parent's owner can perform this operation
+///
+/// `merchant_accounts` are passed as writable remaining accounts
+pub fn cascade_cashback_rate(
+    accounts: &accounts::CascadeCashbackRate,
+    merchant_accounts: &[Pubkey],
+) -> Instruction {
+    let mut metas = accounts.to_account_metas();
+    metas.extend(
+        merchant_accounts
+            .iter()
+            .map(|key| AccountMeta::new(*key, false)),
+    );
+    instruction(metas, CASCADE_CASHBACK_RATE_DISCRIMINATOR, ())
+}
+
+/// Escrow a sponsor's tokens as a cashback budget for a group of merchants
+/// Purchases at member merchants that pass the budget are rewarded out of it
+/// instead of with newly minted tokens, until it runs out
+pub fn create_reward_budget(
+    accounts: &accounts::CreateRewardBudget,
+    budget_id: u64,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_REWARD_BUDGET_DISCRIMINATOR,
+        (budget_id, amount),
+    )
+}
+
+/// Add tokens to a reward budget
+/// Only the budget's sponsor can perform this operation
+pub fn top_up_reward_budget(accounts: &accounts::TopUpRewardBudget, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        TOP_UP_REWARD_BUDGET_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Return unspent tokens from a reward budget to its sponsor
+/// Only the budget's sponsor can perform this operation
+pub fn withdraw_reward_budget(
+    accounts: &accounts::WithdrawRewardBudget,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        WITHDRAW_REWARD_BUDGET_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Let a reward budget pay the rewards of a merchant's purchases
+/// Only the budget's sponsor can perform this operation
+pub fn add_budget_member(accounts: &accounts::AddBudgetMember) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ADD_BUDGET_MEMBER_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Stop a reward budget from paying a merchant's rewards
+/// Only the budget's sponsor can perform this operation
+pub fn remove_budget_member(accounts: &accounts::RemoveBudgetMember) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REMOVE_BUDGET_MEMBER_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Create or update a protocol-funded cashback boost for every merchant in a category
+/// Only the update authority can perform this operation
+pub fn set_category_boost(
+    accounts: &accounts::SetCategoryBoost,
+    category: &str,
+    bonus_bps: u16,
+    start: i64,
+    end: i64,
+    budget: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_CATEGORY_BOOST_DISCRIMINATOR,
+        (category, bonus_bps, start, end, budget),
+    )
+}
+
+/// Remove a category boost
+/// Only the update authority can perform this operation
+pub fn close_category_boost(accounts: &accounts::CloseCategoryBoost) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CLOSE_CATEGORY_BOOST_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Change the token account the merchant's redeemed tokens are paid to
+/// Only the merchant owner can perform this operation
+pub fn set_payout_account(accounts: &accounts::SetPayoutAccount) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_PAYOUT_ACCOUNT_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Propose a new owner for the merchant, who takes over with `accept_merchant_ownership`
+/// Only the merchant owner can perform this operation
+pub fn propose_merchant_owner(
+    accounts: &accounts::ProposeMerchantOwner,
+    new_owner: Pubkey,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROPOSE_MERCHANT_OWNER_DISCRIMINATOR,
+        new_owner,
+    )
+}
+
+/// Take over a merchant as its proposed owner; the merchant keeps its address
+/// Only the proposed owner can perform this operation
+pub fn accept_merchant_ownership(accounts: &accounts::AcceptMerchantOwnership) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ACCEPT_MERCHANT_OWNERSHIP_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Reward customers who visit the merchant `streak_target` times within a window
+/// Only the merchant owner can perform this operation
+pub fn set_streak_reward(
+    accounts: &accounts::SetStreakReward,
+    streak_target: u16,
+    streak_window_seconds: u32,
+    streak_bonus: u64,
+    streak_counts_every_purchase: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_STREAK_REWARD_DISCRIMINATOR,
+        (
+            streak_target,
+            streak_window_seconds,
+            streak_bonus,
+            streak_counts_every_purchase,
+        ),
+    )
+}
+
+/// Close a merchant account and reclaim its rent
+/// The merchant's registry index stays in place, marked closed
+pub fn close_merchant(accounts: &accounts::CloseMerchant) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CLOSE_MERCHANT_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Set the extra cashback earned by customers who stake in the voucher pool
+/// Only the update authority can perform this operation
+pub fn set_staker_boost(
+    accounts: &accounts::SetStakerBoost,
+    staker_boost_threshold: u64,
+    staker_boost_bps: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_STAKER_BOOST_DISCRIMINATOR,
+        (staker_boost_threshold, staker_boost_bps),
+    )
+}
+
+/// Require a co-signer on administrative mints above a single-signature limit
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_mint_cosigner(
+    accounts: &accounts::SetMintCosigner,
+    mint_cosigner: Pubkey,
+    single_sig_mint_limit: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_MINT_COSIGNER_DISCRIMINATOR,
+        (mint_cosigner, single_sig_mint_limit),
+    )
+}
+
+/// Set the largest transfer, mint and purchase the program accepts
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_limits(
+    accounts: &accounts::SetLimits,
+    max_transfer_amount: u64,
+    max_mint_per_tx: u64,
+    max_purchase_idr: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_LIMITS_DISCRIMINATOR,
+        (max_transfer_amount, max_mint_per_tx, max_purchase_idr),
+    )
+}
+
+/// Cap the tokens administrative paths and purchase rewards may mint, each on its own
+/// Only the update authority can perform this operation; under a timelock use
+/// `queue_param_change`
+pub fn set_mint_caps(
+    accounts: &accounts::SetMintCaps,
+    admin_mint_cap: u64,
+    reward_mint_cap: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_MINT_CAPS_DISCRIMINATOR,
+        (admin_mint_cap, reward_mint_cap),
+    )
+}
+
+/// Set how often and how far merchants may change their cashback rates
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_rate_change_policy(
+    accounts: &accounts::SetRateChangePolicy,
+    rate_change_cooldown_seconds: u32,
+    max_rate_increase_bps: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_RATE_CHANGE_POLICY_DISCRIMINATOR,
+        (rate_change_cooldown_seconds, max_rate_increase_bps),
+    )
+}
+
+/// Cap merchants' cashback rates and set the rate that raises a `HighCashbackAlertEvent`
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_cashback_limits(
+    accounts: &accounts::SetCashbackLimits,
+    max_cashback_bps: u16,
+    alert_threshold_bps: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_CASHBACK_LIMITS_DISCRIMINATOR,
+        (max_cashback_bps, alert_threshold_bps),
+    )
+}
+
+/// Value tokens from a Pyth price feed instead of the static token rate
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_price_feed(
+    accounts: &accounts::SetPriceFeed,
+    price_feed: Pubkey,
+    max_price_age_seconds: u32,
+    max_price_confidence_bps: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_PRICE_FEED_DISCRIMINATOR,
+        (price_feed, max_price_age_seconds, max_price_confidence_bps),
+    )
+}
+
+/// Change how long after a purchase its customer may open a dispute
+/// Only the update authority can perform this operation, and only without a timelock;
+/// otherwise queue the change
+pub fn set_dispute_window(
+    accounts: &accounts::SetDisputeWindow,
+    dispute_window_seconds: u32,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_DISPUTE_WINDOW_DISCRIMINATOR,
+        dispute_window_seconds,
+    )
+}
+
+/// Set the backend key that records purchase fulfillment and how long after a purchase
+/// it may do so
+/// Only the update authority can perform this operation
+pub fn set_fulfillment_authority(
+    accounts: &accounts::SetFulfillmentAuthority,
+    fulfillment_authority: Pubkey,
+    fulfillment_window_seconds: u32,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_FULFILLMENT_AUTHORITY_DISCRIMINATOR,
+        (fulfillment_authority, fulfillment_window_seconds),
+    )
+}
+
+/// Hold purchase rewards in escrow as expiring reward lots instead of minting them
+/// to the customer
+/// Only the update authority can perform this operation
+pub fn set_reward_expiry(
+    accounts: &accounts::SetRewardExpiry,
+    enabled: bool,
+    reward_lifetime_seconds: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_REWARD_EXPIRY_DISCRIMINATOR,
+        (enabled, reward_lifetime_seconds),
+    )
+}
+
+/// Burn the remainder of an expired reward lot
+/// Anyone can call this; cranking a lot twice is a no-op
+pub fn expire_rewards(accounts: &accounts::ExpireRewards) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        EXPIRE_REWARDS_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Move an unexpired reward lot into the customer's wallet and close it
+/// Lots already burned by `expire_rewards` can also be closed this way
+pub fn withdraw_vested_rewards(accounts: &accounts::WithdrawVestedRewards) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        WITHDRAW_VESTED_REWARDS_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Mint a merchant incentive grant into escrow, vesting linearly to the beneficiary
+/// Only the update authority can perform this operation; grants above the
+/// single-signature mint limit also need the mint co-signer
+pub fn create_vesting_grant(
+    accounts: &accounts::CreateVestingGrant,
+    beneficiary: Pubkey,
+    total: u64,
+    start: i64,
+    cliff: i64,
+    duration: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CREATE_VESTING_GRANT_DISCRIMINATOR,
+        (beneficiary, total, start, cliff, duration),
+    )
+}
+
+/// Withdraw the vested, unclaimed part of a vesting grant
+/// Only the grant's beneficiary can perform this operation
+pub fn claim_vested(accounts: &accounts::ClaimVested) -> Instruction {
+    instruction(accounts.to_account_metas(), CLAIM_VESTED_DISCRIMINATOR, ())
+}
+
+/// Stop a vesting grant and return its unvested tokens to the treasury
+/// Only the update authority can perform this operation
+pub fn revoke_vesting_grant(accounts: &accounts::RevokeVestingGrant) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REVOKE_VESTING_GRANT_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Turn the wallet activity feed on or off
+/// While enabled, purchases and transfers must also write their `ActivityRecord`s
+/// Only the update authority can perform this operation
+pub fn set_activity_recording(
+    accounts: &accounts::SetActivityRecording,
+    enabled: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_ACTIVITY_RECORDING_DISCRIMINATOR,
+        enabled,
+    )
+}
+
+/// Choose whether `process_purchase` and `pay_invoice` write a slim
+/// `PurchaseTransactionV2` instead of a `PurchaseTransaction`
+/// Records already written keep their layout
+/// Only the update authority can perform this operation
+pub fn set_slim_purchase_records(
+    accounts: &accounts::SetSlimPurchaseRecords,
+    enabled: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_SLIM_PURCHASE_RECORDS_DISCRIMINATOR,
+        enabled,
+    )
+}
+
+/// Choose whether purchase records, their PDA seeds and purchase events carry
+/// sha256(customer || salt) instead of the customer's wallet
+/// The salt is stored on the config and can only be set once, so each customer keeps
+/// one hash; records already written keep what they hold
+/// Only the update authority can perform this operation
+pub fn set_customer_hashing(
+    accounts: &accounts::SetCustomerHashing,
+    enabled: bool,
+    salt: &[u8; 32],
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_CUSTOMER_HASHING_DISCRIMINATOR,
+        (enabled, salt),
+    )
+}
+
+/// Set the protocol fee taken from redeemed tokens and the treasury that receives it
+/// Only the update authority can perform this operation
+/// While a parameter timelock is set the fee must stay the same; queue fee changes instead
+pub fn set_protocol_fee(accounts: &accounts::SetProtocolFee, protocol_fee_bps: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_PROTOCOL_FEE_DISCRIMINATOR,
+        protocol_fee_bps,
+    )
+}
+
+/// Set the fee taken from peer-to-peer transfers, paid to the protocol treasury
+/// Only the update authority can perform this operation; requires a treasury to be set
+pub fn set_transfer_fee(accounts: &accounts::SetTransferFee, transfer_fee_bps: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_TRANSFER_FEE_DISCRIMINATOR,
+        transfer_fee_bps,
+    )
+}
+
+/// Configure dual control and the per-withdrawal cap for treasury withdrawals
+/// Only the update authority can perform this operation
+pub fn set_treasury_controls(
+    accounts: &accounts::SetTreasuryControls,
+    treasury_cosigner: Pubkey,
+    treasury_withdrawal_cap: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_TREASURY_CONTROLS_DISCRIMINATOR,
+        (treasury_cosigner, treasury_withdrawal_cap),
+    )
+}
+
+/// Withdraw collected protocol fees from the treasury token account
+/// Requires the update authority, plus the co-signer when dual control is on
+pub fn withdraw_treasury(
+    accounts: &accounts::WithdrawTreasury,
+    amount: u64,
+    reason_code: u16,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        WITHDRAW_TREASURY_DISCRIMINATOR,
+        (amount, reason_code),
+    )
+}
+
+/// Block a Lokal token account and freeze it, starting the clawback waiting period
+/// Only the update authority can perform this operation
+pub fn restrict_account(accounts: &accounts::RestrictAccount, reason_code: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RESTRICT_ACCOUNT_DISCRIMINATOR,
+        reason_code,
+    )
+}
+
+/// Lift a restriction that was not used for a clawback and thaw the account
+/// Only the update authority can perform this operation
+pub fn lift_account_restriction(accounts: &accounts::LiftAccountRestriction) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        LIFT_ACCOUNT_RESTRICTION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Credit the treasury with the balance of an account blocked for the waiting period
+/// Requires the update authority and the mint co-signer; the account stays frozen
+pub fn clawback(accounts: &accounts::Clawback) -> Instruction {
+    instruction(accounts.to_account_metas(), CLAWBACK_DISCRIMINATOR, ())
+}
+
+/// Set the delay that queued parameter changes must wait out
+/// Lengthening applies immediately; shortening an active timelock must be queued
+pub fn set_param_timelock(
+    accounts: &accounts::SetParamTimelock,
+    param_timelock_seconds: i64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_PARAM_TIMELOCK_DISCRIMINATOR,
+        param_timelock_seconds,
+    )
+}
+
+/// Queue a change to the token rate, protocol fee, mint co-signer, timelock or a pool's
+/// configuration
+/// The change can be executed once the configured timelock has passed
+pub fn queue_param_change(
+    accounts: &accounts::QueueParamChange,
+    change: ParamChange,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        QUEUE_PARAM_CHANGE_DISCRIMINATOR,
+        change,
+    )
+}
+
+/// Apply a queued parameter change after its timelock
+/// Anyone can execute it; the pending change account is closed to its proposer
+pub fn execute_param_change(accounts: &accounts::ExecuteParamChange) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        EXECUTE_PARAM_CHANGE_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Cancel a queued parameter change
+/// Only the authority that queued it can perform this operation
+pub fn cancel_param_change(accounts: &accounts::CancelParamChange) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CANCEL_PARAM_CHANGE_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Set the guardian, a key that can pause the program, pools and merchants
+/// Only the update authority can perform this operation
+pub fn set_guardian(accounts: &accounts::SetGuardian, guardian: Pubkey) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_GUARDIAN_DISCRIMINATOR,
+        guardian,
+    )
+}
+
+/// Halt or resume purchases and transfers
+/// The guardian can only pause; unpausing requires the update authority
+pub fn set_global_pause(accounts: &accounts::SetGlobalPause, paused: bool) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_GLOBAL_PAUSE_DISCRIMINATOR,
+        paused,
+    )
+}
+
+/// Suspend or reinstate a merchant, blocking purchases at it while suspended
+/// The guardian can only suspend; reinstating requires the update authority
+pub fn set_merchant_suspension(
+    accounts: &accounts::SetMerchantSuspension,
+    suspended: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_MERCHANT_SUSPENSION_DISCRIMINATOR,
+        suspended,
+    )
+}
+
+/// Preview the reward a purchase would earn, using the same math as `process_purchase`
+/// Read-only, so clients can simulate it without the customer's signature
+pub fn preview_purchase_reward(
+    accounts: &accounts::PreviewPurchaseReward,
+    fiat_amount: u64,
+    redeem_token_amount: Option<u64>,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PREVIEW_PURCHASE_REWARD_DISCRIMINATOR,
+        (fiat_amount, redeem_token_amount),
+    )
+}
+
+/// Report the program's health: pause flags, supply and mint headroom, pending
+/// parameter changes, and a pool's switches, vault backing and yield freshness
+/// Read-only, so monitoring bots can simulate it
+pub fn get_program_status(accounts: &accounts::GetProgramStatus) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        GET_PROGRAM_STATUS_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Transfer Lokal tokens between user accounts
+/// Enables peer-to-peer token transfers within the ecosystem
+/// The transfer fee, unless the sender is exempt, goes to the treasury out of `amount`
+pub fn transfer_tokens(
+    accounts: &accounts::TransferTokens,
+    amount: u64,
+    transaction_id: &[u8; 32],
+    memo: &str,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        TRANSFER_TOKENS_DISCRIMINATOR,
+        (amount, transaction_id, memo),
+    )
+}
+
+/// Exempt a wallet (e.g. a merchant) from the transfer fee on transfers it sends
+/// Only the update authority can perform this operation
+pub fn add_fee_exemption(accounts: &accounts::AddFeeExemption) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ADD_FEE_EXEMPTION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Remove a wallet's transfer fee exemption
+/// Only the update authority can perform this operation
+pub fn remove_fee_exemption(accounts: &accounts::RemoveFeeExemption) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REMOVE_FEE_EXEMPTION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Initialize a new voucher staking pool for LOKAL tokens
+/// Creates the pool state and configures staking parameters
+/// Several pools can coexist, each addressed by its voucher mint and `pool_id`
+pub fn initialize_pool(
+    accounts: &accounts::InitializePool,
+    pool_id: u16,
+    config: PoolConfig,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        INITIALIZE_POOL_DISCRIMINATOR,
+        (pool_id, config),
+    )
+}
+
+/// Deposit voucher tokens into the staking pool using delegated authority
+/// The user must have previously approved the pool delegate for at least `amount`
+/// When `auto_revoke_delegation` is set, any leftover approval is revoked afterwards
+/// Any insurance fee stays in the vault; the user is credited with the rest
+/// Mints sLOKAL receipt tokens 1:1 for the credited stake
+/// Whitelist-gated pools also require the user's whitelist entry
+pub fn deposit_voucher(accounts: &accounts::DepositVoucher, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        DEPOSIT_VOUCHER_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Deposit voucher tokens into the staking pool on behalf of a beneficiary
+/// The funder signs and pays from its own token account; the stake, its receipts and the
+/// right to redeem belong to the beneficiary, with the funder recorded as `funded_by`
+/// The per-user cap and whitelist apply to the beneficiary
+pub fn deposit_for_beneficiary(
+    accounts: &accounts::DepositForBeneficiary,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        DEPOSIT_FOR_BENEFICIARY_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Record yield earned from staking activities
+/// Called by the backend after swapping vouchers to SOL and earning yield
+/// The pool's performance fee is set aside in `protocol_fees_accrued`; stakers get the rest
+pub fn record_yield(
+    accounts: &accounts::RecordYield,
+    sol_amount: u64,
+    yield_batch_id: &[u8; 32],
+    expected_previous_index: Option<u128>,
+    external_sequence: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RECORD_YIELD_DISCRIMINATOR,
+        (
+            sol_amount,
+            yield_batch_id,
+            expected_previous_index,
+            external_sequence,
+        ),
+    )
+}
+
+/// Reset the pool's yield journal position after a genuine journal reset
+/// Only the pool delegate can perform this operation; always logged and evented, since
+/// it lets journal entries be recorded again
+pub fn reset_external_sequence(
+    accounts: &accounts::ResetExternalSequence,
+    last_external_sequence: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RESET_EXTERNAL_SEQUENCE_DISCRIMINATOR,
+        last_external_sequence,
+    )
+}
+
+/// Point the pool's deployed SOL at an SPL stake pool
+/// Only the pool authority can perform this operation; only available in builds with the
+/// `stake-pool` feature
+pub fn configure_stake_pool(accounts: &accounts::ConfigureStakePool) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CONFIGURE_STAKE_POOL_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Deposit SOL from the pool's SOL vault into its stake pool for pool tokens
+/// Only the pool authority can perform this operation; only available in builds with the
+/// `stake-pool` feature
+pub fn deploy_to_stake_pool(accounts: &accounts::DeployToStakePool, lamports: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        DEPLOY_TO_STAKE_POOL_DISCRIMINATOR,
+        lamports,
+    )
+}
+
+/// Redeem pool tokens for SOL paid back into the pool's SOL vault, realizing the gain
+/// or loss against their cost basis
+/// Only the pool authority can perform this operation; only available in builds with the
+/// `stake-pool` feature
+pub fn unwind_from_stake_pool(
+    accounts: &accounts::UnwindFromStakePool,
+    pool_tokens: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        UNWIND_FROM_STAKE_POOL_DISCRIMINATOR,
+        pool_tokens,
+    )
+}
+
+/// Record a loss on deployed capital
+/// Called by the backend; reduces claimable yield in proportion to stake
+pub fn record_loss(accounts: &accounts::RecordLoss, sol_amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RECORD_LOSS_DISCRIMINATOR,
+        sol_amount,
+    )
+}
+
+/// Redeem staked vouchers and claim earned yield
+/// Allows users to unstake their tokens and withdraw
+/// Withdrawals before the stake's lock expires pay the pool's early-withdrawal penalty
+/// Burns an equal amount of sLOKAL receipts, so at most min(staked, receipts) is redeemable
+/// Stake locked as collateral for a `TokenAdvance` cannot be redeemed until it is repaid
+pub fn redeem_voucher(accounts: &accounts::RedeemVoucher, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REDEEM_VOUCHER_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Redeem the whole stake and claim earned yield, for a full exit
+/// Redeems `staked_amount` as it stands when the instruction runs, so yield, losses or
+/// penalties applied since the caller last read the stake leave no dust behind
+/// Same accounts and rules as `redeem_voucher`; follow it with `close_stake_record` in
+/// the same transaction to also reclaim the record's rent
+pub fn redeem_all_vouchers(accounts: &accounts::RedeemVoucher) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REDEEM_ALL_VOUCHERS_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Update pool configuration settings
+/// Only the pool authority can perform this operation
+/// Only the provided fields change; the merged config is validated as a whole
+/// Rejected while a parameter timelock is set; use `queue_param_change` instead
+pub fn update_pool_config(
+    accounts: &accounts::UpdatePoolConfig,
+    update: PoolConfigUpdate,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        UPDATE_POOL_CONFIG_DISCRIMINATOR,
+        update,
+    )
+}
+
+/// Set the share of a stake its owner may borrow with `borrow_against_stake`
+/// Only the pool authority can perform this operation; 0 disables new advances
+/// Rejected while a parameter timelock is set; use `queue_param_change` instead
+pub fn set_advance_ltv(accounts: &accounts::SetAdvanceLtv, advance_ltv_bps: u16) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_ADVANCE_LTV_DISCRIMINATOR,
+        advance_ltv_bps,
+    )
+}
+
+/// Mint an interest-free advance of Lokal tokens against the user's stake
+/// The outstanding advance may reach the pool's `advance_ltv_bps` of the stake; the
+/// stake securing it is locked against `redeem_voucher` and `request_unstake`
+pub fn borrow_against_stake(accounts: &accounts::BorrowAgainstStake, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        BORROW_AGAINST_STAKE_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Burn Lokal tokens to repay part or all of an advance
+/// The stake lock shrinks with the advance; a fully repaid advance is closed
+pub fn repay_advance(accounts: &accounts::RepayAdvance, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REPAY_ADVANCE_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Set the token account that receives vouchers withdrawn for deployment
+/// Only the pool authority can perform this operation
+pub fn set_deployment_destination(accounts: &accounts::SetDeploymentDestination) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_DEPLOYMENT_DESTINATION_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Withdraw staked vouchers from the vault for off-chain deployment
+/// Called by the backend before swapping vouchers to SOL
+pub fn withdraw_for_deployment(
+    accounts: &accounts::WithdrawForDeployment,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        WITHDRAW_FOR_DEPLOYMENT_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Return previously deployed voucher tokens to the vault
+pub fn return_principal(accounts: &accounts::ReturnPrincipal, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        RETURN_PRINCIPAL_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Close an empty user stake record and reclaim its rent
+/// Callable by the staker or the pool delegate; rent returns to whoever paid for the record
+pub fn close_stake_record(accounts: &accounts::CloseStakeRecord) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CLOSE_STAKE_RECORD_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Close a batch of fully exited stake records, passed as writable remaining accounts
+/// Only the pool delegate can call this; records it did not pay for, or that still
+/// hold stake, history or yield, are skipped
+pub fn close_stake_records(accounts: &accounts::CloseStakeRecords) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CLOSE_STAKE_RECORDS_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Resync a user's reward checkpoint to the pool's current reward index
+/// Only the pool authority can perform this operation; any unclaimed yield
+/// between the two indices is forfeited
+pub fn repair_stake_record(accounts: &accounts::RepairStakeRecord) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REPAIR_STAKE_RECORD_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Set the guardian key that can pause the pool in an emergency
+/// Only the pool authority can perform this operation
+pub fn set_pool_guardian(accounts: &accounts::SetPoolGuardian, guardian: Pubkey) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_POOL_GUARDIAN_DISCRIMINATOR,
+        guardian,
+    )
+}
+
+/// Emergency pause for pool deposits and withdrawals
+/// Callable by the pool authority, the pool guardian or the mint guardian; only the
+/// authority can unpause
+pub fn set_pool_pause(
+    accounts: &accounts::SetPoolPause,
+    pause_deposits: Option<bool>,
+    pause_withdrawals: Option<bool>,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_POOL_PAUSE_DISCRIMINATOR,
+        (pause_deposits, pause_withdrawals),
+    )
+}
+
+/// Nominate a new pool authority (first step of a two-step transfer)
+/// Only the current pool authority can perform this operation
+pub fn propose_pool_authority(
+    accounts: &accounts::ProposePoolAuthority,
+    new_authority: Pubkey,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROPOSE_POOL_AUTHORITY_DISCRIMINATOR,
+        new_authority,
+    )
+}
+
+/// Accept a pool authority nomination (second step of a two-step transfer)
+/// Must be signed by the nominated authority
+pub fn accept_pool_authority(accounts: &accounts::AcceptPoolAuthority) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ACCEPT_POOL_AUTHORITY_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Opt a stake in or out of automatic yield compounding
+pub fn set_auto_compound(accounts: &accounts::SetAutoCompound, enabled: bool) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SET_AUTO_COMPOUND_DISCRIMINATOR,
+        enabled,
+    )
+}
+
+/// Restake a user's claimable yield as vouchers bought by the delegate
+/// Only the pool delegate can perform this operation
+pub fn compound_user(accounts: &accounts::CompoundUser, rate: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        COMPOUND_USER_DISCRIMINATOR,
+        rate,
+    )
+}
+
+/// Approve a wallet to deposit into a whitelist-gated pool
+/// Only the pool authority can perform this operation
+pub fn add_to_whitelist(accounts: &accounts::AddToWhitelist) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ADD_TO_WHITELIST_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Revoke a wallet's whitelist approval; existing stakes can still be withdrawn
+/// Only the pool authority can perform this operation
+pub fn remove_from_whitelist(accounts: &accounts::RemoveFromWhitelist) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REMOVE_FROM_WHITELIST_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Settle accrued yield into `pending_payout` for a batch of stake records
+/// Records are passed as writable remaining accounts; up-to-date records are skipped
+/// Only the pool delegate can perform this operation
+///
+/// `stake_records` are passed as writable remaining accounts
+pub fn settle_users(accounts: &accounts::SettleUsers, stake_records: &[Pubkey]) -> Instruction {
+    let mut metas = accounts.to_account_metas();
+    metas.extend(
+        stake_records
+            .iter()
+            .map(|key| AccountMeta::new(*key, false)),
+    );
+    instruction(metas, SETTLE_USERS_DISCRIMINATOR, ())
+}
+
+/// Accrue the guaranteed minimum APY yield since the last yield update
+/// Permissionless; accrual is capped by the pool's guaranteed yield budget
+pub fn accrue_minimum_yield(accounts: &accounts::AccrueMinimumYield) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        ACCRUE_MINIMUM_YIELD_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Queue part of a stake for withdrawal; the amount stops earning yield immediately
+/// Only available in pools with queued withdrawals enabled
+pub fn request_unstake(accounts: &accounts::RequestUnstake, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        REQUEST_UNSTAKE_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Cancel a pending unstake request and restake its amount
+pub fn cancel_unstake(accounts: &accounts::CancelUnstake) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        CANCEL_UNSTAKE_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Pay out an unstake request once the pool's unstake delay has passed
+/// Permissionless; the vouchers always go to the requesting user
+pub fn process_unstake(accounts: &accounts::ProcessUnstake) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PROCESS_UNSTAKE_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Check the pool's accounting invariants and emit a pass/fail report
+/// Permissionless; in strict mode any failed invariant returns an error
+pub fn verify_pool_invariants(
+    accounts: &accounts::VerifyPoolInvariants,
+    strict: bool,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        VERIFY_POOL_INVARIANTS_DISCRIMINATOR,
+        strict,
+    )
+}
+
+/// Move insurance reserve to the deployment destination to cover a recorded loss
+/// Only callable by the pool authority; total draws are capped by cumulative losses
+pub fn draw_insurance(accounts: &accounts::DrawInsurance, amount: u64) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        DRAW_INSURANCE_DISCRIMINATOR,
+        amount,
+    )
+}
+
+/// Preview the yield a user would claim on redeem, using the same math as `redeem_voucher`
+/// Read-only, so clients can simulate it without the user's signature
+pub fn preview_claimable_yield(
+    accounts: &accounts::PreviewClaimableYield,
+    user: Pubkey,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        PREVIEW_CLAIMABLE_YIELD_DISCRIMINATOR,
+        user,
+    )
+}
+
+/// Refresh the `pending_yield` and `pool_share_bps` cached on a stake record
+/// Permissionless; deposits and redemptions refresh it too, and between those it goes
+/// stale as the pool records yield
+pub fn sync_stake_view(accounts: &accounts::SyncStakeView) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        SYNC_STAKE_VIEW_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Write today's aggregate protocol stats (supply, merchants, purchases, transfers, staking)
+/// Permissionless; calling again on the same day refreshes the snapshot
+pub fn write_stats_snapshot(
+    accounts: &accounts::WriteStatsSnapshot,
+    day_index: u64,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        WRITE_STATS_SNAPSHOT_DISCRIMINATOR,
+        day_index,
+    )
+}
+
+/// Rewrite the original singleton pool from its first-deployment layout
+/// Grows the account to the current `PoolState` size and creates its receipt mint
+pub fn migrate_pool(accounts: &accounts::MigratePool) -> Instruction {
+    instruction(accounts.to_account_metas(), MIGRATE_POOL_DISCRIMINATOR, ())
+}
+
+/// Rewrite a stake in the original pool from its first-deployment layout
+/// Permissionless; also mints the staker's sLOKAL receipts, which legacy stakes lack
+pub fn migrate_stake_record(accounts: &accounts::MigrateStakeRecord) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MIGRATE_STAKE_RECORD_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Rewrite the mint configuration from its first-deployment layout
+/// Grows the account to the current `LokalMintConfig` size and fills the fields the
+/// legacy layout lacks with the defaults of `initialize_lokal_mint`
+pub fn migrate_config(accounts: &accounts::MigrateConfig) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MIGRATE_CONFIG_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Rewrite a merchant from its first-deployment layout
+/// Permissionless; grows the account to the current `MerchantAccount` size and gives
+/// the merchant the registry position legacy merchants lack
+pub fn migrate_merchant(accounts: &accounts::MigrateMerchant) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MIGRATE_MERCHANT_DISCRIMINATOR,
+        (),
+    )
+}
+
+/// Rewrite a purchase record from its 164-byte layout
+/// Permissionless; grows the account to the current `PurchaseTransaction` size with the
+/// fields added since unset, so disputes and fulfillment can load it again
+pub fn migrate_purchase_transaction(
+    accounts: &accounts::MigratePurchaseTransaction,
+) -> Instruction {
+    instruction(
+        accounts.to_account_metas(),
+        MIGRATE_PURCHASE_TRANSACTION_DISCRIMINATOR,
+        (),
+    )
+}
@@ -0,0 +1,35 @@
+//! Rust client for the Carsa program
+//! Builds instructions, derives program addresses and decodes accounts without linking
+//! the program or the Anchor runtime, so backends and off-chain tools can depend on it
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod purchase;
+pub mod state;
+pub mod status;
+
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+
+solana_program::declare_id!("FicaEwstRkE9pwHZPWS34XAjnbH6vc8aZ2Ly4EiksmxY");
+
+/// An account type stored by the program behind Anchor's 8-byte discriminator
+pub trait CarsaAccount: BorshDeserialize {
+    /// First 8 bytes of sha256("account:<TypeName>")
+    const DISCRIMINATOR: [u8; 8];
+
+    /// Decodes the account from its raw data, checking the discriminator
+    /// Trailing bytes past the encoded fields are ignored, as Anchor does
+    fn try_deserialize(data: &[u8]) -> std::io::Result<Self> {
+        let body = data.strip_prefix(&Self::DISCRIMINATOR).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "discriminator mismatch")
+        })?;
+        Self::deserialize(&mut &body[..])
+    }
+}
+
+/// The program's address, for callers that keep the id in a variable
+pub fn program_id() -> Pubkey {
+    ID
+}
@@ -0,0 +1,576 @@
+//! Program derived addresses used by the Carsa program
+//! Each `find_*` helper takes the same seeds as the matching `seeds = [...]` constraint
+//! and returns the address with its bump
+
+use solana_program::pubkey::Pubkey;
+
+use crate::ID;
+
+/// Seeds for deriving the mint authority PDA
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+
+/// Seeds for deriving the config account PDA
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seeds for deriving merchant account PDAs
+pub const MERCHANT_SEED: &[u8] = b"merchant";
+
+/// Seeds for deriving the merchant registry PDA
+pub const MERCHANT_REGISTRY_SEED: &[u8] = b"merchant_registry";
+
+/// Seeds for deriving merchant index PDAs
+pub const MERCHANT_INDEX_SEED: &[u8] = b"merchant_index";
+
+/// Seeds for deriving daily stats snapshot PDAs
+pub const SNAPSHOT_SEED: &[u8] = b"snapshot";
+
+/// Seeds for deriving customer monthly stats PDAs
+pub const CUSTOMER_MONTHLY_STATS_SEED: &[u8] = b"customer_monthly_stats";
+
+/// Seeds for deriving purchase transaction PDAs
+pub const TRANSACTION_SEED: &[u8] = b"transaction";
+
+/// Seeds for deriving token transfer PDAs
+pub const TRANSFER_SEED: &[u8] = b"transfer";
+
+/// Seeds for deriving the treasury authority PDA
+pub const TREASURY_AUTHORITY_SEED: &[u8] = b"treasury_authority";
+
+/// Seeds for deriving treasury withdrawal PDAs
+pub const TREASURY_WITHDRAWAL_SEED: &[u8] = b"treasury_withdrawal";
+
+/// Seeds for deriving fee exemption PDAs
+pub const FEE_EXEMPTION_SEED: &[u8] = b"fee_exemption";
+
+/// Seeds for deriving activity record PDAs
+pub const ACTIVITY_SEED: &[u8] = b"activity";
+
+/// Seeds for deriving pending parameter change PDAs
+pub const PARAM_CHANGE_SEED: &[u8] = b"param_change";
+
+/// Seeds for deriving the reward escrow authority PDA
+pub const REWARD_ESCROW_SEED: &[u8] = b"reward_escrow";
+
+/// Seeds for deriving reward lot PDAs
+pub const REWARD_LOT_SEED: &[u8] = b"reward_lot";
+
+/// Seeds for deriving the vesting escrow authority PDA
+pub const VESTING_ESCROW_SEED: &[u8] = b"vesting_escrow";
+
+/// Seeds for deriving vesting grant PDAs
+pub const VESTING_GRANT_SEED: &[u8] = b"vesting_grant";
+
+/// Seeds for deriving invoice PDAs
+pub const INVOICE_SEED: &[u8] = b"invoice";
+
+/// Seeds for deriving the hold escrow authority PDA
+pub const HOLD_ESCROW_SEED: &[u8] = b"hold_escrow";
+
+/// Seeds for deriving hold PDAs
+pub const HOLD_SEED: &[u8] = b"hold";
+
+/// Seeds for deriving dispute PDAs
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+
+/// Seeds for deriving settlement record PDAs
+pub const SETTLEMENT_SEED: &[u8] = b"settlement";
+
+/// Seeds for deriving the gift card escrow authority PDA
+pub const GIFT_CARD_ESCROW_SEED: &[u8] = b"gift_card_escrow";
+
+/// Seeds for deriving gift card PDAs
+pub const GIFT_CARD_SEED: &[u8] = b"gift_card";
+
+/// Seeds for deriving subscription PDAs
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+
+/// Seeds for deriving airdrop PDAs
+pub const AIRDROP_SEED: &[u8] = b"airdrop";
+
+/// Seeds for deriving airdrop claim PDAs
+pub const AIRDROP_CLAIM_SEED: &[u8] = b"airdrop_claim";
+
+/// Seeds for deriving the session escrow authority PDA
+pub const SESSION_ESCROW_SEED: &[u8] = b"session_escrow";
+
+/// Seeds for deriving session key PDAs
+pub const SESSION_KEY_SEED: &[u8] = b"session_key";
+
+/// Seeds for deriving point-of-sale key PDAs
+pub const POS_KEY_SEED: &[u8] = b"pos_key";
+
+/// Seeds for deriving merchant operator PDAs
+pub const MERCHANT_OPERATOR_SEED: &[u8] = b"merchant_operator";
+
+/// Seeds for deriving parent merchant PDAs
+pub const PARENT_MERCHANT_SEED: &[u8] = b"parent_merchant";
+
+/// Seeds for deriving the reward budget escrow authority PDA
+pub const REWARD_BUDGET_ESCROW_SEED: &[u8] = b"reward_budget_escrow";
+
+/// Seeds for deriving reward budget PDAs
+pub const REWARD_BUDGET_SEED: &[u8] = b"reward_budget";
+
+/// Seeds for deriving budget membership PDAs
+pub const BUDGET_MEMBERSHIP_SEED: &[u8] = b"budget_membership";
+
+/// Seeds for deriving category boost PDAs
+pub const CATEGORY_BOOST_SEED: &[u8] = b"category_boost";
+
+/// Seeds for deriving customer-merchant visit stats PDAs
+pub const CUSTOMER_MERCHANT_STATS_SEED: &[u8] = b"cm_stats";
+
+/// Seeds for deriving transaction stub PDAs
+pub const TRANSACTION_STUB_SEED: &[u8] = b"tx_stub";
+
+/// Seeds for deriving purchase reference index PDAs
+pub const REFERENCE_INDEX_SEED: &[u8] = b"reference";
+
+/// Seeds for deriving the pool state PDA
+pub const POOL_STATE_SEED: &[u8] = b"pool_state";
+
+/// Seeds for deriving the pool vault authority PDA
+pub const POOL_VAULT_AUTHORITY_SEED: &[u8] = b"pool_vault_authority";
+
+/// Seeds for deriving user stake record PDAs
+pub const USER_STAKE_SEED: &[u8] = b"user_stake";
+
+/// Seeds for deriving the pool receipt mint PDA
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+
+/// Seeds for deriving stake whitelist entry PDAs
+pub const STAKE_WHITELIST_SEED: &[u8] = b"stake_whitelist";
+
+/// Seeds for deriving unstake request PDAs
+pub const UNSTAKE_REQUEST_SEED: &[u8] = b"unstake_request";
+
+/// Seeds for deriving token advance PDAs
+pub const TOKEN_ADVANCE_SEED: &[u8] = b"token_advance";
+
+/// Seeds for deriving stake pool deployment PDAs
+pub const STAKE_POOL_DEPLOYMENT_SEED: &[u8] = b"stake_pool_deployment";
+
+/// Seeds for deriving the system account holding a pool's SOL awaiting deployment
+pub const POOL_SOL_VAULT_SEED: &[u8] = b"pool_sol_vault";
+
+/// Seeds for deriving the token account holding a pool's stake pool tokens
+pub const STAKE_POOL_TOKENS_SEED: &[u8] = b"stake_pool_tokens";
+
+/// Seeds for deriving stake action history PDAs
+pub const STAKE_ACTION_SEED: &[u8] = b"stake_action";
+
+/// Seeds for deriving yield batch PDAs
+pub const YIELD_BATCH_SEED: &[u8] = b"yield_batch";
+
+/// Seeds of the authority Anchor's `#[event_cpi]` self-invocations sign with
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+/// Seed suffix identifying a pool of a voucher mint in the pool state, vault authority and
+/// receipt mint PDAs, after the mint
+/// Pool 0 uses no suffix, so the pool created before multi-pool support keeps its addresses
+pub fn pool_id_seed(pool_id: u16) -> Vec<u8> {
+    if pool_id == 0 {
+        Vec::new()
+    } else {
+        pool_id.to_le_bytes().to_vec()
+    }
+}
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &ID)
+}
+
+/// Authority signing the program's event CPIs
+pub fn find_event_authority_pda() -> (Pubkey, u8) {
+    find(&[EVENT_AUTHORITY_SEED])
+}
+
+/// Mint authority of the Lokal token
+pub fn find_mint_authority_pda() -> (Pubkey, u8) {
+    find(&[MINT_AUTHORITY_SEED])
+}
+
+/// Program-wide `LokalMintConfig`
+pub fn find_config_pda() -> (Pubkey, u8) {
+    find(&[CONFIG_SEED])
+}
+
+/// `MerchantAccount` registered by `merchant_owner`
+/// A merchant keeps this address after `accept_merchant_ownership` hands it to a new owner
+pub fn find_merchant_pda(merchant_owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[MERCHANT_SEED, merchant_owner.as_ref()])
+}
+
+/// Program-wide `MerchantRegistry`
+pub fn find_merchant_registry_pda() -> (Pubkey, u8) {
+    find(&[MERCHANT_REGISTRY_SEED])
+}
+
+/// `MerchantIndex` at position `index` of the registry
+pub fn find_merchant_index_pda(index: u64) -> (Pubkey, u8) {
+    find(&[MERCHANT_INDEX_SEED, &index.to_le_bytes()])
+}
+
+/// `StatsSnapshot` for `day_index` (days since the Unix epoch)
+pub fn find_stats_snapshot_pda(day_index: u64) -> (Pubkey, u8) {
+    find(&[SNAPSHOT_SEED, &day_index.to_le_bytes()])
+}
+
+/// `CustomerMonthlyStats` of `customer` for `year_month` (YYYYMM)
+pub fn find_customer_monthly_stats_pda(customer: &Pubkey, year_month: u32) -> (Pubkey, u8) {
+    find(&[
+        CUSTOMER_MONTHLY_STATS_SEED,
+        customer.as_ref(),
+        &year_month.to_le_bytes(),
+    ])
+}
+
+/// What purchase records hold for `customer` once the config sets
+/// `hash_customer_in_records`: sha256(customer || salt), with the config's
+/// `customer_hash_salt`
+/// Passed as `customer` to the purchase record, stub and stats finders, it finds a
+/// customer's own privacy-mode history
+pub fn customer_hash(customer: &Pubkey, salt: &[u8; 32]) -> Pubkey {
+    Pubkey::new_from_array(solana_program::hash::hashv(&[customer.as_ref(), salt]).to_bytes())
+}
+
+/// `PurchaseTransaction` of `customer` at `merchant_account` for `transaction_id`
+pub fn find_transaction_pda(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> (Pubkey, u8) {
+    find(&[
+        TRANSACTION_SEED,
+        merchant_account.as_ref(),
+        customer.as_ref(),
+        transaction_id,
+    ])
+}
+
+/// `PurchaseTransactionV2` of `customer` at `merchant_account` for `transaction_id`,
+/// addressed by its first 16 bytes
+pub fn find_slim_transaction_pda(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> (Pubkey, u8) {
+    find(&[
+        TRANSACTION_SEED,
+        merchant_account.as_ref(),
+        customer.as_ref(),
+        &transaction_id[..16],
+    ])
+}
+
+/// `PurchaseTransaction` of `customer` for `transaction_id`, for records created before
+/// purchases were keyed by merchant
+pub fn find_legacy_transaction_pda(customer: &Pubkey, transaction_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[TRANSACTION_SEED, customer.as_ref(), transaction_id])
+}
+
+/// `TokenTransfer` of `sender` for `transaction_id`
+pub fn find_transfer_pda(sender: &Pubkey, transaction_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[TRANSFER_SEED, sender.as_ref(), transaction_id])
+}
+
+/// Authority over the treasury token account
+pub fn find_treasury_authority_pda() -> (Pubkey, u8) {
+    find(&[TREASURY_AUTHORITY_SEED])
+}
+
+/// `TreasuryWithdrawal` numbered `withdrawal_index`
+pub fn find_treasury_withdrawal_pda(withdrawal_index: u64) -> (Pubkey, u8) {
+    find(&[TREASURY_WITHDRAWAL_SEED, &withdrawal_index.to_le_bytes()])
+}
+
+/// `FeeExemption` for `wallet`
+pub fn find_fee_exemption_pda(wallet: &Pubkey) -> (Pubkey, u8) {
+    find(&[FEE_EXEMPTION_SEED, wallet.as_ref()])
+}
+
+/// `ActivityRecord` of `customer` for `transaction_id`, with `kind` the `ActivityKind` as u8;
+/// purchases in privacy mode pass the `customer_hash`
+pub fn find_activity_pda(customer: &Pubkey, transaction_id: &[u8; 32], kind: u8) -> (Pubkey, u8) {
+    find(&[ACTIVITY_SEED, customer.as_ref(), transaction_id, &[kind]])
+}
+
+/// `PendingParamChange` numbered `change_id`
+pub fn find_param_change_pda(change_id: u64) -> (Pubkey, u8) {
+    find(&[PARAM_CHANGE_SEED, &change_id.to_le_bytes()])
+}
+
+/// Authority over the reward escrow token account
+pub fn find_reward_escrow_pda() -> (Pubkey, u8) {
+    find(&[REWARD_ESCROW_SEED])
+}
+
+/// `RewardLot` of `customer` for `transaction_id`, or of its `customer_hash` in privacy mode
+pub fn find_reward_lot_pda(customer: &Pubkey, transaction_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[REWARD_LOT_SEED, customer.as_ref(), transaction_id])
+}
+
+/// Authority over the vesting escrow token account
+pub fn find_vesting_escrow_pda() -> (Pubkey, u8) {
+    find(&[VESTING_ESCROW_SEED])
+}
+
+/// `VestingGrant` numbered `grant_id`
+pub fn find_vesting_grant_pda(grant_id: u64) -> (Pubkey, u8) {
+    find(&[VESTING_GRANT_SEED, &grant_id.to_le_bytes()])
+}
+
+/// `Invoice` issued by `merchant_account` under `reference_id`
+pub fn find_invoice_pda(merchant_account: &Pubkey, reference_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[INVOICE_SEED, merchant_account.as_ref(), reference_id])
+}
+
+/// Authority over the hold escrow token account
+pub fn find_hold_escrow_pda() -> (Pubkey, u8) {
+    find(&[HOLD_ESCROW_SEED])
+}
+
+/// `Hold` of `customer` for `hold_id`
+pub fn find_hold_pda(customer: &Pubkey, hold_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[HOLD_SEED, customer.as_ref(), hold_id])
+}
+
+/// `Dispute` over the purchase record at `transaction_record`
+pub fn find_dispute_pda(transaction_record: &Pubkey) -> (Pubkey, u8) {
+    find(&[DISPUTE_SEED, transaction_record.as_ref()])
+}
+
+/// `SettlementRecord` of `merchant_account` for `period_id`
+pub fn find_settlement_pda(merchant_account: &Pubkey, period_id: u64) -> (Pubkey, u8) {
+    find(&[
+        SETTLEMENT_SEED,
+        merchant_account.as_ref(),
+        &period_id.to_le_bytes(),
+    ])
+}
+
+/// Authority over the gift card escrow token account
+pub fn find_gift_card_escrow_pda() -> (Pubkey, u8) {
+    find(&[GIFT_CARD_ESCROW_SEED])
+}
+
+/// `GiftCard` redeemable with the code hashing to `code_hash`
+pub fn find_gift_card_pda(code_hash: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[GIFT_CARD_SEED, code_hash])
+}
+
+/// `Subscription` of `customer` to `merchant_account`
+pub fn find_subscription_pda(customer: &Pubkey, merchant_account: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        SUBSCRIPTION_SEED,
+        customer.as_ref(),
+        merchant_account.as_ref(),
+    ])
+}
+
+/// `Airdrop` numbered `airdrop_id`
+pub fn find_airdrop_pda(airdrop_id: u64) -> (Pubkey, u8) {
+    find(&[AIRDROP_SEED, &airdrop_id.to_le_bytes()])
+}
+
+/// `AirdropClaim` of `claimant` against `airdrop`
+pub fn find_airdrop_claim_pda(airdrop: &Pubkey, claimant: &Pubkey) -> (Pubkey, u8) {
+    find(&[AIRDROP_CLAIM_SEED, airdrop.as_ref(), claimant.as_ref()])
+}
+
+/// Authority over the session escrow token account
+pub fn find_session_escrow_pda() -> (Pubkey, u8) {
+    find(&[SESSION_ESCROW_SEED])
+}
+
+/// `SessionKey` letting `session_signer` act for `customer`
+pub fn find_session_key_pda(customer: &Pubkey, session_signer: &Pubkey) -> (Pubkey, u8) {
+    find(&[SESSION_KEY_SEED, customer.as_ref(), session_signer.as_ref()])
+}
+
+/// `PosKey` registering `pos_key` for `merchant_account`
+pub fn find_pos_key_pda(merchant_account: &Pubkey, pos_key: &Pubkey) -> (Pubkey, u8) {
+    find(&[POS_KEY_SEED, merchant_account.as_ref(), pos_key.as_ref()])
+}
+
+/// `MerchantOperator` letting `operator` act for `merchant_account`
+pub fn find_merchant_operator_pda(merchant_account: &Pubkey, operator: &Pubkey) -> (Pubkey, u8) {
+    find(&[
+        MERCHANT_OPERATOR_SEED,
+        merchant_account.as_ref(),
+        operator.as_ref(),
+    ])
+}
+
+/// `ParentMerchant` owned by `owner`
+pub fn find_parent_merchant_pda(owner: &Pubkey) -> (Pubkey, u8) {
+    find(&[PARENT_MERCHANT_SEED, owner.as_ref()])
+}
+
+/// Authority over the reward budget escrow token account
+pub fn find_reward_budget_escrow_pda() -> (Pubkey, u8) {
+    find(&[REWARD_BUDGET_ESCROW_SEED])
+}
+
+/// `RewardBudget` of `sponsor` numbered `budget_id`
+pub fn find_reward_budget_pda(sponsor: &Pubkey, budget_id: u64) -> (Pubkey, u8) {
+    find(&[
+        REWARD_BUDGET_SEED,
+        sponsor.as_ref(),
+        &budget_id.to_le_bytes(),
+    ])
+}
+
+/// `BudgetMembership` of `merchant_account` in `reward_budget`
+pub fn find_budget_membership_pda(
+    reward_budget: &Pubkey,
+    merchant_account: &Pubkey,
+) -> (Pubkey, u8) {
+    find(&[
+        BUDGET_MEMBERSHIP_SEED,
+        reward_budget.as_ref(),
+        merchant_account.as_ref(),
+    ])
+}
+
+/// `CategoryBoost` of merchant `category`, zero-padded to 16 bytes as merchants store it
+pub fn find_category_boost_pda(category: &str) -> (Pubkey, u8) {
+    let mut category_bytes = [0u8; 16];
+    let category_len = category.len().min(16);
+    category_bytes[..category_len].copy_from_slice(&category.as_bytes()[..category_len]);
+    find(&[CATEGORY_BOOST_SEED, &category_bytes])
+}
+
+/// `CustomerMerchantStats` of `customer` at `merchant_account`
+pub fn find_customer_merchant_stats_pda(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+) -> (Pubkey, u8) {
+    find(&[
+        CUSTOMER_MERCHANT_STATS_SEED,
+        merchant_account.as_ref(),
+        customer.as_ref(),
+    ])
+}
+
+/// `TransactionStub` of the purchase by `customer` at `merchant_account` for
+/// `transaction_id`, kept after its record is closed
+pub fn find_transaction_stub_pda(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> (Pubkey, u8) {
+    find(&[
+        TRANSACTION_STUB_SEED,
+        merchant_account.as_ref(),
+        customer.as_ref(),
+        transaction_id,
+    ])
+}
+
+/// `ReferenceIndex` of the purchase with `reference_code`, as stored on its record
+/// See `purchase::find_purchase_by_reference` to start from the code a customer reads out
+pub fn find_reference_index_pda(reference_code: &[u8; 8]) -> (Pubkey, u8) {
+    find(&[REFERENCE_INDEX_SEED, reference_code])
+}
+
+/// Offset of `voucher_mint` in `PoolState` account data, for a `getProgramAccounts` memcmp
+/// filter listing the pools that stake one mint
+pub const POOL_VOUCHER_MINT_OFFSET: usize = 8 + 32 + 32 + 32;
+
+/// `PoolState` of pool `pool_id` of `voucher_mint`
+pub fn find_pool_state_pda(voucher_mint: &Pubkey, pool_id: u16) -> (Pubkey, u8) {
+    find(&[
+        POOL_STATE_SEED,
+        voucher_mint.as_ref(),
+        &pool_id_seed(pool_id),
+    ])
+}
+
+/// Authority over the vault of pool `pool_id` of `voucher_mint`
+pub fn find_pool_vault_authority_pda(voucher_mint: &Pubkey, pool_id: u16) -> (Pubkey, u8) {
+    find(&[
+        POOL_VAULT_AUTHORITY_SEED,
+        voucher_mint.as_ref(),
+        &pool_id_seed(pool_id),
+    ])
+}
+
+/// Receipt token mint of pool `pool_id` of `voucher_mint`
+pub fn find_receipt_mint_pda(voucher_mint: &Pubkey, pool_id: u16) -> (Pubkey, u8) {
+    find(&[
+        RECEIPT_MINT_SEED,
+        voucher_mint.as_ref(),
+        &pool_id_seed(pool_id),
+    ])
+}
+
+/// `PoolState` of pool `pool_id` created before PDAs were keyed by mint
+/// (`mint_keyed` unset), such as the original pool 0
+pub fn find_legacy_pool_state_pda(pool_id: u16) -> (Pubkey, u8) {
+    find(&[POOL_STATE_SEED, &pool_id_seed(pool_id)])
+}
+
+/// Authority over the vault of a pool created before PDAs were keyed by mint
+pub fn find_legacy_pool_vault_authority_pda(pool_id: u16) -> (Pubkey, u8) {
+    find(&[POOL_VAULT_AUTHORITY_SEED, &pool_id_seed(pool_id)])
+}
+
+/// Receipt token mint of a pool created before PDAs were keyed by mint
+pub fn find_legacy_receipt_mint_pda(pool_id: u16) -> (Pubkey, u8) {
+    find(&[RECEIPT_MINT_SEED, &pool_id_seed(pool_id)])
+}
+
+/// `UserStakeRecord` of `user` in the pool at `pool_state`
+pub fn find_user_stake_pda(pool_state: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find(&[USER_STAKE_SEED, pool_state.as_ref(), user.as_ref()])
+}
+
+/// `StakeWhitelistEntry` of `user` in the pool at `pool_state`
+pub fn find_stake_whitelist_pda(pool_state: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find(&[STAKE_WHITELIST_SEED, pool_state.as_ref(), user.as_ref()])
+}
+
+/// `UnstakeRequest` of `user` in the pool at `pool_state`
+pub fn find_unstake_request_pda(pool_state: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find(&[UNSTAKE_REQUEST_SEED, pool_state.as_ref(), user.as_ref()])
+}
+
+/// `TokenAdvance` of `user` in the pool at `pool_state`
+pub fn find_token_advance_pda(pool_state: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find(&[TOKEN_ADVANCE_SEED, pool_state.as_ref(), user.as_ref()])
+}
+
+/// `StakePoolDeployment` of the pool at `pool_state`
+pub fn find_stake_pool_deployment_pda(pool_state: &Pubkey) -> (Pubkey, u8) {
+    find(&[STAKE_POOL_DEPLOYMENT_SEED, pool_state.as_ref()])
+}
+
+/// SOL vault of the pool at `pool_state`; send SOL here to deploy it into the stake pool
+pub fn find_pool_sol_vault_pda(pool_state: &Pubkey) -> (Pubkey, u8) {
+    find(&[POOL_SOL_VAULT_SEED, pool_state.as_ref()])
+}
+
+/// Stake pool token account of the pool at `pool_state`
+pub fn find_stake_pool_tokens_pda(pool_state: &Pubkey) -> (Pubkey, u8) {
+    find(&[STAKE_POOL_TOKENS_SEED, pool_state.as_ref()])
+}
+
+/// `StakeActionRecord` numbered `action_index` among `user`'s actions in the pool at `pool_state`
+pub fn find_stake_action_pda(
+    pool_state: &Pubkey,
+    user: &Pubkey,
+    action_index: u64,
+) -> (Pubkey, u8) {
+    find(&[
+        STAKE_ACTION_SEED,
+        pool_state.as_ref(),
+        user.as_ref(),
+        &action_index.to_le_bytes(),
+    ])
+}
+
+/// `YieldBatch` recorded in the pool at `pool_state` under `yield_batch_id`
+pub fn find_yield_batch_pda(pool_state: &Pubkey, yield_batch_id: &[u8; 32]) -> (Pubkey, u8) {
+    find(&[YIELD_BATCH_SEED, pool_state.as_ref(), yield_batch_id])
+}
@@ -0,0 +1,183 @@
+//! A single view over purchase records of either layout
+//! Deployments with `slim_purchase_records` write `PurchaseTransactionV2`; older records,
+//! and those of other deployments, stay `PurchaseTransaction`
+//! Both carry a short reference code customers read out to support, which
+//! `find_purchase_by_reference` resolves through the purchase's `ReferenceIndex`
+
+use solana_program::hash::hash;
+use solana_program::pubkey::Pubkey;
+
+use crate::pda::find_reference_index_pda;
+use crate::state::{PurchaseTransaction, PurchaseTransactionV2};
+use crate::CarsaAccount;
+
+/// Prefix reference codes are shown with, as in `CRSA-7F3K9Q2M`
+pub const REFERENCE_CODE_PREFIX: &str = "CRSA-";
+
+/// Crockford base32, the alphabet of reference codes
+const REFERENCE_CODE_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// The reference code the program stores for the purchase recorded at
+/// `transaction_record`: the first 5 bytes of sha256 of the address, in base32
+pub fn reference_code(transaction_record: &Pubkey) -> [u8; 8] {
+    let bits = hash(transaction_record.as_ref()).to_bytes()[..5]
+        .iter()
+        .fold(0u64, |bits, byte| bits << 8 | u64::from(*byte));
+    let mut code = [0; 8];
+    for (i, character) in code.iter_mut().enumerate() {
+        *character = REFERENCE_CODE_ALPHABET[(bits >> (35 - 5 * i) & 0x1f) as usize];
+    }
+    code
+}
+
+/// `reference_code` as shown to customers, with `REFERENCE_CODE_PREFIX`
+pub fn format_reference_code(reference_code: &[u8; 8]) -> String {
+    format!(
+        "{REFERENCE_CODE_PREFIX}{}",
+        String::from_utf8_lossy(reference_code)
+    )
+}
+
+/// Parses a reference code as a customer might read it out: with or without the prefix,
+/// in either case, with `I`/`L` for 1 and `O` for 0 as Crockford base32 allows
+/// Returns `None` unless it is 8 base32 characters
+pub fn parse_reference_code(code: &str) -> Option<[u8; 8]> {
+    let code = code.trim().to_ascii_uppercase();
+    let code = code.strip_prefix(REFERENCE_CODE_PREFIX).unwrap_or(&code);
+    let mut parsed = [0; 8];
+    if code.len() != parsed.len() {
+        return None;
+    }
+    for (character, parsed) in code.bytes().zip(parsed.iter_mut()) {
+        *parsed = match character {
+            b'I' | b'L' => b'1',
+            b'O' => b'0',
+            character if REFERENCE_CODE_ALPHABET.contains(&character) => character,
+            _ => return None,
+        };
+    }
+    Some(parsed)
+}
+
+/// The `ReferenceIndex` of the purchase a customer's reference code names, whose
+/// `transaction_record` and `transaction_stub` locate the purchase without a scan
+/// Returns `None` when `code` does not parse
+pub fn find_purchase_by_reference(code: &str) -> Option<(Pubkey, u8)> {
+    parse_reference_code(code).map(|reference_code| find_reference_index_pda(&reference_code))
+}
+
+/// A purchase record in whichever layout it was written
+#[derive(Clone, Debug)]
+pub enum PurchaseRecord {
+    V1(PurchaseTransaction),
+    V2(PurchaseTransactionV2),
+}
+
+impl PurchaseRecord {
+    /// Decodes a purchase record of either layout, telling them apart by discriminator
+    pub fn try_deserialize(data: &[u8]) -> std::io::Result<Self> {
+        if data.starts_with(&PurchaseTransactionV2::DISCRIMINATOR) {
+            PurchaseTransactionV2::try_deserialize(data).map(Self::V2)
+        } else {
+            PurchaseTransaction::try_deserialize(data).map(Self::V1)
+        }
+    }
+
+    pub fn customer(&self) -> Pubkey {
+        match self {
+            Self::V1(record) => record.customer,
+            Self::V2(record) => record.customer,
+        }
+    }
+
+    pub fn merchant(&self) -> Pubkey {
+        match self {
+            Self::V1(record) => record.merchant,
+            Self::V2(record) => record.merchant,
+        }
+    }
+
+    pub fn fiat_amount(&self) -> u64 {
+        match self {
+            Self::V1(record) => record.fiat_amount,
+            Self::V2(record) => record.fiat_amount,
+        }
+    }
+
+    pub fn redeemed_token_amount(&self) -> u64 {
+        match self {
+            Self::V1(record) => record.redeemed_token_amount,
+            Self::V2(record) => record.redeemed_token_amount,
+        }
+    }
+
+    pub fn reward_amount(&self) -> u64 {
+        match self {
+            Self::V1(record) => record.reward_amount,
+            Self::V2(record) => record.reward_amount,
+        }
+    }
+
+    pub fn protocol_fee(&self) -> u64 {
+        match self {
+            Self::V1(record) => record.protocol_fee,
+            Self::V2(record) => record.protocol_fee,
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Self::V1(record) => record.timestamp,
+            Self::V2(record) => record.timestamp,
+        }
+    }
+
+    /// The transaction id as stored: all 32 bytes for v1, the first 16 for v2
+    pub fn transaction_id(&self) -> &[u8] {
+        match self {
+            Self::V1(record) => &record.transaction_id,
+            Self::V2(record) => &record.transaction_id,
+        }
+    }
+
+    pub fn invoice(&self) -> Pubkey {
+        match self {
+            Self::V1(record) => record.invoice,
+            Self::V2(record) => record.invoice,
+        }
+    }
+
+    pub fn used_tokens(&self) -> bool {
+        self.redeemed_token_amount() > 0
+    }
+
+    pub fn disputed(&self) -> bool {
+        match self {
+            Self::V1(record) => record.disputed,
+            Self::V2(record) => record.disputed,
+        }
+    }
+
+    pub fn refunded(&self) -> bool {
+        match self {
+            Self::V1(record) => record.refunded,
+            Self::V2(record) => record.refunded,
+        }
+    }
+
+    pub fn fulfilled_at(&self) -> i64 {
+        match self {
+            Self::V1(record) => record.fulfilled_at,
+            Self::V2(record) => record.fulfilled_at,
+        }
+    }
+
+    /// The reference code, all zero bytes for records written before codes existed and
+    /// for those of holds and sessions
+    pub fn reference_code(&self) -> [u8; 8] {
+        match self {
+            Self::V1(record) => record.reference_code,
+            Self::V2(record) => record.reference_code,
+        }
+    }
+}
@@ -0,0 +1,2172 @@
+//! Mirrors of the account and argument types defined in the program's `state` module
+//! Field order and types match the program, so Borsh encodes them identically
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::CarsaAccount;
+
+/// State account that stores the configuration and metadata for the Lokal token mint
+/// This account is owned by the program and stores essential mint information
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct LokalMintConfig {
+    /// The public key of the mint account for Lokal tokens
+    pub mint: Pubkey,
+
+    /// The bump seed for the PDA that acts as mint authority
+    /// This ensures the program has full control over token minting
+    pub mint_authority_bump: u8,
+
+    /// The bump seed for this config account's PDA
+    pub config_bump: u8,
+
+    /// The authority that can update mint configuration (typically program deployer)
+    pub update_authority: Pubkey,
+
+    /// Total supply of Lokal tokens ever minted
+    /// Used for tracking and analytics purposes
+    pub total_supply: u64,
+
+    /// Minimum voucher pool stake a customer needs for the staker cashback boost
+    pub staker_boost_threshold: u64,
+
+    /// Extra cashback for stakers, added to the merchant's rate (in basis points)
+    pub staker_boost_bps: u16,
+
+    /// Sequence number of the last event emitted by mint, purchase or transfer
+    /// instructions; indexers use it to detect gaps
+    pub event_sequence: u64,
+
+    /// Whether purchases and transfers also write an `ActivityRecord`
+    pub record_activity: bool,
+
+    /// Number of purchases processed
+    pub total_purchases: u64,
+
+    /// Number of peer-to-peer transfers made
+    pub total_transfers: u64,
+
+    /// Share of redeemed tokens routed to the treasury (in basis points)
+    pub protocol_fee_bps: u16,
+
+    /// Token account that receives protocol fees
+    pub treasury_token_account: Pubkey,
+
+    /// Share of each peer-to-peer transfer routed to the treasury (in basis points)
+    pub transfer_fee_bps: u16,
+
+    /// Second signer required on treasury withdrawals (default pubkey = not required)
+    pub treasury_cosigner: Pubkey,
+
+    /// Largest amount a single treasury withdrawal may move (0 = no cap)
+    pub treasury_withdrawal_cap: u64,
+
+    /// Number of treasury withdrawals made, numbering their records
+    pub treasury_withdrawal_count: u64,
+
+    /// Value of one Lokal token in Indonesian Rupiah (IDR)
+    pub token_to_idr_rate: u64,
+
+    /// Delay before a queued parameter change can be executed (0 = no timelock)
+    pub param_timelock_seconds: i64,
+
+    /// Number of parameter changes queued, numbering their PDAs
+    pub param_change_count: u64,
+
+    /// Key that can pause the program, pools and merchants but change nothing else
+    /// (default pubkey = no guardian)
+    pub guardian: Pubkey,
+
+    /// Whether purchases and transfers are halted
+    pub paused: bool,
+
+    /// Second signer required on mints above `single_sig_mint_limit`
+    /// (default pubkey = not required)
+    pub mint_cosigner: Pubkey,
+
+    /// Largest amount `mint_lokal_tokens` can mint with the update authority alone
+    pub single_sig_mint_limit: u64,
+
+    /// Pyth price account quoting IDR per token, used instead of `token_to_idr_rate`
+    /// (default pubkey = use the static rate)
+    pub price_feed: Pubkey,
+
+    /// Oldest price from `price_feed` a purchase will accept
+    pub max_price_age_seconds: u32,
+
+    /// Widest confidence interval from `price_feed` a purchase will accept, relative to
+    /// the price (in basis points)
+    pub max_price_confidence_bps: u16,
+
+    /// Whether purchase rewards are held in escrow as expiring `RewardLot`s instead of
+    /// being minted to the customer
+    pub expiring_rewards: bool,
+
+    /// How long a reward lot can be withdrawn before it expires
+    pub reward_lifetime_seconds: i64,
+
+    /// Number of vesting grants created, numbering their PDAs
+    pub vesting_grant_count: u64,
+
+    /// How long after a purchase its customer may open a dispute (0 = disputes disabled)
+    pub dispute_window_seconds: u32,
+
+    /// Decimals of `mint`, passed to every `transfer_checked`
+    /// (0 on configs created before it was recorded; read through `decimals()`)
+    pub decimals: u8,
+
+    /// Total Lokal tokens the program has burned (settlements, expired reward lots)
+    /// `total_supply - total_burned` should equal the mint's supply; see `reconcile_supply`
+    pub total_burned: u64,
+
+    /// Backend key that records off-chain fulfillment of purchases with `mark_fulfilled`
+    /// (default pubkey = fulfillment not recorded)
+    pub fulfillment_authority: Pubkey,
+
+    /// How long after a purchase its fulfillment can be recorded
+    pub fulfillment_window_seconds: u32,
+
+    /// Largest peer-to-peer transfer (0 = no limit)
+    pub max_transfer_amount: u64,
+
+    /// Largest amount `mint_lokal_tokens` mints in one call (0 = no limit)
+    pub max_mint_per_tx: u64,
+
+    /// Largest purchase in Indonesian Rupiah (IDR) (0 = no limit)
+    pub max_purchase_idr: u64,
+
+    /// Shortest time between two changes of a merchant's cashback rate (0 = no cooldown)
+    pub rate_change_cooldown_seconds: u32,
+
+    /// Largest increase of a merchant's cashback rate in one change, in basis points
+    /// (0 = no cap); decreases are never capped
+    pub max_rate_increase_bps: u16,
+
+    /// Whether purchases record a slim `PurchaseTransactionV2` instead of a
+    /// `PurchaseTransaction`
+    pub slim_purchase_records: bool,
+
+    /// Tokens minted by administrative paths: `mint_lokal_tokens`, vesting grants, dispute
+    /// refunds, airdrop claims and clawbacks (counted from when it was added)
+    pub total_admin_minted: u64,
+
+    /// Largest `total_admin_minted` may grow to (0 = no cap)
+    pub admin_mint_cap: u64,
+
+    /// Tokens minted as purchase rewards (counted from when it was added)
+    pub total_reward_minted: u64,
+
+    /// Largest `total_reward_minted` may grow to (0 = no cap)
+    pub reward_mint_cap: u64,
+
+    /// Number of queued parameter changes not yet executed or cancelled (counted from when
+    /// it was added)
+    pub pending_param_changes: u16,
+
+    /// Whether purchase records, their PDA seeds and purchase events carry
+    /// `customer_hash` of the customer instead of their wallet
+    pub hash_customer_in_records: bool,
+
+    /// Salt of `customer_hash`, set once so each customer's hash stays stable
+    pub customer_hash_salt: [u8; 32],
+
+    /// Highest cashback rate a merchant may set or earn rewards at (in basis points)
+    /// Configs created before it hold 0, read as `DEFAULT_MAX_CASHBACK_BPS`
+    pub max_cashback_bps: u16,
+
+    /// Cashback rate above which setting a merchant's rate emits `HighCashbackAlertEvent`
+    /// (in basis points, 0 = no alerts)
+    pub alert_threshold_bps: u16,
+
+    /// Reserved space for future upgrades (1 byte)
+    pub reserved: [u8; 1],
+}
+
+impl CarsaAccount for LokalMintConfig {
+    const DISCRIMINATOR: [u8; 8] = [217, 53, 103, 181, 28, 29, 159, 189];
+}
+
+/// `LokalMintConfig` as stored by the first deployment
+/// `LokalMintConfig` cannot decode it; `migrate_config` rewrites it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct LegacyLokalMintConfig {
+    pub mint: Pubkey,
+    pub mint_authority_bump: u8,
+    pub config_bump: u8,
+    pub update_authority: Pubkey,
+    pub total_supply: u64,
+    pub reserved: [u8; 64],
+}
+
+/// Merchant account that stores merchant-specific information and settings
+/// This account tracks participating merchants and their reward configurations
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct MerchantAccount {
+    /// The merchant's wallet public key for receiving payments
+    /// Instructions authorize the owner by this field, not by the account's address, whose
+    /// seeds keep the wallet it was registered with
+    pub merchant_wallet: Pubkey,
+
+    /// The merchant's display name
+    pub name: [u8; 32], // Fixed size for efficient storage
+
+    /// The merchant's category (e.g., "restaurant", "bookstore", "coffee_shop")
+    pub category: [u8; 16],
+
+    /// Cashback percentage (in basis points, e.g., 500 = 5%)
+    pub cashback_rate: u16,
+
+    /// Whether the merchant is currently active
+    pub is_active: bool,
+
+    /// Total number of transactions processed for this merchant
+    pub total_transactions: u64,
+
+    /// Total amount of tokens distributed as rewards for this merchant
+    pub total_rewards_distributed: u64,
+
+    /// Total purchase volume (in Indonesian Rupiah IDR) processed
+    pub total_volume: u64,
+
+    /// Timestamp when merchant was registered
+    pub created_at: i64,
+
+    /// The bump seed for this merchant account's PDA
+    pub bump: u8,
+
+    /// Position of this merchant's `MerchantIndex` in the registry
+    pub registry_index: u64,
+
+    /// Whether the update authority or guardian has suspended this merchant
+    /// Unlike `is_active`, the merchant cannot lift it
+    pub suspended: bool,
+
+    /// Smallest purchase (fiat + token value, in IDR) the merchant accepts (0 = no minimum)
+    pub min_purchase_amount: u64,
+
+    /// Premium on the IDR value of tokens redeemed here (in basis points, 1000 = +10%)
+    pub redemption_bonus_bps: u16,
+
+    /// Redeemed tokens the merchant has received but not yet settled for IDR
+    pub unsettled_tokens: u64,
+
+    /// Whether purchases must carry an Ed25519 approval from the merchant wallet or
+    /// one of its registered POS keys
+    pub require_merchant_signature: bool,
+
+    /// The franchise parent this outlet belongs to (default pubkey when independent)
+    pub parent_merchant: Pubkey,
+
+    /// Whether purchases are recorded by extending `receipt_chain_hash` instead of
+    /// creating a `PurchaseTransaction` account
+    pub compressed_receipts: bool,
+
+    /// Hash chain over the merchant's compressed receipts (all zeros before the first)
+    pub receipt_chain_hash: [u8; 32],
+
+    /// The only token account redeemed tokens may be paid to
+    /// Default pubkey until set; the first redemption then locks in the account it pays
+    pub payout_token_account: Pubkey,
+
+    /// Timestamp of the last change to `cashback_rate` (0 if never changed)
+    pub last_rate_change_at: i64,
+
+    /// Visits a customer must make within `streak_window_seconds` to earn `streak_bonus`
+    /// (0 = no streak reward)
+    pub streak_target: u16,
+
+    /// How long a customer has from their first visit to complete a streak
+    pub streak_window_seconds: u32,
+
+    /// Reward tokens minted to a customer completing a streak
+    pub streak_bonus: u64,
+
+    /// Whether every purchase counts as a visit; otherwise a customer's purchases on one
+    /// UTC day count once
+    pub streak_counts_every_purchase: bool,
+
+    /// Last nonce of the merchant's POS integration, advanced by purchases that pass
+    /// `expected_nonce` (0 = none used yet)
+    pub pos_nonce: u64,
+
+    /// Whether the update authority registered this merchant and its owner has not yet
+    /// accepted with `accept_merchant_registration`; no purchases until then
+    pub pending_activation: bool,
+
+    /// The wallet proposed to take over the merchant with `accept_merchant_ownership`
+    /// (default pubkey when no transfer is pending)
+    pub pending_owner: Pubkey,
+
+    /// Reserved space for future upgrades (3 bytes)
+    pub reserved: [u8; 3],
+}
+
+impl CarsaAccount for MerchantAccount {
+    const DISCRIMINATOR: [u8; 8] = [182, 10, 106, 140, 73, 79, 234, 186];
+}
+
+/// `MerchantAccount` as stored by merchants registered with the first deployment
+/// `MerchantAccount` cannot decode these accounts; `migrate_merchant` rewrites them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct LegacyMerchantAccount {
+    pub merchant_wallet: Pubkey,
+    pub name: [u8; 32],
+    pub category: [u8; 16],
+    pub cashback_rate: u16,
+    pub is_active: bool,
+    pub total_transactions: u64,
+    pub total_rewards_distributed: u64,
+    pub total_volume: u64,
+    pub created_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
+
+/// Global counter of registered merchants
+/// Clients enumerate merchants by walking `MerchantIndex` PDAs 0..merchant_count
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct MerchantRegistry {
+    /// Number of merchants ever registered, including closed ones
+    pub merchant_count: u64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl CarsaAccount for MerchantRegistry {
+    const DISCRIMINATOR: [u8; 8] = [30, 206, 224, 3, 61, 48, 215, 115];
+}
+
+/// Pointer from a registry position to a merchant account
+/// Kept as a tombstone when the merchant closes, so indexes are never renumbered
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct MerchantIndex {
+    /// The merchant account at this position
+    pub merchant: Pubkey,
+
+    /// Position in the registry (starts at 0)
+    pub index: u64,
+
+    /// Whether the merchant account has been closed
+    pub closed: bool,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for MerchantIndex {
+    const DISCRIMINATOR: [u8; 8] = [119, 199, 0, 117, 168, 25, 249, 233];
+}
+
+/// Purchase transaction record for tracking and analytics
+/// This account stores details of each purchase transaction including token redemptions
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PurchaseTransaction {
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// The merchant's account public key
+    pub merchant: Pubkey,
+
+    /// Fiat purchase amount in Indonesian Rupiah (IDR)
+    pub fiat_amount: u64,
+
+    /// Amount of tokens redeemed as payment (0 if none)
+    pub redeemed_token_amount: u64,
+
+    /// Total transaction value (fiat + token value in IDR)
+    pub total_value: u64,
+
+    /// Reward tokens minted for this purchase, including any category boost
+    pub reward_amount: u64,
+
+    /// Cashback rate applied, including any staker boost (in basis points)
+    /// Does not include the protocol's category boost, recorded separately below
+    pub cashback_rate: u16,
+
+    /// Whether tokens were used in this transaction
+    pub used_tokens: bool,
+
+    /// Timestamp of the transaction
+    pub timestamp: i64,
+
+    /// Transaction signature for reference
+    pub transaction_id: [u8; 32],
+
+    /// The bump seed for this transaction account's PDA
+    pub bump: u8,
+
+    /// Staker boost included in `cashback_rate` (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+
+    /// Part of `redeemed_token_amount` routed to the treasury instead of the merchant
+    pub protocol_fee: u64,
+
+    /// Merchant's redemption bonus applied to the redeemed tokens' value (in basis points)
+    pub redemption_bonus_bps: u16,
+
+    /// The invoice this purchase paid (default pubkey if not paid by invoice)
+    pub invoice: Pubkey,
+
+    /// Whether the customer has disputed this purchase; refunds then only go through
+    /// the dispute's resolution
+    pub disputed: bool,
+
+    /// Whether the redeemed tokens have been refunded to the customer
+    pub refunded: bool,
+
+    /// When the backend recorded the purchase's off-chain fulfillment (0 = not yet)
+    pub fulfilled_at: i64,
+
+    /// Protocol category boost on top of `cashback_rate` (in basis points, 0 if none applied)
+    pub category_boost_bps: u16,
+
+    /// Part of `reward_amount` minted by the category boost rather than the merchant's rate
+    pub category_boost_reward: u64,
+
+    /// Merchant's streak bonus minted because this purchase completed a streak (0 if none),
+    /// on top of `reward_amount`
+    pub streak_bonus: u64,
+
+    /// Short code support staff look the purchase up by (see `ReferenceIndex`)
+    pub reference_code: [u8; 8],
+
+    /// Reserved space for future upgrades (4 bytes)
+    pub reserved: [u8; 4],
+}
+
+impl CarsaAccount for PurchaseTransaction {
+    const DISCRIMINATOR: [u8; 8] = [25, 229, 12, 152, 184, 97, 88, 220];
+}
+
+/// `PurchaseTransaction` as stored by purchases recorded before it outgrew 164 bytes
+/// The staker boost, protocol fee and redemption bonus were carved from the reserved bytes,
+/// so first-deployment records decode with them zeroed. `PurchaseTransaction` cannot
+/// decode these accounts; `migrate_purchase_transaction` rewrites them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct LegacyPurchaseTransaction {
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub fiat_amount: u64,
+    pub redeemed_token_amount: u64,
+    pub total_value: u64,
+    pub reward_amount: u64,
+    pub cashback_rate: u16,
+    pub used_tokens: bool,
+    pub timestamp: i64,
+    pub transaction_id: [u8; 32],
+    pub bump: u8,
+    pub staker_boost_bps: u16,
+    pub protocol_fee: u64,
+    pub redemption_bonus_bps: u16,
+    pub reserved: [u8; 4],
+}
+
+/// Slim purchase record, written instead of `PurchaseTransaction` when the config sets
+/// `slim_purchase_records`
+/// Leaves out the fields `PurchaseProcessedEvent` already carries and that follow from the
+/// rest (`total_value`, `cashback_rate`, `used_tokens`), and keeps half the transaction id
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PurchaseTransactionV2 {
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// The merchant's account public key
+    pub merchant: Pubkey,
+
+    /// Fiat purchase amount in Indonesian Rupiah (IDR)
+    pub fiat_amount: u64,
+
+    /// Amount of tokens redeemed as payment (0 if none)
+    pub redeemed_token_amount: u64,
+
+    /// Reward tokens minted for this purchase, including any category boost
+    pub reward_amount: u64,
+
+    /// Part of `redeemed_token_amount` routed to the treasury instead of the merchant
+    pub protocol_fee: u64,
+
+    /// Timestamp of the transaction
+    pub timestamp: i64,
+
+    /// When the backend recorded the purchase's off-chain fulfillment (0 = not yet)
+    pub fulfilled_at: i64,
+
+    /// First 16 bytes of the transaction id, which also seed this record's PDA
+    pub transaction_id: [u8; 16],
+
+    /// The invoice this purchase paid (default pubkey if not paid by invoice)
+    pub invoice: Pubkey,
+
+    /// Staker boost included in the applied cashback rate (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+
+    /// Merchant's redemption bonus applied to the redeemed tokens' value (in basis points)
+    pub redemption_bonus_bps: u16,
+
+    /// The bump seed for this transaction account's PDA
+    pub bump: u8,
+
+    /// Whether the customer has disputed this purchase
+    pub disputed: bool,
+
+    /// Whether the redeemed tokens have been refunded to the customer
+    pub refunded: bool,
+
+    /// Part of `reward_amount` minted by the protocol's category boost (0 if none applied)
+    pub category_boost_reward: u64,
+
+    /// Merchant's streak bonus minted on top of `reward_amount` (0 if no streak completed)
+    pub streak_bonus: u64,
+
+    /// Short code support staff look the purchase up by (see `ReferenceIndex`)
+    pub reference_code: [u8; 8],
+}
+
+impl CarsaAccount for PurchaseTransactionV2 {
+    const DISCRIMINATOR: [u8; 8] = [8, 158, 8, 155, 116, 16, 69, 17];
+}
+
+/// A fixed-amount bill a merchant creates for a customer to pay by reference
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Invoice {
+    /// The merchant account the invoice is payable to
+    pub merchant: Pubkey,
+
+    /// Merchant-chosen reference, unique per merchant
+    pub reference_id: [u8; 32],
+
+    /// Fiat amount charged in Indonesian Rupiah (IDR)
+    pub fiat_amount: u64,
+
+    /// Most tokens the customer may redeem towards the invoice (0 = none)
+    pub max_redeem_token_amount: u64,
+
+    /// Timestamp from which the invoice can no longer be paid
+    pub expires_at: i64,
+
+    /// Timestamp when the invoice was created
+    pub created_at: i64,
+
+    /// Whether the invoice has been paid
+    pub paid: bool,
+
+    /// The customer that paid the invoice
+    pub paid_by: Pubkey,
+
+    /// The purchase transaction record of the payment (default pubkey when the merchant
+    /// uses compressed receipts)
+    pub purchase: Pubkey,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for Invoice {
+    const DISCRIMINATOR: [u8; 8] = [51, 194, 250, 114, 6, 104, 18, 164];
+}
+
+/// Tokens escrowed behind a secret code, claimable by whoever presents the code
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct GiftCard {
+    /// The wallet that bought the gift card and may reclaim it after expiry
+    pub issuer: Pubkey,
+
+    /// SHA-256 hash of the secret code
+    pub code_hash: [u8; 32],
+
+    /// Tokens held for the gift card (in smallest unit, considering 9 decimals)
+    pub amount: u64,
+
+    /// Timestamp from which the gift card can no longer be redeemed and the issuer may
+    /// reclaim it
+    pub expires_at: i64,
+
+    /// Timestamp when the gift card was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for GiftCard {
+    const DISCRIMINATOR: [u8; 8] = [54, 20, 8, 38, 75, 170, 131, 139];
+}
+
+/// Redeemed tokens a merchant burned in exchange for an IDR payout made off-chain
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct SettlementRecord {
+    /// The merchant account that settled
+    pub merchant: Pubkey,
+
+    /// Merchant-chosen settlement period, unique per merchant
+    pub period_id: u64,
+
+    /// Tokens burned (in smallest unit, considering 9 decimals)
+    pub token_amount: u64,
+
+    /// IDR owed to the merchant for the burned tokens
+    pub idr_value: u64,
+
+    /// Value of one token in IDR used for `idr_value`
+    pub token_to_idr_rate: u64,
+
+    /// Timestamp of the settlement
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for SettlementRecord {
+    const DISCRIMINATOR: [u8; 8] = [172, 159, 67, 74, 96, 85, 37, 205];
+}
+
+/// Recurring payment a customer authorized to a merchant, charged through an SPL
+/// delegate approval held by this account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Subscription {
+    /// The paying customer
+    pub customer: Pubkey,
+
+    /// The merchant account being paid
+    pub merchant: Pubkey,
+
+    /// The customer's token account the approval was granted on
+    pub customer_token_account: Pubkey,
+
+    /// Tokens charged each period (in smallest unit, considering 9 decimals)
+    pub amount_per_period: u64,
+
+    /// Seconds between charges
+    pub period_seconds: i64,
+
+    /// Number of periods the customer agreed to pay for
+    pub max_periods: u32,
+
+    /// Number of periods charged so far
+    pub periods_charged: u32,
+
+    /// Timestamp from which the next period can be charged
+    pub next_charge_at: i64,
+
+    /// Timestamp when the subscription was created
+    pub created_at: i64,
+
+    /// Whether charges are rewarded and recorded like purchases
+    pub earn_rewards: bool,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for Subscription {
+    const DISCRIMINATOR: [u8; 8] = [64, 7, 26, 135, 102, 132, 98, 33];
+}
+
+/// A merkle-rooted token distribution whose allocations are minted as they are claimed
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Airdrop {
+    /// Authority-chosen identifier, part of the PDA seeds
+    pub airdrop_id: u64,
+
+    /// Root of the merkle tree of (wallet, amount) allocations
+    pub merkle_root: [u8; 32],
+
+    /// Most tokens the airdrop may mint in total (in smallest unit, considering 9 decimals)
+    pub budget: u64,
+
+    /// Tokens minted to claimants so far
+    pub claimed_amount: u64,
+
+    /// Timestamp from which claims are refused and the budget can be swept
+    pub expires_at: i64,
+
+    /// Timestamp when the airdrop was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for Airdrop {
+    const DISCRIMINATOR: [u8; 8] = [31, 112, 159, 158, 124, 237, 9, 241];
+}
+
+/// Marker that a wallet has claimed its allocation from an airdrop
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct AirdropClaim {
+    /// The airdrop claimed from
+    pub airdrop: Pubkey,
+
+    /// The wallet that claimed
+    pub claimant: Pubkey,
+
+    /// Tokens minted for the claim
+    pub amount: u64,
+
+    /// Timestamp of the claim
+    pub claimed_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for AirdropClaim {
+    const DISCRIMINATOR: [u8; 8] = [231, 12, 74, 54, 245, 181, 248, 38];
+}
+
+/// An ephemeral key a customer lets make purchases on their behalf, spending from
+/// tokens escrowed for it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct SessionKey {
+    /// The customer the session purchases for
+    pub customer: Pubkey,
+
+    /// The ephemeral key allowed to sign session purchases
+    pub session_signer: Pubkey,
+
+    /// Tokens escrowed for the session to redeem (in smallest unit, considering 9 decimals)
+    pub spend_limit: u64,
+
+    /// Tokens redeemed through the session so far
+    pub spent: u64,
+
+    /// Timestamp from which the session can no longer be used
+    pub expires_at: i64,
+
+    /// Timestamp when the session was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for SessionKey {
+    const DISCRIMINATOR: [u8; 8] = [93, 186, 163, 139, 160, 255, 81, 112];
+}
+
+/// A point-of-sale key allowed to approve purchases for a merchant
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PosKey {
+    /// The merchant account the key approves purchases for
+    pub merchant: Pubkey,
+
+    /// The terminal's Ed25519 public key
+    pub pos_key: Pubkey,
+
+    /// Timestamp when the key was registered
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for PosKey {
+    const DISCRIMINATOR: [u8; 8] = [70, 207, 57, 83, 172, 168, 103, 85];
+}
+
+/// A wallet allowed to act for a merchant at the till without holding the owner key
+/// Operators may approve purchases and create invoices, but not change the merchant's
+/// settings or move its funds
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct MerchantOperator {
+    /// The merchant account the operator acts for
+    pub merchant: Pubkey,
+
+    /// The operator's wallet
+    pub operator: Pubkey,
+
+    /// Timestamp when the operator was added
+    pub added_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for MerchantOperator {
+    const DISCRIMINATOR: [u8; 8] = [122, 171, 77, 92, 149, 221, 56, 83];
+}
+
+/// A franchise brand whose outlets are registered as child merchants
+/// Keeps aggregate counters over all of its outlets' purchases
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ParentMerchant {
+    /// The brand's owner wallet, which co-signs outlet registrations
+    pub owner: Pubkey,
+
+    /// The brand's display name
+    pub name: [u8; 32],
+
+    /// Cashback rate (in basis points) `cascade_cashback_rate` applies to outlets
+    pub default_cashback_rate: u16,
+
+    /// Number of outlets ever registered under this parent, including closed ones
+    pub child_count: u32,
+
+    /// Total number of transactions processed across all outlets
+    pub total_transactions: u64,
+
+    /// Total amount of tokens distributed as rewards across all outlets
+    pub total_rewards_distributed: u64,
+
+    /// Total purchase volume (in IDR) processed across all outlets
+    pub total_volume: u64,
+
+    /// Timestamp when the parent was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for ParentMerchant {
+    const DISCRIMINATOR: [u8; 8] = [209, 82, 45, 149, 92, 238, 125, 102];
+}
+
+/// Cashback a sponsor pays for purchases at its member merchants, out of escrowed tokens
+/// Purchase rewards are transferred from the escrow instead of minted while it lasts
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct RewardBudget {
+    /// The wallet that funded the budget and may top it up or withdraw from it
+    pub sponsor: Pubkey,
+
+    /// Sponsor-chosen identifier, unique per sponsor
+    pub budget_id: u64,
+
+    /// Escrowed tokens still available for rewards
+    pub remaining_budget: u64,
+
+    /// Tokens paid out as rewards so far
+    pub total_paid: u64,
+
+    /// Number of merchants currently in the budget
+    pub member_count: u32,
+
+    /// Timestamp when the budget was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for RewardBudget {
+    const DISCRIMINATOR: [u8; 8] = [183, 187, 157, 83, 40, 135, 235, 108];
+}
+
+/// Marks a merchant as sponsored by a reward budget
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct BudgetMembership {
+    /// The reward budget paying the merchant's rewards
+    pub reward_budget: Pubkey,
+
+    /// The sponsored merchant account
+    pub merchant: Pubkey,
+
+    /// Timestamp when the merchant joined the budget
+    pub joined_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for BudgetMembership {
+    const DISCRIMINATOR: [u8; 8] = [59, 34, 196, 162, 77, 26, 63, 36];
+}
+
+/// Stage of a purchase dispute
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeStatus {
+    /// Awaiting the update authority's decision
+    Open,
+    /// Decided for the customer, whose redeemed tokens were refunded
+    Upheld,
+    /// Decided for the merchant
+    Rejected,
+}
+
+/// A customer's claim against one of their purchase records, kept as an on-chain
+/// paper trail after it is resolved
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Dispute {
+    /// The disputed purchase transaction record
+    pub purchase: Pubkey,
+
+    /// The customer that opened the dispute
+    pub customer: Pubkey,
+
+    /// The merchant account of the disputed purchase
+    pub merchant: Pubkey,
+
+    /// Customer's reason for the dispute, as an off-chain code
+    pub reason_code: u16,
+
+    /// Merchant's response, as an off-chain code (0 until the merchant responds)
+    pub response_code: u16,
+
+    /// Current stage of the dispute
+    pub status: DisputeStatus,
+
+    /// Timestamp when the dispute was opened
+    pub opened_at: i64,
+
+    /// Timestamp of the merchant's latest response (0 if none)
+    pub responded_at: i64,
+
+    /// Timestamp when the dispute was resolved (0 while open)
+    pub resolved_at: i64,
+
+    /// Tokens refunded to the customer when the dispute was upheld
+    pub refund_amount: u64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for Dispute {
+    const DISCRIMINATOR: [u8; 8] = [36, 49, 241, 67, 40, 36, 241, 74];
+}
+
+/// Where a purchase stands after it was processed
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Processed, with nothing recorded against it since
+    Completed,
+    /// Acknowledged by the fulfillment authority
+    Fulfilled,
+    /// Under dispute by its customer
+    Disputed,
+    /// The customer's redeemed tokens were refunded
+    Refunded,
+    /// Its dispute was decided for the merchant
+    Resolved,
+}
+
+/// Durable trace of a purchase, kept at the purchase's merchant, customer and transaction
+/// id after its full record is closed, so refunds, disputes and fulfillments of the same
+/// transaction id all go through one status
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TransactionStub {
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// The merchant's account public key
+    pub merchant: Pubkey,
+
+    /// Current status of the purchase
+    pub status: TransactionStatus,
+
+    /// Timestamp of the purchase, then of its latest status change
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for TransactionStub {
+    const DISCRIMINATOR: [u8; 8] = [198, 83, 46, 61, 223, 254, 122, 220];
+}
+
+/// Points a purchase's reference code at its record, so support can find a purchase from
+/// the code a customer reads out without scanning
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ReferenceIndex {
+    /// The reference code, as stored on the purchase record
+    pub reference_code: [u8; 8],
+
+    /// The purchase record, of either layout, the code was derived from
+    pub transaction_record: Pubkey,
+
+    /// The purchase's `TransactionStub`, kept after the record is closed
+    pub transaction_stub: Pubkey,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for ReferenceIndex {
+    const DISCRIMINATOR: [u8; 8] = [144, 45, 116, 36, 214, 88, 70, 151];
+}
+
+/// Tokens a customer has set aside in escrow for a merchant to capture at the end of a
+/// purchase, e.g. when the bill is settled after a meal
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct Hold {
+    /// The customer whose tokens are held
+    pub customer: Pubkey,
+
+    /// The merchant account that may capture the hold
+    pub merchant: Pubkey,
+
+    /// Customer-chosen identifier, unique per customer and reused as the capture's
+    /// transaction id
+    pub hold_id: [u8; 32],
+
+    /// Tokens held in escrow (in smallest unit, considering 9 decimals)
+    pub amount: u64,
+
+    /// Timestamp from which the hold can no longer be captured and the customer may
+    /// release it
+    pub expires_at: i64,
+
+    /// Timestamp when the hold was created
+    pub created_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for Hold {
+    const DISCRIMINATOR: [u8; 8] = [110, 65, 238, 142, 146, 91, 196, 171];
+}
+
+/// A field's value before and after an instruction changed it
+/// Mutation events carry one per field they touch, so an indexer can rebuild the field's
+/// full history from the events alone
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Changed<T> {
+    pub old: T,
+    pub new: T,
+}
+
+/// Tokens a customer redeems as part of a purchase
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct RedemptionParams {
+    /// Amount of tokens to redeem (in smallest unit, considering 9 decimals)
+    pub token_amount: u64,
+}
+
+/// Reward a purchase would earn, returned by `preview_purchase_reward`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PurchaseRewardPreview {
+    /// Total transaction value (fiat + token value in IDR)
+    pub total_value: u64,
+
+    /// Cashback rate applied, including any staker boost (in basis points)
+    pub cashback_rate: u16,
+
+    /// Staker boost included in `cashback_rate` (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+
+    /// Reward tokens the purchase would mint, including `category_boost_reward`
+    pub reward_amount: u64,
+
+    /// Category boost rate on top of `cashback_rate` (in basis points, 0 if none applies)
+    pub category_boost_bps: u16,
+
+    /// Part of `reward_amount` paid by the category boost
+    pub category_boost_reward: u64,
+}
+
+/// Health summary returned by `get_program_status`, for monitoring bots
+/// The layout only ever grows at the end, raising `version` each time, so older decoders
+/// keep reading the fields they know
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ProgramStatus {
+    /// Layout version, `ProgramStatus::VERSION` when written
+    pub version: u8,
+
+    /// Whether purchases and transfers are halted
+    pub paused: bool,
+
+    /// Supply reported by the Lokal mint
+    pub mint_supply: u64,
+
+    /// Supply tracked in the config, `total_supply - total_burned`
+    pub tracked_supply: u64,
+
+    /// Tokens administrative paths may still mint (`u64::MAX` when uncapped)
+    pub admin_mint_headroom: u64,
+
+    /// Tokens purchase rewards may still mint (`u64::MAX` when uncapped)
+    pub reward_mint_headroom: u64,
+
+    /// Queued parameter changes not yet executed or cancelled
+    pub pending_param_changes: u16,
+
+    /// The voucher pool reported on
+    pub pool: Pubkey,
+
+    /// Whether the pool's config accepts deposits
+    pub deposits_enabled: bool,
+
+    /// Whether the pool's config allows withdrawals
+    pub withdrawals_enabled: bool,
+
+    /// Whether the pool's guardian or authority has paused deposits
+    pub deposits_paused: bool,
+
+    /// Whether the pool's guardian or authority has paused withdrawals
+    pub withdrawals_paused: bool,
+
+    /// Tokens held by the pool vault
+    pub vault_balance: u64,
+
+    /// Vouchers staked in the pool
+    pub total_staked: u64,
+
+    /// `vault_balance + deployed_amount - total_staked`: 0 when every stake is backed,
+    /// positive while the vault holds reserves, negative on a shortfall
+    pub vault_delta: i64,
+
+    /// Seconds since the pool last recorded yield
+    pub yield_update_age_seconds: i64,
+}
+
+/// Token transfer record for tracking P2P transfers
+/// This account stores details of token transfers between users
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TokenTransfer {
+    /// The sender's public key
+    pub from: Pubkey,
+
+    /// The recipient's public key
+    pub to: Pubkey,
+
+    /// Amount of tokens sent, before the transfer fee
+    pub amount: u64,
+
+    /// Timestamp of the transfer
+    pub timestamp: i64,
+
+    /// Transfer transaction signature for reference
+    pub transaction_id: [u8; 32],
+
+    /// Optional memo/note for the transfer
+    pub memo: [u8; 64],
+
+    /// The bump seed for this transfer account's PDA
+    pub bump: u8,
+
+    /// Transfer fee taken from `amount` for the treasury
+    pub fee: u64,
+
+    /// Amount the recipient received (`amount` - `fee`)
+    pub net_amount: u64,
+}
+
+impl CarsaAccount for TokenTransfer {
+    const DISCRIMINATOR: [u8; 8] = [190, 106, 98, 208, 197, 124, 235, 221];
+}
+
+/// Audit record of a single treasury withdrawal
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TreasuryWithdrawal {
+    /// Position of this withdrawal (starts at 0)
+    pub index: u64,
+
+    /// The update authority that made the withdrawal
+    pub authority: Pubkey,
+
+    /// The co-signer, or the default pubkey when none was required
+    pub cosigner: Pubkey,
+
+    /// Token account the fees were sent to
+    pub destination: Pubkey,
+
+    /// Amount withdrawn
+    pub amount: u64,
+
+    /// Caller-defined code describing why the funds were moved
+    pub reason_code: u16,
+
+    /// Timestamp of the withdrawal
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for TreasuryWithdrawal {
+    const DISCRIMINATOR: [u8; 8] = [72, 44, 242, 62, 197, 40, 89, 81];
+}
+
+/// A parameter that can only change after the config's timelock
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub enum ParamChange {
+    /// New `LokalMintConfig::token_to_idr_rate`
+    TokenToIdrRate(u64),
+    /// New `LokalMintConfig::protocol_fee_bps`
+    ProtocolFeeBps(u16),
+    /// New `LokalMintConfig::param_timelock_seconds`
+    ParamTimelockSeconds(i64),
+    /// New `LokalMintConfig::mint_cosigner` and `single_sig_mint_limit`
+    MintCosigner {
+        mint_cosigner: Pubkey,
+        single_sig_mint_limit: u64,
+    },
+    /// New `LokalMintConfig::price_feed` and its staleness and confidence bounds
+    PriceFeed {
+        price_feed: Pubkey,
+        max_price_age_seconds: u32,
+        max_price_confidence_bps: u16,
+    },
+    /// New `LokalMintConfig::dispute_window_seconds`
+    DisputeWindowSeconds(u32),
+    /// Partial update to the configuration of a voucher pool
+    PoolConfig {
+        pool_id: u16,
+        update: PoolConfigUpdate,
+    },
+    /// New `LokalMintConfig::max_transfer_amount`, `max_mint_per_tx` and `max_purchase_idr`
+    Limits {
+        max_transfer_amount: u64,
+        max_mint_per_tx: u64,
+        max_purchase_idr: u64,
+    },
+    /// New `LokalMintConfig::rate_change_cooldown_seconds` and `max_rate_increase_bps`
+    RateChangePolicy {
+        rate_change_cooldown_seconds: u32,
+        max_rate_increase_bps: u16,
+    },
+    /// New `LokalMintConfig::admin_mint_cap` and `reward_mint_cap`
+    MintCaps {
+        admin_mint_cap: u64,
+        reward_mint_cap: u64,
+    },
+    /// New `LokalMintConfig::max_cashback_bps` and `alert_threshold_bps`
+    CashbackLimits {
+        max_cashback_bps: u16,
+        alert_threshold_bps: u16,
+    },
+    /// New `PoolState::advance_ltv_bps` of a voucher pool
+    AdvanceLtv { pool_id: u16, advance_ltv_bps: u16 },
+}
+
+/// A parameter change waiting out the timelock
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PendingParamChange {
+    /// Position of this change among all queued changes (starts at 0)
+    pub id: u64,
+
+    /// The authority that queued the change, refunded when it is executed or cancelled
+    pub proposer: Pubkey,
+
+    /// The parameter and its new value
+    pub change: ParamChange,
+
+    /// Timestamp when the change was queued
+    pub queued_at: i64,
+
+    /// Earliest timestamp at which the change can be executed
+    pub effective_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for PendingParamChange {
+    const DISCRIMINATOR: [u8; 8] = [206, 196, 38, 132, 189, 11, 183, 115];
+}
+
+/// A purchase reward held in escrow until the customer withdraws it or it expires
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct RewardLot {
+    /// The customer the reward was earned by, or their `customer_hash` for purchases made
+    /// in privacy mode
+    pub customer: Pubkey,
+
+    /// Transaction ID of the purchase that earned the reward
+    pub transaction_id: [u8; 32],
+
+    /// Reward tokens still in escrow
+    pub amount: u64,
+
+    /// Reward tokens burned by `expire_rewards`
+    pub expired_amount: u64,
+
+    /// Timestamp when the reward was minted into escrow
+    pub minted_at: i64,
+
+    /// Timestamp from which the reward can no longer be withdrawn
+    pub expires_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for RewardLot {
+    const DISCRIMINATOR: [u8; 8] = [97, 16, 151, 228, 144, 75, 107, 199];
+}
+
+/// Tokens granted to a merchant that vest linearly from `start` over `duration`,
+/// claimable from `cliff`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct VestingGrant {
+    /// Position of this grant among all grants (starts at 0)
+    pub id: u64,
+
+    /// The wallet that can claim the vested tokens
+    pub beneficiary: Pubkey,
+
+    /// Tokens granted
+    pub total: u64,
+
+    /// Tokens claimed so far
+    pub released: u64,
+
+    /// Timestamp vesting starts from
+    pub start: i64,
+
+    /// Timestamp before which nothing can be claimed
+    pub cliff: i64,
+
+    /// Seconds from `start` until the whole grant has vested
+    pub duration: i64,
+
+    /// Timestamp the grant was revoked, freezing vesting (0 = not revoked)
+    pub revoked_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for VestingGrant {
+    const DISCRIMINATOR: [u8; 8] = [95, 107, 4, 155, 183, 117, 81, 55];
+}
+
+/// Waives the transfer fee for transfers sent by a wallet (e.g. a merchant)
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct FeeExemption {
+    /// The exempt wallet
+    pub wallet: Pubkey,
+
+    /// Timestamp when the exemption was granted
+    pub added_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for FeeExemption {
+    const DISCRIMINATOR: [u8; 8] = [203, 171, 87, 15, 227, 37, 162, 31];
+}
+
+/// Token redemption record for tracking spending at merchants
+/// This account stores details when customers spend Lokal tokens at merchants
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TokenRedemption {
+    /// The customer who redeemed tokens
+    pub customer: Pubkey,
+
+    /// The merchant where tokens were redeemed
+    pub merchant: Pubkey,
+
+    /// Amount of tokens redeemed
+    pub token_amount: u64,
+
+    /// Fiat value of the redemption (in Indonesian Rupiah IDR)
+    pub fiat_value: u64,
+
+    /// Discount percentage applied (in basis points)
+    pub discount_rate: u16,
+
+    /// Timestamp of the redemption
+    pub timestamp: i64,
+
+    /// Redemption transaction signature for reference
+    pub transaction_id: [u8; 32],
+
+    /// The bump seed for this redemption account's PDA
+    pub bump: u8,
+
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl CarsaAccount for TokenRedemption {
+    const DISCRIMINATOR: [u8; 8] = [116, 31, 235, 253, 162, 204, 183, 23];
+}
+
+/// Kind of wallet activity captured by an `ActivityRecord`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityKind {
+    Purchase,
+    Transfer,
+    Redemption,
+}
+
+/// Compact entry in a wallet's activity feed
+/// Written alongside purchase and transfer records when `record_activity` is enabled;
+/// `wallet` comes first so one memcmp filter finds every entry for a wallet
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ActivityRecord {
+    /// The wallet the activity belongs to (the customer or the sender); purchases made in
+    /// privacy mode record the customer's `customer_hash` instead
+    pub wallet: Pubkey,
+
+    /// The merchant account for purchases and redemptions, the recipient for transfers
+    pub counterparty: Pubkey,
+
+    /// Lokal tokens involved: reward minted, tokens transferred or tokens redeemed
+    pub amount: u64,
+
+    /// Timestamp of the activity
+    pub timestamp: i64,
+
+    /// What kind of activity this entry records
+    pub kind: ActivityKind,
+
+    /// Transaction identifier shared with the purchase or transfer record
+    pub transaction_id: [u8; 32],
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for ActivityRecord {
+    const DISCRIMINATOR: [u8; 8] = [239, 117, 49, 169, 154, 126, 114, 206];
+}
+
+/// Daily aggregate protocol stats for dashboards
+/// Rewritten by every snapshot taken during its day
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct StatsSnapshot {
+    /// Day this snapshot covers (unix_timestamp / 86400)
+    pub day_index: u64,
+
+    /// Total Lokal tokens ever minted
+    pub total_supply: u64,
+
+    /// Merchants ever registered, including closed ones
+    pub merchant_count: u64,
+
+    /// Purchases processed
+    pub total_purchases: u64,
+
+    /// Peer-to-peer transfers made
+    pub total_transfers: u64,
+
+    /// Vouchers staked in the original voucher pool
+    pub total_voucher_staked: u64,
+
+    /// Stakers in the original voucher pool
+    pub total_stakers: u64,
+
+    /// Yield earned by the original voucher pool
+    pub total_yield_earned: u64,
+
+    /// Timestamp of the latest snapshot this day
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl CarsaAccount for StatsSnapshot {
+    const DISCRIMINATOR: [u8; 8] = [252, 189, 5, 168, 65, 112, 76, 225];
+}
+
+/// A customer's purchase totals for one calendar month (UTC), for the spending passbook
+/// Each month gets its own account, created by the first purchase made in it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct CustomerMonthlyStats {
+    /// The customer these totals belong to, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// The month covered, as YYYYMM (e.g. 202401)
+    pub year_month: u32,
+
+    /// Purchases made this month
+    pub purchase_count: u64,
+
+    /// Total value spent this month, fiat plus redeemed tokens (in IDR)
+    pub total_spent: u64,
+
+    /// Reward tokens earned this month
+    pub rewards_earned: u64,
+
+    /// Tokens redeemed toward purchases this month
+    pub tokens_redeemed: u64,
+
+    /// Timestamp of the latest purchase this month
+    pub updated_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for CustomerMonthlyStats {
+    const DISCRIMINATOR: [u8; 8] = [113, 3, 101, 205, 164, 137, 238, 60];
+}
+
+/// A customer's visits to one merchant, counting toward the merchant's streak bonus
+/// Created by the customer's first purchase at the merchant
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct CustomerMerchantStats {
+    /// The merchant visited
+    pub merchant: Pubkey,
+
+    /// The visiting customer, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// Visits counted in the current streak window
+    pub visit_count: u16,
+
+    /// Timestamp of the first visit in the current streak window
+    pub window_start: i64,
+
+    /// Timestamp of the last visit counted
+    pub last_visit: i64,
+
+    /// Streaks the customer has completed at the merchant
+    pub streaks_completed: u32,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for CustomerMerchantStats {
+    const DISCRIMINATOR: [u8; 8] = [60, 230, 142, 12, 179, 77, 1, 247];
+}
+
+/// Administrative block on a Lokal token account, frozen for as long as it exists
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct AccountRestriction {
+    /// The restricted token account
+    pub token_account: Pubkey,
+
+    /// Owner of the token account when it was restricted
+    pub owner: Pubkey,
+
+    /// The update authority that placed the restriction
+    pub authority: Pubkey,
+
+    /// Caller-defined code describing why the account was blocked
+    pub reason_code: u16,
+
+    /// Timestamp the account was blocked
+    pub blocked_at: i64,
+
+    /// Whether the balance was clawed back; the restriction can no longer be lifted
+    pub clawed_back: bool,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for AccountRestriction {
+    const DISCRIMINATOR: [u8; 8] = [173, 18, 158, 101, 237, 212, 42, 69];
+}
+
+/// Audit record of a clawback from a restricted token account
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct ClawbackRecord {
+    /// The restricted token account
+    pub token_account: Pubkey,
+
+    /// Owner of the token account
+    pub owner: Pubkey,
+
+    /// The restriction the clawback relied on
+    pub restriction: Pubkey,
+
+    /// Treasury token account credited with the balance
+    pub treasury_token_account: Pubkey,
+
+    /// The update authority that made the clawback
+    pub authority: Pubkey,
+
+    /// The mint co-signer that approved it
+    pub cosigner: Pubkey,
+
+    /// Balance clawed back
+    pub amount: u64,
+
+    /// Reason code of the restriction
+    pub reason_code: u16,
+
+    /// Timestamp the account was blocked
+    pub blocked_at: i64,
+
+    /// Timestamp of the clawback
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for ClawbackRecord {
+    const DISCRIMINATOR: [u8; 8] = [180, 93, 108, 89, 176, 115, 87, 73];
+}
+
+/// Extra cashback the protocol mints for purchases at every merchant in a category,
+/// on top of the merchant's own rate, between `start` and `end` and up to `budget`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct CategoryBoost {
+    /// The merchant category boosted, as stored on `MerchantAccount`
+    pub category: [u8; 16],
+
+    /// Extra cashback rate (in basis points)
+    pub bonus_bps: u16,
+
+    /// Timestamp the boost starts applying
+    pub start: i64,
+
+    /// Timestamp the boost stops applying
+    pub end: i64,
+
+    /// Most reward tokens the boost can mint in total
+    pub budget: u64,
+
+    /// Reward tokens the boost has minted so far
+    pub spent: u64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for CategoryBoost {
+    const DISCRIMINATOR: [u8; 8] = [223, 248, 4, 35, 244, 179, 3, 44];
+}
+
+/// Configuration parameters for the voucher staking pool
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct PoolConfig {
+    /// Minimum amount required to stake
+    pub min_stake_amount: u64,
+
+    /// Maximum amount that can be staked per user
+    pub max_stake_per_user: u64,
+
+    /// Whether the pool accepts new deposits
+    pub deposits_enabled: bool,
+
+    /// Whether users can withdraw/redeem
+    pub withdrawals_enabled: bool,
+
+    /// Guaranteed minimum annual yield (in basis points, e.g., 1200 = 12%)
+    /// Accrued over time by `accrue_minimum_yield`, up to `guaranteed_yield_budget`
+    pub apy_basis_points: u16,
+
+    /// Whether deposits revoke any approval left over after the transfer
+    /// Revoking needs the token account owner, so deposits must be co-signed by the user
+    pub auto_revoke_delegation: bool,
+
+    /// How long each deposit locks the user's stake (0 = no lockup)
+    pub lockup_seconds: u32,
+
+    /// Penalty on withdrawals before the lock expires (in basis points)
+    /// Zero rejects early withdrawals outright instead of penalizing them
+    pub early_withdraw_penalty_bps: u16,
+
+    /// Maximum share of the staked principal the delegate may deploy (in basis points)
+    pub max_deployment_bps: u16,
+
+    /// Maximum total vouchers the pool accepts (0 = unlimited)
+    pub max_total_staked: u64,
+
+    /// Stake sizes at which each yield tier starts (0 = tier disabled)
+    /// Enabled tiers must come first and be strictly increasing
+    pub tier_thresholds: [u64; 3],
+
+    /// Yield weight of each tier (in basis points, 10000 = 1x)
+    /// Stakes below the first threshold are weighted 1x; tier changes apply to
+    /// each user from their next deposit or withdrawal
+    pub tier_multipliers_bps: [u16; 3],
+
+    /// Minimum time between a user's last deposit and their next withdrawal (0 = none)
+    pub withdraw_cooldown_seconds: u32,
+
+    /// Whether only wallets with a `StakeWhitelistEntry` may deposit
+    pub whitelist_enabled: bool,
+
+    /// Share of each recorded yield taken as a protocol fee (in basis points)
+    pub performance_fee_bps: u16,
+
+    /// Wallet entitled to the accrued performance fees
+    pub fee_recipient: Pubkey,
+
+    /// Total guaranteed minimum yield the pool may owe (0 = no minimum accrual)
+    pub guaranteed_yield_budget: u64,
+
+    /// Whether withdrawals must go through `request_unstake`/`process_unstake`
+    /// Direct `redeem_voucher` is rejected while this is on
+    pub queued_withdrawals: bool,
+
+    /// Time between an unstake request and when it can be processed
+    pub unstake_delay_seconds: u32,
+
+    /// Whether deposits and withdrawals write a `StakeActionRecord` audit entry
+    /// Each entry costs rent, so this is off unless explicitly enabled
+    pub record_history: bool,
+
+    /// Share of each deposit kept as insurance against deployment losses (in basis points)
+    /// The tokens stay in the vault; the depositor is credited with the remainder
+    pub insurance_fee_bps: u16,
+}
+
+/// Partial update to the pool configuration
+/// Fields left as `None` keep their current value
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default)]
+pub struct PoolConfigUpdate {
+    pub min_stake_amount: Option<u64>,
+    pub max_stake_per_user: Option<u64>,
+    pub deposits_enabled: Option<bool>,
+    pub withdrawals_enabled: Option<bool>,
+    pub apy_basis_points: Option<u16>,
+    pub auto_revoke_delegation: Option<bool>,
+    pub lockup_seconds: Option<u32>,
+    pub early_withdraw_penalty_bps: Option<u16>,
+    pub max_deployment_bps: Option<u16>,
+    pub max_total_staked: Option<u64>,
+    pub tier_thresholds: Option<[u64; 3]>,
+    pub tier_multipliers_bps: Option<[u16; 3]>,
+    pub withdraw_cooldown_seconds: Option<u32>,
+    pub whitelist_enabled: Option<bool>,
+    pub performance_fee_bps: Option<u16>,
+    pub fee_recipient: Option<Pubkey>,
+    pub guaranteed_yield_budget: Option<u64>,
+    pub queued_withdrawals: Option<bool>,
+    pub unstake_delay_seconds: Option<u32>,
+    pub record_history: Option<bool>,
+    pub insurance_fee_bps: Option<u16>,
+}
+
+/// Main pool state account for voucher staking
+/// Tracks overall pool metrics and configuration
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct PoolState {
+    /// The authority that can manage the pool (admin)
+    pub pool_authority: Pubkey,
+
+    /// The delegate authority that can execute deposits on behalf of users
+    pub pool_delegate: Pubkey,
+
+    /// The vault token account that holds staked voucher tokens
+    pub vault_ata: Pubkey,
+
+    /// The mint address of the voucher token (LOKAL token)
+    pub voucher_mint: Pubkey,
+
+    /// Pool configuration parameters
+    pub config: PoolConfig,
+
+    /// Total amount of voucher tokens currently staked in the pool
+    pub total_voucher_staked: u64,
+
+    /// Total SOL/WSOL staked (after swaps)
+    pub total_sol_staked: u64,
+
+    /// Total yield earned (in SOL/WSOL)
+    pub total_yield_earned: u64,
+
+    /// Number of unique stakers
+    pub total_stakers: u64,
+
+    /// Reward index for calculating proportional yields
+    pub reward_index: u128,
+
+    /// Timestamp when pool was created
+    pub created_at: i64,
+
+    /// Timestamp of last yield update
+    pub last_yield_update: i64,
+
+    /// Identifier of this pool, part of its PDA seeds (see `pool_id_seed`)
+    pub pool_id: u16,
+
+    /// Voucher tokens forfeited through early-withdrawal penalties
+    /// These tokens remain in the vault
+    pub penalty_reserve: u64,
+
+    /// Token account receiving vouchers withdrawn for deployment (set by the pool authority)
+    pub deployment_destination: Pubkey,
+
+    /// Voucher tokens currently withdrawn from the vault for deployment
+    pub deployed_amount: u64,
+
+    /// Key that can pause (but not unpause) the pool in an emergency
+    pub guardian: Pubkey,
+
+    /// Emergency pause on deposits, independent of `config.deposits_enabled`
+    pub deposits_paused: bool,
+
+    /// Emergency pause on withdrawals, independent of `config.withdrawals_enabled`
+    pub withdrawals_paused: bool,
+
+    /// Scaled yield left over from the last reward index update
+    /// Carried into the next `record_yield` so rounding never loses yield
+    pub dust_carry: u128,
+
+    /// Total losses recorded against deployed capital
+    pub cumulative_losses: u64,
+
+    /// Cumulative loss per staked token (scaled like `reward_index`)
+    /// Offsets the reward index when computing what users can claim
+    pub loss_index: u128,
+
+    /// Sum of all users' tier-weighted stakes
+    /// Yield and losses are distributed against this instead of `total_voucher_staked`
+    pub weighted_total_staked: u128,
+
+    /// The bump seed for this pool state PDA
+    pub bump: u8,
+
+    /// Authority nominated by `propose_pool_authority`, awaiting acceptance
+    pub pending_pool_authority: Pubkey,
+
+    /// The sLOKAL receipt mint, minted 1:1 on deposit and burned on redemption
+    pub receipt_mint: Pubkey,
+
+    /// Performance fees taken from recorded yield and not yet collected
+    pub protocol_fees_accrued: u64,
+
+    /// Guaranteed minimum yield added to the reward index by `accrue_minimum_yield`
+    /// A liability of the pool authority, capped by `config.guaranteed_yield_budget`
+    pub guaranteed_yield_owed: u64,
+
+    /// Reward index seen by the last passing `verify_pool_invariants`
+    /// The reward index must never fall below it
+    pub last_verified_index: u128,
+
+    /// Vouchers requested for unstaking and still waiting in the vault
+    pub queued_unstake_amount: u64,
+
+    /// Deposit fees held in the vault as insurance against deployment losses
+    pub insurance_reserve: u64,
+
+    /// Total insurance drawn so far; never exceeds `cumulative_losses`
+    pub insurance_drawn: u64,
+
+    /// Sequence number of the last event emitted for this pool; indexers use it to detect gaps
+    pub event_sequence: u64,
+
+    /// Share of a stake its owner may borrow with `borrow_against_stake` (in basis points,
+    /// 0 = advances disabled)
+    pub advance_ltv_bps: u16,
+
+    /// Lokal tokens advanced against stakes in this pool and not yet repaid
+    pub total_advanced: u64,
+
+    /// Position in the backend's yield journal of the last `record_yield`
+    /// Each call must pass the next position, so a replayed journal is rejected
+    pub last_external_sequence: u64,
+
+    /// Whether the pool's PDAs are seeded by its voucher mint (see `PoolState::mint_seed`)
+    /// False for pools created before, which keep the addresses of their `pool_id` alone
+    pub mint_keyed: bool,
+
+    /// Reserved space for future upgrades (13 bytes)
+    pub reserved: [u8; 13],
+}
+
+impl CarsaAccount for PoolState {
+    const DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+}
+
+/// `PoolConfig` as stored by pools created before the config grew
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LegacyPoolConfig {
+    pub min_stake_amount: u64,
+    pub max_stake_per_user: u64,
+    pub deposits_enabled: bool,
+    pub withdrawals_enabled: bool,
+    pub apy_basis_points: u16,
+}
+
+/// `PoolState` as stored by the original singleton pool, before multi-pool support
+/// `PoolState` cannot load these accounts; `migrate_pool` rewrites them in place
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct LegacyPoolState {
+    pub pool_authority: Pubkey,
+    pub pool_delegate: Pubkey,
+    pub vault_ata: Pubkey,
+    pub voucher_mint: Pubkey,
+    pub config: LegacyPoolConfig,
+    pub total_voucher_staked: u64,
+    pub total_sol_staked: u64,
+    pub total_yield_earned: u64,
+    pub total_stakers: u64,
+    pub reward_index: u128,
+    pub created_at: i64,
+    pub last_yield_update: i64,
+    pub bump: u8,
+    pub reserved: [u8; 64],
+}
+
+/// Individual user stake record
+/// Tracks each user's staking position and rewards
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct UserStakeRecord {
+    /// The user who owns this stake
+    pub user: Pubkey,
+
+    /// The pool this stake belongs to
+    pub pool: Pubkey,
+
+    /// Amount of voucher tokens staked by this user
+    pub staked_amount: u64,
+
+    /// User's reward index snapshot (for yield calculations)
+    pub user_reward_index: u128,
+
+    /// Total yield claimed by this user
+    pub total_yield_claimed: u64,
+
+    /// Timestamp when user first staked
+    pub staked_at: i64,
+
+    /// Timestamp of last stake/unstake action
+    /// Withdrawal cooldowns run from `last_deposit_at` instead, so back-to-back
+    /// withdrawals are not throttled
+    pub last_action_at: i64,
+
+    /// The bump seed for this user stake record PDA
+    pub bump: u8,
+
+    /// Timestamp until which the stake is locked (extended by each deposit)
+    pub lock_until: i64,
+
+    /// User's loss index snapshot (offsets yield accrued since the reward snapshot)
+    pub user_loss_index: u128,
+
+    /// Tier-weighted stake this user contributes to `weighted_total_staked`
+    pub weighted_stake: u64,
+
+    /// Yield settled at a checkpoint but not yet claimed
+    pub pending_payout: u64,
+
+    /// Timestamp of the user's last deposit
+    pub last_deposit_at: i64,
+
+    /// Whether the delegate may restake this user's yield via `compound_user`
+    pub auto_compound: bool,
+
+    /// Rate used by the most recent compound (lamports per `COMPOUND_RATE_SCALE` vouchers)
+    pub last_compound_rate: u64,
+
+    /// Total vouchers credited to this stake by compounding
+    pub total_compounded: u64,
+
+    /// Number of `StakeActionRecord` history entries written for this stake
+    /// Also the index of the next entry, which is part of its PDA seeds
+    pub action_count: u64,
+
+    /// Yield `redeem_voucher` would pay as of `view_synced_at`
+    /// A cache for clients; yield recorded since the last sync is not included
+    pub pending_yield: u64,
+
+    /// Share of the pool's tier-weighted stake as of `view_synced_at`, in basis points
+    pub pool_share_bps: u16,
+
+    /// Timestamp `pending_yield` and `pool_share_bps` were last refreshed
+    pub view_synced_at: i64,
+
+    /// Who paid the record's rent at initialization, and so receives it back on close
+    pub rent_payer: Pubkey,
+
+    /// Wallet behind the latest `deposit_for_beneficiary` into this stake, if any
+    /// Recorded for transparency only; it gives the funder no rights over the stake
+    pub funded_by: Pubkey,
+
+    /// Part of `staked_amount` held as collateral for the user's `TokenAdvance`, which
+    /// cannot be withdrawn until the advance is repaid
+    pub locked_amount: u64,
+}
+
+impl CarsaAccount for UserStakeRecord {
+    const DISCRIMINATOR: [u8; 8] = [2, 228, 217, 21, 212, 139, 4, 208];
+}
+
+/// `UserStakeRecord` as stored by stakes in the original singleton pool
+/// `UserStakeRecord` cannot decode these accounts; `migrate_stake_record` rewrites them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug)]
+pub struct LegacyUserStakeRecord {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub staked_amount: u64,
+    pub user_reward_index: u128,
+    pub total_yield_claimed: u64,
+    pub staked_at: i64,
+    pub last_action_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
+
+/// Record of a processed `record_yield` batch
+/// Its PDA is derived from the batch id, so a replayed batch fails to initialize
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct YieldBatch {
+    /// The pool the yield was recorded for
+    pub pool: Pubkey,
+
+    /// Backend-supplied identifier of the batch
+    pub batch_id: [u8; 32],
+
+    /// Amount of yield recorded
+    pub sol_amount: u64,
+
+    /// Timestamp when the batch was recorded
+    pub recorded_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for YieldBatch {
+    const DISCRIMINATOR: [u8; 8] = [0, 52, 121, 38, 216, 201, 140, 84];
+}
+
+/// Approval for a wallet to deposit into a whitelist-gated pool
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct StakeWhitelistEntry {
+    /// The pool the approval applies to
+    pub pool: Pubkey,
+
+    /// The approved wallet
+    pub user: Pubkey,
+
+    /// Timestamp when the wallet was approved
+    pub added_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for StakeWhitelistEntry {
+    const DISCRIMINATOR: [u8; 8] = [32, 253, 58, 3, 38, 244, 182, 120];
+}
+
+/// A user's pending withdrawal in a queued-withdrawal pool
+/// The amount has already left the user's stake and no longer earns yield
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct UnstakeRequest {
+    /// The pool the request belongs to
+    pub pool: Pubkey,
+
+    /// The user who requested the withdrawal
+    pub user: Pubkey,
+
+    /// Vouchers waiting to be paid out
+    pub amount: u64,
+
+    /// Timestamp of the latest request (top-ups restart the delay)
+    pub requested_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for UnstakeRequest {
+    const DISCRIMINATOR: [u8; 8] = [127, 34, 23, 191, 211, 254, 202, 42];
+}
+
+/// Lokal tokens a user borrowed against their stake, interest free
+/// The collateral is locked in the user's `UserStakeRecord` until the advance is repaid
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct TokenAdvance {
+    /// The pool whose stake secures the advance
+    pub pool: Pubkey,
+
+    /// The borrower
+    pub user: Pubkey,
+
+    /// Tokens borrowed and not yet repaid
+    pub amount: u64,
+
+    /// The pool's `advance_ltv_bps` when the advance was last drawn, used to size the lock
+    pub ltv_bps: u16,
+
+    /// Timestamp of the latest borrow
+    pub last_borrowed_at: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for TokenAdvance {
+    const DISCRIMINATOR: [u8; 8] = [79, 102, 58, 66, 228, 74, 62, 96];
+}
+
+/// A pool's position in an SPL stake pool, built by `deploy_to_stake_pool`
+/// SOL to deploy waits in the pool's SOL vault PDA, and unwinds pay back into it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct StakePoolDeployment {
+    /// The voucher pool deploying its capital
+    pub pool: Pubkey,
+
+    /// The SPL stake-pool program CPI'd into
+    pub stake_pool_program: Pubkey,
+
+    /// The stake pool deposited into
+    pub stake_pool: Pubkey,
+
+    /// Token account of the stake pool's mint holding the pool tokens, owned by this PDA
+    pub pool_token_account: Pubkey,
+
+    /// Lamports paid for the pool tokens still held (cost basis)
+    pub deployed_lamports: u64,
+
+    /// Pool tokens held, as counted by deposits and unwinds
+    pub pool_tokens: u64,
+
+    /// Lamports deposited into the stake pool over the deployment's lifetime
+    pub total_deposited_lamports: u64,
+
+    /// Lamports returned by unwinds over the deployment's lifetime
+    pub total_withdrawn_lamports: u64,
+
+    /// Lamports returned by unwinds less the cost basis of the tokens unwound
+    /// Negative when the stake pool lost value or charged more in fees than it earned
+    pub realized_pnl_lamports: i64,
+
+    /// The bump seed of the pool's SOL vault PDA
+    pub sol_vault_bump: u8,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for StakePoolDeployment {
+    const DISCRIMINATOR: [u8; 8] = [48, 91, 92, 144, 105, 145, 189, 18];
+}
+
+/// Kind of stake change captured by a `StakeActionRecord`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeActionType {
+    Deposit,
+    Withdrawal,
+}
+
+/// Immutable audit entry for a single deposit or withdrawal
+/// Written only when the pool has `record_history` enabled
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
+pub struct StakeActionRecord {
+    /// The pool the action happened in
+    pub pool: Pubkey,
+
+    /// The user whose stake changed
+    pub user: Pubkey,
+
+    /// Position of this entry in the user's history (starts at 0)
+    pub action_index: u64,
+
+    /// Whether this was a deposit or a withdrawal
+    pub action_type: StakeActionType,
+
+    /// Vouchers deposited or withdrawn
+    pub amount: u64,
+
+    /// The user's staked amount after the action
+    pub resulting_balance: u64,
+
+    /// Pool reward index at the time of the action
+    pub reward_index: u128,
+
+    /// Timestamp of the action
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CarsaAccount for StakeActionRecord {
+    const DISCRIMINATOR: [u8; 8] = [220, 114, 135, 179, 178, 187, 5, 251];
+}
@@ -0,0 +1,22 @@
+//! Decoding of the summary `get_program_status` returns, for monitoring bots that simulate it
+
+use borsh::BorshDeserialize;
+
+use crate::state::ProgramStatus;
+
+impl ProgramStatus {
+    /// Layout version this client decodes
+    pub const VERSION: u8 = 1;
+
+    /// Decodes the return data of a simulated `get_program_status`
+    /// Fields a newer program appends are ignored; a layout older than `VERSION` is rejected
+    pub fn from_return_data(data: &[u8]) -> std::io::Result<Self> {
+        match data.first() {
+            Some(version) if *version >= Self::VERSION => Self::deserialize(&mut &data[..]),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "unsupported program status version",
+            )),
+        }
+    }
+}
@@ -0,0 +1,1252 @@
+//! Checks the client against the program crate itself: discriminators, account metas,
+//! instruction data, PDAs and account layouts must all match what Anchor generates
+
+use anchor_lang::{AccountSerialize, Discriminator, InstructionData, ToAccountMetas};
+use carsa_client::instructions::*;
+use carsa_client::purchase::{
+    find_purchase_by_reference, format_reference_code, parse_reference_code, reference_code,
+    PurchaseRecord,
+};
+use carsa_client::{accounts, pda, state, CarsaAccount};
+use solana_program::instruction::AccountMeta;
+use solana_program::pubkey::Pubkey;
+
+#[test]
+fn instruction_discriminators_match() {
+    for (client, program) in [
+        (
+            INITIALIZE_LOKAL_MINT_DISCRIMINATOR,
+            carsa::instruction::InitializeLokalMint::DISCRIMINATOR,
+        ),
+        (
+            MINT_LOKAL_TOKENS_DISCRIMINATOR,
+            carsa::instruction::MintLokalTokens::DISCRIMINATOR,
+        ),
+        (
+            RECONCILE_SUPPLY_DISCRIMINATOR,
+            carsa::instruction::ReconcileSupply::DISCRIMINATOR,
+        ),
+        (
+            REGISTER_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::RegisterMerchant::DISCRIMINATOR,
+        ),
+        (
+            REGISTER_MERCHANT_BY_AUTHORITY_DISCRIMINATOR,
+            carsa::instruction::RegisterMerchantByAuthority::DISCRIMINATOR,
+        ),
+        (
+            ACCEPT_MERCHANT_REGISTRATION_DISCRIMINATOR,
+            carsa::instruction::AcceptMerchantRegistration::DISCRIMINATOR,
+        ),
+        (
+            PROCESS_PURCHASE_WITH_REDEMPTION_DISCRIMINATOR,
+            carsa::instruction::ProcessPurchaseWithRedemption::DISCRIMINATOR,
+        ),
+        (
+            PROCESS_PURCHASE_DISCRIMINATOR,
+            carsa::instruction::ProcessPurchase::DISCRIMINATOR,
+        ),
+        (
+            CREATE_INVOICE_DISCRIMINATOR,
+            carsa::instruction::CreateInvoice::DISCRIMINATOR,
+        ),
+        (
+            PAY_INVOICE_DISCRIMINATOR,
+            carsa::instruction::PayInvoice::DISCRIMINATOR,
+        ),
+        (
+            CREATE_HOLD_DISCRIMINATOR,
+            carsa::instruction::CreateHold::DISCRIMINATOR,
+        ),
+        (
+            CAPTURE_HOLD_DISCRIMINATOR,
+            carsa::instruction::CaptureHold::DISCRIMINATOR,
+        ),
+        (
+            RELEASE_HOLD_DISCRIMINATOR,
+            carsa::instruction::ReleaseHold::DISCRIMINATOR,
+        ),
+        (
+            OPEN_DISPUTE_DISCRIMINATOR,
+            carsa::instruction::OpenDispute::DISCRIMINATOR,
+        ),
+        (
+            RESPOND_DISPUTE_DISCRIMINATOR,
+            carsa::instruction::RespondDispute::DISCRIMINATOR,
+        ),
+        (
+            RESOLVE_DISPUTE_DISCRIMINATOR,
+            carsa::instruction::ResolveDispute::DISCRIMINATOR,
+        ),
+        (
+            MARK_FULFILLED_DISCRIMINATOR,
+            carsa::instruction::MarkFulfilled::DISCRIMINATOR,
+        ),
+        (
+            SETTLE_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::SettleMerchant::DISCRIMINATOR,
+        ),
+        (
+            CREATE_GIFT_CARD_DISCRIMINATOR,
+            carsa::instruction::CreateGiftCard::DISCRIMINATOR,
+        ),
+        (
+            REDEEM_GIFT_CARD_DISCRIMINATOR,
+            carsa::instruction::RedeemGiftCard::DISCRIMINATOR,
+        ),
+        (
+            RECLAIM_GIFT_CARD_DISCRIMINATOR,
+            carsa::instruction::ReclaimGiftCard::DISCRIMINATOR,
+        ),
+        (
+            CREATE_SUBSCRIPTION_DISCRIMINATOR,
+            carsa::instruction::CreateSubscription::DISCRIMINATOR,
+        ),
+        (
+            CHARGE_SUBSCRIPTION_DISCRIMINATOR,
+            carsa::instruction::ChargeSubscription::DISCRIMINATOR,
+        ),
+        (
+            CANCEL_SUBSCRIPTION_DISCRIMINATOR,
+            carsa::instruction::CancelSubscription::DISCRIMINATOR,
+        ),
+        (
+            CREATE_AIRDROP_DISCRIMINATOR,
+            carsa::instruction::CreateAirdrop::DISCRIMINATOR,
+        ),
+        (
+            CLAIM_AIRDROP_DISCRIMINATOR,
+            carsa::instruction::ClaimAirdrop::DISCRIMINATOR,
+        ),
+        (
+            SWEEP_AIRDROP_DISCRIMINATOR,
+            carsa::instruction::SweepAirdrop::DISCRIMINATOR,
+        ),
+        (
+            CREATE_SESSION_KEY_DISCRIMINATOR,
+            carsa::instruction::CreateSessionKey::DISCRIMINATOR,
+        ),
+        (
+            PROCESS_PURCHASE_WITH_SESSION_DISCRIMINATOR,
+            carsa::instruction::ProcessPurchaseWithSession::DISCRIMINATOR,
+        ),
+        (
+            REVOKE_SESSION_KEY_DISCRIMINATOR,
+            carsa::instruction::RevokeSessionKey::DISCRIMINATOR,
+        ),
+        (
+            UPDATE_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::UpdateMerchant::DISCRIMINATOR,
+        ),
+        (
+            REGISTER_POS_KEY_DISCRIMINATOR,
+            carsa::instruction::RegisterPosKey::DISCRIMINATOR,
+        ),
+        (
+            REMOVE_POS_KEY_DISCRIMINATOR,
+            carsa::instruction::RemovePosKey::DISCRIMINATOR,
+        ),
+        (
+            ADD_MERCHANT_OPERATOR_DISCRIMINATOR,
+            carsa::instruction::AddMerchantOperator::DISCRIMINATOR,
+        ),
+        (
+            REMOVE_MERCHANT_OPERATOR_DISCRIMINATOR,
+            carsa::instruction::RemoveMerchantOperator::DISCRIMINATOR,
+        ),
+        (
+            CREATE_PARENT_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::CreateParentMerchant::DISCRIMINATOR,
+        ),
+        (
+            UPDATE_PARENT_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::UpdateParentMerchant::DISCRIMINATOR,
+        ),
+        (
+            CASCADE_CASHBACK_RATE_DISCRIMINATOR,
+            carsa::instruction::CascadeCashbackRate::DISCRIMINATOR,
+        ),
+        (
+            CREATE_REWARD_BUDGET_DISCRIMINATOR,
+            carsa::instruction::CreateRewardBudget::DISCRIMINATOR,
+        ),
+        (
+            TOP_UP_REWARD_BUDGET_DISCRIMINATOR,
+            carsa::instruction::TopUpRewardBudget::DISCRIMINATOR,
+        ),
+        (
+            WITHDRAW_REWARD_BUDGET_DISCRIMINATOR,
+            carsa::instruction::WithdrawRewardBudget::DISCRIMINATOR,
+        ),
+        (
+            ADD_BUDGET_MEMBER_DISCRIMINATOR,
+            carsa::instruction::AddBudgetMember::DISCRIMINATOR,
+        ),
+        (
+            REMOVE_BUDGET_MEMBER_DISCRIMINATOR,
+            carsa::instruction::RemoveBudgetMember::DISCRIMINATOR,
+        ),
+        (
+            SET_PAYOUT_ACCOUNT_DISCRIMINATOR,
+            carsa::instruction::SetPayoutAccount::DISCRIMINATOR,
+        ),
+        (
+            PROPOSE_MERCHANT_OWNER_DISCRIMINATOR,
+            carsa::instruction::ProposeMerchantOwner::DISCRIMINATOR,
+        ),
+        (
+            ACCEPT_MERCHANT_OWNERSHIP_DISCRIMINATOR,
+            carsa::instruction::AcceptMerchantOwnership::DISCRIMINATOR,
+        ),
+        (
+            CLOSE_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::CloseMerchant::DISCRIMINATOR,
+        ),
+        (
+            SET_STAKER_BOOST_DISCRIMINATOR,
+            carsa::instruction::SetStakerBoost::DISCRIMINATOR,
+        ),
+        (
+            SET_MINT_COSIGNER_DISCRIMINATOR,
+            carsa::instruction::SetMintCosigner::DISCRIMINATOR,
+        ),
+        (
+            SET_LIMITS_DISCRIMINATOR,
+            carsa::instruction::SetLimits::DISCRIMINATOR,
+        ),
+        (
+            SET_MINT_CAPS_DISCRIMINATOR,
+            carsa::instruction::SetMintCaps::DISCRIMINATOR,
+        ),
+        (
+            SET_RATE_CHANGE_POLICY_DISCRIMINATOR,
+            carsa::instruction::SetRateChangePolicy::DISCRIMINATOR,
+        ),
+        (
+            SET_CASHBACK_LIMITS_DISCRIMINATOR,
+            carsa::instruction::SetCashbackLimits::DISCRIMINATOR,
+        ),
+        (
+            SET_PRICE_FEED_DISCRIMINATOR,
+            carsa::instruction::SetPriceFeed::DISCRIMINATOR,
+        ),
+        (
+            SET_DISPUTE_WINDOW_DISCRIMINATOR,
+            carsa::instruction::SetDisputeWindow::DISCRIMINATOR,
+        ),
+        (
+            SET_FULFILLMENT_AUTHORITY_DISCRIMINATOR,
+            carsa::instruction::SetFulfillmentAuthority::DISCRIMINATOR,
+        ),
+        (
+            SET_REWARD_EXPIRY_DISCRIMINATOR,
+            carsa::instruction::SetRewardExpiry::DISCRIMINATOR,
+        ),
+        (
+            EXPIRE_REWARDS_DISCRIMINATOR,
+            carsa::instruction::ExpireRewards::DISCRIMINATOR,
+        ),
+        (
+            WITHDRAW_VESTED_REWARDS_DISCRIMINATOR,
+            carsa::instruction::WithdrawVestedRewards::DISCRIMINATOR,
+        ),
+        (
+            CREATE_VESTING_GRANT_DISCRIMINATOR,
+            carsa::instruction::CreateVestingGrant::DISCRIMINATOR,
+        ),
+        (
+            CLAIM_VESTED_DISCRIMINATOR,
+            carsa::instruction::ClaimVested::DISCRIMINATOR,
+        ),
+        (
+            REVOKE_VESTING_GRANT_DISCRIMINATOR,
+            carsa::instruction::RevokeVestingGrant::DISCRIMINATOR,
+        ),
+        (
+            SET_ACTIVITY_RECORDING_DISCRIMINATOR,
+            carsa::instruction::SetActivityRecording::DISCRIMINATOR,
+        ),
+        (
+            SET_SLIM_PURCHASE_RECORDS_DISCRIMINATOR,
+            carsa::instruction::SetSlimPurchaseRecords::DISCRIMINATOR,
+        ),
+        (
+            SET_CUSTOMER_HASHING_DISCRIMINATOR,
+            carsa::instruction::SetCustomerHashing::DISCRIMINATOR,
+        ),
+        (
+            SET_PROTOCOL_FEE_DISCRIMINATOR,
+            carsa::instruction::SetProtocolFee::DISCRIMINATOR,
+        ),
+        (
+            SET_TRANSFER_FEE_DISCRIMINATOR,
+            carsa::instruction::SetTransferFee::DISCRIMINATOR,
+        ),
+        (
+            SET_TREASURY_CONTROLS_DISCRIMINATOR,
+            carsa::instruction::SetTreasuryControls::DISCRIMINATOR,
+        ),
+        (
+            WITHDRAW_TREASURY_DISCRIMINATOR,
+            carsa::instruction::WithdrawTreasury::DISCRIMINATOR,
+        ),
+        (
+            RESTRICT_ACCOUNT_DISCRIMINATOR,
+            carsa::instruction::RestrictAccount::DISCRIMINATOR,
+        ),
+        (
+            LIFT_ACCOUNT_RESTRICTION_DISCRIMINATOR,
+            carsa::instruction::LiftAccountRestriction::DISCRIMINATOR,
+        ),
+        (
+            CLAWBACK_DISCRIMINATOR,
+            carsa::instruction::Clawback::DISCRIMINATOR,
+        ),
+        (
+            SET_CATEGORY_BOOST_DISCRIMINATOR,
+            carsa::instruction::SetCategoryBoost::DISCRIMINATOR,
+        ),
+        (
+            CLOSE_CATEGORY_BOOST_DISCRIMINATOR,
+            carsa::instruction::CloseCategoryBoost::DISCRIMINATOR,
+        ),
+        (
+            SET_STREAK_REWARD_DISCRIMINATOR,
+            carsa::instruction::SetStreakReward::DISCRIMINATOR,
+        ),
+        (
+            SET_PARAM_TIMELOCK_DISCRIMINATOR,
+            carsa::instruction::SetParamTimelock::DISCRIMINATOR,
+        ),
+        (
+            QUEUE_PARAM_CHANGE_DISCRIMINATOR,
+            carsa::instruction::QueueParamChange::DISCRIMINATOR,
+        ),
+        (
+            EXECUTE_PARAM_CHANGE_DISCRIMINATOR,
+            carsa::instruction::ExecuteParamChange::DISCRIMINATOR,
+        ),
+        (
+            CANCEL_PARAM_CHANGE_DISCRIMINATOR,
+            carsa::instruction::CancelParamChange::DISCRIMINATOR,
+        ),
+        (
+            SET_GUARDIAN_DISCRIMINATOR,
+            carsa::instruction::SetGuardian::DISCRIMINATOR,
+        ),
+        (
+            SET_GLOBAL_PAUSE_DISCRIMINATOR,
+            carsa::instruction::SetGlobalPause::DISCRIMINATOR,
+        ),
+        (
+            SET_MERCHANT_SUSPENSION_DISCRIMINATOR,
+            carsa::instruction::SetMerchantSuspension::DISCRIMINATOR,
+        ),
+        (
+            PREVIEW_PURCHASE_REWARD_DISCRIMINATOR,
+            carsa::instruction::PreviewPurchaseReward::DISCRIMINATOR,
+        ),
+        (
+            GET_PROGRAM_STATUS_DISCRIMINATOR,
+            carsa::instruction::GetProgramStatus::DISCRIMINATOR,
+        ),
+        (
+            TRANSFER_TOKENS_DISCRIMINATOR,
+            carsa::instruction::TransferTokens::DISCRIMINATOR,
+        ),
+        (
+            ADD_FEE_EXEMPTION_DISCRIMINATOR,
+            carsa::instruction::AddFeeExemption::DISCRIMINATOR,
+        ),
+        (
+            REMOVE_FEE_EXEMPTION_DISCRIMINATOR,
+            carsa::instruction::RemoveFeeExemption::DISCRIMINATOR,
+        ),
+        (
+            INITIALIZE_POOL_DISCRIMINATOR,
+            carsa::instruction::InitializePool::DISCRIMINATOR,
+        ),
+        (
+            DEPOSIT_VOUCHER_DISCRIMINATOR,
+            carsa::instruction::DepositVoucher::DISCRIMINATOR,
+        ),
+        (
+            DEPOSIT_FOR_BENEFICIARY_DISCRIMINATOR,
+            carsa::instruction::DepositForBeneficiary::DISCRIMINATOR,
+        ),
+        (
+            RECORD_YIELD_DISCRIMINATOR,
+            carsa::instruction::RecordYield::DISCRIMINATOR,
+        ),
+        (
+            RESET_EXTERNAL_SEQUENCE_DISCRIMINATOR,
+            carsa::instruction::ResetExternalSequence::DISCRIMINATOR,
+        ),
+        (
+            CONFIGURE_STAKE_POOL_DISCRIMINATOR,
+            carsa::instruction::ConfigureStakePool::DISCRIMINATOR,
+        ),
+        (
+            DEPLOY_TO_STAKE_POOL_DISCRIMINATOR,
+            carsa::instruction::DeployToStakePool::DISCRIMINATOR,
+        ),
+        (
+            UNWIND_FROM_STAKE_POOL_DISCRIMINATOR,
+            carsa::instruction::UnwindFromStakePool::DISCRIMINATOR,
+        ),
+        (
+            RECORD_LOSS_DISCRIMINATOR,
+            carsa::instruction::RecordLoss::DISCRIMINATOR,
+        ),
+        (
+            REDEEM_VOUCHER_DISCRIMINATOR,
+            carsa::instruction::RedeemVoucher::DISCRIMINATOR,
+        ),
+        (
+            REDEEM_ALL_VOUCHERS_DISCRIMINATOR,
+            carsa::instruction::RedeemAllVouchers::DISCRIMINATOR,
+        ),
+        (
+            UPDATE_POOL_CONFIG_DISCRIMINATOR,
+            carsa::instruction::UpdatePoolConfig::DISCRIMINATOR,
+        ),
+        (
+            SET_ADVANCE_LTV_DISCRIMINATOR,
+            carsa::instruction::SetAdvanceLtv::DISCRIMINATOR,
+        ),
+        (
+            BORROW_AGAINST_STAKE_DISCRIMINATOR,
+            carsa::instruction::BorrowAgainstStake::DISCRIMINATOR,
+        ),
+        (
+            REPAY_ADVANCE_DISCRIMINATOR,
+            carsa::instruction::RepayAdvance::DISCRIMINATOR,
+        ),
+        (
+            SET_DEPLOYMENT_DESTINATION_DISCRIMINATOR,
+            carsa::instruction::SetDeploymentDestination::DISCRIMINATOR,
+        ),
+        (
+            WITHDRAW_FOR_DEPLOYMENT_DISCRIMINATOR,
+            carsa::instruction::WithdrawForDeployment::DISCRIMINATOR,
+        ),
+        (
+            RETURN_PRINCIPAL_DISCRIMINATOR,
+            carsa::instruction::ReturnPrincipal::DISCRIMINATOR,
+        ),
+        (
+            CLOSE_STAKE_RECORD_DISCRIMINATOR,
+            carsa::instruction::CloseStakeRecord::DISCRIMINATOR,
+        ),
+        (
+            CLOSE_STAKE_RECORDS_DISCRIMINATOR,
+            carsa::instruction::CloseStakeRecords::DISCRIMINATOR,
+        ),
+        (
+            REPAIR_STAKE_RECORD_DISCRIMINATOR,
+            carsa::instruction::RepairStakeRecord::DISCRIMINATOR,
+        ),
+        (
+            SET_POOL_GUARDIAN_DISCRIMINATOR,
+            carsa::instruction::SetPoolGuardian::DISCRIMINATOR,
+        ),
+        (
+            SET_POOL_PAUSE_DISCRIMINATOR,
+            carsa::instruction::SetPoolPause::DISCRIMINATOR,
+        ),
+        (
+            PROPOSE_POOL_AUTHORITY_DISCRIMINATOR,
+            carsa::instruction::ProposePoolAuthority::DISCRIMINATOR,
+        ),
+        (
+            ACCEPT_POOL_AUTHORITY_DISCRIMINATOR,
+            carsa::instruction::AcceptPoolAuthority::DISCRIMINATOR,
+        ),
+        (
+            SET_AUTO_COMPOUND_DISCRIMINATOR,
+            carsa::instruction::SetAutoCompound::DISCRIMINATOR,
+        ),
+        (
+            COMPOUND_USER_DISCRIMINATOR,
+            carsa::instruction::CompoundUser::DISCRIMINATOR,
+        ),
+        (
+            ADD_TO_WHITELIST_DISCRIMINATOR,
+            carsa::instruction::AddToWhitelist::DISCRIMINATOR,
+        ),
+        (
+            REMOVE_FROM_WHITELIST_DISCRIMINATOR,
+            carsa::instruction::RemoveFromWhitelist::DISCRIMINATOR,
+        ),
+        (
+            SETTLE_USERS_DISCRIMINATOR,
+            carsa::instruction::SettleUsers::DISCRIMINATOR,
+        ),
+        (
+            ACCRUE_MINIMUM_YIELD_DISCRIMINATOR,
+            carsa::instruction::AccrueMinimumYield::DISCRIMINATOR,
+        ),
+        (
+            REQUEST_UNSTAKE_DISCRIMINATOR,
+            carsa::instruction::RequestUnstake::DISCRIMINATOR,
+        ),
+        (
+            CANCEL_UNSTAKE_DISCRIMINATOR,
+            carsa::instruction::CancelUnstake::DISCRIMINATOR,
+        ),
+        (
+            PROCESS_UNSTAKE_DISCRIMINATOR,
+            carsa::instruction::ProcessUnstake::DISCRIMINATOR,
+        ),
+        (
+            VERIFY_POOL_INVARIANTS_DISCRIMINATOR,
+            carsa::instruction::VerifyPoolInvariants::DISCRIMINATOR,
+        ),
+        (
+            DRAW_INSURANCE_DISCRIMINATOR,
+            carsa::instruction::DrawInsurance::DISCRIMINATOR,
+        ),
+        (
+            PREVIEW_CLAIMABLE_YIELD_DISCRIMINATOR,
+            carsa::instruction::PreviewClaimableYield::DISCRIMINATOR,
+        ),
+        (
+            SYNC_STAKE_VIEW_DISCRIMINATOR,
+            carsa::instruction::SyncStakeView::DISCRIMINATOR,
+        ),
+        (
+            WRITE_STATS_SNAPSHOT_DISCRIMINATOR,
+            carsa::instruction::WriteStatsSnapshot::DISCRIMINATOR,
+        ),
+        (
+            MIGRATE_POOL_DISCRIMINATOR,
+            carsa::instruction::MigratePool::DISCRIMINATOR,
+        ),
+        (
+            MIGRATE_STAKE_RECORD_DISCRIMINATOR,
+            carsa::instruction::MigrateStakeRecord::DISCRIMINATOR,
+        ),
+        (
+            MIGRATE_CONFIG_DISCRIMINATOR,
+            carsa::instruction::MigrateConfig::DISCRIMINATOR,
+        ),
+        (
+            MIGRATE_MERCHANT_DISCRIMINATOR,
+            carsa::instruction::MigrateMerchant::DISCRIMINATOR,
+        ),
+        (
+            MIGRATE_PURCHASE_TRANSACTION_DISCRIMINATOR,
+            carsa::instruction::MigratePurchaseTransaction::DISCRIMINATOR,
+        ),
+    ] {
+        assert_eq!(client, program);
+    }
+}
+
+#[test]
+fn account_discriminators_match() {
+    for (client, program) in [
+        (
+            state::LokalMintConfig::DISCRIMINATOR,
+            carsa::state::LokalMintConfig::DISCRIMINATOR,
+        ),
+        (
+            state::MerchantAccount::DISCRIMINATOR,
+            carsa::state::MerchantAccount::DISCRIMINATOR,
+        ),
+        (
+            state::MerchantRegistry::DISCRIMINATOR,
+            carsa::state::MerchantRegistry::DISCRIMINATOR,
+        ),
+        (
+            state::MerchantIndex::DISCRIMINATOR,
+            carsa::state::MerchantIndex::DISCRIMINATOR,
+        ),
+        (
+            state::PurchaseTransaction::DISCRIMINATOR,
+            carsa::state::PurchaseTransaction::DISCRIMINATOR,
+        ),
+        (
+            state::PurchaseTransactionV2::DISCRIMINATOR,
+            carsa::state::PurchaseTransactionV2::DISCRIMINATOR,
+        ),
+        (
+            state::Invoice::DISCRIMINATOR,
+            carsa::state::Invoice::DISCRIMINATOR,
+        ),
+        (
+            state::GiftCard::DISCRIMINATOR,
+            carsa::state::GiftCard::DISCRIMINATOR,
+        ),
+        (
+            state::SettlementRecord::DISCRIMINATOR,
+            carsa::state::SettlementRecord::DISCRIMINATOR,
+        ),
+        (
+            state::Subscription::DISCRIMINATOR,
+            carsa::state::Subscription::DISCRIMINATOR,
+        ),
+        (
+            state::Airdrop::DISCRIMINATOR,
+            carsa::state::Airdrop::DISCRIMINATOR,
+        ),
+        (
+            state::AirdropClaim::DISCRIMINATOR,
+            carsa::state::AirdropClaim::DISCRIMINATOR,
+        ),
+        (
+            state::SessionKey::DISCRIMINATOR,
+            carsa::state::SessionKey::DISCRIMINATOR,
+        ),
+        (
+            state::PosKey::DISCRIMINATOR,
+            carsa::state::PosKey::DISCRIMINATOR,
+        ),
+        (
+            state::MerchantOperator::DISCRIMINATOR,
+            carsa::state::MerchantOperator::DISCRIMINATOR,
+        ),
+        (
+            state::ParentMerchant::DISCRIMINATOR,
+            carsa::state::ParentMerchant::DISCRIMINATOR,
+        ),
+        (
+            state::RewardBudget::DISCRIMINATOR,
+            carsa::state::RewardBudget::DISCRIMINATOR,
+        ),
+        (
+            state::BudgetMembership::DISCRIMINATOR,
+            carsa::state::BudgetMembership::DISCRIMINATOR,
+        ),
+        (
+            state::Dispute::DISCRIMINATOR,
+            carsa::state::Dispute::DISCRIMINATOR,
+        ),
+        (
+            state::Hold::DISCRIMINATOR,
+            carsa::state::Hold::DISCRIMINATOR,
+        ),
+        (
+            state::TokenTransfer::DISCRIMINATOR,
+            carsa::state::TokenTransfer::DISCRIMINATOR,
+        ),
+        (
+            state::TreasuryWithdrawal::DISCRIMINATOR,
+            carsa::state::TreasuryWithdrawal::DISCRIMINATOR,
+        ),
+        (
+            state::AccountRestriction::DISCRIMINATOR,
+            carsa::state::AccountRestriction::DISCRIMINATOR,
+        ),
+        (
+            state::ClawbackRecord::DISCRIMINATOR,
+            carsa::state::ClawbackRecord::DISCRIMINATOR,
+        ),
+        (
+            state::CategoryBoost::DISCRIMINATOR,
+            carsa::state::CategoryBoost::DISCRIMINATOR,
+        ),
+        (
+            state::CustomerMerchantStats::DISCRIMINATOR,
+            carsa::state::CustomerMerchantStats::DISCRIMINATOR,
+        ),
+        (
+            state::TransactionStub::DISCRIMINATOR,
+            carsa::state::TransactionStub::DISCRIMINATOR,
+        ),
+        (
+            state::ReferenceIndex::DISCRIMINATOR,
+            carsa::state::ReferenceIndex::DISCRIMINATOR,
+        ),
+        (
+            state::PendingParamChange::DISCRIMINATOR,
+            carsa::state::PendingParamChange::DISCRIMINATOR,
+        ),
+        (
+            state::RewardLot::DISCRIMINATOR,
+            carsa::state::RewardLot::DISCRIMINATOR,
+        ),
+        (
+            state::VestingGrant::DISCRIMINATOR,
+            carsa::state::VestingGrant::DISCRIMINATOR,
+        ),
+        (
+            state::FeeExemption::DISCRIMINATOR,
+            carsa::state::FeeExemption::DISCRIMINATOR,
+        ),
+        (
+            state::TokenRedemption::DISCRIMINATOR,
+            carsa::state::TokenRedemption::DISCRIMINATOR,
+        ),
+        (
+            state::ActivityRecord::DISCRIMINATOR,
+            carsa::state::ActivityRecord::DISCRIMINATOR,
+        ),
+        (
+            state::StatsSnapshot::DISCRIMINATOR,
+            carsa::state::StatsSnapshot::DISCRIMINATOR,
+        ),
+        (
+            state::CustomerMonthlyStats::DISCRIMINATOR,
+            carsa::state::CustomerMonthlyStats::DISCRIMINATOR,
+        ),
+        (
+            state::PoolState::DISCRIMINATOR,
+            carsa::state::PoolState::DISCRIMINATOR,
+        ),
+        (
+            state::UserStakeRecord::DISCRIMINATOR,
+            carsa::state::UserStakeRecord::DISCRIMINATOR,
+        ),
+        (
+            state::YieldBatch::DISCRIMINATOR,
+            carsa::state::YieldBatch::DISCRIMINATOR,
+        ),
+        (
+            state::StakeWhitelistEntry::DISCRIMINATOR,
+            carsa::state::StakeWhitelistEntry::DISCRIMINATOR,
+        ),
+        (
+            state::UnstakeRequest::DISCRIMINATOR,
+            carsa::state::UnstakeRequest::DISCRIMINATOR,
+        ),
+        (
+            state::TokenAdvance::DISCRIMINATOR,
+            carsa::state::TokenAdvance::DISCRIMINATOR,
+        ),
+        (
+            state::StakePoolDeployment::DISCRIMINATOR,
+            carsa::state::StakePoolDeployment::DISCRIMINATOR,
+        ),
+        (
+            state::StakeActionRecord::DISCRIMINATOR,
+            carsa::state::StakeActionRecord::DISCRIMINATOR,
+        ),
+    ] {
+        assert_eq!(client, program);
+    }
+}
+
+fn purchase_accounts(
+    transaction_record: Option<Pubkey>,
+) -> (accounts::ProcessPurchase, carsa::accounts::ProcessPurchase) {
+    let customer = Pubkey::new_unique();
+    let merchant_account = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let customer_token_account = Pubkey::new_unique();
+    let merchant_token_account = Pubkey::new_unique();
+    let (mint_authority, _) = pda::find_mint_authority_pda();
+    let (config, _) = pda::find_config_pda();
+    let token_program = Pubkey::new_unique();
+    let system_program = Pubkey::new_unique();
+
+    let client = accounts::ProcessPurchase {
+        customer,
+        merchant_account,
+        mint,
+        mint_authority,
+        config,
+        customer_token_account,
+        merchant_token_account,
+        transaction_record,
+        token_program,
+        system_program,
+        stake_pool: None,
+        customer_stake_record: None,
+        activity_record: None,
+        redemption_activity_record: None,
+        treasury_token_account: None,
+        price_feed: None,
+        reward_escrow: None,
+        reward_lot: None,
+        invoice: None,
+        instructions_sysvar: None,
+        pos_key: None,
+        parent_merchant: None,
+        reward_budget: None,
+        budget_membership: None,
+        reward_budget_escrow: None,
+        associated_token_program: None,
+        merchant_operator: None,
+        customer_monthly_stats: None,
+        slim_transaction_record: None,
+        category_boost: None,
+        customer_merchant_stats: None,
+        transaction_stub: transaction_record,
+        reference_index: transaction_record,
+    };
+    let program = carsa::accounts::ProcessPurchase {
+        customer,
+        merchant_account,
+        mint,
+        mint_authority,
+        config,
+        customer_token_account,
+        merchant_token_account,
+        transaction_record,
+        token_program,
+        system_program,
+        stake_pool: None,
+        customer_stake_record: None,
+        activity_record: None,
+        redemption_activity_record: None,
+        treasury_token_account: None,
+        price_feed: None,
+        reward_escrow: None,
+        reward_lot: None,
+        invoice: None,
+        instructions_sysvar: None,
+        pos_key: None,
+        parent_merchant: None,
+        reward_budget: None,
+        budget_membership: None,
+        reward_budget_escrow: None,
+        associated_token_program: None,
+        merchant_operator: None,
+        customer_monthly_stats: None,
+        slim_transaction_record: None,
+        category_boost: None,
+        customer_merchant_stats: None,
+        transaction_stub: transaction_record,
+        reference_index: transaction_record,
+        event_authority: pda::find_event_authority_pda().0,
+        program: carsa::ID,
+    };
+    (client, program)
+}
+
+#[test]
+fn process_purchase_matches_anchor() {
+    let transaction_id = [7u8; 32];
+    for transaction_record in [None, Some(Pubkey::new_unique())] {
+        let (client, program) = purchase_accounts(transaction_record);
+
+        let instruction =
+            process_purchase(&client, 250_000, Some(3_000_000_000), &transaction_id, Some(4));
+        assert_eq!(instruction.program_id, carsa::ID);
+        assert_eq!(instruction.accounts, program.to_account_metas(None));
+        let expected = carsa::instruction::ProcessPurchase {
+            fiat_amount: 250_000,
+            redeem_token_amount: Some(3_000_000_000),
+            transaction_id,
+            expected_nonce: Some(4),
+        };
+        assert_eq!(instruction.data, expected.data());
+    }
+}
+
+#[test]
+fn optional_signer_matches_anchor() {
+    let authority = Pubkey::new_unique();
+    let destination = Pubkey::new_unique();
+    for cosigner in [None, Some(Pubkey::new_unique())] {
+        let client = accounts::MintLokalTokens {
+            authority,
+            mint: Pubkey::new_unique(),
+            mint_authority: pda::find_mint_authority_pda().0,
+            config: pda::find_config_pda().0,
+            destination,
+            token_program: Pubkey::new_unique(),
+            cosigner,
+        };
+        let program = carsa::accounts::MintLokalTokens {
+            authority: client.authority,
+            mint: client.mint,
+            mint_authority: client.mint_authority,
+            config: client.config,
+            destination: client.destination,
+            token_program: client.token_program,
+            cosigner,
+            event_authority: pda::find_event_authority_pda().0,
+            program: carsa::ID,
+        };
+
+        let instruction = mint_lokal_tokens(&client, 42);
+        assert_eq!(instruction.accounts, program.to_account_metas(None));
+        assert_eq!(
+            instruction.data,
+            carsa::instruction::MintLokalTokens { amount: 42 }.data()
+        );
+    }
+}
+
+#[test]
+fn settle_users_appends_stake_records() {
+    let client = accounts::SettleUsers {
+        pool_delegate: Pubkey::new_unique(),
+        pool_state: pda::find_pool_state_pda(&Pubkey::new_unique(), 1).0,
+    };
+    let stake_records = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let instruction = settle_users(&client, &stake_records);
+    let base = client.to_account_metas();
+    assert_eq!(&instruction.accounts[..base.len()], &base[..]);
+    assert_eq!(
+        &instruction.accounts[base.len()..],
+        &[
+            AccountMeta::new(stake_records[0], false),
+            AccountMeta::new(stake_records[1], false)
+        ]
+    );
+    assert_eq!(instruction.data, carsa::instruction::SettleUsers {}.data());
+}
+
+#[test]
+fn pool_config_arguments_encode_like_the_program() {
+    let config = state::PoolConfig {
+        min_stake_amount: 1_000_000_000,
+        max_stake_per_user: 1_000_000_000_000,
+        deposits_enabled: true,
+        withdrawals_enabled: true,
+        apy_basis_points: 500,
+        auto_revoke_delegation: false,
+        lockup_seconds: 3_600,
+        early_withdraw_penalty_bps: 100,
+        max_deployment_bps: 8_000,
+        max_total_staked: 0,
+        tier_thresholds: [10, 20, 30],
+        tier_multipliers_bps: [10_000, 11_000, 12_000],
+        withdraw_cooldown_seconds: 600,
+        whitelist_enabled: false,
+        performance_fee_bps: 1_000,
+        fee_recipient: Pubkey::new_unique(),
+        guaranteed_yield_budget: 5_000,
+        queued_withdrawals: true,
+        unstake_delay_seconds: 86_400,
+        record_history: true,
+        insurance_fee_bps: 50,
+    };
+    let program_config: carsa::state::PoolConfig =
+        anchor_lang::AnchorDeserialize::try_from_slice(&borsh::to_vec(&config).unwrap()).unwrap();
+
+    let voucher_mint = Pubkey::new_unique();
+    let accounts = accounts::InitializePool {
+        pool_authority: Pubkey::new_unique(),
+        pool_delegate: Pubkey::new_unique(),
+        voucher_mint,
+        pool_state: pda::find_pool_state_pda(&voucher_mint, 3).0,
+        pool_vault_authority: pda::find_pool_vault_authority_pda(&voucher_mint, 3).0,
+        vault_ata: Pubkey::new_unique(),
+        receipt_mint: pda::find_receipt_mint_pda(&voucher_mint, 3).0,
+        system_program: Pubkey::new_unique(),
+        token_program: Pubkey::new_unique(),
+        associated_token_program: Pubkey::new_unique(),
+    };
+    let instruction = initialize_pool(&accounts, 3, config);
+    let expected = carsa::instruction::InitializePool {
+        pool_id: 3,
+        config: program_config,
+    };
+    assert_eq!(instruction.data, expected.data());
+}
+
+#[test]
+fn pdas_match_the_program_seeds() {
+    use carsa::state::*;
+    let find = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &carsa::ID);
+    let owner = Pubkey::new_unique();
+
+    assert_eq!(pda::find_config_pda(), find(&[CONFIG_SEED]));
+    assert_eq!(
+        pda::find_merchant_pda(&owner),
+        find(&[MERCHANT_SEED, owner.as_ref()])
+    );
+    assert_eq!(
+        pda::find_merchant_index_pda(5),
+        find(&[MERCHANT_INDEX_SEED, &5u64.to_le_bytes()])
+    );
+    let voucher_mint = Pubkey::new_unique();
+    for pool_id in [0, 1, 300] {
+        let pool_seed = pool_id_seed(pool_id);
+        assert_eq!(
+            pda::find_pool_state_pda(&voucher_mint, pool_id),
+            find(&[POOL_STATE_SEED, voucher_mint.as_ref(), &pool_seed])
+        );
+        assert_eq!(
+            pda::find_pool_vault_authority_pda(&voucher_mint, pool_id),
+            find(&[POOL_VAULT_AUTHORITY_SEED, voucher_mint.as_ref(), &pool_seed])
+        );
+        assert_eq!(
+            pda::find_receipt_mint_pda(&voucher_mint, pool_id),
+            find(&[RECEIPT_MINT_SEED, voucher_mint.as_ref(), &pool_seed])
+        );
+        assert_eq!(
+            pda::find_legacy_pool_state_pda(pool_id),
+            find(&[POOL_STATE_SEED, &pool_seed])
+        );
+        assert_eq!(
+            pda::find_legacy_pool_vault_authority_pda(pool_id),
+            find(&[POOL_VAULT_AUTHORITY_SEED, &pool_seed])
+        );
+        assert_eq!(
+            pda::find_legacy_receipt_mint_pda(pool_id),
+            find(&[RECEIPT_MINT_SEED, &pool_seed])
+        );
+    }
+    // Each mint's first pool sits at the mint alone
+    assert_eq!(
+        pda::find_pool_state_pda(&voucher_mint, 0),
+        find(&[POOL_STATE_SEED, voucher_mint.as_ref()])
+    );
+    let (merchant, customer, transaction_id) = (owner, Pubkey::new_unique(), [3u8; 32]);
+    assert_eq!(
+        pda::find_transaction_pda(&merchant, &customer, &transaction_id),
+        find(&[
+            TRANSACTION_SEED,
+            merchant.as_ref(),
+            customer.as_ref(),
+            &transaction_id
+        ])
+    );
+    assert_eq!(
+        pda::find_slim_transaction_pda(&merchant, &customer, &transaction_id),
+        find(&[
+            TRANSACTION_SEED,
+            merchant.as_ref(),
+            customer.as_ref(),
+            &PurchaseTransactionV2::short_id(&transaction_id)
+        ])
+    );
+    assert_eq!(
+        pda::POOL_VOUCHER_MINT_OFFSET,
+        8 + std::mem::offset_of!(PoolState, voucher_mint)
+    );
+    assert_eq!(
+        pda::find_category_boost_pda("bookstore"),
+        find(&[
+            CATEGORY_BOOST_SEED,
+            &MerchantAccount::category_bytes("bookstore")
+        ])
+    );
+    assert_eq!(
+        pda::find_customer_merchant_stats_pda(&merchant, &customer),
+        find(&[
+            CUSTOMER_MERCHANT_STATS_SEED,
+            merchant.as_ref(),
+            customer.as_ref()
+        ])
+    );
+    assert_eq!(
+        pda::find_transaction_stub_pda(&merchant, &customer, &transaction_id),
+        find(&[
+            TRANSACTION_STUB_SEED,
+            merchant.as_ref(),
+            customer.as_ref(),
+            &transaction_id
+        ])
+    );
+    let reference_code = carsa::state::ReferenceIndex::reference_code(&merchant);
+    assert_eq!(
+        pda::find_reference_index_pda(&reference_code),
+        find(&[REFERENCE_INDEX_SEED, &reference_code])
+    );
+    let (pool_state, _) = pda::find_pool_state_pda(&voucher_mint, 1);
+    assert_eq!(
+        pda::find_user_stake_pda(&pool_state, &owner),
+        find(&[USER_STAKE_SEED, pool_state.as_ref(), owner.as_ref()])
+    );
+    assert_eq!(
+        pda::find_token_advance_pda(&pool_state, &owner),
+        find(&[TOKEN_ADVANCE_SEED, pool_state.as_ref(), owner.as_ref()])
+    );
+    assert_eq!(
+        pda::find_stake_pool_deployment_pda(&pool_state),
+        find(&[STAKE_POOL_DEPLOYMENT_SEED, pool_state.as_ref()])
+    );
+    assert_eq!(
+        pda::find_pool_sol_vault_pda(&pool_state),
+        find(&[POOL_SOL_VAULT_SEED, pool_state.as_ref()])
+    );
+    assert_eq!(
+        pda::find_stake_pool_tokens_pda(&pool_state),
+        find(&[STAKE_POOL_TOKENS_SEED, pool_state.as_ref()])
+    );
+    assert_eq!(
+        pda::find_stake_action_pda(&pool_state, &owner, 2),
+        find(&[
+            STAKE_ACTION_SEED,
+            pool_state.as_ref(),
+            owner.as_ref(),
+            &2u64.to_le_bytes()
+        ])
+    );
+}
+
+#[test]
+fn deserializes_program_accounts() {
+    let merchant = Pubkey::new_unique();
+    let index = carsa::state::MerchantIndex {
+        merchant,
+        index: 9,
+        closed: true,
+        bump: 254,
+    };
+    let mut data = Vec::new();
+    index.try_serialize(&mut data).unwrap();
+    // Accounts are allocated at their maximum size, so decoding must tolerate trailing bytes
+    data.resize(carsa::state::MerchantIndex::LEN + 16, 0);
+
+    let decoded = state::MerchantIndex::try_deserialize(&data).unwrap();
+    assert_eq!(decoded.merchant, merchant);
+    assert_eq!(decoded.index, 9);
+    assert!(decoded.closed);
+    assert_eq!(decoded.bump, 254);
+
+    // Another account type's bytes are rejected by discriminator
+    assert!(state::MerchantRegistry::try_deserialize(&data).is_err());
+}
+
+#[test]
+fn purchase_record_view_reads_either_layout() {
+    let customer = Pubkey::new_unique();
+    let merchant = Pubkey::new_unique();
+    let transaction_id = [5u8; 32];
+
+    let v1 = carsa::state::PurchaseTransaction {
+        customer,
+        merchant,
+        fiat_amount: 100_000,
+        redeemed_token_amount: 2_000_000_000,
+        total_value: 102_000,
+        reward_amount: 5_100_000_000,
+        cashback_rate: 500,
+        used_tokens: true,
+        timestamp: 1_700_000_000,
+        transaction_id,
+        bump: 255,
+        staker_boost_bps: 0,
+        protocol_fee: 0,
+        redemption_bonus_bps: 0,
+        invoice: Pubkey::default(),
+        disputed: false,
+        refunded: false,
+        fulfilled_at: 0,
+        category_boost_bps: 0,
+        category_boost_reward: 0,
+        streak_bonus: 0,
+        reference_code: *b"CSM7NBFR",
+        reserved: [0; 4],
+    };
+    let v2 = carsa::state::PurchaseTransactionV2 {
+        customer,
+        merchant,
+        fiat_amount: 100_000,
+        redeemed_token_amount: 2_000_000_000,
+        reward_amount: 5_100_000_000,
+        protocol_fee: 0,
+        timestamp: 1_700_000_000,
+        fulfilled_at: 0,
+        transaction_id: carsa::state::PurchaseTransactionV2::short_id(&transaction_id),
+        invoice: Pubkey::default(),
+        staker_boost_bps: 0,
+        redemption_bonus_bps: 0,
+        bump: 255,
+        disputed: false,
+        refunded: false,
+        category_boost_reward: 0,
+        streak_bonus: 0,
+        reference_code: *b"CSM7NBFR",
+    };
+
+    let mut v1_data = Vec::new();
+    v1.try_serialize(&mut v1_data).unwrap();
+    let mut v2_data = Vec::new();
+    v2.try_serialize(&mut v2_data).unwrap();
+
+    let v1_view = PurchaseRecord::try_deserialize(&v1_data).unwrap();
+    let v2_view = PurchaseRecord::try_deserialize(&v2_data).unwrap();
+    assert!(matches!(v1_view, PurchaseRecord::V1(_)));
+    assert!(matches!(v2_view, PurchaseRecord::V2(_)));
+    for view in [&v1_view, &v2_view] {
+        assert_eq!(view.customer(), customer);
+        assert_eq!(view.merchant(), merchant);
+        assert_eq!(view.fiat_amount(), 100_000);
+        assert_eq!(view.reward_amount(), 5_100_000_000);
+        assert!(view.used_tokens());
+        assert_eq!(view.transaction_id(), &transaction_id[..view.transaction_id().len()]);
+        assert_eq!(&view.reference_code(), b"CSM7NBFR");
+    }
+    assert_eq!(v1_view.transaction_id().len(), 32);
+    assert_eq!(v2_view.transaction_id().len(), 16);
+
+    // Other accounts are neither layout
+    let mut data = Vec::new();
+    carsa::state::MerchantIndex {
+        merchant,
+        index: 0,
+        closed: false,
+        bump: 255,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    assert!(PurchaseRecord::try_deserialize(&data).is_err());
+}
+
+#[test]
+fn reference_codes_match_the_program() {
+    for transaction_record in [Pubkey::default(), Pubkey::new_unique(), Pubkey::new_unique()] {
+        let code = carsa::state::ReferenceIndex::reference_code(&transaction_record);
+        assert_eq!(reference_code(&transaction_record), code);
+
+        // Customers read the prefixed form out; support may type it any way
+        let shown = format_reference_code(&code);
+        assert_eq!(shown.len(), 13);
+        assert_eq!(parse_reference_code(&shown), Some(code));
+        assert_eq!(parse_reference_code(&shown.to_lowercase()), Some(code));
+        assert_eq!(parse_reference_code(&shown[5..]), Some(code));
+        assert_eq!(
+            find_purchase_by_reference(&shown),
+            Some(pda::find_reference_index_pda(&code))
+        );
+    }
+
+    assert_eq!(parse_reference_code("crsa-csm7nbfr"), Some(*b"CSM7NBFR"));
+    assert_eq!(parse_reference_code("CRSA-CSM7NBFO"), Some(*b"CSM7NBF0"));
+    assert_eq!(parse_reference_code("CRSA-CSM7NBF"), None);
+    assert_eq!(parse_reference_code("CRSA-CSM7NBFU"), None);
+    assert_eq!(find_purchase_by_reference("not a code"), None);
+}
+
+#[test]
+fn program_status_decodes_from_return_data() {
+    assert_eq!(state::ProgramStatus::VERSION, carsa::state::ProgramStatus::VERSION);
+
+    let pool = Pubkey::new_unique();
+    let status = carsa::state::ProgramStatus {
+        version: carsa::state::ProgramStatus::VERSION,
+        paused: true,
+        mint_supply: 12,
+        tracked_supply: 11,
+        admin_mint_headroom: u64::MAX,
+        reward_mint_headroom: 3,
+        pending_param_changes: 2,
+        pool,
+        deposits_enabled: true,
+        withdrawals_enabled: false,
+        deposits_paused: false,
+        withdrawals_paused: true,
+        vault_balance: 40,
+        total_staked: 45,
+        vault_delta: -5,
+        yield_update_age_seconds: 600,
+    };
+    let mut data = anchor_lang::AnchorSerialize::try_to_vec(&status).unwrap();
+
+    let decoded = state::ProgramStatus::from_return_data(&data).unwrap();
+    assert!(decoded.paused);
+    assert_eq!(decoded.tracked_supply, 11);
+    assert_eq!(decoded.reward_mint_headroom, 3);
+    assert_eq!(decoded.pending_param_changes, 2);
+    assert_eq!(decoded.pool, pool);
+    assert!(decoded.withdrawals_paused);
+    assert_eq!(decoded.vault_delta, -5);
+    assert_eq!(decoded.yield_update_age_seconds, 600);
+
+    // A newer layout appends fields, which this client skips
+    data.extend_from_slice(&[7; 9]);
+    assert_eq!(state::ProgramStatus::from_return_data(&data).unwrap(), decoded);
+
+    data[0] = 0;
+    assert!(state::ProgramStatus::from_return_data(&data).is_err());
+    assert!(state::ProgramStatus::from_return_data(&[]).is_err());
+}
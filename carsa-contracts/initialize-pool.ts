@@ -18,8 +18,6 @@ import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
-  createAssociatedTokenAccountInstruction,
-  getAccount,
 } from "@solana/spl-token";
 import { Carsa } from "./target/types/carsa";
 import * as fs from "fs";
@@ -36,7 +34,7 @@ const DELEGATE_KEYPAIR_PATH = "../delegate-keypair.json";
 // Helper functions
 function getPoolStatePDA(programId: PublicKey): PublicKey {
   const [pda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("pool_state")],
+    [Buffer.from("pool_state"), LOKAL_MINT.toBuffer()],
     programId
   );
   return pda;
@@ -44,7 +42,7 @@ function getPoolStatePDA(programId: PublicKey): PublicKey {
 
 function getPoolVaultAuthorityPDA(programId: PublicKey): PublicKey {
   const [pda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("pool_vault_authority")],
+    [Buffer.from("pool_vault_authority"), LOKAL_MINT.toBuffer()],
     programId
   );
   return pda;
@@ -87,7 +85,7 @@ async function main() {
     console.log("  Pool State:", poolState.toBase58());
     console.log("  Pool Vault Authority:", poolVaultAuthority.toBase58());
     
-    // Get vault ATA
+    // Vault ATA (created by initialize_pool if it doesn't exist yet)
     const vaultAta = await getAssociatedTokenAddress(
       LOKAL_MINT,
       poolVaultAuthority,
@@ -107,24 +105,6 @@ async function main() {
       console.log("\n✅ Pool not found, proceeding with initialization");
     }
     
-    // Check if vault ATA exists, create if not
-    try {
-      await getAccount(connection, vaultAta);
-      console.log("✅ Vault ATA already exists");
-    } catch (error) {
-      console.log("\n📦 Creating vault ATA...");
-      const createAtaIx = createAssociatedTokenAccountInstruction(
-        poolAuthority.publicKey,
-        vaultAta,
-        poolVaultAuthority,
-        LOKAL_MINT
-      );
-      
-      const createAtaTx = new anchor.web3.Transaction().add(createAtaIx);
-      const createAtaSig = await provider.sendAndConfirm(createAtaTx, [poolAuthority]);
-      console.log("✅ Vault ATA created:", createAtaSig);
-    }
-    
     // Pool configuration
     const poolConfig = {
       minStakeAmount: new anchor.BN(100_000_000), // 0.1 LOKAL minimum
@@ -132,6 +112,22 @@ async function main() {
       depositsEnabled: true,
       withdrawalsEnabled: true,
       apyBasisPoints: 1200, // 12% APY
+      autoRevokeDelegation: false,
+      lockupSeconds: 0,
+      earlyWithdrawPenaltyBps: 0,
+      maxDeploymentBps: 0,
+      maxTotalStaked: new anchor.BN(0),
+      tierThresholds: [new anchor.BN(0), new anchor.BN(0), new anchor.BN(0)],
+      tierMultipliersBps: [0, 0, 0],
+      withdrawCooldownSeconds: 0,
+      whitelistEnabled: false,
+      performanceFeeBps: 0,
+      feeRecipient: PublicKey.default,
+      guaranteedYieldBudget: new anchor.BN(0),
+      queuedWithdrawals: false,
+      unstakeDelaySeconds: 0,
+      recordHistory: false,
+      insuranceFeeBps: 0,
     };
     
     console.log("\n📋 Pool Configuration:");
@@ -144,12 +140,13 @@ async function main() {
     // Initialize pool
     console.log("\n🚀 Initializing pool...");
     const tx = await program.methods
-      .initializePool(poolConfig)
+      .initializePool(0, poolConfig) // Pool 0 of a mint is seeded by the mint alone
       .accounts({
         poolAuthority: poolAuthority.publicKey,
         poolDelegate: poolAuthority.publicKey, // Using same key for both
         voucherMint: LOKAL_MINT,
         vaultAta: vaultAta,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
       })
       .signers([poolAuthority])
       .rpc();
@@ -48,6 +48,14 @@ function getPoolVaultAuthorityPDA(programId: PublicKey): PublicKey {
   return pda;
 }
 
+function getReceiptMintPDA(programId: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("receipt_mint")],
+    programId
+  );
+  return pda;
+}
+
 function getUserStakePDA(
   programId: PublicKey,
   poolState: PublicKey,
@@ -149,6 +157,8 @@ async function main() {
       poolVaultAuthority,
       true
     );
+    const receiptMint = getReceiptMintPDA(program.programId);
+    const userReceiptAta = await getAssociatedTokenAddress(receiptMint, userPubkey);
     
     console.log("\nAccounts:");
     console.log("  Pool State:", poolState.toBase58());
@@ -164,6 +174,9 @@ async function main() {
         poolDelegate: poolDelegate.publicKey,
         userVoucherAta: userAta,
         poolVaultAta: poolVaultAta,
+        receiptMint: receiptMint,
+        userReceiptAta: userReceiptAta,
+        poolVaultAuthority: poolVaultAuthority,
       })
       .signers([poolDelegate])
       .rpc();
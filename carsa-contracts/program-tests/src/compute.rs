@@ -0,0 +1,63 @@
+//! Compute-unit measurement: every transaction the harness sends is held to
+//! `DEFAULT_COMPUTE_UNIT_CEILING` per instruction, and `process_metered` returns what a
+//! transaction used so tests can hold an instruction to its own `COMPUTE_UNIT_CEILING`
+//!
+//! Compute is only metered in full on the SBF build. Build it with `cargo build-sbf` and run
+//! the tests with `SBF_OUT_DIR=target/deploy`, which makes `ProgramTest` load the built
+//! program instead of the native processor. Natively only syscalls are metered, so the
+//! ceilings hold trivially
+
+use anchor_lang::solana_program::instruction::Instruction;
+use carsa::instructions::DEFAULT_COMPUTE_UNIT_CEILING;
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::Harness;
+
+/// Whether the harness runs the SBF build, so compute measurements are real
+pub fn metered() -> bool {
+    std::env::var_os("SBF_OUT_DIR").is_some() || std::env::var_os("BPF_OUT_DIR").is_some()
+}
+
+/// Asserts that `name` used at most `ceiling` compute units, printing the measurement so
+/// ceilings can be recalibrated from the test output
+pub fn assert_compute_units(name: &str, consumed: u64, ceiling: u64) {
+    let source = if metered() { "SBF" } else { "native, syscalls only" };
+    println!("{name}: {consumed} of {ceiling} compute units ({source})");
+    assert!(
+        consumed <= ceiling,
+        "{name} used {consumed} compute units, over its ceiling of {ceiling}"
+    );
+}
+
+impl Harness {
+    /// Like `process`, returning the compute units the transaction used
+    pub async fn process_metered(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<u64, BanksClientError> {
+        let mut banks_client = self.context.banks_client.clone();
+        let blockhash = banks_client.get_latest_blockhash().await?;
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let outcome = banks_client.process_transaction_with_metadata(transaction).await?;
+        outcome.result.map_err(BanksClientError::TransactionError)?;
+
+        let consumed = outcome.metadata.map_or(0, |metadata| metadata.compute_units_consumed);
+        assert_compute_units(
+            "transaction",
+            consumed,
+            DEFAULT_COMPUTE_UNIT_CEILING * instructions.len() as u64,
+        );
+        Ok(consumed)
+    }
+}
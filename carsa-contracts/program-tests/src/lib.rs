@@ -0,0 +1,496 @@
+//! Fixtures for running the carsa program in `solana-program-test`, without a validator
+//!
+//! `Harness::start` boots a bank with the program loaded and the Lokal mint initialized.
+//! The other fixtures fund wallets, register merchants and drive the core instructions,
+//! so a new instruction test only has to build the accounts specific to it
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_interface::TokenAccount;
+use carsa::error::CarsaError;
+use carsa::state::*;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::AccountSharedData;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::TransactionError;
+
+mod compute;
+mod pool;
+
+pub use compute::*;
+pub use pool::*;
+
+/// One Lokal token in base units (9 decimals)
+pub const TOKEN: u64 = 1_000_000_000;
+
+/// The classic SPL Token program and Token-2022, for flows that must work on either
+pub const TOKEN_PROGRAMS: [Pubkey; 2] = [spl_token::ID, spl_token_2022::ID];
+
+/// Runs the program natively. Anchor's entry ties the accounts slice to the accounts'
+/// lifetime, which the test runtime's processor signature does not, so the slice is leaked
+fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    carsa::entry(program_id, accounts, data)
+}
+
+/// Address of a carsa PDA
+pub fn pda(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &carsa::ID).0
+}
+
+/// Signer of the program's self-CPI events, required by every `#[event_cpi]` instruction
+pub fn event_authority() -> Pubkey {
+    pda(&[b"__event_authority"])
+}
+
+/// A fresh 32-byte id, for transaction records and yield batches
+pub fn unique_id() -> [u8; 32] {
+    Keypair::new().pubkey().to_bytes()
+}
+
+pub fn merchant_address(owner: &Pubkey) -> Pubkey {
+    pda(&[MERCHANT_SEED, owner.as_ref()])
+}
+
+pub fn transaction_record_address(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> Pubkey {
+    pda(&[TRANSACTION_SEED, merchant_account.as_ref(), customer.as_ref(), transaction_id])
+}
+
+pub fn slim_transaction_record_address(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> Pubkey {
+    pda(&[TRANSACTION_SEED, merchant_account.as_ref(), customer.as_ref(), &transaction_id[..16]])
+}
+
+pub fn transaction_stub_address(
+    merchant_account: &Pubkey,
+    customer: &Pubkey,
+    transaction_id: &[u8; 32],
+) -> Pubkey {
+    pda(&[TRANSACTION_STUB_SEED, merchant_account.as_ref(), customer.as_ref(), transaction_id])
+}
+
+/// `ReferenceIndex` of the purchase recorded at `transaction_record`
+pub fn reference_index_address(transaction_record: &Pubkey) -> Pubkey {
+    pda(&[REFERENCE_INDEX_SEED, &ReferenceIndex::reference_code(transaction_record)])
+}
+
+pub fn transfer_record_address(sender: &Pubkey, transaction_id: &[u8; 32]) -> Pubkey {
+    pda(&[TRANSFER_SEED, sender.as_ref(), transaction_id])
+}
+
+/// Asserts that a transaction failed with `expected`
+pub fn assert_carsa_error(result: Result<(), BanksClientError>, expected: CarsaError) {
+    let name = format!("{expected:?}");
+    let code: u32 = expected.into();
+
+    match result {
+        Ok(()) => panic!("expected {name}, but the transaction succeeded"),
+        Err(error) => match error.unwrap() {
+            TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+                assert_eq!(actual, code, "expected {name}");
+            }
+            other => panic!("expected {name}, got {other:?}"),
+        },
+    }
+}
+
+/// Accounts of a purchase at a merchant with none of the optional features enabled
+pub struct Purchase {
+    pub customer: Pubkey,
+    pub customer_token_account: Pubkey,
+    pub merchant_account: Pubkey,
+    pub merchant_token_account: Pubkey,
+}
+
+/// A bank running the carsa program with the Lokal mint initialized
+pub struct Harness {
+    pub context: ProgramTestContext,
+    pub update_authority: Keypair,
+    pub mint: Pubkey,
+    /// The token program owning the Lokal mint and every token account of the harness
+    pub token_program: Pubkey,
+    pub mint_authority: Pubkey,
+    pub config: Pubkey,
+    pub merchant_registry: Pubkey,
+}
+
+impl Harness {
+    /// Starts the bank and initializes the Lokal mint, config and merchant registry
+    pub async fn start() -> Self {
+        Self::start_with(Self::program_test()).await
+    }
+
+    /// Like `start`, with the Lokal mint owned by `token_program`
+    pub async fn start_with_token_program(token_program: Pubkey) -> Self {
+        Self::boot(Self::program_test(), token_program).await
+    }
+
+    /// A test runtime with only the carsa program, for tests that add programs of their own
+    pub fn program_test() -> ProgramTest {
+        ProgramTest::new("carsa", carsa::ID, processor!(process_instruction))
+    }
+
+    /// Like `start`, on a test runtime built with `program_test`
+    pub async fn start_with(program_test: ProgramTest) -> Self {
+        Self::boot(program_test, spl_token::ID).await
+    }
+
+    async fn boot(program_test: ProgramTest, token_program: Pubkey) -> Self {
+        let mint = Keypair::new();
+
+        let mut harness = Harness {
+            context: program_test.start_with_context().await,
+            update_authority: Keypair::new(),
+            mint: mint.pubkey(),
+            token_program,
+            mint_authority: pda(&[MINT_AUTHORITY_SEED]),
+            config: pda(&[CONFIG_SEED]),
+            merchant_registry: pda(&[MERCHANT_REGISTRY_SEED]),
+        };
+        let update_authority = harness.update_authority.pubkey();
+        harness.fund(&update_authority);
+
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::InitializeLokalMint {
+                update_authority,
+                mint: harness.mint,
+                mint_authority: harness.mint_authority,
+                config: harness.config,
+                merchant_registry: harness.merchant_registry,
+                token_program: harness.token_program,
+                system_program: system_program::ID,
+                rent: anchor_lang::solana_program::sysvar::rent::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::InitializeLokalMint {}.data(),
+        };
+        harness
+            .process(&[instruction], &[&harness.update_authority, &mint])
+            .await
+            .unwrap();
+
+        harness
+    }
+
+    /// Sends `instructions` in one transaction paid by the harness payer, holding it to
+    /// the default compute-unit ceiling of each instruction
+    pub async fn process(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        self.process_metered(instructions, signers).await.map(drop)
+    }
+
+    /// Deserializes the Anchor account at `address`
+    pub async fn account<T: AccountDeserialize>(&self, address: Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .clone()
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Lamports held by `address`, zero once it is closed
+    pub async fn lamports(&self, address: Pubkey) -> u64 {
+        self.context.banks_client.clone().get_balance(address).await.unwrap()
+    }
+
+    pub async fn token_balance(&self, token_account: Pubkey) -> u64 {
+        self.account::<TokenAccount>(token_account).await.amount
+    }
+
+    /// Gives `wallet` 10 SOL
+    pub fn fund(&mut self, wallet: &Pubkey) {
+        self.context.set_account(
+            wallet,
+            &AccountSharedData::new(10 * LAMPORTS_PER_SOL, 0, &system_program::ID),
+        );
+    }
+
+    /// A new wallet holding 10 SOL
+    pub fn funded_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.fund(&user.pubkey());
+        user
+    }
+
+    pub async fn clock(&self) -> Clock {
+        self.context.banks_client.clone().get_sysvar().await.unwrap()
+    }
+
+    /// Moves the bank's clock `seconds` forward, for lockups, cooldowns and expiries
+    /// Also waits for a new blockhash, so retrying a transaction rejected before the warp
+    /// is not dropped as a duplicate
+    pub async fn warp_forward(&mut self, seconds: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+        self.wait_for_new_blockhash().await;
+    }
+
+    /// Waits until the bank moves to a new blockhash, so a transaction identical to one
+    /// already sent gets a new signature instead of failing as `AlreadyProcessed`
+    pub async fn wait_for_new_blockhash(&mut self) {
+        self.context.last_blockhash =
+            self.context.banks_client.get_latest_blockhash().await.unwrap();
+        self.context.get_new_latest_blockhash().await.unwrap();
+    }
+
+    /// Address of `owner`'s associated token account for `mint` under the harness token program
+    pub fn associated_token_address(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    /// Creates `owner`'s associated Lokal token account
+    pub async fn create_token_account(&self, owner: &Pubkey) -> Pubkey {
+        let instruction = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.context.payer.pubkey(),
+            owner,
+            &self.mint,
+            &self.token_program,
+        );
+        self.process(&[instruction], &[]).await.unwrap();
+        self.associated_token_address(owner, &self.mint)
+    }
+
+    pub fn mint_tokens_instruction(&self, authority: &Pubkey, destination: Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::MintLokalTokens {
+                authority: *authority,
+                mint: self.mint,
+                mint_authority: self.mint_authority,
+                config: self.config,
+                destination,
+                token_program: self.token_program,
+                cosigner: None,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::MintLokalTokens { amount }.data(),
+        }
+    }
+
+    /// Mints Lokal tokens to `destination` with the update authority
+    pub async fn mint_tokens(&self, destination: Pubkey, amount: u64) {
+        let instruction =
+            self.mint_tokens_instruction(&self.update_authority.pubkey(), destination, amount);
+        self.process(&[instruction], &[&self.update_authority])
+            .await
+            .unwrap();
+    }
+
+    pub async fn register_merchant_instruction(
+        &self,
+        owner: &Pubkey,
+        name: &str,
+        category: &str,
+        cashback_rate: u16,
+        min_purchase_amount: u64,
+    ) -> Instruction {
+        let registry: MerchantRegistry = self.account(self.merchant_registry).await;
+
+        Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::RegisterMerchant {
+                merchant_owner: *owner,
+                merchant_account: merchant_address(owner),
+                merchant_registry: self.merchant_registry,
+                merchant_index: pda(&[MERCHANT_INDEX_SEED, &registry.merchant_count.to_le_bytes()]),
+                system_program: system_program::ID,
+                parent_merchant: None,
+                parent_owner: None,
+                payout_token_account: Some(self.associated_token_address(owner, &self.mint)),
+                config: self.config,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::RegisterMerchant {
+                name: name.to_string(),
+                category: category.to_string(),
+                cashback_rate,
+                min_purchase_amount,
+            }
+            .data(),
+        }
+    }
+
+    /// Registers an independent merchant owned by `owner` and returns its account
+    pub async fn register_merchant(
+        &self,
+        owner: &Keypair,
+        cashback_rate: u16,
+        min_purchase_amount: u64,
+    ) -> Pubkey {
+        let instruction = self
+            .register_merchant_instruction(
+                &owner.pubkey(),
+                "Warung Test",
+                "retail",
+                cashback_rate,
+                min_purchase_amount,
+            )
+            .await;
+        self.process(&[instruction], &[owner]).await.unwrap();
+        merchant_address(&owner.pubkey())
+    }
+
+    /// Accounts of a `process_purchase`, passing the Associated Token program so a customer
+    /// without a Lokal token account gets one at `purchase.customer_token_account`
+    pub fn process_purchase_accounts(
+        &self,
+        purchase: &Purchase,
+        transaction_id: [u8; 32],
+    ) -> carsa::accounts::ProcessPurchase {
+        let transaction_record = transaction_record_address(
+            &purchase.merchant_account,
+            &purchase.customer,
+            &transaction_id,
+        );
+        carsa::accounts::ProcessPurchase {
+            customer: purchase.customer,
+            merchant_account: purchase.merchant_account,
+            mint: self.mint,
+            mint_authority: self.mint_authority,
+            config: self.config,
+            customer_token_account: purchase.customer_token_account,
+            merchant_token_account: purchase.merchant_token_account,
+            transaction_record: Some(transaction_record),
+            token_program: self.token_program,
+            system_program: system_program::ID,
+            stake_pool: None,
+            customer_stake_record: None,
+            activity_record: None,
+            redemption_activity_record: None,
+            treasury_token_account: None,
+            price_feed: None,
+            reward_escrow: None,
+            reward_lot: None,
+            invoice: None,
+            instructions_sysvar: None,
+            pos_key: None,
+            parent_merchant: None,
+            reward_budget: None,
+            budget_membership: None,
+            reward_budget_escrow: None,
+            associated_token_program: Some(spl_associated_token_account::ID),
+            merchant_operator: None,
+            customer_monthly_stats: None,
+            slim_transaction_record: None,
+            category_boost: None,
+            customer_merchant_stats: None,
+            transaction_stub: Some(transaction_stub_address(
+                &purchase.merchant_account,
+                &purchase.customer,
+                &transaction_id,
+            )),
+            reference_index: Some(reference_index_address(&transaction_record)),
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+    }
+
+    /// Builds a `process_purchase` with the accounts of `process_purchase_accounts`
+    pub fn process_purchase_instruction(
+        &self,
+        purchase: &Purchase,
+        fiat_amount: u64,
+        redeem_token_amount: Option<u64>,
+        transaction_id: [u8; 32],
+    ) -> Instruction {
+        Instruction {
+            program_id: carsa::ID,
+            accounts: self
+                .process_purchase_accounts(purchase, transaction_id)
+                .to_account_metas(None),
+            data: carsa::instruction::ProcessPurchase {
+                fiat_amount,
+                redeem_token_amount,
+                transaction_id,
+                expected_nonce: None,
+            }
+            .data(),
+        }
+    }
+
+    /// Processes a purchase under a fresh transaction id and returns its record
+    pub async fn purchase(
+        &self,
+        customer: &Keypair,
+        purchase: &Purchase,
+        fiat_amount: u64,
+        redeem_token_amount: Option<u64>,
+    ) -> Result<Pubkey, BanksClientError> {
+        let transaction_id = unique_id();
+        let instruction =
+            self.process_purchase_instruction(purchase, fiat_amount, redeem_token_amount, transaction_id);
+        self.process(&[instruction], &[customer]).await?;
+        Ok(transaction_record_address(&purchase.merchant_account, &customer.pubkey(), &transaction_id))
+    }
+
+    /// Transfers tokens under a fresh transaction id and returns the transfer record
+    pub async fn transfer_tokens(
+        &self,
+        sender: &Keypair,
+        sender_token_account: Pubkey,
+        recipient_token_account: Pubkey,
+        amount: u64,
+    ) -> Result<Pubkey, BanksClientError> {
+        let transaction_id = unique_id();
+        let transfer_record = transfer_record_address(&sender.pubkey(), &transaction_id);
+
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::TransferTokens {
+                sender: sender.pubkey(),
+                sender_token_account,
+                recipient_token_account,
+                mint: self.mint,
+                config: self.config,
+                transfer_record,
+                token_program: self.token_program,
+                system_program: system_program::ID,
+                activity_record: None,
+                fee_exemption: None,
+                treasury_token_account: None,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::TransferTokens {
+                amount,
+                transaction_id,
+                memo: "program test".to_string(),
+            }
+            .data(),
+        };
+        self.process(&[instruction], &[sender]).await?;
+        Ok(transfer_record)
+    }
+}
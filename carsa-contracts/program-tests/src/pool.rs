@@ -0,0 +1,614 @@
+//! Voucher pool fixtures: a pool staking the Lokal token, and deposits, yield and
+//! redemptions against it
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    self, get_associated_token_address_with_program_id, spl_associated_token_account,
+};
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{transfer_fee, transfer_hook, ExtensionType};
+use carsa::state::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+
+use crate::{event_authority, pda, unique_id, Harness, TOKEN};
+
+/// A voucher pool and the keys that run it
+pub struct Pool {
+    pub pool_id: u16,
+    pub authority: Keypair,
+    pub delegate: Keypair,
+    pub voucher_mint: Pubkey,
+    pub token_program: Pubkey,
+    pub state: Pubkey,
+    pub vault_authority: Pubkey,
+    pub vault: Pubkey,
+    pub receipt_mint: Pubkey,
+}
+
+impl Pool {
+    pub fn stake_record(&self, user: &Pubkey) -> Pubkey {
+        pda(&[USER_STAKE_SEED, self.state.as_ref(), user.as_ref()])
+    }
+
+    pub fn token_advance(&self, user: &Pubkey) -> Pubkey {
+        pda(&[TOKEN_ADVANCE_SEED, self.state.as_ref(), user.as_ref()])
+    }
+
+    pub fn voucher_account(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.voucher_mint, &self.token_program)
+    }
+
+    pub fn receipt_account(&self, user: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(user, &self.receipt_mint, &self.token_program)
+    }
+}
+
+/// An open pool between 1 and 1,000 tokens per user, with no lockup, fees or tiers
+pub fn pool_config() -> PoolConfig {
+    PoolConfig {
+        min_stake_amount: TOKEN,
+        max_stake_per_user: 1_000 * TOKEN,
+        deposits_enabled: true,
+        withdrawals_enabled: true,
+        apy_basis_points: 0,
+        auto_revoke_delegation: false,
+        lockup_seconds: 0,
+        early_withdraw_penalty_bps: 0,
+        max_deployment_bps: 0,
+        max_total_staked: 0,
+        tier_thresholds: [0; 3],
+        tier_multipliers_bps: [0; 3],
+        withdraw_cooldown_seconds: 0,
+        whitelist_enabled: false,
+        performance_fee_bps: 0,
+        fee_recipient: Pubkey::default(),
+        guaranteed_yield_budget: 0,
+        queued_withdrawals: false,
+        unstake_delay_seconds: 0,
+        record_history: false,
+        insurance_fee_bps: 0,
+    }
+}
+
+impl Harness {
+    /// Initializes pool `pool_id` over the Lokal mint with a new authority and delegate
+    pub async fn initialize_pool(&mut self, pool_id: u16, config: PoolConfig) -> Pool {
+        self.try_initialize_pool(pool_id, config).await.unwrap()
+    }
+
+    /// Like `initialize_pool`, returning the error when the program rejects `config`
+    pub async fn try_initialize_pool(
+        &mut self,
+        pool_id: u16,
+        config: PoolConfig,
+    ) -> Result<Pool, BanksClientError> {
+        self.try_initialize_pool_with_mint(pool_id, config, self.mint).await
+    }
+
+    /// Initializes pool `pool_id` staking `voucher_mint` instead of the Lokal mint
+    pub async fn initialize_pool_with_mint(
+        &mut self,
+        pool_id: u16,
+        config: PoolConfig,
+        voucher_mint: Pubkey,
+    ) -> Pool {
+        self.try_initialize_pool_with_mint(pool_id, config, voucher_mint).await.unwrap()
+    }
+
+    /// Like `initialize_pool_with_mint`, returning the error when the program rejects the
+    /// mint or `config`
+    pub async fn try_initialize_pool_with_mint(
+        &mut self,
+        pool_id: u16,
+        config: PoolConfig,
+        voucher_mint: Pubkey,
+    ) -> Result<Pool, BanksClientError> {
+        let pool_seed = pool_id_seed(pool_id);
+        let vault_authority =
+            pda(&[POOL_VAULT_AUTHORITY_SEED, voucher_mint.as_ref(), &pool_seed]);
+        let pool = Pool {
+            pool_id,
+            authority: self.funded_user(),
+            delegate: self.funded_user(),
+            voucher_mint,
+            token_program: self.token_program,
+            state: pda(&[POOL_STATE_SEED, voucher_mint.as_ref(), &pool_seed]),
+            vault_authority,
+            vault: self.associated_token_address(&vault_authority, &voucher_mint),
+            receipt_mint: pda(&[RECEIPT_MINT_SEED, voucher_mint.as_ref(), &pool_seed]),
+        };
+
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::InitializePool {
+                pool_authority: pool.authority.pubkey(),
+                pool_delegate: pool.delegate.pubkey(),
+                pool_state: pool.state,
+                pool_vault_authority: pool.vault_authority,
+                voucher_mint: pool.voucher_mint,
+                vault_ata: pool.vault,
+                receipt_mint: pool.receipt_mint,
+                system_program: system_program::ID,
+                token_program: pool.token_program,
+                associated_token_program: associated_token::ID,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::InitializePool { pool_id, config }.data(),
+        };
+        self.process(&[instruction], &[&pool.authority]).await?;
+
+        Ok(pool)
+    }
+
+    /// Creates a voucher mint other than the Lokal mint, as a regional token would be, with
+    /// the payer as its mint authority
+    pub async fn create_voucher_mint(&self) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.context.payer.pubkey();
+        let rent = self.context.banks_client.clone().get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token_2022::state::Mint::LEN),
+                spl_token_2022::state::Mint::LEN as u64,
+                &self.token_program,
+            ),
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                9,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Like `create_voucher_mint`, with `extension` initialized on the mint first. Needs a
+    /// harness started on Token-2022
+    pub async fn create_voucher_mint_with_extension(&self, extension: ExtensionType) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.context.payer.pubkey();
+        let rent = self.context.banks_client.clone().get_rent().await.unwrap();
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[extension])
+                .unwrap();
+        let initialize_extension = match extension {
+            ExtensionType::TransferFeeConfig => {
+                transfer_fee::instruction::initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    Some(&payer),
+                    Some(&payer),
+                    100,
+                    u64::MAX,
+                )
+            }
+            ExtensionType::TransferHook => transfer_hook::instruction::initialize(
+                &self.token_program,
+                &mint.pubkey(),
+                Some(payer),
+                Some(Pubkey::new_unique()),
+            ),
+            ExtensionType::PermanentDelegate => {
+                spl_token_2022::instruction::initialize_permanent_delegate(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    &payer,
+                )
+            }
+            _ => unimplemented!("{extension:?}"),
+        }
+        .unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &self.token_program,
+            ),
+            initialize_extension,
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                9,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Creates `owner`'s associated account of a mint from `create_voucher_mint` and mints
+    /// `amount` to it
+    pub async fn mint_voucher_tokens(&self, voucher_mint: &Pubkey, owner: &Pubkey, amount: u64) {
+        let payer = self.context.payer.pubkey();
+        let token_account = self.associated_token_address(owner, voucher_mint);
+        let instructions = [
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                owner,
+                voucher_mint,
+                &self.token_program,
+            ),
+            spl_token_2022::instruction::mint_to(
+                &self.token_program,
+                voucher_mint,
+                &token_account,
+                &payer,
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[]).await.unwrap();
+    }
+
+    /// Approves `delegate` to move `amount` of the user's vouchers
+    pub fn approve_delegate_instruction(
+        &self,
+        pool: &Pool,
+        user: &Pubkey,
+        delegate: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        spl_token_2022::instruction::approve(
+            &pool.token_program,
+            &pool.voucher_account(user),
+            delegate,
+            user,
+            &[],
+            amount,
+        )
+        .unwrap()
+    }
+
+    pub fn deposit_voucher_instruction(
+        &self,
+        pool: &Pool,
+        user: &Pubkey,
+        delegate: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::DepositVoucher {
+                user: *user,
+                pool_delegate: *delegate,
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(user),
+                stake_whitelist_entry: None,
+                stake_action_record: None,
+                user_voucher_ata: pool.voucher_account(user),
+                pool_vault_ata: pool.vault,
+                voucher_mint: pool.voucher_mint,
+                receipt_mint: pool.receipt_mint,
+                user_receipt_ata: pool.receipt_account(user),
+                pool_vault_authority: pool.vault_authority,
+                system_program: system_program::ID,
+                token_program: pool.token_program,
+                associated_token_program: associated_token::ID,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::DepositVoucher { amount }.data(),
+        }
+    }
+
+    /// Approves the pool delegate and deposits `amount` in one transaction, as the backend does
+    pub async fn deposit_voucher(
+        &self,
+        pool: &Pool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let delegate = pool.delegate.pubkey();
+        let instructions = [
+            self.approve_delegate_instruction(pool, &user.pubkey(), &delegate, amount),
+            self.deposit_voucher_instruction(pool, &user.pubkey(), &delegate, amount),
+        ];
+        self.process(&instructions, &[user, &pool.delegate]).await
+    }
+
+    /// Stakes `amount` from the funder's own voucher account into the beneficiary's stake
+    pub async fn deposit_for_beneficiary(
+        &self,
+        pool: &Pool,
+        funder: &Keypair,
+        beneficiary: &Pubkey,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::DepositForBeneficiary {
+                funder: funder.pubkey(),
+                beneficiary: *beneficiary,
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(beneficiary),
+                stake_whitelist_entry: None,
+                stake_action_record: None,
+                funder_voucher_ata: pool.voucher_account(&funder.pubkey()),
+                pool_vault_ata: pool.vault,
+                voucher_mint: pool.voucher_mint,
+                receipt_mint: pool.receipt_mint,
+                beneficiary_receipt_ata: pool.receipt_account(beneficiary),
+                pool_vault_authority: pool.vault_authority,
+                system_program: system_program::ID,
+                token_program: pool.token_program,
+                associated_token_program: associated_token::ID,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::DepositForBeneficiary { amount }.data(),
+        };
+        self.process(&[instruction], &[funder]).await
+    }
+
+    /// Records `sol_amount` of yield under a fresh batch id, at the next journal position
+    pub async fn record_yield(
+        &self,
+        pool: &Pool,
+        sol_amount: u64,
+        expected_previous_index: Option<u128>,
+    ) -> Result<(), BanksClientError> {
+        let state: PoolState = self.account(pool.state).await;
+        let external_sequence = state.last_external_sequence + 1;
+        self.record_yield_at(pool, sol_amount, expected_previous_index, external_sequence)
+            .await
+    }
+
+    /// Like `record_yield`, at journal position `external_sequence`
+    pub async fn record_yield_at(
+        &self,
+        pool: &Pool,
+        sol_amount: u64,
+        expected_previous_index: Option<u128>,
+        external_sequence: u64,
+    ) -> Result<(), BanksClientError> {
+        let yield_batch_id = unique_id();
+
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::RecordYield {
+                pool_delegate: pool.delegate.pubkey(),
+                pool_state: pool.state,
+                yield_batch: pda(&[YIELD_BATCH_SEED, pool.state.as_ref(), &yield_batch_id]),
+                system_program: system_program::ID,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::RecordYield {
+                sol_amount,
+                yield_batch_id,
+                expected_previous_index,
+                external_sequence,
+            }
+            .data(),
+        };
+        self.process(&[instruction], &[&pool.delegate]).await
+    }
+
+    /// Resets the pool's yield journal position, signed by the pool delegate
+    pub async fn reset_external_sequence(
+        &self,
+        pool: &Pool,
+        last_external_sequence: u64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::ResetExternalSequence {
+                pool_delegate: pool.delegate.pubkey(),
+                pool_state: pool.state,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::ResetExternalSequence { last_external_sequence }.data(),
+        };
+        self.process(&[instruction], &[&pool.delegate]).await
+    }
+
+    pub fn redeem_voucher_instruction(&self, pool: &Pool, user: &Pubkey, amount: u64) -> Instruction {
+        Instruction {
+            program_id: carsa::ID,
+            accounts: self.redeem_voucher_accounts(pool, user).to_account_metas(None),
+            data: carsa::instruction::RedeemVoucher { amount }.data(),
+        }
+    }
+
+    /// Redeems the user's whole stake, whatever it has become
+    pub fn redeem_all_vouchers_instruction(&self, pool: &Pool, user: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: carsa::ID,
+            accounts: self.redeem_voucher_accounts(pool, user).to_account_metas(None),
+            data: carsa::instruction::RedeemAllVouchers {}.data(),
+        }
+    }
+
+    fn redeem_voucher_accounts(&self, pool: &Pool, user: &Pubkey) -> carsa::accounts::RedeemVoucher {
+        carsa::accounts::RedeemVoucher {
+            user: *user,
+            pool_state: pool.state,
+            user_stake_record: pool.stake_record(user),
+            stake_action_record: None,
+            user_voucher_ata: pool.voucher_account(user),
+            pool_vault_ata: pool.vault,
+            voucher_mint: pool.voucher_mint,
+            pool_vault_authority: pool.vault_authority,
+            receipt_mint: pool.receipt_mint,
+            user_receipt_ata: pool.receipt_account(user),
+            system_program: system_program::ID,
+            token_program: pool.token_program,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+    }
+
+    pub async fn redeem_voucher(
+        &self,
+        pool: &Pool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = self.redeem_voucher_instruction(pool, &user.pubkey(), amount);
+        self.process(&[instruction], &[user]).await
+    }
+
+    /// Sets the pool's advance LTV, signed by the pool authority
+    pub async fn set_advance_ltv(
+        &self,
+        pool: &Pool,
+        advance_ltv_bps: u16,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::SetAdvanceLtv {
+                pool_authority: pool.authority.pubkey(),
+                pool_state: pool.state,
+                config: self.config,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::SetAdvanceLtv { advance_ltv_bps }.data(),
+        };
+        self.process(&[instruction], &[&pool.authority]).await
+    }
+
+    /// Borrows `amount` Lokal tokens against the user's stake into their voucher account
+    pub async fn borrow_against_stake(
+        &self,
+        pool: &Pool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::BorrowAgainstStake {
+                user: user.pubkey(),
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(&user.pubkey()),
+                token_advance: pool.token_advance(&user.pubkey()),
+                mint: self.mint,
+                mint_authority: self.mint_authority,
+                config: self.config,
+                user_token_account: pool.voucher_account(&user.pubkey()),
+                token_program: self.token_program,
+                system_program: system_program::ID,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::BorrowAgainstStake { amount }.data(),
+        };
+        self.process(&[instruction], &[user]).await
+    }
+
+    /// Repays `amount` of the user's advance from their voucher account
+    pub async fn repay_advance(
+        &self,
+        pool: &Pool,
+        user: &Keypair,
+        amount: u64,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::RepayAdvance {
+                user: user.pubkey(),
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(&user.pubkey()),
+                token_advance: pool.token_advance(&user.pubkey()),
+                mint: self.mint,
+                config: self.config,
+                user_token_account: pool.voucher_account(&user.pubkey()),
+                token_program: self.token_program,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::RepayAdvance { amount }.data(),
+        };
+        self.process(&[instruction], &[user]).await
+    }
+
+    /// Closes the user's stake record, signed by `authority`, sending rent to `rent_receiver`
+    pub async fn close_stake_record(
+        &self,
+        pool: &Pool,
+        user: &Pubkey,
+        authority: &Keypair,
+        rent_receiver: Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::CloseStakeRecord {
+                authority: authority.pubkey(),
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(user),
+                rent_receiver,
+                event_authority: event_authority(),
+                program: carsa::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::CloseStakeRecord {}.data(),
+        };
+        self.process(&[instruction], &[authority]).await
+    }
+
+    /// Batch-closes the stake records of `users`, signed by the pool delegate
+    pub async fn close_stake_records(
+        &self,
+        pool: &Pool,
+        users: &[Pubkey],
+    ) -> Result<(), BanksClientError> {
+        let mut accounts = carsa::accounts::CloseStakeRecords {
+            pool_delegate: pool.delegate.pubkey(),
+            pool_state: pool.state,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None);
+        accounts.extend(users.iter().map(|user| AccountMeta::new(pool.stake_record(user), false)));
+
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts,
+            data: carsa::instruction::CloseStakeRecords {}.data(),
+        };
+        self.process(&[instruction], &[&pool.delegate]).await
+    }
+
+    /// Refreshes the user's cached stake view, signed by the payer alone
+    pub async fn sync_stake_view(
+        &self,
+        pool: &Pool,
+        user: &Pubkey,
+    ) -> Result<(), BanksClientError> {
+        let instruction = Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::SyncStakeView {
+                pool_state: pool.state,
+                user_stake_record: pool.stake_record(user),
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::SyncStakeView {}.data(),
+        };
+        self.process(&[instruction], &[]).await
+    }
+}
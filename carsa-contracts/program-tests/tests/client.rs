@@ -0,0 +1,268 @@
+//! Round trips through the carsa-client crate: instructions built by the client run against
+//! the program, and the accounts they write decode with the client's types
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::system_program;
+use anchor_spl::associated_token;
+use carsa_client::purchase::{find_purchase_by_reference, format_reference_code, reference_code};
+use carsa_client::{accounts, instructions, pda, state, CarsaAccount};
+use carsa_program_tests::*;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+/// Decodes the account at `address` with the client's mirror of its type
+async fn client_account<T: CarsaAccount>(harness: &Harness, address: Pubkey) -> T {
+    let account = harness
+        .context
+        .banks_client
+        .clone()
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap_or_else(|| panic!("account {address} does not exist"));
+    T::try_deserialize(&account.data).unwrap()
+}
+
+#[tokio::test]
+async fn registers_merchant_and_purchases_with_client_builders() {
+    let mut harness = Harness::start().await;
+    let owner = harness.funded_user();
+    let customer = harness.funded_user();
+
+    let (merchant_account, _) = pda::find_merchant_pda(&owner.pubkey());
+    let register = instructions::register_merchant(
+        &accounts::RegisterMerchant {
+            merchant_owner: owner.pubkey(),
+            merchant_account,
+            merchant_registry: pda::find_merchant_registry_pda().0,
+            merchant_index: pda::find_merchant_index_pda(0).0,
+            system_program: system_program::ID,
+            parent_merchant: None,
+            parent_owner: None,
+            payout_token_account: None,
+            config: pda::find_config_pda().0,
+        },
+        "Warung Klien",
+        "retail",
+        500,
+        0,
+    );
+    harness.process(&[register], &[&owner]).await.unwrap();
+
+    let merchant: state::MerchantAccount = client_account(&harness, merchant_account).await;
+    assert_eq!(merchant.merchant_wallet, owner.pubkey());
+    assert!(merchant.name.starts_with(b"Warung Klien"));
+    assert_eq!(merchant.cashback_rate, 500);
+    let index: state::MerchantIndex =
+        client_account(&harness, pda::find_merchant_index_pda(0).0).await;
+    assert_eq!(index.merchant, merchant_account);
+
+    let customer_token_account = harness.create_token_account(&customer.pubkey()).await;
+    let merchant_token_account = harness.create_token_account(&owner.pubkey()).await;
+    let transaction_id = unique_id();
+    let (transaction_record, _) = pda::find_transaction_pda(&merchant_account, &customer.pubkey(), &transaction_id);
+    let (transaction_stub, _) =
+        pda::find_transaction_stub_pda(&merchant_account, &customer.pubkey(), &transaction_id);
+    let (reference_index, _) =
+        pda::find_reference_index_pda(&reference_code(&transaction_record));
+    let purchase = instructions::process_purchase(
+        &accounts::ProcessPurchase {
+            customer: customer.pubkey(),
+            merchant_account,
+            mint: harness.mint,
+            mint_authority: pda::find_mint_authority_pda().0,
+            config: pda::find_config_pda().0,
+            customer_token_account,
+            merchant_token_account,
+            transaction_record: Some(transaction_record),
+            token_program: harness.token_program,
+            system_program: system_program::ID,
+            stake_pool: None,
+            customer_stake_record: None,
+            activity_record: None,
+            redemption_activity_record: None,
+            treasury_token_account: None,
+            price_feed: None,
+            reward_escrow: None,
+            reward_lot: None,
+            invoice: None,
+            instructions_sysvar: None,
+            pos_key: None,
+            parent_merchant: None,
+            reward_budget: None,
+            budget_membership: None,
+            reward_budget_escrow: None,
+            associated_token_program: None,
+            merchant_operator: None,
+            customer_monthly_stats: None,
+            slim_transaction_record: None,
+            category_boost: None,
+            customer_merchant_stats: None,
+            transaction_stub: Some(transaction_stub),
+            reference_index: Some(reference_index),
+        },
+        100_000,
+        None,
+        &transaction_id,
+        None,
+    );
+    harness.process(&[purchase], &[&customer]).await.unwrap();
+
+    assert_eq!(
+        harness.token_balance(customer_token_account).await,
+        5 * TOKEN
+    );
+    let record: state::PurchaseTransaction = client_account(&harness, transaction_record).await;
+    assert_eq!(record.customer, customer.pubkey());
+    assert_eq!(record.reward_amount, 5 * TOKEN);
+
+    // Support finds the purchase again from the code the customer reads out
+    let code = format_reference_code(&record.reference_code);
+    let (found, _) = find_purchase_by_reference(&code.to_lowercase()).unwrap();
+    assert_eq!(found, reference_index);
+    let index: state::ReferenceIndex = client_account(&harness, found).await;
+    assert_eq!(index.reference_code, record.reference_code);
+    assert_eq!(index.transaction_record, transaction_record);
+    assert_eq!(index.transaction_stub, transaction_stub);
+    let config: state::LokalMintConfig = client_account(&harness, pda::find_config_pda().0).await;
+    assert_eq!(config.mint, harness.mint);
+}
+
+#[tokio::test]
+async fn deposits_into_pool_with_client_builders() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(2, pool_config()).await;
+    let user = harness.funded_user();
+    let voucher_account = harness.create_token_account(&user.pubkey()).await;
+    harness.mint_tokens(voucher_account, 10 * TOKEN).await;
+
+    let (pool_state, _) = pda::find_pool_state_pda(&harness.mint, 2);
+    let (pool_vault_authority, _) = pda::find_pool_vault_authority_pda(&harness.mint, 2);
+    let (receipt_mint, _) = pda::find_receipt_mint_pda(&harness.mint, 2);
+    assert_eq!(
+        (pool_state, pool_vault_authority, receipt_mint),
+        (pool.state, pool.vault_authority, pool.receipt_mint)
+    );
+
+    let (user_stake_record, _) = pda::find_user_stake_pda(&pool_state, &user.pubkey());
+    let deposit = instructions::deposit_voucher(
+        &accounts::DepositVoucher {
+            user: user.pubkey(),
+            pool_delegate: pool.delegate.pubkey(),
+            pool_state,
+            user_stake_record,
+            stake_whitelist_entry: None,
+            stake_action_record: None,
+            user_voucher_ata: voucher_account,
+            pool_vault_ata: harness.associated_token_address(&pool_vault_authority, &harness.mint),
+            voucher_mint: harness.mint,
+            receipt_mint,
+            user_receipt_ata: harness.associated_token_address(&user.pubkey(), &receipt_mint),
+            pool_vault_authority,
+            system_program: system_program::ID,
+            token_program: harness.token_program,
+            associated_token_program: associated_token::ID,
+        },
+        4 * TOKEN,
+    );
+    let approve = harness.approve_delegate_instruction(
+        &pool,
+        &user.pubkey(),
+        &pool.delegate.pubkey(),
+        4 * TOKEN,
+    );
+    harness
+        .process(&[approve, deposit], &[&user, &pool.delegate])
+        .await
+        .unwrap();
+
+    let stake: state::UserStakeRecord = client_account(&harness, user_stake_record).await;
+    assert_eq!(stake.user, user.pubkey());
+    assert_eq!(stake.staked_amount, 4 * TOKEN);
+    let state: state::PoolState = client_account(&harness, pool_state).await;
+    assert_eq!(state.pool_id, 2);
+    assert_eq!(state.total_voucher_staked, 4 * TOKEN);
+}
+
+/// Simulates `get_program_status` for `pool` and decodes its return data with the client
+async fn program_status(harness: &Harness, pool: &Pool) -> state::ProgramStatus {
+    let instruction = instructions::get_program_status(&accounts::GetProgramStatus {
+        config: pda::find_config_pda().0,
+        mint: harness.mint,
+        pool_state: pool.state,
+        vault_ata: pool.vault,
+    });
+    let mut banks_client = harness.context.banks_client.clone();
+    let blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&harness.context.payer.pubkey()),
+        &[&harness.context.payer],
+        blockhash,
+    );
+    let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, carsa::ID);
+    state::ProgramStatus::from_return_data(&return_data.data).unwrap()
+}
+
+#[tokio::test]
+async fn program_status_tracks_supply_caps_pauses_and_vault() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(0, pool_config()).await;
+    let authority = harness.update_authority.pubkey();
+    let config = pda::find_config_pda().0;
+
+    let status = program_status(&harness, &pool).await;
+    assert_eq!(status.version, state::ProgramStatus::VERSION);
+    assert!(!status.paused);
+    assert_eq!((status.mint_supply, status.tracked_supply), (0, 0));
+    assert_eq!(status.admin_mint_headroom, u64::MAX);
+    assert_eq!(status.pool, pool.state);
+    assert!(status.deposits_enabled && !status.deposits_paused);
+    assert_eq!((status.vault_balance, status.total_staked, status.vault_delta), (0, 0, 0));
+
+    let user = harness.funded_user();
+    let voucher_account = harness.create_token_account(&user.pubkey()).await;
+    harness.mint_tokens(voucher_account, 10 * TOKEN).await;
+    harness.deposit_voucher(&pool, &user, 4 * TOKEN).await.unwrap();
+    let status = program_status(&harness, &pool).await;
+    assert_eq!((status.mint_supply, status.tracked_supply), (10 * TOKEN, 10 * TOKEN));
+    assert_eq!((status.vault_balance, status.total_staked), (4 * TOKEN, 4 * TOKEN));
+    assert_eq!(status.vault_delta, 0);
+
+    // Tokens sent straight to the vault show up as a surplus over the staked total
+    harness.mint_tokens(pool.vault, TOKEN).await;
+    let set_caps = instructions::set_mint_caps(
+        &accounts::SetMintCaps { authority, config },
+        20 * TOKEN,
+        0,
+    );
+    harness.process(&[set_caps], &[&harness.update_authority]).await.unwrap();
+    let status = program_status(&harness, &pool).await;
+    assert_eq!(status.vault_delta, TOKEN as i64);
+    assert_eq!(status.admin_mint_headroom, 9 * TOKEN);
+    assert_eq!(status.reward_mint_headroom, u64::MAX);
+
+    let pause_pool = instructions::set_pool_pause(
+        &accounts::SetPoolPause {
+            authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            config,
+        },
+        None,
+        Some(true),
+    );
+    let pause =
+        instructions::set_global_pause(&accounts::SetGlobalPause { authority, config }, true);
+    harness
+        .process(&[pause_pool, pause], &[&pool.authority, &harness.update_authority])
+        .await
+        .unwrap();
+    harness.warp_forward(600).await;
+    let status = program_status(&harness, &pool).await;
+    assert!(status.paused);
+    assert!(!status.deposits_paused && status.withdrawals_paused);
+    assert!(status.yield_update_age_seconds >= 600);
+}
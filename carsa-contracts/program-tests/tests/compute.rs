@@ -0,0 +1,51 @@
+//! Compute-unit ceilings of the hottest instructions, read from the `COMPUTE_UNIT_CEILING`
+//! next to each handler
+//!
+//! Every other instruction the suite sends is held to `DEFAULT_COMPUTE_UNIT_CEILING` by the
+//! harness itself. The numbers only mean something on the SBF build; see the `compute`
+//! module of the harness
+
+use carsa::instructions::{DepositVoucher, ProcessPurchase, RedeemVoucher};
+use carsa_program_tests::*;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn process_purchase_stays_under_its_ceiling() {
+    let mut harness = Harness::start().await;
+    let merchant_owner = harness.funded_user();
+    let customer = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+
+    let instruction = harness.process_purchase_instruction(&purchase, 100_000, None, unique_id());
+    let consumed = harness.process_metered(&[instruction], &[&customer]).await.unwrap();
+    assert_compute_units("process_purchase", consumed, ProcessPurchase::COMPUTE_UNIT_CEILING);
+}
+
+#[tokio::test]
+async fn deposit_and_redeem_voucher_stay_under_their_ceilings() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(1, pool_config()).await;
+    let user = harness.funded_user();
+    let voucher_account = harness.create_token_account(&user.pubkey()).await;
+    harness.mint_tokens(voucher_account, 100 * TOKEN).await;
+
+    // Approved on its own so the deposit is measured alone
+    let delegate = pool.delegate.pubkey();
+    let approve =
+        harness.approve_delegate_instruction(&pool, &user.pubkey(), &delegate, 40 * TOKEN);
+    harness.process(&[approve], &[&user]).await.unwrap();
+
+    let deposit = harness.deposit_voucher_instruction(&pool, &user.pubkey(), &delegate, 40 * TOKEN);
+    let consumed = harness.process_metered(&[deposit], &[&pool.delegate]).await.unwrap();
+    assert_compute_units("deposit_voucher", consumed, DepositVoucher::COMPUTE_UNIT_CEILING);
+
+    let redeem = harness.redeem_voucher_instruction(&pool, &user.pubkey(), 40 * TOKEN);
+    let consumed = harness.process_metered(&[redeem], &[&user]).await.unwrap();
+    assert_compute_units("redeem_voucher", consumed, RedeemVoucher::COMPUTE_UNIT_CEILING);
+}
@@ -0,0 +1,136 @@
+//! Carsa called over CPI by the example carsa-caller program: a signer passed through
+//! to `mint_lokal_tokens`, and a PDA escrow signing `process_purchase` with its seeds
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_caller::ESCROW_SEED;
+use carsa_program_tests::*;
+use solana_program_test::processor;
+use solana_sdk::signature::Signer;
+
+/// Runs the caller natively, leaking the accounts slice as `process_instruction` does for carsa
+fn process_caller(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    carsa_caller::entry(program_id, accounts, data)
+}
+
+async fn start() -> Harness {
+    let mut program_test = Harness::program_test();
+    program_test.add_program("carsa_caller", carsa_caller::ID, processor!(process_caller));
+    Harness::start_with(program_test).await
+}
+
+fn escrow_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[ESCROW_SEED, owner.as_ref()], &carsa_caller::ID).0
+}
+
+fn mint_through_carsa_instruction(
+    harness: &Harness,
+    authority: &Pubkey,
+    destination: Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: carsa_caller::ID,
+        accounts: carsa_caller::accounts::MintThroughCarsa {
+            authority: *authority,
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            config: harness.config,
+            destination,
+            token_program: harness.token_program,
+            carsa_event_authority: event_authority(),
+            carsa_program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa_caller::instruction::MintThroughCarsa { amount }.data(),
+    }
+}
+
+#[tokio::test]
+async fn mints_through_cpi() {
+    let mut harness = start().await;
+    let user = harness.funded_user();
+    let destination = harness.create_token_account(&user.pubkey()).await;
+
+    let update_authority = harness.update_authority.pubkey();
+    let instruction =
+        mint_through_carsa_instruction(&harness, &update_authority, destination, 7 * TOKEN);
+    harness
+        .process(&[instruction], &[&harness.update_authority])
+        .await
+        .unwrap();
+    assert_eq!(harness.token_balance(destination).await, 7 * TOKEN);
+
+    // Carsa still checks the signer the caller passes through
+    let instruction = mint_through_carsa_instruction(&harness, &user.pubkey(), destination, TOKEN);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&user]).await,
+        CarsaError::UpdateAuthorityMismatch,
+    );
+}
+
+#[tokio::test]
+async fn purchases_from_escrow_through_cpi() {
+    let mut harness = start().await;
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let merchant_token_account = harness.create_token_account(&merchant_owner.pubkey()).await;
+
+    // The escrow is a system account of the caller, funded to pay for the purchase record
+    let owner = harness.funded_user();
+    let escrow = escrow_address(&owner.pubkey());
+    harness.fund(&escrow);
+    let escrow_token_account = harness.create_token_account(&escrow).await;
+    harness.mint_tokens(escrow_token_account, 10 * TOKEN).await;
+
+    let transaction_id = unique_id();
+    let transaction_record = transaction_record_address(&merchant_account, &escrow, &transaction_id);
+    let instruction = Instruction {
+        program_id: carsa_caller::ID,
+        accounts: carsa_caller::accounts::PurchaseFromEscrow {
+            owner: owner.pubkey(),
+            escrow,
+            merchant_account,
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            config: harness.config,
+            escrow_token_account,
+            merchant_token_account,
+            transaction_record,
+            transaction_stub: transaction_stub_address(&merchant_account, &escrow, &transaction_id),
+            reference_index: reference_index_address(&transaction_record),
+            token_program: harness.token_program,
+            system_program: system_program::ID,
+            carsa_event_authority: event_authority(),
+            carsa_program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa_caller::instruction::PurchaseFromEscrow {
+            fiat_amount: 50_000,
+            redeem_token_amount: Some(10 * TOKEN),
+            transaction_id,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[&owner]).await.unwrap();
+
+    // 10 tokens are worth 10,000 IDR, so the cashback is 5% of 60,000 IDR
+    let reward = 3 * TOKEN;
+    assert_eq!(harness.token_balance(escrow_token_account).await, reward);
+    assert_eq!(
+        harness.token_balance(merchant_token_account).await,
+        10 * TOKEN
+    );
+
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!(record.customer, escrow);
+    assert_eq!(record.merchant, merchant_account);
+    assert!(record.used_tokens);
+    assert_eq!(record.reward_amount, reward);
+}
@@ -0,0 +1,288 @@
+//! Each of the specific error codes split out of `InvalidAmount` and friends, triggered
+//! through the instruction that raises it, the decimals and frozen-account checks on
+//! Lokal tokens, and the mint extensions a pool refuses
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::ExtensionType;
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn initialize_pool_rejects_each_invalid_setting() {
+    let mut harness = Harness::start().await;
+
+    let cases = [
+        (PoolConfig { min_stake_amount: 0, ..pool_config() }, CarsaError::InvalidPoolLimits),
+        (PoolConfig { max_stake_per_user: TOKEN / 2, ..pool_config() }, CarsaError::InvalidPoolLimits),
+        (PoolConfig { apy_basis_points: 10_001, ..pool_config() }, CarsaError::InvalidApyConfig),
+        (PoolConfig { early_withdraw_penalty_bps: 10_001, ..pool_config() }, CarsaError::InvalidPenaltyConfig),
+        (PoolConfig { max_deployment_bps: 10_001, ..pool_config() }, CarsaError::InvalidDeploymentCap),
+        (PoolConfig { insurance_fee_bps: 10_000, ..pool_config() }, CarsaError::InvalidInsuranceFee),
+    ];
+    for (config, expected) in cases {
+        let result = harness.try_initialize_pool(1, config).await.map(|_| ());
+        assert_carsa_error(result, expected);
+    }
+}
+
+#[tokio::test]
+async fn update_pool_config_rejects_limits_that_cross() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(1, pool_config()).await;
+
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::UpdatePoolConfig {
+            pool_authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::UpdatePoolConfig {
+            update: PoolConfigUpdate {
+                min_stake_amount: Some(2_000 * TOKEN),
+                ..Default::default()
+            },
+        }
+        .data(),
+    };
+    assert_carsa_error(
+        harness.process(&[instruction], &[&pool.authority]).await,
+        CarsaError::InvalidPoolLimits,
+    );
+}
+
+#[tokio::test]
+async fn deposit_below_minimum_is_rejected() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(1, pool_config()).await;
+    let user = harness.funded_user();
+    let voucher_account = harness.create_token_account(&user.pubkey()).await;
+    harness.mint_tokens(voucher_account, 10 * TOKEN).await;
+
+    assert_carsa_error(
+        harness.deposit_voucher(&pool, &user, TOKEN - 1).await,
+        CarsaError::StakeBelowMinimum,
+    );
+}
+
+#[tokio::test]
+async fn returning_more_than_was_deployed_is_rejected() {
+    let mut harness = Harness::start().await;
+    let pool = harness.initialize_pool(1, pool_config()).await;
+    let source = harness.create_token_account(&pool.delegate.pubkey()).await;
+    harness.mint_tokens(source, 10 * TOKEN).await;
+
+    // Nothing has been deployed, so any return is too much
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ReturnPrincipal {
+            pool_delegate: pool.delegate.pubkey(),
+            pool_state: pool.state,
+            source,
+            pool_vault_ata: pool.vault,
+            voucher_mint: pool.voucher_mint,
+            token_program: pool.token_program,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ReturnPrincipal { amount: TOKEN }.data(),
+    };
+    assert_carsa_error(
+        harness.process(&[instruction], &[&pool.delegate]).await,
+        CarsaError::ReturnExceedsDeployed,
+    );
+}
+
+#[tokio::test]
+async fn staker_boost_above_full_cashback_is_rejected() {
+    let harness = Harness::start().await;
+
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetStakerBoost {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetStakerBoost {
+            staker_boost_threshold: TOKEN,
+            staker_boost_bps: 10_001,
+        }
+        .data(),
+    };
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::InvalidStakerBoost,
+    );
+}
+
+#[tokio::test]
+async fn initialize_pool_rejects_mints_with_unsafe_extensions() {
+    let mut harness = Harness::start_with_token_program(spl_token_2022::ID).await;
+
+    let extensions = [
+        ExtensionType::TransferFeeConfig,
+        ExtensionType::TransferHook,
+        ExtensionType::PermanentDelegate,
+    ];
+    for extension in extensions {
+        let voucher_mint = harness.create_voucher_mint_with_extension(extension).await;
+        let result = harness
+            .try_initialize_pool_with_mint(1, pool_config(), voucher_mint)
+            .await
+            .map(|_| ());
+        assert_carsa_error(result, CarsaError::UnsupportedMintExtension);
+    }
+}
+
+#[tokio::test]
+async fn transfer_memo_over_64_bytes_is_rejected() {
+    let mut harness = Harness::start().await;
+    let sender = harness.funded_user();
+    let recipient = harness.funded_user();
+    let sender_token_account = harness.create_token_account(&sender.pubkey()).await;
+    let recipient_token_account = harness.create_token_account(&recipient.pubkey()).await;
+    harness.mint_tokens(sender_token_account, 10 * TOKEN).await;
+
+    let transaction_id = unique_id();
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::TransferTokens {
+            sender: sender.pubkey(),
+            sender_token_account,
+            recipient_token_account,
+            mint: harness.mint,
+            config: harness.config,
+            transfer_record: transfer_record_address(&sender.pubkey(), &transaction_id),
+            token_program: harness.token_program,
+            system_program: system_program::ID,
+            activity_record: None,
+            fee_exemption: None,
+            treasury_token_account: None,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::TransferTokens {
+            amount: TOKEN,
+            transaction_id,
+            memo: "m".repeat(65),
+        }
+        .data(),
+    };
+    assert_carsa_error(
+        harness.process(&[instruction], &[&sender]).await,
+        CarsaError::MemoTooLong,
+    );
+}
+
+#[tokio::test]
+async fn mint_with_other_decimals_is_rejected() {
+    let mut harness = Harness::start().await;
+    let customer = harness.funded_user();
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+    harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+
+    // Rewrite the Lokal mint with 6 decimals, which the config no longer agrees with
+    let mut mint_account = harness
+        .context
+        .banks_client
+        .get_account(harness.mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+    mint.decimals = 6;
+    spl_token::state::Mint::pack(mint, &mut mint_account.data).unwrap();
+    harness.context.set_account(&harness.mint, &mint_account.into());
+
+    assert_carsa_error(
+        harness
+            .transfer_tokens(
+                &customer,
+                purchase.customer_token_account,
+                purchase.merchant_token_account,
+                TOKEN,
+            )
+            .await
+            .map(drop),
+        CarsaError::InvalidMintDecimals,
+    );
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 10_000, Some(TOKEN)).await.map(drop),
+        CarsaError::InvalidMintDecimals,
+    );
+}
+
+#[tokio::test]
+async fn frozen_token_account_is_rejected() {
+    let mut harness = Harness::start().await;
+    let customer = harness.funded_user();
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+    harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+
+    // Freeze the customer's account as the mint's freeze authority would
+    let mut token_account = harness
+        .context
+        .banks_client
+        .get_account(purchase.customer_token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut state = spl_token::state::Account::unpack(&token_account.data).unwrap();
+    state.state = spl_token::state::AccountState::Frozen;
+    spl_token::state::Account::pack(state, &mut token_account.data).unwrap();
+    harness
+        .context
+        .set_account(&purchase.customer_token_account, &token_account.into());
+
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 10_000, Some(TOKEN)).await.map(drop),
+        CarsaError::AccountFrozen,
+    );
+    assert_carsa_error(
+        harness
+            .transfer_tokens(
+                &customer,
+                purchase.customer_token_account,
+                purchase.merchant_token_account,
+                TOKEN,
+            )
+            .await
+            .map(drop),
+        CarsaError::AccountFrozen,
+    );
+    let instruction = harness.mint_tokens_instruction(
+        &harness.update_authority.pubkey(),
+        purchase.customer_token_account,
+        TOKEN,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::AccountFrozen,
+    );
+}
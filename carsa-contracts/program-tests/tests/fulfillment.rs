@@ -0,0 +1,433 @@
+//! Off-chain fulfillment acknowledgments: the backend records on the purchase that, say,
+//! a phone credit top-up went through
+//! Also covers the transaction stub that follows a purchase through fulfillment and disputes
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+const FULFILLMENT_WINDOW_SECONDS: u32 = 3_600;
+
+fn mark_fulfilled_instruction(
+    harness: &Harness,
+    signer: &Pubkey,
+    transaction_record: Pubkey,
+    transaction_stub: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::MarkFulfilled {
+            fulfillment_authority: *signer,
+            config: harness.config,
+            transaction_record,
+            transaction_stub,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::MarkFulfilled {}.data(),
+    }
+}
+
+fn open_dispute_instruction(
+    harness: &Harness,
+    customer: &Pubkey,
+    transaction_record: Pubkey,
+    transaction_stub: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::OpenDispute {
+            customer: *customer,
+            config: harness.config,
+            transaction_record,
+            dispute: pda(&[DISPUTE_SEED, transaction_record.as_ref()]),
+            transaction_stub,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::OpenDispute { reason_code: 1 }.data(),
+    }
+}
+
+fn resolve_dispute_instruction(
+    harness: &Harness,
+    purchase: &Purchase,
+    transaction_record: Pubkey,
+    transaction_stub: Pubkey,
+    uphold: bool,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ResolveDispute {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            dispute: pda(&[DISPUTE_SEED, transaction_record.as_ref()]),
+            transaction_record,
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            customer_token_account: purchase.customer_token_account,
+            token_program: harness.token_program,
+            cosigner: None,
+            transaction_stub,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ResolveDispute { uphold }.data(),
+    }
+}
+
+/// The stub kept for the purchase recorded at `transaction_record`
+async fn transaction_stub_of(harness: &Harness, transaction_record: Pubkey) -> Pubkey {
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    transaction_stub_address(&record.merchant, &record.customer, &record.transaction_id)
+}
+
+/// A purchase, with a fulfillment authority configured
+async fn setup() -> (Harness, Keypair, Pubkey) {
+    let (harness, fulfillment_authority, _, _, transaction_record) = setup_purchase().await;
+    (harness, fulfillment_authority, transaction_record)
+}
+
+/// A purchase and its customer, with a fulfillment authority configured
+async fn setup_purchase() -> (Harness, Keypair, Keypair, Purchase, Pubkey) {
+    let mut harness = Harness::start().await;
+    let fulfillment_authority = harness.funded_user();
+    let merchant_owner = harness.funded_user();
+    let customer = harness.funded_user();
+
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetFulfillmentAuthority {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetFulfillmentAuthority {
+            fulfillment_authority: fulfillment_authority.pubkey(),
+            fulfillment_window_seconds: FULFILLMENT_WINDOW_SECONDS,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+    let transaction_record = harness.purchase(&customer, &purchase, 50_000, None).await.unwrap();
+
+    (harness, fulfillment_authority, customer, purchase, transaction_record)
+}
+
+#[tokio::test]
+async fn fulfillment_is_recorded_once() {
+    let (mut harness, fulfillment_authority, transaction_record) = setup().await;
+    let stub = transaction_stub_of(&harness, transaction_record).await;
+
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    harness.process(&[instruction], &[&fulfillment_authority]).await.unwrap();
+
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!(record.fulfilled_at, harness.clock().await.unix_timestamp);
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Fulfilled);
+    assert_eq!(transaction_stub.timestamp, record.fulfilled_at);
+
+    // A retried webhook must not overwrite the first acknowledgment
+    harness.warp_forward(60).await;
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&fulfillment_authority]).await,
+        CarsaError::AlreadyFulfilled,
+    );
+}
+
+#[tokio::test]
+async fn rejects_fulfillment_from_other_signers() {
+    let (mut harness, _, transaction_record) = setup().await;
+    let stub = transaction_stub_of(&harness, transaction_record).await;
+    let impostor = harness.funded_user();
+
+    let instruction =
+        mark_fulfilled_instruction(&harness, &impostor.pubkey(), transaction_record, stub);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&impostor]).await,
+        CarsaError::Unauthorized,
+    );
+
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!(record.fulfilled_at, 0);
+}
+
+#[tokio::test]
+async fn rejects_fulfillment_after_the_window() {
+    let (mut harness, fulfillment_authority, transaction_record) = setup().await;
+    let stub = transaction_stub_of(&harness, transaction_record).await;
+    harness.warp_forward(FULFILLMENT_WINDOW_SECONDS as i64 + 1).await;
+
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&fulfillment_authority]).await,
+        CarsaError::FulfillmentWindowClosed,
+    );
+}
+
+#[tokio::test]
+async fn stub_follows_a_purchase_through_fulfillment_and_a_refund() {
+    let (mut harness, fulfillment_authority, customer, purchase, transaction_record) =
+        setup_purchase().await;
+    let stub = transaction_stub_of(&harness, transaction_record).await;
+
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.customer, customer.pubkey());
+    assert_eq!(transaction_stub.merchant, purchase.merchant_account);
+    assert_eq!(transaction_stub.status, TransactionStatus::Completed);
+
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    harness.process(&[instruction], &[&fulfillment_authority]).await.unwrap();
+
+    // A fulfilled purchase can still be disputed, and upholding the dispute refunds it
+    let instruction =
+        open_dispute_instruction(&harness, &customer.pubkey(), transaction_record, stub);
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Disputed);
+
+    let instruction =
+        resolve_dispute_instruction(&harness, &purchase, transaction_record, stub, true);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Refunded);
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert!(record.refunded);
+
+    // The refund is final
+    let instruction =
+        resolve_dispute_instruction(&harness, &purchase, transaction_record, stub, false);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::DisputeAlreadyResolved,
+    );
+}
+
+#[tokio::test]
+async fn disputed_purchase_cannot_be_fulfilled_until_resolved() {
+    let (mut harness, fulfillment_authority, customer, purchase, transaction_record) =
+        setup_purchase().await;
+    let stub = transaction_stub_of(&harness, transaction_record).await;
+
+    let instruction =
+        open_dispute_instruction(&harness, &customer.pubkey(), transaction_record, stub);
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&fulfillment_authority]).await,
+        CarsaError::InvalidTransactionTransition,
+    );
+
+    let instruction =
+        resolve_dispute_instruction(&harness, &purchase, transaction_record, stub, false);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Resolved);
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert!(!record.refunded);
+
+    // A resolved purchase is final, even for a late fulfillment acknowledgment
+    harness.warp_forward(1).await;
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&fulfillment_authority]).await,
+        CarsaError::TransactionFinalized,
+    );
+}
+
+/// Stands in a purchase recorded before `PurchaseTransaction` outgrew 164 bytes, at the
+/// customer-only address of the first deployment, and returns its address and transaction id
+async fn set_legacy_purchase_record(
+    harness: &mut Harness,
+    purchase: &Purchase,
+) -> (Pubkey, [u8; 32]) {
+    let transaction_id = unique_id();
+    let (address, bump) = Pubkey::find_program_address(
+        &[TRANSACTION_SEED, purchase.customer.as_ref(), &transaction_id],
+        &carsa::ID,
+    );
+    let mut data = PurchaseTransaction::DISCRIMINATOR.to_vec();
+    LegacyPurchaseTransaction {
+        customer: purchase.customer,
+        merchant: purchase.merchant_account,
+        fiat_amount: 50_000,
+        redeemed_token_amount: 0,
+        total_value: 50_000,
+        reward_amount: 2_500,
+        cashback_rate: 500,
+        used_tokens: false,
+        timestamp: harness.clock().await.unix_timestamp,
+        transaction_id,
+        bump,
+        staker_boost_bps: 0,
+        protocol_fee: 0,
+        redemption_bonus_bps: 0,
+        reserved: [0; 4],
+    }
+    .serialize(&mut data)
+    .unwrap();
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let account = solana_sdk::account::Account {
+        lamports: rent.minimum_balance(data.len()),
+        data,
+        owner: carsa::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    harness.context.set_account(&address, &account.into());
+    (address, transaction_id)
+}
+
+fn migrate_purchase_transaction_instruction(
+    payer: &Pubkey,
+    transaction_record: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::MigratePurchaseTransaction {
+            payer: *payer,
+            transaction_record,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::MigratePurchaseTransaction {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn pre_upgrade_purchase_can_be_disputed_once_migrated() {
+    let (mut harness, _, customer, purchase, _) = setup_purchase().await;
+    let (transaction_record, transaction_id) =
+        set_legacy_purchase_record(&mut harness, &purchase).await;
+    let stub =
+        transaction_stub_address(&purchase.merchant_account, &customer.pubkey(), &transaction_id);
+
+    // The 164-byte record does not load as a `PurchaseTransaction`
+    let instruction =
+        open_dispute_instruction(&harness, &customer.pubkey(), transaction_record, stub);
+    assert!(harness.process(&[instruction], &[&customer]).await.is_err());
+
+    let instruction =
+        migrate_purchase_transaction_instruction(&customer.pubkey(), transaction_record);
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+    let account =
+        harness.context.banks_client.clone().get_account(transaction_record).await.unwrap();
+    assert_eq!(account.unwrap().data.len(), PurchaseTransaction::LEN);
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!((record.customer, record.transaction_id), (customer.pubkey(), transaction_id));
+    assert_eq!((record.fiat_amount, record.reward_amount), (50_000, 2_500));
+    assert!(!record.disputed && record.fulfilled_at == 0);
+
+    // Migrating twice is rejected
+    let instruction = migrate_purchase_transaction_instruction(
+        &harness.update_authority.pubkey(),
+        transaction_record,
+    );
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::NotALegacyAccount,
+    );
+
+    // Identical to the rejected attempt, so it needs a new blockhash
+    harness.wait_for_new_blockhash().await;
+    let instruction =
+        open_dispute_instruction(&harness, &customer.pubkey(), transaction_record, stub);
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert!(record.disputed);
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Disputed);
+}
+
+#[tokio::test]
+async fn pre_upgrade_purchase_can_be_fulfilled_once_migrated() {
+    let (mut harness, fulfillment_authority, _, purchase, _) = setup_purchase().await;
+    let (transaction_record, transaction_id) =
+        set_legacy_purchase_record(&mut harness, &purchase).await;
+    let stub =
+        transaction_stub_address(&purchase.merchant_account, &purchase.customer, &transaction_id);
+
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    assert!(harness.process(&[instruction], &[&fulfillment_authority]).await.is_err());
+
+    let instruction = migrate_purchase_transaction_instruction(
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+    );
+    harness.process(&[instruction], &[&fulfillment_authority]).await.unwrap();
+
+    // The record predates stubs, so fulfilling it creates one. Identical to the rejected
+    // attempt, so it needs a new blockhash
+    harness.wait_for_new_blockhash().await;
+    let instruction = mark_fulfilled_instruction(
+        &harness,
+        &fulfillment_authority.pubkey(),
+        transaction_record,
+        stub,
+    );
+    harness.process(&[instruction], &[&fulfillment_authority]).await.unwrap();
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!(record.fulfilled_at, harness.clock().await.unix_timestamp);
+    let transaction_stub: TransactionStub = harness.account(stub).await;
+    assert_eq!(transaction_stub.status, TransactionStatus::Fulfilled);
+    assert_eq!(transaction_stub.customer, purchase.customer);
+}
@@ -0,0 +1,2127 @@
+//! Lokal token flows: mint initialization, merchant registration, purchases and transfers
+//!
+//! Purchases and transfers run once with a classic SPL Token mint and once with a
+//! Token-2022 mint
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{
+    system_program, AccountSerialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::permanent_delegate::get_permanent_delegate;
+use anchor_spl::token_2022::spl_token_2022::extension::StateWithExtensions;
+use anchor_spl::token_interface::TokenAccount;
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+/// A harness with a 5% cashback merchant and a customer, each holding a Lokal token account
+async fn setup() -> (Harness, Keypair, Purchase) {
+    setup_with(Harness::start().await).await
+}
+
+/// Like `setup`, on `harness`
+async fn setup_with(mut harness: Harness) -> (Harness, Keypair, Purchase) {
+    let merchant_owner = harness.funded_user();
+    let customer = harness.funded_user();
+
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+
+    (harness, customer, purchase)
+}
+
+/// Creates a Lokal token account for `owner` at a fresh keypair address, off its
+/// associated token address
+async fn create_keypair_token_account(harness: &mut Harness, owner: &Pubkey) -> Pubkey {
+    let token_account = Keypair::new();
+    let rent = harness.context.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &harness.context.payer.pubkey(),
+            &token_account.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_account3(
+            &spl_token::ID,
+            &token_account.pubkey(),
+            &harness.mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    harness.process(&instructions, &[&token_account]).await.unwrap();
+    token_account.pubkey()
+}
+
+#[tokio::test]
+async fn initializes_mint_and_config() {
+    let harness = Harness::start().await;
+
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.mint, harness.mint);
+    assert_eq!(config.update_authority, harness.update_authority.pubkey());
+    assert_eq!(config.token_to_idr_rate, LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE);
+    assert!(!config.paused);
+
+    let registry: MerchantRegistry = harness.account(harness.merchant_registry).await;
+    assert_eq!({ registry.merchant_count }, 0);
+
+    // Under Token-2022 the mint authority PDA is also the mint's permanent delegate
+    let mut harness = Harness::start_with_token_program(spl_token_2022::ID).await;
+    let mint_account = harness
+        .context
+        .banks_client
+        .get_account(harness.mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data).unwrap();
+    assert_eq!(get_permanent_delegate(&mint), Some(harness.mint_authority));
+}
+
+#[tokio::test]
+async fn registers_merchant() {
+    let mut harness = Harness::start().await;
+    let owner = harness.funded_user();
+
+    let merchant_account = harness.register_merchant(&owner, 750, 10_000).await;
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.merchant_wallet, owner.pubkey());
+    assert_eq!(merchant.cashback_rate, 750);
+    assert_eq!(merchant.min_purchase_amount, 10_000);
+    assert!(merchant.is_active);
+    assert_eq!(merchant.total_transactions, 0);
+
+    let registry: MerchantRegistry = harness.account(harness.merchant_registry).await;
+    assert_eq!({ registry.merchant_count }, 1);
+    let index: MerchantIndex = harness
+        .account(pda(&[MERCHANT_INDEX_SEED, &0u64.to_le_bytes()]))
+        .await;
+    assert_eq!(index.merchant, merchant_account);
+}
+
+#[tokio::test]
+async fn rejects_invalid_merchant_registration() {
+    let mut harness = Harness::start().await;
+    let owner = harness.funded_user();
+
+    for (name, category, cashback_rate, expected) in [
+        ("Warung Test", "retail", 10_001, CarsaError::InvalidCashbackRate),
+        ("", "retail", 500, CarsaError::InvalidMerchantName),
+        ("Warung Test", "", 500, CarsaError::InvalidMerchantCategory),
+    ] {
+        let instruction = harness
+            .register_merchant_instruction(&owner.pubkey(), name, category, cashback_rate, 0)
+            .await;
+        assert_carsa_error(harness.process(&[instruction], &[&owner]).await, expected);
+    }
+}
+
+async fn register_merchant_by_authority_instruction(
+    harness: &Harness,
+    merchant_wallet: &Pubkey,
+) -> Instruction {
+    let registry: MerchantRegistry = harness.account(harness.merchant_registry).await;
+
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::RegisterMerchantByAuthority {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            merchant_account: merchant_address(merchant_wallet),
+            merchant_registry: harness.merchant_registry,
+            merchant_index: pda(&[MERCHANT_INDEX_SEED, &registry.merchant_count.to_le_bytes()]),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::RegisterMerchantByAuthority {
+            merchant_wallet: *merchant_wallet,
+            name: "Warung Pameran".to_string(),
+            category: "retail".to_string(),
+            cashback_rate: 500,
+        }
+        .data(),
+    }
+}
+
+fn accept_merchant_registration_instruction(
+    harness: &Harness,
+    merchant_owner: &Pubkey,
+    merchant_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::AcceptMerchantRegistration {
+            merchant_owner: *merchant_owner,
+            merchant_account,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::AcceptMerchantRegistration {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn authority_registered_merchant_waits_for_its_owner() {
+    let (mut harness, customer, purchase) = setup().await;
+    let owner = harness.funded_user();
+
+    // The update authority signs and pays; the owner's wallet is only named
+    let instruction = register_merchant_by_authority_instruction(&harness, &owner.pubkey()).await;
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let merchant_account = merchant_address(&owner.pubkey());
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.merchant_wallet, owner.pubkey());
+    assert_eq!(merchant.cashback_rate, 500);
+    assert!(merchant.pending_activation);
+    assert_eq!(merchant.registry_index, 1);
+
+    let pending_purchase = Purchase {
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&owner.pubkey()).await,
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &pending_purchase, 100_000, None).await.map(drop),
+        CarsaError::MerchantPendingActivation,
+    );
+
+    // Only the named wallet can accept
+    let intruder = harness.funded_user();
+    let instruction =
+        accept_merchant_registration_instruction(&harness, &intruder.pubkey(), merchant_account);
+    assert!(harness.process(&[instruction], &[&intruder]).await.is_err());
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert!(merchant.pending_activation);
+
+    let instruction =
+        accept_merchant_registration_instruction(&harness, &owner.pubkey(), merchant_account);
+    harness.process(&[instruction], &[&owner]).await.unwrap();
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert!(!merchant.pending_activation);
+
+    harness.purchase(&customer, &pending_purchase, 100_000, None).await.unwrap();
+    assert_eq!(
+        harness.token_balance(pending_purchase.customer_token_account).await,
+        5 * TOKEN
+    );
+
+    let instruction =
+        accept_merchant_registration_instruction(&harness, &owner.pubkey(), merchant_account);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&owner]).await,
+        CarsaError::MerchantRegistrationAlreadyAccepted,
+    );
+}
+
+fn propose_merchant_owner_instruction(
+    harness: &Harness,
+    merchant_owner: &Pubkey,
+    merchant_account: Pubkey,
+    new_owner: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ProposeMerchantOwner {
+            merchant_owner: *merchant_owner,
+            merchant_account,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ProposeMerchantOwner { new_owner }.data(),
+    }
+}
+
+fn accept_merchant_ownership_instruction(
+    harness: &Harness,
+    new_owner: &Pubkey,
+    merchant_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::AcceptMerchantOwnership {
+            new_owner: *new_owner,
+            merchant_account,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::AcceptMerchantOwnership {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn merchant_ownership_transfer_keeps_the_merchant_address() {
+    let mut harness = Harness::start().await;
+    let old_owner = harness.funded_user();
+    let new_owner = harness.funded_user();
+    let customer = harness.funded_user();
+    let merchant_account = harness.register_merchant(&old_owner, 500, 0).await;
+
+    // Nothing changes hands until the proposed owner accepts
+    let instruction = propose_merchant_owner_instruction(
+        &harness,
+        &old_owner.pubkey(),
+        merchant_account,
+        new_owner.pubkey(),
+    );
+    harness.process(&[instruction], &[&old_owner]).await.unwrap();
+    let intruder = harness.funded_user();
+    let instruction =
+        accept_merchant_ownership_instruction(&harness, &intruder.pubkey(), merchant_account);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&intruder]).await,
+        CarsaError::NotPendingMerchantOwner,
+    );
+
+    let instruction =
+        accept_merchant_ownership_instruction(&harness, &new_owner.pubkey(), merchant_account);
+    harness.process(&[instruction], &[&new_owner]).await.unwrap();
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.merchant_wallet, new_owner.pubkey());
+    assert_eq!(merchant.pending_owner, Pubkey::default());
+    assert_eq!(merchant.payout_token_account, Pubkey::default());
+
+    // The old owner loses access and the new one gains it
+    let old = old_owner.pubkey();
+    let instruction = set_cashback_rate_instruction(&harness, &old, merchant_account, None, 400);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&old_owner]).await,
+        CarsaError::MerchantOwnerMismatch,
+    );
+    let new = new_owner.pubkey();
+    let instruction = set_cashback_rate_instruction(&harness, &new, merchant_account, None, 400);
+    harness.process(&[instruction], &[&new_owner]).await.unwrap();
+
+    // Purchases keep going to the same address, with redemptions paid to the new owner
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&new_owner.pubkey()).await,
+    };
+    harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+    harness.purchase(&customer, &purchase, 100_000, Some(10 * TOKEN)).await.unwrap();
+    assert_eq!(harness.token_balance(purchase.merchant_token_account).await, 10 * TOKEN);
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.payout_token_account, purchase.merchant_token_account);
+    assert_eq!(merchant.total_transactions, 1);
+}
+
+#[tokio::test]
+async fn purchase_without_redemption_mints_cashback() {
+    for token_program in TOKEN_PROGRAMS {
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (harness, customer, purchase) = setup_with(harness).await;
+
+        let transaction_record =
+            harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+
+        // 5% of 100,000 IDR at 1,000 IDR per token
+        let reward = 5 * TOKEN;
+        assert_eq!(harness.token_balance(purchase.customer_token_account).await, reward);
+
+        let record: PurchaseTransaction = harness.account(transaction_record).await;
+        assert_eq!(record.customer, customer.pubkey());
+        assert_eq!(record.merchant, purchase.merchant_account);
+        assert_eq!(record.fiat_amount, 100_000);
+        assert_eq!(record.reward_amount, reward);
+        assert!(!record.used_tokens);
+
+        let merchant: MerchantAccount = harness.account(purchase.merchant_account).await;
+        assert_eq!(merchant.total_transactions, 1);
+        assert_eq!(merchant.total_volume, 100_000);
+        assert_eq!(merchant.total_rewards_distributed, reward);
+    }
+}
+
+#[tokio::test]
+async fn purchase_with_redemption_pays_merchant_in_tokens() {
+    for token_program in TOKEN_PROGRAMS {
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (harness, customer, purchase) = setup_with(harness).await;
+        harness.mint_tokens(purchase.customer_token_account, 20 * TOKEN).await;
+
+        let transaction_record = harness
+            .purchase(&customer, &purchase, 50_000, Some(10 * TOKEN))
+            .await
+            .unwrap();
+
+        // 10 tokens are worth 10,000 IDR, so the cashback is 5% of 60,000 IDR
+        let reward = 3 * TOKEN;
+        assert_eq!(
+            harness.token_balance(purchase.customer_token_account).await,
+            20 * TOKEN - 10 * TOKEN + reward
+        );
+        assert_eq!(harness.token_balance(purchase.merchant_token_account).await, 10 * TOKEN);
+
+        let record: PurchaseTransaction = harness.account(transaction_record).await;
+        assert!(record.used_tokens);
+        assert_eq!(record.redeemed_token_amount, 10 * TOKEN);
+        assert_eq!(record.total_value, 60_000);
+        assert_eq!(record.reward_amount, reward);
+    }
+}
+
+#[tokio::test]
+async fn first_purchase_creates_customer_token_account() {
+    for token_program in TOKEN_PROGRAMS {
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (mut harness, _, purchase) = setup_with(harness).await;
+
+        // A brand-new wallet with no Lokal token account purchases in a single instruction
+        let customer = harness.funded_user();
+        let purchase = Purchase {
+            customer: customer.pubkey(),
+            customer_token_account: harness.associated_token_address(&customer.pubkey(), &harness.mint),
+            ..purchase
+        };
+        harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+
+        assert_eq!(harness.token_balance(purchase.customer_token_account).await, 5 * TOKEN);
+    }
+}
+
+#[tokio::test]
+async fn purchase_accepts_existing_non_associated_token_account() {
+    let (mut harness, customer, purchase) = setup().await;
+
+    let token_account = create_keypair_token_account(&mut harness, &customer.pubkey()).await;
+    let purchase = Purchase {
+        customer_token_account: token_account,
+        ..purchase
+    };
+    harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+
+    assert_eq!(harness.token_balance(token_account).await, 5 * TOKEN);
+}
+
+#[tokio::test]
+async fn rejects_missing_customer_token_account_off_the_associated_address() {
+    let (harness, customer, purchase) = setup().await;
+
+    let purchase = Purchase {
+        customer_token_account: Keypair::new().pubkey(),
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 100_000, None).await.map(drop),
+        CarsaError::InvalidCustomerTokenAccount,
+    );
+}
+
+#[tokio::test]
+async fn passbook_splits_purchases_by_month() {
+    let (mut harness, customer, purchase) = setup().await;
+    harness.mint_tokens(purchase.customer_token_account, 20 * TOKEN).await;
+
+    let stats_address = |year_month: u32| {
+        pda(&[CUSTOMER_MONTHLY_STATS_SEED, customer.pubkey().as_ref(), &year_month.to_le_bytes()])
+    };
+    let passbook_purchase = |harness: &Harness, fiat_amount, redeem_token_amount, year_month| {
+        let transaction_id = unique_id();
+        let mut accounts = harness.process_purchase_accounts(&purchase, transaction_id);
+        accounts.customer_monthly_stats = Some(stats_address(year_month));
+        Instruction {
+            program_id: carsa::ID,
+            accounts: accounts.to_account_metas(None),
+            data: carsa::instruction::ProcessPurchase {
+                fiat_amount,
+                redeem_token_amount,
+                transaction_id,
+                expected_nonce: None,
+            }
+            .data(),
+        }
+    };
+
+    let first_month = CustomerMonthlyStats::year_month(harness.clock().await.unix_timestamp).unwrap();
+    for (fiat_amount, redeem_token_amount) in [(100_000, None), (50_000, Some(10 * TOKEN))] {
+        let instruction = passbook_purchase(&harness, fiat_amount, redeem_token_amount, first_month);
+        harness.process(&[instruction], &[&customer]).await.unwrap();
+    }
+
+    // Warp a day at a time until the calendar month turns over
+    while CustomerMonthlyStats::year_month(harness.clock().await.unix_timestamp) == Some(first_month) {
+        harness.warp_forward(86_400).await;
+    }
+    let second_month = CustomerMonthlyStats::year_month(harness.clock().await.unix_timestamp).unwrap();
+
+    // A purchase keyed to the old month no longer matches the clock
+    let instruction = passbook_purchase(&harness, 20_000, None, first_month);
+    assert!(harness.process(&[instruction], &[&customer]).await.is_err());
+    let instruction = passbook_purchase(&harness, 20_000, None, second_month);
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+
+    let first: CustomerMonthlyStats = harness.account(stats_address(first_month)).await;
+    assert_eq!(first.customer, customer.pubkey());
+    assert_eq!(first.year_month, first_month);
+    assert_eq!(first.purchase_count, 2);
+    // 150,000 IDR in fiat plus 10 tokens worth 10,000 IDR
+    assert_eq!(first.total_spent, 160_000);
+    assert_eq!(first.tokens_redeemed, 10 * TOKEN);
+    // 5% of 100,000 IDR and of 60,000 IDR
+    assert_eq!(first.rewards_earned, 5 * TOKEN + 3 * TOKEN);
+
+    let second: CustomerMonthlyStats = harness.account(stats_address(second_month)).await;
+    assert_eq!(second.year_month, second_month);
+    assert_eq!(second.purchase_count, 1);
+    assert_eq!(second.total_spent, 20_000);
+    assert_eq!(second.tokens_redeemed, 0);
+    assert_eq!(second.rewards_earned, TOKEN);
+}
+
+#[tokio::test]
+async fn rejects_invalid_purchases() {
+    let (harness, customer, purchase) = setup().await;
+
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 0, None).await.map(drop),
+        CarsaError::InvalidPurchaseAmount,
+    );
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 1_000_000_001, None).await.map(drop),
+        CarsaError::PurchaseAmountTooLarge,
+    );
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.map(drop),
+        CarsaError::InsufficientBalance,
+    );
+}
+
+#[tokio::test]
+async fn transaction_ids_are_scoped_to_the_merchant() {
+    let (mut harness, customer, purchase) = setup().await;
+    let other_owner = harness.funded_user();
+    let other_purchase = Purchase {
+        merchant_account: harness.register_merchant(&other_owner, 500, 0).await,
+        merchant_token_account: harness.create_token_account(&other_owner.pubkey()).await,
+        ..purchase
+    };
+
+    // Two merchants' tills may hand out the same id to one customer
+    let transaction_id = unique_id();
+    for purchase in [&purchase, &other_purchase] {
+        let instruction = harness.process_purchase_instruction(purchase, 50_000, None, transaction_id);
+        harness.process(&[instruction], &[&customer]).await.unwrap();
+
+        let record: PurchaseTransaction = harness
+            .account(transaction_record_address(
+                &purchase.merchant_account,
+                &customer.pubkey(),
+                &transaction_id,
+            ))
+            .await;
+        assert_eq!(record.merchant, purchase.merchant_account);
+    }
+
+    // Replaying the id at the same merchant is rejected
+    let instruction = harness.process_purchase_instruction(&purchase, 60_000, None, transaction_id);
+    assert!(harness.process(&[instruction], &[&customer]).await.is_err());
+}
+
+#[tokio::test]
+async fn pos_nonces_must_advance_one_at_a_time() {
+    let (harness, customer, purchase) = setup().await;
+    let nonce_purchase = |expected_nonce| {
+        let transaction_id = unique_id();
+        Instruction {
+            program_id: carsa::ID,
+            accounts: harness
+                .process_purchase_accounts(&purchase, transaction_id)
+                .to_account_metas(None),
+            data: carsa::instruction::ProcessPurchase {
+                fiat_amount: 50_000,
+                redeem_token_amount: None,
+                transaction_id,
+                expected_nonce,
+            }
+            .data(),
+        }
+    };
+
+    for expected_nonce in [1, 2] {
+        harness.process(&[nonce_purchase(Some(expected_nonce))], &[&customer]).await.unwrap();
+        let merchant: MerchantAccount = harness.account(purchase.merchant_account).await;
+        assert_eq!(merchant.pos_nonce, expected_nonce);
+    }
+
+    // A replayed request carries a used nonce, even under a fresh transaction id
+    assert_carsa_error(
+        harness.process(&[nonce_purchase(Some(2))], &[&customer]).await,
+        CarsaError::InvalidPosNonce,
+    );
+    assert_carsa_error(
+        harness.process(&[nonce_purchase(Some(4))], &[&customer]).await,
+        CarsaError::InvalidPosNonce,
+    );
+
+    // Purchases without a nonce leave the counter alone
+    harness.process(&[nonce_purchase(None)], &[&customer]).await.unwrap();
+    harness.process(&[nonce_purchase(Some(3))], &[&customer]).await.unwrap();
+    let merchant: MerchantAccount = harness.account(purchase.merchant_account).await;
+    assert_eq!(merchant.pos_nonce, 3);
+}
+
+#[tokio::test]
+async fn purchase_is_indexed_by_its_reference_code() {
+    let (harness, customer, purchase) = setup().await;
+
+    let transaction_record = harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+
+    let record: PurchaseTransaction = harness.account(transaction_record).await;
+    assert_eq!(record.reference_code, ReferenceIndex::reference_code(&transaction_record));
+    let index: ReferenceIndex = harness.account(reference_index_address(&transaction_record)).await;
+    assert_eq!(index.reference_code, record.reference_code);
+    assert_eq!(index.transaction_record, transaction_record);
+}
+
+#[tokio::test]
+async fn reference_code_collision_fails_the_purchase_until_retried() {
+    let (mut harness, customer, purchase) = setup().await;
+    let transaction_id = unique_id();
+    let accounts = harness.process_purchase_accounts(&purchase, transaction_id);
+    let transaction_record = accounts.transaction_record.unwrap();
+
+    // Stand in for an earlier purchase whose record hashed to the same code
+    let mut data = Vec::new();
+    ReferenceIndex {
+        reference_code: ReferenceIndex::reference_code(&transaction_record),
+        transaction_record: Pubkey::new_unique(),
+        transaction_stub: Pubkey::new_unique(),
+        bump: 255,
+    }
+    .try_serialize(&mut data)
+    .unwrap();
+    let taken = solana_sdk::account::Account {
+        lamports: LAMPORTS_PER_SOL,
+        data,
+        owner: carsa::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    harness.context.set_account(&accounts.reference_index.unwrap(), &taken.into());
+
+    let instruction =
+        harness.process_purchase_instruction(&purchase, 100_000, None, transaction_id);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&customer]).await,
+        CarsaError::ReferenceCodeCollision,
+    );
+    let account = harness.context.banks_client.clone().get_account(transaction_record).await;
+    assert!(account.unwrap().is_none());
+
+    // Retrying under a new transaction id derives a new record and so a new code
+    let transaction_record = harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+    let index: ReferenceIndex = harness.account(reference_index_address(&transaction_record)).await;
+    assert_eq!(index.transaction_record, transaction_record);
+}
+
+#[tokio::test]
+async fn rejects_purchase_below_merchant_minimum() {
+    let (mut harness, customer, purchase) = setup().await;
+    let owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&owner, 500, 10_000).await;
+
+    let purchase = Purchase {
+        merchant_account,
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 5_000, None).await.map(drop),
+        CarsaError::PurchaseBelowMinimum,
+    );
+    harness.purchase(&customer, &purchase, 10_000, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_redemption_to_unregistered_merchant_account() {
+    let (mut harness, customer, purchase) = setup().await;
+    harness.mint_tokens(purchase.customer_token_account, 20 * TOKEN).await;
+
+    let merchant: MerchantAccount = harness.account(purchase.merchant_account).await;
+    assert_eq!(merchant.payout_token_account, purchase.merchant_token_account);
+
+    // An old account of the merchant's own is still not the registered one
+    let old_account = create_keypair_token_account(&mut harness, &merchant.merchant_wallet).await;
+    let purchase = Purchase {
+        merchant_token_account: old_account,
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.map(drop),
+        CarsaError::PayoutAccountMismatch,
+    );
+}
+
+fn set_payout_account_instruction(
+    harness: &Harness,
+    merchant_owner: &Pubkey,
+    payout_token_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetPayoutAccount {
+            merchant_owner: *merchant_owner,
+            merchant_account: merchant_address(merchant_owner),
+            payout_token_account,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetPayoutAccount {}.data(),
+    }
+}
+
+#[tokio::test]
+async fn changed_payout_account_receives_redemptions() {
+    let (mut harness, customer, purchase) = setup().await;
+    harness.mint_tokens(purchase.customer_token_account, 20 * TOKEN).await;
+
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let old_account = harness.create_token_account(&merchant_owner.pubkey()).await;
+    let new_account = create_keypair_token_account(&mut harness, &merchant_owner.pubkey()).await;
+
+    let instruction =
+        set_payout_account_instruction(&harness, &merchant_owner.pubkey(), new_account);
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.payout_token_account, new_account);
+
+    let purchase = Purchase {
+        merchant_account,
+        merchant_token_account: old_account,
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.map(drop),
+        CarsaError::PayoutAccountMismatch,
+    );
+
+    let purchase = Purchase {
+        merchant_token_account: new_account,
+        ..purchase
+    };
+    harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.unwrap();
+    assert_eq!(harness.token_balance(new_account).await, TOKEN);
+}
+
+#[tokio::test]
+async fn unregistered_merchant_locks_in_first_payout_account() {
+    let (mut harness, customer, purchase) = setup().await;
+    harness.mint_tokens(purchase.customer_token_account, 20 * TOKEN).await;
+
+    // Registered without a payout account, as merchants from before payout accounts were
+    let merchant_owner = harness.funded_user();
+    let mut instruction = harness
+        .register_merchant_instruction(&merchant_owner.pubkey(), "Warung Lama", "retail", 500, 0)
+        .await;
+    // Leave out the trailing optional payout account
+    let payout_meta = instruction.accounts.len() - 1;
+    instruction.accounts[payout_meta].pubkey = carsa::ID;
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+    let merchant_account = merchant_address(&merchant_owner.pubkey());
+
+    let first_account = harness.create_token_account(&merchant_owner.pubkey()).await;
+    let other_account = create_keypair_token_account(&mut harness, &merchant_owner.pubkey()).await;
+    let purchase = Purchase {
+        merchant_account,
+        merchant_token_account: first_account,
+        ..purchase
+    };
+    harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.unwrap();
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.payout_token_account, first_account);
+    let purchase = Purchase {
+        merchant_token_account: other_account,
+        ..purchase
+    };
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 50_000, Some(TOKEN)).await.map(drop),
+        CarsaError::PayoutAccountMismatch,
+    );
+}
+
+#[tokio::test]
+async fn transfers_tokens_between_wallets() {
+    for token_program in TOKEN_PROGRAMS {
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (mut harness, sender, purchase) = setup_with(harness).await;
+        let recipient = harness.funded_user();
+        let recipient_token_account = harness.create_token_account(&recipient.pubkey()).await;
+        harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+
+        let transfer_record = harness
+            .transfer_tokens(
+                &sender,
+                purchase.customer_token_account,
+                recipient_token_account,
+                4 * TOKEN,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(harness.token_balance(purchase.customer_token_account).await, 6 * TOKEN);
+        assert_eq!(harness.token_balance(recipient_token_account).await, 4 * TOKEN);
+
+        let record: TokenTransfer = harness.account(transfer_record).await;
+        assert_eq!(record.from, sender.pubkey());
+        assert_eq!(record.to, recipient.pubkey());
+        assert_eq!(record.amount, 4 * TOKEN);
+        assert_eq!(record.fee, 0);
+        assert_eq!(record.net_amount, 4 * TOKEN);
+    }
+}
+
+#[tokio::test]
+async fn rejects_invalid_transfers() {
+    let (mut harness, sender, purchase) = setup().await;
+    let recipient = harness.funded_user();
+    let recipient_token_account = harness.create_token_account(&recipient.pubkey()).await;
+    harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+
+    let from = purchase.customer_token_account;
+    for (to, amount, expected) in [
+        (recipient_token_account, 0, CarsaError::InvalidTransferAmount),
+        (recipient_token_account, 10_001 * TOKEN, CarsaError::TransferAmountTooLarge),
+        (recipient_token_account, 11 * TOKEN, CarsaError::InsufficientBalance),
+        (from, TOKEN, CarsaError::SelfTransferNotAllowed),
+    ] {
+        assert_carsa_error(
+            harness.transfer_tokens(&sender, from, to, amount).await.map(drop),
+            expected,
+        );
+    }
+}
+
+fn set_limits_instruction(
+    harness: &Harness,
+    max_transfer_amount: u64,
+    max_mint_per_tx: u64,
+    max_purchase_idr: u64,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetLimits {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetLimits {
+            max_transfer_amount,
+            max_mint_per_tx,
+            max_purchase_idr,
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn limits_are_enforced_at_the_configured_values() {
+    let (mut harness, customer, purchase) = setup().await;
+    let recipient = harness.funded_user();
+    let recipient_token_account = harness.create_token_account(&recipient.pubkey()).await;
+
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.max_transfer_amount, LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT);
+    assert_eq!(config.max_mint_per_tx, LokalMintConfig::DEFAULT_MAX_MINT_PER_TX);
+    assert_eq!(config.max_purchase_idr, LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR);
+
+    let instruction = set_limits_instruction(&harness, 2 * TOKEN, 5 * TOKEN, 100_000);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let authority = harness.update_authority.pubkey();
+    let from = purchase.customer_token_account;
+    let instruction = harness.mint_tokens_instruction(&authority, from, 5 * TOKEN + 1);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::MintAmountTooLarge,
+    );
+    harness.mint_tokens(from, 5 * TOKEN).await;
+
+    assert_carsa_error(
+        harness
+            .transfer_tokens(&customer, from, recipient_token_account, 2 * TOKEN + 1)
+            .await
+            .map(drop),
+        CarsaError::TransferAmountTooLarge,
+    );
+    harness.transfer_tokens(&customer, from, recipient_token_account, 2 * TOKEN).await.unwrap();
+
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 100_001, None).await.map(drop),
+        CarsaError::PurchaseAmountTooLarge,
+    );
+    harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn zero_limits_mean_no_limit() {
+    let (harness, customer, purchase) = setup().await;
+    let instruction = set_limits_instruction(&harness, 0, 0, 0);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    // Beyond every default limit
+    harness.mint_tokens(purchase.customer_token_account, 20_000 * TOKEN).await;
+    harness
+        .transfer_tokens(
+            &customer,
+            purchase.customer_token_account,
+            purchase.merchant_token_account,
+            15_000 * TOKEN,
+        )
+        .await
+        .unwrap();
+    harness.purchase(&customer, &purchase, 2_000_000_000, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn slim_purchase_records_replace_full_ones_when_enabled() {
+    let (harness, customer, purchase) = setup().await;
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetSlimPurchaseRecords {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetSlimPurchaseRecords { enabled: true }.data(),
+    };
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    // A full record no longer satisfies the purchase
+    let transaction_id = unique_id();
+    let instruction =
+        harness.process_purchase_instruction(&purchase, 100_000, None, transaction_id);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&customer]).await,
+        CarsaError::InvalidTransactionRecord,
+    );
+
+    let slim_record =
+        slim_transaction_record_address(&purchase.merchant_account, &customer.pubkey(), &transaction_id);
+    let mut accounts = harness.process_purchase_accounts(&purchase, transaction_id);
+    accounts.transaction_record = None;
+    accounts.slim_transaction_record = Some(slim_record);
+    accounts.reference_index = Some(reference_index_address(&slim_record));
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: accounts.to_account_metas(None),
+        data: carsa::instruction::ProcessPurchase {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+            transaction_id,
+            expected_nonce: None,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+
+    let record: PurchaseTransactionV2 = harness.account(slim_record).await;
+    assert_eq!(record.customer, customer.pubkey());
+    assert_eq!(record.merchant, purchase.merchant_account);
+    assert_eq!(record.fiat_amount, 100_000);
+    assert_eq!(record.reward_amount, 5 * TOKEN);
+    assert_eq!(record.transaction_id, transaction_id[..16]);
+    let account = harness.context.banks_client.clone().get_account(slim_record).await.unwrap();
+    assert_eq!(account.unwrap().data.len(), PurchaseTransactionV2::LEN);
+}
+
+fn set_rate_change_policy_instruction(
+    harness: &Harness,
+    rate_change_cooldown_seconds: u32,
+    max_rate_increase_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetRateChangePolicy {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetRateChangePolicy {
+            rate_change_cooldown_seconds,
+            max_rate_increase_bps,
+        }
+        .data(),
+    }
+}
+
+/// An `update_merchant` changing only the cashback rate, co-signed by `update_authority`
+/// when given
+fn set_cashback_rate_instruction(
+    harness: &Harness,
+    merchant_owner: &Pubkey,
+    merchant_account: Pubkey,
+    update_authority: Option<Pubkey>,
+    new_cashback_rate: u16,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::UpdateMerchant {
+            merchant_owner: *merchant_owner,
+            merchant_account,
+            config: harness.config,
+            update_authority,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::UpdateMerchant {
+            new_cashback_rate: Some(new_cashback_rate),
+            is_active: None,
+            min_purchase_amount: None,
+            redemption_bonus_bps: None,
+            require_merchant_signature: None,
+            compressed_receipts: None,
+        }
+        .data(),
+    }
+}
+
+#[tokio::test]
+async fn cashback_rate_changes_wait_out_the_cooldown() {
+    let mut harness = Harness::start().await;
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let instruction = set_rate_change_policy_instruction(&harness, 3_600, 0);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let owner = merchant_owner.pubkey();
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 400);
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 600);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&merchant_owner]).await,
+        CarsaError::RateChangeCooldown,
+    );
+
+    harness.warp_forward(3_600).await;
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 600);
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.cashback_rate, 600);
+    assert_eq!(merchant.last_rate_change_at, harness.clock().await.unix_timestamp);
+}
+
+#[tokio::test]
+async fn cashback_rate_increases_are_capped_per_change() {
+    let mut harness = Harness::start().await;
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let instruction = set_rate_change_policy_instruction(&harness, 0, 200);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let owner = merchant_owner.pubkey();
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 701);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&merchant_owner]).await,
+        CarsaError::RateChangeTooLarge,
+    );
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 700);
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+
+    // Decreases are never capped
+    let instruction = set_cashback_rate_instruction(&harness, &owner, merchant_account, None, 100);
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.cashback_rate, 100);
+}
+
+#[tokio::test]
+async fn update_authority_bypasses_the_rate_change_policy() {
+    let mut harness = Harness::start().await;
+    let merchant_owner = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+    let instruction = set_rate_change_policy_instruction(&harness, 3_600, 100);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let owner = merchant_owner.pubkey();
+    let authority = Some(harness.update_authority.pubkey());
+    for rate in [2_000, 3_000] {
+        let instruction =
+            set_cashback_rate_instruction(&harness, &owner, merchant_account, authority, rate);
+        harness
+            .process(&[instruction], &[&merchant_owner, &harness.update_authority])
+            .await
+            .unwrap();
+    }
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.cashback_rate, 3_000);
+}
+
+fn set_cashback_limits_instruction(
+    harness: &Harness,
+    max_cashback_bps: u16,
+    alert_threshold_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetCashbackLimits {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetCashbackLimits { max_cashback_bps, alert_threshold_bps }
+            .data(),
+    }
+}
+
+#[tokio::test]
+async fn cashback_rates_above_the_protocol_maximum_are_rejected() {
+    let mut harness = Harness::start().await;
+    let instruction = set_cashback_limits_instruction(&harness, 1_000, 500);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.max_cashback_bps, 1_000);
+    assert_eq!(config.alert_threshold_bps, 500);
+
+    let merchant_owner = harness.funded_user();
+    let owner = merchant_owner.pubkey();
+    let instruction =
+        harness.register_merchant_instruction(&owner, "Warung Test", "retail", 1_001, 0).await;
+    assert_carsa_error(
+        harness.process(&[instruction], &[&merchant_owner]).await,
+        CarsaError::CashbackRateAboveMax,
+    );
+
+    // Rates above the alert threshold are still allowed
+    let merchant_account = harness.register_merchant(&merchant_owner, 1_000, 0).await;
+
+    // Not even the update authority's co-signature lifts the maximum
+    let authority = Some(harness.update_authority.pubkey());
+    let instruction =
+        set_cashback_rate_instruction(&harness, &owner, merchant_account, authority, 1_001);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&merchant_owner, &harness.update_authority]).await,
+        CarsaError::CashbackRateAboveMax,
+    );
+}
+
+#[tokio::test]
+async fn lowering_the_maximum_stops_rewards_above_it() {
+    let (harness, customer, purchase) = setup().await;
+    let instruction = set_cashback_limits_instruction(&harness, 400, 0);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 100_000, None).await.map(drop),
+        CarsaError::CashbackRateAboveMax,
+    );
+
+    let instruction = set_cashback_limits_instruction(&harness, 500, 0);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_invalid_cashback_limits() {
+    let harness = Harness::start().await;
+
+    for (max_cashback_bps, alert_threshold_bps) in [(0, 0), (10_001, 0), (1_000, 10_001)] {
+        let instruction =
+            set_cashback_limits_instruction(&harness, max_cashback_bps, alert_threshold_bps);
+        assert_carsa_error(
+            harness.process(&[instruction], &[&harness.update_authority]).await,
+            CarsaError::InvalidCashbackRate,
+        );
+    }
+}
+
+#[tokio::test]
+async fn rejects_mint_from_non_authority() {
+    let (mut harness, _, purchase) = setup().await;
+    let impostor = harness.funded_user();
+
+    let instruction =
+        harness.mint_tokens_instruction(&impostor.pubkey(), purchase.customer_token_account, TOKEN);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&impostor]).await,
+        CarsaError::UpdateAuthorityMismatch,
+    );
+    assert_eq!(harness.token_balance(purchase.customer_token_account).await, 0);
+}
+
+fn set_mint_caps_instruction(
+    harness: &Harness,
+    admin_mint_cap: u64,
+    reward_mint_cap: u64,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetMintCaps {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetMintCaps { admin_mint_cap, reward_mint_cap }.data(),
+    }
+}
+
+#[tokio::test]
+async fn admin_and_reward_mints_count_against_separate_caps() {
+    let (harness, customer, purchase) = setup().await;
+    let authority = harness.update_authority.pubkey();
+    let destination = purchase.customer_token_account;
+
+    harness.mint_tokens(destination, 3 * TOKEN).await;
+    // 5% of 100,000 IDR at 1,000 IDR per token
+    harness.purchase(&customer, &purchase, 100_000, None).await.unwrap();
+
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_admin_minted, 3 * TOKEN);
+    assert_eq!(config.total_reward_minted, 5 * TOKEN);
+    assert_eq!(config.total_supply, 8 * TOKEN);
+
+    let instruction = set_mint_caps_instruction(&harness, 4 * TOKEN, 9 * TOKEN);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    // Each cap only sees its own counter: 1 token of admin room, 4 of reward room
+    let instruction = harness.mint_tokens_instruction(&authority, destination, TOKEN + 1);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::AdminMintCapExceeded,
+    );
+    harness.mint_tokens(destination, TOKEN).await;
+
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 100_000, None).await.map(drop),
+        CarsaError::RewardMintCapExceeded,
+    );
+    harness.purchase(&customer, &purchase, 80_000, None).await.unwrap();
+
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_admin_minted, 4 * TOKEN);
+    assert_eq!(config.total_reward_minted, 9 * TOKEN);
+    assert_eq!(config.total_supply, 13 * TOKEN);
+}
+
+fn reconcile_supply_instruction(harness: &Harness, caller: &Pubkey, force: bool) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ReconcileSupply {
+            caller: *caller,
+            config: harness.config,
+            mint: harness.mint,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ReconcileSupply { force }.data(),
+    }
+}
+
+#[tokio::test]
+async fn reconcile_supply_detects_and_repairs_drift() {
+    let (mut harness, customer, purchase) = setup().await;
+    harness.mint_tokens(purchase.customer_token_account, 10 * TOKEN).await;
+
+    // Burned outside the program, so the config never hears about it
+    let burn = spl_token::instruction::burn(
+        &harness.token_program,
+        &purchase.customer_token_account,
+        &harness.mint,
+        &customer.pubkey(),
+        &[],
+        3 * TOKEN,
+    )
+    .unwrap();
+    harness.process(&[burn], &[&customer]).await.unwrap();
+
+    // Checking is permissionless and changes nothing
+    let watcher = harness.funded_user();
+    let check = reconcile_supply_instruction(&harness, &watcher.pubkey(), false);
+    harness.process(&[check], &[&watcher]).await.unwrap();
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_supply - config.total_burned, 10 * TOKEN);
+
+    let repair = reconcile_supply_instruction(&harness, &watcher.pubkey(), true);
+    assert_carsa_error(
+        harness.process(&[repair], &[&watcher]).await,
+        CarsaError::UpdateAuthorityMismatch,
+    );
+
+    let authority = harness.update_authority.pubkey();
+    let repair = reconcile_supply_instruction(&harness, &authority, true);
+    harness.process(&[repair], &[&harness.update_authority]).await.unwrap();
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_supply, 10 * TOKEN);
+    assert_eq!(config.total_burned, 3 * TOKEN);
+
+    // Supply that appeared outside the program is added to `total_supply`
+    let mut mint_account = harness
+        .context
+        .banks_client
+        .get_account(harness.mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+    mint.supply += 2 * TOKEN;
+    spl_token::state::Mint::pack(mint, &mut mint_account.data).unwrap();
+    harness.context.set_account(&harness.mint, &mint_account.into());
+
+    // Identical to the first repair, so it needs a new blockhash
+    harness.wait_for_new_blockhash().await;
+    let repair = reconcile_supply_instruction(&harness, &authority, true);
+    harness.process(&[repair], &[&harness.update_authority]).await.unwrap();
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_supply, 12 * TOKEN);
+    assert_eq!(config.total_burned, 3 * TOKEN);
+    assert_eq!(config.total_supply - config.total_burned, 9 * TOKEN);
+}
+
+/// Points the protocol treasury at a token account owned by the treasury PDA
+async fn set_up_treasury(harness: &Harness) -> Pubkey {
+    let treasury_authority = pda(&[TREASURY_AUTHORITY_SEED]);
+    let treasury_token_account = harness.create_token_account(&treasury_authority).await;
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetProtocolFee {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            treasury_authority,
+            treasury_token_account,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetProtocolFee { protocol_fee_bps: 0 }.data(),
+    };
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    treasury_token_account
+}
+
+fn set_mint_cosigner_instruction(harness: &Harness, mint_cosigner: Pubkey) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetMintCosigner {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetMintCosigner { mint_cosigner, single_sig_mint_limit: 0 }
+            .data(),
+    }
+}
+
+fn restrict_account_instruction(harness: &Harness, token_account: Pubkey) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::RestrictAccount {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            token_account,
+            restriction: pda(&[ACCOUNT_RESTRICTION_SEED, token_account.as_ref()]),
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            token_program: harness.token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::RestrictAccount { reason_code: 7 }.data(),
+    }
+}
+
+fn lift_account_restriction_instruction(harness: &Harness, token_account: Pubkey) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::LiftAccountRestriction {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            token_account,
+            restriction: pda(&[ACCOUNT_RESTRICTION_SEED, token_account.as_ref()]),
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            token_program: harness.token_program,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::LiftAccountRestriction {}.data(),
+    }
+}
+
+fn clawback_instruction(
+    harness: &Harness,
+    cosigner: Option<Pubkey>,
+    token_account: Pubkey,
+    treasury_token_account: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::Clawback {
+            authority: harness.update_authority.pubkey(),
+            cosigner,
+            config: harness.config,
+            token_account,
+            restriction: pda(&[ACCOUNT_RESTRICTION_SEED, token_account.as_ref()]),
+            treasury_token_account,
+            clawback_record: pda(&[CLAWBACK_RECORD_SEED, token_account.as_ref()]),
+            mint: harness.mint,
+            mint_authority: harness.mint_authority,
+            token_program: harness.token_program,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::Clawback {}.data(),
+    }
+}
+
+/// Like `setup`, on a Token-2022 Lokal mint, whose permanent delegate a clawback needs
+async fn setup_clawback() -> (Harness, Keypair, Purchase) {
+    setup_with(Harness::start_with_token_program(spl_token_2022::ID).await).await
+}
+
+#[tokio::test]
+async fn clawback_waits_out_the_blocking_period() {
+    let (mut harness, _, purchase) = setup_clawback().await;
+    let blocked = purchase.customer_token_account;
+    harness.mint_tokens(blocked, 10 * TOKEN).await;
+    let treasury_token_account = set_up_treasury(&harness).await;
+    let cosigner = harness.funded_user();
+    // Minted before the cosigner is set, which mints would otherwise need too
+    let unblocked = harness.create_token_account(&cosigner.pubkey()).await;
+    harness.mint_tokens(unblocked, TOKEN).await;
+    let instruction = set_mint_cosigner_instruction(&harness, cosigner.pubkey());
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let impostor = harness.funded_user();
+
+    // Nothing can be clawed back from an account that was never blocked
+    let clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), unblocked, treasury_token_account);
+    let result = harness.process(&[clawback], &[&harness.update_authority, &cosigner]).await;
+    assert!(result.is_err());
+
+    let instruction = restrict_account_instruction(&harness, blocked);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    assert!(harness.account::<TokenAccount>(blocked).await.is_frozen());
+
+    // The waiting period is checked before the signers, so an unsigned attempt shows it
+    let early = clawback_instruction(&harness, None, blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[early], &[&harness.update_authority]).await,
+        CarsaError::ClawbackWaitingPeriod,
+    );
+    harness.warp_forward(AccountRestriction::CLAWBACK_WAITING_PERIOD_SECONDS - 60).await;
+    let early =
+        clawback_instruction(&harness, Some(impostor.pubkey()), blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[early], &[&harness.update_authority, &impostor]).await,
+        CarsaError::ClawbackWaitingPeriod,
+    );
+
+    harness.warp_forward(60).await;
+    let clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), blocked, treasury_token_account);
+    let supply_before = harness.account::<LokalMintConfig>(harness.config).await.total_supply;
+    harness.process(&[clawback], &[&harness.update_authority, &cosigner]).await.unwrap();
+    assert_eq!(harness.token_balance(treasury_token_account).await, 10 * TOKEN);
+
+    // The balance itself moves, so nothing is minted, and the emptied account stays frozen
+    assert_eq!(harness.token_balance(blocked).await, 0);
+    assert!(harness.account::<TokenAccount>(blocked).await.is_frozen());
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert_eq!(config.total_supply, supply_before);
+
+    let record: ClawbackRecord =
+        harness.account(pda(&[CLAWBACK_RECORD_SEED, blocked.as_ref()])).await;
+    assert_eq!(record.token_account, blocked);
+    assert_eq!(record.owner, purchase.customer);
+    assert_eq!(record.treasury_token_account, treasury_token_account);
+    assert_eq!(record.authority, harness.update_authority.pubkey());
+    assert_eq!(record.cosigner, cosigner.pubkey());
+    assert_eq!(record.amount, 10 * TOKEN);
+    assert_eq!(record.reason_code, 7);
+    assert_eq!(
+        record.timestamp - record.blocked_at,
+        AccountRestriction::CLAWBACK_WAITING_PERIOD_SECONDS
+    );
+
+    // A clawed back account can never be thawed again
+    let lift = lift_account_restriction_instruction(&harness, blocked);
+    assert_carsa_error(
+        harness.process(&[lift], &[&harness.update_authority]).await,
+        CarsaError::AlreadyClawedBack,
+    );
+}
+
+#[tokio::test]
+async fn clawback_requires_the_update_authority_and_mint_cosigner() {
+    let (mut harness, _, purchase) = setup_clawback().await;
+    let blocked = purchase.customer_token_account;
+    harness.mint_tokens(blocked, 4 * TOKEN).await;
+    let treasury_token_account = set_up_treasury(&harness).await;
+    let cosigner = harness.funded_user();
+    let impostor = harness.funded_user();
+
+    let instruction = restrict_account_instruction(&harness, blocked);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    harness.warp_forward(AccountRestriction::CLAWBACK_WAITING_PERIOD_SECONDS).await;
+
+    // Without a configured co-signer there is no dual control, so no clawback at all
+    let clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[clawback], &[&harness.update_authority, &cosigner]).await,
+        CarsaError::ClawbackCosignerRequired,
+    );
+
+    let instruction = set_mint_cosigner_instruction(&harness, cosigner.pubkey());
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let clawback = clawback_instruction(&harness, None, blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[clawback], &[&harness.update_authority]).await,
+        CarsaError::ClawbackCosignerRequired,
+    );
+    let clawback =
+        clawback_instruction(&harness, Some(impostor.pubkey()), blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[clawback], &[&harness.update_authority, &impostor]).await,
+        CarsaError::ClawbackCosignerRequired,
+    );
+
+    let mut clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), blocked, treasury_token_account);
+    clawback.accounts[0].pubkey = impostor.pubkey();
+    assert_carsa_error(
+        harness.process(&[clawback], &[&impostor, &cosigner]).await,
+        CarsaError::UpdateAuthorityMismatch,
+    );
+
+    let clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), blocked, treasury_token_account);
+    harness.process(&[clawback], &[&harness.update_authority, &cosigner]).await.unwrap();
+    assert_eq!(harness.token_balance(treasury_token_account).await, 4 * TOKEN);
+}
+
+#[tokio::test]
+async fn clawback_rejects_a_legacy_mint() {
+    let (mut harness, _, purchase) = setup().await;
+    let blocked = purchase.customer_token_account;
+    harness.mint_tokens(blocked, 4 * TOKEN).await;
+    let treasury_token_account = set_up_treasury(&harness).await;
+    let cosigner = harness.funded_user();
+    let instruction = set_mint_cosigner_instruction(&harness, cosigner.pubkey());
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let instruction = restrict_account_instruction(&harness, blocked);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    harness.warp_forward(AccountRestriction::CLAWBACK_WAITING_PERIOD_SECONDS).await;
+
+    // A classic SPL mint has no permanent delegate to move the balance with, and the
+    // treasury is never minted a replacement
+    let clawback =
+        clawback_instruction(&harness, Some(cosigner.pubkey()), blocked, treasury_token_account);
+    assert_carsa_error(
+        harness.process(&[clawback], &[&harness.update_authority, &cosigner]).await,
+        CarsaError::ClawbackUnsupportedMint,
+    );
+    assert_eq!(harness.token_balance(treasury_token_account).await, 0);
+    assert_eq!(harness.token_balance(blocked).await, 4 * TOKEN);
+}
+
+#[tokio::test]
+async fn lifting_a_restriction_thaws_the_account() {
+    let (mut harness, customer, purchase) = setup().await;
+    let recipient = harness.funded_user();
+    let recipient_token_account = harness.create_token_account(&recipient.pubkey()).await;
+    let blocked = purchase.customer_token_account;
+    harness.mint_tokens(blocked, 3 * TOKEN).await;
+
+    let instruction = restrict_account_instruction(&harness, blocked);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    assert!(harness
+        .transfer_tokens(&customer, blocked, recipient_token_account, TOKEN)
+        .await
+        .is_err());
+
+    let instruction = lift_account_restriction_instruction(&harness, blocked);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    assert!(!harness.account::<TokenAccount>(blocked).await.is_frozen());
+    assert_eq!(
+        harness.lamports(pda(&[ACCOUNT_RESTRICTION_SEED, blocked.as_ref()])).await,
+        0
+    );
+    harness.transfer_tokens(&customer, blocked, recipient_token_account, TOKEN).await.unwrap();
+}
+
+fn category_boost_address(category: &str) -> Pubkey {
+    pda(&[CATEGORY_BOOST_SEED, &MerchantAccount::category_bytes(category)])
+}
+
+/// Sets a `budget` boost of `bonus_bps` for `category` over `[start, end)`
+fn set_category_boost_instruction(
+    harness: &Harness,
+    category: &str,
+    bonus_bps: u16,
+    start: i64,
+    end: i64,
+    budget: u64,
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetCategoryBoost {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            category_boost: category_boost_address(category),
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetCategoryBoost {
+            category: category.to_string(),
+            bonus_bps,
+            start,
+            end,
+            budget,
+        }
+        .data(),
+    }
+}
+
+/// Purchases 100,000 IDR passing `category_boost` and returns the purchase record
+async fn boosted_purchase(
+    harness: &Harness,
+    customer: &Keypair,
+    purchase: &Purchase,
+    category_boost: Pubkey,
+) -> Result<Pubkey, BanksClientError> {
+    let transaction_id = unique_id();
+    let mut accounts = harness.process_purchase_accounts(purchase, transaction_id);
+    accounts.category_boost = Some(category_boost);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: accounts.to_account_metas(None),
+        data: carsa::instruction::ProcessPurchase {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+            transaction_id,
+            expected_nonce: None,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[customer]).await?;
+    Ok(transaction_record_address(&purchase.merchant_account, &customer.pubkey(), &transaction_id))
+}
+
+#[tokio::test]
+async fn category_boost_adds_to_the_merchant_rate_until_its_budget_runs_out() {
+    let (harness, customer, purchase) = setup().await;
+    let now = harness.clock().await.unix_timestamp;
+    let instruction =
+        set_category_boost_instruction(&harness, "retail", 200, now, now + 3_600, 3 * TOKEN);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let category_boost = category_boost_address("retail");
+
+    // 5% from the merchant and 2% from the boost on 100,000 IDR
+    let record = boosted_purchase(&harness, &customer, &purchase, category_boost).await.unwrap();
+    let record: PurchaseTransaction = harness.account(record).await;
+    assert_eq!(record.cashback_rate, 500);
+    assert_eq!((record.category_boost_bps, record.category_boost_reward), (200, 2 * TOKEN));
+    assert_eq!(record.reward_amount, 7 * TOKEN);
+
+    // Only 1 token of the budget is left, so the second boost is clamped to it
+    let record = boosted_purchase(&harness, &customer, &purchase, category_boost).await.unwrap();
+    let record: PurchaseTransaction = harness.account(record).await;
+    assert_eq!((record.category_boost_bps, record.category_boost_reward), (200, TOKEN));
+    assert_eq!(record.reward_amount, 6 * TOKEN);
+    let boost: CategoryBoost = harness.account(category_boost).await;
+    assert_eq!(boost.spent, 3 * TOKEN);
+
+    let record = boosted_purchase(&harness, &customer, &purchase, category_boost).await.unwrap();
+    let record: PurchaseTransaction = harness.account(record).await;
+    assert_eq!((record.category_boost_bps, record.category_boost_reward), (0, 0));
+    assert_eq!(harness.token_balance(purchase.customer_token_account).await, 18 * TOKEN);
+}
+
+/// Simulates `preview_purchase_reward` for the 100,000 IDR `boosted_purchase` makes
+async fn preview_boosted_purchase(
+    harness: &Harness,
+    purchase: &Purchase,
+    category_boost: Pubkey,
+) -> PurchaseRewardPreview {
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::PreviewPurchaseReward {
+            customer: purchase.customer,
+            merchant_account: purchase.merchant_account,
+            config: harness.config,
+            stake_pool: None,
+            customer_stake_record: None,
+            price_feed: None,
+            category_boost: Some(category_boost),
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::PreviewPurchaseReward {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+        }
+        .data(),
+    };
+    let mut banks_client = harness.context.banks_client.clone();
+    let blockhash = banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&harness.context.payer.pubkey()),
+        &[&harness.context.payer],
+        blockhash,
+    );
+    let simulation = banks_client.simulate_transaction(transaction).await.unwrap();
+    simulation.result.unwrap().unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    PurchaseRewardPreview::try_from_slice(&return_data.data).unwrap()
+}
+
+#[tokio::test]
+async fn preview_matches_a_purchase_with_a_live_category_boost() {
+    let (harness, customer, purchase) = setup().await;
+    let now = harness.clock().await.unix_timestamp;
+    let instruction =
+        set_category_boost_instruction(&harness, "retail", 200, now, now + 3_600, 3 * TOKEN);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let category_boost = category_boost_address("retail");
+
+    // The second purchase is clamped to what is left of the budget, and the preview with it
+    for (boost_reward, reward) in [(2 * TOKEN, 7 * TOKEN), (TOKEN, 6 * TOKEN)] {
+        let spent = harness.account::<CategoryBoost>(category_boost).await.spent;
+        let preview = preview_boosted_purchase(&harness, &purchase, category_boost).await;
+        assert_eq!(preview.category_boost_bps, 200);
+        assert_eq!(preview.category_boost_reward, boost_reward);
+        assert_eq!(preview.reward_amount, reward);
+        assert_eq!(harness.account::<CategoryBoost>(category_boost).await.spent, spent);
+
+        let record =
+            boosted_purchase(&harness, &customer, &purchase, category_boost).await.unwrap();
+        let record: PurchaseTransaction = harness.account(record).await;
+        assert_eq!(record.reward_amount, preview.reward_amount);
+        assert_eq!(record.category_boost_bps, preview.category_boost_bps);
+        assert_eq!(record.category_boost_reward, preview.category_boost_reward);
+    }
+}
+
+#[tokio::test]
+async fn expired_category_boost_is_ignored() {
+    let (mut harness, customer, purchase) = setup().await;
+    let now = harness.clock().await.unix_timestamp;
+    let instruction =
+        set_category_boost_instruction(&harness, "retail", 200, now, now + 60, 10 * TOKEN);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    harness.warp_forward(120).await;
+
+    let category_boost = category_boost_address("retail");
+    let record = boosted_purchase(&harness, &customer, &purchase, category_boost).await.unwrap();
+    let record: PurchaseTransaction = harness.account(record).await;
+    assert_eq!((record.category_boost_bps, record.category_boost_reward), (0, 0));
+    assert_eq!(record.reward_amount, 5 * TOKEN);
+    let boost: CategoryBoost = harness.account(category_boost).await;
+    assert_eq!(boost.spent, 0);
+}
+
+#[tokio::test]
+async fn category_boost_for_another_category_is_rejected() {
+    let (harness, customer, purchase) = setup().await;
+    let now = harness.clock().await.unix_timestamp;
+    let instruction =
+        set_category_boost_instruction(&harness, "bookstore", 200, now, now + 3_600, 10 * TOKEN);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+
+    let category_boost = category_boost_address("bookstore");
+    assert_carsa_error(
+        boosted_purchase(&harness, &customer, &purchase, category_boost).await.map(drop),
+        CarsaError::CategoryBoostMismatch,
+    );
+}
+
+const DAY: i64 = 86_400;
+
+/// A harness with a 5% cashback merchant paying `10 * TOKEN` for `streak_target` visits
+/// within `streak_window_seconds`, and a customer, each holding a Lokal token account
+async fn setup_streak(
+    streak_target: u16,
+    streak_window_seconds: u32,
+) -> (Harness, Keypair, Purchase) {
+    let mut harness = Harness::start().await;
+    let merchant_owner = harness.funded_user();
+    let customer = harness.funded_user();
+    let merchant_account = harness.register_merchant(&merchant_owner, 500, 0).await;
+
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetStreakReward {
+            merchant_owner: merchant_owner.pubkey(),
+            merchant_account,
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetStreakReward {
+            streak_target,
+            streak_window_seconds,
+            streak_bonus: 10 * TOKEN,
+            streak_counts_every_purchase: false,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[&merchant_owner]).await.unwrap();
+
+    let purchase = Purchase {
+        customer: customer.pubkey(),
+        customer_token_account: harness.create_token_account(&customer.pubkey()).await,
+        merchant_account,
+        merchant_token_account: harness.create_token_account(&merchant_owner.pubkey()).await,
+    };
+    (harness, customer, purchase)
+}
+
+fn customer_merchant_stats_address(purchase: &Purchase) -> Pubkey {
+    pda(&[
+        CUSTOMER_MERCHANT_STATS_SEED,
+        purchase.merchant_account.as_ref(),
+        purchase.customer.as_ref(),
+    ])
+}
+
+/// Purchases 100,000 IDR passing the customer's visit stats and returns the streak bonus
+/// the purchase record shows
+async fn streak_purchase(harness: &Harness, customer: &Keypair, purchase: &Purchase) -> u64 {
+    let transaction_id = unique_id();
+    let mut accounts = harness.process_purchase_accounts(purchase, transaction_id);
+    accounts.customer_merchant_stats = Some(customer_merchant_stats_address(purchase));
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: accounts.to_account_metas(None),
+        data: carsa::instruction::ProcessPurchase {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+            transaction_id,
+            expected_nonce: None,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[customer]).await.unwrap();
+    let record =
+        transaction_record_address(&purchase.merchant_account, &customer.pubkey(), &transaction_id);
+    harness.account::<PurchaseTransaction>(record).await.streak_bonus
+}
+
+#[tokio::test]
+async fn visit_streak_completes_and_pays_the_bonus() {
+    let (mut harness, customer, purchase) = setup_streak(3, 30 * DAY as u32).await;
+
+    // The merchant's streak needs the customer's visit stats
+    assert_carsa_error(
+        harness.purchase(&customer, &purchase, 100_000, None).await.map(drop),
+        CarsaError::InvalidCustomerMerchantStats,
+    );
+
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    harness.warp_forward(DAY).await;
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    harness.warp_forward(DAY).await;
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 10 * TOKEN);
+    assert_eq!(
+        harness.token_balance(purchase.customer_token_account).await,
+        3 * 5 * TOKEN + 10 * TOKEN
+    );
+
+    let stats: CustomerMerchantStats =
+        harness.account(customer_merchant_stats_address(&purchase)).await;
+    assert_eq!((stats.visit_count, stats.streaks_completed), (0, 1));
+}
+
+#[tokio::test]
+async fn visit_streak_restarts_once_its_window_runs_out() {
+    let (mut harness, customer, purchase) = setup_streak(2, 2 * DAY as u32).await;
+
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    harness.warp_forward(3 * DAY).await;
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    let stats: CustomerMerchantStats =
+        harness.account(customer_merchant_stats_address(&purchase)).await;
+    assert_eq!(stats.visit_count, 1);
+
+    harness.warp_forward(DAY).await;
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 10 * TOKEN);
+}
+
+#[tokio::test]
+async fn visit_streak_counts_purchases_on_one_day_once() {
+    let (mut harness, customer, purchase) = setup_streak(2, 30 * DAY as u32).await;
+
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 0);
+    let stats: CustomerMerchantStats =
+        harness.account(customer_merchant_stats_address(&purchase)).await;
+    assert_eq!(stats.visit_count, 1);
+
+    harness.warp_forward(DAY).await;
+    assert_eq!(streak_purchase(&harness, &customer, &purchase).await, 10 * TOKEN);
+}
+
+const CUSTOMER_HASH_SALT: [u8; 32] = [9; 32];
+
+fn set_customer_hashing_instruction(
+    harness: &Harness,
+    enabled: bool,
+    salt: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetCustomerHashing {
+            authority: harness.update_authority.pubkey(),
+            config: harness.config,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetCustomerHashing { enabled, salt }.data(),
+    }
+}
+
+/// Purchases 100,000 IDR with the record and stub at `recorded_customer`'s addresses and
+/// returns the record
+async fn purchase_recorded_as(
+    harness: &Harness,
+    customer: &Keypair,
+    purchase: &Purchase,
+    recorded_customer: &Pubkey,
+) -> Result<Pubkey, BanksClientError> {
+    let transaction_id = unique_id();
+    let record =
+        transaction_record_address(&purchase.merchant_account, recorded_customer, &transaction_id);
+    let mut accounts = harness.process_purchase_accounts(purchase, transaction_id);
+    accounts.transaction_record = Some(record);
+    accounts.transaction_stub = Some(transaction_stub_address(
+        &purchase.merchant_account,
+        recorded_customer,
+        &transaction_id,
+    ));
+    accounts.reference_index = Some(reference_index_address(&record));
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: accounts.to_account_metas(None),
+        data: carsa::instruction::ProcessPurchase {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+            transaction_id,
+            expected_nonce: None,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[customer]).await?;
+    Ok(record)
+}
+
+#[tokio::test]
+async fn privacy_mode_records_the_customer_by_a_stable_hash() {
+    let (harness, customer, purchase) = setup().await;
+    let hash = carsa_client::pda::customer_hash(&customer.pubkey(), &CUSTOMER_HASH_SALT);
+
+    // Off by default: the record is at, and holds, the wallet
+    let record = purchase_recorded_as(&harness, &customer, &purchase, &customer.pubkey())
+        .await
+        .unwrap();
+    let transaction: PurchaseTransaction = harness.account(record).await;
+    assert_eq!(transaction.customer, customer.pubkey());
+
+    let instruction = set_customer_hashing_instruction(&harness, true, CUSTOMER_HASH_SALT);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert!(config.hash_customer_in_records);
+    assert_eq!(config.customer_hash(&customer.pubkey()), hash);
+
+    // The wallet's addresses no longer satisfy the purchase
+    let result = purchase_recorded_as(&harness, &customer, &purchase, &customer.pubkey()).await;
+    assert!(result.is_err());
+
+    // Every purchase maps the customer to the same hash, which the client computes too
+    for _ in 0..2 {
+        let record = purchase_recorded_as(&harness, &customer, &purchase, &hash).await.unwrap();
+        let transaction: PurchaseTransaction = harness.account(record).await;
+        assert_eq!(transaction.customer, hash);
+        let stub_address = transaction_stub_address(
+            &purchase.merchant_account,
+            &hash,
+            &transaction.transaction_id,
+        );
+        let stub: TransactionStub = harness.account(stub_address).await;
+        assert_eq!(stub.customer, hash);
+    }
+    assert_eq!(
+        harness.token_balance(purchase.customer_token_account).await,
+        3 * 5 * TOKEN
+    );
+}
+
+#[tokio::test]
+async fn customer_hash_salt_cannot_change_once_set() {
+    let (harness, _, _) = setup().await;
+
+    // An all-zero salt means none is set yet, so it cannot switch hashing on
+    let instruction = set_customer_hashing_instruction(&harness, true, [0; 32]);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::InvalidCustomerHashSalt,
+    );
+
+    let instruction = set_customer_hashing_instruction(&harness, true, CUSTOMER_HASH_SALT);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let instruction = set_customer_hashing_instruction(&harness, true, [7; 32]);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&harness.update_authority]).await,
+        CarsaError::CustomerHashSaltLocked,
+    );
+
+    // Switching off keeps the salt, so switching back on restores the same hashes
+    let instruction = set_customer_hashing_instruction(&harness, false, [0; 32]);
+    harness.process(&[instruction], &[&harness.update_authority]).await.unwrap();
+    let config: LokalMintConfig = harness.account(harness.config).await;
+    assert!(!config.hash_customer_in_records);
+    assert_eq!(config.customer_hash_salt, CUSTOMER_HASH_SALT);
+}
+
+#[tokio::test]
+async fn privacy_mode_keys_activity_and_reward_lots_by_the_hash() {
+    let (harness, customer, purchase) = setup().await;
+    let hash = carsa_client::pda::customer_hash(&customer.pubkey(), &CUSTOMER_HASH_SALT);
+    let reward_escrow = pda(&[REWARD_ESCROW_SEED]);
+
+    let instructions = [
+        set_customer_hashing_instruction(&harness, true, CUSTOMER_HASH_SALT),
+        Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::SetActivityRecording {
+                authority: harness.update_authority.pubkey(),
+                config: harness.config,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::SetActivityRecording { enabled: true }.data(),
+        },
+        Instruction {
+            program_id: carsa::ID,
+            accounts: carsa::accounts::SetRewardExpiry {
+                authority: harness.update_authority.pubkey(),
+                config: harness.config,
+                mint: harness.mint,
+                mint_authority: harness.mint_authority,
+                reward_escrow,
+                token_program: harness.token_program,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: carsa::instruction::SetRewardExpiry {
+                enabled: true,
+                reward_lifetime_seconds: 86_400,
+            }
+            .data(),
+        },
+    ];
+    harness.process(&instructions, &[&harness.update_authority]).await.unwrap();
+
+    let transaction_id = unique_id();
+    let record = transaction_record_address(&purchase.merchant_account, &hash, &transaction_id);
+    let (activity_record, _) = carsa_client::pda::find_activity_pda(
+        &hash,
+        &transaction_id,
+        ActivityKind::Purchase as u8,
+    );
+    let (reward_lot, _) = carsa_client::pda::find_reward_lot_pda(&hash, &transaction_id);
+    let mut accounts = harness.process_purchase_accounts(&purchase, transaction_id);
+    accounts.transaction_record = Some(record);
+    accounts.transaction_stub =
+        Some(transaction_stub_address(&purchase.merchant_account, &hash, &transaction_id));
+    accounts.reference_index = Some(reference_index_address(&record));
+    accounts.activity_record = Some(activity_record);
+    accounts.reward_lot = Some(reward_lot);
+    accounts.reward_escrow = Some(reward_escrow);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: accounts.to_account_metas(None),
+        data: carsa::instruction::ProcessPurchase {
+            fiat_amount: 100_000,
+            redeem_token_amount: None,
+            transaction_id,
+            expected_nonce: None,
+        }
+        .data(),
+    };
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+
+    // Neither account names the wallet next to the merchant
+    let activity: ActivityRecord = harness.account(activity_record).await;
+    assert_eq!(activity.wallet, hash);
+    assert_eq!(activity.counterparty, purchase.merchant_account);
+    let lot: RewardLot = harness.account(reward_lot).await;
+    assert_eq!(lot.customer, hash);
+
+    // The customer still withdraws the lot with their own signature
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::WithdrawVestedRewards {
+            customer: customer.pubkey(),
+            mint_authority: harness.mint_authority,
+            config: harness.config,
+            reward_escrow,
+            customer_token_account: purchase.customer_token_account,
+            mint: harness.mint,
+            reward_lot,
+            token_program: harness.token_program,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::WithdrawVestedRewards {}.data(),
+    };
+    harness.process(&[instruction], &[&customer]).await.unwrap();
+    assert_eq!(harness.token_balance(purchase.customer_token_account).await, 5 * TOKEN);
+}
@@ -0,0 +1,162 @@
+//! Merchant operators: wallets acting for a merchant at the till, limited to actions that
+//! move no funds, such as creating invoices
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_sdk::signature::{Keypair, Signer};
+
+fn operator_address(merchant_account: &Pubkey, operator: &Pubkey) -> Pubkey {
+    pda(&[MERCHANT_OPERATOR_SEED, merchant_account.as_ref(), operator.as_ref()])
+}
+
+fn add_operator_instruction(owner: &Pubkey, operator: &Pubkey) -> Instruction {
+    let merchant_account = merchant_address(owner);
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::AddMerchantOperator {
+            merchant_owner: *owner,
+            merchant_account,
+            merchant_operator: operator_address(&merchant_account, operator),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::AddMerchantOperator { operator: *operator }.data(),
+    }
+}
+
+fn remove_operator_instruction(owner: &Pubkey, operator: &Pubkey) -> Instruction {
+    let merchant_account = merchant_address(owner);
+    Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::RemoveMerchantOperator {
+            merchant_owner: *owner,
+            merchant_account,
+            merchant_operator: operator_address(&merchant_account, operator),
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::RemoveMerchantOperator {}.data(),
+    }
+}
+
+/// `create_invoice` for the merchant signed by `signer`, passing its operator account if
+/// `as_operator`, along with the invoice address
+fn create_invoice_instruction(
+    merchant_account: Pubkey,
+    signer: &Pubkey,
+    as_operator: bool,
+    expires_at: i64,
+) -> (Instruction, Pubkey) {
+    let reference_id = unique_id();
+    let invoice = pda(&[INVOICE_SEED, merchant_account.as_ref(), &reference_id]);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::CreateInvoice {
+            merchant_owner: *signer,
+            merchant_account,
+            invoice,
+            system_program: system_program::ID,
+            merchant_operator: as_operator.then(|| operator_address(&merchant_account, signer)),
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::CreateInvoice {
+            reference_id,
+            fiat_amount: 50_000,
+            max_redeem_token_amount: None,
+            expires_at,
+        }
+        .data(),
+    };
+    (instruction, invoice)
+}
+
+/// A merchant with an operator added by its owner
+async fn setup() -> (Harness, Keypair, Keypair, Pubkey) {
+    let mut harness = Harness::start().await;
+    let owner = harness.funded_user();
+    let operator = harness.funded_user();
+
+    let merchant_account = harness.register_merchant(&owner, 500, 0).await;
+    harness
+        .process(&[add_operator_instruction(&owner.pubkey(), &operator.pubkey())], &[&owner])
+        .await
+        .unwrap();
+
+    (harness, owner, operator, merchant_account)
+}
+
+#[tokio::test]
+async fn operator_creates_an_invoice() {
+    let (harness, _, operator, merchant_account) = setup().await;
+
+    let added: MerchantOperator = harness
+        .account(operator_address(&merchant_account, &operator.pubkey()))
+        .await;
+    assert_eq!(added.merchant, merchant_account);
+    assert_eq!(added.operator, operator.pubkey());
+
+    let expires_at = harness.clock().await.unix_timestamp + 600;
+    let (instruction, invoice) =
+        create_invoice_instruction(merchant_account, &operator.pubkey(), true, expires_at);
+    harness.process(&[instruction], &[&operator]).await.unwrap();
+
+    let invoice: Invoice = harness.account(invoice).await;
+    assert_eq!(invoice.merchant, merchant_account);
+    assert_eq!(invoice.fiat_amount, 50_000);
+}
+
+#[tokio::test]
+async fn operator_cannot_update_the_merchant() {
+    let (harness, _, operator, merchant_account) = setup().await;
+
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::UpdateMerchant {
+            merchant_owner: operator.pubkey(),
+            merchant_account,
+            config: harness.config,
+            update_authority: None,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::UpdateMerchant {
+            new_cashback_rate: Some(1_000),
+            is_active: None,
+            min_purchase_amount: None,
+            redemption_bonus_bps: None,
+            require_merchant_signature: None,
+            compressed_receipts: None,
+        }
+        .data(),
+    };
+    assert!(harness.process(&[instruction], &[&operator]).await.is_err());
+
+    let merchant: MerchantAccount = harness.account(merchant_account).await;
+    assert_eq!(merchant.cashback_rate, 500);
+}
+
+#[tokio::test]
+async fn removed_operator_loses_access() {
+    let (harness, owner, operator, merchant_account) = setup().await;
+    harness
+        .process(&[remove_operator_instruction(&owner.pubkey(), &operator.pubkey())], &[&owner])
+        .await
+        .unwrap();
+
+    let expires_at = harness.clock().await.unix_timestamp + 600;
+    let (instruction, _) =
+        create_invoice_instruction(merchant_account, &operator.pubkey(), true, expires_at);
+    assert!(harness.process(&[instruction], &[&operator]).await.is_err());
+
+    // Without the operator account the signer is just another wallet
+    let (instruction, _) =
+        create_invoice_instruction(merchant_account, &operator.pubkey(), false, expires_at);
+    assert_carsa_error(
+        harness.process(&[instruction], &[&operator]).await,
+        CarsaError::MerchantOwnerMismatch,
+    );
+}
@@ -0,0 +1,429 @@
+//! Pool SOL deployed into an SPL stake pool and unwound again, against a mock stake-pool
+//! program that keeps the real account layout, instruction encoding and exchange rate
+//!
+//! Only built with the `stake-pool` feature:
+//! `cargo test -p carsa-program-tests --features stake-pool`
+#![cfg(feature = "stake-pool")]
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use carsa::error::CarsaError;
+use carsa::stake_pool::{AUTHORITY_WITHDRAW, STAKE_PROGRAM_ID};
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_program_test::{processor, BanksClientError};
+use solana_sdk::account::{AccountSharedData, ReadableAccount};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+
+/// Offsets of the stake pool fields the mock reads and writes, as laid out by SPL stake-pool
+const WITHDRAW_BUMP_OFFSET: usize = 97;
+const RESERVE_STAKE_OFFSET: usize = 130;
+const POOL_MINT_OFFSET: usize = 162;
+const MANAGER_FEE_ACCOUNT_OFFSET: usize = 194;
+const TOKEN_PROGRAM_OFFSET: usize = 226;
+const TOTAL_LAMPORTS_OFFSET: usize = 258;
+const POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+const STAKE_POOL_LEN: usize = 611;
+
+/// The mock starts at 1.05 SOL per pool token, as a pool that has already earned rewards
+const INITIAL_TOTAL_LAMPORTS: u64 = 1_050 * LAMPORTS_PER_SOL;
+const INITIAL_POOL_TOKEN_SUPPLY: u64 = 1_000 * LAMPORTS_PER_SOL;
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn write_u64(data: &mut [u8], offset: usize, value: u64) {
+    data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+/// `DepositSol` and `WithdrawSol` of SPL stake-pool, without fees: deposits move the
+/// lamports into the reserve and mint at the pool's rate, withdrawals burn and pay out of
+/// the reserve at the same rate
+fn process_stake_pool(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let amount = u64::from_le_bytes(data[1..9].try_into().unwrap());
+    let (stake_pool, withdraw_authority) = (&accounts[0], &accounts[1]);
+    let (bump, total_lamports, pool_token_supply) = {
+        let pool_data = stake_pool.try_borrow_data()?;
+        (
+            pool_data[WITHDRAW_BUMP_OFFSET],
+            read_u64(&pool_data, TOTAL_LAMPORTS_OFFSET),
+            read_u64(&pool_data, POOL_TOKEN_SUPPLY_OFFSET),
+        )
+    };
+    let authority_seeds: &[&[u8]] = &[stake_pool.key.as_ref(), AUTHORITY_WITHDRAW, &[bump]];
+
+    let (total_lamports, pool_token_supply) = match data[0] {
+        14 => {
+            let [_, _, reserve, from, destination, _, _, mint, system, token_program] = accounts
+            else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let pool_tokens =
+                (amount as u128 * pool_token_supply as u128 / total_lamports as u128) as u64;
+            invoke(
+                &system_instruction::transfer(from.key, reserve.key, amount),
+                &[from.clone(), reserve.clone(), system.clone()],
+            )?;
+            invoke_signed(
+                &spl_token::instruction::mint_to(
+                    token_program.key,
+                    mint.key,
+                    destination.key,
+                    withdraw_authority.key,
+                    &[],
+                    pool_tokens,
+                )?,
+                &[
+                    mint.clone(),
+                    destination.clone(),
+                    withdraw_authority.clone(),
+                    token_program.clone(),
+                ],
+                &[authority_seeds],
+            )?;
+            (total_lamports + amount, pool_token_supply + pool_tokens)
+        }
+        16 => {
+            let [_, _, token_authority, source, reserve, to, _, mint, _, _, _, token_program] =
+                accounts
+            else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            let lamports =
+                (amount as u128 * total_lamports as u128 / pool_token_supply as u128) as u64;
+            invoke(
+                &spl_token::instruction::burn(
+                    token_program.key,
+                    source.key,
+                    mint.key,
+                    token_authority.key,
+                    &[],
+                    amount,
+                )?,
+                &[
+                    source.clone(),
+                    mint.clone(),
+                    token_authority.clone(),
+                    token_program.clone(),
+                ],
+            )?;
+            **reserve.try_borrow_mut_lamports()? -= lamports;
+            **to.try_borrow_mut_lamports()? += lamports;
+            (total_lamports - lamports, pool_token_supply - amount)
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let mut pool_data = stake_pool.try_borrow_mut_data()?;
+    write_u64(&mut pool_data, TOTAL_LAMPORTS_OFFSET, total_lamports);
+    write_u64(&mut pool_data, POOL_TOKEN_SUPPLY_OFFSET, pool_token_supply);
+    Ok(())
+}
+
+/// A mock stake pool and the accounts it names
+#[derive(Clone, Copy)]
+struct StakePool {
+    program_id: Pubkey,
+    address: Pubkey,
+    withdraw_authority: Pubkey,
+    reserve: Pubkey,
+    mint: Pubkey,
+    manager_fee_account: Pubkey,
+}
+
+/// The carsa accounts of a pool's deployment
+struct Deployment {
+    address: Pubkey,
+    sol_vault: Pubkey,
+    pool_token_account: Pubkey,
+}
+
+impl Deployment {
+    fn of(pool: &Pool) -> Self {
+        Deployment {
+            address: pda(&[STAKE_POOL_DEPLOYMENT_SEED, pool.state.as_ref()]),
+            sol_vault: pda(&[POOL_SOL_VAULT_SEED, pool.state.as_ref()]),
+            pool_token_account: pda(&[STAKE_POOL_TOKENS_SEED, pool.state.as_ref()]),
+        }
+    }
+}
+
+/// A harness running the mock stake-pool program, with pool 1, an initialized stake pool
+/// and 10 SOL waiting in the pool's SOL vault
+async fn setup() -> (Harness, Pool, StakePool) {
+    let stake_pool_program = Keypair::new().pubkey();
+    let mut program_test = Harness::program_test();
+    program_test.add_program("stake_pool", stake_pool_program, processor!(process_stake_pool));
+    let mut harness = Harness::start_with(program_test).await;
+    let pool = harness.initialize_pool(1, pool_config()).await;
+
+    let address = Keypair::new().pubkey();
+    let (withdraw_authority, bump) =
+        Pubkey::find_program_address(&[address.as_ref(), AUTHORITY_WITHDRAW], &stake_pool_program);
+    let stake_pool = StakePool {
+        program_id: stake_pool_program,
+        address,
+        withdraw_authority,
+        reserve: Keypair::new().pubkey(),
+        mint: create_pool_mint(&harness, &withdraw_authority).await,
+        manager_fee_account: Keypair::new().pubkey(),
+    };
+
+    let mut data = vec![0u8; STAKE_POOL_LEN];
+    data[0] = 1;
+    data[WITHDRAW_BUMP_OFFSET] = bump;
+    for (offset, key) in [
+        (RESERVE_STAKE_OFFSET, stake_pool.reserve),
+        (POOL_MINT_OFFSET, stake_pool.mint),
+        (MANAGER_FEE_ACCOUNT_OFFSET, stake_pool.manager_fee_account),
+        (TOKEN_PROGRAM_OFFSET, spl_token::ID),
+    ] {
+        data[offset..offset + 32].copy_from_slice(key.as_ref());
+    }
+    write_u64(&mut data, TOTAL_LAMPORTS_OFFSET, INITIAL_TOTAL_LAMPORTS);
+    write_u64(&mut data, POOL_TOKEN_SUPPLY_OFFSET, INITIAL_POOL_TOKEN_SUPPLY);
+    let mut account = AccountSharedData::new(LAMPORTS_PER_SOL, STAKE_POOL_LEN, &stake_pool_program);
+    account.set_data_from_slice(&data);
+    harness.context.set_account(&stake_pool.address, &account);
+    harness.context.set_account(
+        &stake_pool.reserve,
+        &AccountSharedData::new(INITIAL_TOTAL_LAMPORTS, 0, &stake_pool_program),
+    );
+
+    harness.fund(&Deployment::of(&pool).sol_vault);
+    (harness, pool, stake_pool)
+}
+
+/// Creates the stake pool's token mint, with the withdraw authority minting
+async fn create_pool_mint(harness: &Harness, withdraw_authority: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let payer = harness.context.payer.pubkey();
+    let rent = harness.context.banks_client.clone().get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            withdraw_authority,
+            None,
+            9,
+        )
+        .unwrap(),
+    ];
+    harness.process(&instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+/// Credits the stake pool with `lamports` of staking rewards, raising its exchange rate
+async fn accrue_rewards(harness: &mut Harness, stake_pool: &StakePool, lamports: u64) {
+    let mut banks_client = harness.context.banks_client.clone();
+    let mut account: AccountSharedData =
+        banks_client.get_account(stake_pool.address).await.unwrap().unwrap().into();
+    let mut data = account.data().to_vec();
+    let total_lamports = read_u64(&data, TOTAL_LAMPORTS_OFFSET);
+    write_u64(&mut data, TOTAL_LAMPORTS_OFFSET, total_lamports + lamports);
+    account.set_data_from_slice(&data);
+    harness.context.set_account(&stake_pool.address, &account);
+
+    let reserve = harness.lamports(stake_pool.reserve).await;
+    harness.context.set_account(
+        &stake_pool.reserve,
+        &AccountSharedData::new(reserve + lamports, 0, &stake_pool.program_id),
+    );
+}
+
+async fn configure_stake_pool(
+    harness: &Harness,
+    pool: &Pool,
+    stake_pool: &StakePool,
+) -> Result<(), BanksClientError> {
+    let deployment = Deployment::of(pool);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ConfigureStakePool {
+            pool_authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            stake_pool_deployment: deployment.address,
+            stake_pool_program: stake_pool.program_id,
+            stake_pool: stake_pool.address,
+            pool_token_mint: stake_pool.mint,
+            pool_token_account: deployment.pool_token_account,
+            sol_vault: deployment.sol_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ConfigureStakePool {}.data(),
+    };
+    harness.process(&[instruction], &[&pool.authority]).await
+}
+
+async fn deploy_to_stake_pool(
+    harness: &Harness,
+    pool: &Pool,
+    stake_pool: &StakePool,
+    lamports: u64,
+) -> Result<(), BanksClientError> {
+    let deployment = Deployment::of(pool);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::DeployToStakePool {
+            pool_authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            stake_pool_deployment: deployment.address,
+            sol_vault: deployment.sol_vault,
+            stake_pool_program: stake_pool.program_id,
+            stake_pool: stake_pool.address,
+            stake_pool_withdraw_authority: stake_pool.withdraw_authority,
+            reserve_stake: stake_pool.reserve,
+            manager_fee_account: stake_pool.manager_fee_account,
+            pool_token_mint: stake_pool.mint,
+            pool_token_account: deployment.pool_token_account,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::DeployToStakePool { lamports }.data(),
+    };
+    harness.process(&[instruction], &[&pool.authority]).await
+}
+
+async fn unwind_from_stake_pool(
+    harness: &Harness,
+    pool: &Pool,
+    stake_pool: &StakePool,
+    pool_tokens: u64,
+) -> Result<(), BanksClientError> {
+    let deployment = Deployment::of(pool);
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::UnwindFromStakePool {
+            pool_authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            stake_pool_deployment: deployment.address,
+            sol_vault: deployment.sol_vault,
+            stake_pool_program: stake_pool.program_id,
+            stake_pool: stake_pool.address,
+            stake_pool_withdraw_authority: stake_pool.withdraw_authority,
+            reserve_stake: stake_pool.reserve,
+            manager_fee_account: stake_pool.manager_fee_account,
+            pool_token_mint: stake_pool.mint,
+            pool_token_account: deployment.pool_token_account,
+            clock: sysvar::clock::ID,
+            stake_history: sysvar::stake_history::ID,
+            stake_program: STAKE_PROGRAM_ID,
+            token_program: spl_token::ID,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::UnwindFromStakePool { pool_tokens }.data(),
+    };
+    harness.process(&[instruction], &[&pool.authority]).await
+}
+
+#[tokio::test]
+async fn deploys_and_unwinds_with_a_realized_gain() {
+    let (mut harness, pool, stake_pool) = setup().await;
+    let deployment = Deployment::of(&pool);
+    configure_stake_pool(&harness, &pool, &stake_pool).await.unwrap();
+
+    let vault_before = harness.lamports(deployment.sol_vault).await;
+    deploy_to_stake_pool(&harness, &pool, &stake_pool, 2 * LAMPORTS_PER_SOL).await.unwrap();
+
+    // 2 SOL at 1.05 SOL per token
+    let minted = 1_904_761_904;
+    assert_eq!(harness.token_balance(deployment.pool_token_account).await, minted);
+    assert_eq!(harness.lamports(deployment.sol_vault).await, vault_before - 2 * LAMPORTS_PER_SOL);
+    let record: StakePoolDeployment = harness.account(deployment.address).await;
+    assert_eq!(record.stake_pool, stake_pool.address);
+    assert_eq!(record.pool_tokens, minted);
+    assert_eq!(record.deployed_lamports, 2 * LAMPORTS_PER_SOL);
+
+    // Rewards of 10% of the stake pool's starting stake lift the rate to about 1.155 SOL
+    accrue_rewards(&mut harness, &stake_pool, INITIAL_TOTAL_LAMPORTS / 10).await;
+
+    // Half the tokens carry half the cost basis and come back at the new rate
+    let vault_before = harness.lamports(deployment.sol_vault).await;
+    unwind_from_stake_pool(&harness, &pool, &stake_pool, minted / 2).await.unwrap();
+    let received = harness.lamports(deployment.sol_vault).await - vault_before;
+    assert_eq!(received, 1_099_809_885);
+    let record: StakePoolDeployment = harness.account(deployment.address).await;
+    assert_eq!(record.pool_tokens, minted - minted / 2);
+    assert_eq!(record.deployed_lamports, LAMPORTS_PER_SOL);
+    assert_eq!(record.realized_pnl_lamports, (received - LAMPORTS_PER_SOL) as i64);
+
+    // Unwinding the rest releases the whole remaining cost basis. The two halves are equal,
+    // so the second unwind needs a new blockhash to differ from the first
+    harness.wait_for_new_blockhash().await;
+    unwind_from_stake_pool(&harness, &pool, &stake_pool, minted - minted / 2).await.unwrap();
+    assert_eq!(harness.token_balance(deployment.pool_token_account).await, 0);
+    let record: StakePoolDeployment = harness.account(deployment.address).await;
+    assert_eq!(record.pool_tokens, 0);
+    assert_eq!(record.deployed_lamports, 0);
+    assert_eq!(record.total_deposited_lamports, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(
+        record.realized_pnl_lamports,
+        record.total_withdrawn_lamports as i64 - record.total_deposited_lamports as i64
+    );
+    assert!(record.realized_pnl_lamports > 0);
+}
+
+#[tokio::test]
+async fn rejects_accounts_the_stake_pool_does_not_name() {
+    let (harness, pool, stake_pool) = setup().await;
+
+    // The pool token mint must be the stake pool's
+    let other_mint = create_pool_mint(&harness, &stake_pool.withdraw_authority).await;
+    let wrong_mint = StakePool { mint: other_mint, ..stake_pool };
+    assert_carsa_error(
+        configure_stake_pool(&harness, &pool, &wrong_mint).await,
+        CarsaError::InvalidStakePool,
+    );
+    configure_stake_pool(&harness, &pool, &stake_pool).await.unwrap();
+
+    // Deposits must reach the stake pool's own reserve
+    let wrong_reserve = StakePool { reserve: Keypair::new().pubkey(), ..stake_pool };
+    assert_carsa_error(
+        deploy_to_stake_pool(&harness, &pool, &wrong_reserve, LAMPORTS_PER_SOL).await,
+        CarsaError::InvalidStakePool,
+    );
+
+    // Only SOL already in the vault can be deployed, and only tokens held can be unwound
+    let vault = harness.lamports(Deployment::of(&pool).sol_vault).await;
+    assert_carsa_error(
+        deploy_to_stake_pool(&harness, &pool, &stake_pool, vault + 1).await,
+        CarsaError::InsufficientPoolSol,
+    );
+    deploy_to_stake_pool(&harness, &pool, &stake_pool, LAMPORTS_PER_SOL).await.unwrap();
+    let record: StakePoolDeployment = harness.account(Deployment::of(&pool).address).await;
+    assert_carsa_error(
+        unwind_from_stake_pool(&harness, &pool, &stake_pool, record.pool_tokens + 1).await,
+        CarsaError::InvalidAmount,
+    );
+}
@@ -0,0 +1,645 @@
+//! Voucher pool flows: the deposit, record_yield and redeem cycle, and the time-gated
+//! withdrawal rules, with the clock warped instead of waited out
+//!
+//! The deposit and redeem flows run once with a classic SPL Token voucher mint and once
+//! with a Token-2022 mint
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use carsa::error::CarsaError;
+use carsa::state::*;
+use carsa_program_tests::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+
+const YIELD_LAMPORTS: u64 = 1_000_000;
+
+/// A harness with pool 1 on `config` and a staker holding 100 vouchers
+async fn setup(config: PoolConfig) -> (Harness, Pool, Keypair) {
+    setup_with(Harness::start().await, config).await
+}
+
+/// Like `setup`, on `harness`
+async fn setup_with(mut harness: Harness, config: PoolConfig) -> (Harness, Pool, Keypair) {
+    let pool = harness.initialize_pool(1, config).await;
+
+    let user = harness.funded_user();
+    let voucher_account = harness.create_token_account(&user.pubkey()).await;
+    harness.mint_tokens(voucher_account, 100 * TOKEN).await;
+
+    (harness, pool, user)
+}
+
+#[tokio::test]
+async fn deposit_record_yield_redeem_cycle() {
+    for token_program in TOKEN_PROGRAMS {
+        let config = PoolConfig {
+            lockup_seconds: 3_600,
+            ..pool_config()
+        };
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (mut harness, pool, user) = setup_with(harness, config).await;
+
+        harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+        assert_eq!(harness.token_balance(pool.vault).await, 40 * TOKEN);
+        assert_eq!(harness.token_balance(pool.receipt_account(&user.pubkey())).await, 40 * TOKEN);
+
+        let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+        assert_eq!(stake.staked_amount, 40 * TOKEN);
+        let clock = harness.clock().await;
+        assert_eq!(stake.lock_until, clock.unix_timestamp + 3_600);
+
+        harness.record_yield(&pool, YIELD_LAMPORTS, Some(0)).await.unwrap();
+        let state: PoolState = harness.account(pool.state).await;
+        assert!(state.reward_index > 0);
+        assert_eq!({ state.total_yield_earned }, YIELD_LAMPORTS);
+
+        // Locked stake without an early withdrawal penalty cannot leave before the lock
+        assert_carsa_error(
+            harness.redeem_voucher(&pool, &user, 10 * TOKEN).await,
+            CarsaError::StakeLocked,
+        );
+
+        harness.warp_forward(3_600).await;
+        harness.redeem_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+
+        assert_eq!(harness.token_balance(pool.voucher_account(&user.pubkey())).await, 100 * TOKEN);
+        assert_eq!(harness.token_balance(pool.receipt_account(&user.pubkey())).await, 0);
+
+        // The sole staker claims all of the yield, less at most one lamport of rounding
+        let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+        assert_eq!(stake.staked_amount, 0);
+        assert!(YIELD_LAMPORTS - stake.total_yield_claimed <= 1);
+
+        let state: PoolState = harness.account(pool.state).await;
+        assert_eq!({ state.total_voucher_staked }, 0);
+        assert_eq!({ state.total_stakers }, 0);
+    }
+}
+
+#[tokio::test]
+async fn redeem_waits_for_withdraw_cooldown() {
+    for token_program in TOKEN_PROGRAMS {
+        let config = PoolConfig {
+            withdraw_cooldown_seconds: 600,
+            ..pool_config()
+        };
+        let harness = Harness::start_with_token_program(token_program).await;
+        let (mut harness, pool, user) = setup_with(harness, config).await;
+        harness.deposit_voucher(&pool, &user, 20 * TOKEN).await.unwrap();
+
+        assert_carsa_error(
+            harness.redeem_voucher(&pool, &user, 5 * TOKEN).await,
+            CarsaError::CooldownActive,
+        );
+
+        harness.warp_forward(600).await;
+        harness.redeem_voucher(&pool, &user, 10 * TOKEN).await.unwrap();
+
+        let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+        assert_eq!(stake.staked_amount, 10 * TOKEN);
+    }
+}
+
+/// Redeems the user's whole stake in its own transaction
+async fn redeem_all(harness: &Harness, pool: &Pool, user: &Keypair) -> Result<(), BanksClientError> {
+    let instruction = harness.redeem_all_vouchers_instruction(pool, &user.pubkey());
+    harness.process(&[instruction], &[user]).await
+}
+
+/// Asserts that the user holds all 100 vouchers again and nothing is left staked
+async fn assert_fully_exited(harness: &Harness, pool: &Pool, user: &Keypair) {
+    assert_eq!(harness.token_balance(pool.voucher_account(&user.pubkey())).await, 100 * TOKEN);
+    assert_eq!(harness.token_balance(pool.receipt_account(&user.pubkey())).await, 0);
+
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.staked_amount, 0);
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.total_voucher_staked }, 0);
+    assert_eq!({ state.total_stakers }, 0);
+}
+
+#[tokio::test]
+async fn redeem_all_leaves_no_dust() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+
+    // A partial redemption one base unit short of the stake leaves dust behind
+    harness.redeem_voucher(&pool, &user, 40 * TOKEN - 1).await.unwrap();
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.staked_amount, 1);
+
+    harness.record_yield(&pool, YIELD_LAMPORTS, None).await.unwrap();
+    redeem_all(&harness, &pool, &user).await.unwrap();
+
+    assert_fully_exited(&harness, &pool, &user).await;
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert!(YIELD_LAMPORTS - stake.total_yield_claimed <= 1);
+}
+
+#[tokio::test]
+async fn redeem_all_twice_fails_the_second_time() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+
+    // Both in one transaction, so the failed second one also rolls back the first
+    let instruction = harness.redeem_all_vouchers_instruction(&pool, &user.pubkey());
+    assert_carsa_error(
+        harness.process(&[instruction.clone(), instruction], &[&user]).await,
+        CarsaError::InvalidAmount,
+    );
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.staked_amount, 40 * TOKEN);
+
+    redeem_all(&harness, &pool, &user).await.unwrap();
+    assert_fully_exited(&harness, &pool, &user).await;
+}
+
+#[tokio::test]
+async fn redeem_all_after_a_partial_redemption() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+    harness.redeem_voucher(&pool, &user, 15 * TOKEN).await.unwrap();
+
+    redeem_all(&harness, &pool, &user).await.unwrap();
+    assert_fully_exited(&harness, &pool, &user).await;
+}
+
+#[tokio::test]
+async fn synced_stake_view_matches_the_next_redemption() {
+    let (mut harness, pool, first) = setup(pool_config()).await;
+    let second = harness.funded_user();
+    let voucher_account = harness.create_token_account(&second.pubkey()).await;
+    harness.mint_tokens(voucher_account, 100 * TOKEN).await;
+
+    harness.deposit_voucher(&pool, &first, 40 * TOKEN).await.unwrap();
+    harness.deposit_voucher(&pool, &second, 60 * TOKEN).await.unwrap();
+
+    // Deposits refresh the depositor's view only, so the first one is stale
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&first.pubkey())).await;
+    assert_eq!(stake.pool_share_bps, 10_000);
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&second.pubkey())).await;
+    assert_eq!((stake.pending_yield, stake.pool_share_bps), (0, 6_000));
+
+    harness.record_yield(&pool, YIELD_LAMPORTS, None).await.unwrap();
+
+    // Both synced before either redeems, since a redemption changes the other's share
+    let mut synced_views = Vec::new();
+    for (user, share_bps) in [(&first, 4_000), (&second, 6_000)] {
+        harness.sync_stake_view(&pool, &user.pubkey()).await.unwrap();
+        let synced: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+        assert_eq!(synced.pool_share_bps, share_bps);
+        assert!(synced.pending_yield > 0);
+        synced_views.push((user, synced));
+    }
+
+    for (user, synced) in synced_views {
+        redeem_all(&harness, &pool, user).await.unwrap();
+        let redeemed: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+        assert_eq!(redeemed.total_yield_claimed - synced.total_yield_claimed, synced.pending_yield);
+
+        // Redemptions refresh the view too
+        assert_eq!((redeemed.pending_yield, redeemed.pool_share_bps), (0, 0));
+    }
+}
+
+#[tokio::test]
+async fn closing_as_the_user_returns_rent_to_the_delegate() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+    redeem_all(&harness, &pool, &user).await.unwrap();
+
+    let record = pool.stake_record(&user.pubkey());
+    let stake: UserStakeRecord = harness.account(record).await;
+    assert_eq!(stake.rent_payer, pool.delegate.pubkey());
+
+    // The user signs, but cannot redirect the rent to themselves
+    assert_carsa_error(
+        harness.close_stake_record(&pool, &user.pubkey(), &user, user.pubkey()).await,
+        CarsaError::RentReceiverMismatch,
+    );
+
+    let rent = harness.lamports(record).await;
+    let delegate_before = harness.lamports(pool.delegate.pubkey()).await;
+    harness.close_stake_record(&pool, &user.pubkey(), &user, pool.delegate.pubkey()).await.unwrap();
+
+    assert_eq!(harness.lamports(pool.delegate.pubkey()).await - delegate_before, rent);
+    assert_eq!(harness.lamports(record).await, 0);
+}
+
+#[tokio::test]
+async fn batch_close_skips_records_with_stake_remaining() {
+    let (mut harness, pool, exited) = setup(pool_config()).await;
+    let staying = harness.funded_user();
+    let voucher_account = harness.create_token_account(&staying.pubkey()).await;
+    harness.mint_tokens(voucher_account, 100 * TOKEN).await;
+
+    harness.deposit_voucher(&pool, &exited, 40 * TOKEN).await.unwrap();
+    harness.deposit_voucher(&pool, &staying, 60 * TOKEN).await.unwrap();
+    redeem_all(&harness, &pool, &exited).await.unwrap();
+
+    let rent = harness.lamports(pool.stake_record(&exited.pubkey())).await;
+    let delegate_before = harness.lamports(pool.delegate.pubkey()).await;
+    harness.close_stake_records(&pool, &[exited.pubkey(), staying.pubkey()]).await.unwrap();
+
+    assert_eq!(harness.lamports(pool.delegate.pubkey()).await - delegate_before, rent);
+    assert_eq!(harness.lamports(pool.stake_record(&exited.pubkey())).await, 0);
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&staying.pubkey())).await;
+    assert_eq!(stake.staked_amount, 60 * TOKEN);
+}
+
+/// A wallet holding 100 vouchers, to fund other users' stakes
+async fn funder(harness: &mut Harness) -> Keypair {
+    let funder = harness.funded_user();
+    let voucher_account = harness.create_token_account(&funder.pubkey()).await;
+    harness.mint_tokens(voucher_account, 100 * TOKEN).await;
+    funder
+}
+
+#[tokio::test]
+async fn only_the_beneficiary_redeems_a_funded_stake() {
+    let (mut harness, pool, beneficiary) = setup(pool_config()).await;
+    let funder = funder(&mut harness).await;
+
+    assert_carsa_error(
+        harness.deposit_for_beneficiary(&pool, &funder, &funder.pubkey(), 40 * TOKEN).await,
+        CarsaError::InvalidBeneficiary,
+    );
+    harness
+        .deposit_for_beneficiary(&pool, &funder, &beneficiary.pubkey(), 40 * TOKEN)
+        .await
+        .unwrap();
+
+    assert_eq!(harness.token_balance(pool.voucher_account(&funder.pubkey())).await, 60 * TOKEN);
+    assert_eq!(
+        harness.token_balance(pool.receipt_account(&beneficiary.pubkey())).await,
+        40 * TOKEN
+    );
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&beneficiary.pubkey())).await;
+    assert_eq!(stake.user, beneficiary.pubkey());
+    assert_eq!(stake.staked_amount, 40 * TOKEN);
+    assert_eq!(stake.funded_by, funder.pubkey());
+    assert_eq!(stake.rent_payer, funder.pubkey());
+
+    // The funder signing in the beneficiary's place fails the stake record's seeds
+    let mut instruction = harness.redeem_all_vouchers_instruction(&pool, &beneficiary.pubkey());
+    instruction.accounts[0].pubkey = funder.pubkey();
+    assert!(harness.process(&[instruction], &[&funder]).await.is_err());
+
+    redeem_all(&harness, &pool, &beneficiary).await.unwrap();
+    assert_eq!(
+        harness.token_balance(pool.voucher_account(&beneficiary.pubkey())).await,
+        140 * TOKEN
+    );
+    assert_eq!(harness.token_balance(pool.voucher_account(&funder.pubkey())).await, 60 * TOKEN);
+}
+
+#[tokio::test]
+async fn per_user_cap_counts_own_and_funded_deposits() {
+    let config = PoolConfig { max_stake_per_user: 50 * TOKEN, ..pool_config() };
+    let (mut harness, pool, beneficiary) = setup(config).await;
+    let funder = funder(&mut harness).await;
+
+    harness.deposit_voucher(&pool, &beneficiary, 30 * TOKEN).await.unwrap();
+    assert_carsa_error(
+        harness.deposit_for_beneficiary(&pool, &funder, &beneficiary.pubkey(), 30 * TOKEN).await,
+        CarsaError::ExceedsMaxStake,
+    );
+    harness
+        .deposit_for_beneficiary(&pool, &funder, &beneficiary.pubkey(), 20 * TOKEN)
+        .await
+        .unwrap();
+
+    // The cap is now used up for the beneficiary's own deposits too
+    assert_carsa_error(
+        harness.deposit_voucher(&pool, &beneficiary, TOKEN).await,
+        CarsaError::ExceedsMaxStake,
+    );
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&beneficiary.pubkey())).await;
+    assert_eq!(stake.staked_amount, 50 * TOKEN);
+    // The delegate created the record, so its rent still goes back there
+    assert_eq!(stake.rent_payer, pool.delegate.pubkey());
+}
+
+#[tokio::test]
+async fn pools_of_different_mints_reject_each_others_tokens() {
+    let (mut harness, lokal_pool, user) = setup(pool_config()).await;
+    let regional_mint = harness.create_voucher_mint().await;
+    // Pool ids are per mint, so the regional token has its own pool 1
+    let regional_pool = harness.initialize_pool_with_mint(1, pool_config(), regional_mint).await;
+    assert_ne!(regional_pool.state, lokal_pool.state);
+    assert_eq!(regional_pool.state, pda(&[POOL_STATE_SEED, regional_mint.as_ref(), &[1, 0]]));
+    harness.mint_voucher_tokens(&regional_mint, &user.pubkey(), 100 * TOKEN).await;
+
+    harness.deposit_voucher(&lokal_pool, &user, 10 * TOKEN).await.unwrap();
+    harness.deposit_voucher(&regional_pool, &user, 20 * TOKEN).await.unwrap();
+
+    // Lokal tokens offered to the regional pool, as the source account or as the mint,
+    // with both accounts approved so only the mint check stands in the way
+    let delegate = regional_pool.delegate.pubkey();
+    let deposit =
+        harness.deposit_voucher_instruction(&regional_pool, &user.pubkey(), &delegate, TOKEN);
+    let regional_account = regional_pool.voucher_account(&user.pubkey());
+    for (replaced, lokal) in [
+        (regional_account, lokal_pool.voucher_account(&user.pubkey())),
+        (regional_mint, harness.mint),
+    ] {
+        let mut instruction = deposit.clone();
+        for meta in instruction.accounts.iter_mut().filter(|meta| meta.pubkey == replaced) {
+            meta.pubkey = lokal;
+        }
+        let instructions = [
+            harness.approve_delegate_instruction(&lokal_pool, &user.pubkey(), &delegate, TOKEN),
+            harness.approve_delegate_instruction(&regional_pool, &user.pubkey(), &delegate, TOKEN),
+            instruction,
+        ];
+        assert_carsa_error(
+            harness.process(&instructions, &[&user, &regional_pool.delegate]).await,
+            CarsaError::InvalidMint,
+        );
+    }
+
+    // Each pool only holds and counts its own mint
+    assert_eq!(harness.token_balance(lokal_pool.vault).await, 10 * TOKEN);
+    assert_eq!(harness.token_balance(regional_pool.vault).await, 20 * TOKEN);
+    let state: PoolState = harness.account(regional_pool.state).await;
+    assert_eq!({ state.voucher_mint }, regional_mint);
+    assert_eq!({ state.total_voucher_staked }, 20 * TOKEN);
+    assert!(state.mint_keyed);
+}
+
+#[tokio::test]
+async fn rejects_invalid_deposits() {
+    let config = PoolConfig {
+        max_stake_per_user: 50 * TOKEN,
+        ..pool_config()
+    };
+    let (mut harness, pool, user) = setup(config).await;
+
+    assert_carsa_error(
+        harness.deposit_voucher(&pool, &user, TOKEN / 2).await,
+        CarsaError::StakeBelowMinimum,
+    );
+    assert_carsa_error(
+        harness.deposit_voucher(&pool, &user, 60 * TOKEN).await,
+        CarsaError::ExceedsMaxStake,
+    );
+
+    // Without an approval the delegate cannot pull the user's vouchers
+    let delegate = pool.delegate.pubkey();
+    let deposit = harness.deposit_voucher_instruction(&pool, &user.pubkey(), &delegate, 10 * TOKEN);
+    assert_carsa_error(
+        harness.process(&[deposit], &[&pool.delegate]).await,
+        CarsaError::MissingDelegation,
+    );
+
+    // Only the pool's delegate may deposit, even when the user approved another wallet
+    let impostor = harness.funded_user();
+    let instructions = [
+        harness.approve_delegate_instruction(&pool, &user.pubkey(), &impostor.pubkey(), 10 * TOKEN),
+        harness.deposit_voucher_instruction(&pool, &user.pubkey(), &impostor.pubkey(), 10 * TOKEN),
+    ];
+    assert_carsa_error(
+        harness.process(&instructions, &[&user, &impostor]).await,
+        CarsaError::UnauthorizedDelegate,
+    );
+}
+
+#[tokio::test]
+async fn out_of_order_yield_journal_positions_are_rejected() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 20 * TOKEN).await.unwrap();
+
+    // A fresh pool starts at position 0, so the first yield is position 1
+    assert_carsa_error(
+        harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 2).await,
+        CarsaError::ExternalSequenceMismatch,
+    );
+    harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 1).await.unwrap();
+
+    // Replaying position 1 under a new batch id is caught, as is skipping ahead
+    for external_sequence in [0, 1, 3] {
+        assert_carsa_error(
+            harness.record_yield_at(&pool, YIELD_LAMPORTS, None, external_sequence).await,
+            CarsaError::ExternalSequenceMismatch,
+        );
+    }
+    harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 2).await.unwrap();
+
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.last_external_sequence }, 2);
+    assert_eq!({ state.total_yield_earned }, 2 * YIELD_LAMPORTS);
+}
+
+#[tokio::test]
+async fn yield_resumes_after_an_external_sequence_reset() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 20 * TOKEN).await.unwrap();
+    harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 1).await.unwrap();
+    harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 2).await.unwrap();
+
+    // Only the delegate may move the position
+    let instruction = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::ResetExternalSequence {
+            pool_delegate: pool.authority.pubkey(),
+            pool_state: pool.state,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::ResetExternalSequence { last_external_sequence: 0 }.data(),
+    };
+    assert_carsa_error(
+        harness.process(&[instruction], &[&pool.authority]).await,
+        CarsaError::UnauthorizedDelegate,
+    );
+
+    // The backend's new journal starts over
+    harness.reset_external_sequence(&pool, 0).await.unwrap();
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.last_external_sequence }, 0);
+
+    assert_carsa_error(
+        harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 3).await,
+        CarsaError::ExternalSequenceMismatch,
+    );
+    harness.record_yield_at(&pool, YIELD_LAMPORTS, None, 1).await.unwrap();
+
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.last_external_sequence }, 1);
+    assert_eq!({ state.total_yield_earned }, 3 * YIELD_LAMPORTS);
+}
+
+#[tokio::test]
+async fn borrowing_above_the_advance_ltv_fails() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+
+    assert_carsa_error(
+        harness.borrow_against_stake(&pool, &user, TOKEN).await,
+        CarsaError::AdvancesDisabled,
+    );
+
+    // Half of the 40 staked may be borrowed, across any number of draws
+    harness.set_advance_ltv(&pool, 5_000).await.unwrap();
+    harness.borrow_against_stake(&pool, &user, 15 * TOKEN).await.unwrap();
+    assert_carsa_error(
+        harness.borrow_against_stake(&pool, &user, 5 * TOKEN + 1).await,
+        CarsaError::AdvanceAboveLtv,
+    );
+    harness.borrow_against_stake(&pool, &user, 5 * TOKEN).await.unwrap();
+
+    let advance: TokenAdvance = harness.account(pool.token_advance(&user.pubkey())).await;
+    assert_eq!(advance.amount, 20 * TOKEN);
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.locked_amount, 40 * TOKEN);
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.total_advanced }, 20 * TOKEN);
+    assert_eq!(harness.token_balance(pool.voucher_account(&user.pubkey())).await, 80 * TOKEN);
+
+    assert_carsa_error(
+        harness.set_advance_ltv(&pool, 10_001).await,
+        CarsaError::InvalidAdvanceLtv,
+    );
+}
+
+#[tokio::test]
+async fn redeeming_stake_locked_by_an_advance_fails() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+    harness.set_advance_ltv(&pool, 5_000).await.unwrap();
+    harness.borrow_against_stake(&pool, &user, 10 * TOKEN).await.unwrap();
+
+    // 20 of the 40 staked secure the advance; the rest stays redeemable
+    assert_carsa_error(
+        harness.redeem_voucher(&pool, &user, 20 * TOKEN + 1).await,
+        CarsaError::StakeLockedByAdvance,
+    );
+    assert_carsa_error(
+        redeem_all(&harness, &pool, &user).await,
+        CarsaError::StakeLockedByAdvance,
+    );
+    harness.redeem_voucher(&pool, &user, 20 * TOKEN).await.unwrap();
+
+    // The remaining stake supports no further advance
+    assert_carsa_error(
+        harness.borrow_against_stake(&pool, &user, 1).await,
+        CarsaError::AdvanceAboveLtv,
+    );
+}
+
+#[tokio::test]
+async fn repaying_the_whole_advance_unlocks_the_stake() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 40 * TOKEN).await.unwrap();
+    harness.set_advance_ltv(&pool, 5_000).await.unwrap();
+    harness.borrow_against_stake(&pool, &user, 10 * TOKEN).await.unwrap();
+
+    assert_carsa_error(
+        harness.repay_advance(&pool, &user, 10 * TOKEN + 1).await,
+        CarsaError::InvalidRepayAmount,
+    );
+
+    // A partial repayment unlocks the collateral behind it
+    harness.repay_advance(&pool, &user, 4 * TOKEN).await.unwrap();
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.locked_amount, 12 * TOKEN);
+
+    harness.repay_advance(&pool, &user, 6 * TOKEN).await.unwrap();
+    let stake: UserStakeRecord = harness.account(pool.stake_record(&user.pubkey())).await;
+    assert_eq!(stake.locked_amount, 0);
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.total_advanced }, 0);
+    let advance = harness
+        .context
+        .banks_client
+        .clone()
+        .get_account(pool.token_advance(&user.pubkey()))
+        .await
+        .unwrap();
+    assert!(advance.is_none(), "a repaid advance is closed");
+
+    redeem_all(&harness, &pool, &user).await.unwrap();
+    assert_fully_exited(&harness, &pool, &user).await;
+}
+
+#[tokio::test]
+async fn rejects_invalid_yield_and_redemptions() {
+    let (harness, pool, user) = setup(pool_config()).await;
+    harness.deposit_voucher(&pool, &user, 20 * TOKEN).await.unwrap();
+
+    assert_carsa_error(
+        harness.record_yield(&pool, YIELD_LAMPORTS, Some(42)).await,
+        CarsaError::RewardIndexMismatch,
+    );
+    assert_carsa_error(
+        harness.record_yield(&pool, 0, None).await,
+        CarsaError::InvalidAmount,
+    );
+    assert_carsa_error(
+        harness.redeem_voucher(&pool, &user, 21 * TOKEN).await,
+        CarsaError::InsufficientBalance,
+    );
+}
+
+#[tokio::test]
+async fn redemptions_cannot_spend_the_insurance_reserve() {
+    let config = PoolConfig {
+        insurance_fee_bps: 1_000,
+        max_deployment_bps: 5_000,
+        ..pool_config()
+    };
+    let (mut harness, pool, user) = setup(config).await;
+
+    // 10% of the deposit stays in the vault as insurance, and 90 vouchers are staked
+    harness.deposit_voucher(&pool, &user, 100 * TOKEN).await.unwrap();
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.insurance_reserve }, 10 * TOKEN);
+
+    let destination_owner = harness.funded_user();
+    let destination = harness.create_token_account(&destination_owner.pubkey()).await;
+    let set_destination = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::SetDeploymentDestination {
+            pool_authority: pool.authority.pubkey(),
+            pool_state: pool.state,
+            destination,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::SetDeploymentDestination {}.data(),
+    };
+    let withdraw = Instruction {
+        program_id: carsa::ID,
+        accounts: carsa::accounts::WithdrawForDeployment {
+            pool_delegate: pool.delegate.pubkey(),
+            pool_state: pool.state,
+            pool_vault_ata: pool.vault,
+            voucher_mint: pool.voucher_mint,
+            pool_vault_authority: pool.vault_authority,
+            destination,
+            token_program: pool.token_program,
+            event_authority: event_authority(),
+            program: carsa::ID,
+        }
+        .to_account_metas(None),
+        data: carsa::instruction::WithdrawForDeployment { amount: 45 * TOKEN }.data(),
+    };
+    harness
+        .process(&[set_destination, withdraw], &[&pool.authority, &pool.delegate])
+        .await
+        .unwrap();
+    assert_eq!(harness.token_balance(pool.vault).await, 55 * TOKEN);
+
+    // The vault holds 55, but 10 of them are the reserve
+    assert_carsa_error(
+        harness.redeem_voucher(&pool, &user, 50 * TOKEN).await,
+        CarsaError::InsufficientVaultLiquidity,
+    );
+    harness.redeem_voucher(&pool, &user, 45 * TOKEN).await.unwrap();
+    assert_eq!(harness.token_balance(pool.vault).await, 10 * TOKEN);
+    let state: PoolState = harness.account(pool.state).await;
+    assert_eq!({ state.insurance_reserve }, 10 * TOKEN);
+}
@@ -0,0 +1,201 @@
+//! Example program that composes with Carsa over CPI
+//! Kept in the workspace to show, and test, how a partner program calls Carsa through
+//! its `cpi` feature: one instruction passes a signer through to `mint_lokal_tokens`,
+//! the other pays for a purchase from a program-owned escrow signing with its seeds
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenInterface;
+use carsa::program::Carsa;
+
+declare_id!("GMghnzLqLp7ikB48Q17CJ2bHhmwdsYNjvqSb5SjiVw3v");
+
+/// Seeds for deriving an owner's escrow PDA, the customer in escrowed purchases
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+#[program]
+pub mod carsa_caller {
+    use super::*;
+
+    /// Mint Lokal tokens through Carsa
+    /// The signer must be Carsa's update authority; its signature carries into the CPI
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the Carsa accounts
+    /// * `amount` - Amount of tokens to mint (in base units)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn mint_through_carsa(ctx: Context<MintThroughCarsa>, amount: u64) -> Result<()> {
+        let cpi_accounts = carsa::cpi::accounts::MintLokalTokens {
+            authority: ctx.accounts.authority.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            mint_authority: ctx.accounts.mint_authority.to_account_info(),
+            config: ctx.accounts.config.to_account_info(),
+            destination: ctx.accounts.destination.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            cosigner: None,
+            event_authority: ctx.accounts.carsa_event_authority.to_account_info(),
+            program: ctx.accounts.carsa_program.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.carsa_program.to_account_info();
+        carsa::cpi::mint_lokal_tokens(CpiContext::new(cpi_program, cpi_accounts), amount)
+    }
+
+    /// Process a Carsa purchase with the owner's escrow PDA as the customer
+    /// The escrow signs with its seeds, pays for the purchase record and receives the cashback
+    ///
+    /// # Arguments
+    /// * `ctx` - The context containing the escrow and Carsa accounts
+    /// * `fiat_amount` - Purchase amount in IDR
+    /// * `redeem_token_amount` - Tokens the escrow redeems toward the purchase, if any
+    /// * `transaction_id` - Unique id of the purchase record
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error
+    pub fn purchase_from_escrow(
+        ctx: Context<PurchaseFromEscrow>,
+        fiat_amount: u64,
+        redeem_token_amount: Option<u64>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let cpi_accounts = carsa::cpi::accounts::ProcessPurchase {
+            customer: ctx.accounts.escrow.to_account_info(),
+            merchant_account: ctx.accounts.merchant_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            mint_authority: ctx.accounts.mint_authority.to_account_info(),
+            config: ctx.accounts.config.to_account_info(),
+            customer_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+            merchant_token_account: ctx.accounts.merchant_token_account.to_account_info(),
+            transaction_record: Some(ctx.accounts.transaction_record.to_account_info()),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            stake_pool: None,
+            customer_stake_record: None,
+            activity_record: None,
+            redemption_activity_record: None,
+            treasury_token_account: None,
+            price_feed: None,
+            reward_escrow: None,
+            reward_lot: None,
+            invoice: None,
+            instructions_sysvar: None,
+            pos_key: None,
+            parent_merchant: None,
+            reward_budget: None,
+            budget_membership: None,
+            reward_budget_escrow: None,
+            associated_token_program: None,
+            merchant_operator: None,
+            customer_monthly_stats: None,
+            slim_transaction_record: None,
+            category_boost: None,
+            customer_merchant_stats: None,
+            transaction_stub: Some(ctx.accounts.transaction_stub.to_account_info()),
+            reference_index: Some(ctx.accounts.reference_index.to_account_info()),
+            event_authority: ctx.accounts.carsa_event_authority.to_account_info(),
+            program: ctx.accounts.carsa_program.to_account_info(),
+        };
+
+        let owner = ctx.accounts.owner.key();
+        let escrow_seeds: &[&[u8]] = &[ESCROW_SEED, owner.as_ref(), &[ctx.bumps.escrow]];
+        let signer_seeds = &[escrow_seeds];
+        let cpi_program = ctx.accounts.carsa_program.to_account_info();
+
+        carsa::cpi::process_purchase(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds),
+            fiat_amount,
+            redeem_token_amount,
+            transaction_id,
+            None,
+        )
+    }
+}
+
+/// Accounts for minting through Carsa
+/// Carsa validates everything it is passed, so the Carsa accounts stay unchecked here
+#[derive(Accounts)]
+pub struct MintThroughCarsa<'info> {
+    /// Carsa's update authority
+    pub authority: Signer<'info>,
+
+    /// CHECK: Carsa checks it is the Lokal mint
+    #[account(mut)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks its seeds
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks its seeds and update authority
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks it is a Lokal token account
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Carsa checks its seeds when emitting events
+    pub carsa_event_authority: UncheckedAccount<'info>,
+
+    pub carsa_program: Program<'info, Carsa>,
+}
+
+/// Accounts for a purchase paid from an escrow
+#[derive(Accounts)]
+pub struct PurchaseFromEscrow<'info> {
+    /// Owner of the escrow
+    pub owner: Signer<'info>,
+
+    /// The owner's escrow, a system account holding lamports for the purchase record
+    #[account(
+        mut,
+        seeds = [ESCROW_SEED, owner.key().as_ref()],
+        bump,
+    )]
+    pub escrow: SystemAccount<'info>,
+
+    /// CHECK: Carsa checks it is an active merchant
+    #[account(mut)]
+    pub merchant_account: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks it is the Lokal mint
+    #[account(mut)]
+    pub mint: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks its seeds
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks its seeds
+    #[account(mut)]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks it is the escrow's Lokal token account
+    #[account(mut)]
+    pub escrow_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa checks it against the merchant when tokens are redeemed
+    #[account(mut)]
+    pub merchant_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa creates it at the escrow's transaction PDA
+    #[account(mut)]
+    pub transaction_record: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa creates it at the escrow's transaction stub PDA
+    #[account(mut)]
+    pub transaction_stub: UncheckedAccount<'info>,
+
+    /// CHECK: Carsa creates it at the transaction record's reference index PDA
+    #[account(mut)]
+    pub reference_index: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Carsa checks its seeds when emitting events
+    pub carsa_event_authority: UncheckedAccount<'info>,
+
+    pub carsa_program: Program<'info, Carsa>,
+}
@@ -97,9 +97,497 @@ pub enum CarsaError {
     #[msg("Invalid owner - account owner does not match expected value")]
     InvalidOwner,
     
+    // Retired: overflow is always reported as `ArithmeticOverflow`
+    // Kept so the codes after it keep their numbers
     #[msg("Arithmetic overflow occurred")]
     Overflow,
     
     #[msg("Division by zero attempted")]
     DivisionByZero,
+    
+    #[msg("Missing delegation - the user has not approved the pool delegate on this token account")]
+    MissingDelegation,
+    
+    #[msg("Insufficient delegation - the approved amount is lower than the deposit amount")]
+    InsufficientDelegation,
+    
+    #[msg("User signature required - the pool revokes leftover approvals, which only the token account owner can sign")]
+    UserSignatureRequired,
+    
+    #[msg("Stake is locked - the lockup period has not expired yet")]
+    StakeLocked,
+    
+    #[msg("Insufficient vault liquidity - deployed principal must be returned before this withdrawal")]
+    InsufficientVaultLiquidity,
+    
+    #[msg("Deployment exceeds the configured share of the staked principal")]
+    DeploymentCapExceeded,
+    
+    #[msg("Invalid deployment destination - account does not match the one set by the pool authority")]
+    InvalidDeploymentDestination,
+    
+    #[msg("Stake record still holds staked tokens and cannot be closed")]
+    StakeRecordNotEmpty,
+    
+    #[msg("Stake record has unclaimed yield and cannot be closed")]
+    PendingYield,
+    
+    #[msg("Only the staker or the pool delegate can close this stake record")]
+    UnauthorizedCloser,
+    
+    #[msg("Deposits are paused for this pool")]
+    DepositsPaused,
+    
+    #[msg("Withdrawals are paused for this pool")]
+    WithdrawalsPaused,
+    
+    #[msg("Only the authority or a guardian can pause")]
+    UnauthorizedPauser,
+    
+    #[msg("A guardian can pause but only the authority can unpause")]
+    GuardianCannotUnpause,
+    
+    #[msg("Pool accounting is inconsistent")]
+    StateInconsistency,
+    
+    #[msg("Pool reward index changed since it was read")]
+    RewardIndexMismatch,
+    
+    #[msg("Loss exceeds the pool's recorded SOL value")]
+    LossExceedsPoolValue,
+    
+    #[msg("Pool has reached its maximum total stake")]
+    PoolCapReached,
+    
+    #[msg("Yield tier thresholds must be increasing with non-zero multipliers")]
+    InvalidTierConfig,
+    
+    #[msg("No pool authority transfer is pending")]
+    NoPendingAuthority,
+    
+    #[msg("Signer is not the pending pool authority")]
+    NotPendingAuthority,
+    
+    #[msg("Withdrawal cooldown since the last deposit has not elapsed")]
+    CooldownActive,
+    
+    #[msg("Auto compounding is not enabled for this stake")]
+    AutoCompoundDisabled,
+    
+    #[msg("Claimable yield is below the cost of one voucher unit")]
+    NothingToCompound,
+    
+    #[msg("Not enough receipt tokens to redeem this amount")]
+    InsufficientReceipts,
+    
+    #[msg("Wallet is not whitelisted for this pool")]
+    NotWhitelisted,
+    
+    #[msg("Performance fee must be at most 100% and have a fee recipient")]
+    InvalidFeeConfig,
+    
+    #[msg("Too many stake records in one settlement batch")]
+    SettleBatchTooLarge,
+    
+    #[msg("Account is not a writable stake record of this pool")]
+    InvalidStakeRecord,
+    
+    #[msg("Pool invariant check failed")]
+    PoolInvariantViolated,
+    
+    #[msg("Withdrawals in this pool must go through the unstake queue")]
+    QueuedWithdrawalsOnly,
+    
+    #[msg("The unstake queue is not enabled for this pool")]
+    QueuedWithdrawalsDisabled,
+    
+    #[msg("Unstake delay has not elapsed")]
+    UnstakeDelayActive,
+    
+    #[msg("A stake history record must be passed exactly when the pool records history")]
+    InvalidStakeHistory,
+    
+    #[msg("Stake records with history entries cannot be closed")]
+    StakeHistoryExists,
+    
+    #[msg("Insurance draw exceeds the reserve or the recorded losses")]
+    InsuranceDrawTooLarge,
+    
+    #[msg("Activity records must be passed exactly when activity recording is enabled")]
+    InvalidActivityRecord,
+    
+    #[msg("Merchant index does not point at this merchant")]
+    MerchantIndexMismatch,
+    
+    #[msg("Snapshots can only be written for the current day")]
+    InvalidSnapshotDay,
+    
+    #[msg("Protocol fee must be at most 100%")]
+    InvalidProtocolFee,
+    
+    #[msg("Treasury token account is missing or does not match the configured one")]
+    InvalidTreasuryAccount,
+    
+    #[msg("Transfer fee must be at most 100% and have a treasury account")]
+    InvalidTransferFee,
+    
+    #[msg("Treasury withdrawals require the configured co-signer")]
+    TreasuryCosignerRequired,
+    
+    #[msg("Withdrawal exceeds the per-withdrawal treasury cap")]
+    TreasuryWithdrawalCapExceeded,
+    
+    #[msg("This parameter is timelocked and must be changed through queue_param_change")]
+    TimelockActive,
+    
+    #[msg("Queued parameter change cannot be executed before its effective time")]
+    ParamChangeNotReady,
+    
+    #[msg("Parameter change value or accounts are invalid")]
+    InvalidParamChange,
+    
+    #[msg("Purchases and transfers are paused")]
+    ProgramPaused,
+    
+    #[msg("Merchant has been suspended")]
+    MerchantSuspended,
+    
+    #[msg("Mints above the single-signature limit require the configured co-signer")]
+    MintCosignerRequired,
+    
+    #[msg("Purchase value is below the merchant's minimum")]
+    PurchaseBelowMinimum,
+    
+    #[msg("Redemption bonus must be at most 100%")]
+    InvalidRedemptionBonus,
+    
+    #[msg("Price feed is missing, stale or outside its confidence bound")]
+    StalePriceFeed,
+    
+    #[msg("Price feed needs a nonzero maximum age and a confidence bound of at most 100%")]
+    InvalidPriceFeedConfig,
+    
+    #[msg("Reward escrow and lot must be supplied exactly when expiring rewards are enabled")]
+    InvalidRewardLot,
+    
+    #[msg("Expiring rewards need a positive lifetime")]
+    InvalidRewardLifetime,
+    
+    #[msg("Reward lot has not expired yet")]
+    RewardLotNotExpired,
+    
+    #[msg("Reward lot has expired and must be burned with expire_rewards first")]
+    RewardLotExpired,
+    
+    #[msg("Vesting grant needs a positive amount and duration, with the cliff inside the schedule")]
+    InvalidVestingSchedule,
+    
+    #[msg("Vesting grant cannot be claimed before its cliff")]
+    VestingCliffNotReached,
+    
+    #[msg("No vested tokens left to claim")]
+    NothingVested,
+    
+    #[msg("Vesting grant has already been revoked")]
+    VestingGrantRevoked,
+    
+    #[msg("Invoice expiry must be in the future")]
+    InvalidInvoiceExpiry,
+    
+    #[msg("Invoice has already been paid")]
+    InvoiceAlreadyPaid,
+    
+    #[msg("Invoice has expired")]
+    InvoiceExpired,
+    
+    #[msg("Invoice is missing, does not match the amount confirmed, or was passed to process_purchase")]
+    InvoiceMismatch,
+    
+    #[msg("Redemption exceeds the invoice's maximum")]
+    InvoiceRedemptionExceeded,
+    
+    #[msg("Hold needs a positive amount and an expiry in the future")]
+    InvalidHold,
+    
+    #[msg("Hold has expired")]
+    HoldExpired,
+    
+    #[msg("Hold has not expired yet")]
+    HoldNotExpired,
+    
+    #[msg("Capture exceeds the amount held")]
+    HoldCaptureExceeded,
+    
+    #[msg("Hold belongs to another merchant or customer")]
+    HoldMismatch,
+    
+    #[msg("Purchase is outside the dispute window")]
+    DisputeWindowClosed,
+    
+    #[msg("Dispute has already been resolved")]
+    DisputeNotOpen,
+    
+    #[msg("Dispute was already resolved the other way")]
+    DisputeAlreadyResolved,
+    
+    #[msg("Purchase has already been refunded")]
+    PurchaseAlreadyRefunded,
+    
+    #[msg("Settlement must burn a positive amount no larger than the merchant's unsettled tokens")]
+    InvalidSettlementAmount,
+    
+    #[msg("Gift card needs a positive amount and an expiry in the future")]
+    InvalidGiftCard,
+    
+    #[msg("Gift card code does not match")]
+    InvalidGiftCardCode,
+    
+    #[msg("Gift card has expired")]
+    GiftCardExpired,
+    
+    #[msg("Gift card has not expired yet")]
+    GiftCardNotExpired,
+    
+    #[msg("Subscription needs a positive amount, period length and number of periods")]
+    InvalidSubscription,
+    
+    #[msg("Subscription period has not elapsed yet")]
+    SubscriptionPeriodNotElapsed,
+    
+    #[msg("Subscription has charged all of its periods")]
+    SubscriptionExhausted,
+    
+    #[msg("Subscription's token approval is missing or too small for the charge")]
+    SubscriptionApprovalInsufficient,
+    
+    #[msg("Airdrop needs a positive budget and an expiry in the future")]
+    InvalidAirdrop,
+    
+    #[msg("Merkle proof does not match the airdrop's allocation")]
+    InvalidMerkleProof,
+    
+    #[msg("Airdrop has expired")]
+    AirdropExpired,
+    
+    #[msg("Airdrop has not expired yet")]
+    AirdropNotExpired,
+    
+    #[msg("Claim exceeds the airdrop's remaining budget")]
+    AirdropBudgetExceeded,
+    
+    #[msg("Session key needs an expiry in the future")]
+    InvalidSessionKey,
+    
+    #[msg("Session key has expired")]
+    SessionKeyExpired,
+    
+    #[msg("Redemption exceeds the session key's remaining allowance")]
+    SessionAllowanceExceeded,
+    
+    #[msg("Purchase needs an Ed25519 approval from the merchant wallet or a registered POS key")]
+    MerchantSignatureMissing,
+    
+    #[msg("Parent merchant's owner must co-sign the registration")]
+    ParentOwnerMismatch,
+    
+    #[msg("Merchant account does not belong to this parent merchant")]
+    ParentMerchantMismatch,
+    
+    #[msg("Too many merchant accounts in one cascade batch")]
+    CascadeBatchTooLarge,
+    
+    #[msg("Reward budget amount must be positive")]
+    InvalidRewardBudget,
+    
+    #[msg("Merchant is not a member of this reward budget")]
+    NotBudgetMember,
+    
+    #[msg("Withdrawal exceeds the reward budget's remaining tokens")]
+    RewardBudgetInsufficient,
+    
+    #[msg("Purchase record of the configured layout must be passed exactly when the merchant keeps full receipts")]
+    InvalidTransactionRecord,
+    
+    #[msg("Signed purchase approvals rely on purchase records and cannot be used with compressed receipts")]
+    CompressedReceiptsSignatureConflict,
+    
+    #[msg("Stake amount is below the pool's minimum stake")]
+    StakeBelowMinimum,
+    
+    #[msg("Pool limits are invalid - the minimum stake must be positive and not above the per-user maximum")]
+    InvalidPoolLimits,
+    
+    #[msg("Invalid APY - must be at most 10000 basis points")]
+    InvalidApyConfig,
+    
+    #[msg("Invalid early withdrawal penalty - must be at most 10000 basis points")]
+    InvalidPenaltyConfig,
+    
+    #[msg("Invalid deployment cap - must be at most 10000 basis points")]
+    InvalidDeploymentCap,
+    
+    #[msg("Invalid insurance fee - must be below 10000 basis points")]
+    InvalidInsuranceFee,
+    
+    #[msg("Returned principal exceeds the amount currently deployed")]
+    ReturnExceedsDeployed,
+    
+    #[msg("Invalid staker boost - must be at most 10000 basis points")]
+    InvalidStakerBoost,
+    
+    #[msg("Transfer memo is too long - at most 64 bytes")]
+    MemoTooLong,
+    
+    #[msg("Mint decimals do not match the decimals recorded in the config")]
+    InvalidMintDecimals,
+    
+    #[msg("Customer token account must be a Lokal token account, or the customer's associated token account to create it")]
+    InvalidCustomerTokenAccount,
+    
+    #[msg("Mint does not match the Lokal mint recorded in the config")]
+    MintMismatch,
+    
+    #[msg("Token account is frozen")]
+    AccountFrozen,
+    
+    #[msg("Merchant token account is not the merchant's registered payout account")]
+    PayoutAccountMismatch,
+    
+    #[msg("Purchase fulfillment has already been recorded")]
+    AlreadyFulfilled,
+    
+    #[msg("Fulfillment window for this purchase has closed")]
+    FulfillmentWindowClosed,
+    
+    #[msg("Batch account must be writable")]
+    RemainingAccountNotWritable,
+    
+    #[msg("Batch account is not owned by the expected program")]
+    RemainingAccountOwnerMismatch,
+    
+    #[msg("Batch account is not of the expected type")]
+    InvalidRemainingAccount,
+    
+    #[msg("Cashback rate was changed too recently")]
+    RateChangeCooldown,
+    
+    #[msg("Cashback rate increase exceeds the per-change cap")]
+    RateChangeTooLarge,
+    
+    #[msg("Rent receiver is not the account that paid for the stake record")]
+    RentReceiverMismatch,
+    
+    #[msg("Too many stake records in one close batch")]
+    CloseBatchTooLarge,
+    
+    #[msg("Beneficiary must be a different wallet than the funder")]
+    InvalidBeneficiary,
+    
+    #[msg("Clawbacks require the configured mint co-signer")]
+    ClawbackCosignerRequired,
+    
+    #[msg("Account has not been blocked long enough to claw back")]
+    ClawbackWaitingPeriod,
+    
+    #[msg("Account balance has already been clawed back")]
+    AlreadyClawedBack,
+    
+    #[msg("Restricted account holds no tokens to claw back")]
+    NothingToClawBack,
+    
+    #[msg("Mint would take administrative mints past their cap")]
+    AdminMintCapExceeded,
+    
+    #[msg("Mint would take purchase reward mints past their cap")]
+    RewardMintCapExceeded,
+    
+    #[msg("Category boost settings are invalid")]
+    InvalidCategoryBoost,
+    
+    #[msg("Category boost is for a different category than the merchant's")]
+    CategoryBoostMismatch,
+    
+    #[msg("Streak rewards need a window and a bonus")]
+    InvalidStreakReward,
+    
+    #[msg("Customer-merchant stats are required for merchants with a streak reward")]
+    InvalidCustomerMerchantStats,
+    
+    #[msg("Transaction stub is required alongside the purchase record")]
+    InvalidTransactionStub,
+    
+    #[msg("Purchase has been refunded or its dispute resolved")]
+    TransactionFinalized,
+    
+    #[msg("Purchase is already under dispute")]
+    TransactionAlreadyDisputed,
+    
+    #[msg("Purchase is not under dispute")]
+    TransactionNotDisputed,
+    
+    #[msg("Purchase status cannot change this way")]
+    InvalidTransactionTransition,
+    
+    #[msg("Customer hash salt is already set")]
+    CustomerHashSaltLocked,
+    
+    #[msg("Hashing customers in records needs a non-zero salt")]
+    InvalidCustomerHashSalt,
+    
+    #[msg("POS nonce must be one past the merchant's last")]
+    InvalidPosNonce,
+    
+    #[msg("Merchant has not accepted its registration yet")]
+    MerchantPendingActivation,
+    
+    #[msg("Merchant registration has already been accepted")]
+    MerchantRegistrationAlreadyAccepted,
+    
+    #[msg("Proposed merchant owner must differ from the current owner")]
+    InvalidPendingMerchantOwner,
+    
+    #[msg("Signer is not the merchant's proposed owner")]
+    NotPendingMerchantOwner,
+    
+    #[msg("Cashback rate is above the protocol maximum")]
+    CashbackRateAboveMax,
+    
+    #[msg("Reference index is required alongside the purchase record")]
+    InvalidReferenceIndex,
+    
+    #[msg("Purchase reference code is already taken; retry with a new transaction ID")]
+    ReferenceCodeCollision,
+    
+    #[msg("Advance LTV must be at most 10000 basis points")]
+    InvalidAdvanceLtv,
+    
+    #[msg("This pool does not offer advances against stake")]
+    AdvancesDisabled,
+    
+    #[msg("Advance would exceed the pool's loan-to-value limit for this stake")]
+    AdvanceAboveLtv,
+    
+    #[msg("Repayment must be positive and at most the outstanding advance")]
+    InvalidRepayAmount,
+    
+    #[msg("Stake is locked as collateral for an outstanding advance")]
+    StakeLockedByAdvance,
+    
+    #[msg("Yield journal position must follow the pool's last recorded position")]
+    ExternalSequenceMismatch,
+    
+    #[msg("Account does not match the pool's configured SPL stake pool")]
+    InvalidStakePool,
+    
+    #[msg("The pool's SOL vault holds less than the amount to deploy")]
+    InsufficientPoolSol,
+    
+    #[msg("Account is not in the legacy layout this migration converts")]
+    NotALegacyAccount,
+    
+    #[msg("Mint has a transfer fee, a transfer hook or a permanent delegate other than the program")]
+    UnsupportedMintExtension,
+    
+    #[msg("Clawbacks need a Token-2022 Lokal mint with the program as permanent delegate")]
+    ClawbackUnsupportedMint,
 }
@@ -0,0 +1,387 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, Mint, MintTo, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use super::voucher_pool::mint_config;
+
+/// Set the share of a stake its owner may borrow against
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetAdvanceLtv<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The mint configuration, read for its parameter timelock
+    /// CHECK: Seeds are verified; may be uninitialized when no mint exists
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+/// Mint Lokal tokens against the user's stake, locking the stake that secures them
+/// Advances are interest free and have no due date; the lock lifts as they are repaid
+#[event_cpi]
+#[derive(Accounts)]
+pub struct BorrowAgainstStake<'info> {
+    /// The staker borrowing, pays for the advance account
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool the stake is in
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = !pool_state.load()?.withdrawals_paused @ CarsaError::WithdrawalsPaused,
+        constraint = pool_state.load()?.voucher_mint == config.mint @ CarsaError::InvalidMint
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record, its collateral lock raised by the advance
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// The user's advance in this pool, topped up by repeated borrows
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = TokenAdvance::LEN,
+        seeds = [TOKEN_ADVANCE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub token_advance: Account<'info, TokenAdvance>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The user's token account receiving the advance
+    #[account(
+        mut,
+        constraint = user_token_account.mint == config.mint @ CarsaError::InvalidMint,
+        constraint = user_token_account.owner == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program for the mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Burn Lokal tokens to pay down an advance, unlocking the stake behind them
+/// A fully repaid advance is closed and its rent returned to the user
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RepayAdvance<'info> {
+    /// The borrower, receives the advance account's rent once it is repaid
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool the stake is in
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record, its collateral lock lowered by the repayment
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// The advance being repaid
+    #[account(
+        mut,
+        seeds = [TOKEN_ADVANCE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = token_advance.bump
+    )]
+    pub token_advance: Account<'info, TokenAdvance>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The user's token account the repayment is burned from
+    #[account(
+        mut,
+        constraint = user_token_account.mint == config.mint @ CarsaError::InvalidMint
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program for the burn
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Set a pool's advance LTV, failing if it is not a valid share
+/// Returns the LTV it replaced
+pub(crate) fn apply_advance_ltv(pool_state: &mut PoolState, advance_ltv_bps: u16) -> Result<u16> {
+    require!(advance_ltv_bps <= 10_000, CarsaError::InvalidAdvanceLtv);
+    let old_ltv_bps = pool_state.advance_ltv_bps;
+    pool_state.advance_ltv_bps = advance_ltv_bps;
+    Ok(old_ltv_bps)
+}
+
+impl SetAdvanceLtv<'_> {
+    /// Handler for setting a pool's advance LTV
+    pub fn handler(ctx: Context<SetAdvanceLtv>, advance_ltv_bps: u16) -> Result<()> {
+        // Under a timelock the change goes through `queue_param_change` instead
+        let timelock = mint_config(&ctx.accounts.config)?.map_or(0, |c| c.param_timelock_seconds);
+        require!(timelock == 0, CarsaError::TimelockActive);
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let old_ltv_bps = apply_advance_ltv(pool_state, advance_ltv_bps)?;
+
+        log_verbose!("Advance LTV set to {} bps", advance_ltv_bps);
+
+        emit_cpi!(AdvanceLtvSetEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            advance_ltv_bps: Changed { old: old_ltv_bps, new: advance_ltv_bps },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl BorrowAgainstStake<'_> {
+    /// Handler for borrowing against a stake
+    pub fn handler(ctx: Context<BorrowAgainstStake>, amount: u64) -> Result<()> {
+        require!(amount > 0, CarsaError::InvalidAmount);
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let ltv_bps = pool_state.advance_ltv_bps;
+        require!(ltv_bps > 0, CarsaError::AdvancesDisabled);
+
+        // The whole outstanding advance is checked, and locked, at the current LTV
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let token_advance = &mut ctx.accounts.token_advance;
+        let outstanding = token_advance
+            .amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let max_advance = TokenAdvance::max_advance(user_stake_record.staked_amount, ltv_bps)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(outstanding <= max_advance, CarsaError::AdvanceAboveLtv);
+
+        let config = &mut ctx.accounts.config;
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, amount)?;
+        config.total_supply = config
+            .total_supply
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let clock = Clock::get()?;
+        token_advance.pool = ctx.accounts.pool_state.key();
+        token_advance.user = ctx.accounts.user.key();
+        token_advance.amount = outstanding;
+        token_advance.ltv_bps = ltv_bps;
+        token_advance.last_borrowed_at = clock.unix_timestamp;
+        token_advance.bump = ctx.bumps.token_advance;
+
+        user_stake_record.locked_amount = TokenAdvance::collateral_for(outstanding, ltv_bps)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state.total_advanced = pool_state
+            .total_advanced
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "User {} borrowed {} tokens, {} outstanding against {} locked",
+            token_advance.user,
+            amount,
+            outstanding,
+            user_stake_record.locked_amount
+        );
+
+        emit_cpi!(AdvanceBorrowedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: token_advance.pool,
+            user: token_advance.user,
+            amount,
+            outstanding,
+            locked_amount: user_stake_record.locked_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl RepayAdvance<'_> {
+    /// Handler for repaying an advance
+    pub fn handler(ctx: Context<RepayAdvance>, amount: u64) -> Result<()> {
+        let token_advance = &mut ctx.accounts.token_advance;
+        require!(
+            amount > 0 && amount <= token_advance.amount,
+            CarsaError::InvalidRepayAmount
+        );
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token_interface::burn(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+        let config = &mut ctx.accounts.config;
+        config.total_burned = config
+            .total_burned
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // The lock shrinks at the LTV the advance was drawn at
+        token_advance.amount -= amount;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        user_stake_record.locked_amount = if token_advance.amount == 0 {
+            0
+        } else {
+            TokenAdvance::collateral_for(token_advance.amount, token_advance.ltv_bps)
+                .ok_or(CarsaError::ArithmeticOverflow)?
+        };
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        pool_state.total_advanced = pool_state
+            .total_advanced
+            .checked_sub(amount)
+            .ok_or(CarsaError::StateInconsistency)?;
+
+        log_verbose!(
+            "User {} repaid {} tokens, {} outstanding",
+            token_advance.user,
+            amount,
+            token_advance.amount
+        );
+
+        emit_cpi!(AdvanceRepaidEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: token_advance.pool,
+            user: token_advance.user,
+            amount,
+            outstanding: token_advance.amount,
+            locked_amount: user_stake_record.locked_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        if token_advance.amount == 0 {
+            token_advance.close(ctx.accounts.user.to_account_info())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AdvanceLtvSetEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub advance_ltv_bps: Changed<u16>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvanceBorrowedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// Total advance outstanding after this borrow
+    pub outstanding: u64,
+    /// Stake locked as collateral after this borrow
+    pub locked_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdvanceRepaidEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    /// Advance still outstanding; 0 means the advance was closed
+    pub outstanding: u64,
+    /// Stake still locked as collateral
+    pub locked_amount: u64,
+    pub timestamp: i64,
+}
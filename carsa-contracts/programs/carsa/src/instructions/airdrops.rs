@@ -0,0 +1,312 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use super::mint_tokens::{check_mint_cosigner, record_admin_mint};
+
+/// Publish the merkle root of an airdrop's allocations along with its budget
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(airdrop_id: u64)]
+pub struct CreateAirdrop<'info> {
+    /// The update authority of the mint configuration, paying for the airdrop account
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The airdrop to be created
+    #[account(
+        init,
+        payer = authority,
+        space = Airdrop::LEN,
+        seeds = [AIRDROP_SEED, &airdrop_id.to_le_bytes()],
+        bump,
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// The configured mint co-signer, required when the budget is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Signer<'info>>,
+}
+
+/// Mint a wallet's allocation after checking it against the airdrop's merkle root
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimAirdrop<'info> {
+    /// The wallet claiming its allocation, paying for the claim marker
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    /// The claimant's token account receiving the allocation
+    #[account(
+        mut,
+        constraint = claimant_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = claimant_token_account.owner == claimant.key() @ CarsaError::InvalidOwner
+    )]
+    pub claimant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The airdrop being claimed from
+    #[account(
+        mut,
+        seeds = [AIRDROP_SEED, &airdrop.airdrop_id.to_le_bytes()],
+        bump = airdrop.bump,
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+
+    /// Marker created on the first claim, so each wallet can claim only once
+    #[account(
+        init,
+        payer = claimant,
+        space = AirdropClaim::LEN,
+        seeds = [AIRDROP_CLAIM_SEED, airdrop.key().as_ref(), claimant.key().as_ref()],
+        bump,
+    )]
+    pub airdrop_claim: Account<'info, AirdropClaim>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// SPL Token program for minting the allocation
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Retire an expired airdrop's unclaimed budget, closing the airdrop
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SweepAirdrop<'info> {
+    /// The update authority of the mint configuration, refunded the airdrop's rent
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The expired airdrop
+    #[account(
+        mut,
+        close = authority,
+        seeds = [AIRDROP_SEED, &airdrop.airdrop_id.to_le_bytes()],
+        bump = airdrop.bump,
+    )]
+    pub airdrop: Account<'info, Airdrop>,
+}
+
+impl<'info> CreateAirdrop<'info> {
+    /// Handler for creating an airdrop
+    pub fn handler(
+        ctx: Context<CreateAirdrop>,
+        airdrop_id: u64,
+        merkle_root: [u8; 32],
+        budget: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            budget > 0 && expires_at > clock.unix_timestamp,
+            CarsaError::InvalidAirdrop
+        );
+
+        // The budget is minted on claim without further sign-off, so approve it as one mint
+        let config = &mut ctx.accounts.config;
+        let cosigner = check_mint_cosigner(config, budget, ctx.accounts.cosigner.as_ref())?;
+
+        let airdrop = &mut ctx.accounts.airdrop;
+        airdrop.set_inner(Airdrop {
+            airdrop_id,
+            merkle_root,
+            budget,
+            claimed_amount: 0,
+            expires_at,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.airdrop,
+        });
+
+        log_verbose!(
+            "Airdrop {} created: budget {} tokens, expires at {}",
+            airdrop_id,
+            budget,
+            expires_at
+        );
+
+        emit_cpi!(AirdropCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            airdrop: airdrop.key(),
+            airdrop_id,
+            merkle_root,
+            budget,
+            expires_at,
+            cosigner,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ClaimAirdrop<'info> {
+    /// Handler for claiming an airdrop allocation
+    pub fn handler(ctx: Context<ClaimAirdrop>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        let clock = Clock::get()?;
+        let airdrop = &mut ctx.accounts.airdrop;
+        require!(clock.unix_timestamp < airdrop.expires_at, CarsaError::AirdropExpired);
+
+        let claimant = ctx.accounts.claimant.key();
+        require!(
+            proof.len() <= Airdrop::MAX_PROOF_LEN
+                && Airdrop::verify_proof(&airdrop.merkle_root, Airdrop::leaf(&claimant, amount), &proof),
+            CarsaError::InvalidMerkleProof
+        );
+        require!(amount <= airdrop.unclaimed_amount(), CarsaError::AirdropBudgetExceeded);
+
+        airdrop.claimed_amount += amount;
+
+        let config = &mut ctx.accounts.config;
+        record_admin_mint(config, amount)?;
+
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.claimant_token_account.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, amount)?;
+
+        ctx.accounts.airdrop_claim.set_inner(AirdropClaim {
+            airdrop: airdrop.key(),
+            claimant,
+            amount,
+            claimed_at: clock.unix_timestamp,
+            bump: ctx.bumps.airdrop_claim,
+        });
+
+        log_verbose!("Airdrop {} claimed by {}: {} tokens", airdrop.airdrop_id, claimant, amount);
+
+        emit_cpi!(AirdropClaimedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            airdrop: airdrop.key(),
+            claimant,
+            amount,
+            claimed_amount: airdrop.claimed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SweepAirdrop<'info> {
+    /// Handler for sweeping an expired airdrop
+    pub fn handler(ctx: Context<SweepAirdrop>) -> Result<()> {
+        let clock = Clock::get()?;
+        let airdrop = &ctx.accounts.airdrop;
+        require!(clock.unix_timestamp >= airdrop.expires_at, CarsaError::AirdropNotExpired);
+
+        // Allocations are minted on claim, so the unclaimed budget is simply never minted
+        let unclaimed_amount = airdrop.unclaimed_amount();
+        log_verbose!(
+            "Airdrop {} swept: {} of {} tokens unclaimed",
+            airdrop.airdrop_id,
+            unclaimed_amount,
+            airdrop.budget
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(AirdropSweptEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            airdrop: airdrop.key(),
+            airdrop_id: airdrop.airdrop_id,
+            claimed_amount: airdrop.claimed_amount,
+            unclaimed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AirdropCreatedEvent {
+    pub sequence: u64,
+    pub airdrop: Pubkey,
+    pub airdrop_id: u64,
+    pub merkle_root: [u8; 32],
+    pub budget: u64,
+    pub expires_at: i64,
+    /// The mint co-signer that approved the budget (default pubkey when not required)
+    pub cosigner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AirdropClaimedEvent {
+    pub sequence: u64,
+    pub airdrop: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    /// Tokens claimed from the airdrop so far, including this claim
+    pub claimed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AirdropSweptEvent {
+    pub sequence: u64,
+    pub airdrop: Pubkey,
+    pub airdrop_id: u64,
+    pub claimed_amount: u64,
+    /// Budget retired without being minted
+    pub unclaimed_amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,488 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Escrow a sponsor's tokens as a cashback budget for a group of merchants
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(budget_id: u64)]
+pub struct CreateRewardBudget<'info> {
+    /// The wallet sponsoring the budget, paying for its accounts
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// The sponsor's token account the budget is funded from
+    #[account(
+        mut,
+        constraint = sponsor_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [REWARD_BUDGET_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_budget_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The reward budget to be created
+    #[account(
+        init,
+        payer = sponsor,
+        space = RewardBudget::LEN,
+        seeds = [REWARD_BUDGET_SEED, sponsor.key().as_ref(), &budget_id.to_le_bytes()],
+        bump,
+    )]
+    pub reward_budget: Account<'info, RewardBudget>,
+
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Add tokens to a reward budget
+#[event_cpi]
+#[derive(Accounts)]
+pub struct TopUpRewardBudget<'info> {
+    /// The budget's sponsor
+    pub sponsor: Signer<'info>,
+
+    /// The sponsor's token account the tokens come from
+    #[account(
+        mut,
+        constraint = sponsor_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_budget_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The reward budget being topped up
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_SEED, sponsor.key().as_ref(), &reward_budget.budget_id.to_le_bytes()],
+        bump = reward_budget.bump,
+    )]
+    pub reward_budget: Account<'info, RewardBudget>,
+
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Return unspent tokens from a reward budget to its sponsor
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawRewardBudget<'info> {
+    /// The budget's sponsor
+    pub sponsor: Signer<'info>,
+
+    /// The sponsor's token account receiving the tokens
+    #[account(
+        mut,
+        constraint = sponsor_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding budget tokens until they are paid out or withdrawn
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_budget_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The reward budget being withdrawn from
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_SEED, sponsor.key().as_ref(), &reward_budget.budget_id.to_le_bytes()],
+        bump = reward_budget.bump,
+    )]
+    pub reward_budget: Account<'info, RewardBudget>,
+
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Let a reward budget pay the rewards of a merchant's purchases
+#[derive(Accounts)]
+pub struct AddBudgetMember<'info> {
+    /// The budget's sponsor, paying for the membership
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// The reward budget the merchant joins
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_SEED, sponsor.key().as_ref(), &reward_budget.budget_id.to_le_bytes()],
+        bump = reward_budget.bump,
+    )]
+    pub reward_budget: Account<'info, RewardBudget>,
+
+    /// The merchant being sponsored
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The membership to be created
+    #[account(
+        init,
+        payer = sponsor,
+        space = BudgetMembership::LEN,
+        seeds = [BUDGET_MEMBERSHIP_SEED, reward_budget.key().as_ref(), merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub budget_membership: Account<'info, BudgetMembership>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Stop a reward budget from paying a merchant's rewards
+#[derive(Accounts)]
+pub struct RemoveBudgetMember<'info> {
+    /// The budget's sponsor, refunded the membership's rent
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// The reward budget the merchant leaves
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_SEED, sponsor.key().as_ref(), &reward_budget.budget_id.to_le_bytes()],
+        bump = reward_budget.bump,
+    )]
+    pub reward_budget: Account<'info, RewardBudget>,
+
+    /// The membership to be removed
+    #[account(
+        mut,
+        close = sponsor,
+        seeds = [
+            BUDGET_MEMBERSHIP_SEED,
+            reward_budget.key().as_ref(),
+            budget_membership.merchant.as_ref()
+        ],
+        bump = budget_membership.bump,
+    )]
+    pub budget_membership: Account<'info, BudgetMembership>,
+}
+
+/// Move `amount` out of the reward budget escrow, signed by the mint authority PDA
+pub(crate) fn release_from_budget_escrow<'info>(
+    config: &LokalMintConfig,
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    reward_budget_escrow: &InterfaceAccount<'info, TokenAccount>,
+    mint_authority: &UncheckedAccount<'info>,
+    destination: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let authority_seeds = &[
+        MINT_AUTHORITY_SEED,
+        &[config.mint_authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let cpi_accounts = token_interface::TransferChecked {
+        from: reward_budget_escrow.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination,
+        authority: mint_authority.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, config.decimals())
+}
+
+/// Take a purchase's reward out of `reward_budget` when the merchant is a member
+/// Returns whether the budget pays it; without a budget, or once it is exhausted,
+/// the reward is minted as usual
+pub(crate) fn spend_reward_budget(
+    merchant: &Pubkey,
+    reward_budget: Option<&mut Account<RewardBudget>>,
+    budget_membership: Option<&Account<BudgetMembership>>,
+    reward_amount: u64,
+) -> Result<bool> {
+    let Some(reward_budget) = reward_budget else {
+        return Ok(false);
+    };
+    require!(
+        budget_membership.is_some_and(|membership| {
+            membership.reward_budget == reward_budget.key() && membership.merchant == *merchant
+        }),
+        CarsaError::NotBudgetMember
+    );
+
+    Ok(reward_budget.try_spend(reward_amount))
+}
+
+impl<'info> CreateRewardBudget<'info> {
+    /// Handler for creating a reward budget
+    pub fn handler(ctx: Context<CreateRewardBudget>, budget_id: u64, amount: u64) -> Result<()> {
+        require!(amount > 0, CarsaError::InvalidRewardBudget);
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.sponsor_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.reward_budget_escrow.to_account_info(),
+            authority: ctx.accounts.sponsor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.config.decimals())?;
+
+        let clock = Clock::get()?;
+        let sponsor = ctx.accounts.sponsor.key();
+        let reward_budget = &mut ctx.accounts.reward_budget;
+        reward_budget.set_inner(RewardBudget {
+            sponsor,
+            budget_id,
+            remaining_budget: amount,
+            total_paid: 0,
+            member_count: 0,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.reward_budget,
+        });
+
+        log_verbose!("Reward budget {} created by {}: {} tokens", budget_id, sponsor, amount);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(RewardBudgetFundedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            reward_budget: reward_budget.key(),
+            sponsor,
+            amount,
+            remaining_budget: amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> TopUpRewardBudget<'info> {
+    /// Handler for topping up a reward budget
+    pub fn handler(ctx: Context<TopUpRewardBudget>, amount: u64) -> Result<()> {
+        require!(amount > 0, CarsaError::InvalidRewardBudget);
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.sponsor_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.reward_budget_escrow.to_account_info(),
+            authority: ctx.accounts.sponsor.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.config.decimals())?;
+
+        let reward_budget = &mut ctx.accounts.reward_budget;
+        reward_budget.remaining_budget = reward_budget
+            .remaining_budget
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Reward budget {} topped up: {} tokens, {} remaining",
+            reward_budget.budget_id,
+            amount,
+            reward_budget.remaining_budget
+        );
+
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(RewardBudgetFundedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            reward_budget: reward_budget.key(),
+            sponsor: reward_budget.sponsor,
+            amount,
+            remaining_budget: reward_budget.remaining_budget,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawRewardBudget<'info> {
+    /// Handler for withdrawing unspent tokens from a reward budget
+    pub fn handler(ctx: Context<WithdrawRewardBudget>, amount: u64) -> Result<()> {
+        require!(amount > 0, CarsaError::InvalidRewardBudget);
+
+        let reward_budget = &mut ctx.accounts.reward_budget;
+        require!(
+            amount <= reward_budget.remaining_budget,
+            CarsaError::RewardBudgetInsufficient
+        );
+        reward_budget.remaining_budget -= amount;
+
+        release_from_budget_escrow(
+            &ctx.accounts.config,
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.reward_budget_escrow,
+            &ctx.accounts.mint_authority,
+            ctx.accounts.sponsor_token_account.to_account_info(),
+            amount,
+        )?;
+
+        log_verbose!(
+            "Reward budget {} withdrawn: {} tokens, {} remaining",
+            reward_budget.budget_id,
+            amount,
+            reward_budget.remaining_budget
+        );
+
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(RewardBudgetWithdrawnEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            reward_budget: reward_budget.key(),
+            sponsor: reward_budget.sponsor,
+            amount,
+            remaining_budget: reward_budget.remaining_budget,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> AddBudgetMember<'info> {
+    /// Handler for adding a merchant to a reward budget
+    pub fn handler(ctx: Context<AddBudgetMember>) -> Result<()> {
+        let clock = Clock::get()?;
+        let reward_budget = &mut ctx.accounts.reward_budget;
+        let merchant = ctx.accounts.merchant_account.key();
+        reward_budget.member_count = reward_budget
+            .member_count
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        ctx.accounts.budget_membership.set_inner(BudgetMembership {
+            reward_budget: reward_budget.key(),
+            merchant,
+            joined_at: clock.unix_timestamp,
+            bump: ctx.bumps.budget_membership,
+        });
+
+        log_verbose!("Merchant {} joined reward budget {}", merchant, reward_budget.budget_id);
+
+        Ok(())
+    }
+}
+
+impl<'info> RemoveBudgetMember<'info> {
+    /// Handler for removing a merchant from a reward budget
+    pub fn handler(ctx: Context<RemoveBudgetMember>) -> Result<()> {
+        let reward_budget = &mut ctx.accounts.reward_budget;
+        reward_budget.member_count = reward_budget.member_count.saturating_sub(1);
+
+        log_verbose!(
+            "Merchant {} left reward budget {}",
+            ctx.accounts.budget_membership.merchant,
+            reward_budget.budget_id
+        );
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct RewardBudgetFundedEvent {
+    pub sequence: u64,
+    pub reward_budget: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub remaining_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardBudgetWithdrawnEvent {
+    pub sequence: u64,
+    pub reward_budget: Pubkey,
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub remaining_budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardBudgetSpentEvent {
+    pub sequence: u64,
+    pub reward_budget: Pubkey,
+    pub merchant: Pubkey,
+    pub customer: Pubkey,
+    pub amount: u64,
+    pub remaining_budget: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::math::{calculate_reward, LOKAL_DECIMALS};
+
+/// Create or update the protocol's cashback boost for a merchant category
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(category: String)]
+pub struct SetCategoryBoost<'info> {
+    /// The update authority of the mint configuration, pays for a new boost
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The category's boost (PDA), created on first use
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = CategoryBoost::LEN,
+        seeds = [CATEGORY_BOOST_SEED, &MerchantAccount::category_bytes(&category)],
+        bump,
+    )]
+    pub category_boost: Account<'info, CategoryBoost>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Remove a category's boost and reclaim its rent
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseCategoryBoost<'info> {
+    /// The update authority of the mint configuration, receives the rent
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The boost to remove
+    #[account(
+        mut,
+        close = authority,
+        seeds = [CATEGORY_BOOST_SEED, category_boost.category.as_ref()],
+        bump = category_boost.bump,
+    )]
+    pub category_boost: Account<'info, CategoryBoost>,
+}
+
+/// The category boost's part of a purchase reward, without charging its budget
+/// Returns the boost rate and reward, both 0 when no boost is passed, it is not live or
+/// its budget is spent; a boost for another category is rejected
+pub(crate) fn category_boost_reward(
+    category_boost: Option<&CategoryBoost>,
+    merchant_category: &[u8; 16],
+    total_value: u64,
+    token_to_idr_rate: u64,
+    now: i64,
+) -> Result<(u16, u64)> {
+    let Some(category_boost) = category_boost else {
+        return Ok((0, 0));
+    };
+    require!(
+        category_boost.category == *merchant_category,
+        CarsaError::CategoryBoostMismatch
+    );
+
+    let full_reward = calculate_reward(
+        total_value,
+        category_boost.bonus_bps,
+        token_to_idr_rate,
+        LOKAL_DECIMALS,
+    )?;
+    let boost_reward = category_boost.boost_reward(full_reward, now);
+    if boost_reward == 0 {
+        return Ok((0, 0));
+    }
+    Ok((category_boost.bonus_bps, boost_reward))
+}
+
+/// The category boost's part of a purchase reward, charged to its budget
+/// Same result as `category_boost_reward`
+pub(crate) fn apply_category_boost(
+    category_boost: Option<&mut Account<CategoryBoost>>,
+    merchant_category: &[u8; 16],
+    total_value: u64,
+    token_to_idr_rate: u64,
+    now: i64,
+) -> Result<(u16, u64)> {
+    let Some(category_boost) = category_boost else {
+        return Ok((0, 0));
+    };
+    let (bonus_bps, boost_reward) = category_boost_reward(
+        Some(category_boost),
+        merchant_category,
+        total_value,
+        token_to_idr_rate,
+        now,
+    )?;
+
+    category_boost.spent = category_boost
+        .spent
+        .checked_add(boost_reward)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    Ok((bonus_bps, boost_reward))
+}
+
+impl<'info> SetCategoryBoost<'info> {
+    /// Handler for setting a category boost
+    /// Updating a boost keeps what it has spent, so `budget` is its lifetime total
+    pub fn handler(
+        ctx: Context<SetCategoryBoost>,
+        category: String,
+        bonus_bps: u16,
+        start: i64,
+        end: i64,
+        budget: u64,
+    ) -> Result<()> {
+        require!(
+            category.len() <= 16 && !category.is_empty(),
+            CarsaError::InvalidMerchantCategory
+        );
+        require!(
+            bonus_bps > 0 && bonus_bps <= 10_000 && start < end,
+            CarsaError::InvalidCategoryBoost
+        );
+
+        let category_boost = &mut ctx.accounts.category_boost;
+        category_boost.category = MerchantAccount::category_bytes(&category);
+        category_boost.bonus_bps = bonus_bps;
+        category_boost.start = start;
+        category_boost.end = end;
+        category_boost.budget = budget;
+        category_boost.bump = ctx.bumps.category_boost;
+
+        log_verbose!(
+            "Category boost for {}: {}bps from {} to {}, {} of {} budget spent",
+            category,
+            bonus_bps,
+            start,
+            end,
+            category_boost.spent,
+            budget
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(CategoryBoostSetEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            category_boost: category_boost.key(),
+            category: category_boost.category,
+            bonus_bps,
+            start,
+            end,
+            budget,
+            spent: category_boost.spent,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> CloseCategoryBoost<'info> {
+    /// Handler for removing a category boost
+    pub fn handler(ctx: Context<CloseCategoryBoost>) -> Result<()> {
+        let category_boost = &ctx.accounts.category_boost;
+
+        log_verbose!("Category boost {} closed", category_boost.key());
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(CategoryBoostClosedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            category_boost: category_boost.key(),
+            category: category_boost.category,
+            spent: category_boost.spent,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CategoryBoostSetEvent {
+    pub sequence: u64,
+    pub category_boost: Pubkey,
+    pub category: [u8; 16],
+    pub bonus_bps: u16,
+    pub start: i64,
+    pub end: i64,
+    pub budget: u64,
+    pub spent: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CategoryBoostClosedEvent {
+    pub sequence: u64,
+    pub category_boost: Pubkey,
+    pub category: [u8; 16],
+    pub spent: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,418 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use super::mint_tokens::require_program_permanent_delegate;
+
+/// Block a Lokal token account, freezing it with the mint's freeze authority
+/// The restriction is the prerequisite for a clawback once it has aged
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RestrictAccount<'info> {
+    /// The update authority of the mint configuration, pays for the restriction
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The token account to block
+    #[account(
+        mut,
+        constraint = token_account.mint == config.mint @ CarsaError::MintMismatch
+    )]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Restriction marking the account as blocked
+    #[account(
+        init,
+        payer = authority,
+        space = AccountRestriction::LEN,
+        seeds = [ACCOUNT_RESTRICTION_SEED, token_account.key().as_ref()],
+        bump,
+    )]
+    pub restriction: Account<'info, AccountRestriction>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// PDA that is the mint's freeze authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// SPL Token program for the freeze
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Lift a restriction that has not been used for a clawback, thawing the account
+#[event_cpi]
+#[derive(Accounts)]
+pub struct LiftAccountRestriction<'info> {
+    /// The update authority of the mint configuration, receives the restriction's rent
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The blocked token account
+    #[account(mut)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Restriction to lift
+    #[account(
+        mut,
+        close = authority,
+        seeds = [ACCOUNT_RESTRICTION_SEED, token_account.key().as_ref()],
+        bump = restriction.bump,
+        constraint = !restriction.clawed_back @ CarsaError::AlreadyClawedBack,
+    )]
+    pub restriction: Account<'info, AccountRestriction>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// PDA that is the mint's freeze authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// SPL Token program for the thaw
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Claw back the balance of an account that has been blocked for the waiting period
+/// The balance moves to the treasury through the mint's permanent delegate, so only a
+/// Token-2022 Lokal mint created with the program as that delegate supports clawbacks
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    /// The update authority of the mint configuration, pays for the record
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// The configured mint co-signer; always required for a clawback
+    pub cosigner: Option<Signer<'info>>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The blocked token account
+    #[account(mut)]
+    pub token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Restriction the clawback relies on
+    #[account(
+        mut,
+        seeds = [ACCOUNT_RESTRICTION_SEED, token_account.key().as_ref()],
+        bump = restriction.bump,
+        constraint = !restriction.clawed_back @ CarsaError::AlreadyClawedBack,
+    )]
+    pub restriction: Account<'info, AccountRestriction>,
+
+    /// The configured treasury token account
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Record of this clawback
+    #[account(
+        init,
+        payer = authority,
+        space = ClawbackRecord::LEN,
+        seeds = [CLAWBACK_RECORD_SEED, token_account.key().as_ref()],
+        bump,
+    )]
+    pub clawback_record: Account<'info, ClawbackRecord>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// PDA that is the mint's freeze authority and permanent delegate
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Token-2022 program for the transfer
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RestrictAccount<'info> {
+    /// Handler for blocking a token account
+    pub fn handler(ctx: Context<RestrictAccount>, reason_code: u16) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if !ctx.accounts.token_account.is_frozen() {
+            let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[config.mint_authority_bump]];
+            let signer_seeds = &[authority_seeds];
+            let cpi_accounts = token_interface::FreezeAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::freeze_account(cpi_ctx)?;
+        }
+
+        let clock = Clock::get()?;
+        let restriction = &mut ctx.accounts.restriction;
+        restriction.token_account = ctx.accounts.token_account.key();
+        restriction.owner = ctx.accounts.token_account.owner;
+        restriction.authority = ctx.accounts.authority.key();
+        restriction.reason_code = reason_code;
+        restriction.blocked_at = clock.unix_timestamp;
+        restriction.clawed_back = false;
+        restriction.bump = ctx.bumps.restriction;
+
+        log_verbose!(
+            "Token account {} of {} blocked (reason {})",
+            restriction.token_account,
+            restriction.owner,
+            reason_code
+        );
+
+        emit_cpi!(AccountRestrictedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            token_account: restriction.token_account,
+            owner: restriction.owner,
+            authority: restriction.authority,
+            reason_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> LiftAccountRestriction<'info> {
+    /// Handler for unblocking a token account
+    pub fn handler(ctx: Context<LiftAccountRestriction>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if ctx.accounts.token_account.is_frozen() {
+            let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[config.mint_authority_bump]];
+            let signer_seeds = &[authority_seeds];
+            let cpi_accounts = token_interface::ThawAccount {
+                account: ctx.accounts.token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::thaw_account(cpi_ctx)?;
+        }
+
+        log_verbose!("Token account {} unblocked", ctx.accounts.token_account.key());
+
+        emit_cpi!(AccountRestrictionLiftedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            token_account: ctx.accounts.token_account.key(),
+            authority: ctx.accounts.authority.key(),
+            blocked_at: ctx.accounts.restriction.blocked_at,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> Clawback<'info> {
+    /// Handler for clawing back a blocked account's balance to the treasury
+    pub fn handler(ctx: Context<Clawback>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        let clock = Clock::get()?;
+        let restriction = &mut ctx.accounts.restriction;
+        require!(
+            restriction.clawback_allowed_at(clock.unix_timestamp),
+            CarsaError::ClawbackWaitingPeriod
+        );
+
+        // Dual control is not optional here: without a configured co-signer nothing is clawed back
+        require!(config.mint_cosigner != Pubkey::default(), CarsaError::ClawbackCosignerRequired);
+        let cosigner = ctx
+            .accounts
+            .cosigner
+            .as_ref()
+            .ok_or(CarsaError::ClawbackCosignerRequired)?;
+        require!(
+            cosigner.key() == config.mint_cosigner,
+            CarsaError::ClawbackCosignerRequired
+        );
+
+        let amount = ctx.accounts.token_account.amount;
+        require!(amount > 0, CarsaError::NothingToClawBack);
+
+        // Never mint a replacement: a mint that cannot move the balance cannot claw back
+        require_program_permanent_delegate(
+            &ctx.accounts.mint.to_account_info(),
+            &ctx.accounts.mint_authority.key(),
+        )?;
+        restriction.clawed_back = true;
+
+        // A frozen account cannot send, so thaw it for the transfer and freeze it again after
+        let authority_seeds: &[&[u8]] = &[MINT_AUTHORITY_SEED, &[config.mint_authority_bump]];
+        let signer_seeds = &[authority_seeds];
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let token_account = ctx.accounts.token_account.to_account_info();
+        let mint = ctx.accounts.mint.to_account_info();
+        let mint_authority = ctx.accounts.mint_authority.to_account_info();
+
+        if ctx.accounts.token_account.is_frozen() {
+            let thaw_accounts = token_interface::ThawAccount {
+                account: token_account.clone(),
+                mint: mint.clone(),
+                authority: mint_authority.clone(),
+            };
+            token_interface::thaw_account(CpiContext::new_with_signer(
+                cpi_program.clone(),
+                thaw_accounts,
+                signer_seeds,
+            ))?;
+        }
+
+        let transfer_accounts = token_interface::TransferChecked {
+            from: token_account.clone(),
+            mint: mint.clone(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: mint_authority.clone(),
+        };
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(cpi_program.clone(), transfer_accounts, signer_seeds),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        let freeze_accounts = token_interface::FreezeAccount {
+            account: token_account,
+            mint,
+            authority: mint_authority,
+        };
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            cpi_program,
+            freeze_accounts,
+            signer_seeds,
+        ))?;
+
+        let clawback_record = &mut ctx.accounts.clawback_record;
+        clawback_record.token_account = restriction.token_account;
+        clawback_record.owner = restriction.owner;
+        clawback_record.restriction = restriction.key();
+        clawback_record.treasury_token_account = ctx.accounts.treasury_token_account.key();
+        clawback_record.authority = ctx.accounts.authority.key();
+        clawback_record.cosigner = cosigner.key();
+        clawback_record.amount = amount;
+        clawback_record.reason_code = restriction.reason_code;
+        clawback_record.blocked_at = restriction.blocked_at;
+        clawback_record.timestamp = clock.unix_timestamp;
+        clawback_record.bump = ctx.bumps.clawback_record;
+
+        log_verbose!(
+            "Clawed back {}.{:09} tokens from {} to the treasury (reason {})",
+            amount / 1_000_000_000,
+            amount % 1_000_000_000,
+            clawback_record.token_account,
+            clawback_record.reason_code
+        );
+
+        emit_cpi!(ClawbackEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            token_account: clawback_record.token_account,
+            owner: clawback_record.owner,
+            treasury_token_account: clawback_record.treasury_token_account,
+            authority: clawback_record.authority,
+            cosigner: clawback_record.cosigner,
+            amount,
+            reason_code: clawback_record.reason_code,
+            blocked_at: clawback_record.blocked_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct AccountRestrictedEvent {
+    pub sequence: u64,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub authority: Pubkey,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountRestrictionLiftedEvent {
+    pub sequence: u64,
+    pub token_account: Pubkey,
+    pub authority: Pubkey,
+    pub blocked_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackEvent {
+    pub sequence: u64,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub treasury_token_account: Pubkey,
+    pub authority: Pubkey,
+    pub cosigner: Pubkey,
+    pub amount: u64,
+    pub reason_code: u16,
+    pub blocked_at: i64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,410 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use super::mint_tokens::{check_mint_cosigner, record_admin_mint};
+
+/// Dispute one of the customer's own purchases within the dispute window
+#[event_cpi]
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    /// The customer of the disputed purchase, paying for the dispute account
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The disputed purchase, which must be the customer's own
+    /// Either seed scheme is accepted, so purchases recorded before records were keyed by
+    /// merchant can still be disputed
+    #[account(
+        mut,
+        constraint = config.is_record_customer(&transaction_record.customer, &customer.key()) @ CarsaError::InvalidTransactionRecord,
+        constraint = transaction_record.is_at(&transaction_record.key()) @ CarsaError::InvalidTransactionRecord
+    )]
+    pub transaction_record: Account<'info, PurchaseTransaction>,
+
+    /// The dispute to be created, at most one per purchase
+    #[account(
+        init,
+        payer = customer,
+        space = Dispute::LEN,
+        seeds = [DISPUTE_SEED, transaction_record.key().as_ref()],
+        bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    /// The purchase's stub, moved to disputed; created here for purchases recorded
+    /// before stubs existed
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = TransactionStub::LEN,
+        seeds = [
+            TRANSACTION_STUB_SEED,
+            transaction_record.merchant.as_ref(),
+            transaction_record.customer.as_ref(),
+            &transaction_record.transaction_id
+        ],
+        bump,
+    )]
+    pub transaction_stub: Account<'info, TransactionStub>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Attach the merchant's response to an open dispute
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RespondDispute<'info> {
+    /// The merchant's owner wallet
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant of the disputed purchase
+    #[account(
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The dispute being answered
+    #[account(
+        mut,
+        seeds = [DISPUTE_SEED, dispute.purchase.as_ref()],
+        bump = dispute.bump,
+        constraint = dispute.merchant == merchant_account.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub dispute: Account<'info, Dispute>,
+}
+
+/// Decide a dispute: upholding it refunds the customer's redeemed tokens, rejecting it
+/// closes it without a refund
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    /// The update authority of the mint configuration, paying for a missing stub
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The dispute being decided
+    #[account(
+        mut,
+        seeds = [DISPUTE_SEED, dispute.purchase.as_ref()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    /// The disputed purchase
+    #[account(
+        mut,
+        address = dispute.purchase
+    )]
+    pub transaction_record: Account<'info, PurchaseTransaction>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// The customer's token account receiving any refund
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = config.is_record_customer(&dispute.customer, &customer_token_account.owner) @ CarsaError::InvalidOwner
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program for minting the refund
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The configured mint co-signer, required when the refund is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Signer<'info>>,
+
+    /// The purchase's stub, moved to refunded or resolved; created here for purchases recorded
+    /// before stubs existed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TransactionStub::LEN,
+        seeds = [
+            TRANSACTION_STUB_SEED,
+            transaction_record.merchant.as_ref(),
+            transaction_record.customer.as_ref(),
+            &transaction_record.transaction_id
+        ],
+        bump,
+    )]
+    pub transaction_stub: Account<'info, TransactionStub>,
+
+    /// System program required for creating a missing stub
+    pub system_program: Program<'info, System>,
+}
+
+/// Fill in a stub that `init_if_needed` just created for a purchase recorded before stubs
+/// existed, carrying over the status its record shows
+pub(crate) fn backfill_transaction_stub(
+    transaction_stub: &mut Account<TransactionStub>,
+    transaction_record: &PurchaseTransaction,
+    bump: u8,
+) {
+    if transaction_stub.customer != Pubkey::default() {
+        return;
+    }
+    let status = if transaction_record.refunded {
+        TransactionStatus::Refunded
+    } else if transaction_record.disputed {
+        TransactionStatus::Disputed
+    } else if transaction_record.fulfilled_at != 0 {
+        TransactionStatus::Fulfilled
+    } else {
+        TransactionStatus::Completed
+    };
+    transaction_stub.set_inner(TransactionStub {
+        customer: transaction_record.customer,
+        merchant: transaction_record.merchant,
+        status,
+        timestamp: transaction_record.timestamp,
+        bump,
+    });
+}
+
+impl<'info> OpenDispute<'info> {
+    /// Handler for opening a dispute
+    pub fn handler(ctx: Context<OpenDispute>, reason_code: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        let transaction_record = &mut ctx.accounts.transaction_record;
+
+        let window_end = transaction_record
+            .timestamp
+            .checked_add(config.dispute_window_seconds as i64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            config.dispute_window_seconds > 0 && clock.unix_timestamp <= window_end,
+            CarsaError::DisputeWindowClosed
+        );
+        require!(!transaction_record.refunded, CarsaError::PurchaseAlreadyRefunded);
+
+        let transaction_stub = &mut ctx.accounts.transaction_stub;
+        backfill_transaction_stub(transaction_stub, transaction_record, ctx.bumps.transaction_stub);
+        transaction_stub.transition(TransactionStatus::Disputed, clock.unix_timestamp)?;
+
+        // Freezes refunds on the record to the dispute's resolution
+        transaction_record.disputed = true;
+
+        let dispute = &mut ctx.accounts.dispute;
+        dispute.set_inner(Dispute {
+            purchase: transaction_record.key(),
+            customer: transaction_record.customer,
+            merchant: transaction_record.merchant,
+            reason_code,
+            response_code: 0,
+            status: DisputeStatus::Open,
+            opened_at: clock.unix_timestamp,
+            responded_at: 0,
+            resolved_at: 0,
+            refund_amount: 0,
+            bump: ctx.bumps.dispute,
+        });
+
+        log_verbose!(
+            "Dispute {} opened on purchase {} with reason {}",
+            dispute.key(),
+            dispute.purchase,
+            reason_code
+        );
+
+        emit_cpi!(DisputeOpenedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            dispute: dispute.key(),
+            purchase: dispute.purchase,
+            customer: dispute.customer,
+            merchant: dispute.merchant,
+            reason_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RespondDispute<'info> {
+    /// Handler for the merchant's response to a dispute
+    pub fn handler(ctx: Context<RespondDispute>, response_code: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        let dispute = &mut ctx.accounts.dispute;
+        require!(dispute.status == DisputeStatus::Open, CarsaError::DisputeNotOpen);
+
+        dispute.response_code = response_code;
+        dispute.responded_at = clock.unix_timestamp;
+
+        log_verbose!("Dispute {} answered with response {}", dispute.key(), response_code);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(DisputeRespondedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            dispute: dispute.key(),
+            merchant: dispute.merchant,
+            response_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ResolveDispute<'info> {
+    /// Handler for deciding a dispute
+    pub fn handler(ctx: Context<ResolveDispute>, uphold: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        let decision = if uphold {
+            DisputeStatus::Upheld
+        } else {
+            DisputeStatus::Rejected
+        };
+
+        // Repeating the decision is a no-op, so a retried resolution cannot refund twice
+        let dispute = &mut ctx.accounts.dispute;
+        if dispute.status == decision {
+            log_verbose!("Dispute {} already resolved as {:?}", dispute.key(), decision);
+            return Ok(());
+        }
+        require!(dispute.status == DisputeStatus::Open, CarsaError::DisputeAlreadyResolved);
+
+        let config = &mut ctx.accounts.config;
+        let transaction_record = &mut ctx.accounts.transaction_record;
+        let transaction_stub = &mut ctx.accounts.transaction_stub;
+        backfill_transaction_stub(transaction_stub, transaction_record, ctx.bumps.transaction_stub);
+        transaction_stub.transition(
+            if uphold { TransactionStatus::Refunded } else { TransactionStatus::Resolved },
+            clock.unix_timestamp,
+        )?;
+        let mut refund_amount = 0;
+        let mut cosigner = Pubkey::default();
+
+        // Refund path: re-credit the redeemed tokens; the merchant settles any fiat
+        // off-chain
+        if uphold {
+            require!(!transaction_record.refunded, CarsaError::PurchaseAlreadyRefunded);
+            refund_amount = transaction_record.redeemed_token_amount;
+
+            if refund_amount > 0 {
+                // Refunds are mints too, so large ones need the co-signer
+                cosigner = check_mint_cosigner(config, refund_amount, ctx.accounts.cosigner.as_ref())?;
+
+                record_admin_mint(config, refund_amount)?;
+
+                let authority_seeds = &[
+                    MINT_AUTHORITY_SEED,
+                    &[config.mint_authority_bump],
+                ];
+                let signer_seeds = &[&authority_seeds[..]];
+
+                let cpi_accounts = token_interface::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.customer_token_account.to_account_info(),
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+                token_interface::mint_to(cpi_ctx, refund_amount)?;
+            }
+
+            transaction_record.refunded = true;
+        }
+
+        dispute.status = decision;
+        dispute.resolved_at = clock.unix_timestamp;
+        dispute.refund_amount = refund_amount;
+
+        log_verbose!(
+            "Dispute {} resolved as {:?}, refunded {} tokens",
+            dispute.key(),
+            decision,
+            refund_amount
+        );
+
+        emit_cpi!(DisputeResolvedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            dispute: dispute.key(),
+            purchase: dispute.purchase,
+            customer: dispute.customer,
+            upheld: uphold,
+            refund_amount,
+            cosigner,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct DisputeOpenedEvent {
+    pub sequence: u64,
+    pub dispute: Pubkey,
+    pub purchase: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeRespondedEvent {
+    pub sequence: u64,
+    pub dispute: Pubkey,
+    pub merchant: Pubkey,
+    pub response_code: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolvedEvent {
+    pub sequence: u64,
+    pub dispute: Pubkey,
+    pub purchase: Pubkey,
+    pub customer: Pubkey,
+    pub upheld: bool,
+    /// Tokens re-credited to the customer (0 when rejected)
+    pub refund_amount: u64,
+    /// The mint co-signer that approved the refund (default pubkey when not required)
+    pub cosigner: Pubkey,
+    pub timestamp: i64,
+}
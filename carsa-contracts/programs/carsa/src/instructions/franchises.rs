@@ -0,0 +1,233 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::utils::remaining_accounts::expect_program_accounts;
+use super::rewards::{HighCashbackAlertEvent, MerchantUpdatedEvent};
+
+/// Create the parent account of a franchise brand
+#[derive(Accounts)]
+pub struct CreateParentMerchant<'info> {
+    /// The brand's owner wallet, paying for the parent account
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The parent merchant to be created
+    #[account(
+        init,
+        payer = owner,
+        space = ParentMerchant::LEN,
+        seeds = [PARENT_MERCHANT_SEED, owner.key().as_ref()],
+        bump,
+    )]
+    pub parent_merchant: Account<'info, ParentMerchant>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Update the cashback rate a parent cascades to its outlets
+#[derive(Accounts)]
+pub struct UpdateParentMerchant<'info> {
+    /// The brand's owner wallet
+    pub owner: Signer<'info>,
+
+    /// The parent merchant to update
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, owner.key().as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Account<'info, ParentMerchant>,
+}
+
+/// Apply a parent's default cashback rate to a batch of its outlets
+/// Outlet merchant accounts are passed as writable `remaining_accounts`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CascadeCashbackRate<'info> {
+    /// The brand's owner wallet
+    pub owner: Signer<'info>,
+
+    /// The parent merchant whose default rate is applied
+    #[account(
+        seeds = [PARENT_MERCHANT_SEED, owner.key().as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Account<'info, ParentMerchant>,
+
+    /// Configuration account containing mint settings
+    /// Writable so each outlet's change can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+impl<'info> CreateParentMerchant<'info> {
+    /// Handler for creating a parent merchant
+    pub fn handler(
+        ctx: Context<CreateParentMerchant>,
+        name: String,
+        default_cashback_rate: u16,
+    ) -> Result<()> {
+        require!(name.len() <= 32 && !name.is_empty(), CarsaError::InvalidMerchantName);
+        require!(default_cashback_rate <= 10_000, CarsaError::InvalidCashbackRate);
+
+        let mut name_bytes = [0u8; 32];
+        name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+        let clock = Clock::get()?;
+        ctx.accounts.parent_merchant.set_inner(ParentMerchant {
+            owner: ctx.accounts.owner.key(),
+            name: name_bytes,
+            default_cashback_rate,
+            child_count: 0,
+            total_transactions: 0,
+            total_rewards_distributed: 0,
+            total_volume: 0,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.parent_merchant,
+        });
+
+        log_verbose!(
+            "Parent merchant registered: {}, default cashback: {}bps",
+            name,
+            default_cashback_rate
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> UpdateParentMerchant<'info> {
+    /// Handler for updating a parent merchant's default cashback rate
+    /// Outlets keep their own rates until the parent cascades the new one
+    pub fn handler(ctx: Context<UpdateParentMerchant>, default_cashback_rate: u16) -> Result<()> {
+        require!(default_cashback_rate <= 10_000, CarsaError::InvalidCashbackRate);
+
+        ctx.accounts.parent_merchant.default_cashback_rate = default_cashback_rate;
+        log_verbose!("Parent merchant default cashback updated to: {}bps", default_cashback_rate);
+
+        Ok(())
+    }
+}
+
+impl<'info> CascadeCashbackRate<'info> {
+    /// Handler for cascading a parent's default cashback rate to its outlets
+    pub fn handler(ctx: Context<'_, '_, 'info, 'info, CascadeCashbackRate<'info>>) -> Result<()> {
+        let parent_key = ctx.accounts.parent_merchant.key();
+        let rate = ctx.accounts.parent_merchant.default_cashback_rate;
+        let timestamp = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.config.allows_cashback_rate(rate),
+            CarsaError::CashbackRateAboveMax
+        );
+
+        let outlets = expect_program_accounts::<MerchantAccount>(
+            ctx.remaining_accounts,
+            MAX_CASCADE_BATCH,
+            CarsaError::CascadeBatchTooLarge,
+        )?;
+
+        for merchant_account in outlets {
+            let mut merchant_account = merchant_account?;
+            require!(
+                merchant_account.parent_merchant == parent_key,
+                CarsaError::ParentMerchantMismatch
+            );
+
+            // The parent's rate applies without the cooldown and increase cap, but still
+            // starts a cooldown for the outlet's own changes
+            let old = MerchantAccount::clone(&merchant_account);
+            if merchant_account.cashback_rate != rate {
+                merchant_account.cashback_rate = rate;
+                merchant_account.last_rate_change_at = timestamp;
+            }
+            merchant_account.exit(ctx.program_id)?;
+
+            let config = &mut ctx.accounts.config;
+            emit_cpi!(MerchantUpdatedEvent::between(
+                config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                merchant_account.key(),
+                &old,
+                &merchant_account,
+                timestamp,
+            ));
+            if old.cashback_rate != rate && config.is_high_cashback_rate(rate) {
+                emit_cpi!(HighCashbackAlertEvent {
+                    sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                    merchant: merchant_account.key(),
+                    cashback_rate: rate,
+                    alert_threshold_bps: config.alert_threshold_bps,
+                    timestamp,
+                });
+            }
+        }
+
+        log_verbose!(
+            "Cashback rate {}bps cascaded to {} outlets of parent {}",
+            rate,
+            ctx.remaining_accounts.len(),
+            parent_key
+        );
+
+        Ok(())
+    }
+}
+
+/// Link a newly registered merchant to its parent, which must co-sign the registration
+pub(crate) fn join_parent(
+    merchant_account: &mut MerchantAccount,
+    parent_merchant: &mut Account<ParentMerchant>,
+    parent_owner: Option<&Signer>,
+) -> Result<()> {
+    require!(
+        parent_owner.is_some_and(|owner| owner.key() == parent_merchant.owner),
+        CarsaError::ParentOwnerMismatch
+    );
+
+    merchant_account.parent_merchant = parent_merchant.key();
+    parent_merchant.child_count = parent_merchant
+        .child_count
+        .checked_add(1)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    Ok(())
+}
+
+/// Add a purchase at `merchant_account` to its parent's aggregate counters
+/// Outlets must always pass their parent, so the aggregates stay the sum of the outlets';
+/// independent merchants must not pass one
+pub(crate) fn roll_up_purchase(
+    merchant_account: &MerchantAccount,
+    parent_merchant: Option<&mut Account<ParentMerchant>>,
+    total_value: u64,
+    reward_amount: u64,
+) -> Result<()> {
+    let parent_merchant = match parent_merchant {
+        Some(parent_merchant) if parent_merchant.key() == merchant_account.parent_merchant => {
+            parent_merchant
+        }
+        None if merchant_account.parent_merchant == Pubkey::default() => return Ok(()),
+        _ => return err!(CarsaError::ParentMerchantMismatch),
+    };
+
+    parent_merchant.total_transactions = parent_merchant
+        .total_transactions
+        .checked_add(1)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    parent_merchant.total_volume = parent_merchant
+        .total_volume
+        .checked_add(total_value)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    parent_merchant.total_rewards_distributed = parent_merchant
+        .total_rewards_distributed
+        .checked_add(reward_amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+use super::disputes::backfill_transaction_stub;
+
+/// Set the backend key that records off-chain fulfillment, and how long it has to do so
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFulfillmentAuthority<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Record that the off-chain fulfillment of a purchase, such as a phone credit top-up,
+/// has completed
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MarkFulfilled<'info> {
+    /// The configured fulfillment authority, paying for a missing stub
+    #[account(
+        mut,
+        constraint = fulfillment_authority.key() != Pubkey::default() @ CarsaError::Unauthorized,
+        constraint = fulfillment_authority.key() == config.fulfillment_authority @ CarsaError::Unauthorized
+    )]
+    pub fulfillment_authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The fulfilled purchase
+    /// Records from before `PurchaseTransaction` outgrew 164 bytes must go through
+    /// `migrate_purchase_transaction` first
+    #[account(mut)]
+    pub transaction_record: Account<'info, PurchaseTransaction>,
+
+    /// The purchase's stub, moved to fulfilled; created here for purchases recorded
+    /// before stubs existed
+    #[account(
+        init_if_needed,
+        payer = fulfillment_authority,
+        space = TransactionStub::LEN,
+        seeds = [
+            TRANSACTION_STUB_SEED,
+            transaction_record.merchant.as_ref(),
+            transaction_record.customer.as_ref(),
+            &transaction_record.transaction_id
+        ],
+        bump,
+    )]
+    pub transaction_stub: Account<'info, TransactionStub>,
+
+    /// System program required for creating a missing stub
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetFulfillmentAuthority<'info> {
+    /// Handler for updating the fulfillment authority and window
+    pub fn handler(
+        ctx: Context<SetFulfillmentAuthority>,
+        fulfillment_authority: Pubkey,
+        fulfillment_window_seconds: u32,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let old_fulfillment_authority = config.fulfillment_authority;
+        let old_fulfillment_window_seconds = config.fulfillment_window_seconds;
+        config.fulfillment_authority = fulfillment_authority;
+        config.fulfillment_window_seconds = fulfillment_window_seconds;
+
+        log_verbose!(
+            "Fulfillment authority set to {} with a {}s window",
+            fulfillment_authority,
+            fulfillment_window_seconds
+        );
+
+        emit_cpi!(FulfillmentAuthorityUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            fulfillment_authority: Changed {
+                old: old_fulfillment_authority,
+                new: fulfillment_authority,
+            },
+            fulfillment_window_seconds: Changed {
+                old: old_fulfillment_window_seconds,
+                new: fulfillment_window_seconds,
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> MarkFulfilled<'info> {
+    /// Handler for recording a purchase's fulfillment
+    pub fn handler(ctx: Context<MarkFulfilled>) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        let transaction_record = &mut ctx.accounts.transaction_record;
+
+        require!(transaction_record.fulfilled_at == 0, CarsaError::AlreadyFulfilled);
+        let window_end = transaction_record
+            .timestamp
+            .checked_add(config.fulfillment_window_seconds as i64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            clock.unix_timestamp <= window_end,
+            CarsaError::FulfillmentWindowClosed
+        );
+
+        let transaction_stub = &mut ctx.accounts.transaction_stub;
+        backfill_transaction_stub(transaction_stub, transaction_record, ctx.bumps.transaction_stub);
+        transaction_stub.transition(TransactionStatus::Fulfilled, clock.unix_timestamp)?;
+
+        transaction_record.fulfilled_at = clock.unix_timestamp;
+
+        log_verbose!("Purchase {} fulfilled", transaction_record.key());
+
+        emit_cpi!(PurchaseFulfilledEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            purchase: transaction_record.key(),
+            customer: transaction_record.customer,
+            merchant: transaction_record.merchant,
+            transaction_id: transaction_record.transaction_id,
+            fulfilled_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct FulfillmentAuthorityUpdatedEvent {
+    pub sequence: u64,
+    pub fulfillment_authority: Changed<Pubkey>,
+    pub fulfillment_window_seconds: Changed<u32>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchaseFulfilledEvent {
+    pub sequence: u64,
+    pub purchase: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub transaction_id: [u8; 32],
+    pub fulfilled_at: i64,
+}
@@ -0,0 +1,374 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Escrow tokens behind the hash of a secret code
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(amount: u64, code_hash: [u8; 32])]
+pub struct CreateGiftCard<'info> {
+    /// The wallet buying the gift card, paying for its accounts
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    /// The issuer's token account the gift card is funded from
+    #[account(
+        mut,
+        constraint = issuer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub issuer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    #[account(
+        init_if_needed,
+        payer = issuer,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [GIFT_CARD_ESCROW_SEED],
+        bump,
+    )]
+    pub gift_card_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The gift card to be created
+    #[account(
+        init,
+        payer = issuer,
+        space = GiftCard::LEN,
+        seeds = [GIFT_CARD_SEED, &code_hash],
+        bump,
+    )]
+    pub gift_card: Account<'info, GiftCard>,
+
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Claim a gift card's tokens by presenting its secret code
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RedeemGiftCard<'info> {
+    /// The wallet presenting the code
+    pub claimer: Signer<'info>,
+
+    /// The claimer's token account receiving the tokens
+    #[account(
+        mut,
+        constraint = claimer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub claimer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The gift card's issuer, refunded the gift card's rent
+    /// CHECK: Only receives lamports; must be the gift card's issuer
+    #[account(
+        mut,
+        address = gift_card.issuer
+    )]
+    pub issuer: UncheckedAccount<'info>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    #[account(
+        mut,
+        seeds = [GIFT_CARD_ESCROW_SEED],
+        bump,
+    )]
+    pub gift_card_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The gift card, closed so its code cannot be replayed
+    #[account(
+        mut,
+        close = issuer,
+        seeds = [GIFT_CARD_SEED, &gift_card.code_hash],
+        bump = gift_card.bump,
+    )]
+    pub gift_card: Account<'info, GiftCard>,
+
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Return an expired gift card's tokens to its issuer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReclaimGiftCard<'info> {
+    /// The wallet that bought the gift card
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    /// The issuer's token account receiving the tokens
+    #[account(
+        mut,
+        constraint = issuer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub issuer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding gift card tokens until they are redeemed or reclaimed
+    #[account(
+        mut,
+        seeds = [GIFT_CARD_ESCROW_SEED],
+        bump,
+    )]
+    pub gift_card_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The expired gift card
+    #[account(
+        mut,
+        close = issuer,
+        seeds = [GIFT_CARD_SEED, &gift_card.code_hash],
+        bump = gift_card.bump,
+        constraint = gift_card.issuer == issuer.key() @ CarsaError::Unauthorized
+    )]
+    pub gift_card: Account<'info, GiftCard>,
+
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Move `amount` out of the gift card escrow, signed by the mint authority PDA
+fn release_from_escrow<'info>(
+    config: &LokalMintConfig,
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    gift_card_escrow: &InterfaceAccount<'info, TokenAccount>,
+    mint_authority: &UncheckedAccount<'info>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    let authority_seeds = &[
+        MINT_AUTHORITY_SEED,
+        &[config.mint_authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let cpi_accounts = token_interface::TransferChecked {
+        from: gift_card_escrow.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: mint_authority.to_account_info(),
+    };
+    let cpi_program = token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, amount, config.decimals())
+}
+
+impl<'info> CreateGiftCard<'info> {
+    /// Handler for creating a gift card
+    pub fn handler(
+        ctx: Context<CreateGiftCard>,
+        amount: u64,
+        code_hash: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            amount > 0 && expires_at > clock.unix_timestamp,
+            CarsaError::InvalidGiftCard
+        );
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.issuer_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.gift_card_escrow.to_account_info(),
+            authority: ctx.accounts.issuer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.config.decimals())?;
+
+        let issuer = ctx.accounts.issuer.key();
+        let gift_card = &mut ctx.accounts.gift_card;
+        gift_card.set_inner(GiftCard {
+            issuer,
+            code_hash,
+            amount,
+            expires_at,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.gift_card,
+        });
+
+        log_verbose!("Gift card {} created: {} tokens, expires at {}", gift_card.key(), amount, expires_at);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(GiftCardCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            gift_card: gift_card.key(),
+            issuer,
+            amount,
+            expires_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RedeemGiftCard<'info> {
+    /// Handler for redeeming a gift card
+    /// The code is only hashed, never logged or stored
+    pub fn handler(ctx: Context<RedeemGiftCard>, code_preimage: Vec<u8>) -> Result<()> {
+        let clock = Clock::get()?;
+        let gift_card = &ctx.accounts.gift_card;
+        require!(
+            code_preimage.len() <= GiftCard::MAX_CODE_LEN
+                && hash(&code_preimage).to_bytes() == gift_card.code_hash,
+            CarsaError::InvalidGiftCardCode
+        );
+        require!(clock.unix_timestamp < gift_card.expires_at, CarsaError::GiftCardExpired);
+
+        release_from_escrow(
+            &ctx.accounts.config,
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.gift_card_escrow,
+            &ctx.accounts.mint_authority,
+            &ctx.accounts.claimer_token_account,
+            gift_card.amount,
+        )?;
+
+        log_verbose!("Gift card {} redeemed: {} tokens", gift_card.key(), gift_card.amount);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(GiftCardRedeemedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            gift_card: gift_card.key(),
+            issuer: gift_card.issuer,
+            claimer: ctx.accounts.claimer.key(),
+            amount: gift_card.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReclaimGiftCard<'info> {
+    /// Handler for reclaiming an expired gift card
+    pub fn handler(ctx: Context<ReclaimGiftCard>) -> Result<()> {
+        let clock = Clock::get()?;
+        let gift_card = &ctx.accounts.gift_card;
+        require!(clock.unix_timestamp >= gift_card.expires_at, CarsaError::GiftCardNotExpired);
+
+        release_from_escrow(
+            &ctx.accounts.config,
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.gift_card_escrow,
+            &ctx.accounts.mint_authority,
+            &ctx.accounts.issuer_token_account,
+            gift_card.amount,
+        )?;
+
+        log_verbose!("Gift card {} reclaimed: {} tokens", gift_card.key(), gift_card.amount);
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(GiftCardReclaimedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            gift_card: gift_card.key(),
+            issuer: gift_card.issuer,
+            amount: gift_card.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct GiftCardCreatedEvent {
+    pub sequence: u64,
+    pub gift_card: Pubkey,
+    pub issuer: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GiftCardRedeemedEvent {
+    pub sequence: u64,
+    pub gift_card: Pubkey,
+    pub issuer: Pubkey,
+    pub claimer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GiftCardReclaimedEvent {
+    pub sequence: u64,
+    pub gift_card: Pubkey,
+    pub issuer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
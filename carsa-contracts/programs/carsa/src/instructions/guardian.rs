@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Set the guardian key that can pause but not change parameters
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Halt or resume purchases and transfers
+/// The guardian can only pause; unpausing requires the update authority
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetGlobalPause<'info> {
+    /// The update authority or guardian, checked in the handler
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Suspend or reinstate a merchant
+/// The guardian can only suspend; reinstating requires the update authority
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMerchantSuspension<'info> {
+    /// The update authority or guardian, checked in the handler
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The merchant to suspend or reinstate
+    #[account(mut)]
+    pub merchant_account: Account<'info, MerchantAccount>,
+}
+
+/// Only the update authority may lift a pause; the guardian may only apply one
+fn check_pauser(config: &LokalMintConfig, signer: &Pubkey, pausing: bool) -> Result<()> {
+    if *signer != config.update_authority {
+        require!(config.is_guardian(signer), CarsaError::UnauthorizedPauser);
+        require!(pausing, CarsaError::GuardianCannotUnpause);
+    }
+    Ok(())
+}
+
+impl<'info> SetGuardian<'info> {
+    /// Handler for updating the guardian
+    pub fn handler(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let old_guardian = config.guardian;
+        config.guardian = guardian;
+
+        log_verbose!("Guardian set to {}", guardian);
+
+        emit_cpi!(GuardianUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            guardian: Changed { old: old_guardian, new: guardian },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SetGlobalPause<'info> {
+    /// Handler for pausing or unpausing purchases and transfers
+    pub fn handler(ctx: Context<SetGlobalPause>, paused: bool) -> Result<()> {
+        let triggered_by = ctx.accounts.authority.key();
+        let config = &mut ctx.accounts.config;
+        check_pauser(config, &triggered_by, paused)?;
+
+        let old_paused = config.paused;
+        config.paused = paused;
+
+        log_verbose!("Global pause set to {} by {}", paused, triggered_by);
+
+        emit_cpi!(GlobalPauseEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            paused: Changed { old: old_paused, new: paused },
+            triggered_by,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SetMerchantSuspension<'info> {
+    /// Handler for suspending or reinstating a merchant
+    pub fn handler(ctx: Context<SetMerchantSuspension>, suspended: bool) -> Result<()> {
+        let triggered_by = ctx.accounts.authority.key();
+        let config = &mut ctx.accounts.config;
+        check_pauser(config, &triggered_by, suspended)?;
+
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let old_suspended = merchant_account.suspended;
+        merchant_account.suspended = suspended;
+
+        log_verbose!(
+            "Merchant {} suspension set to {} by {}",
+            merchant_account.key(),
+            suspended,
+            triggered_by
+        );
+
+        emit_cpi!(MerchantSuspensionEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            suspended: Changed { old: old_suspended, new: suspended },
+            triggered_by,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct GuardianUpdatedEvent {
+    pub sequence: u64,
+    pub guardian: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GlobalPauseEvent {
+    pub sequence: u64,
+    pub paused: Changed<bool>,
+    /// The update authority or guardian that made the change
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantSuspensionEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub suspended: Changed<bool>,
+    /// The update authority or guardian that made the change
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,747 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::instructions::rewards::{
+    current_token_to_idr_rate, effective_cashback_rate, purchase_reward,
+    PurchaseProcessedEvent,
+};
+use super::franchises::roll_up_purchase;
+use super::mint_tokens::record_reward_mint;
+
+/// Hold tokens in escrow for a merchant to capture when the bill is settled
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(hold_id: [u8; 32])]
+pub struct CreateHold<'info> {
+    /// The customer placing the hold, paying for the hold account
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// The merchant that may capture the hold
+    #[account(
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The customer's token account the held tokens come from
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account holding tokens until the hold is captured or released
+    #[account(
+        init_if_needed,
+        payer = customer,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [HOLD_ESCROW_SEED],
+        bump,
+    )]
+    pub hold_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The hold to be created
+    #[account(
+        init,
+        payer = customer,
+        space = Hold::LEN,
+        seeds = [HOLD_SEED, customer.key().as_ref(), &hold_id],
+        bump,
+    )]
+    pub hold: Account<'info, Hold>,
+
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Capture part or all of a hold as the token side of a purchase, releasing the rest
+/// back to the customer
+/// The purchase is rewarded and recorded exactly like `process_purchase`, with the
+/// hold's id as the transaction id
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CaptureHold<'info> {
+    /// The merchant's owner wallet, paying for the purchase record
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant capturing the hold
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch,
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The hold being captured, closed to the customer that paid for it
+    #[account(
+        mut,
+        close = customer,
+        seeds = [HOLD_SEED, hold.customer.as_ref(), &hold.hold_id],
+        bump = hold.bump,
+        constraint = hold.merchant == merchant_account.key() @ CarsaError::HoldMismatch
+    )]
+    pub hold: Account<'info, Hold>,
+
+    /// The customer that placed the hold
+    /// CHECK: Only receives the hold's rent; must be the hold's customer
+    #[account(
+        mut,
+        address = hold.customer @ CarsaError::HoldMismatch
+    )]
+    pub customer: UncheckedAccount<'info>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The customer's token account receiving the reward and the released remainder
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = customer_token_account.owner == hold.customer @ CarsaError::HoldMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The merchant's token account receiving the captured tokens
+    #[account(
+        mut,
+        constraint = merchant_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account holding tokens until the hold is captured or released
+    #[account(
+        mut,
+        seeds = [HOLD_ESCROW_SEED],
+        bump,
+    )]
+    pub hold_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Purchase transaction record for tracking
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = PurchaseTransaction::LEN,
+        seeds = [
+            TRANSACTION_SEED,
+            hold.merchant.as_ref(),
+            config.record_customer(&hold.customer).as_ref(),
+            &hold.hold_id
+        ],
+        bump,
+    )]
+    pub transaction_record: Account<'info, PurchaseTransaction>,
+
+    /// SPL Token program for the transfers and reward mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            stake_pool.load()?.mint_seed().as_ref(),
+            pool_id_seed(0).as_ref()
+        ],
+        bump = stake_pool.load()?.bump,
+        constraint = stake_pool.load()?.voucher_mint == config.mint @ CarsaError::MintMismatch,
+    )]
+    pub stake_pool: Option<AccountLoader<'info, PoolState>>,
+
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Account<'info, UserStakeRecord>>,
+
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&hold.customer).as_ref(),
+            &hold.hold_id,
+            &[ActivityKind::Purchase as u8]
+        ],
+        bump,
+    )]
+    pub activity_record: Option<Account<'info, ActivityRecord>>,
+
+    /// Activity feed entry for the captured tokens, required only when activity recording
+    /// is enabled and tokens are captured
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&hold.customer).as_ref(),
+            &hold.hold_id,
+            &[ActivityKind::Redemption as u8]
+        ],
+        bump,
+    )]
+    pub redemption_activity_record: Option<Account<'info, ActivityRecord>>,
+
+    /// The configured treasury token account, required when the capture owes a protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The configured Pyth price account, required when the config sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = RewardLot::LEN,
+        seeds = [REWARD_LOT_SEED, config.record_customer(&hold.customer).as_ref(), &hold.hold_id],
+        bump,
+    )]
+    pub reward_lot: Option<Account<'info, RewardLot>>,
+
+    /// The merchant's franchise parent, required when the merchant has one
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, parent_merchant.owner.as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Option<Account<'info, ParentMerchant>>,
+}
+
+/// Return an expired hold's tokens to the customer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReleaseHold<'info> {
+    /// The customer that placed the hold
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding tokens until the hold is captured or released
+    #[account(
+        mut,
+        seeds = [HOLD_ESCROW_SEED],
+        bump,
+    )]
+    pub hold_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The customer's token account receiving the held tokens
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The expired hold
+    #[account(
+        mut,
+        close = customer,
+        seeds = [HOLD_SEED, customer.key().as_ref(), &hold.hold_id],
+        bump = hold.bump,
+    )]
+    pub hold: Account<'info, Hold>,
+
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CreateHold<'info> {
+    /// Handler for placing a hold on the customer's tokens
+    pub fn handler(
+        ctx: Context<CreateHold>,
+        hold_id: [u8; 32],
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            amount > 0 && expires_at > clock.unix_timestamp,
+            CarsaError::InvalidHold
+        );
+        require!(
+            ctx.accounts.customer_token_account.amount >= amount,
+            CarsaError::InsufficientBalance
+        );
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.customer_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.hold_escrow.to_account_info(),
+            authority: ctx.accounts.customer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token_interface::transfer_checked(cpi_ctx, amount, ctx.accounts.config.decimals())?;
+
+        let customer = ctx.accounts.customer.key();
+        let merchant = ctx.accounts.merchant_account.key();
+        let hold = &mut ctx.accounts.hold;
+        hold.set_inner(Hold {
+            customer,
+            merchant,
+            hold_id,
+            amount,
+            expires_at,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.hold,
+        });
+
+        log_verbose!(
+            "Hold {} placed: {} tokens for merchant {}, expires at {}",
+            hold.key(),
+            amount,
+            merchant,
+            expires_at
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(HoldCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer,
+            merchant,
+            hold: hold.key(),
+            amount,
+            expires_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> CaptureHold<'info> {
+    /// Handler for capturing a hold as the token side of a purchase
+    pub fn handler(
+        ctx: Context<CaptureHold>,
+        fiat_amount: u64,
+        capture_amount: u64,
+    ) -> Result<()> {
+        require!(fiat_amount > 0, CarsaError::InvalidPurchaseAmount);
+        require!(
+            ctx.accounts.config.allows_purchase(fiat_amount),
+            CarsaError::PurchaseAmountTooLarge
+        );
+
+        let clock = Clock::get()?;
+        let hold = &ctx.accounts.hold;
+        require!(!hold.is_expired(clock.unix_timestamp), CarsaError::HoldExpired);
+        require!(capture_amount <= hold.amount, CarsaError::HoldCaptureExceeded);
+        let released_amount = hold.amount - capture_amount;
+        let customer_key = hold.customer;
+        let transaction_id = hold.hold_id;
+        let hold_key = hold.key();
+
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let config = &mut ctx.accounts.config;
+        let transaction_record = &mut ctx.accounts.transaction_record;
+        let record_customer = config.record_customer(&customer_key);
+
+        let cashback_rate = effective_cashback_rate(
+            ctx.program_id,
+            config,
+            merchant_account.cashback_rate,
+            &customer_key,
+            ctx.accounts.stake_pool.as_ref(),
+            ctx.accounts.customer_stake_record.as_ref(),
+        )?;
+        let staker_boost_bps = cashback_rate.saturating_sub(merchant_account.cashback_rate);
+
+        // Captured tokens count as redeemed, exactly as in `process_purchase`
+        let used_tokens = capture_amount > 0;
+        let redemption_bonus_bps = if used_tokens {
+            merchant_account.redemption_bonus_bps
+        } else {
+            0
+        };
+
+        require!(
+            config.record_activity == ctx.accounts.activity_record.is_some()
+                && (config.record_activity && used_tokens)
+                    == ctx.accounts.redemption_activity_record.is_some(),
+            CarsaError::InvalidActivityRecord
+        );
+        require!(
+            config.expiring_rewards == ctx.accounts.reward_escrow.is_some()
+                && config.expiring_rewards == ctx.accounts.reward_lot.is_some(),
+            CarsaError::InvalidRewardLot
+        );
+
+        let token_to_idr_rate = current_token_to_idr_rate(
+            config,
+            ctx.accounts.price_feed.as_ref(),
+            clock.unix_timestamp,
+        )?;
+        let (total_value, reward_amount) = purchase_reward(
+            fiat_amount,
+            capture_amount,
+            cashback_rate,
+            token_to_idr_rate,
+            redemption_bonus_bps,
+        )?;
+        require!(
+            total_value >= merchant_account.min_purchase_amount,
+            CarsaError::PurchaseBelowMinimum
+        );
+
+        let protocol_fee = config
+            .protocol_fee(capture_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        // Pay out the escrow: the fee to the treasury, the capture to the merchant and
+        // whatever was not captured back to the customer
+        if protocol_fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(CarsaError::InvalidTreasuryAccount)?;
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.hold_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(cpi_ctx, protocol_fee, config.decimals())?;
+        }
+        for (destination, amount) in [
+            (ctx.accounts.merchant_token_account.to_account_info(), capture_amount - protocol_fee),
+            (ctx.accounts.customer_token_account.to_account_info(), released_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.hold_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: destination,
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(cpi_ctx, amount, config.decimals())?;
+        }
+
+        if reward_amount > 0 {
+            record_reward_mint(config, reward_amount)?;
+
+            // Expiring rewards are held in escrow until withdrawn
+            let reward_destination = match ctx.accounts.reward_escrow.as_ref() {
+                Some(reward_escrow) => reward_escrow.to_account_info(),
+                None => ctx.accounts.customer_token_account.to_account_info(),
+            };
+            let cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: reward_destination,
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::mint_to(cpi_ctx, reward_amount)?;
+        }
+
+        config.total_purchases = config
+            .total_purchases
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_transactions = merchant_account
+            .total_transactions
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_volume = merchant_account
+            .total_volume
+            .checked_add(total_value)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_rewards_distributed = merchant_account
+            .total_rewards_distributed
+            .checked_add(reward_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.unsettled_tokens = merchant_account
+            .unsettled_tokens
+            .checked_add(capture_amount - protocol_fee)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        roll_up_purchase(
+            merchant_account,
+            ctx.accounts.parent_merchant.as_mut(),
+            total_value,
+            reward_amount,
+        )?;
+
+        transaction_record.customer = record_customer;
+        transaction_record.merchant = merchant_account.key();
+        transaction_record.fiat_amount = fiat_amount;
+        transaction_record.redeemed_token_amount = capture_amount;
+        transaction_record.total_value = total_value;
+        transaction_record.reward_amount = reward_amount;
+        transaction_record.cashback_rate = cashback_rate;
+        transaction_record.used_tokens = used_tokens;
+        transaction_record.timestamp = clock.unix_timestamp;
+        transaction_record.transaction_id = transaction_id;
+        transaction_record.bump = ctx.bumps.transaction_record;
+        transaction_record.staker_boost_bps = staker_boost_bps;
+        transaction_record.protocol_fee = protocol_fee;
+        transaction_record.redemption_bonus_bps = redemption_bonus_bps;
+        transaction_record.invoice = Pubkey::default();
+
+        if let (Some(reward_lot), Some(bump)) = (
+            ctx.accounts.reward_lot.as_mut(),
+            ctx.bumps.reward_lot,
+        ) {
+            reward_lot.set_inner(RewardLot {
+                customer: record_customer,
+                transaction_id,
+                amount: reward_amount,
+                expired_amount: 0,
+                minted_at: clock.unix_timestamp,
+                expires_at: clock
+                    .unix_timestamp
+                    .checked_add(config.reward_lifetime_seconds)
+                    .ok_or(CarsaError::ArithmeticOverflow)?,
+                bump,
+            });
+        }
+
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.activity_record.as_mut(),
+            ctx.bumps.activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: reward_amount,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Purchase,
+                transaction_id,
+                bump,
+            });
+        }
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.redemption_activity_record.as_mut(),
+            ctx.bumps.redemption_activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: capture_amount,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Redemption,
+                transaction_id,
+                bump,
+            });
+        }
+
+        log_verbose!(
+            "Hold {} captured: {} tokens and Rp {} fiat, total value Rp {} IDR, {} tokens released, earned {} reward tokens",
+            hold_key,
+            capture_amount,
+            fiat_amount,
+            total_value,
+            released_amount,
+            reward_amount
+        );
+
+        emit_cpi!(PurchaseProcessedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: record_customer,
+            merchant: merchant_account.key(),
+            fiat_amount,
+            redeemed_token_amount: capture_amount,
+            total_value,
+            reward_amount,
+            cashback_rate,
+            staker_boost_bps,
+            category_boost_bps: 0,
+            category_boost_reward: 0,
+            streak_bonus: 0,
+            protocol_fee,
+            redemption_bonus_bps,
+            invoice: Pubkey::default(),
+            transaction_id,
+            pos_nonce: None,
+            reference_code: None,
+            timestamp: clock.unix_timestamp,
+        });
+        emit_cpi!(HoldCapturedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: customer_key,
+            merchant: merchant_account.key(),
+            hold: hold_key,
+            captured_amount: capture_amount,
+            released_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReleaseHold<'info> {
+    /// Handler for returning an expired hold to the customer
+    pub fn handler(ctx: Context<ReleaseHold>) -> Result<()> {
+        let clock = Clock::get()?;
+        let hold = &ctx.accounts.hold;
+        require!(hold.is_expired(clock.unix_timestamp), CarsaError::HoldNotExpired);
+
+        let config = &mut ctx.accounts.config;
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.hold_escrow.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.customer_token_account.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::transfer_checked(cpi_ctx, hold.amount, config.decimals())?;
+
+        log_verbose!("Hold {} released: {} tokens", hold.key(), hold.amount);
+
+        emit_cpi!(HoldReleasedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: hold.customer,
+            merchant: hold.merchant,
+            hold: hold.key(),
+            amount: hold.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct HoldCreatedEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HoldCapturedEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold: Pubkey,
+    /// Tokens captured into the purchase, including any protocol fee
+    pub captured_amount: u64,
+    /// Tokens returned to the customer
+    pub released_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HoldReleasedEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub hold: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,431 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::*;
+use crate::instructions::rewards::{registered_merchant_count, take_registry_position};
+use crate::instructions::voucher_pool::mint_receipts;
+use crate::state::*;
+
+/// Grow `account` to `new_len` bytes, topping its rent up from `payer`
+/// The new bytes are zeroed; callers rewrite the data afterwards
+pub(crate) fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+
+    account.resize(new_len)?;
+    Ok(())
+}
+
+// ============================================================================
+// Migrate Pool Instruction
+// ============================================================================
+
+/// Rewrite the original singleton pool from its first-deployment layout into `PoolState`
+/// That layout predates receipts, so this also creates the pool's sLOKAL receipt mint
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// The pool authority recorded in the legacy pool; pays for the larger account
+    #[account(mut)]
+    pub pool_authority: Signer<'info>,
+
+    /// The legacy pool state (PDA of pool 0)
+    /// CHECK: Still in the legacy layout, so the handler decodes it by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [POOL_STATE_SEED],
+        bump
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// The vault authority PDA, which mints and burns receipts
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [POOL_VAULT_AUTHORITY_SEED],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// The voucher token mint the pool stakes
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// The sLOKAL receipt mint (PDA), minted by the vault authority
+    #[account(
+        init,
+        payer = pool_authority,
+        seeds = [RECEIPT_MINT_SEED],
+        bump,
+        mint::decimals = voucher_mint.decimals,
+        mint::authority = pool_vault_authority
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// System program for the rent top-up and mint creation
+    pub system_program: Program<'info, System>,
+
+    /// Token program for the receipt mint
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl MigratePool<'_> {
+    pub fn handler(ctx: Context<MigratePool>) -> Result<()> {
+        let pool_info = ctx.accounts.pool_state.to_account_info();
+        let legacy = LegacyPoolState::from_account_data(&pool_info.try_borrow_data()?)
+            .ok_or(CarsaError::NotALegacyAccount)?;
+
+        require!(
+            ctx.accounts.pool_authority.key() == legacy.pool_authority,
+            CarsaError::Unauthorized
+        );
+        require!(
+            ctx.accounts.voucher_mint.key() == legacy.voucher_mint,
+            CarsaError::InvalidMint
+        );
+
+        let pool = legacy
+            .migrate(ctx.accounts.receipt_mint.key())
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        grow_account(
+            &pool_info,
+            &ctx.accounts.pool_authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PoolState::LEN,
+        )?;
+        pool_info.try_borrow_mut_data()?[8..]
+            .copy_from_slice(anchor_lang::__private::bytemuck::bytes_of(&pool));
+
+        log_verbose!("Pool migrated to the current layout");
+        log_verbose!("Receipt Mint: {}", pool.receipt_mint);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Migrate Stake Record Instruction
+// ============================================================================
+
+/// Rewrite a stake in the original pool from its first-deployment layout
+/// Permissionless, since it changes nothing the staker owns. Receipts did not exist when
+/// legacy stakes were made, so this also mints the staker's sLOKAL for the stake
+#[derive(Accounts)]
+pub struct MigrateStakeRecord<'info> {
+    /// Pays for the larger record and the receipt account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The staker who owns the record
+    /// CHECK: Only used to derive the record and receipt account addresses
+    pub user: UncheckedAccount<'info>,
+
+    /// The original pool, already migrated with `migrate_pool`
+    #[account(
+        seeds = [POOL_STATE_SEED],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The legacy stake record (PDA)
+    /// CHECK: Still in the legacy layout, so the handler decodes it by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_stake_record: UncheckedAccount<'info>,
+
+    /// Pool vault authority PDA (receipt mint authority)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [POOL_VAULT_AUTHORITY_SEED],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's receipt token account (created if it doesn't exist)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = user
+    )]
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// System program for the rent top-up and receipt account creation
+    pub system_program: Program<'info, System>,
+
+    /// Token program for minting receipts
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl MigrateStakeRecord<'_> {
+    pub fn handler(ctx: Context<MigrateStakeRecord>) -> Result<()> {
+        let pool_state = ctx.accounts.pool_state.load()?;
+        let record_info = ctx.accounts.user_stake_record.to_account_info();
+        let legacy = LegacyUserStakeRecord::from_account_data(&record_info.try_borrow_data()?)
+            .ok_or(CarsaError::NotALegacyAccount)?;
+
+        require!(
+            legacy.user == ctx.accounts.user.key() && legacy.pool == ctx.accounts.pool_state.key(),
+            CarsaError::InvalidOwner
+        );
+
+        let record = legacy
+            .migrate(pool_state.pool_delegate)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        grow_account(
+            &record_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            UserStakeRecord::LEN,
+        )?;
+        record.try_serialize(&mut &mut record_info.try_borrow_mut_data()?[..])?;
+
+        mint_receipts(
+            &pool_state,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.receipt_mint.to_account_info(),
+            ctx.accounts.user_receipt_ata.to_account_info(),
+            ctx.accounts.pool_vault_authority.to_account_info(),
+            ctx.bumps.pool_vault_authority,
+            record.staked_amount,
+        )?;
+
+        log_verbose!("Stake record migrated to the current layout");
+        log_verbose!("User: {}", record.user);
+        log_verbose!("Receipts minted: {}", record.staked_amount);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Migrate Config Instruction
+// ============================================================================
+
+/// Rewrite the mint configuration from its first-deployment layout into `LokalMintConfig`
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// The update authority recorded in the legacy config; pays for the larger account
+    #[account(mut)]
+    pub update_authority: Signer<'info>,
+
+    /// The legacy configuration account (PDA)
+    /// CHECK: Still in the legacy layout, so the handler decodes it by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// The Lokal token mint, whose decimals the legacy layout did not record
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateConfig<'_> {
+    pub fn handler(ctx: Context<MigrateConfig>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        let legacy = LegacyLokalMintConfig::from_account_data(&config_info.try_borrow_data()?)
+            .ok_or(CarsaError::NotALegacyAccount)?;
+
+        require!(
+            ctx.accounts.update_authority.key() == legacy.update_authority,
+            CarsaError::UpdateAuthorityMismatch
+        );
+        require!(
+            ctx.accounts.mint.key() == legacy.mint,
+            CarsaError::InvalidMint
+        );
+
+        let config = legacy.migrate(ctx.accounts.mint.decimals);
+
+        grow_account(
+            &config_info,
+            &ctx.accounts.update_authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            LokalMintConfig::LEN,
+        )?;
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        log_verbose!("Config migrated to the current layout");
+        log_verbose!("Mint: {}, Decimals: {}", config.mint, config.decimals);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Migrate Merchant Instruction
+// ============================================================================
+
+/// Rewrite a merchant from its first-deployment layout into `MerchantAccount`
+/// Permissionless, since it changes nothing the merchant controls. Legacy merchants
+/// predate the registry, so this also gives the merchant its registry position
+#[derive(Accounts)]
+pub struct MigrateMerchant<'info> {
+    /// Pays for the larger account and the merchant's index entry
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The wallet the merchant registered with
+    /// CHECK: Only used to derive the merchant account address
+    pub merchant_owner: UncheckedAccount<'info>,
+
+    /// The legacy merchant account (PDA)
+    /// CHECK: Still in the legacy layout, so the handler decodes it by hand
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [MERCHANT_SEED, merchant_owner.key().as_ref()],
+        bump
+    )]
+    pub merchant_account: UncheckedAccount<'info>,
+
+    /// Registry that numbers merchants
+    /// Created here if no merchant has registered since the registry was added
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = MerchantRegistry::LEN,
+        seeds = [MERCHANT_REGISTRY_SEED],
+        bump,
+    )]
+    pub merchant_registry: AccountLoader<'info, MerchantRegistry>,
+
+    /// Index entry pointing at the migrated merchant
+    #[account(
+        init,
+        payer = payer,
+        space = MerchantIndex::LEN,
+        seeds = [MERCHANT_INDEX_SEED, registered_merchant_count(&merchant_registry)?.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub merchant_index: Account<'info, MerchantIndex>,
+
+    /// System program for the rent top-up and index creation
+    pub system_program: Program<'info, System>,
+}
+
+impl MigrateMerchant<'_> {
+    pub fn handler(ctx: Context<MigrateMerchant>) -> Result<()> {
+        let merchant_info = ctx.accounts.merchant_account.to_account_info();
+        let legacy = LegacyMerchantAccount::from_account_data(&merchant_info.try_borrow_data()?)
+            .ok_or(CarsaError::NotALegacyAccount)?;
+
+        require!(
+            legacy.merchant_wallet == ctx.accounts.merchant_owner.key(),
+            CarsaError::InvalidOwner
+        );
+
+        let mut merchant_account = legacy.migrate();
+        take_registry_position(
+            merchant_info.key(),
+            &mut merchant_account,
+            &ctx.accounts.merchant_registry,
+            ctx.bumps.merchant_registry,
+            &mut ctx.accounts.merchant_index,
+            ctx.bumps.merchant_index,
+        )?;
+
+        grow_account(
+            &merchant_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            MerchantAccount::LEN,
+        )?;
+        merchant_account.try_serialize(&mut &mut merchant_info.try_borrow_mut_data()?[..])?;
+
+        log_verbose!("Merchant migrated to the current layout");
+        log_verbose!("Merchant: {}", merchant_info.key());
+        log_verbose!("Registry index: {}", merchant_account.registry_index);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Migrate Purchase Transaction Instruction
+// ============================================================================
+
+/// Rewrite a purchase record from its 164-byte layout into `PurchaseTransaction`
+/// Permissionless, since it only adds fields in their unset state
+#[derive(Accounts)]
+pub struct MigratePurchaseTransaction<'info> {
+    /// Pays for the larger account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The legacy purchase record, at either its merchant-scoped or customer-only address
+    /// CHECK: Still in the legacy layout, so the handler decodes it and checks its address
+    #[account(mut, owner = crate::ID)]
+    pub transaction_record: UncheckedAccount<'info>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+impl MigratePurchaseTransaction<'_> {
+    pub fn handler(ctx: Context<MigratePurchaseTransaction>) -> Result<()> {
+        let record_info = ctx.accounts.transaction_record.to_account_info();
+        let legacy = LegacyPurchaseTransaction::from_account_data(&record_info.try_borrow_data()?)
+            .ok_or(CarsaError::NotALegacyAccount)?;
+
+        let transaction_record = legacy.migrate();
+        require!(
+            transaction_record.is_at(&record_info.key()),
+            CarsaError::InvalidTransactionRecord
+        );
+
+        grow_account(
+            &record_info,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            PurchaseTransaction::LEN,
+        )?;
+        transaction_record.try_serialize(&mut &mut record_info.try_borrow_mut_data()?[..])?;
+
+        log_verbose!("Purchase record migrated to the current layout");
+        log_verbose!("Record: {}", record_info.key());
+
+        Ok(())
+    }
+}
@@ -1,7 +1,17 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use anchor_lang::solana_program::program::invoke;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::permanent_delegate::get_permanent_delegate;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint as SplMint;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::error::CarsaError;
+use crate::math::LOKAL_DECIMALS;
+use crate::oracle::validate_price_feed_config;
 
 /// Initialize the Lokal token mint and configuration
 /// This instruction creates the SPL token mint and sets up the program as the mint authority
@@ -13,15 +23,11 @@ pub struct InitializeLokalMint<'info> {
     pub update_authority: Signer<'info>,
     
     /// The mint account for Lokal tokens
-    /// This will be created and owned by the SPL Token program
-    #[account(
-        init,
-        payer = update_authority,
-        mint::decimals = 9, // Standard SPL token decimals
-        mint::authority = mint_authority, // PDA as mint authority
-        mint::freeze_authority = mint_authority, // PDA can freeze tokens if needed
-    )]
-    pub mint: Account<'info, Mint>,
+    /// Created by the handler and owned by the token program, with the mint authority PDA
+    /// as mint and freeze authority; under Token-2022 the PDA is also the permanent
+    /// delegate, which lets a clawback move a blocked balance
+    #[account(mut)]
+    pub mint: Signer<'info>,
     
     /// Program Derived Address that acts as the mint authority
     /// This ensures only the program can mint new tokens
@@ -43,8 +49,18 @@ pub struct InitializeLokalMint<'info> {
     )]
     pub config: Account<'info, LokalMintConfig>,
     
+    /// Registry that numbers merchants as they register
+    #[account(
+        init,
+        payer = update_authority,
+        space = MerchantRegistry::LEN,
+        seeds = [MERCHANT_REGISTRY_SEED],
+        bump,
+    )]
+    pub merchant_registry: AccountLoader<'info, MerchantRegistry>,
+    
     /// SPL Token program required for mint operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     
     /// System program required for account creation
     pub system_program: Program<'info, System>,
@@ -55,6 +71,7 @@ pub struct InitializeLokalMint<'info> {
 
 /// Mint Lokal tokens to a specified token account
 /// This is used for reward distribution when users make purchases
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(amount: u64)]
 pub struct MintLokalTokens<'info> {
@@ -68,9 +85,9 @@ pub struct MintLokalTokens<'info> {
     /// Must match the mint stored in config
     #[account(
         mut,
-        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
     )]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
     
     /// Program Derived Address that acts as the mint authority
     /// CHECK: This account is derived using seeds and verified in constraints
@@ -92,17 +109,339 @@ pub struct MintLokalTokens<'info> {
     /// This should be the user's associated token account for Lokal tokens
     #[account(
         mut,
-        constraint = destination.mint == mint.key()
+        constraint = destination.mint == mint.key() @ CarsaError::MintMismatch,
+        constraint = !destination.is_frozen() @ CarsaError::AccountFrozen
     )]
-    pub destination: Account<'info, TokenAccount>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
     
     /// SPL Token program for mint operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// Second signer, required above the single-signature limit when a co-signer is set
+    pub cosigner: Option<Signer<'info>>,
+}
+
+/// Compare the mint's real supply with the supply tracked in the config
+/// Anyone can check; only the update authority can repair the counters
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReconcileSupply<'info> {
+    /// Any wallet; must be the update authority to force a repair
+    pub caller: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// The mint account for Lokal tokens
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+/// Configure the cashback boost for customers who stake in the voucher pool
+#[derive(Accounts)]
+pub struct SetStakerBoost<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Configure the co-signer required on large administrative mints
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMintCosigner<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Set the largest transfer, mint and purchase the program accepts
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Set separate caps on the tokens minted by administrative paths and as purchase rewards
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetMintCaps<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Set how often and how far merchants may change their cashback rates
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRateChangePolicy<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Set the highest cashback rate merchants may use and the rate that raises an alert
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetCashbackLimits<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Value tokens from a Pyth price feed instead of the static rate
+#[derive(Accounts)]
+pub struct SetPriceFeed<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Change how long customers have to dispute a purchase
+#[derive(Accounts)]
+pub struct SetDisputeWindow<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Turn the wallet activity feed on or off
+#[derive(Accounts)]
+pub struct SetActivityRecording<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Choose between full and slim purchase records
+#[derive(Accounts)]
+pub struct SetSlimPurchaseRecords<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Switch privacy mode, which hashes the customer in purchase records, on or off
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetCustomerHashing<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Configure the protocol fee taken from token redemptions
+#[derive(Accounts)]
+pub struct SetProtocolFee<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// PDA that must own the treasury, so only `withdraw_treasury` can move fees out
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [TREASURY_AUTHORITY_SEED],
+        bump,
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+    
+    /// Lokal token account that will receive protocol fees
+    #[account(
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.owner == treasury_authority.key() @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Configure the fee taken from peer-to-peer transfers
+#[derive(Accounts)]
+pub struct SetTransferFee<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
 }
 
 impl<'info> InitializeLokalMint<'info> {
     /// Handler for initializing the Lokal token mint
     pub fn handler(ctx: Context<InitializeLokalMint>) -> Result<()> {
+        let mint = ctx.accounts.mint.to_account_info();
+        let mint_authority = ctx.accounts.mint_authority.key();
+        let token_program = ctx.accounts.token_program.to_account_info();
+
+        // A legacy SPL mint has no extensions, so only a Token-2022 mint can be clawed back
+        let extensions: &[ExtensionType] = if token_program.key() == spl_token_2022::ID {
+            &[ExtensionType::PermanentDelegate]
+        } else {
+            &[]
+        };
+        let space = ExtensionType::try_calculate_account_len::<SplMint>(extensions)?;
+        system_program::create_account(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: ctx.accounts.update_authority.to_account_info(),
+                    to: mint.clone(),
+                },
+            ),
+            ctx.accounts.rent.minimum_balance(space),
+            space as u64,
+            token_program.key,
+        )?;
+        if !extensions.is_empty() {
+            let instruction = spl_token_2022::instruction::initialize_permanent_delegate(
+                token_program.key,
+                mint.key,
+                &mint_authority,
+            )?;
+            invoke(&instruction, std::slice::from_ref(&mint))?;
+        }
+        token_interface::initialize_mint2(
+            CpiContext::new(token_program, token_interface::InitializeMint2 { mint: mint.clone() }),
+            LOKAL_DECIMALS,
+            &mint_authority,
+            Some(&mint_authority),
+        )?;
+
+        reject_unsafe_mint_extensions(&mint)?;
+
         let config = &mut ctx.accounts.config;
         
         // Store mint configuration
@@ -111,8 +450,51 @@ impl<'info> InitializeLokalMint<'info> {
         config.config_bump = ctx.bumps.config;
         config.update_authority = ctx.accounts.update_authority.key();
         config.total_supply = 0;
+        config.staker_boost_threshold = 0;
+        config.staker_boost_bps = 0;
+        config.record_activity = false;
+        config.total_purchases = 0;
+        config.total_transfers = 0;
+        config.protocol_fee_bps = 0;
+        config.treasury_token_account = Pubkey::default();
+        config.transfer_fee_bps = 0;
+        config.treasury_cosigner = Pubkey::default();
+        config.treasury_withdrawal_cap = 0;
+        config.treasury_withdrawal_count = 0;
+        config.token_to_idr_rate = LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE;
+        config.param_timelock_seconds = 0;
+        config.param_change_count = 0;
+        config.guardian = Pubkey::default();
+        config.paused = false;
+        config.mint_cosigner = Pubkey::default();
+        config.single_sig_mint_limit = 0;
+        config.price_feed = Pubkey::default();
+        config.max_price_age_seconds = 0;
+        config.max_price_confidence_bps = 0;
+        config.expiring_rewards = false;
+        config.reward_lifetime_seconds = 0;
+        config.vesting_grant_count = 0;
+        config.dispute_window_seconds = LokalMintConfig::DEFAULT_DISPUTE_WINDOW_SECONDS;
+        config.decimals = LOKAL_DECIMALS;
+        config.total_burned = 0;
+        config.fulfillment_authority = Pubkey::default();
+        config.fulfillment_window_seconds = 0;
+        config.max_transfer_amount = LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT;
+        config.max_mint_per_tx = LokalMintConfig::DEFAULT_MAX_MINT_PER_TX;
+        config.max_purchase_idr = LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR;
+        config.rate_change_cooldown_seconds = 0;
+        config.max_rate_increase_bps = 0;
+        config.slim_purchase_records = false;
+        config.hash_customer_in_records = false;
+        config.customer_hash_salt = [0; 32];
+        config.max_cashback_bps = LokalMintConfig::DEFAULT_MAX_CASHBACK_BPS;
+        config.alert_threshold_bps = 0;
+        
+        let merchant_registry = &mut *ctx.accounts.merchant_registry.load_init()?;
+        merchant_registry.merchant_count = 0;
+        merchant_registry.bump = ctx.bumps.merchant_registry;
         
-        msg!(
+        log_verbose!(
             "Lokal token mint initialized successfully. Mint: {}, Authority: {}",
             config.mint,
             ctx.accounts.mint_authority.key()
@@ -122,23 +504,105 @@ impl<'info> InitializeLokalMint<'info> {
     }
 }
 
+/// Reject a mint with an extension that lets tokens leave or shrink without the program:
+/// a transfer fee (vaults receive less than they credit), a transfer hook (arbitrary code
+/// on every transfer) or a permanent delegate other than the program's mint authority
+/// (can move any balance)
+/// Mints without extensions, including every legacy SPL mint, pass
+pub(crate) fn reject_unsafe_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<SplMint>::unpack(&data)?;
+
+    for extension in mint_state.get_extension_types()? {
+        require!(
+            !matches!(
+                extension,
+                ExtensionType::TransferFeeConfig | ExtensionType::TransferHook
+            ),
+            CarsaError::UnsupportedMintExtension
+        );
+    }
+    if let Some(delegate) = get_permanent_delegate(&mint_state) {
+        let (program_authority, _) =
+            Pubkey::find_program_address(&[MINT_AUTHORITY_SEED], &crate::ID);
+        require!(delegate == program_authority, CarsaError::UnsupportedMintExtension);
+    }
+    Ok(())
+}
+
+/// The Lokal mint's permanent delegate, which must be the mint authority PDA for the
+/// program to move tokens out of an account it does not own
+pub(crate) fn require_program_permanent_delegate(
+    mint: &AccountInfo,
+    mint_authority: &Pubkey,
+) -> Result<()> {
+    require!(*mint.owner == spl_token_2022::ID, CarsaError::ClawbackUnsupportedMint);
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    require!(
+        get_permanent_delegate(&mint_state) == Some(*mint_authority),
+        CarsaError::ClawbackUnsupportedMint
+    );
+    Ok(())
+}
+
+/// Require the configured co-signer on mints above the single-signature limit
+/// Returns the co-signer that signed, or the default pubkey when none was needed
+pub(crate) fn check_mint_cosigner(
+    config: &LokalMintConfig,
+    amount: u64,
+    cosigner: Option<&Signer>,
+) -> Result<Pubkey> {
+    if config.mint_cosigner == Pubkey::default() || amount <= config.single_sig_mint_limit {
+        return Ok(Pubkey::default());
+    }
+    
+    let cosigner = cosigner.ok_or(CarsaError::MintCosignerRequired)?;
+    require!(
+        cosigner.key() == config.mint_cosigner,
+        CarsaError::MintCosignerRequired
+    );
+    Ok(cosigner.key())
+}
+
+/// Count an administrative mint of `amount` against `admin_mint_cap` and add it to the supply
+pub(crate) fn record_admin_mint(config: &mut LokalMintConfig, amount: u64) -> Result<()> {
+    config.total_admin_minted = config
+        .admin_minted_after(amount)
+        .ok_or(CarsaError::AdminMintCapExceeded)?;
+    config.total_supply = config
+        .total_supply
+        .checked_add(amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Count a purchase reward mint of `amount` against `reward_mint_cap` and add it to the supply
+pub(crate) fn record_reward_mint(config: &mut LokalMintConfig, amount: u64) -> Result<()> {
+    config.total_reward_minted = config
+        .reward_minted_after(amount)
+        .ok_or(CarsaError::RewardMintCapExceeded)?;
+    config.total_supply = config
+        .total_supply
+        .checked_add(amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    Ok(())
+}
+
 impl<'info> MintLokalTokens<'info> {
     /// Handler for minting Lokal tokens
     pub fn handler(ctx: Context<MintLokalTokens>, amount: u64) -> Result<()> {
         // Validate mint amount
         require!(amount > 0, CarsaError::InvalidMintAmount);
         
-        // Set maximum mint amount per transaction (10,000 tokens with 9 decimals)
-        const MAX_MINT_AMOUNT: u64 = 10_000_000_000_000; // 10,000 * 10^9
-        require!(amount <= MAX_MINT_AMOUNT, CarsaError::MintAmountTooLarge);
-        
         let config = &mut ctx.accounts.config;
+        require!(config.allows_mint(amount), CarsaError::MintAmountTooLarge);
         
-        // Update total supply with overflow protection
-        config.total_supply = config
-            .total_supply
-            .checked_add(amount)
-            .ok_or(CarsaError::ArithmeticOverflow)?;
+        // Dual control: large mints need the configured co-signer too
+        let cosigner = check_mint_cosigner(config, amount, ctx.accounts.cosigner.as_ref())?;
+        
+        // Update total supply, within the administrative mint cap
+        record_admin_mint(config, amount)?;
         
         // Create signer seeds for CPI call
         let authority_seeds = &[
@@ -148,7 +612,7 @@ impl<'info> MintLokalTokens<'info> {
         let signer_seeds = &[&authority_seeds[..]];
         
         // Create CPI context for minting tokens
-        let cpi_accounts = token::MintTo {
+        let cpi_accounts = token_interface::MintTo {
             mint: ctx.accounts.mint.to_account_info(),
             to: ctx.accounts.destination.to_account_info(),
             authority: ctx.accounts.mint_authority.to_account_info(),
@@ -158,15 +622,518 @@ impl<'info> MintLokalTokens<'info> {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
         
         // Execute the mint operation
-        token::mint_to(cpi_ctx, amount)?;
+        token_interface::mint_to(cpi_ctx, amount)?;
         
-        msg!(
+        log_verbose!(
             "Minted {} Lokal tokens to {}. New total supply: {}",
             amount,
             ctx.accounts.destination.key(),
             config.total_supply
         );
         
+        emit_cpi!(LokalTokensMintedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            destination: ctx.accounts.destination.key(),
+            amount,
+            total_supply: config.total_supply,
+            cosigner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> ReconcileSupply<'info> {
+    /// Handler for reconciling the tracked supply with the mint
+    pub fn handler(ctx: Context<ReconcileSupply>, force: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let mint_supply = ctx.accounts.mint.supply;
+        let tracked_supply = config
+            .total_supply
+            .checked_sub(config.total_burned)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let delta = i64::try_from(mint_supply as i128 - tracked_supply as i128)
+            .map_err(|_| CarsaError::ArithmeticOverflow)?;
+        
+        if force {
+            require_keys_eq!(
+                ctx.accounts.caller.key(),
+                config.update_authority,
+                CarsaError::UpdateAuthorityMismatch
+            );
+            
+            // Untracked mints count towards the supply, untracked burns towards the burned total
+            let adjustment = delta.unsigned_abs();
+            if delta > 0 {
+                config.total_supply = config
+                    .total_supply
+                    .checked_add(adjustment)
+                    .ok_or(CarsaError::ArithmeticOverflow)?;
+            } else {
+                config.total_burned = config
+                    .total_burned
+                    .checked_add(adjustment)
+                    .ok_or(CarsaError::ArithmeticOverflow)?;
+            }
+        }
+        
+        log_verbose!(
+            "Mint supply {}, tracked supply {}, delta {}{}",
+            mint_supply,
+            tracked_supply,
+            delta,
+            if force { " (repaired)" } else { "" }
+        );
+        
+        emit_cpi!(SupplyReconciliationEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            mint_supply,
+            tracked_supply,
+            delta,
+            repaired: force && delta != 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetStakerBoost<'info> {
+    /// Handler for updating the staker cashback boost
+    pub fn handler(
+        ctx: Context<SetStakerBoost>,
+        staker_boost_threshold: u64,
+        staker_boost_bps: u16,
+    ) -> Result<()> {
+        require!(staker_boost_bps <= 10_000, CarsaError::InvalidStakerBoost);
+        
+        let config = &mut ctx.accounts.config;
+        config.staker_boost_threshold = staker_boost_threshold;
+        config.staker_boost_bps = staker_boost_bps;
+        
+        log_verbose!(
+            "Staker cashback boost set to {}bps for stakes of at least {}",
+            staker_boost_bps,
+            staker_boost_threshold
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetActivityRecording<'info> {
+    /// Handler for enabling or disabling activity records
+    pub fn handler(ctx: Context<SetActivityRecording>, enabled: bool) -> Result<()> {
+        ctx.accounts.config.record_activity = enabled;
+        
+        log_verbose!("Activity recording enabled: {}", enabled);
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetSlimPurchaseRecords<'info> {
+    /// Handler for choosing the purchase record layout
+    pub fn handler(ctx: Context<SetSlimPurchaseRecords>, enabled: bool) -> Result<()> {
+        ctx.accounts.config.slim_purchase_records = enabled;
+        
+        log_verbose!("Slim purchase records enabled: {}", enabled);
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetCustomerHashing<'info> {
+    /// Handler for switching privacy mode
+    /// The first non-zero salt sticks; later calls pass it again or all zeros
+    pub fn handler(
+        ctx: Context<SetCustomerHashing>,
+        enabled: bool,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // A new salt would give every customer a new hash and split their history
+        if salt != [0; 32] {
+            require!(
+                config.customer_hash_salt == [0; 32] || config.customer_hash_salt == salt,
+                CarsaError::CustomerHashSaltLocked
+            );
+            config.customer_hash_salt = salt;
+        }
+        require!(
+            !enabled || config.customer_hash_salt != [0; 32],
+            CarsaError::InvalidCustomerHashSalt
+        );
+        let old_enabled = config.hash_customer_in_records;
+        config.hash_customer_in_records = enabled;
+        
+        log_verbose!("Customer hashing in records enabled: {}", enabled);
+        
+        emit_cpi!(CustomerHashingUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            hash_customer_in_records: Changed { old: old_enabled, new: enabled },
+            customer_hash_salt: config.customer_hash_salt,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetMintCosigner<'info> {
+    /// Handler for updating the mint co-signer and single-signature limit
+    pub fn handler(
+        ctx: Context<SetMintCosigner>,
+        mint_cosigner: Pubkey,
+        single_sig_mint_limit: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        let old_mint_cosigner = config.mint_cosigner;
+        let old_single_sig_mint_limit = config.single_sig_mint_limit;
+        config.mint_cosigner = mint_cosigner;
+        config.single_sig_mint_limit = single_sig_mint_limit;
+        
+        log_verbose!(
+            "Mint co-signer set: {}, single-signature limit {}",
+            mint_cosigner,
+            single_sig_mint_limit
+        );
+        
+        emit_cpi!(MintCosignerUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            mint_cosigner: Changed { old: old_mint_cosigner, new: mint_cosigner },
+            single_sig_mint_limit: Changed {
+                old: old_single_sig_mint_limit,
+                new: single_sig_mint_limit,
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetLimits<'info> {
+    /// Handler for updating the transfer, mint and purchase limits
+    pub fn handler(
+        ctx: Context<SetLimits>,
+        max_transfer_amount: u64,
+        max_mint_per_tx: u64,
+        max_purchase_idr: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        let old_max_transfer_amount = config.max_transfer_amount;
+        let old_max_mint_per_tx = config.max_mint_per_tx;
+        let old_max_purchase_idr = config.max_purchase_idr;
+        config.max_transfer_amount = max_transfer_amount;
+        config.max_mint_per_tx = max_mint_per_tx;
+        config.max_purchase_idr = max_purchase_idr;
+        
+        log_verbose!(
+            "Limits set: transfer {}, mint {}, purchase Rp {}",
+            max_transfer_amount,
+            max_mint_per_tx,
+            max_purchase_idr
+        );
+        
+        emit_cpi!(LimitsUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            max_transfer_amount: Changed { old: old_max_transfer_amount, new: max_transfer_amount },
+            max_mint_per_tx: Changed { old: old_max_mint_per_tx, new: max_mint_per_tx },
+            max_purchase_idr: Changed { old: old_max_purchase_idr, new: max_purchase_idr },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetMintCaps<'info> {
+    /// Handler for updating the administrative and purchase reward mint caps
+    pub fn handler(
+        ctx: Context<SetMintCaps>,
+        admin_mint_cap: u64,
+        reward_mint_cap: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        let old_admin_mint_cap = config.admin_mint_cap;
+        let old_reward_mint_cap = config.reward_mint_cap;
+        config.admin_mint_cap = admin_mint_cap;
+        config.reward_mint_cap = reward_mint_cap;
+        
+        log_verbose!(
+            "Mint caps set: admin {}, reward {}",
+            admin_mint_cap,
+            reward_mint_cap
+        );
+        
+        emit_cpi!(MintCapsUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            admin_mint_cap: Changed { old: old_admin_mint_cap, new: admin_mint_cap },
+            reward_mint_cap: Changed { old: old_reward_mint_cap, new: reward_mint_cap },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
         Ok(())
     }
 }
+
+impl<'info> SetRateChangePolicy<'info> {
+    /// Handler for updating the cashback rate cooldown and increase cap
+    pub fn handler(
+        ctx: Context<SetRateChangePolicy>,
+        rate_change_cooldown_seconds: u32,
+        max_rate_increase_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        require!(max_rate_increase_bps <= 10_000, CarsaError::InvalidCashbackRate);
+        let old_rate_change_cooldown_seconds = config.rate_change_cooldown_seconds;
+        let old_max_rate_increase_bps = config.max_rate_increase_bps;
+        config.rate_change_cooldown_seconds = rate_change_cooldown_seconds;
+        config.max_rate_increase_bps = max_rate_increase_bps;
+        
+        log_verbose!(
+            "Rate change policy set: {}s cooldown, +{}bps cap",
+            rate_change_cooldown_seconds,
+            max_rate_increase_bps
+        );
+        
+        emit_cpi!(RateChangePolicyUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            rate_change_cooldown_seconds: Changed {
+                old: old_rate_change_cooldown_seconds,
+                new: rate_change_cooldown_seconds,
+            },
+            max_rate_increase_bps: Changed {
+                old: old_max_rate_increase_bps,
+                new: max_rate_increase_bps,
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetCashbackLimits<'info> {
+    /// Handler for updating the cashback rate cap and alert threshold
+    /// Lowering the cap also stops rewards at merchants already above it
+    pub fn handler(
+        ctx: Context<SetCashbackLimits>,
+        max_cashback_bps: u16,
+        alert_threshold_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        validate_cashback_limits(max_cashback_bps, alert_threshold_bps)?;
+        let old_max_cashback_bps = config.max_cashback_bps();
+        let old_alert_threshold_bps = config.alert_threshold_bps;
+        config.max_cashback_bps = max_cashback_bps;
+        config.alert_threshold_bps = alert_threshold_bps;
+        
+        log_verbose!(
+            "Cashback limits set: {}bps max, alert above {}bps",
+            max_cashback_bps,
+            alert_threshold_bps
+        );
+        
+        emit_cpi!(CashbackLimitsUpdatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            max_cashback_bps: Changed { old: old_max_cashback_bps, new: max_cashback_bps },
+            alert_threshold_bps: Changed {
+                old: old_alert_threshold_bps,
+                new: alert_threshold_bps,
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+}
+
+/// Check a cashback rate cap and alert threshold before they are set or queued
+pub(crate) fn validate_cashback_limits(max_cashback_bps: u16, alert_threshold_bps: u16) -> Result<()> {
+    require!(
+        max_cashback_bps > 0 && max_cashback_bps <= 10_000 && alert_threshold_bps <= 10_000,
+        CarsaError::InvalidCashbackRate
+    );
+    Ok(())
+}
+
+impl<'info> SetPriceFeed<'info> {
+    /// Handler for updating the price feed and its staleness and confidence bounds
+    pub fn handler(
+        ctx: Context<SetPriceFeed>,
+        price_feed: Pubkey,
+        max_price_age_seconds: u32,
+        max_price_confidence_bps: u16,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock these change through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        validate_price_feed_config(&price_feed, max_price_age_seconds, max_price_confidence_bps)?;
+        config.price_feed = price_feed;
+        config.max_price_age_seconds = max_price_age_seconds;
+        config.max_price_confidence_bps = max_price_confidence_bps;
+        
+        log_verbose!(
+            "Price feed set: {}, max age {}s, max confidence {}bps",
+            price_feed,
+            max_price_age_seconds,
+            max_price_confidence_bps
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetDisputeWindow<'info> {
+    /// Handler for updating the dispute window
+    pub fn handler(ctx: Context<SetDisputeWindow>, dispute_window_seconds: u32) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock this changes through `queue_param_change`
+        require!(config.param_timelock_seconds == 0, CarsaError::TimelockActive);
+        config.dispute_window_seconds = dispute_window_seconds;
+        
+        log_verbose!("Dispute window set to {}s", dispute_window_seconds);
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetProtocolFee<'info> {
+    /// Handler for updating the protocol fee and treasury
+    pub fn handler(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        require!(protocol_fee_bps <= 10_000, CarsaError::InvalidProtocolFee);
+        
+        let config = &mut ctx.accounts.config;
+        
+        // Under a timelock the fee changes through `queue_param_change`; only the
+        // treasury can still be pointed elsewhere here
+        require!(
+            config.param_timelock_seconds == 0 || protocol_fee_bps == config.protocol_fee_bps,
+            CarsaError::TimelockActive
+        );
+        config.protocol_fee_bps = protocol_fee_bps;
+        config.treasury_token_account = ctx.accounts.treasury_token_account.key();
+        
+        log_verbose!(
+            "Protocol fee set to {}bps, treasury: {}",
+            protocol_fee_bps,
+            config.treasury_token_account
+        );
+        
+        Ok(())
+    }
+}
+
+impl<'info> SetTransferFee<'info> {
+    /// Handler for updating the transfer fee
+    pub fn handler(ctx: Context<SetTransferFee>, transfer_fee_bps: u16) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        
+        // Fees go to the treasury configured by `set_protocol_fee`
+        require!(
+            transfer_fee_bps <= 10_000
+                && (transfer_fee_bps == 0 || config.treasury_token_account != Pubkey::default()),
+            CarsaError::InvalidTransferFee
+        );
+        config.transfer_fee_bps = transfer_fee_bps;
+        
+        log_verbose!("Transfer fee set to {}bps", transfer_fee_bps);
+        
+        Ok(())
+    }
+}
+
+#[event]
+pub struct LokalTokensMintedEvent {
+    pub sequence: u64,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub total_supply: u64,
+    /// Default pubkey when no co-signer was required
+    pub cosigner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SupplyReconciliationEvent {
+    pub sequence: u64,
+    /// Supply reported by the mint
+    pub mint_supply: u64,
+    /// `total_supply - total_burned` before any repair
+    pub tracked_supply: u64,
+    /// `mint_supply - tracked_supply`
+    pub delta: i64,
+    /// Whether the config counters were snapped to the mint
+    pub repaired: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintCosignerUpdatedEvent {
+    pub sequence: u64,
+    pub mint_cosigner: Changed<Pubkey>,
+    pub single_sig_mint_limit: Changed<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LimitsUpdatedEvent {
+    pub sequence: u64,
+    pub max_transfer_amount: Changed<u64>,
+    pub max_mint_per_tx: Changed<u64>,
+    pub max_purchase_idr: Changed<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintCapsUpdatedEvent {
+    pub sequence: u64,
+    pub admin_mint_cap: Changed<u64>,
+    pub reward_mint_cap: Changed<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CustomerHashingUpdatedEvent {
+    pub sequence: u64,
+    pub hash_customer_in_records: Changed<bool>,
+    pub customer_hash_salt: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RateChangePolicyUpdatedEvent {
+    pub sequence: u64,
+    pub rate_change_cooldown_seconds: Changed<u32>,
+    pub max_rate_increase_bps: Changed<u16>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CashbackLimitsUpdatedEvent {
+    pub sequence: u64,
+    pub max_cashback_bps: Changed<u16>,
+    pub alert_threshold_bps: Changed<u16>,
+    pub timestamp: i64,
+}
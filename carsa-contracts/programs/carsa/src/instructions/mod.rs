@@ -1,9 +1,68 @@
+pub mod advances;
+pub mod airdrops;
+pub mod budgets;
+pub mod category_boosts;
+pub mod clawback;
+pub mod disputes;
+pub mod franchises;
+pub mod fulfillment;
+pub mod gift_cards;
+pub mod guardian;
+pub mod holds;
+pub mod migrations;
 pub mod mint_tokens;
+pub mod onboarding;
+pub mod operators;
+pub mod ownership;
+pub mod pos_keys;
+pub mod reward_lots;
 pub mod rewards;
+pub mod sessions;
+pub mod settlements;
+#[cfg(feature = "stake-pool")]
+pub mod stake_pool_deployment;
+pub mod stats;
+pub mod streaks;
+pub mod subscriptions;
+pub mod timelock;
 pub mod transfers;
+pub mod treasury;
+pub mod vesting;
 pub mod voucher_pool;
 
+pub use advances::*;
+pub use airdrops::*;
+pub use budgets::*;
+pub use category_boosts::*;
+pub use clawback::*;
+pub use disputes::*;
+pub use franchises::*;
+pub use fulfillment::*;
+pub use gift_cards::*;
+pub use guardian::*;
+pub use holds::*;
+pub use migrations::*;
 pub use mint_tokens::*;
+pub use onboarding::*;
+pub use operators::*;
+pub use ownership::*;
+pub use pos_keys::*;
+pub use reward_lots::*;
 pub use rewards::*;
+pub use sessions::*;
+pub use settlements::*;
+#[cfg(feature = "stake-pool")]
+pub use stake_pool_deployment::*;
+pub use stats::*;
+pub use streaks::*;
+pub use subscriptions::*;
+pub use timelock::*;
 pub use transfers::*;
+pub use treasury::*;
+pub use vesting::*;
 pub use voucher_pool::*;
+
+/// Compute units any instruction may use: the runtime's default budget per instruction,
+/// so no caller needs a compute budget instruction. Handlers with a tighter
+/// `COMPUTE_UNIT_CEILING` of their own must also stay under that
+pub const DEFAULT_COMPUTE_UNIT_CEILING: u64 = 200_000;
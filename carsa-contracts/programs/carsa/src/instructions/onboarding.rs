@@ -0,0 +1,195 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+use super::rewards::{
+    registered_merchant_count, take_registry_position, validate_merchant_details,
+    HighCashbackAlertEvent,
+};
+
+/// Register a merchant on its owner's behalf, paid by the update authority
+/// The merchant takes no purchases until its owner accepts the registration
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(merchant_wallet: Pubkey)]
+pub struct RegisterMerchantByAuthority<'info> {
+    /// The update authority of the mint configuration, paying for the new accounts
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    /// Writable so the registration can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The merchant account to be created, at the owner's usual address
+    #[account(
+        init,
+        payer = authority,
+        space = MerchantAccount::LEN,
+        seeds = [MERCHANT_SEED, merchant_wallet.as_ref()],
+        bump,
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Registry that numbers merchants as they register
+    /// Created here if the mint was initialized before the registry existed
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = MerchantRegistry::LEN,
+        seeds = [MERCHANT_REGISTRY_SEED],
+        bump,
+    )]
+    pub merchant_registry: AccountLoader<'info, MerchantRegistry>,
+
+    /// Index entry pointing at the new merchant
+    #[account(
+        init,
+        payer = authority,
+        space = MerchantIndex::LEN,
+        seeds = [MERCHANT_INDEX_SEED, registered_merchant_count(&merchant_registry)?.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub merchant_index: Account<'info, MerchantIndex>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Accept a registration the update authority made for this wallet's merchant
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptMerchantRegistration<'info> {
+    /// The merchant's owner wallet
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant account to activate
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    /// Writable so the acceptance can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+impl<'info> RegisterMerchantByAuthority<'info> {
+    /// Handler for registering a merchant on its owner's behalf
+    /// The merchant starts with no minimum purchase, which its owner can set with
+    /// `update_merchant` once it has accepted
+    pub fn handler(
+        ctx: Context<RegisterMerchantByAuthority>,
+        merchant_wallet: Pubkey,
+        name: String,
+        category: String,
+        cashback_rate: u16,
+    ) -> Result<()> {
+        validate_merchant_details(&ctx.accounts.config, &name, &category, cashback_rate)?;
+
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let clock = Clock::get()?;
+
+        merchant_account.merchant_wallet = merchant_wallet;
+        merchant_account.name = MerchantAccount::name_bytes(&name);
+        merchant_account.category = MerchantAccount::category_bytes(&category);
+        merchant_account.cashback_rate = cashback_rate;
+        merchant_account.is_active = true;
+        merchant_account.pending_activation = true;
+        merchant_account.created_at = clock.unix_timestamp;
+        merchant_account.bump = ctx.bumps.merchant_account;
+
+        take_registry_position(
+            merchant_account.key(),
+            merchant_account,
+            &ctx.accounts.merchant_registry,
+            ctx.bumps.merchant_registry,
+            &mut ctx.accounts.merchant_index,
+            ctx.bumps.merchant_index,
+        )?;
+
+        log_verbose!(
+            "Merchant pre-registered for {}: {} ({}), Cashback: {}bps",
+            merchant_wallet,
+            name,
+            category,
+            cashback_rate
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(MerchantRegisteredByAuthorityEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            merchant_wallet,
+            authority: ctx.accounts.authority.key(),
+            cashback_rate,
+            timestamp: clock.unix_timestamp,
+        });
+        if config.is_high_cashback_rate(cashback_rate) {
+            emit_cpi!(HighCashbackAlertEvent {
+                sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                merchant: merchant_account.key(),
+                cashback_rate,
+                alert_threshold_bps: config.alert_threshold_bps,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> AcceptMerchantRegistration<'info> {
+    /// Handler for accepting a registration made by the update authority
+    pub fn handler(ctx: Context<AcceptMerchantRegistration>) -> Result<()> {
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        require!(
+            merchant_account.pending_activation,
+            CarsaError::MerchantRegistrationAlreadyAccepted
+        );
+        merchant_account.pending_activation = false;
+
+        log_verbose!("Merchant {} accepted its registration", merchant_account.key());
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(MerchantRegistrationAcceptedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            merchant_wallet: merchant_account.merchant_wallet,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MerchantRegisteredByAuthorityEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub merchant_wallet: Pubkey,
+    pub authority: Pubkey,
+    pub cashback_rate: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantRegistrationAcceptedEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub merchant_wallet: Pubkey,
+    pub timestamp: i64,
+}
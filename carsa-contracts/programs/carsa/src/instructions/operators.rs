@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Add a wallet that may act for the merchant at the till
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct AddMerchantOperator<'info> {
+    /// The merchant's owner wallet, paying for the operator account
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant the operator acts for
+    #[account(
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The operator to be added
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = MerchantOperator::LEN,
+        seeds = [MERCHANT_OPERATOR_SEED, merchant_account.key().as_ref(), operator.as_ref()],
+        bump,
+    )]
+    pub merchant_operator: Account<'info, MerchantOperator>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Remove a merchant operator, e.g. when a cashier leaves
+#[derive(Accounts)]
+pub struct RemoveMerchantOperator<'info> {
+    /// The merchant's owner wallet
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant the operator acted for
+    #[account(
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The operator to be removed, closed to the merchant owner
+    #[account(
+        mut,
+        close = merchant_owner,
+        seeds = [MERCHANT_OPERATOR_SEED, merchant_account.key().as_ref(), merchant_operator.operator.as_ref()],
+        bump = merchant_operator.bump,
+    )]
+    pub merchant_operator: Account<'info, MerchantOperator>,
+}
+
+impl<'info> AddMerchantOperator<'info> {
+    /// Handler for adding a merchant operator
+    pub fn handler(ctx: Context<AddMerchantOperator>, operator: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let merchant = ctx.accounts.merchant_account.key();
+        ctx.accounts.merchant_operator.set_inner(MerchantOperator {
+            merchant,
+            operator,
+            added_at: clock.unix_timestamp,
+            bump: ctx.bumps.merchant_operator,
+        });
+
+        log_verbose!("Operator {} added to merchant {}", operator, merchant);
+
+        Ok(())
+    }
+}
+
+impl<'info> RemoveMerchantOperator<'info> {
+    /// Handler for removing a merchant operator
+    pub fn handler(ctx: Context<RemoveMerchantOperator>) -> Result<()> {
+        log_verbose!(
+            "Operator {} removed from merchant {}",
+            ctx.accounts.merchant_operator.operator,
+            ctx.accounts.merchant_account.key()
+        );
+
+        Ok(())
+    }
+}
+
+/// Whether `signer` may act for `merchant_account` at the till: its owner wallet, or the
+/// operator behind `merchant_operator` when that account belongs to the merchant
+/// Only for actions that move no funds to new destinations; settings and payouts stay
+/// with the owner
+pub(crate) fn is_authorized_for_merchant(
+    merchant_account: &Account<MerchantAccount>,
+    signer: &Pubkey,
+    merchant_operator: Option<&Account<MerchantOperator>>,
+) -> bool {
+    *signer == merchant_account.merchant_wallet
+        || merchant_operator.is_some_and(|merchant_operator| {
+            merchant_operator.merchant == merchant_account.key()
+                && merchant_operator.operator == *signer
+        })
+}
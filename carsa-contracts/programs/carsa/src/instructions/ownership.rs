@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Propose a new owner for a merchant, who takes over once they accept
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposeMerchantOwner<'info> {
+    /// The merchant's current owner wallet
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant account to hand over
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    /// Writable so the proposal can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Take over a merchant as its proposed owner
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptMerchantOwnership<'info> {
+    /// The proposed owner's wallet
+    pub new_owner: Signer<'info>,
+
+    /// The merchant account to take over, which keeps its address
+    #[account(
+        mut,
+        constraint = merchant_account.pending_owner == new_owner.key() @ CarsaError::NotPendingMerchantOwner
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    /// Writable so the transfer can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+impl<'info> ProposeMerchantOwner<'info> {
+    /// Handler for proposing a new merchant owner
+    /// Proposing the default pubkey cancels a pending transfer
+    pub fn handler(ctx: Context<ProposeMerchantOwner>, new_owner: Pubkey) -> Result<()> {
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        require!(
+            new_owner != merchant_account.merchant_wallet,
+            CarsaError::InvalidPendingMerchantOwner
+        );
+        let old_pending_owner = merchant_account.pending_owner;
+        merchant_account.pending_owner = new_owner;
+
+        log_verbose!(
+            "Merchant {} proposed owner: {}",
+            merchant_account.key(),
+            new_owner
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(MerchantOwnerProposedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            merchant_wallet: merchant_account.merchant_wallet,
+            pending_owner: Changed { old: old_pending_owner, new: new_owner },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> AcceptMerchantOwnership<'info> {
+    /// Handler for taking over a merchant
+    /// The payout account is cleared, so redemptions lock in the new owner's first token
+    /// account unless they set one with `set_payout_account`
+    pub fn handler(ctx: Context<AcceptMerchantOwnership>) -> Result<()> {
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let old_merchant_wallet = merchant_account.merchant_wallet;
+        let old_payout_token_account = merchant_account.payout_token_account;
+        merchant_account.merchant_wallet = ctx.accounts.new_owner.key();
+        merchant_account.pending_owner = Pubkey::default();
+        merchant_account.payout_token_account = Pubkey::default();
+
+        log_verbose!(
+            "Merchant {} transferred from {} to {}",
+            merchant_account.key(),
+            old_merchant_wallet,
+            merchant_account.merchant_wallet
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(MerchantOwnershipTransferredEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            merchant_wallet: Changed {
+                old: old_merchant_wallet,
+                new: merchant_account.merchant_wallet,
+            },
+            payout_token_account: Changed {
+                old: old_payout_token_account,
+                new: Pubkey::default(),
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MerchantOwnerProposedEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub merchant_wallet: Pubkey,
+    pub pending_owner: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantOwnershipTransferredEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub merchant_wallet: Changed<Pubkey>,
+    pub payout_token_account: Changed<Pubkey>,
+    pub timestamp: i64,
+}
@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
+use crate::state::*;
+use crate::error::CarsaError;
+use super::operators::is_authorized_for_merchant;
+
+/// Register a point-of-sale key that may approve the merchant's purchases
+#[derive(Accounts)]
+#[instruction(pos_key: Pubkey)]
+pub struct RegisterPosKey<'info> {
+    /// The merchant's owner wallet, paying for the POS key account
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant the key approves purchases for
+    #[account(
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The POS key to be registered
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = PosKey::LEN,
+        seeds = [POS_KEY_SEED, merchant_account.key().as_ref(), pos_key.as_ref()],
+        bump,
+    )]
+    pub pos_key_account: Account<'info, PosKey>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Remove a point-of-sale key, e.g. when a terminal is lost
+#[derive(Accounts)]
+pub struct RemovePosKey<'info> {
+    /// The merchant's owner wallet
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant the key approved purchases for
+    #[account(
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The POS key to be removed, closed to the merchant owner
+    #[account(
+        mut,
+        close = merchant_owner,
+        seeds = [POS_KEY_SEED, merchant_account.key().as_ref(), pos_key_account.pos_key.as_ref()],
+        bump = pos_key_account.bump,
+    )]
+    pub pos_key_account: Account<'info, PosKey>,
+}
+
+impl<'info> RegisterPosKey<'info> {
+    /// Handler for registering a POS key
+    pub fn handler(ctx: Context<RegisterPosKey>, pos_key: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let merchant = ctx.accounts.merchant_account.key();
+        ctx.accounts.pos_key_account.set_inner(PosKey {
+            merchant,
+            pos_key,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.pos_key_account,
+        });
+
+        log_verbose!("POS key {} registered for merchant {}", pos_key, merchant);
+
+        Ok(())
+    }
+}
+
+impl<'info> RemovePosKey<'info> {
+    /// Handler for removing a POS key
+    pub fn handler(ctx: Context<RemovePosKey>) -> Result<()> {
+        log_verbose!(
+            "POS key {} removed from merchant {}",
+            ctx.accounts.pos_key_account.pos_key,
+            ctx.accounts.merchant_account.key()
+        );
+
+        Ok(())
+    }
+}
+
+/// Require an Ed25519 program instruction in the transaction whose signer is the merchant
+/// wallet, `pos_key` or `merchant_operator`, over the purchase's approval message
+/// The runtime rejects the whole transaction if that signature is invalid, so finding
+/// the instruction is enough. The transaction id in the message makes each approval
+/// single-use, as the purchase record PDA cannot be created twice
+pub(crate) fn check_merchant_signature(
+    merchant_account: &Account<MerchantAccount>,
+    pos_key: Option<&Account<PosKey>>,
+    merchant_operator: Option<&Account<MerchantOperator>>,
+    instructions_sysvar: Option<&UncheckedAccount>,
+    customer: &Pubkey,
+    fiat_amount: u64,
+    transaction_id: &[u8; 32],
+) -> Result<()> {
+    let instructions_sysvar = instructions_sysvar.ok_or(CarsaError::MerchantSignatureMissing)?;
+    let message = purchase_approval_message(
+        &merchant_account.key(),
+        customer,
+        fiat_amount,
+        transaction_id,
+    );
+    let is_approver = |signer: &Pubkey| {
+        is_authorized_for_merchant(merchant_account, signer, merchant_operator)
+            || pos_key.is_some_and(|pos_key| pos_key.pos_key == *signer)
+    };
+
+    let mut index = 0;
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions_sysvar) {
+        if instruction.program_id == ed25519_program::ID
+            && signs_message(&instruction.data, is_approver, &message)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+
+    err!(CarsaError::MerchantSignatureMissing)
+}
+
+/// Whether Ed25519 program instruction data holds a signature by a key `is_signer` accepts
+/// over exactly `message`
+/// The data is a signature count and padding byte, followed by one 14-byte offsets entry
+/// per signature: signature offset and instruction index, public key offset and
+/// instruction index, message offset, size and instruction index (each a u16 LE)
+fn signs_message(data: &[u8], is_signer: impl Fn(&Pubkey) -> bool, message: &[u8]) -> bool {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return false,
+    };
+
+    (0..count).any(|signature| {
+        let start = 2 + signature * 14;
+        let Some(offsets) = data.get(start..start + 14) else {
+            return false;
+        };
+        let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]) as usize;
+
+        // Only accept parts stored in this instruction, not borrowed from another one
+        let here = u16::MAX as usize;
+        if read(2) != here || read(6) != here || read(12) != here {
+            return false;
+        }
+
+        let public_key = data.get(read(4)..read(4) + 32);
+        let signed_message = data.get(read(8)..read(8) + read(10));
+        match (public_key, signed_message) {
+            (Some(public_key), Some(signed_message)) => {
+                signed_message == message
+                    && Pubkey::try_from(public_key).is_ok_and(|signer| is_signer(&signer))
+            }
+            _ => false,
+        }
+    })
+}
@@ -0,0 +1,308 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Turn expiring rewards on or off and create the escrow that holds them
+#[derive(Accounts)]
+pub struct SetRewardExpiry<'info> {
+    /// The update authority of the mint configuration, paying for the escrow
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Token account holding rewards until they are withdrawn or expire
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// SPL Token program for creating the escrow
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Burn the remainder of an expired reward lot
+/// Permissionless, so anyone can crank expiry
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireRewards<'info> {
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding rewards until they are withdrawn or expire
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The expired reward lot
+    #[account(
+        mut,
+        seeds = [REWARD_LOT_SEED, reward_lot.customer.as_ref(), &reward_lot.transaction_id],
+        bump = reward_lot.bump,
+    )]
+    pub reward_lot: Account<'info, RewardLot>,
+
+    /// SPL Token program for burning the expired rewards
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Move an unexpired reward lot into the customer's wallet, or close a lot already
+/// burned by `expire_rewards`, returning its rent to the customer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawVestedRewards<'info> {
+    /// The customer that earned the reward
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding rewards until they are withdrawn or expire
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The customer's token account receiving the reward
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The reward lot to withdraw
+    #[account(
+        mut,
+        close = customer,
+        seeds = [REWARD_LOT_SEED, reward_lot.customer.as_ref(), &reward_lot.transaction_id],
+        bump = reward_lot.bump,
+        constraint = config.is_record_customer(&reward_lot.customer, &customer.key()) @ CarsaError::InvalidOwner,
+    )]
+    pub reward_lot: Account<'info, RewardLot>,
+
+    /// SPL Token program for the transfer
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SetRewardExpiry<'info> {
+    /// Handler for turning expiring rewards on or off
+    pub fn handler(
+        ctx: Context<SetRewardExpiry>,
+        enabled: bool,
+        reward_lifetime_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            !enabled || reward_lifetime_seconds > 0,
+            CarsaError::InvalidRewardLifetime
+        );
+
+        // Lots already in escrow keep their expiry; this only affects new purchases
+        let config = &mut ctx.accounts.config;
+        config.expiring_rewards = enabled;
+        config.reward_lifetime_seconds = reward_lifetime_seconds;
+
+        log_verbose!(
+            "Expiring rewards set to {}, lifetime {}s",
+            enabled,
+            reward_lifetime_seconds
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> ExpireRewards<'info> {
+    /// Handler for burning an expired reward lot
+    pub fn handler(ctx: Context<ExpireRewards>) -> Result<()> {
+        let clock = Clock::get()?;
+        let reward_lot = &mut ctx.accounts.reward_lot;
+        require!(reward_lot.is_expired(clock.unix_timestamp), CarsaError::RewardLotNotExpired);
+
+        // Cranking a lot twice is a no-op
+        let amount = reward_lot.amount;
+        if amount == 0 {
+            log_verbose!("Reward lot {} already expired", reward_lot.key());
+            return Ok(());
+        }
+
+        let config = &mut ctx.accounts.config;
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let cpi_accounts = token_interface::Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.reward_escrow.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::burn(cpi_ctx, amount)?;
+        config.total_burned = config
+            .total_burned
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        reward_lot.amount = 0;
+        reward_lot.expired_amount = reward_lot
+            .expired_amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!("Expired {} reward tokens from lot {}", amount, reward_lot.key());
+
+        emit_cpi!(RewardsExpiredEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: reward_lot.customer,
+            reward_lot: reward_lot.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawVestedRewards<'info> {
+    /// Handler for withdrawing a reward lot to the customer's wallet
+    pub fn handler(ctx: Context<WithdrawVestedRewards>) -> Result<()> {
+        let clock = Clock::get()?;
+        let reward_lot = &ctx.accounts.reward_lot;
+        let amount = reward_lot.amount;
+
+        // Once expired, the remainder belongs to the crank rather than the customer
+        if reward_lot.is_expired(clock.unix_timestamp) {
+            require!(amount == 0, CarsaError::RewardLotExpired);
+        }
+
+        let config = &mut ctx.accounts.config;
+        if amount > 0 {
+            let authority_seeds = &[
+                MINT_AUTHORITY_SEED,
+                &[config.mint_authority_bump],
+            ];
+            let signer_seeds = &[&authority_seeds[..]];
+
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.reward_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.customer_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(cpi_ctx, amount, config.decimals())?;
+        }
+
+        log_verbose!("Withdrew {} reward tokens from lot {}", amount, reward_lot.key());
+
+        emit_cpi!(RewardsWithdrawnEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: reward_lot.customer,
+            reward_lot: reward_lot.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct RewardsExpiredEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub reward_lot: Pubkey,
+    /// Reward tokens burned
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsWithdrawnEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub reward_lot: Pubkey,
+    /// Reward tokens moved to the customer's wallet (0 when closing an expired lot)
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -1,10 +1,21 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self as token, Mint, Token, TokenAccount};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id, AssociatedToken};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::error::CarsaError;
+use crate::math::{calculate_reward, calculate_token_value, LOKAL_DECIMALS};
+use crate::oracle::token_to_idr_rate_from_feed;
+use super::budgets::{release_from_budget_escrow, spend_reward_budget, RewardBudgetSpentEvent};
+use super::category_boosts::{apply_category_boost, category_boost_reward};
+use super::streaks::record_streak_visit;
+use super::franchises::{join_parent, roll_up_purchase};
+use super::operators::is_authorized_for_merchant;
+use super::pos_keys::check_merchant_signature;
+use super::mint_tokens::record_reward_mint;
 
 /// Register a new merchant in the Carsa loyalty program
 /// This instruction creates a merchant account with specific cashback rates
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(name: String, category: String, cashback_rate: u16)]
 pub struct RegisterMerchant<'info> {
@@ -23,14 +34,64 @@ pub struct RegisterMerchant<'info> {
     )]
     pub merchant_account: Account<'info, MerchantAccount>,
     
+    /// Registry that numbers merchants as they register
+    /// Created here if the mint was initialized before the registry existed
+    #[account(
+        init_if_needed,
+        payer = merchant_owner,
+        space = MerchantRegistry::LEN,
+        seeds = [MERCHANT_REGISTRY_SEED],
+        bump,
+    )]
+    pub merchant_registry: AccountLoader<'info, MerchantRegistry>,
+    
+    /// Index entry pointing at the new merchant
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = MerchantIndex::LEN,
+        seeds = [MERCHANT_INDEX_SEED, registered_merchant_count(&merchant_registry)?.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub merchant_index: Account<'info, MerchantIndex>,
+    
     /// System program required for account creation
     pub system_program: Program<'info, System>,
+    
+    /// The franchise parent the merchant registers under, if any
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, parent_merchant.owner.as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Option<Account<'info, ParentMerchant>>,
+    
+    /// The parent merchant's owner, co-signing registration under `parent_merchant`
+    pub parent_owner: Option<Signer<'info>>,
+    
+    /// The token account redeemed tokens are paid to, normally the owner's associated
+    /// token account; when omitted, the first redemption locks in the account it pays
+    /// CHECK: Only its address is recorded; a payment to it fails if it is not a Lokal token account
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+    
+    /// Configuration account containing mint settings
+    /// Writable so a high cashback alert can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
 }
 
 /// Process a purchase transaction and distribute rewards
 /// This is the core instruction that handles reward distribution logic and optional token redemption
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(fiat_amount: u64, redeem_token_amount: Option<u64>, transaction_id: [u8; 32])]
+// `Option<RedemptionParams>` serializes like the `Option<u64>` of the legacy
+// `process_purchase`, and `pay_invoice` takes its confirmed amount in the first slot,
+// so all three instructions share these accounts
+#[instruction(fiat_amount: u64, redemption: Option<RedemptionParams>, transaction_id: [u8; 32])]
 pub struct ProcessPurchase<'info> {
     /// The customer making the purchase
     #[account(mut)]
@@ -39,16 +100,19 @@ pub struct ProcessPurchase<'info> {
     /// The merchant account receiving the purchase
     #[account(
         mut,
-        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
     )]
     pub merchant_account: Account<'info, MerchantAccount>,
     
     /// The Lokal token mint
     #[account(
         mut,
-        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch,
+        constraint = mint.decimals == config.decimals() @ CarsaError::InvalidMintDecimals
     )]
-    pub mint: Account<'info, Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
     /// Program Derived Address that acts as the mint authority
     /// CHECK: This account is derived using seeds and verified in constraints
@@ -63,39 +127,310 @@ pub struct ProcessPurchase<'info> {
         mut,
         seeds = [CONFIG_SEED],
         bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
     )]
     pub config: Account<'info, LokalMintConfig>,
     
     /// The customer's token account that will receive the reward tokens and source for redemption
-    #[account(
-        mut,
-        constraint = customer_token_account.mint == mint.key()
-    )]
-    pub customer_token_account: Account<'info, TokenAccount>,
+    /// Either an existing Lokal token account, or the customer's associated token account,
+    /// created here on a first purchase when `associated_token_program` is passed
+    /// CHECK: Its owner, mint and state are checked in the handler, after it is created if needed
+    #[account(mut)]
+    pub customer_token_account: UncheckedAccount<'info>,
     
     /// The merchant's token account (required when redeeming tokens, otherwise can be any account)
     /// CHECK: This account is validated during runtime when redemption occurs
     #[account(mut)]
-    pub merchant_token_account: Account<'info, TokenAccount>,
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
     
-    /// Purchase transaction record for tracking
+    /// Purchase transaction record for tracking, keyed by merchant and customer, or the
+    /// customer's hash in privacy mode
+    /// Omitted for merchants with compressed receipts, which extend their receipt chain instead
     #[account(
         init,
         payer = customer,
         space = PurchaseTransaction::LEN,
-        seeds = [TRANSACTION_SEED, customer.key().as_ref(), &transaction_id],
+        seeds = [
+            TRANSACTION_SEED,
+            merchant_account.key().as_ref(),
+            config.record_customer(&customer.key()).as_ref(),
+            &transaction_id
+        ],
         bump,
     )]
-    pub transaction_record: Account<'info, PurchaseTransaction>,
+    pub transaction_record: Option<Account<'info, PurchaseTransaction>>,
     
     /// SPL Token program for mint operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+    
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            stake_pool.load()?.mint_seed().as_ref(),
+            pool_id_seed(0).as_ref()
+        ],
+        bump = stake_pool.load()?.bump,
+        constraint = stake_pool.load()?.voucher_mint == config.mint @ CarsaError::MintMismatch,
+    )]
+    pub stake_pool: Option<AccountLoader<'info, PoolState>>,
+    
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Account<'info, UserStakeRecord>>,
+    
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    #[account(
+        init,
+        payer = customer,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&customer.key()).as_ref(),
+            &transaction_id,
+            &[ActivityKind::Purchase as u8]
+        ],
+        bump,
+    )]
+    pub activity_record: Option<Account<'info, ActivityRecord>>,
+    
+    /// Activity feed entry for the redeemed tokens, required only when activity recording
+    /// is enabled and the purchase redeems tokens
+    #[account(
+        init,
+        payer = customer,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&customer.key()).as_ref(),
+            &transaction_id,
+            &[ActivityKind::Redemption as u8]
+        ],
+        bump,
+    )]
+    pub redemption_activity_record: Option<Account<'info, ActivityRecord>>,
+    
+    /// The configured treasury token account, required when a redemption owes a protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// The configured Pyth price account, required when the config sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    #[account(
+        init,
+        payer = customer,
+        space = RewardLot::LEN,
+        seeds = [REWARD_LOT_SEED, config.record_customer(&customer.key()).as_ref(), &transaction_id],
+        bump,
+    )]
+    pub reward_lot: Option<Account<'info, RewardLot>>,
+    
+    /// The invoice being paid, only with `pay_invoice`
+    #[account(
+        mut,
+        seeds = [INVOICE_SEED, merchant_account.key().as_ref(), &invoice.reference_id],
+        bump = invoice.bump,
+    )]
+    pub invoice: Option<Account<'info, Invoice>>,
+    
+    /// The instructions sysvar, required when the merchant requires signed approvals
+    /// CHECK: Address is checked; read through instruction introspection
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+    
+    /// The POS key that approved the purchase, when it was not the merchant wallet
+    #[account(
+        seeds = [POS_KEY_SEED, merchant_account.key().as_ref(), pos_key.pos_key.as_ref()],
+        bump = pos_key.bump,
+    )]
+    pub pos_key: Option<Account<'info, PosKey>>,
+    
+    /// The merchant's franchise parent, required when the merchant has one
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, parent_merchant.owner.as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Option<Account<'info, ParentMerchant>>,
+    
+    /// A sponsor's reward budget to pay the reward from instead of minting it
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_SEED, reward_budget.sponsor.as_ref(), &reward_budget.budget_id.to_le_bytes()],
+        bump = reward_budget.bump,
+    )]
+    pub reward_budget: Option<Account<'info, RewardBudget>>,
+    
+    /// The merchant's membership in `reward_budget`, checked in the handler
+    pub budget_membership: Option<Account<'info, BudgetMembership>>,
+    
+    /// Escrow holding sponsored budgets, required with `reward_budget`
+    #[account(
+        mut,
+        seeds = [REWARD_BUDGET_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_budget_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    /// Associated Token program, required to create `customer_token_account` for a new customer
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    
+    /// The operator that approved the purchase, when it was not the merchant wallet
+    #[account(
+        seeds = [MERCHANT_OPERATOR_SEED, merchant_account.key().as_ref(), merchant_operator.operator.as_ref()],
+        bump = merchant_operator.bump,
+    )]
+    pub merchant_operator: Option<Account<'info, MerchantOperator>>,
+    
+    /// The customer's passbook for the current month, created by their first purchase in it
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = CustomerMonthlyStats::LEN,
+        seeds = [
+            CUSTOMER_MONTHLY_STATS_SEED,
+            config.record_customer(&customer.key()).as_ref(),
+            current_year_month()?.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub customer_monthly_stats: Option<Account<'info, CustomerMonthlyStats>>,
+    
+    /// Slim purchase record, passed instead of `transaction_record` when the config sets
+    /// `slim_purchase_records`; keyed by the first 16 bytes of the transaction id
+    #[account(
+        init,
+        payer = customer,
+        space = PurchaseTransactionV2::LEN,
+        seeds = [
+            TRANSACTION_SEED,
+            merchant_account.key().as_ref(),
+            config.record_customer(&customer.key()).as_ref(),
+            &transaction_id[..16]
+        ],
+        bump,
+    )]
+    pub slim_transaction_record: Option<Account<'info, PurchaseTransactionV2>>,
+    
+    /// The protocol's boost for the merchant's category, adding to the reward while live
+    #[account(
+        mut,
+        seeds = [CATEGORY_BOOST_SEED, category_boost.category.as_ref()],
+        bump = category_boost.bump,
+    )]
+    pub category_boost: Option<Account<'info, CategoryBoost>>,
+    
+    /// The customer's visits to the merchant, created by their first purchase there
+    /// Required when the merchant has a streak reward
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = CustomerMerchantStats::LEN,
+        seeds = [
+            CUSTOMER_MERCHANT_STATS_SEED,
+            merchant_account.key().as_ref(),
+            config.record_customer(&customer.key()).as_ref()
+        ],
+        bump,
+    )]
+    pub customer_merchant_stats: Option<Account<'info, CustomerMerchantStats>>,
+    
+    /// Durable stub of the purchase, kept when its record is closed
+    /// Required with either purchase record layout
+    #[account(
+        init,
+        payer = customer,
+        space = TransactionStub::LEN,
+        seeds = [
+            TRANSACTION_STUB_SEED,
+            merchant_account.key().as_ref(),
+            config.record_customer(&customer.key()).as_ref(),
+            &transaction_id
+        ],
+        bump,
+    )]
+    pub transaction_stub: Option<Account<'info, TransactionStub>>,
+    
+    /// Index from the purchase's reference code to its record, created with the record
+    /// Required with either purchase record layout; taken by an earlier purchase, it fails
+    /// this one with `ReferenceCodeCollision`
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = ReferenceIndex::LEN,
+        seeds = [
+            REFERENCE_INDEX_SEED,
+            &ReferenceIndex::reference_code(&purchase_record_key(
+                transaction_record.as_ref(),
+                slim_transaction_record.as_ref()
+            ))
+        ],
+        bump,
+    )]
+    pub reference_index: Option<Account<'info, ReferenceIndex>>,
+}
+
+/// Create an invoice for a customer to pay by reference with `pay_invoice`
+#[derive(Accounts)]
+#[instruction(reference_id: [u8; 32])]
+pub struct CreateInvoice<'info> {
+    /// The merchant's owner wallet or one of its operators, paying for the invoice
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+    
+    /// The merchant the invoice is payable to
+    #[account(
+        constraint = is_authorized_for_merchant(
+            &merchant_account,
+            &merchant_owner.key(),
+            merchant_operator.as_ref()
+        ) @ CarsaError::MerchantOwnerMismatch,
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+    
+    /// The invoice to be created
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = Invoice::LEN,
+        seeds = [INVOICE_SEED, merchant_account.key().as_ref(), &reference_id],
+        bump,
+    )]
+    pub invoice: Account<'info, Invoice>,
     
     /// System program required for account creation
     pub system_program: Program<'info, System>,
+    
+    /// The signer's operator account, when the signer is not the merchant owner
+    #[account(
+        seeds = [MERCHANT_OPERATOR_SEED, merchant_account.key().as_ref(), merchant_owner.key().as_ref()],
+        bump = merchant_operator.bump,
+    )]
+    pub merchant_operator: Option<Account<'info, MerchantOperator>>,
 }
 
 /// Update merchant settings (cashback rate, active status, etc.)
+#[event_cpi]
 #[derive(Accounts)]
 pub struct UpdateMerchant<'info> {
     /// The merchant's owner wallet
@@ -105,11 +440,347 @@ pub struct UpdateMerchant<'info> {
     /// The merchant account to update
     #[account(
         mut,
-        seeds = [MERCHANT_SEED, merchant_owner.key().as_ref()],
-        bump = merchant_account.bump,
         constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
     )]
     pub merchant_account: Account<'info, MerchantAccount>,
+    
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// The update authority, co-signing to lift the cashback rate cooldown and increase cap
+    #[account(
+        constraint = update_authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub update_authority: Option<Signer<'info>>,
+}
+
+/// Change the token account a merchant's redeemed tokens are paid to
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPayoutAccount<'info> {
+    /// The merchant's owner wallet
+    pub merchant_owner: Signer<'info>,
+    
+    /// The merchant account to update
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+    
+    /// The new payout token account
+    #[account(
+        constraint = payout_token_account.mint == config.mint @ CarsaError::MintMismatch
+    )]
+    pub payout_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Close a merchant account and return its rent to the owner
+/// The merchant's registry index is kept as a tombstone
+#[derive(Accounts)]
+pub struct CloseMerchant<'info> {
+    /// The merchant's owner wallet
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+    
+    /// The merchant account to close
+    #[account(
+        mut,
+        close = merchant_owner,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+    
+    /// The merchant's index entry, marked closed
+    #[account(
+        mut,
+        seeds = [MERCHANT_INDEX_SEED, merchant_account.registry_index.to_le_bytes().as_ref()],
+        bump = merchant_index.bump,
+        constraint = merchant_index.merchant == merchant_account.key() @ CarsaError::MerchantIndexMismatch
+    )]
+    pub merchant_index: Account<'info, MerchantIndex>,
+}
+
+/// Preview the reward a purchase would earn without making it
+/// Read-only, so clients can simulate it with only a fee payer
+#[derive(Accounts)]
+pub struct PreviewPurchaseReward<'info> {
+    /// The customer the purchase would be made by
+    /// CHECK: Only its address is used, to check the stake record
+    pub customer: UncheckedAccount<'info>,
+    
+    /// The merchant account the purchase would be made at
+    #[account(
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            stake_pool.load()?.mint_seed().as_ref(),
+            pool_id_seed(0).as_ref()
+        ],
+        bump = stake_pool.load()?.bump,
+        constraint = stake_pool.load()?.voucher_mint == config.mint @ CarsaError::MintMismatch,
+    )]
+    pub stake_pool: Option<AccountLoader<'info, PoolState>>,
+    
+    /// The customer's stake record in `stake_pool`, to include the staker boost
+    pub customer_stake_record: Option<Account<'info, UserStakeRecord>>,
+    
+    /// The configured Pyth price account, required when the config sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+    
+    /// The protocol's boost for the merchant's category, to include it while live
+    #[account(
+        seeds = [CATEGORY_BOOST_SEED, category_boost.category.as_ref()],
+        bump = category_boost.bump,
+    )]
+    pub category_boost: Option<Account<'info, CategoryBoost>>,
+}
+
+/// Whether `init_if_needed` created the registry in this instruction, in which case
+/// its discriminator is only written on exit
+fn registry_is_new(registry: &AccountLoader<MerchantRegistry>) -> Result<bool> {
+    let data = registry.as_ref().try_borrow_data()?;
+    Ok(data[..MerchantRegistry::DISCRIMINATOR.len()].iter().all(|byte| *byte == 0))
+}
+
+/// Merchants registered so far, zero for a registry created in this instruction
+pub(crate) fn registered_merchant_count(registry: &AccountLoader<MerchantRegistry>) -> Result<u64> {
+    if registry_is_new(registry)? {
+        return Ok(0);
+    }
+    Ok(registry.load()?.merchant_count)
+}
+
+/// The current calendar month as YYYYMM, seeding the month's `CustomerMonthlyStats`
+fn current_year_month() -> Result<u32> {
+    CustomerMonthlyStats::year_month(Clock::get()?.unix_timestamp)
+        .ok_or_else(|| error!(CarsaError::ArithmeticOverflow))
+}
+
+/// Load the registry for writing, initializing it if it was just created
+fn load_registry_mut<'a>(
+    registry: &'a AccountLoader<MerchantRegistry>,
+) -> Result<std::cell::RefMut<'a, MerchantRegistry>> {
+    if registry_is_new(registry)? {
+        registry.load_init()
+    } else {
+        registry.load_mut()
+    }
+}
+
+/// Check the details a new merchant registers with
+pub(crate) fn validate_merchant_details(
+    config: &LokalMintConfig,
+    name: &str,
+    category: &str,
+    cashback_rate: u16,
+) -> Result<()> {
+    require!(name.len() <= 32 && !name.is_empty(), CarsaError::InvalidMerchantName);
+    require!(category.len() <= 16 && !category.is_empty(), CarsaError::InvalidMerchantCategory);
+    require!(cashback_rate <= 10_000, CarsaError::InvalidCashbackRate); // Max 100%
+    require!(config.allows_cashback_rate(cashback_rate), CarsaError::CashbackRateAboveMax);
+    Ok(())
+}
+
+/// Give a new merchant the next registry position and point its index entry at it
+/// `merchant_key` is the address of `merchant_account`
+pub(crate) fn take_registry_position(
+    merchant_key: Pubkey,
+    merchant_account: &mut MerchantAccount,
+    merchant_registry: &AccountLoader<MerchantRegistry>,
+    registry_bump: u8,
+    merchant_index: &mut Account<MerchantIndex>,
+    index_bump: u8,
+) -> Result<()> {
+    let merchant_registry = &mut *load_registry_mut(merchant_registry)?;
+    merchant_registry.bump = registry_bump;
+    merchant_account.registry_index = merchant_registry.merchant_count;
+    merchant_registry.merchant_count = merchant_registry
+        .merchant_count
+        .checked_add(1)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    merchant_index.merchant = merchant_key;
+    merchant_index.index = merchant_account.registry_index;
+    merchant_index.closed = false;
+    merchant_index.bump = index_bump;
+    Ok(())
+}
+
+/// Read the customer's token account, creating their associated one first if it is missing
+fn load_customer_token_account(accounts: &ProcessPurchase) -> Result<TokenAccount> {
+    let customer_token_account = accounts.customer_token_account.to_account_info();
+    let token_program = accounts.token_program.key();
+
+    if customer_token_account.data_is_empty() {
+        let associated_token_program = accounts
+            .associated_token_program
+            .as_ref()
+            .ok_or(CarsaError::InvalidCustomerTokenAccount)?;
+        require_keys_eq!(
+            customer_token_account.key(),
+            get_associated_token_address_with_program_id(
+                &accounts.customer.key(),
+                &accounts.mint.key(),
+                &token_program,
+            ),
+            CarsaError::InvalidCustomerTokenAccount
+        );
+
+        let cpi_accounts = associated_token::Create {
+            payer: accounts.customer.to_account_info(),
+            associated_token: customer_token_account.clone(),
+            authority: accounts.customer.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            system_program: accounts.system_program.to_account_info(),
+            token_program: accounts.token_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(associated_token_program.to_account_info(), cpi_accounts);
+        associated_token::create(cpi_ctx)?;
+
+        log_verbose!("Created token account {} for customer", customer_token_account.key());
+    }
+
+    require_keys_eq!(
+        *customer_token_account.owner,
+        token_program,
+        CarsaError::InvalidCustomerTokenAccount
+    );
+    let token_account =
+        TokenAccount::try_deserialize(&mut &customer_token_account.try_borrow_data()?[..])?;
+    require_keys_eq!(token_account.mint, accounts.mint.key(), CarsaError::MintMismatch);
+    require!(!token_account.is_frozen(), CarsaError::AccountFrozen);
+
+    Ok(token_account)
+}
+
+/// Address of the purchase record an instruction writes, of either layout, or the default
+/// pubkey when it writes none
+pub(crate) fn purchase_record_key(
+    transaction_record: Option<&impl Key>,
+    slim_transaction_record: Option<&impl Key>,
+) -> Pubkey {
+    transaction_record
+        .map(Key::key)
+        .or(slim_transaction_record.map(Key::key))
+        .unwrap_or_default()
+}
+
+/// Cashback rate for a purchase, including the staker boost when the customer's stake
+/// record in the original voucher pool is supplied
+pub(crate) fn effective_cashback_rate(
+    program_id: &Pubkey,
+    config: &LokalMintConfig,
+    merchant_cashback_rate: u16,
+    customer: &Pubkey,
+    stake_pool: Option<&AccountLoader<PoolState>>,
+    stake_record: Option<&Account<UserStakeRecord>>,
+) -> Result<u16> {
+    // Merchants left above a lowered cap earn their customers nothing until they comply
+    require!(
+        config.allows_cashback_rate(merchant_cashback_rate),
+        CarsaError::CashbackRateAboveMax
+    );
+    match (stake_pool, stake_record) {
+        (Some(stake_pool), Some(stake_record)) => {
+            // The record must be the customer's own stake PDA in the pool
+            let expected = Pubkey::create_program_address(
+                &[
+                    USER_STAKE_SEED,
+                    stake_pool.key().as_ref(),
+                    customer.as_ref(),
+                    &[stake_record.bump],
+                ],
+                program_id,
+            )
+            .map_err(|_| error!(CarsaError::InvalidStakeRecord))?;
+            require!(
+                expected == stake_record.key()
+                    && stake_record.pool == stake_pool.key()
+                    && stake_record.user == *customer,
+                CarsaError::InvalidStakeRecord
+            );
+            Ok(config.boosted_cashback_rate(merchant_cashback_rate, stake_record.staked_amount))
+        }
+        (None, None) => Ok(merchant_cashback_rate),
+        _ => err!(CarsaError::InvalidStakeRecord),
+    }
+}
+
+/// Value of one token in IDR: the price feed's when the config sets one, which must then
+/// be supplied, fresh and tight enough, otherwise the static `token_to_idr_rate`
+pub(crate) fn current_token_to_idr_rate(
+    config: &LokalMintConfig,
+    price_feed: Option<&UncheckedAccount>,
+    now: i64,
+) -> Result<u64> {
+    if config.price_feed == Pubkey::default() {
+        return Ok(config.token_to_idr_rate);
+    }
+
+    let price_feed = price_feed
+        .filter(|price_feed| price_feed.key() == config.price_feed)
+        .ok_or(CarsaError::StalePriceFeed)?;
+    token_to_idr_rate_from_feed(
+        price_feed,
+        now,
+        config.max_price_age_seconds,
+        config.max_price_confidence_bps,
+    )
+}
+
+/// Total purchase value in IDR and the reward tokens it earns, with one token worth
+/// `token_to_idr_rate` IDR plus the merchant's `redemption_bonus_bps` when redeemed
+pub(crate) fn purchase_reward(
+    fiat_amount: u64,
+    redeemed_tokens: u64,
+    cashback_rate: u16,
+    token_to_idr_rate: u64,
+    redemption_bonus_bps: u16,
+) -> Result<(u64, u64)> {
+    let token_value_in_idr = calculate_token_value(
+        redeemed_tokens,
+        token_to_idr_rate,
+        redemption_bonus_bps,
+        LOKAL_DECIMALS,
+    )?;
+    let total_value = fiat_amount
+        .checked_add(token_value_in_idr)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    let reward_amount = calculate_reward(total_value, cashback_rate, token_to_idr_rate, LOKAL_DECIMALS)?;
+
+    Ok((total_value, reward_amount))
 }
 
 impl<'info> RegisterMerchant<'info> {
@@ -119,11 +790,9 @@ impl<'info> RegisterMerchant<'info> {
         name: String,
         category: String,
         cashback_rate: u16,
+        min_purchase_amount: u64,
     ) -> Result<()> {
-        // Validate inputs
-        require!(name.len() <= 32 && !name.is_empty(), CarsaError::InvalidMerchantName);
-        require!(category.len() <= 16 && !category.is_empty(), CarsaError::InvalidMerchantCategory);
-        require!(cashback_rate <= 10_000, CarsaError::InvalidCashbackRate); // Max 100%
+        validate_merchant_details(&ctx.accounts.config, &name, &category, cashback_rate)?;
 
         let merchant_account = &mut ctx.accounts.merchant_account;
         let clock = Clock::get()?;
@@ -132,121 +801,415 @@ impl<'info> RegisterMerchant<'info> {
         merchant_account.merchant_wallet = ctx.accounts.merchant_owner.key();
         merchant_account.cashback_rate = cashback_rate;
         merchant_account.is_active = true;
+        merchant_account.suspended = false;
+        merchant_account.min_purchase_amount = min_purchase_amount;
         merchant_account.total_transactions = 0;
         merchant_account.total_rewards_distributed = 0;
         merchant_account.total_volume = 0;
         merchant_account.created_at = clock.unix_timestamp;
         merchant_account.bump = ctx.bumps.merchant_account;
+        merchant_account.parent_merchant = Pubkey::default();
+        merchant_account.last_rate_change_at = 0;
+        merchant_account.payout_token_account = ctx
+            .accounts
+            .payout_token_account
+            .as_ref()
+            .map_or(Pubkey::default(), |payout_token_account| payout_token_account.key());
 
-        // Convert strings to fixed-size byte arrays with padding
-        let mut name_bytes = [0u8; 32];
-        let name_slice = name.as_bytes();
-        let name_len = name_slice.len().min(32);
-        name_bytes[..name_len].copy_from_slice(&name_slice[..name_len]);
-        merchant_account.name = name_bytes;
+        // Outlets of a franchise join their parent with its owner's co-signature
+        if let Some(parent_merchant) = ctx.accounts.parent_merchant.as_mut() {
+            join_parent(merchant_account, parent_merchant, ctx.accounts.parent_owner.as_ref())?;
+            log_verbose!("Merchant joined parent merchant {}", parent_merchant.key());
+        }
+
+        take_registry_position(
+            merchant_account.key(),
+            merchant_account,
+            &ctx.accounts.merchant_registry,
+            ctx.bumps.merchant_registry,
+            &mut ctx.accounts.merchant_index,
+            ctx.bumps.merchant_index,
+        )?;
 
-        let mut category_bytes = [0u8; 16];
-        let category_slice = category.as_bytes();
-        let category_len = category_slice.len().min(16);
-        category_bytes[..category_len].copy_from_slice(&category_slice[..category_len]);
-        merchant_account.category = category_bytes;
+        merchant_account.name = MerchantAccount::name_bytes(&name);
+        merchant_account.category = MerchantAccount::category_bytes(&category);
 
-        msg!(
+        log_verbose!(
             "Merchant registered: {} ({}), Cashback: {}bps",
             name,
             category,
             cashback_rate
         );
 
+        let config = &mut ctx.accounts.config;
+        if config.is_high_cashback_rate(cashback_rate) {
+            emit_cpi!(HighCashbackAlertEvent {
+                sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                merchant: merchant_account.key(),
+                cashback_rate,
+                alert_threshold_bps: config.alert_threshold_bps,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> CreateInvoice<'info> {
+    /// Handler for creating an invoice
+    pub fn handler(
+        ctx: Context<CreateInvoice>,
+        reference_id: [u8; 32],
+        fiat_amount: u64,
+        max_redeem_token_amount: Option<u64>,
+        expires_at: i64,
+    ) -> Result<()> {
+        // The purchase limit is checked when the invoice is paid, against the limit then
+        require!(fiat_amount > 0, CarsaError::InvalidPurchaseAmount);
+
+        let clock = Clock::get()?;
+        require!(expires_at > clock.unix_timestamp, CarsaError::InvalidInvoiceExpiry);
+
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.set_inner(Invoice {
+            merchant: ctx.accounts.merchant_account.key(),
+            reference_id,
+            fiat_amount,
+            max_redeem_token_amount: max_redeem_token_amount.unwrap_or(0),
+            expires_at,
+            created_at: clock.unix_timestamp,
+            paid: false,
+            paid_by: Pubkey::default(),
+            purchase: Pubkey::default(),
+            bump: ctx.bumps.invoice,
+        });
+
+        log_verbose!(
+            "Invoice {} created: Rp {}, expires at {}",
+            invoice.key(),
+            fiat_amount,
+            expires_at
+        );
+
         Ok(())
     }
 }
 
 impl<'info> ProcessPurchase<'info> {
+    /// Compute units a fiat-only purchase may use, checked by `program-tests`
+    /// Lower it as the handler gets cheaper; raising it needs a profile showing why
+    pub const COMPUTE_UNIT_CEILING: u64 = 44_900;
+
     /// Handler for processing purchases and distributing rewards with optional token redemption
     pub fn handler(
         ctx: Context<ProcessPurchase>,
         fiat_amount: u64,
-        redeem_token_amount: Option<u64>,
+        redemption: Option<RedemptionParams>,
         transaction_id: [u8; 32],
+        expected_nonce: Option<u64>,
+    ) -> Result<()> {
+        // Invoices are only paid through `pay_invoice`, which checks them first
+        require!(ctx.accounts.invoice.is_none(), CarsaError::InvoiceMismatch);
+
+        Self::settle(
+            ctx,
+            fiat_amount,
+            redemption,
+            transaction_id,
+            Pubkey::default(),
+            expected_nonce,
+        )
+    }
+
+    /// Handler for paying an invoice, taking the amount from the invoice
+    /// `invoice_amount` is the amount the customer was shown and must match the invoice
+    pub fn pay_invoice_handler(
+        ctx: Context<ProcessPurchase>,
+        invoice_amount: u64,
+        redemption: Option<RedemptionParams>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let purchase = match (
+            ctx.accounts.transaction_record.as_ref(),
+            ctx.accounts.slim_transaction_record.as_ref(),
+        ) {
+            (Some(record), _) => record.key(),
+            (None, Some(record)) => record.key(),
+            (None, None) => Pubkey::default(),
+        };
+        let customer = ctx.accounts.customer.key();
+        let invoice = ctx
+            .accounts
+            .invoice
+            .as_mut()
+            .ok_or(CarsaError::InvoiceMismatch)?;
+        require!(!invoice.paid, CarsaError::InvoiceAlreadyPaid);
+        require!(clock.unix_timestamp < invoice.expires_at, CarsaError::InvoiceExpired);
+        require!(invoice_amount == invoice.fiat_amount, CarsaError::InvoiceMismatch);
+        require!(
+            redemption.map_or(0, |redemption| redemption.token_amount)
+                <= invoice.max_redeem_token_amount,
+            CarsaError::InvoiceRedemptionExceeded
+        );
+
+        invoice.paid = true;
+        invoice.paid_by = customer;
+        invoice.purchase = purchase;
+        let invoice_key = invoice.key();
+        let fiat_amount = invoice.fiat_amount;
+
+        log_verbose!("Paying invoice {}", invoice_key);
+
+        Self::settle(ctx, fiat_amount, redemption, transaction_id, invoice_key, None)
+    }
+
+    /// Shared purchase logic for `handler` and `pay_invoice_handler`
+    fn settle(
+        ctx: Context<ProcessPurchase>,
+        fiat_amount: u64,
+        redemption: Option<RedemptionParams>,
+        transaction_id: [u8; 32],
+        invoice: Pubkey,
+        expected_nonce: Option<u64>,
     ) -> Result<()> {
         // Validate fiat amount
         require!(fiat_amount > 0, CarsaError::InvalidPurchaseAmount);
-        
-        // Set maximum purchase amount (Rp 1,000,000,000 IDR = 1 billion IDR)
-        const MAX_PURCHASE_AMOUNT: u64 = 1_000_000_000; // 1 billion IDR
-        require!(fiat_amount <= MAX_PURCHASE_AMOUNT, CarsaError::PurchaseAmountTooLarge);
+        require!(
+            ctx.accounts.config.allows_purchase(fiat_amount),
+            CarsaError::PurchaseAmountTooLarge
+        );
 
+        let customer_token_account = load_customer_token_account(ctx.accounts)?;
         let merchant_account = &mut ctx.accounts.merchant_account;
         let config = &mut ctx.accounts.config;
-        let transaction_record = &mut ctx.accounts.transaction_record;
         let clock = Clock::get()?;
 
+        // Stakers in the voucher pool earn a cashback boost on top of the merchant's rate
+        let customer_key = ctx.accounts.customer.key();
+        let cashback_rate = effective_cashback_rate(
+            ctx.program_id,
+            config,
+            merchant_account.cashback_rate,
+            &customer_key,
+            ctx.accounts.stake_pool.as_ref(),
+            ctx.accounts.customer_stake_record.as_ref(),
+        )?;
+        let staker_boost_bps = cashback_rate.saturating_sub(merchant_account.cashback_rate);
+        // In privacy mode the purchase's records and events name the customer by hash only
+        let record_customer = config.record_customer(&customer_key);
+
+        // Invoices are written by the merchant, so paying one needs no separate approval
+        if merchant_account.require_merchant_signature && invoice == Pubkey::default() {
+            check_merchant_signature(
+                merchant_account,
+                ctx.accounts.pos_key.as_ref(),
+                ctx.accounts.merchant_operator.as_ref(),
+                ctx.accounts.instructions_sysvar.as_ref(),
+                &customer_key,
+                fiat_amount,
+                &transaction_id,
+            )?;
+        }
+
+        // A POS nonce must be the next one, so a replayed request fails even with a new
+        // transaction ID
+        if let Some(expected_nonce) = expected_nonce {
+            require!(
+                merchant_account.pos_nonce.checked_add(1) == Some(expected_nonce),
+                CarsaError::InvalidPosNonce
+            );
+            merchant_account.pos_nonce = expected_nonce;
+        }
+
         // Handle token redemption if specified
-        let redeemed_tokens = redeem_token_amount.unwrap_or(0);
+        let redeemed_tokens = redemption.map_or(0, |redemption| redemption.token_amount);
+        let redemption_bonus_bps = if redeemed_tokens > 0 {
+            merchant_account.redemption_bonus_bps
+        } else {
+            0
+        };
         let used_tokens = redeemed_tokens > 0;
 
+        require!(
+            config.record_activity == ctx.accounts.activity_record.is_some()
+                && (config.record_activity && used_tokens)
+                    == ctx.accounts.redemption_activity_record.is_some(),
+            CarsaError::InvalidActivityRecord
+        );
+        let full_receipts = !merchant_account.compressed_receipts;
+        require!(
+            (full_receipts && !config.slim_purchase_records)
+                == ctx.accounts.transaction_record.is_some()
+                && (full_receipts && config.slim_purchase_records)
+                    == ctx.accounts.slim_transaction_record.is_some(),
+            CarsaError::InvalidTransactionRecord
+        );
+        require!(
+            full_receipts == ctx.accounts.transaction_stub.is_some(),
+            CarsaError::InvalidTransactionStub
+        );
+        require!(
+            full_receipts == ctx.accounts.reference_index.is_some(),
+            CarsaError::InvalidReferenceIndex
+        );
+        require!(
+            config.expiring_rewards == ctx.accounts.reward_escrow.is_some()
+                && config.expiring_rewards == ctx.accounts.reward_lot.is_some(),
+            CarsaError::InvalidRewardLot
+        );
+
+        // Calculate total transaction value and the reward it earns
+        let token_to_idr_rate = current_token_to_idr_rate(
+            config,
+            ctx.accounts.price_feed.as_ref(),
+            clock.unix_timestamp,
+        )?;
+        let (total_value, merchant_reward) = purchase_reward(
+            fiat_amount,
+            redeemed_tokens,
+            cashback_rate,
+            token_to_idr_rate,
+            redemption_bonus_bps,
+        )?;
+
+        // The minimum applies to the whole value so redemptions cannot dodge it
+        require!(
+            total_value >= merchant_account.min_purchase_amount,
+            CarsaError::PurchaseBelowMinimum
+        );
+
+        // A live category boost adds the protocol's rate on top, as far as its budget goes
+        let (category_boost_bps, category_boost_reward) = apply_category_boost(
+            ctx.accounts.category_boost.as_mut(),
+            &merchant_account.category,
+            total_value,
+            token_to_idr_rate,
+            clock.unix_timestamp,
+        )?;
+        let reward_amount = merchant_reward
+            .checked_add(category_boost_reward)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // The treasury's cut comes out of the merchant's share, never the purchase value
+        let protocol_fee = config
+            .protocol_fee(redeemed_tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
         if used_tokens {
             // Validate token redemption amount
             require!(redeemed_tokens > 0, CarsaError::InvalidRedemptionAmount);
             
             // Check customer has sufficient balance
             require!(
-                ctx.accounts.customer_token_account.amount >= redeemed_tokens,
+                customer_token_account.amount >= redeemed_tokens,
                 CarsaError::InsufficientBalance
             );
 
             // Validate merchant token account ownership via program constraint
             // The actual token account validation will be done by the SPL Token program
             // during the transfer instruction, so we don't need to parse the account data here
+            // A frozen account is refused up front rather than failing inside the transfer
+            require!(
+                !ctx.accounts.merchant_token_account.is_frozen(),
+                CarsaError::AccountFrozen
+            );
+
+            // Merchants registered without a payout account lock in the first one they are
+            // paid to, which must then be their own
+            let merchant_token_account = &ctx.accounts.merchant_token_account;
+            if merchant_account.payout_token_account == Pubkey::default() {
+                require_keys_eq!(
+                    merchant_token_account.owner,
+                    merchant_account.merchant_wallet,
+                    CarsaError::InvalidOwner
+                );
+                merchant_account.payout_token_account = merchant_token_account.key();
+            }
+            require_keys_eq!(
+                merchant_token_account.key(),
+                merchant_account.payout_token_account,
+                CarsaError::PayoutAccountMismatch
+            );
+
+            // Route the protocol fee to the treasury
+            if protocol_fee > 0 {
+                let treasury_token_account = ctx
+                    .accounts
+                    .treasury_token_account
+                    .as_ref()
+                    .ok_or(CarsaError::InvalidTreasuryAccount)?;
 
-            // Transfer tokens from customer to merchant
-            let cpi_accounts = token::Transfer {
+                let cpi_accounts = token_interface::TransferChecked {
+                    from: ctx.accounts.customer_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: treasury_token_account.to_account_info(),
+                    authority: ctx.accounts.customer.to_account_info(),
+                };
+
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+                token_interface::transfer_checked(
+                    cpi_ctx,
+                    protocol_fee,
+                    config.decimals(),
+                )?;
+            }
+
+            // Transfer the remaining tokens from customer to merchant
+            let cpi_accounts = token_interface::TransferChecked {
                 from: ctx.accounts.customer_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
                 to: ctx.accounts.merchant_token_account.to_account_info(),
                 authority: ctx.accounts.customer.to_account_info(),
             };
 
             let cpi_program = ctx.accounts.token_program.to_account_info();
             let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-            token::transfer(cpi_ctx, redeemed_tokens)?;
+            token_interface::transfer_checked(
+                cpi_ctx,
+                redeemed_tokens - protocol_fee,
+                config.decimals(),
+            )?;
         }
 
-        // Calculate total transaction value (fiat + token value in IDR)
-        // Token to IDR conversion: 1 token = Rp 1,000
-        const TOKEN_TO_FIAT_RATE: u64 = 1_000;
-        let token_value_in_idr = redeemed_tokens
-            .checked_div(1_000_000_000) // Convert from token units to tokens
-            .ok_or(CarsaError::ArithmeticOverflow)?
-            .checked_mul(TOKEN_TO_FIAT_RATE)
-            .ok_or(CarsaError::ArithmeticOverflow)?;
+        // A sponsor's budget pays the merchant's reward while it can cover it
+        let budget_paid = spend_reward_budget(
+            &merchant_account.key(),
+            ctx.accounts.reward_budget.as_mut(),
+            ctx.accounts.budget_membership.as_ref(),
+            merchant_reward,
+        )?;
 
-        let total_value = fiat_amount
-            .checked_add(token_value_in_idr)
-            .ok_or(CarsaError::ArithmeticOverflow)?;
-
-        // Calculate reward amount based on total transaction value and cashback rate
-        // Formula: reward_tokens = ((total_value * cashback_rate) / 10_000 / 1_000) * 10^9
-        let reward_calculation = (total_value as u128)
-            .checked_mul(merchant_account.cashback_rate as u128)
-            .ok_or(CarsaError::ArithmeticOverflow)?
-            .checked_mul(1_000_000_000u128) // Convert to token units (9 decimals)
-            .ok_or(CarsaError::ArithmeticOverflow)?
-            .checked_div(10_000u128) // Convert basis points to decimal
-            .ok_or(CarsaError::ArithmeticOverflow)?
-            .checked_div(1_000u128) // Convert IDR to tokens (1 token = Rp 1,000)
-            .ok_or(CarsaError::ArithmeticOverflow)?;
+        // Expiring rewards are held in escrow until withdrawn
+        let reward_destination = match ctx.accounts.reward_escrow.as_ref() {
+            Some(reward_escrow) => reward_escrow.to_account_info(),
+            None => ctx.accounts.customer_token_account.to_account_info(),
+        };
 
-        let reward_amount = u64::try_from(reward_calculation)
-            .map_err(|_| CarsaError::ArithmeticOverflow)?;
+        if budget_paid {
+            let reward_budget_escrow = ctx
+                .accounts
+                .reward_budget_escrow
+                .as_ref()
+                .ok_or(CarsaError::InvalidRewardBudget)?;
+            release_from_budget_escrow(
+                config,
+                &ctx.accounts.token_program,
+                &ctx.accounts.mint,
+                reward_budget_escrow,
+                &ctx.accounts.mint_authority,
+                reward_destination.clone(),
+                merchant_reward,
+            )?;
+        }
 
-        // Only mint reward tokens if reward amount > 0
-        if reward_amount > 0 {
-            // Update global configuration with overflow protection
-            config.total_supply = config
-                .total_supply
-                .checked_add(reward_amount)
-                .ok_or(CarsaError::ArithmeticOverflow)?;
+        // Mint what the budget did not pay, including any category boost
+        let minted_reward = if budget_paid { category_boost_reward } else { reward_amount };
+        if minted_reward > 0 {
+            // Count it against the purchase reward mint cap
+            record_reward_mint(config, minted_reward)?;
 
             // Create signer seeds for CPI call to mint tokens
             let authority_seeds = &[
@@ -256,9 +1219,9 @@ impl<'info> ProcessPurchase<'info> {
             let signer_seeds = &[&authority_seeds[..]];
 
             // Create CPI context for minting reward tokens
-            let cpi_accounts = token::MintTo {
+            let cpi_accounts = token_interface::MintTo {
                 mint: ctx.accounts.mint.to_account_info(),
-                to: ctx.accounts.customer_token_account.to_account_info(),
+                to: reward_destination,
                 authority: ctx.accounts.mint_authority.to_account_info(),
             };
 
@@ -266,9 +1229,40 @@ impl<'info> ProcessPurchase<'info> {
             let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
 
             // Execute the mint operation to distribute rewards
-            token::mint_to(cpi_ctx, reward_amount)?;
+            token_interface::mint_to(cpi_ctx, minted_reward)?;
         }
 
+        // Completing the merchant's visit streak mints its bonus straight to the customer
+        let streak_bonus = record_streak_visit(
+            merchant_account,
+            ctx.accounts.customer_merchant_stats.as_mut(),
+            ctx.bumps.customer_merchant_stats,
+            &record_customer,
+            clock.unix_timestamp,
+        )?;
+        if streak_bonus > 0 {
+            record_reward_mint(config, streak_bonus)?;
+
+            let authority_seeds = &[
+                MINT_AUTHORITY_SEED,
+                &[config.mint_authority_bump],
+            ];
+            let signer_seeds = &[&authority_seeds[..]];
+            let cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.customer_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::mint_to(cpi_ctx, streak_bonus)?;
+        }
+
+        config.total_purchases = config
+            .total_purchases
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
         // Update merchant statistics with overflow protection
         merchant_account.total_transactions = merchant_account
             .total_transactions
@@ -283,66 +1277,612 @@ impl<'info> ProcessPurchase<'info> {
         merchant_account.total_rewards_distributed = merchant_account
             .total_rewards_distributed
             .checked_add(reward_amount)
+            .and_then(|total| total.checked_add(streak_bonus))
             .ok_or(CarsaError::ArithmeticOverflow)?;
 
-        // Record the transaction
-        transaction_record.customer = ctx.accounts.customer.key();
-        transaction_record.merchant = merchant_account.key();
-        transaction_record.fiat_amount = fiat_amount;
-        transaction_record.redeemed_token_amount = redeemed_tokens;
-        transaction_record.total_value = total_value;
-        transaction_record.reward_amount = reward_amount;
-        transaction_record.cashback_rate = merchant_account.cashback_rate;
-        transaction_record.used_tokens = used_tokens;
-        transaction_record.timestamp = clock.unix_timestamp;
-        transaction_record.transaction_id = transaction_id;
-        transaction_record.bump = ctx.bumps.transaction_record;
-
-        // Log detailed transaction information
+        // The merchant's share of the redemption awaits settlement for IDR
+        merchant_account.unsettled_tokens = merchant_account
+            .unsettled_tokens
+            .checked_add(redeemed_tokens - protocol_fee)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        roll_up_purchase(
+            merchant_account,
+            ctx.accounts.parent_merchant.as_mut(),
+            total_value,
+            reward_amount,
+        )?;
+
+        // Record the transaction, or chain its receipt for merchants with compressed receipts
+        // Those purchases are not deduplicated by transaction id on-chain; indexers must
+        // drop repeated ids when replaying the receipts
+        let receipt_chain_hash = if merchant_account.compressed_receipts {
+            Some(merchant_account.chain_receipt(
+                &record_customer,
+                fiat_amount,
+                redeemed_tokens,
+                reward_amount,
+                &transaction_id,
+                clock.unix_timestamp,
+            ))
+        } else {
+            None
+        };
+        let record_key = purchase_record_key(
+            ctx.accounts.transaction_record.as_ref(),
+            ctx.accounts.slim_transaction_record.as_ref(),
+        );
+        let reference_code = full_receipts.then(|| ReferenceIndex::reference_code(&record_key));
+        if let (Some(transaction_record), Some(bump)) = (
+            ctx.accounts.transaction_record.as_mut(),
+            ctx.bumps.transaction_record,
+        ) {
+            transaction_record.customer = record_customer;
+            transaction_record.merchant = merchant_account.key();
+            transaction_record.fiat_amount = fiat_amount;
+            transaction_record.redeemed_token_amount = redeemed_tokens;
+            transaction_record.total_value = total_value;
+            transaction_record.reward_amount = reward_amount;
+            transaction_record.cashback_rate = cashback_rate;
+            transaction_record.used_tokens = used_tokens;
+            transaction_record.timestamp = clock.unix_timestamp;
+            transaction_record.transaction_id = transaction_id;
+            transaction_record.bump = bump;
+            transaction_record.staker_boost_bps = staker_boost_bps;
+            transaction_record.protocol_fee = protocol_fee;
+            transaction_record.redemption_bonus_bps = redemption_bonus_bps;
+            transaction_record.invoice = invoice;
+            transaction_record.category_boost_bps = category_boost_bps;
+            transaction_record.category_boost_reward = category_boost_reward;
+            transaction_record.streak_bonus = streak_bonus;
+            transaction_record.reference_code = reference_code.unwrap_or_default();
+        }
+        if let (Some(transaction_record), Some(bump)) = (
+            ctx.accounts.slim_transaction_record.as_mut(),
+            ctx.bumps.slim_transaction_record,
+        ) {
+            transaction_record.set_inner(PurchaseTransactionV2 {
+                customer: record_customer,
+                merchant: merchant_account.key(),
+                fiat_amount,
+                redeemed_token_amount: redeemed_tokens,
+                reward_amount,
+                protocol_fee,
+                timestamp: clock.unix_timestamp,
+                fulfilled_at: 0,
+                transaction_id: PurchaseTransactionV2::short_id(&transaction_id),
+                invoice,
+                staker_boost_bps,
+                redemption_bonus_bps,
+                bump,
+                disputed: false,
+                refunded: false,
+                category_boost_reward,
+                streak_bonus,
+                reference_code: reference_code.unwrap_or_default(),
+            });
+        }
+        if let (Some(transaction_stub), Some(bump)) = (
+            ctx.accounts.transaction_stub.as_mut(),
+            ctx.bumps.transaction_stub,
+        ) {
+            transaction_stub.set_inner(TransactionStub {
+                customer: record_customer,
+                merchant: merchant_account.key(),
+                status: TransactionStatus::Completed,
+                timestamp: clock.unix_timestamp,
+                bump,
+            });
+        }
+
+        // Record and index are both new, so an index already in use belongs to another
+        // purchase whose record address hashes to the same code
+        if let (Some(reference_index), Some(bump), Some(reference_code)) = (
+            ctx.accounts.reference_index.as_mut(),
+            ctx.bumps.reference_index,
+            reference_code,
+        ) {
+            require_keys_eq!(
+                reference_index.transaction_record,
+                Pubkey::default(),
+                CarsaError::ReferenceCodeCollision
+            );
+            reference_index.set_inner(ReferenceIndex {
+                reference_code,
+                transaction_record: record_key,
+                transaction_stub: ctx
+                    .accounts
+                    .transaction_stub
+                    .as_ref()
+                    .map_or_else(Pubkey::default, |stub| stub.key()),
+                bump,
+            });
+        }
+
+        if let (Some(reward_lot), Some(bump)) = (
+            ctx.accounts.reward_lot.as_mut(),
+            ctx.bumps.reward_lot,
+        ) {
+            reward_lot.set_inner(RewardLot {
+                customer: record_customer,
+                transaction_id,
+                amount: reward_amount,
+                expired_amount: 0,
+                minted_at: clock.unix_timestamp,
+                expires_at: clock
+                    .unix_timestamp
+                    .checked_add(config.reward_lifetime_seconds)
+                    .ok_or(CarsaError::ArithmeticOverflow)?,
+                bump,
+            });
+        }
+
+        // Add the purchase, and any redemption, to the customer's activity feed
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.activity_record.as_mut(),
+            ctx.bumps.activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: reward_amount,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Purchase,
+                transaction_id,
+                bump,
+            });
+        }
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.redemption_activity_record.as_mut(),
+            ctx.bumps.redemption_activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: redeemed_tokens,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Redemption,
+                transaction_id,
+                bump,
+            });
+        }
+
+        // Add the purchase to the customer's passbook for this month
+        if let (Some(monthly_stats), Some(bump)) = (
+            ctx.accounts.customer_monthly_stats.as_mut(),
+            ctx.bumps.customer_monthly_stats,
+        ) {
+            monthly_stats.customer = record_customer;
+            monthly_stats.year_month = current_year_month()?;
+            monthly_stats.bump = bump;
+            monthly_stats
+                .record_purchase(total_value, redeemed_tokens, reward_amount, clock.unix_timestamp)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+        }
+
         if used_tokens {
-            msg!(
-                "Purchase with token redemption: Customer redeemed {} tokens, paid Rp {} fiat, total value Rp {} IDR, earned {} reward tokens ({}% cashback)",
-                redeemed_tokens as f64 / 1_000_000_000.0, // Convert to display units
+            log_verbose!(
+                "Purchase with token redemption: Customer redeemed {}.{:09} tokens, paid Rp {} fiat, total value Rp {} IDR, earned {}.{:09} reward tokens ({}bps cashback)",
+                redeemed_tokens / 1_000_000_000,
+                redeemed_tokens % 1_000_000_000,
                 fiat_amount,
                 total_value,
-                reward_amount as f64 / 1_000_000_000.0,
-                merchant_account.cashback_rate as f64 / 100.0
+                reward_amount / 1_000_000_000,
+                reward_amount % 1_000_000_000,
+                cashback_rate
             );
         } else {
-            msg!(
-                "Purchase with fiat only: Paid Rp {} IDR, earned {} reward tokens ({}% cashback)",
+            log_verbose!(
+                "Purchase with fiat only: Paid Rp {} IDR, earned {}.{:09} reward tokens ({}bps cashback)",
                 fiat_amount,
-                reward_amount as f64 / 1_000_000_000.0,
-                merchant_account.cashback_rate as f64 / 100.0
+                reward_amount / 1_000_000_000,
+                reward_amount % 1_000_000_000,
+                cashback_rate
+            );
+        }
+
+        if staker_boost_bps > 0 {
+            log_verbose!("Staker cashback boost applied: {}bps", staker_boost_bps);
+        }
+        if streak_bonus > 0 {
+            log_verbose!(
+                "Visit streak completed: {}.{:09} bonus tokens",
+                streak_bonus / 1_000_000_000,
+                streak_bonus % 1_000_000_000
+            );
+        }
+        if category_boost_reward > 0 {
+            log_verbose!(
+                "Category boost applied: {}bps, {}.{:09} tokens",
+                category_boost_bps,
+                category_boost_reward / 1_000_000_000,
+                category_boost_reward % 1_000_000_000
             );
         }
+        if protocol_fee > 0 {
+            log_verbose!(
+                "Protocol fee: {}.{:09} tokens",
+                protocol_fee / 1_000_000_000,
+                protocol_fee % 1_000_000_000
+            );
+        }
+
+        emit_cpi!(PurchaseProcessedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: record_customer,
+            merchant: merchant_account.key(),
+            fiat_amount,
+            redeemed_token_amount: redeemed_tokens,
+            total_value,
+            reward_amount,
+            cashback_rate,
+            staker_boost_bps,
+            category_boost_bps,
+            category_boost_reward,
+            streak_bonus,
+            protocol_fee,
+            redemption_bonus_bps,
+            invoice,
+            transaction_id,
+            pos_nonce: expected_nonce,
+            reference_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        if let Some(receipt_chain_hash) = receipt_chain_hash {
+            emit_cpi!(PurchaseReceiptEvent {
+                sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                merchant: merchant_account.key(),
+                customer: record_customer,
+                fiat_amount,
+                redeemed_token_amount: redeemed_tokens,
+                reward_amount,
+                transaction_id,
+                timestamp: clock.unix_timestamp,
+                receipt_chain_hash,
+            });
+        }
+
+        if let (true, Some(reward_budget)) = (budget_paid, ctx.accounts.reward_budget.as_ref()) {
+            log_verbose!(
+                "Reward paid from budget {}: {} tokens remaining",
+                reward_budget.budget_id,
+                reward_budget.remaining_budget
+            );
+            emit_cpi!(RewardBudgetSpentEvent {
+                sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                reward_budget: reward_budget.key(),
+                merchant: merchant_account.key(),
+                customer: record_customer,
+                amount: merchant_reward,
+                remaining_budget: reward_budget.remaining_budget,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> CloseMerchant<'info> {
+    /// Handler for closing a merchant account
+    pub fn handler(ctx: Context<CloseMerchant>) -> Result<()> {
+        // Tombstone rather than remove, so later indexes keep their positions
+        let merchant_index = &mut ctx.accounts.merchant_index;
+        merchant_index.closed = true;
+
+        log_verbose!(
+            "Merchant closed: {} (index {})",
+            ctx.accounts.merchant_account.key(),
+            merchant_index.index
+        );
 
         Ok(())
     }
 }
 
+impl<'info> PreviewPurchaseReward<'info> {
+    /// Handler for previewing a purchase reward with the same math as `process_purchase`
+    pub fn handler(
+        ctx: Context<PreviewPurchaseReward>,
+        fiat_amount: u64,
+        redeem_token_amount: Option<u64>,
+    ) -> Result<PurchaseRewardPreview> {
+        require!(fiat_amount > 0, CarsaError::InvalidPurchaseAmount);
+        require!(
+            ctx.accounts.config.allows_purchase(fiat_amount),
+            CarsaError::PurchaseAmountTooLarge
+        );
+
+        let merchant_cashback_rate = ctx.accounts.merchant_account.cashback_rate;
+        let cashback_rate = effective_cashback_rate(
+            ctx.program_id,
+            &ctx.accounts.config,
+            merchant_cashback_rate,
+            &ctx.accounts.customer.key(),
+            ctx.accounts.stake_pool.as_ref(),
+            ctx.accounts.customer_stake_record.as_ref(),
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let token_to_idr_rate = current_token_to_idr_rate(
+            &ctx.accounts.config,
+            ctx.accounts.price_feed.as_ref(),
+            now,
+        )?;
+        let (total_value, merchant_reward) = purchase_reward(
+            fiat_amount,
+            redeem_token_amount.unwrap_or(0),
+            cashback_rate,
+            token_to_idr_rate,
+            ctx.accounts.merchant_account.redemption_bonus_bps,
+        )?;
+        require!(
+            total_value >= ctx.accounts.merchant_account.min_purchase_amount,
+            CarsaError::PurchaseBelowMinimum
+        );
+
+        // The boost is only read; the purchase itself charges its budget
+        let (category_boost_bps, category_boost_reward) = category_boost_reward(
+            ctx.accounts.category_boost.as_deref(),
+            &ctx.accounts.merchant_account.category,
+            total_value,
+            token_to_idr_rate,
+            now,
+        )?;
+        let reward_amount = merchant_reward
+            .checked_add(category_boost_reward)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        Ok(PurchaseRewardPreview {
+            total_value,
+            cashback_rate,
+            staker_boost_bps: cashback_rate.saturating_sub(merchant_cashback_rate),
+            reward_amount,
+            category_boost_bps,
+            category_boost_reward,
+        })
+    }
+}
+
 impl<'info> UpdateMerchant<'info> {
     /// Handler for updating merchant settings
     pub fn handler(
         ctx: Context<UpdateMerchant>,
         new_cashback_rate: Option<u16>,
         is_active: Option<bool>,
+        min_purchase_amount: Option<u64>,
+        redemption_bonus_bps: Option<u16>,
+        require_merchant_signature: Option<bool>,
+        compressed_receipts: Option<bool>,
     ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
         let merchant_account = &mut ctx.accounts.merchant_account;
+        let old = MerchantAccount::clone(merchant_account);
 
         // Update cashback rate if provided
-        if let Some(rate) = new_cashback_rate {
+        // Unless the update authority co-signs, rate changes are spaced out and increases
+        // capped, so a merchant cannot spike its rate for a single purchase
+        if let Some(rate) = new_cashback_rate.filter(|&rate| rate != merchant_account.cashback_rate) {
             require!(rate <= 10_000, CarsaError::InvalidCashbackRate);
+            // The protocol cap holds even with the update authority's co-signature
+            require!(config.allows_cashback_rate(rate), CarsaError::CashbackRateAboveMax);
+            let now = Clock::get()?.unix_timestamp;
+            if ctx.accounts.update_authority.is_none() {
+                require!(
+                    config.rate_change_cooled_down(merchant_account.last_rate_change_at, now),
+                    CarsaError::RateChangeCooldown
+                );
+                require!(
+                    config.allows_rate_change(merchant_account.cashback_rate, rate),
+                    CarsaError::RateChangeTooLarge
+                );
+            }
             merchant_account.cashback_rate = rate;
-            msg!("Merchant cashback rate updated to: {}bps", rate);
+            merchant_account.last_rate_change_at = now;
+            log_verbose!("Merchant cashback rate updated to: {}bps", rate);
         }
 
         // Update active status if provided
         if let Some(active) = is_active {
             merchant_account.is_active = active;
-            msg!("Merchant active status updated to: {}", active);
+            log_verbose!("Merchant active status updated to: {}", active);
+        }
+
+        // Update minimum purchase amount if provided
+        if let Some(amount) = min_purchase_amount {
+            merchant_account.min_purchase_amount = amount;
+            log_verbose!("Merchant minimum purchase amount updated to: Rp {}", amount);
+        }
+
+        // Update redemption bonus if provided
+        if let Some(bonus) = redemption_bonus_bps {
+            require!(bonus <= 10_000, CarsaError::InvalidRedemptionBonus);
+            merchant_account.redemption_bonus_bps = bonus;
+            log_verbose!("Merchant redemption bonus updated to: {}bps", bonus);
+        }
+
+        // Update the signed approval requirement if provided
+        if let Some(required) = require_merchant_signature {
+            merchant_account.require_merchant_signature = required;
+            log_verbose!("Merchant signature requirement updated to: {}", required);
         }
 
+        // Update the receipt mode if provided
+        if let Some(compressed) = compressed_receipts {
+            merchant_account.compressed_receipts = compressed;
+            log_verbose!("Merchant compressed receipts updated to: {}", compressed);
+        }
+
+        // Approvals are made single-use by the purchase record, which compressed receipts skip
+        require!(
+            !(merchant_account.require_merchant_signature && merchant_account.compressed_receipts),
+            CarsaError::CompressedReceiptsSignatureConflict
+        );
+
+        emit_cpi!(MerchantUpdatedEvent::between(
+            config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant_account.key(),
+            &old,
+            merchant_account,
+            Clock::get()?.unix_timestamp,
+        ));
+        if merchant_account.cashback_rate != old.cashback_rate
+            && config.is_high_cashback_rate(merchant_account.cashback_rate)
+        {
+            emit_cpi!(HighCashbackAlertEvent {
+                sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                merchant: merchant_account.key(),
+                cashback_rate: merchant_account.cashback_rate,
+                alert_threshold_bps: config.alert_threshold_bps,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> SetPayoutAccount<'info> {
+    /// Handler for changing a merchant's payout account
+    pub fn handler(ctx: Context<SetPayoutAccount>) -> Result<()> {
+        let clock = Clock::get()?;
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let old_payout_token_account = merchant_account.payout_token_account;
+        merchant_account.payout_token_account = ctx.accounts.payout_token_account.key();
+
+        log_verbose!(
+            "Merchant {} payout account set to {}",
+            merchant_account.key(),
+            merchant_account.payout_token_account
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(PayoutAccountSetEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            payout_token_account: Changed {
+                old: old_payout_token_account,
+                new: merchant_account.payout_token_account,
+            },
+            timestamp: clock.unix_timestamp,
+        });
+
         Ok(())
     }
 }
+
+#[event]
+pub struct PayoutAccountSetEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    /// Old value is the default pubkey if the merchant had none set
+    pub payout_token_account: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// Every setting `update_merchant` can change, whether or not this update touched it
+#[event]
+pub struct MerchantUpdatedEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub cashback_rate: Changed<u16>,
+    pub is_active: Changed<bool>,
+    pub min_purchase_amount: Changed<u64>,
+    pub redemption_bonus_bps: Changed<u16>,
+    pub require_merchant_signature: Changed<bool>,
+    pub compressed_receipts: Changed<bool>,
+    pub timestamp: i64,
+}
+
+impl MerchantUpdatedEvent {
+    /// Event for a merchant whose settings went from `old` to `new`
+    pub(crate) fn between(
+        sequence: u64,
+        merchant: Pubkey,
+        old: &MerchantAccount,
+        new: &MerchantAccount,
+        timestamp: i64,
+    ) -> Self {
+        Self {
+            sequence,
+            merchant,
+            cashback_rate: Changed { old: old.cashback_rate, new: new.cashback_rate },
+            is_active: Changed { old: old.is_active, new: new.is_active },
+            min_purchase_amount: Changed {
+                old: old.min_purchase_amount,
+                new: new.min_purchase_amount,
+            },
+            redemption_bonus_bps: Changed {
+                old: old.redemption_bonus_bps,
+                new: new.redemption_bonus_bps,
+            },
+            require_merchant_signature: Changed {
+                old: old.require_merchant_signature,
+                new: new.require_merchant_signature,
+            },
+            compressed_receipts: Changed {
+                old: old.compressed_receipts,
+                new: new.compressed_receipts,
+            },
+            timestamp,
+        }
+    }
+}
+
+#[event]
+pub struct PurchaseProcessedEvent {
+    pub sequence: u64,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub fiat_amount: u64,
+    pub redeemed_token_amount: u64,
+    pub total_value: u64,
+    /// Reward tokens paid, including any category boost
+    pub reward_amount: u64,
+    /// Cashback rate applied, including any staker boost but not the category boost
+    pub cashback_rate: u16,
+    pub staker_boost_bps: u16,
+    /// The protocol's category boost rate (0 when no boost applied)
+    pub category_boost_bps: u16,
+    /// Part of `reward_amount` minted by the category boost
+    pub category_boost_reward: u64,
+    /// Merchant's streak bonus minted on top of `reward_amount` (0 if no streak completed)
+    pub streak_bonus: u64,
+    /// Part of the redeemed tokens sent to the treasury
+    pub protocol_fee: u64,
+    /// Merchant's premium on the redeemed tokens' value (0 when none were redeemed)
+    pub redemption_bonus_bps: u16,
+    /// The invoice paid (default pubkey if not paid by invoice)
+    pub invoice: Pubkey,
+    pub transaction_id: [u8; 32],
+    /// The merchant's POS nonce the purchase used (None if it passed none)
+    pub pos_nonce: Option<u64>,
+    /// Code `ReferenceIndex` finds the purchase by (None when no record was written)
+    pub reference_code: Option<[u8; 8]>,
+    pub timestamp: i64,
+}
+
+/// A merchant's cashback rate was set above the config's alert threshold
+#[event]
+pub struct HighCashbackAlertEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub cashback_rate: u16,
+    pub alert_threshold_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurchaseReceiptEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub customer: Pubkey,
+    pub fiat_amount: u64,
+    pub redeemed_token_amount: u64,
+    pub reward_amount: u64,
+    pub transaction_id: [u8; 32],
+    pub timestamp: i64,
+    /// The merchant's receipt chain hash after this receipt
+    pub receipt_chain_hash: [u8; 32],
+}
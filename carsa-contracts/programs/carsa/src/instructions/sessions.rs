@@ -0,0 +1,777 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::instructions::rewards::{
+    current_token_to_idr_rate, effective_cashback_rate, purchase_reward,
+    PurchaseProcessedEvent,
+};
+use super::franchises::roll_up_purchase;
+use super::pos_keys::check_merchant_signature;
+use super::mint_tokens::record_reward_mint;
+
+/// Authorize an ephemeral key to make purchases for the customer, escrowing the tokens
+/// it may redeem
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(session_signer: Pubkey)]
+pub struct CreateSessionKey<'info> {
+    /// The customer authorizing the session, paying for the session account
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// The customer's token account the allowance is escrowed from
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding session allowances until spent or revoked
+    #[account(
+        init_if_needed,
+        payer = customer,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [SESSION_ESCROW_SEED],
+        bump,
+    )]
+    pub session_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The session to be created
+    #[account(
+        init,
+        payer = customer,
+        space = SessionKey::LEN,
+        seeds = [SESSION_KEY_SEED, customer.key().as_ref(), session_signer.as_ref()],
+        bump,
+    )]
+    pub session_key: Account<'info, SessionKey>,
+
+    /// SPL Token program for the transfer into escrow
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Make a purchase for the customer with the session key's signature instead of theirs
+/// Works like `process_purchase`, except redeemed tokens come out of the session's
+/// escrowed allowance
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(fiat_amount: u64, redemption: Option<RedemptionParams>, transaction_id: [u8; 32])]
+pub struct ProcessPurchaseWithSession<'info> {
+    /// The session's ephemeral key, paying for the purchase records
+    #[account(mut)]
+    pub session_signer: Signer<'info>,
+
+    /// The session the signer belongs to
+    #[account(
+        mut,
+        seeds = [SESSION_KEY_SEED, session_key.customer.as_ref(), session_signer.key().as_ref()],
+        bump = session_key.bump,
+    )]
+    pub session_key: Account<'info, SessionKey>,
+
+    /// The merchant account receiving the purchase
+    #[account(
+        mut,
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The customer's token account receiving the reward
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = customer_token_account.owner == session_key.customer @ CarsaError::InvalidOwner
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The merchant's token account receiving redeemed tokens
+    #[account(
+        mut,
+        constraint = merchant_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account holding session allowances until spent or revoked
+    #[account(
+        mut,
+        seeds = [SESSION_ESCROW_SEED],
+        bump,
+    )]
+    pub session_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Purchase transaction record for tracking, keyed by merchant and customer like any purchase
+    #[account(
+        init,
+        payer = session_signer,
+        space = PurchaseTransaction::LEN,
+        seeds = [
+            TRANSACTION_SEED,
+            merchant_account.key().as_ref(),
+            config.record_customer(&session_key.customer).as_ref(),
+            &transaction_id
+        ],
+        bump,
+    )]
+    pub transaction_record: Account<'info, PurchaseTransaction>,
+
+    /// SPL Token program for the transfers and reward mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// Pool 0 of the Lokal mint, the original voucher pool, whose stakers earn the cashback boost
+    /// Only needed together with `customer_stake_record`
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            stake_pool.load()?.mint_seed().as_ref(),
+            pool_id_seed(0).as_ref()
+        ],
+        bump = stake_pool.load()?.bump,
+        constraint = stake_pool.load()?.voucher_mint == config.mint @ CarsaError::MintMismatch,
+    )]
+    pub stake_pool: Option<AccountLoader<'info, PoolState>>,
+
+    /// The customer's stake record in `stake_pool`, to claim the staker boost
+    /// Its address is checked against the pool and customer in the handler
+    pub customer_stake_record: Option<Account<'info, UserStakeRecord>>,
+
+    /// Activity feed entry for the purchase, required only when activity recording is enabled
+    #[account(
+        init,
+        payer = session_signer,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&session_key.customer).as_ref(),
+            &transaction_id,
+            &[ActivityKind::Purchase as u8]
+        ],
+        bump,
+    )]
+    pub activity_record: Option<Account<'info, ActivityRecord>>,
+
+    /// Activity feed entry for the redeemed tokens, required only when activity recording
+    /// is enabled and the purchase redeems tokens
+    #[account(
+        init,
+        payer = session_signer,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            config.record_customer(&session_key.customer).as_ref(),
+            &transaction_id,
+            &[ActivityKind::Redemption as u8]
+        ],
+        bump,
+    )]
+    pub redemption_activity_record: Option<Account<'info, ActivityRecord>>,
+
+    /// The configured treasury token account, required when a redemption owes a protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The configured Pyth price account, required when the config sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Escrow receiving the reward, required when expiring rewards are enabled
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Lot tracking the escrowed reward, required when expiring rewards are enabled
+    #[account(
+        init,
+        payer = session_signer,
+        space = RewardLot::LEN,
+        seeds = [REWARD_LOT_SEED, config.record_customer(&session_key.customer).as_ref(), &transaction_id],
+        bump,
+    )]
+    pub reward_lot: Option<Account<'info, RewardLot>>,
+
+    /// The instructions sysvar, required when the merchant requires signed approvals
+    /// CHECK: Address is checked; read through instruction introspection
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+
+    /// The POS key that approved the purchase, when it was not the merchant wallet
+    #[account(
+        seeds = [POS_KEY_SEED, merchant_account.key().as_ref(), pos_key.pos_key.as_ref()],
+        bump = pos_key.bump,
+    )]
+    pub pos_key: Option<Account<'info, PosKey>>,
+
+    /// The merchant's franchise parent, required when the merchant has one
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, parent_merchant.owner.as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Option<Account<'info, ParentMerchant>>,
+
+    /// The operator that approved the purchase, when it was not the merchant wallet
+    #[account(
+        seeds = [MERCHANT_OPERATOR_SEED, merchant_account.key().as_ref(), merchant_operator.operator.as_ref()],
+        bump = merchant_operator.bump,
+    )]
+    pub merchant_operator: Option<Account<'info, MerchantOperator>>,
+}
+
+/// End a session immediately, returning its unspent allowance to the customer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeSessionKey<'info> {
+    /// The customer that authorized the session
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// The customer's token account receiving the unspent allowance
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding session allowances until spent or revoked
+    #[account(
+        mut,
+        seeds = [SESSION_ESCROW_SEED],
+        bump,
+    )]
+    pub session_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The session being revoked, closed to the customer
+    #[account(
+        mut,
+        close = customer,
+        seeds = [SESSION_KEY_SEED, customer.key().as_ref(), session_key.session_signer.as_ref()],
+        bump = session_key.bump,
+    )]
+    pub session_key: Account<'info, SessionKey>,
+
+    /// SPL Token program for the transfer out of escrow
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CreateSessionKey<'info> {
+    /// Handler for authorizing a session key
+    pub fn handler(
+        ctx: Context<CreateSessionKey>,
+        session_signer: Pubkey,
+        spend_limit: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(expires_at > clock.unix_timestamp, CarsaError::InvalidSessionKey);
+
+        // A zero limit makes a fiat-only session that earns rewards but redeems nothing
+        if spend_limit > 0 {
+            require!(
+                ctx.accounts.customer_token_account.amount >= spend_limit,
+                CarsaError::InsufficientBalance
+            );
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.customer_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.session_escrow.to_account_info(),
+                authority: ctx.accounts.customer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token_interface::transfer_checked(
+                cpi_ctx,
+                spend_limit,
+                ctx.accounts.config.decimals(),
+            )?;
+        }
+
+        let customer = ctx.accounts.customer.key();
+        let session_key = &mut ctx.accounts.session_key;
+        session_key.set_inner(SessionKey {
+            customer,
+            session_signer,
+            spend_limit,
+            spent: 0,
+            expires_at,
+            created_at: clock.unix_timestamp,
+            bump: ctx.bumps.session_key,
+        });
+
+        log_verbose!(
+            "Session key {} authorized: {} tokens, expires at {}",
+            session_signer,
+            spend_limit,
+            expires_at
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(SessionKeyCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            session_key: session_key.key(),
+            customer,
+            session_signer,
+            spend_limit,
+            expires_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ProcessPurchaseWithSession<'info> {
+    /// Handler for a purchase signed by a session key
+    pub fn handler(
+        ctx: Context<ProcessPurchaseWithSession>,
+        fiat_amount: u64,
+        redemption: Option<RedemptionParams>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        require!(fiat_amount > 0, CarsaError::InvalidPurchaseAmount);
+        require!(
+            ctx.accounts.config.allows_purchase(fiat_amount),
+            CarsaError::PurchaseAmountTooLarge
+        );
+
+        let clock = Clock::get()?;
+        let session_key = &mut ctx.accounts.session_key;
+        require!(!session_key.is_expired(clock.unix_timestamp), CarsaError::SessionKeyExpired);
+
+        let redeemed_tokens = redemption.map_or(0, |redemption| redemption.token_amount);
+        require!(
+            redeemed_tokens <= session_key.remaining_allowance(),
+            CarsaError::SessionAllowanceExceeded
+        );
+        let customer_key = session_key.customer;
+
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let config = &mut ctx.accounts.config;
+        let transaction_record = &mut ctx.accounts.transaction_record;
+        let record_customer = config.record_customer(&customer_key);
+
+        let cashback_rate = effective_cashback_rate(
+            ctx.program_id,
+            config,
+            merchant_account.cashback_rate,
+            &customer_key,
+            ctx.accounts.stake_pool.as_ref(),
+            ctx.accounts.customer_stake_record.as_ref(),
+        )?;
+        let staker_boost_bps = cashback_rate.saturating_sub(merchant_account.cashback_rate);
+
+        if merchant_account.require_merchant_signature {
+            check_merchant_signature(
+                merchant_account,
+                ctx.accounts.pos_key.as_ref(),
+                ctx.accounts.merchant_operator.as_ref(),
+                ctx.accounts.instructions_sysvar.as_ref(),
+                &customer_key,
+                fiat_amount,
+                &transaction_id,
+            )?;
+        }
+
+        let used_tokens = redeemed_tokens > 0;
+        let redemption_bonus_bps = if used_tokens {
+            merchant_account.redemption_bonus_bps
+        } else {
+            0
+        };
+
+        require!(
+            config.record_activity == ctx.accounts.activity_record.is_some()
+                && (config.record_activity && used_tokens)
+                    == ctx.accounts.redemption_activity_record.is_some(),
+            CarsaError::InvalidActivityRecord
+        );
+        require!(
+            config.expiring_rewards == ctx.accounts.reward_escrow.is_some()
+                && config.expiring_rewards == ctx.accounts.reward_lot.is_some(),
+            CarsaError::InvalidRewardLot
+        );
+
+        let token_to_idr_rate = current_token_to_idr_rate(
+            config,
+            ctx.accounts.price_feed.as_ref(),
+            clock.unix_timestamp,
+        )?;
+        let (total_value, reward_amount) = purchase_reward(
+            fiat_amount,
+            redeemed_tokens,
+            cashback_rate,
+            token_to_idr_rate,
+            redemption_bonus_bps,
+        )?;
+        require!(
+            total_value >= merchant_account.min_purchase_amount,
+            CarsaError::PurchaseBelowMinimum
+        );
+
+        let protocol_fee = config
+            .protocol_fee(redeemed_tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        // Redeemed tokens come out of the session's escrowed allowance
+        if protocol_fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(CarsaError::InvalidTreasuryAccount)?;
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.session_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(cpi_ctx, protocol_fee, config.decimals())?;
+        }
+        if redeemed_tokens > protocol_fee {
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.session_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.merchant_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(
+                cpi_ctx,
+                redeemed_tokens - protocol_fee,
+                config.decimals(),
+            )?;
+        }
+        session_key.spent += redeemed_tokens;
+
+        // Rewards go to the customer, never the session key
+        if reward_amount > 0 {
+            record_reward_mint(config, reward_amount)?;
+
+            // Expiring rewards are held in escrow until withdrawn
+            let reward_destination = match ctx.accounts.reward_escrow.as_ref() {
+                Some(reward_escrow) => reward_escrow.to_account_info(),
+                None => ctx.accounts.customer_token_account.to_account_info(),
+            };
+            let cpi_accounts = token_interface::MintTo {
+                mint: ctx.accounts.mint.to_account_info(),
+                to: reward_destination,
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::mint_to(cpi_ctx, reward_amount)?;
+        }
+
+        config.total_purchases = config
+            .total_purchases
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_transactions = merchant_account
+            .total_transactions
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_volume = merchant_account
+            .total_volume
+            .checked_add(total_value)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.total_rewards_distributed = merchant_account
+            .total_rewards_distributed
+            .checked_add(reward_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        merchant_account.unsettled_tokens = merchant_account
+            .unsettled_tokens
+            .checked_add(redeemed_tokens - protocol_fee)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        roll_up_purchase(
+            merchant_account,
+            ctx.accounts.parent_merchant.as_mut(),
+            total_value,
+            reward_amount,
+        )?;
+
+        transaction_record.customer = record_customer;
+        transaction_record.merchant = merchant_account.key();
+        transaction_record.fiat_amount = fiat_amount;
+        transaction_record.redeemed_token_amount = redeemed_tokens;
+        transaction_record.total_value = total_value;
+        transaction_record.reward_amount = reward_amount;
+        transaction_record.cashback_rate = cashback_rate;
+        transaction_record.used_tokens = used_tokens;
+        transaction_record.timestamp = clock.unix_timestamp;
+        transaction_record.transaction_id = transaction_id;
+        transaction_record.bump = ctx.bumps.transaction_record;
+        transaction_record.staker_boost_bps = staker_boost_bps;
+        transaction_record.protocol_fee = protocol_fee;
+        transaction_record.redemption_bonus_bps = redemption_bonus_bps;
+        transaction_record.invoice = Pubkey::default();
+
+        if let (Some(reward_lot), Some(bump)) = (
+            ctx.accounts.reward_lot.as_mut(),
+            ctx.bumps.reward_lot,
+        ) {
+            reward_lot.set_inner(RewardLot {
+                customer: record_customer,
+                transaction_id,
+                amount: reward_amount,
+                expired_amount: 0,
+                minted_at: clock.unix_timestamp,
+                expires_at: clock
+                    .unix_timestamp
+                    .checked_add(config.reward_lifetime_seconds)
+                    .ok_or(CarsaError::ArithmeticOverflow)?,
+                bump,
+            });
+        }
+
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.activity_record.as_mut(),
+            ctx.bumps.activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: reward_amount,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Purchase,
+                transaction_id,
+                bump,
+            });
+        }
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.redemption_activity_record.as_mut(),
+            ctx.bumps.redemption_activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: record_customer,
+                counterparty: merchant_account.key(),
+                amount: redeemed_tokens,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Redemption,
+                transaction_id,
+                bump,
+            });
+        }
+
+        log_verbose!(
+            "Session purchase: redeemed {} tokens and paid Rp {} fiat, total value Rp {} IDR, earned {} reward tokens, {} tokens left in session",
+            redeemed_tokens,
+            fiat_amount,
+            total_value,
+            reward_amount,
+            session_key.remaining_allowance()
+        );
+
+        emit_cpi!(PurchaseProcessedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            customer: record_customer,
+            merchant: merchant_account.key(),
+            fiat_amount,
+            redeemed_token_amount: redeemed_tokens,
+            total_value,
+            reward_amount,
+            cashback_rate,
+            staker_boost_bps,
+            category_boost_bps: 0,
+            category_boost_reward: 0,
+            streak_bonus: 0,
+            protocol_fee,
+            redemption_bonus_bps,
+            invoice: Pubkey::default(),
+            transaction_id,
+            pos_nonce: None,
+            reference_code: None,
+            timestamp: clock.unix_timestamp,
+        });
+        emit_cpi!(SessionKeyUsedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            session_key: session_key.key(),
+            customer: customer_key,
+            session_signer: session_key.session_signer,
+            redeemed_token_amount: redeemed_tokens,
+            spent: session_key.spent,
+            transaction_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RevokeSessionKey<'info> {
+    /// Handler for revoking a session key
+    pub fn handler(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        let clock = Clock::get()?;
+        let session_key = &ctx.accounts.session_key;
+        let returned_amount = session_key.remaining_allowance();
+
+        let config = &mut ctx.accounts.config;
+        if returned_amount > 0 {
+            let authority_seeds = &[
+                MINT_AUTHORITY_SEED,
+                &[config.mint_authority_bump],
+            ];
+            let signer_seeds = &[&authority_seeds[..]];
+
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.session_escrow.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.customer_token_account.to_account_info(),
+                authority: ctx.accounts.mint_authority.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+            token_interface::transfer_checked(
+                cpi_ctx,
+                returned_amount,
+                config.decimals(),
+            )?;
+        }
+
+        log_verbose!(
+            "Session key {} revoked: {} tokens returned",
+            session_key.session_signer,
+            returned_amount
+        );
+
+        emit_cpi!(SessionKeyRevokedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            session_key: session_key.key(),
+            customer: session_key.customer,
+            session_signer: session_key.session_signer,
+            spent: session_key.spent,
+            returned_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SessionKeyCreatedEvent {
+    pub sequence: u64,
+    pub session_key: Pubkey,
+    pub customer: Pubkey,
+    pub session_signer: Pubkey,
+    pub spend_limit: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyUsedEvent {
+    pub sequence: u64,
+    pub session_key: Pubkey,
+    pub customer: Pubkey,
+    pub session_signer: Pubkey,
+    pub redeemed_token_amount: u64,
+    /// Tokens redeemed through the session so far, including this purchase
+    pub spent: u64,
+    pub transaction_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionKeyRevokedEvent {
+    pub sequence: u64,
+    pub session_key: Pubkey,
+    pub customer: Pubkey,
+    pub session_signer: Pubkey,
+    pub spent: u64,
+    /// Unspent allowance returned to the customer
+    pub returned_amount: u64,
+    pub timestamp: i64,
+}
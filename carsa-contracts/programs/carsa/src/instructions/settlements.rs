@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::instructions::rewards::current_token_to_idr_rate;
+
+/// Burn redeemed tokens the merchant holds and record the IDR owed for them, paid out
+/// off-chain by bank transfer
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(period_id: u64)]
+pub struct SettleMerchant<'info> {
+    /// The merchant's owner wallet, paying for the settlement record
+    #[account(mut)]
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant settling its redeemed tokens
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The merchant's token account the settled tokens are burned from
+    #[account(
+        mut,
+        constraint = merchant_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = merchant_token_account.owner == merchant_owner.key() @ CarsaError::InvalidOwner
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Record of this settlement, at most one per merchant and period
+    #[account(
+        init,
+        payer = merchant_owner,
+        space = SettlementRecord::LEN,
+        seeds = [SETTLEMENT_SEED, merchant_account.key().as_ref(), &period_id.to_le_bytes()],
+        bump,
+    )]
+    pub settlement_record: Account<'info, SettlementRecord>,
+
+    /// SPL Token program for the burn
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// The configured Pyth price account, required when the config sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> SettleMerchant<'info> {
+    /// Handler for settling a merchant's redeemed tokens
+    pub fn handler(ctx: Context<SettleMerchant>, period_id: u64, token_amount: u64) -> Result<()> {
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        require!(
+            token_amount > 0 && token_amount <= merchant_account.unsettled_tokens,
+            CarsaError::InvalidSettlementAmount
+        );
+
+        let clock = Clock::get()?;
+        let config = &mut ctx.accounts.config;
+        let token_to_idr_rate = current_token_to_idr_rate(
+            config,
+            ctx.accounts.price_feed.as_ref(),
+            clock.unix_timestamp,
+        )?;
+
+        // Valued to the smallest token unit, rounded down
+        let idr_value = (token_amount as u128)
+            .checked_mul(token_to_idr_rate as u128)
+            .ok_or(CarsaError::ArithmeticOverflow)?
+            .checked_div(1_000_000_000u128)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let idr_value = u64::try_from(idr_value).map_err(|_| CarsaError::ArithmeticOverflow)?;
+
+        let cpi_accounts = token_interface::Burn {
+            mint: ctx.accounts.mint.to_account_info(),
+            from: ctx.accounts.merchant_token_account.to_account_info(),
+            authority: ctx.accounts.merchant_owner.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token_interface::burn(CpiContext::new(cpi_program, cpi_accounts), token_amount)?;
+        config.total_burned = config
+            .total_burned
+            .checked_add(token_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        merchant_account.unsettled_tokens -= token_amount;
+
+        let settlement_record = &mut ctx.accounts.settlement_record;
+        settlement_record.set_inner(SettlementRecord {
+            merchant: merchant_account.key(),
+            period_id,
+            token_amount,
+            idr_value,
+            token_to_idr_rate,
+            timestamp: clock.unix_timestamp,
+            bump: ctx.bumps.settlement_record,
+        });
+
+        log_verbose!(
+            "Merchant {} settled period {}: burned {} tokens for Rp {} IDR",
+            merchant_account.key(),
+            period_id,
+            token_amount,
+            idr_value
+        );
+
+        emit_cpi!(MerchantSettledEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            settlement: settlement_record.key(),
+            period_id,
+            token_amount,
+            idr_value,
+            token_to_idr_rate,
+            unsettled_tokens: merchant_account.unsettled_tokens,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct MerchantSettledEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub settlement: Pubkey,
+    pub period_id: u64,
+    pub token_amount: u64,
+    /// IDR owed to the merchant for the burned tokens
+    pub idr_value: u64,
+    pub token_to_idr_rate: u64,
+    /// Tokens still awaiting settlement afterwards
+    pub unsettled_tokens: u64,
+    pub timestamp: i64,
+}
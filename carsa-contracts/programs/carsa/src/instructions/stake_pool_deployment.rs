@@ -0,0 +1,597 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use crate::error::CarsaError;
+use crate::stake_pool::{
+    deposit_sol_instruction, withdraw_sol_instruction, StakePoolAccounts, StakePoolInfo,
+    STAKE_PROGRAM_ID,
+};
+use crate::state::*;
+
+/// Point a pool's deployed capital at an SPL stake pool
+/// Creates the deployment record and the token account that will hold the pool tokens
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ConfigureStakePool<'info> {
+    /// The pool authority (admin), pays for the new accounts
+    #[account(mut)]
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The pool's deployment record
+    #[account(
+        init,
+        payer = pool_authority,
+        space = StakePoolDeployment::LEN,
+        seeds = [STAKE_POOL_DEPLOYMENT_SEED, pool_state.key().as_ref()],
+        bump
+    )]
+    pub stake_pool_deployment: Account<'info, StakePoolDeployment>,
+
+    /// The SPL stake-pool program
+    /// CHECK: Any program; the stake pool must be owned by it
+    #[account(executable)]
+    pub stake_pool_program: UncheckedAccount<'info>,
+
+    /// The stake pool to deposit into
+    /// CHECK: Owner is verified here; the layout is decoded by the handler
+    #[account(
+        owner = stake_pool_program.key() @ CarsaError::InvalidStakePool
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// The stake pool's token mint
+    pub pool_token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token account holding the pool tokens, owned by the deployment record
+    #[account(
+        init,
+        payer = pool_authority,
+        token::mint = pool_token_mint,
+        token::authority = stake_pool_deployment,
+        seeds = [STAKE_POOL_TOKENS_SEED, pool_state.key().as_ref()],
+        bump
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// System account holding SOL waiting to be deployed
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [POOL_SOL_VAULT_SEED, pool_state.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: UncheckedAccount<'info>,
+
+    /// Token program owning the stake pool's mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Deposit SOL from the pool's SOL vault into its stake pool
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DeployToStakePool<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The pool's deployment record
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_DEPLOYMENT_SEED, pool_state.key().as_ref()],
+        bump = stake_pool_deployment.bump
+    )]
+    pub stake_pool_deployment: Account<'info, StakePoolDeployment>,
+
+    /// System account holding SOL waiting to be deployed, signs the deposit
+    #[account(
+        mut,
+        seeds = [POOL_SOL_VAULT_SEED, pool_state.key().as_ref()],
+        bump = stake_pool_deployment.sol_vault_bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// The SPL stake-pool program
+    /// CHECK: Must be the configured program
+    #[account(
+        address = stake_pool_deployment.stake_pool_program @ CarsaError::InvalidStakePool
+    )]
+    pub stake_pool_program: UncheckedAccount<'info>,
+
+    /// The stake pool
+    /// CHECK: Must be the configured stake pool
+    #[account(
+        mut,
+        address = stake_pool_deployment.stake_pool @ CarsaError::InvalidStakePool
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// The stake pool's withdraw authority
+    /// CHECK: Verified against the stake pool by the handler
+    pub stake_pool_withdraw_authority: UncheckedAccount<'info>,
+
+    /// The stake pool's reserve stake account
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+
+    /// The stake pool manager's fee account
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+
+    /// The stake pool's token mint
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub pool_token_mint: UncheckedAccount<'info>,
+
+    /// Token account holding the pool tokens
+    #[account(
+        mut,
+        address = stake_pool_deployment.pool_token_account @ CarsaError::InvalidStakePool
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program owning the stake pool's mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program, which moves the deposited lamports
+    pub system_program: Program<'info, System>,
+}
+
+/// Redeem pool tokens for SOL, paid back into the pool's SOL vault
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UnwindFromStakePool<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The pool's deployment record, signs for the pool tokens it owns
+    #[account(
+        mut,
+        seeds = [STAKE_POOL_DEPLOYMENT_SEED, pool_state.key().as_ref()],
+        bump = stake_pool_deployment.bump
+    )]
+    pub stake_pool_deployment: Account<'info, StakePoolDeployment>,
+
+    /// System account receiving the withdrawn SOL
+    #[account(
+        mut,
+        seeds = [POOL_SOL_VAULT_SEED, pool_state.key().as_ref()],
+        bump = stake_pool_deployment.sol_vault_bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// The SPL stake-pool program
+    /// CHECK: Must be the configured program
+    #[account(
+        address = stake_pool_deployment.stake_pool_program @ CarsaError::InvalidStakePool
+    )]
+    pub stake_pool_program: UncheckedAccount<'info>,
+
+    /// The stake pool
+    /// CHECK: Must be the configured stake pool
+    #[account(
+        mut,
+        address = stake_pool_deployment.stake_pool @ CarsaError::InvalidStakePool
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// The stake pool's withdraw authority
+    /// CHECK: Verified against the stake pool by the handler
+    pub stake_pool_withdraw_authority: UncheckedAccount<'info>,
+
+    /// The stake pool's reserve stake account
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub reserve_stake: UncheckedAccount<'info>,
+
+    /// The stake pool manager's fee account
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub manager_fee_account: UncheckedAccount<'info>,
+
+    /// The stake pool's token mint
+    /// CHECK: Verified against the stake pool by the handler
+    #[account(mut)]
+    pub pool_token_mint: UncheckedAccount<'info>,
+
+    /// Token account holding the pool tokens
+    #[account(
+        mut,
+        address = stake_pool_deployment.pool_token_account @ CarsaError::InvalidStakePool
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Clock sysvar, read by the stake program
+    pub clock: Sysvar<'info, Clock>,
+
+    /// Stake history sysvar, read by the stake program
+    /// CHECK: Address is verified
+    #[account(address = anchor_lang::solana_program::sysvar::stake_history::ID)]
+    pub stake_history: UncheckedAccount<'info>,
+
+    /// The native stake program, which pays out of the reserve
+    /// CHECK: Address is verified
+    #[account(address = STAKE_PROGRAM_ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// Token program owning the stake pool's mint
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Decode the stake pool and check the accounts passed for its roles
+fn verified_stake_pool(
+    stake_pool_program: &UncheckedAccount,
+    stake_pool: &UncheckedAccount,
+    withdraw_authority: &UncheckedAccount,
+    reserve_stake: &UncheckedAccount,
+    manager_fee_account: &UncheckedAccount,
+    pool_token_mint: &UncheckedAccount,
+    token_program: &Interface<TokenInterface>,
+) -> Result<StakePoolAccounts> {
+    let info = stake_pool_info(stake_pool)?;
+    let expected_withdraw_authority = info
+        .withdraw_authority(stake_pool_program.key, stake_pool.key)
+        .ok_or(CarsaError::InvalidStakePool)?;
+    require!(
+        withdraw_authority.key() == expected_withdraw_authority
+            && reserve_stake.key() == info.reserve_stake
+            && manager_fee_account.key() == info.manager_fee_account
+            && pool_token_mint.key() == info.pool_mint
+            && token_program.key() == info.token_program_id,
+        CarsaError::InvalidStakePool
+    );
+
+    Ok(StakePoolAccounts {
+        program_id: stake_pool_program.key(),
+        stake_pool: stake_pool.key(),
+        withdraw_authority: expected_withdraw_authority,
+        reserve_stake: info.reserve_stake,
+        manager_fee_account: info.manager_fee_account,
+        pool_mint: info.pool_mint,
+        token_program: info.token_program_id,
+    })
+}
+
+fn stake_pool_info(stake_pool: &UncheckedAccount) -> Result<StakePoolInfo> {
+    let data = stake_pool.try_borrow_data()?;
+    StakePoolInfo::from_account_data(&data).ok_or_else(|| error!(CarsaError::InvalidStakePool))
+}
+
+/// Lamports the deployment's pool tokens are worth at the stake pool's current rate
+fn position_value(stake_pool: &UncheckedAccount, deployment: &StakePoolDeployment) -> Result<u64> {
+    stake_pool_info(stake_pool)?
+        .lamports_for_pool_tokens(deployment.pool_tokens)
+        .ok_or_else(|| error!(CarsaError::ArithmeticOverflow))
+}
+
+impl ConfigureStakePool<'_> {
+    /// Handler for configuring a pool's stake pool
+    pub fn handler(ctx: Context<ConfigureStakePool>) -> Result<()> {
+        let info = stake_pool_info(&ctx.accounts.stake_pool)?;
+        require!(
+            ctx.accounts.pool_token_mint.key() == info.pool_mint
+                && ctx.accounts.token_program.key() == info.token_program_id,
+            CarsaError::InvalidStakePool
+        );
+
+        let deployment = &mut ctx.accounts.stake_pool_deployment;
+        deployment.set_inner(StakePoolDeployment {
+            pool: ctx.accounts.pool_state.key(),
+            stake_pool_program: ctx.accounts.stake_pool_program.key(),
+            stake_pool: ctx.accounts.stake_pool.key(),
+            pool_token_account: ctx.accounts.pool_token_account.key(),
+            deployed_lamports: 0,
+            pool_tokens: 0,
+            total_deposited_lamports: 0,
+            total_withdrawn_lamports: 0,
+            realized_pnl_lamports: 0,
+            sol_vault_bump: ctx.bumps.sol_vault,
+            bump: ctx.bumps.stake_pool_deployment,
+        });
+
+        log_verbose!("Pool deploys into stake pool {}", deployment.stake_pool);
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        emit_cpi!(StakePoolConfiguredEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: deployment.pool,
+            stake_pool_program: deployment.stake_pool_program,
+            stake_pool: deployment.stake_pool,
+            sol_vault: ctx.accounts.sol_vault.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl DeployToStakePool<'_> {
+    /// Handler for depositing pool SOL into the stake pool
+    pub fn handler(ctx: Context<DeployToStakePool>, lamports: u64) -> Result<()> {
+        require!(lamports > 0, CarsaError::InvalidAmount);
+        require!(
+            ctx.accounts.sol_vault.lamports() >= lamports,
+            CarsaError::InsufficientPoolSol
+        );
+
+        let stake_pool_accounts = verified_stake_pool(
+            &ctx.accounts.stake_pool_program,
+            &ctx.accounts.stake_pool,
+            &ctx.accounts.stake_pool_withdraw_authority,
+            &ctx.accounts.reserve_stake,
+            &ctx.accounts.manager_fee_account,
+            &ctx.accounts.pool_token_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        let pool_key = ctx.accounts.pool_state.key();
+        let sol_vault_seeds: &[&[u8]] = &[
+            POOL_SOL_VAULT_SEED,
+            pool_key.as_ref(),
+            &[ctx.accounts.stake_pool_deployment.sol_vault_bump],
+        ];
+
+        // Pool tokens are counted from the balance change, which includes the referral fee
+        // the stake pool pays back to the same account
+        let tokens_before = ctx.accounts.pool_token_account.amount;
+        let instruction = deposit_sol_instruction(
+            &stake_pool_accounts,
+            ctx.accounts.sol_vault.key,
+            &ctx.accounts.pool_token_account.key(),
+            lamports,
+        );
+        invoke_signed(
+            &instruction,
+            &[
+                ctx.accounts.stake_pool.to_account_info(),
+                ctx.accounts.stake_pool_withdraw_authority.to_account_info(),
+                ctx.accounts.reserve_stake.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.pool_token_account.to_account_info(),
+                ctx.accounts.manager_fee_account.to_account_info(),
+                ctx.accounts.pool_token_mint.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.stake_pool_program.to_account_info(),
+            ],
+            &[sol_vault_seeds],
+        )?;
+        ctx.accounts.pool_token_account.reload()?;
+        let pool_tokens_minted = ctx
+            .accounts
+            .pool_token_account
+            .amount
+            .checked_sub(tokens_before)
+            .ok_or(CarsaError::StateInconsistency)?;
+        require!(pool_tokens_minted > 0, CarsaError::InvalidStakePool);
+
+        let deployment = &mut ctx.accounts.stake_pool_deployment;
+        deployment.deployed_lamports = deployment
+            .deployed_lamports
+            .checked_add(lamports)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        deployment.pool_tokens = deployment
+            .pool_tokens
+            .checked_add(pool_tokens_minted)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        deployment.total_deposited_lamports = deployment
+            .total_deposited_lamports
+            .checked_add(lamports)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let position_value_lamports = position_value(&ctx.accounts.stake_pool, deployment)?;
+
+        log_verbose!(
+            "Deployed {} lamports for {} pool tokens, position worth {}",
+            lamports,
+            pool_tokens_minted,
+            position_value_lamports
+        );
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        emit_cpi!(StakePoolDeployedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: pool_key,
+            stake_pool: deployment.stake_pool,
+            lamports,
+            pool_tokens_minted,
+            deployed_lamports: deployment.deployed_lamports,
+            position_value_lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl UnwindFromStakePool<'_> {
+    /// Handler for withdrawing SOL from the stake pool
+    pub fn handler(ctx: Context<UnwindFromStakePool>, pool_tokens: u64) -> Result<()> {
+        let deployment = &ctx.accounts.stake_pool_deployment;
+        require!(
+            pool_tokens > 0 && pool_tokens <= deployment.pool_tokens,
+            CarsaError::InvalidAmount
+        );
+        let cost_basis = deployment
+            .cost_basis(pool_tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let stake_pool_accounts = verified_stake_pool(
+            &ctx.accounts.stake_pool_program,
+            &ctx.accounts.stake_pool,
+            &ctx.accounts.stake_pool_withdraw_authority,
+            &ctx.accounts.reserve_stake,
+            &ctx.accounts.manager_fee_account,
+            &ctx.accounts.pool_token_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        let pool_key = ctx.accounts.pool_state.key();
+        let deployment_seeds: &[&[u8]] = &[
+            STAKE_POOL_DEPLOYMENT_SEED,
+            pool_key.as_ref(),
+            &[deployment.bump],
+        ];
+
+        let lamports_before = ctx.accounts.sol_vault.lamports();
+        let instruction = withdraw_sol_instruction(
+            &stake_pool_accounts,
+            &deployment.key(),
+            &deployment.pool_token_account,
+            ctx.accounts.sol_vault.key,
+            pool_tokens,
+        );
+        invoke_signed(
+            &instruction,
+            &[
+                ctx.accounts.stake_pool.to_account_info(),
+                ctx.accounts.stake_pool_withdraw_authority.to_account_info(),
+                ctx.accounts.stake_pool_deployment.to_account_info(),
+                ctx.accounts.pool_token_account.to_account_info(),
+                ctx.accounts.reserve_stake.to_account_info(),
+                ctx.accounts.sol_vault.to_account_info(),
+                ctx.accounts.manager_fee_account.to_account_info(),
+                ctx.accounts.pool_token_mint.to_account_info(),
+                ctx.accounts.clock.to_account_info(),
+                ctx.accounts.stake_history.to_account_info(),
+                ctx.accounts.stake_program.to_account_info(),
+                ctx.accounts.token_program.to_account_info(),
+                ctx.accounts.stake_pool_program.to_account_info(),
+            ],
+            &[deployment_seeds],
+        )?;
+        let lamports_received = ctx
+            .accounts
+            .sol_vault
+            .lamports()
+            .checked_sub(lamports_before)
+            .ok_or(CarsaError::StateInconsistency)?;
+
+        let deployment = &mut ctx.accounts.stake_pool_deployment;
+        deployment.deployed_lamports = deployment
+            .deployed_lamports
+            .checked_sub(cost_basis)
+            .ok_or(CarsaError::StateInconsistency)?;
+        deployment.pool_tokens = deployment
+            .pool_tokens
+            .checked_sub(pool_tokens)
+            .ok_or(CarsaError::StateInconsistency)?;
+        deployment.total_withdrawn_lamports = deployment
+            .total_withdrawn_lamports
+            .checked_add(lamports_received)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let pnl = (lamports_received as i64)
+            .checked_sub(cost_basis as i64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        deployment.realized_pnl_lamports = deployment
+            .realized_pnl_lamports
+            .checked_add(pnl)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let position_value_lamports = position_value(&ctx.accounts.stake_pool, deployment)?;
+
+        log_verbose!(
+            "Unwound {} pool tokens for {} lamports against a cost basis of {}",
+            pool_tokens,
+            lamports_received,
+            cost_basis
+        );
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        emit_cpi!(StakePoolUnwoundEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: pool_key,
+            stake_pool: deployment.stake_pool,
+            pool_tokens,
+            lamports_received,
+            cost_basis,
+            realized_pnl_lamports: deployment.realized_pnl_lamports,
+            position_value_lamports,
+            timestamp: ctx.accounts.clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct StakePoolConfiguredEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub stake_pool_program: Pubkey,
+    pub stake_pool: Pubkey,
+    /// Where SOL to deploy must be sent
+    pub sol_vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakePoolDeployedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub stake_pool: Pubkey,
+    pub lamports: u64,
+    pub pool_tokens_minted: u64,
+    /// Cost basis of every pool token held after the deposit
+    pub deployed_lamports: u64,
+    /// What the held pool tokens are worth at the stake pool's rate
+    pub position_value_lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakePoolUnwoundEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub stake_pool: Pubkey,
+    pub pool_tokens: u64,
+    pub lamports_received: u64,
+    /// Share of the cost basis the unwound tokens carried
+    pub cost_basis: u64,
+    /// Deployment's realized profit or loss to date
+    pub realized_pnl_lamports: i64,
+    /// What the pool tokens still held are worth at the stake pool's rate
+    pub position_value_lamports: u64,
+    pub timestamp: i64,
+}
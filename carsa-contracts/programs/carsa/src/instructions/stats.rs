@@ -0,0 +1,162 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Write today's aggregate protocol stats
+/// Permissionless; a second call on the same day refreshes the existing snapshot
+#[derive(Accounts)]
+#[instruction(day_index: u64)]
+pub struct WriteStatsSnapshot<'info> {
+    /// Pays for the snapshot account the first time it is written each day
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// Configuration account containing mint settings and purchase/transfer counters
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// Registry holding the merchant count
+    #[account(
+        seeds = [MERCHANT_REGISTRY_SEED],
+        bump = merchant_registry.load()?.bump,
+    )]
+    pub merchant_registry: AccountLoader<'info, MerchantRegistry>,
+    
+    /// Pool 0 of the Lokal mint (the original voucher pool)
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(0).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_state.load()?.voucher_mint == config.mint @ CarsaError::MintMismatch,
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+    
+    /// Snapshot for `day_index`, which must be the current day
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = StatsSnapshot::LEN,
+        seeds = [SNAPSHOT_SEED, day_index.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub snapshot: Account<'info, StatsSnapshot>,
+    
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Report the program's health in one read-only call, for monitoring bots to simulate
+#[derive(Accounts)]
+pub struct GetProgramStatus<'info> {
+    /// Configuration account containing mint settings
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    /// The voucher pool to report on
+    pub pool_state: AccountLoader<'info, PoolState>,
+    
+    /// The pool's vault
+    #[account(
+        constraint = vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl<'info> WriteStatsSnapshot<'info> {
+    /// Handler for writing the daily stats snapshot
+    pub fn handler(ctx: Context<WriteStatsSnapshot>, day_index: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            StatsSnapshot::day_index(clock.unix_timestamp) == Some(day_index),
+            CarsaError::InvalidSnapshotDay
+        );
+        
+        let config = &ctx.accounts.config;
+        let pool_state = &*ctx.accounts.pool_state.load()?;
+        let snapshot = &mut ctx.accounts.snapshot;
+        
+        snapshot.day_index = day_index;
+        snapshot.total_supply = config.total_supply;
+        snapshot.merchant_count = ctx.accounts.merchant_registry.load()?.merchant_count;
+        snapshot.total_purchases = config.total_purchases;
+        snapshot.total_transfers = config.total_transfers;
+        snapshot.total_voucher_staked = pool_state.total_voucher_staked;
+        snapshot.total_stakers = pool_state.total_stakers;
+        snapshot.total_yield_earned = pool_state.total_yield_earned;
+        snapshot.timestamp = clock.unix_timestamp;
+        snapshot.bump = ctx.bumps.snapshot;
+        
+        log_verbose!(
+            "Stats snapshot for day {}: {} purchases, {} transfers, {} merchants",
+            day_index,
+            snapshot.total_purchases,
+            snapshot.total_transfers,
+            snapshot.merchant_count
+        );
+        
+        Ok(())
+    }
+}
+
+/// Tokens that may still be minted under `cap` with `minted` so far (0 = no cap)
+fn mint_headroom(minted: u64, cap: u64) -> u64 {
+    if cap == 0 {
+        u64::MAX
+    } else {
+        cap.saturating_sub(minted)
+    }
+}
+
+impl<'info> GetProgramStatus<'info> {
+    /// Handler for reporting the program's health
+    pub fn handler(ctx: Context<GetProgramStatus>) -> Result<ProgramStatus> {
+        let clock = Clock::get()?;
+        let config = &ctx.accounts.config;
+        let pool_state = &*ctx.accounts.pool_state.load()?;
+        let pool_config = pool_state.config;
+        let vault_balance = ctx.accounts.vault_ata.amount;
+        let yield_update_age_seconds =
+            clock.unix_timestamp.saturating_sub(pool_state.last_yield_update);
+        
+        let vault_delta = (vault_balance as i128)
+            .checked_add(pool_state.deployed_amount as i128)
+            .and_then(|backing| backing.checked_sub(pool_state.total_voucher_staked as i128))
+            .and_then(|delta| i64::try_from(delta).ok())
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        
+        Ok(ProgramStatus {
+            version: ProgramStatus::VERSION,
+            paused: config.paused,
+            mint_supply: ctx.accounts.mint.supply,
+            tracked_supply: config.total_supply.saturating_sub(config.total_burned),
+            admin_mint_headroom: mint_headroom(config.total_admin_minted, config.admin_mint_cap),
+            reward_mint_headroom: mint_headroom(config.total_reward_minted, config.reward_mint_cap),
+            pending_param_changes: config.pending_param_changes,
+            pool: ctx.accounts.pool_state.key(),
+            deposits_enabled: pool_config.deposits_enabled,
+            withdrawals_enabled: pool_config.withdrawals_enabled,
+            deposits_paused: pool_state.deposits_paused,
+            withdrawals_paused: pool_state.withdrawals_paused,
+            vault_balance,
+            total_staked: pool_state.total_voucher_staked,
+            vault_delta,
+            yield_update_age_seconds,
+        })
+    }
+}
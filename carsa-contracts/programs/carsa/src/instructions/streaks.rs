@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Configure the bonus a merchant pays customers for repeat visits
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetStreakReward<'info> {
+    /// The merchant's owner wallet
+    pub merchant_owner: Signer<'info>,
+
+    /// The merchant account to update
+    #[account(
+        mut,
+        constraint = merchant_account.merchant_wallet == merchant_owner.key() @ CarsaError::MerchantOwnerMismatch
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    /// Writable so the change can take the next event sequence number
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Count a purchase as a visit toward the merchant's streak
+/// Returns the streak bonus to mint, 0 unless the visit completed a streak; merchants
+/// with a streak reward require the customer's visit stats
+pub(crate) fn record_streak_visit(
+    merchant_account: &Account<MerchantAccount>,
+    stats: Option<&mut Account<CustomerMerchantStats>>,
+    bump: Option<u8>,
+    customer: &Pubkey,
+    now: i64,
+) -> Result<u64> {
+    let (stats, bump) = match (stats, bump) {
+        (Some(stats), Some(bump)) => (stats, bump),
+        _ if merchant_account.streak_target == 0 => return Ok(0),
+        _ => return err!(CarsaError::InvalidCustomerMerchantStats),
+    };
+
+    stats.merchant = merchant_account.key();
+    stats.customer = *customer;
+    stats.bump = bump;
+    if merchant_account.streak_target == 0 {
+        return Ok(0);
+    }
+
+    let completed = stats
+        .record_visit(
+            merchant_account.streak_target,
+            merchant_account.streak_window_seconds,
+            merchant_account.streak_counts_every_purchase,
+            now,
+        )
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    Ok(if completed { merchant_account.streak_bonus } else { 0 })
+}
+
+impl<'info> SetStreakReward<'info> {
+    /// Handler for configuring a merchant's streak reward
+    pub fn handler(
+        ctx: Context<SetStreakReward>,
+        streak_target: u16,
+        streak_window_seconds: u32,
+        streak_bonus: u64,
+        streak_counts_every_purchase: bool,
+    ) -> Result<()> {
+        require!(
+            streak_target == 0 || (streak_window_seconds > 0 && streak_bonus > 0),
+            CarsaError::InvalidStreakReward
+        );
+
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let old = (
+            merchant_account.streak_target,
+            merchant_account.streak_window_seconds,
+            merchant_account.streak_bonus,
+            merchant_account.streak_counts_every_purchase,
+        );
+        merchant_account.streak_target = streak_target;
+        merchant_account.streak_window_seconds = streak_window_seconds;
+        merchant_account.streak_bonus = streak_bonus;
+        merchant_account.streak_counts_every_purchase = streak_counts_every_purchase;
+
+        log_verbose!(
+            "Merchant {} streak reward: {} tokens for {} visits within {}s",
+            merchant_account.key(),
+            streak_bonus,
+            streak_target,
+            streak_window_seconds
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(StreakRewardSetEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            merchant: merchant_account.key(),
+            streak_target: Changed { old: old.0, new: streak_target },
+            streak_window_seconds: Changed { old: old.1, new: streak_window_seconds },
+            streak_bonus: Changed { old: old.2, new: streak_bonus },
+            streak_counts_every_purchase: Changed {
+                old: old.3,
+                new: streak_counts_every_purchase,
+            },
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct StreakRewardSetEvent {
+    pub sequence: u64,
+    pub merchant: Pubkey,
+    pub streak_target: Changed<u16>,
+    pub streak_window_seconds: Changed<u32>,
+    pub streak_bonus: Changed<u64>,
+    pub streak_counts_every_purchase: Changed<bool>,
+    pub timestamp: i64,
+}
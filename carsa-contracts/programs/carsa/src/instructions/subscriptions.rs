@@ -0,0 +1,560 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::instructions::rewards::{current_token_to_idr_rate, purchase_reward};
+use super::franchises::roll_up_purchase;
+use super::mint_tokens::record_reward_mint;
+
+/// Authorize a merchant to charge a fixed amount every period, by approving the
+/// subscription PDA as delegate on the customer's token account
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateSubscription<'info> {
+    /// The subscribing customer, paying for the subscription account
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// The merchant being subscribed to
+    #[account(
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The customer's token account charged each period
+    /// Token accounts hold a single delegate, so this replaces any earlier approval on it
+    #[account(
+        mut,
+        constraint = customer_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = customer_token_account.owner == customer.key() @ CarsaError::InvalidOwner
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The subscription to be created, at most one per customer and merchant
+    #[account(
+        init,
+        payer = customer,
+        space = Subscription::LEN,
+        seeds = [SUBSCRIPTION_SEED, customer.key().as_ref(), merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// SPL Token program for the delegate approval
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Charge one elapsed period of a subscription
+/// Anyone may crank this; the subscription PDA signs the transfer as delegate
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ChargeSubscription<'info> {
+    /// The wallet cranking the charge, paying for the reward lot when one is needed
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// The subscription being charged
+    #[account(
+        mut,
+        seeds = [SUBSCRIPTION_SEED, subscription.customer.as_ref(), subscription.merchant.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// The merchant being paid
+    #[account(
+        mut,
+        address = subscription.merchant,
+        constraint = merchant_account.is_active @ CarsaError::MerchantNotActive,
+        constraint = !merchant_account.suspended @ CarsaError::MerchantSuspended,
+        constraint = !merchant_account.pending_activation @ CarsaError::MerchantPendingActivation
+    )]
+    pub merchant_account: Account<'info, MerchantAccount>,
+
+    /// The customer's token account the subscription was approved on
+    #[account(
+        mut,
+        address = subscription.customer_token_account
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The merchant's token account receiving the charge
+    #[account(
+        mut,
+        constraint = merchant_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = merchant_token_account.owner == merchant_account.merchant_wallet @ CarsaError::InvalidOwner
+    )]
+    pub merchant_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// SPL Token program for the charge and reward mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// The configured treasury token account, required when a rewarded charge owes a
+    /// protocol fee
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The configured Pyth price account, required for rewarded charges when the config
+    /// sets a price feed
+    /// CHECK: Must match `config.price_feed`; its data is validated in the handler
+    pub price_feed: Option<UncheckedAccount<'info>>,
+
+    /// Escrow receiving the reward, required for rewarded charges when expiring rewards
+    /// are enabled
+    #[account(
+        mut,
+        seeds = [REWARD_ESCROW_SEED],
+        bump,
+    )]
+    pub reward_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Lot tracking the escrowed reward, required for rewarded charges when expiring
+    /// rewards are enabled
+    #[account(
+        init,
+        payer = cranker,
+        space = RewardLot::LEN,
+        seeds = [
+            REWARD_LOT_SEED,
+            subscription.customer.as_ref(),
+            &subscription.charge_id(subscription.periods_charged)
+        ],
+        bump,
+    )]
+    pub reward_lot: Option<Account<'info, RewardLot>>,
+
+    /// The merchant's franchise parent, required for rewarded charges when the merchant
+    /// has one
+    #[account(
+        mut,
+        seeds = [PARENT_MERCHANT_SEED, parent_merchant.owner.as_ref()],
+        bump = parent_merchant.bump,
+    )]
+    pub parent_merchant: Option<Account<'info, ParentMerchant>>,
+}
+
+/// Stop a subscription, revoking its approval and closing it
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    /// The subscribed customer
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    /// The subscription being cancelled, closed to the customer
+    #[account(
+        mut,
+        close = customer,
+        seeds = [SUBSCRIPTION_SEED, customer.key().as_ref(), subscription.merchant.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// The customer's token account the subscription was approved on
+    #[account(
+        mut,
+        address = subscription.customer_token_account
+    )]
+    pub customer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// SPL Token program for revoking the approval
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CreateSubscription<'info> {
+    /// Handler for creating a subscription
+    pub fn handler(
+        ctx: Context<CreateSubscription>,
+        amount_per_period: u64,
+        period_seconds: i64,
+        max_periods: u32,
+        earn_rewards: bool,
+    ) -> Result<()> {
+        require!(
+            amount_per_period > 0 && period_seconds > 0 && max_periods > 0,
+            CarsaError::InvalidSubscription
+        );
+        let approved_amount = amount_per_period
+            .checked_mul(max_periods as u64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let cpi_accounts = token_interface::Approve {
+            to: ctx.accounts.customer_token_account.to_account_info(),
+            delegate: ctx.accounts.subscription.to_account_info(),
+            authority: ctx.accounts.customer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token_interface::approve(CpiContext::new(cpi_program, cpi_accounts), approved_amount)?;
+
+        let clock = Clock::get()?;
+        let customer = ctx.accounts.customer.key();
+        let merchant = ctx.accounts.merchant_account.key();
+        let subscription = &mut ctx.accounts.subscription;
+        // The first period is due straight away
+        subscription.set_inner(Subscription {
+            customer,
+            merchant,
+            customer_token_account: ctx.accounts.customer_token_account.key(),
+            amount_per_period,
+            period_seconds,
+            max_periods,
+            periods_charged: 0,
+            next_charge_at: clock.unix_timestamp,
+            created_at: clock.unix_timestamp,
+            earn_rewards,
+            bump: ctx.bumps.subscription,
+        });
+
+        log_verbose!(
+            "Subscription {} created: {} tokens every {} seconds for {} periods",
+            subscription.key(),
+            amount_per_period,
+            period_seconds,
+            max_periods
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(SubscriptionCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            subscription: subscription.key(),
+            customer,
+            merchant,
+            amount_per_period,
+            period_seconds,
+            max_periods,
+            earn_rewards,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ChargeSubscription<'info> {
+    /// Handler for charging a subscription period
+    pub fn handler(ctx: Context<ChargeSubscription>) -> Result<()> {
+        let clock = Clock::get()?;
+        let subscription = &mut ctx.accounts.subscription;
+        require!(!subscription.is_exhausted(), CarsaError::SubscriptionExhausted);
+        require!(
+            clock.unix_timestamp >= subscription.next_charge_at,
+            CarsaError::SubscriptionPeriodNotElapsed
+        );
+
+        let amount = subscription.amount_per_period;
+        let customer_token_account = &ctx.accounts.customer_token_account;
+        require!(
+            customer_token_account.delegate == COption::Some(subscription.key())
+                && customer_token_account.delegated_amount >= amount,
+            CarsaError::SubscriptionApprovalInsufficient
+        );
+        require!(customer_token_account.amount >= amount, CarsaError::InsufficientBalance);
+
+        let config = &mut ctx.accounts.config;
+        let merchant_account = &mut ctx.accounts.merchant_account;
+        let earn_rewards = subscription.earn_rewards;
+        require!(
+            (earn_rewards && config.expiring_rewards) == ctx.accounts.reward_escrow.is_some()
+                && (earn_rewards && config.expiring_rewards) == ctx.accounts.reward_lot.is_some(),
+            CarsaError::InvalidRewardLot
+        );
+
+        // Rewarded charges are valued like a purchase paid entirely in tokens
+        let (total_value, reward_amount, protocol_fee) = if earn_rewards {
+            require!(
+                config.allows_cashback_rate(merchant_account.cashback_rate),
+                CarsaError::CashbackRateAboveMax
+            );
+            let token_to_idr_rate = current_token_to_idr_rate(
+                config,
+                ctx.accounts.price_feed.as_ref(),
+                clock.unix_timestamp,
+            )?;
+            let (total_value, reward_amount) = purchase_reward(
+                0,
+                amount,
+                merchant_account.cashback_rate,
+                token_to_idr_rate,
+                merchant_account.redemption_bonus_bps,
+            )?;
+            let protocol_fee = config
+                .protocol_fee(amount)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            (total_value, reward_amount, protocol_fee)
+        } else {
+            (0, 0, 0)
+        };
+
+        let subscription_seeds = &[
+            SUBSCRIPTION_SEED,
+            subscription.customer.as_ref(),
+            subscription.merchant.as_ref(),
+            &[subscription.bump],
+        ];
+        let subscription_signer = &[&subscription_seeds[..]];
+
+        if protocol_fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(CarsaError::InvalidTreasuryAccount)?;
+            let cpi_accounts = token_interface::TransferChecked {
+                from: customer_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: subscription.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, subscription_signer);
+            token_interface::transfer_checked(cpi_ctx, protocol_fee, config.decimals())?;
+        }
+        let cpi_accounts = token_interface::TransferChecked {
+            from: customer_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.merchant_token_account.to_account_info(),
+            authority: subscription.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, subscription_signer);
+        token_interface::transfer_checked(
+            cpi_ctx,
+            amount - protocol_fee,
+            config.decimals(),
+        )?;
+
+        let transaction_id = subscription.charge_id(subscription.periods_charged);
+        if earn_rewards {
+            if reward_amount > 0 {
+                record_reward_mint(config, reward_amount)?;
+
+                let authority_seeds = &[
+                    MINT_AUTHORITY_SEED,
+                    &[config.mint_authority_bump],
+                ];
+                let signer_seeds = &[&authority_seeds[..]];
+
+                // Expiring rewards are held in escrow until withdrawn
+                let reward_destination = match ctx.accounts.reward_escrow.as_ref() {
+                    Some(reward_escrow) => reward_escrow.to_account_info(),
+                    None => customer_token_account.to_account_info(),
+                };
+                let cpi_accounts = token_interface::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: reward_destination,
+                    authority: ctx.accounts.mint_authority.to_account_info(),
+                };
+                let cpi_program = ctx.accounts.token_program.to_account_info();
+                let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+                token_interface::mint_to(cpi_ctx, reward_amount)?;
+            }
+
+            config.total_purchases = config
+                .total_purchases
+                .checked_add(1)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            merchant_account.total_transactions = merchant_account
+                .total_transactions
+                .checked_add(1)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            merchant_account.total_volume = merchant_account
+                .total_volume
+                .checked_add(total_value)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            merchant_account.total_rewards_distributed = merchant_account
+                .total_rewards_distributed
+                .checked_add(reward_amount)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            merchant_account.unsettled_tokens = merchant_account
+                .unsettled_tokens
+                .checked_add(amount - protocol_fee)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            roll_up_purchase(
+                merchant_account,
+                ctx.accounts.parent_merchant.as_mut(),
+                total_value,
+                reward_amount,
+            )?;
+
+            if let (Some(reward_lot), Some(bump)) = (
+                ctx.accounts.reward_lot.as_mut(),
+                ctx.bumps.reward_lot,
+            ) {
+                reward_lot.set_inner(RewardLot {
+                    customer: subscription.customer,
+                    transaction_id,
+                    amount: reward_amount,
+                    expired_amount: 0,
+                    minted_at: clock.unix_timestamp,
+                    expires_at: clock
+                        .unix_timestamp
+                        .checked_add(config.reward_lifetime_seconds)
+                        .ok_or(CarsaError::ArithmeticOverflow)?,
+                    bump,
+                });
+            }
+        }
+
+        subscription.periods_charged += 1;
+        subscription.next_charge_at = subscription
+            .next_charge_at
+            .checked_add(subscription.period_seconds)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Subscription {} charged period {} of {}: {} tokens, earned {} reward tokens",
+            subscription.key(),
+            subscription.periods_charged,
+            subscription.max_periods,
+            amount,
+            reward_amount
+        );
+
+        emit_cpi!(SubscriptionChargedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            subscription: subscription.key(),
+            customer: subscription.customer,
+            merchant: subscription.merchant,
+            period: subscription.periods_charged,
+            amount,
+            protocol_fee,
+            reward_amount,
+            transaction_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> CancelSubscription<'info> {
+    /// Handler for cancelling a subscription
+    pub fn handler(ctx: Context<CancelSubscription>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+
+        // Leave an approval the customer has since granted to someone else alone
+        if ctx.accounts.customer_token_account.delegate == COption::Some(subscription.key()) {
+            let cpi_accounts = token_interface::Revoke {
+                source: ctx.accounts.customer_token_account.to_account_info(),
+                authority: ctx.accounts.customer.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token_interface::revoke(CpiContext::new(cpi_program, cpi_accounts))?;
+        }
+
+        let clock = Clock::get()?;
+        log_verbose!(
+            "Subscription {} cancelled after {} of {} periods",
+            subscription.key(),
+            subscription.periods_charged,
+            subscription.max_periods
+        );
+
+        let config = &mut ctx.accounts.config;
+        emit_cpi!(SubscriptionCancelledEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            subscription: subscription.key(),
+            customer: subscription.customer,
+            merchant: subscription.merchant,
+            periods_charged: subscription.periods_charged,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SubscriptionCreatedEvent {
+    pub sequence: u64,
+    pub subscription: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub amount_per_period: u64,
+    pub period_seconds: i64,
+    pub max_periods: u32,
+    pub earn_rewards: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionChargedEvent {
+    pub sequence: u64,
+    pub subscription: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    /// Number of periods charged including this one
+    pub period: u32,
+    /// Tokens taken from the customer, including any protocol fee
+    pub amount: u64,
+    pub protocol_fee: u64,
+    /// Reward tokens minted for the charge (0 unless the subscription earns rewards)
+    pub reward_amount: u64,
+    /// Transaction ID of the charge, keying its reward lot
+    pub transaction_id: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionCancelledEvent {
+    pub sequence: u64,
+    pub subscription: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub periods_charged: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,471 @@
+use std::mem;
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::error::CarsaError;
+use crate::oracle::validate_price_feed_config;
+use super::mint_tokens::validate_cashback_limits;
+use super::advances::{apply_advance_ltv, AdvanceLtvSetEvent};
+use super::voucher_pool::{merged_pool_config, PoolConfigUpdatedEvent};
+
+/// Configure the delay applied to timelocked parameter changes
+#[derive(Accounts)]
+pub struct SetParamTimelock<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Queue a change to a timelocked parameter
+/// Mint parameters are proposed by the update authority, pool configuration by the
+/// pool authority
+#[event_cpi]
+#[derive(Accounts)]
+pub struct QueueParamChange<'info> {
+    /// The authority proposing the change, pays for the pending change account
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The pool whose configuration changes, only for `ParamChange::PoolConfig` and
+    /// `ParamChange::AdvanceLtv`
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+    )]
+    pub pool_state: Option<AccountLoader<'info, PoolState>>,
+
+    /// The queued change
+    #[account(
+        init,
+        payer = proposer,
+        space = PendingParamChange::LEN,
+        seeds = [
+            PARAM_CHANGE_SEED,
+            config.param_change_count.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Apply a queued change once its timelock has passed
+/// Anyone can execute; the rent goes back to the proposer
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExecuteParamChange<'info> {
+    /// The proposer of the change, receives the pending change account's rent
+    /// CHECK: Only receives lamports, verified against the pending change
+    #[account(
+        mut,
+        constraint = proposer.key() == pending_change.proposer @ CarsaError::InvalidParamChange
+    )]
+    pub proposer: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The pool whose configuration changes, only for `ParamChange::PoolConfig` and
+    /// `ParamChange::AdvanceLtv`
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+    )]
+    pub pool_state: Option<AccountLoader<'info, PoolState>>,
+
+    /// The queued change
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [PARAM_CHANGE_SEED, pending_change.id.to_le_bytes().as_ref()],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+}
+
+/// Drop a queued change before it is executed
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelParamChange<'info> {
+    /// The authority that queued the change
+    #[account(
+        mut,
+        constraint = proposer.key() == pending_change.proposer @ CarsaError::Unauthorized
+    )]
+    pub proposer: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The queued change
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [PARAM_CHANGE_SEED, pending_change.id.to_le_bytes().as_ref()],
+        bump = pending_change.bump,
+    )]
+    pub pending_change: Account<'info, PendingParamChange>,
+}
+
+impl<'info> SetParamTimelock<'info> {
+    /// Handler for updating the parameter timelock
+    pub fn handler(ctx: Context<SetParamTimelock>, param_timelock_seconds: i64) -> Result<()> {
+        require!(param_timelock_seconds >= 0, CarsaError::InvalidParamChange);
+
+        // Lengthening gives users more notice and is safe to apply at once;
+        // shortening an active timelock must itself wait it out
+        let config = &mut ctx.accounts.config;
+        require!(
+            config.param_timelock_seconds == 0
+                || param_timelock_seconds >= config.param_timelock_seconds,
+            CarsaError::TimelockActive
+        );
+        config.param_timelock_seconds = param_timelock_seconds;
+
+        log_verbose!("Parameter timelock set to {}s", param_timelock_seconds);
+
+        Ok(())
+    }
+}
+
+impl<'info> QueueParamChange<'info> {
+    /// Handler for queueing a timelocked parameter change
+    pub fn handler(ctx: Context<QueueParamChange>, change: ParamChange) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let proposer = ctx.accounts.proposer.key();
+
+        // Mint parameters belong to the update authority; reject values that could
+        // never be applied
+        let is_update_authority = proposer == config.update_authority;
+        match change {
+            ParamChange::TokenToIdrRate(rate) => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                require!(rate > 0, CarsaError::InvalidParamChange);
+            }
+            ParamChange::ProtocolFeeBps(fee_bps) => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                require!(fee_bps <= 10_000, CarsaError::InvalidProtocolFee);
+                require!(
+                    fee_bps == 0 || config.treasury_token_account != Pubkey::default(),
+                    CarsaError::InvalidTreasuryAccount
+                );
+            }
+            ParamChange::ParamTimelockSeconds(seconds) => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                require!(seconds >= 0, CarsaError::InvalidParamChange);
+            }
+            ParamChange::MintCosigner { .. }
+            | ParamChange::DisputeWindowSeconds(_)
+            | ParamChange::Limits { .. }
+            | ParamChange::MintCaps { .. } => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+            }
+            ParamChange::RateChangePolicy { max_rate_increase_bps, .. } => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                require!(max_rate_increase_bps <= 10_000, CarsaError::InvalidCashbackRate);
+            }
+            ParamChange::CashbackLimits { max_cashback_bps, alert_threshold_bps } => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                validate_cashback_limits(max_cashback_bps, alert_threshold_bps)?;
+            }
+            ParamChange::PriceFeed { price_feed, max_price_age_seconds, max_price_confidence_bps } => {
+                require!(is_update_authority, CarsaError::UpdateAuthorityMismatch);
+                validate_price_feed_config(
+                    &price_feed,
+                    max_price_age_seconds,
+                    max_price_confidence_bps,
+                )?;
+            }
+            ParamChange::PoolConfig { pool_id, update } => {
+                let pool_state = ctx
+                    .accounts
+                    .pool_state
+                    .as_ref()
+                    .ok_or(CarsaError::InvalidParamChange)?
+                    .load()?;
+                require!(pool_state.pool_id == pool_id, CarsaError::InvalidParamChange);
+                require!(proposer == pool_state.pool_authority, CarsaError::Unauthorized);
+                merged_pool_config(&pool_state.config, &update)?;
+            }
+            ParamChange::AdvanceLtv { pool_id, advance_ltv_bps } => {
+                let pool_state = ctx
+                    .accounts
+                    .pool_state
+                    .as_ref()
+                    .ok_or(CarsaError::InvalidParamChange)?
+                    .load()?;
+                require!(pool_state.pool_id == pool_id, CarsaError::InvalidParamChange);
+                require!(proposer == pool_state.pool_authority, CarsaError::Unauthorized);
+                require!(advance_ltv_bps <= 10_000, CarsaError::InvalidAdvanceLtv);
+            }
+        }
+
+        let clock = Clock::get()?;
+        let effective_at = clock
+            .unix_timestamp
+            .checked_add(config.param_timelock_seconds)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let pending_change = &mut ctx.accounts.pending_change;
+        pending_change.id = config.param_change_count;
+        pending_change.proposer = proposer;
+        pending_change.change = change;
+        pending_change.queued_at = clock.unix_timestamp;
+        pending_change.effective_at = effective_at;
+        pending_change.bump = ctx.bumps.pending_change;
+
+        config.param_change_count = config
+            .param_change_count
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        config.pending_param_changes = config
+            .pending_param_changes
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Parameter change #{} queued, effective at {}",
+            pending_change.id,
+            effective_at
+        );
+
+        emit_cpi!(ParamChangeQueuedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            id: pending_change.id,
+            proposer,
+            change,
+            effective_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ExecuteParamChange<'info> {
+    /// Handler for applying a queued parameter change
+    pub fn handler(ctx: Context<ExecuteParamChange>) -> Result<()> {
+        let clock = Clock::get()?;
+        let pending_change = &ctx.accounts.pending_change;
+        require!(
+            clock.unix_timestamp >= pending_change.effective_at,
+            CarsaError::ParamChangeNotReady
+        );
+
+        // Each arm applies the change and returns the values it replaced, in the same shape
+        let config = &mut ctx.accounts.config;
+        let previous = match pending_change.change {
+            ParamChange::TokenToIdrRate(rate) => {
+                ParamChange::TokenToIdrRate(mem::replace(&mut config.token_to_idr_rate, rate))
+            }
+            ParamChange::ProtocolFeeBps(fee_bps) => {
+                ParamChange::ProtocolFeeBps(mem::replace(&mut config.protocol_fee_bps, fee_bps))
+            }
+            ParamChange::ParamTimelockSeconds(seconds) => ParamChange::ParamTimelockSeconds(
+                mem::replace(&mut config.param_timelock_seconds, seconds),
+            ),
+            ParamChange::DisputeWindowSeconds(seconds) => ParamChange::DisputeWindowSeconds(
+                mem::replace(&mut config.dispute_window_seconds, seconds),
+            ),
+            ParamChange::MintCosigner { mint_cosigner, single_sig_mint_limit } => {
+                ParamChange::MintCosigner {
+                    mint_cosigner: mem::replace(&mut config.mint_cosigner, mint_cosigner),
+                    single_sig_mint_limit: mem::replace(
+                        &mut config.single_sig_mint_limit,
+                        single_sig_mint_limit,
+                    ),
+                }
+            }
+            ParamChange::Limits { max_transfer_amount, max_mint_per_tx, max_purchase_idr } => {
+                ParamChange::Limits {
+                    max_transfer_amount: mem::replace(
+                        &mut config.max_transfer_amount,
+                        max_transfer_amount,
+                    ),
+                    max_mint_per_tx: mem::replace(&mut config.max_mint_per_tx, max_mint_per_tx),
+                    max_purchase_idr: mem::replace(&mut config.max_purchase_idr, max_purchase_idr),
+                }
+            }
+            ParamChange::RateChangePolicy { rate_change_cooldown_seconds, max_rate_increase_bps } => {
+                ParamChange::RateChangePolicy {
+                    rate_change_cooldown_seconds: mem::replace(
+                        &mut config.rate_change_cooldown_seconds,
+                        rate_change_cooldown_seconds,
+                    ),
+                    max_rate_increase_bps: mem::replace(
+                        &mut config.max_rate_increase_bps,
+                        max_rate_increase_bps,
+                    ),
+                }
+            }
+            ParamChange::MintCaps { admin_mint_cap, reward_mint_cap } => ParamChange::MintCaps {
+                admin_mint_cap: mem::replace(&mut config.admin_mint_cap, admin_mint_cap),
+                reward_mint_cap: mem::replace(&mut config.reward_mint_cap, reward_mint_cap),
+            },
+            ParamChange::CashbackLimits { max_cashback_bps, alert_threshold_bps } => {
+                let old_max_cashback_bps = config.max_cashback_bps();
+                config.max_cashback_bps = max_cashback_bps;
+                ParamChange::CashbackLimits {
+                    max_cashback_bps: old_max_cashback_bps,
+                    alert_threshold_bps: mem::replace(
+                        &mut config.alert_threshold_bps,
+                        alert_threshold_bps,
+                    ),
+                }
+            }
+            ParamChange::PriceFeed { price_feed, max_price_age_seconds, max_price_confidence_bps } => {
+                ParamChange::PriceFeed {
+                    price_feed: mem::replace(&mut config.price_feed, price_feed),
+                    max_price_age_seconds: mem::replace(
+                        &mut config.max_price_age_seconds,
+                        max_price_age_seconds,
+                    ),
+                    max_price_confidence_bps: mem::replace(
+                        &mut config.max_price_confidence_bps,
+                        max_price_confidence_bps,
+                    ),
+                }
+            }
+            ParamChange::PoolConfig { pool_id, update } => {
+                let pool_loader = ctx
+                    .accounts
+                    .pool_state
+                    .as_ref()
+                    .ok_or(CarsaError::InvalidParamChange)?;
+                let pool_state = &mut *pool_loader.load_mut()?;
+                require!(pool_state.pool_id == pool_id, CarsaError::InvalidParamChange);
+
+                // The pool may have changed since queueing, so validate again
+                let old_config = pool_state.config;
+                let new_config = merged_pool_config(&old_config, &update)?;
+                pool_state.config = new_config;
+
+                emit_cpi!(PoolConfigUpdatedEvent {
+                    sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                    pool: pool_loader.key(),
+                    config: Changed { old: old_config, new: new_config },
+                    timestamp: clock.unix_timestamp,
+                });
+
+                ParamChange::PoolConfig { pool_id, update: old_config.as_update() }
+            }
+            ParamChange::AdvanceLtv { pool_id, advance_ltv_bps } => {
+                let pool_loader = ctx
+                    .accounts
+                    .pool_state
+                    .as_ref()
+                    .ok_or(CarsaError::InvalidParamChange)?;
+                let pool_state = &mut *pool_loader.load_mut()?;
+                require!(pool_state.pool_id == pool_id, CarsaError::InvalidParamChange);
+                let old_ltv_bps = apply_advance_ltv(pool_state, advance_ltv_bps)?;
+
+                emit_cpi!(AdvanceLtvSetEvent {
+                    sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                    pool: pool_loader.key(),
+                    advance_ltv_bps: Changed { old: old_ltv_bps, new: advance_ltv_bps },
+                    timestamp: clock.unix_timestamp,
+                });
+
+                ParamChange::AdvanceLtv { pool_id, advance_ltv_bps: old_ltv_bps }
+            }
+        };
+
+        // Changes queued before the counter existed were never counted
+        config.pending_param_changes = config.pending_param_changes.saturating_sub(1);
+
+        log_verbose!("Parameter change #{} executed", pending_change.id);
+
+        emit_cpi!(ParamChangeExecutedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            id: pending_change.id,
+            change: Changed { old: previous, new: pending_change.change },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> CancelParamChange<'info> {
+    /// Handler for cancelling a queued parameter change
+    pub fn handler(ctx: Context<CancelParamChange>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let pending_change = &ctx.accounts.pending_change;
+        config.pending_param_changes = config.pending_param_changes.saturating_sub(1);
+
+        log_verbose!("Parameter change #{} cancelled", pending_change.id);
+
+        emit_cpi!(ParamChangeCancelledEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            id: pending_change.id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ParamChangeQueuedEvent {
+    pub sequence: u64,
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub change: ParamChange,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParamChangeExecutedEvent {
+    pub sequence: u64,
+    pub id: u64,
+    /// The parameter's values before the change, and the applied change
+    /// A `PoolConfig` change's old value sets every field
+    pub change: Changed<ParamChange>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParamChangeCancelledEvent {
+    pub sequence: u64,
+    pub id: u64,
+    pub timestamp: i64,
+}
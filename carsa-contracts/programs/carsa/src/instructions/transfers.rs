@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
 use crate::state::*;
 use crate::error::CarsaError;
 
 /// Transfer Lokal tokens between user accounts
 /// This instruction enables peer-to-peer token transfers within the ecosystem
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(amount: u64, transaction_id: [u8; 32], memo: String)]
 pub struct TransferTokens<'info> {
@@ -16,22 +17,34 @@ pub struct TransferTokens<'info> {
     #[account(
         mut,
         constraint = sender_token_account.owner == sender.key(),
-        constraint = sender_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+        constraint = sender_token_account.mint == config.mint @ CarsaError::MintMismatch,
+        constraint = !sender_token_account.is_frozen() @ CarsaError::AccountFrozen
     )]
-    pub sender_token_account: Account<'info, TokenAccount>,
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
     
     /// The recipient's token account
     #[account(
         mut,
-        constraint = recipient_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch,
+        constraint = recipient_token_account.mint == config.mint @ CarsaError::MintMismatch,
+        constraint = !recipient_token_account.is_frozen() @ CarsaError::AccountFrozen,
         constraint = recipient_token_account.key() != sender_token_account.key() @ CarsaError::SelfTransferNotAllowed
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintMismatch,
+        constraint = mint.decimals == config.decimals() @ CarsaError::InvalidMintDecimals
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
     
     /// Configuration account containing mint settings
+    /// Writable so the transfer can take the next event sequence number
     #[account(
+        mut,
         seeds = [CONFIG_SEED],
         bump = config.config_bump,
+        constraint = !config.paused @ CarsaError::ProgramPaused
     )]
     pub config: Account<'info, LokalMintConfig>,
     
@@ -46,10 +59,102 @@ pub struct TransferTokens<'info> {
     pub transfer_record: Account<'info, TokenTransfer>,
     
     /// SPL Token program for transfer operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     
     /// System program required for account creation
     pub system_program: Program<'info, System>,
+    
+    /// Activity feed entry for the sender, required only when activity recording is enabled
+    #[account(
+        init,
+        payer = sender,
+        space = ActivityRecord::LEN,
+        seeds = [
+            ACTIVITY_SEED,
+            sender.key().as_ref(),
+            &transaction_id,
+            &[ActivityKind::Transfer as u8]
+        ],
+        bump,
+    )]
+    pub activity_record: Option<Account<'info, ActivityRecord>>,
+    
+    /// The sender's fee exemption, waiving the transfer fee
+    #[account(
+        seeds = [FEE_EXEMPTION_SEED, sender.key().as_ref()],
+        bump = fee_exemption.bump,
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+    
+    /// The configured treasury token account, required when a transfer fee is owed
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Exempt a wallet's outgoing transfers from the transfer fee
+#[derive(Accounts)]
+pub struct AddFeeExemption<'info> {
+    /// The update authority of the mint configuration, pays for the exemption
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// The wallet being exempted
+    /// CHECK: Only used as a seed for the exemption
+    pub wallet: UncheckedAccount<'info>,
+    
+    /// The exemption (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = FeeExemption::LEN,
+        seeds = [FEE_EXEMPTION_SEED, wallet.key().as_ref()],
+        bump,
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+    
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Remove a wallet's transfer fee exemption and reclaim its rent
+#[derive(Accounts)]
+pub struct RemoveFeeExemption<'info> {
+    /// The update authority of the mint configuration, receives the rent
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+    
+    /// Configuration account containing mint settings
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+    
+    /// The exemption to remove
+    #[account(
+        mut,
+        close = authority,
+        seeds = [FEE_EXEMPTION_SEED, fee_exemption.wallet.as_ref()],
+        bump = fee_exemption.bump,
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
 }
 
 // NOTE: RedeemTokens and BurnTokens functionality has been integrated into ProcessPurchase
@@ -67,12 +172,29 @@ impl<'info> TransferTokens<'info> {
         // Validate transfer amount
         require!(amount > 0, CarsaError::InvalidTransferAmount);
         
-        // Set maximum transfer amount (10,000 tokens with 9 decimals)
-        const MAX_TRANSFER_AMOUNT: u64 = 10_000_000_000_000; // 10,000 * 10^9
-        require!(amount <= MAX_TRANSFER_AMOUNT, CarsaError::TransferAmountTooLarge);
+        require!(
+            ctx.accounts.config.allows_transfer(amount),
+            CarsaError::TransferAmountTooLarge
+        );
         
         // Validate memo length
-        require!(memo.len() <= 64, CarsaError::InvalidMerchantName);
+        require!(memo.len() <= 64, CarsaError::MemoTooLong);
+        
+        require!(
+            ctx.accounts.config.record_activity == ctx.accounts.activity_record.is_some(),
+            CarsaError::InvalidActivityRecord
+        );
+        
+        // Exempt senders keep the whole amount going to the recipient
+        let fee = if ctx.accounts.fee_exemption.is_some() {
+            0
+        } else {
+            ctx.accounts
+                .config
+                .transfer_fee(amount)
+                .ok_or(CarsaError::ArithmeticOverflow)?
+        };
+        let net_amount = amount - fee;
         
         let transfer_record = &mut ctx.accounts.transfer_record;
         let clock = Clock::get()?;
@@ -90,6 +212,8 @@ impl<'info> TransferTokens<'info> {
         transfer_record.timestamp = clock.unix_timestamp;
         transfer_record.transaction_id = transaction_id;
         transfer_record.bump = ctx.bumps.transfer_record;
+        transfer_record.fee = fee;
+        transfer_record.net_amount = net_amount;
 
         // Convert memo to fixed-size byte array
         let mut memo_bytes = [0u8; 64];
@@ -98,9 +222,46 @@ impl<'info> TransferTokens<'info> {
         memo_bytes[..memo_len].copy_from_slice(&memo_slice[..memo_len]);
         transfer_record.memo = memo_bytes;
 
+        // Add the transfer to the sender's activity feed
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.activity_record.as_mut(),
+            ctx.bumps.activity_record,
+        ) {
+            entry.set_inner(ActivityRecord {
+                wallet: transfer_record.from,
+                counterparty: transfer_record.to,
+                amount,
+                timestamp: clock.unix_timestamp,
+                kind: ActivityKind::Transfer,
+                transaction_id,
+                bump,
+            });
+        }
+
+        // Route the transfer fee to the treasury
+        if fee > 0 {
+            let treasury_token_account = ctx
+                .accounts
+                .treasury_token_account
+                .as_ref()
+                .ok_or(CarsaError::InvalidTreasuryAccount)?;
+
+            let cpi_accounts = token_interface::TransferChecked {
+                from: ctx.accounts.sender_token_account.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            };
+
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+            token_interface::transfer_checked(cpi_ctx, fee, ctx.accounts.config.decimals())?;
+        }
+
         // Create CPI context for token transfer
-        let cpi_accounts = token::Transfer {
+        let cpi_accounts = token_interface::TransferChecked {
             from: ctx.accounts.sender_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
             to: ctx.accounts.recipient_token_account.to_account_info(),
             authority: ctx.accounts.sender.to_account_info(),
         };
@@ -108,19 +269,82 @@ impl<'info> TransferTokens<'info> {
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
 
-        // Execute the transfer
-        token::transfer(cpi_ctx, amount)?;
+        // Execute the transfer of the net amount
+        token_interface::transfer_checked(cpi_ctx, net_amount, ctx.accounts.config.decimals())?;
 
-        msg!(
-            "Token transfer: {} tokens from {} to {}",
-            amount as f64 / 1_000_000_000.0, // Convert to display units
+        ctx.accounts.config.total_transfers = ctx
+            .accounts
+            .config
+            .total_transfers
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Token transfer: {}.{:09} tokens from {} to {}",
+            amount / 1_000_000_000, // Convert to display units without floats
+            amount % 1_000_000_000,
             ctx.accounts.sender.key(),
             ctx.accounts.recipient_token_account.owner
         );
+        if fee > 0 {
+            log_verbose!("Transfer fee: {}.{:09} tokens", fee / 1_000_000_000, fee % 1_000_000_000);
+        }
+
+        emit_cpi!(TokensTransferredEvent {
+            sequence: ctx
+                .accounts
+                .config
+                .next_event_sequence()
+                .ok_or(CarsaError::ArithmeticOverflow)?,
+            from: transfer_record.from,
+            to: transfer_record.to,
+            amount,
+            fee,
+            net_amount,
+            transaction_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> AddFeeExemption<'info> {
+    /// Handler for exempting a wallet from the transfer fee
+    pub fn handler(ctx: Context<AddFeeExemption>) -> Result<()> {
+        let fee_exemption = &mut ctx.accounts.fee_exemption;
+        fee_exemption.wallet = ctx.accounts.wallet.key();
+        fee_exemption.added_at = Clock::get()?.unix_timestamp;
+        fee_exemption.bump = ctx.bumps.fee_exemption;
+        
+        log_verbose!("Transfer fee exemption added: {}", fee_exemption.wallet);
+        
+        Ok(())
+    }
+}
 
+impl<'info> RemoveFeeExemption<'info> {
+    /// Handler for removing a wallet's transfer fee exemption
+    pub fn handler(ctx: Context<RemoveFeeExemption>) -> Result<()> {
+        log_verbose!("Transfer fee exemption removed: {}", ctx.accounts.fee_exemption.wallet);
+        
         Ok(())
     }
 }
 
 // NOTE: RedeemTokens and BurnTokens implementation handlers have been removed
 // Their functionality is now integrated into the ProcessPurchase instruction
+
+#[event]
+pub struct TokensTransferredEvent {
+    pub sequence: u64,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    /// Gross amount sent, including the fee
+    pub amount: u64,
+    pub fee: u64,
+    /// Amount the recipient received
+    pub net_amount: u64,
+    pub transaction_id: [u8; 32],
+    pub timestamp: i64,
+}
@@ -0,0 +1,216 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+
+/// Configure dual control and the per-withdrawal cap for treasury withdrawals
+#[derive(Accounts)]
+pub struct SetTreasuryControls<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+}
+
+/// Move collected protocol fees out of the treasury
+/// Every withdrawal leaves a `TreasuryWithdrawal` record behind
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    /// The update authority of the mint configuration, pays for the record
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Second signer, required when the config sets a treasury co-signer
+    pub cosigner: Option<Signer<'info>>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// PDA that owns the treasury token account
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [TREASURY_AUTHORITY_SEED],
+        bump,
+    )]
+    pub treasury_authority: UncheckedAccount<'info>,
+
+    /// The configured treasury token account
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account receiving the withdrawn fees
+    #[account(
+        mut,
+        constraint = destination.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Record of this withdrawal
+    #[account(
+        init,
+        payer = authority,
+        space = TreasuryWithdrawal::LEN,
+        seeds = [
+            TREASURY_WITHDRAWAL_SEED,
+            config.treasury_withdrawal_count.to_le_bytes().as_ref()
+        ],
+        bump,
+    )]
+    pub withdrawal_record: Account<'info, TreasuryWithdrawal>,
+
+    /// SPL Token program for transfer operations
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetTreasuryControls<'info> {
+    /// Handler for updating the treasury co-signer and withdrawal cap
+    pub fn handler(
+        ctx: Context<SetTreasuryControls>,
+        treasury_cosigner: Pubkey,
+        treasury_withdrawal_cap: u64,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.treasury_cosigner = treasury_cosigner;
+        config.treasury_withdrawal_cap = treasury_withdrawal_cap;
+
+        log_verbose!(
+            "Treasury controls set: co-signer {}, cap {}",
+            treasury_cosigner,
+            treasury_withdrawal_cap
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawTreasury<'info> {
+    /// Handler for withdrawing protocol fees from the treasury
+    pub fn handler(ctx: Context<WithdrawTreasury>, amount: u64, reason_code: u16) -> Result<()> {
+        require!(amount > 0, CarsaError::InvalidAmount);
+
+        let config = &mut ctx.accounts.config;
+
+        // Dual control: the configured co-signer must sign too
+        let cosigner = if config.treasury_cosigner != Pubkey::default() {
+            let cosigner = ctx
+                .accounts
+                .cosigner
+                .as_ref()
+                .ok_or(CarsaError::TreasuryCosignerRequired)?;
+            require!(
+                cosigner.key() == config.treasury_cosigner,
+                CarsaError::TreasuryCosignerRequired
+            );
+            cosigner.key()
+        } else {
+            Pubkey::default()
+        };
+
+        require!(
+            config.treasury_withdrawal_cap == 0 || amount <= config.treasury_withdrawal_cap,
+            CarsaError::TreasuryWithdrawalCapExceeded
+        );
+        require!(
+            ctx.accounts.treasury_token_account.amount >= amount,
+            CarsaError::InsufficientBalance
+        );
+
+        // Transfer from the treasury, signed by its PDA owner
+        let treasury_authority_seeds: &[&[u8]] = &[
+            TREASURY_AUTHORITY_SEED,
+            &[ctx.bumps.treasury_authority],
+        ];
+        let signer_seeds = &[treasury_authority_seeds];
+
+        let cpi_accounts = token_interface::TransferChecked {
+            from: ctx.accounts.treasury_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.treasury_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::transfer_checked(cpi_ctx, amount, config.decimals())?;
+
+        let clock = Clock::get()?;
+        let withdrawal_record = &mut ctx.accounts.withdrawal_record;
+        withdrawal_record.index = config.treasury_withdrawal_count;
+        withdrawal_record.authority = ctx.accounts.authority.key();
+        withdrawal_record.cosigner = cosigner;
+        withdrawal_record.destination = ctx.accounts.destination.key();
+        withdrawal_record.amount = amount;
+        withdrawal_record.reason_code = reason_code;
+        withdrawal_record.timestamp = clock.unix_timestamp;
+        withdrawal_record.bump = ctx.bumps.withdrawal_record;
+
+        config.treasury_withdrawal_count = config
+            .treasury_withdrawal_count
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Treasury withdrawal #{}: {}.{:09} tokens to {} (reason {})",
+            withdrawal_record.index,
+            amount / 1_000_000_000,
+            amount % 1_000_000_000,
+            withdrawal_record.destination,
+            reason_code
+        );
+
+        emit_cpi!(TreasuryWithdrawnEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            index: withdrawal_record.index,
+            authority: withdrawal_record.authority,
+            cosigner,
+            destination: withdrawal_record.destination,
+            amount,
+            reason_code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct TreasuryWithdrawnEvent {
+    pub sequence: u64,
+    pub index: u64,
+    pub authority: Pubkey,
+    /// Default pubkey when no co-signer was required
+    pub cosigner: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub reason_code: u16,
+    pub timestamp: i64,
+}
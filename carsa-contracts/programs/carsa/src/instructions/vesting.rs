@@ -0,0 +1,436 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface};
+use crate::state::*;
+use crate::error::CarsaError;
+use super::mint_tokens::{check_mint_cosigner, record_admin_mint};
+
+/// Mint a merchant incentive grant into escrow, vesting linearly to the beneficiary
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateVestingGrant<'info> {
+    /// The update authority of the mint configuration, paying for the grant
+    #[account(
+        mut,
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// The Lokal token mint
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Token account holding granted tokens until they are claimed or revoked
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = mint,
+        token::authority = mint_authority,
+        seeds = [VESTING_ESCROW_SEED],
+        bump,
+    )]
+    pub vesting_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The grant record
+    #[account(
+        init,
+        payer = authority,
+        space = VestingGrant::LEN,
+        seeds = [VESTING_GRANT_SEED, config.vesting_grant_count.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+
+    /// SPL Token program for minting the grant
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program required for account creation
+    pub system_program: Program<'info, System>,
+
+    /// The configured mint co-signer, required when the grant is above the
+    /// single-signature mint limit
+    pub cosigner: Option<Signer<'info>>,
+}
+
+/// Withdraw the vested, unclaimed part of a grant
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// The grant's beneficiary
+    pub beneficiary: Signer<'info>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding granted tokens until they are claimed or revoked
+    #[account(
+        mut,
+        seeds = [VESTING_ESCROW_SEED],
+        bump,
+    )]
+    pub vesting_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The grant being claimed
+    #[account(
+        mut,
+        seeds = [VESTING_GRANT_SEED, vesting_grant.id.to_le_bytes().as_ref()],
+        bump = vesting_grant.bump,
+        constraint = vesting_grant.beneficiary == beneficiary.key() @ CarsaError::Unauthorized
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+
+    /// The beneficiary's token account receiving the vested tokens
+    #[account(
+        mut,
+        constraint = beneficiary_token_account.mint == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub beneficiary_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// SPL Token program for the transfer
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Stop a grant from vesting further and return the unvested tokens to the treasury
+/// Tokens already vested stay claimable by the beneficiary
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RevokeVestingGrant<'info> {
+    /// The update authority of the mint configuration
+    #[account(
+        constraint = authority.key() == config.update_authority @ CarsaError::UpdateAuthorityMismatch
+    )]
+    pub authority: Signer<'info>,
+
+    /// Program Derived Address that acts as the mint authority and owns the escrow
+    /// CHECK: This account is derived using seeds and verified in constraints
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = config.mint_authority_bump,
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    /// Configuration account containing mint settings
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, LokalMintConfig>,
+
+    /// Token account holding granted tokens until they are claimed or revoked
+    #[account(
+        mut,
+        seeds = [VESTING_ESCROW_SEED],
+        bump,
+    )]
+    pub vesting_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// The grant being revoked
+    #[account(
+        mut,
+        seeds = [VESTING_GRANT_SEED, vesting_grant.id.to_le_bytes().as_ref()],
+        bump = vesting_grant.bump,
+    )]
+    pub vesting_grant: Account<'info, VestingGrant>,
+
+    /// The configured treasury token account, receiving the unvested tokens
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury_token_account @ CarsaError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == config.mint @ CarsaError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The Lokal token mint
+    #[account(
+        constraint = mint.key() == config.mint @ CarsaError::MintAuthorityMismatch
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// SPL Token program for the transfer
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Move `amount` out of the vesting escrow, signed by the mint authority PDA
+fn transfer_from_escrow<'info>(
+    config: &LokalMintConfig,
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vesting_escrow: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint_authority: &UncheckedAccount<'info>,
+    amount: u64,
+) -> Result<()> {
+    let authority_seeds = &[
+        MINT_AUTHORITY_SEED,
+        &[config.mint_authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let cpi_accounts = token_interface::TransferChecked {
+        from: vesting_escrow.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: mint_authority.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+    token_interface::transfer_checked(cpi_ctx, amount, config.decimals())
+}
+
+impl<'info> CreateVestingGrant<'info> {
+    /// Handler for creating a vesting grant
+    pub fn handler(
+        ctx: Context<CreateVestingGrant>,
+        beneficiary: Pubkey,
+        total: u64,
+        start: i64,
+        cliff: i64,
+        duration: i64,
+    ) -> Result<()> {
+        let end = start.checked_add(duration).ok_or(CarsaError::InvalidVestingSchedule)?;
+        require!(
+            total > 0 && duration > 0 && start <= cliff && cliff <= end,
+            CarsaError::InvalidVestingSchedule
+        );
+
+        let config = &mut ctx.accounts.config;
+
+        // Grants are mints too, so large ones need the co-signer
+        let cosigner = check_mint_cosigner(config, total, ctx.accounts.cosigner.as_ref())?;
+
+        record_admin_mint(config, total)?;
+
+        let authority_seeds = &[
+            MINT_AUTHORITY_SEED,
+            &[config.mint_authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let cpi_accounts = token_interface::MintTo {
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.vesting_escrow.to_account_info(),
+            authority: ctx.accounts.mint_authority.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        token_interface::mint_to(cpi_ctx, total)?;
+
+        let vesting_grant = &mut ctx.accounts.vesting_grant;
+        vesting_grant.set_inner(VestingGrant {
+            id: config.vesting_grant_count,
+            beneficiary,
+            total,
+            released: 0,
+            start,
+            cliff,
+            duration,
+            revoked_at: 0,
+            bump: ctx.bumps.vesting_grant,
+        });
+
+        config.vesting_grant_count = config
+            .vesting_grant_count
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!(
+            "Vesting grant #{} created: {} tokens to {}, vesting from {} over {}s",
+            vesting_grant.id,
+            total,
+            beneficiary,
+            start,
+            duration
+        );
+
+        emit_cpi!(VestingGrantCreatedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            grant: vesting_grant.key(),
+            beneficiary,
+            total,
+            start,
+            cliff,
+            duration,
+            cosigner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ClaimVested<'info> {
+    /// Handler for claiming vested tokens
+    pub fn handler(ctx: Context<ClaimVested>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vesting_grant = &mut ctx.accounts.vesting_grant;
+        require!(
+            clock.unix_timestamp >= vesting_grant.cliff,
+            CarsaError::VestingCliffNotReached
+        );
+
+        let amount = vesting_grant
+            .claimable_amount(clock.unix_timestamp)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(amount > 0, CarsaError::NothingVested);
+
+        vesting_grant.released = vesting_grant
+            .released
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let config = &mut ctx.accounts.config;
+        transfer_from_escrow(
+            config,
+            &ctx.accounts.token_program,
+            &ctx.accounts.mint,
+            &ctx.accounts.vesting_escrow,
+            &ctx.accounts.beneficiary_token_account,
+            &ctx.accounts.mint_authority,
+            amount,
+        )?;
+
+        log_verbose!(
+            "Claimed {} vested tokens from grant #{} ({} of {} released)",
+            amount,
+            vesting_grant.id,
+            vesting_grant.released,
+            vesting_grant.total
+        );
+
+        emit_cpi!(VestedTokensClaimedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            grant: vesting_grant.key(),
+            beneficiary: vesting_grant.beneficiary,
+            amount,
+            released: vesting_grant.released,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RevokeVestingGrant<'info> {
+    /// Handler for revoking a vesting grant
+    pub fn handler(ctx: Context<RevokeVestingGrant>) -> Result<()> {
+        let clock = Clock::get()?;
+        let vesting_grant = &mut ctx.accounts.vesting_grant;
+        require!(vesting_grant.revoked_at == 0, CarsaError::VestingGrantRevoked);
+
+        let vested = vesting_grant
+            .vested_amount(clock.unix_timestamp)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let unvested = vesting_grant
+            .total
+            .checked_sub(vested)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        vesting_grant.revoked_at = clock.unix_timestamp;
+
+        let config = &mut ctx.accounts.config;
+        if unvested > 0 {
+            transfer_from_escrow(
+                config,
+                &ctx.accounts.token_program,
+                &ctx.accounts.mint,
+                &ctx.accounts.vesting_escrow,
+                &ctx.accounts.treasury_token_account,
+                &ctx.accounts.mint_authority,
+                unvested,
+            )?;
+        }
+
+        log_verbose!(
+            "Vesting grant #{} revoked: {} unvested tokens returned to the treasury",
+            vesting_grant.id,
+            unvested
+        );
+
+        emit_cpi!(VestingGrantRevokedEvent {
+            sequence: config.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            grant: vesting_grant.key(),
+            beneficiary: vesting_grant.beneficiary,
+            vested,
+            returned_to_treasury: unvested,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[event]
+pub struct VestingGrantCreatedEvent {
+    pub sequence: u64,
+    pub grant: Pubkey,
+    pub beneficiary: Pubkey,
+    pub total: u64,
+    pub start: i64,
+    pub cliff: i64,
+    pub duration: i64,
+    /// The mint co-signer that approved the grant (default pubkey when not required)
+    pub cosigner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestedTokensClaimedEvent {
+    pub sequence: u64,
+    pub grant: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    /// Total claimed from the grant after this claim
+    pub released: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingGrantRevokedEvent {
+    pub sequence: u64,
+    pub grant: Pubkey,
+    pub beneficiary: Pubkey,
+    /// Tokens vested at revocation, still claimable by the beneficiary
+    pub vested: u64,
+    pub returned_to_treasury: u64,
+    pub timestamp: i64,
+}
@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Burn, Mint, MintTo, Revoke, TokenAccount, TokenInterface, TransferChecked};
 
 use crate::error::*;
+use crate::instructions::mint_tokens::reject_unsafe_mint_extensions;
 use crate::state::*;
+use crate::utils::remaining_accounts::expect_stake_records;
 
 // ============================================================================
 // Initialize Pool Instruction
@@ -10,7 +13,9 @@ use crate::state::*;
 
 /// Initialize a new voucher staking pool
 /// This creates the pool state and sets up the vault for holding staked tokens
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(pool_id: u16)]
 pub struct InitializePool<'info> {
     /// The authority that manages the pool (admin)
     #[account(mut)]
@@ -20,61 +25,105 @@ pub struct InitializePool<'info> {
     /// CHECK: This is validated by storing it in the pool state
     pub pool_delegate: AccountInfo<'info>,
 
+    /// The voucher token mint (LOKAL token), part of every pool PDA's seeds
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
     /// The pool state account (PDA)
     #[account(
         init,
         payer = pool_authority,
         space = PoolState::LEN,
-        seeds = [POOL_STATE_SEED],
+        seeds = [
+            POOL_STATE_SEED,
+            voucher_mint.key().as_ref(),
+            pool_id_seed(pool_id).as_ref()
+        ],
         bump
     )]
-    pub pool_state: Account<'info, PoolState>,
-
-    /// The vault token account that will hold staked voucher tokens
-    /// Must be initialized before calling this instruction
-    #[account(
-        mut,
-        constraint = vault_ata.mint == voucher_mint.key() @ CarsaError::InvalidMint,
-        constraint = vault_ata.owner == pool_vault_authority.key() @ CarsaError::InvalidOwner
-    )]
-    pub vault_ata: Account<'info, TokenAccount>,
+    pub pool_state: AccountLoader<'info, PoolState>,
 
     /// The vault authority PDA (owns the vault_ata)
     /// CHECK: PDA validation is handled by seeds constraint
     #[account(
-        seeds = [POOL_VAULT_AUTHORITY_SEED],
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            voucher_mint.key().as_ref(),
+            pool_id_seed(pool_id).as_ref()
+        ],
         bump
     )]
     pub pool_vault_authority: AccountInfo<'info>,
 
-    /// The voucher token mint (LOKAL token)
-    pub voucher_mint: Account<'info, Mint>,
+    /// The vault token account that will hold staked voucher tokens
+    /// Created as the vault authority's associated token account if it doesn't exist yet
+    #[account(
+        init_if_needed,
+        payer = pool_authority,
+        associated_token::mint = voucher_mint,
+        associated_token::authority = pool_vault_authority
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The sLOKAL receipt mint (PDA), minted by the vault authority
+    #[account(
+        init,
+        payer = pool_authority,
+        seeds = [
+            RECEIPT_MINT_SEED,
+            voucher_mint.key().as_ref(),
+            pool_id_seed(pool_id).as_ref()
+        ],
+        bump,
+        mint::decimals = voucher_mint.decimals,
+        mint::authority = pool_vault_authority
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
 
     /// System program for account creation
     pub system_program: Program<'info, System>,
 
     /// Token program for SPL token operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program for creating the vault
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl InitializePool<'_> {
-    pub fn handler(ctx: Context<InitializePool>, config: PoolConfig) -> Result<()> {
-        let pool_state = &mut ctx.accounts.pool_state;
+    pub fn handler(ctx: Context<InitializePool>, pool_id: u16, config: PoolConfig) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_init()?;
         let clock = Clock::get()?;
 
         // Validate configuration
         require!(
             config.min_stake_amount > 0,
-            CarsaError::InvalidAmount
+            CarsaError::InvalidPoolLimits
         );
         require!(
             config.max_stake_per_user >= config.min_stake_amount,
-            CarsaError::InvalidAmount
+            CarsaError::InvalidPoolLimits
         );
         require!(
             config.apy_basis_points <= 10000, // Max 100% APY
-            CarsaError::InvalidAmount
+            CarsaError::InvalidApyConfig
+        );
+        require!(
+            config.early_withdraw_penalty_bps <= 10000, // Max 100% penalty
+            CarsaError::InvalidPenaltyConfig
+        );
+        require!(
+            config.max_deployment_bps <= 10000, // Max 100% of principal
+            CarsaError::InvalidDeploymentCap
+        );
+        require!(
+            config.insurance_fee_bps < 10000, // Depositors must be credited something
+            CarsaError::InvalidInsuranceFee
         );
+        require!(config.tiers_valid(), CarsaError::InvalidTierConfig);
+        require!(config.fee_valid(), CarsaError::InvalidFeeConfig);
+
+        // The vault must hold exactly what the pool credits
+        reject_unsafe_mint_extensions(&ctx.accounts.voucher_mint.to_account_info())?;
 
         // Initialize pool state
         pool_state.pool_authority = ctx.accounts.pool_authority.key();
@@ -89,16 +138,42 @@ impl InitializePool<'_> {
         pool_state.reward_index = 0;
         pool_state.created_at = clock.unix_timestamp;
         pool_state.last_yield_update = clock.unix_timestamp;
+        pool_state.pool_id = pool_id;
+        pool_state.mint_keyed = true;
+        pool_state.penalty_reserve = 0;
+        pool_state.deployment_destination = Pubkey::default();
+        pool_state.deployed_amount = 0;
+        pool_state.guardian = Pubkey::default();
+        pool_state.deposits_paused = false;
+        pool_state.withdrawals_paused = false;
+        pool_state.dust_carry = 0;
+        pool_state.cumulative_losses = 0;
+        pool_state.loss_index = 0;
+        pool_state.weighted_total_staked = 0;
+        pool_state.pending_pool_authority = Pubkey::default();
+        pool_state.receipt_mint = ctx.accounts.receipt_mint.key();
+        pool_state.protocol_fees_accrued = 0;
+        pool_state.guaranteed_yield_owed = 0;
+        pool_state.last_verified_index = 0;
+        pool_state.queued_unstake_amount = 0;
+        pool_state.insurance_reserve = 0;
+        pool_state.insurance_drawn = 0;
+        pool_state.event_sequence = 0;
         pool_state.bump = ctx.bumps.pool_state;
 
-        msg!("Voucher pool initialized successfully");
-        msg!("Pool Authority: {}", pool_state.pool_authority);
-        msg!("Pool Delegate: {}", pool_state.pool_delegate);
-        msg!("Vault ATA: {}", pool_state.vault_ata);
-        msg!("Min Stake: {}", pool_state.config.min_stake_amount);
+        log_verbose!("Voucher pool initialized successfully");
+        log_verbose!("Pool ID: {}", pool_id);
+        log_verbose!("Pool Authority: {}", pool_state.pool_authority);
+        log_verbose!("Pool Delegate: {}", pool_state.pool_delegate);
+        log_verbose!("Vault ATA: {}", pool_state.vault_ata);
+        log_verbose!("Receipt Mint: {}", pool_state.receipt_mint);
+        log_verbose!("Min Stake: {}", { pool_state.config.min_stake_amount });
 
         // Emit event
-        emit!(PoolInitializedEvent {
+        emit_cpi!(PoolInitializedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            pool_id,
             pool_authority: pool_state.pool_authority,
             pool_delegate: pool_state.pool_delegate,
             vault_ata: pool_state.vault_ata,
@@ -117,9 +192,11 @@ impl InitializePool<'_> {
 
 /// Deposit voucher tokens into the staking pool
 /// Uses delegated authority (user has pre-approved the pool delegate)
+#[event_cpi]
 #[derive(Accounts)]
 pub struct DepositVoucher<'info> {
     /// The user whose tokens are being deposited
+    /// Must co-sign when the pool is configured to auto-revoke leftover delegation
     /// CHECK: We validate ownership through the token account
     pub user: AccountInfo<'info>,
 
@@ -131,12 +208,17 @@ pub struct DepositVoucher<'info> {
     /// The pool state account
     #[account(
         mut,
-        seeds = [POOL_STATE_SEED],
-        bump = pool_state.bump,
-        constraint = pool_delegate.key() == pool_state.pool_delegate @ CarsaError::UnauthorizedDelegate,
-        constraint = pool_state.config.deposits_enabled @ CarsaError::DepositsDisabled
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate,
+        constraint = pool_state.load()?.config.deposits_enabled @ CarsaError::DepositsDisabled,
+        constraint = !pool_state.load()?.deposits_paused @ CarsaError::DepositsPaused
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
 
     /// User's stake record (created if doesn't exist)
     #[account(
@@ -148,101 +230,227 @@ pub struct DepositVoucher<'info> {
     )]
     pub user_stake_record: Account<'info, UserStakeRecord>,
 
+    /// The user's whitelist approval, required only when the pool is whitelist-gated
+    #[account(
+        seeds = [STAKE_WHITELIST_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = stake_whitelist_entry.bump
+    )]
+    pub stake_whitelist_entry: Option<Account<'info, StakeWhitelistEntry>>,
+
+    /// History entry for this deposit, required only when the pool records history
+    #[account(
+        init,
+        payer = pool_delegate,
+        space = StakeActionRecord::LEN,
+        seeds = [
+            STAKE_ACTION_SEED,
+            pool_state.key().as_ref(),
+            user.key().as_ref(),
+            user_stake_record.action_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_action_record: Option<Account<'info, StakeActionRecord>>,
+
     /// User's voucher token account (source)
+    /// Must have the pool delegate approved as its SPL delegate
     #[account(
         mut,
-        constraint = user_voucher_ata.mint == pool_state.voucher_mint @ CarsaError::InvalidMint,
-        constraint = user_voucher_ata.owner == user.key() @ CarsaError::InvalidOwner
+        constraint = user_voucher_ata.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint,
+        constraint = user_voucher_ata.owner == user.key() @ CarsaError::InvalidOwner,
+        constraint = user_voucher_ata.delegate.contains(&pool_state.load()?.pool_delegate) @ CarsaError::MissingDelegation
     )]
-    pub user_voucher_ata: Account<'info, TokenAccount>,
+    pub user_voucher_ata: InterfaceAccount<'info, TokenAccount>,
 
     /// Pool vault token account (destination)
     #[account(
         mut,
-        constraint = pool_vault_ata.key() == pool_state.vault_ata @ CarsaError::InvalidVault
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's receipt token account (created if it doesn't exist)
+    #[account(
+        init_if_needed,
+        payer = pool_delegate,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = user
     )]
-    pub pool_vault_ata: Account<'info, TokenAccount>,
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault authority PDA (receipt mint authority)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
 
     /// System program for account creation
     pub system_program: Program<'info, System>,
 
     /// Token program for SPL token operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 impl DepositVoucher<'_> {
+    /// Compute units a first deposit, which creates the stake record and receipt account,
+    /// may use; checked by `program-tests`
+    pub const COMPUTE_UNIT_CEILING: u64 = 59_300;
+
     pub fn handler(ctx: Context<DepositVoucher>, amount: u64) -> Result<()> {
-        let pool_state = &mut ctx.accounts.pool_state;
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
         let user_stake_record = &mut ctx.accounts.user_stake_record;
         let clock = Clock::get()?;
 
-        // Validate amount
-        require!(amount > 0, CarsaError::InvalidAmount);
-        require!(
-            amount >= pool_state.config.min_stake_amount,
-            CarsaError::InvalidAmount
-        );
+        let amounts = check_deposit(
+            pool_state,
+            user_stake_record,
+            amount,
+            ctx.accounts.stake_whitelist_entry.is_some(),
+            ctx.accounts.stake_action_record.is_some(),
+        )?;
 
-        // Check user hasn't exceeded max stake
-        let new_user_total = user_stake_record
-            .staked_amount
-            .checked_add(amount)
-            .ok_or(CarsaError::Overflow)?;
+        // Check the user approved enough for this deposit
+        let delegated_amount = ctx.accounts.user_voucher_ata.delegated_amount;
         require!(
-            new_user_total <= pool_state.config.max_stake_per_user,
-            CarsaError::ExceedsMaxStake
+            delegated_amount >= amount,
+            CarsaError::InsufficientDelegation
         );
 
-        // Initialize user stake record if this is their first stake
-        if user_stake_record.staked_amount == 0 {
-            user_stake_record.user = ctx.accounts.user.key();
-            user_stake_record.pool = pool_state.key();
-            user_stake_record.user_reward_index = pool_state.reward_index;
-            user_stake_record.total_yield_claimed = 0;
-            user_stake_record.staked_at = clock.unix_timestamp;
-            user_stake_record.bump = ctx.bumps.user_stake_record;
-            
-            pool_state.total_stakers = pool_state
-                .total_stakers
-                .checked_add(1)
-                .ok_or(CarsaError::Overflow)?;
+        // Revoking leftover approval needs the owner's signature
+        let revoke_leftover = pool_state.config.auto_revoke_delegation;
+        if revoke_leftover {
+            require!(
+                ctx.accounts.user.is_signer,
+                CarsaError::UserSignatureRequired
+            );
         }
 
+        open_or_checkpoint(
+            pool_state,
+            user_stake_record,
+            ctx.accounts.user.key(),
+            ctx.accounts.pool_state.key(),
+            ctx.bumps.user_stake_record,
+            ctx.accounts.pool_delegate.key(),
+            clock.unix_timestamp,
+        )?;
+
         // Transfer tokens from user to vault using delegated authority
         // The user must have already approved the pool_delegate
         let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.user_voucher_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
                 to: ctx.accounts.pool_vault_ata.to_account_info(),
                 authority: ctx.accounts.pool_delegate.to_account_info(),
             },
         );
-        token::transfer(transfer_ctx, amount)?;
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
 
-        // Update pool state
-        pool_state.total_voucher_staked = pool_state
-            .total_voucher_staked
-            .checked_add(amount)
-            .ok_or(CarsaError::Overflow)?;
+        // Revoke whatever approval remains after the transfer
+        // SPL Token already clears the delegate when the allowance is used up exactly
+        let remaining_delegation = delegated_amount - amount;
+        if revoke_leftover && remaining_delegation > 0 {
+            let revoke_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Revoke {
+                    source: ctx.accounts.user_voucher_ata.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            );
+            token_interface::revoke(revoke_ctx)?;
 
-        // Update user stake record
-        user_stake_record.staked_amount = new_user_total;
-        user_stake_record.last_action_at = clock.unix_timestamp;
+            log_verbose!("Revoked leftover delegation: {}", remaining_delegation);
+        }
 
-        msg!("Voucher deposited successfully");
-        msg!("User: {}", ctx.accounts.user.key());
-        msg!("Amount: {}", amount);
-        msg!("New user total: {}", new_user_total);
-        msg!("Pool total staked: {}", pool_state.total_voucher_staked);
+        mint_receipts(
+            pool_state,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.receipt_mint.to_account_info(),
+            ctx.accounts.user_receipt_ata.to_account_info(),
+            ctx.accounts.pool_vault_authority.to_account_info(),
+            ctx.bumps.pool_vault_authority,
+            amounts.staked_amount,
+        )?;
+
+        credit_deposit(pool_state, user_stake_record, &amounts, clock.unix_timestamp)?;
+
+        // Append to the user's audit trail
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.stake_action_record.as_mut(),
+            ctx.bumps.stake_action_record,
+        ) {
+            entry
+                .record(
+                    user_stake_record,
+                    bump,
+                    StakeActionType::Deposit,
+                    amounts.staked_amount,
+                    pool_state.reward_index,
+                    clock.unix_timestamp,
+                )
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+
+            emit_cpi!(StakeActionRecordedEvent {
+                sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                pool: entry.pool,
+                user: entry.user,
+                action_index: entry.action_index,
+                action_type: entry.action_type,
+                amount: entry.amount,
+                resulting_balance: entry.resulting_balance,
+                reward_index: entry.reward_index,
+                timestamp: entry.timestamp,
+            });
+        }
+
+        log_verbose!("Voucher deposited successfully");
+        log_verbose!("User: {}", ctx.accounts.user.key());
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Insurance fee: {}", amounts.insurance_fee);
+        log_verbose!("New user total: {}", amounts.new_user_total);
+        log_verbose!("Locked until: {}", user_stake_record.lock_until);
+        log_verbose!("Pool total staked: {}", { pool_state.total_voucher_staked });
 
         // Emit event
-        emit!(VoucherDepositedEvent {
+        let max_total_staked = pool_state.config.max_total_staked;
+        emit_cpi!(VoucherDepositedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
             user: ctx.accounts.user.key(),
-            pool: pool_state.key(),
+            pool: ctx.accounts.pool_state.key(),
             amount,
-            new_user_total,
+            insurance_fee: amounts.insurance_fee,
+            new_user_total: amounts.new_user_total,
             pool_total_staked: pool_state.total_voucher_staked,
+            remaining_capacity: (max_total_staked > 0)
+                .then(|| max_total_staked.saturating_sub(pool_state.total_voucher_staked)),
             timestamp: clock.unix_timestamp,
         });
 
@@ -250,218 +458,437 @@ impl DepositVoucher<'_> {
     }
 }
 
-// ============================================================================
-// Record Yield Instruction
-// ============================================================================
+/// Amounts of a deposit that passed the pool's limits
+struct DepositAmounts {
+    /// Part of the deposit kept in the insurance reserve
+    insurance_fee: u64,
+    /// Part of the deposit credited to the stake
+    staked_amount: u64,
+    /// The stake after the deposit
+    new_user_total: u64,
+    /// The pool's total stake after the deposit
+    new_pool_total: u64,
+}
 
-/// Record yield earned from staking activities
-/// Called by backend after swapping vouchers to SOL and earning yield
-#[derive(Accounts)]
-pub struct RecordYield<'info> {
-    /// The pool delegate authority (backend service)
-    pub pool_delegate: Signer<'info>,
+/// Check a deposit of `amount` into `user_stake_record` against the pool's limits
+/// Per-user limits apply to the record's owner, whoever funds the deposit
+fn check_deposit(
+    pool_state: &PoolState,
+    user_stake_record: &UserStakeRecord,
+    amount: u64,
+    whitelisted: bool,
+    records_history: bool,
+) -> Result<DepositAmounts> {
+    // Validate amount
+    require!(amount > 0, CarsaError::InvalidAmount);
+    require!(
+        amount >= pool_state.config.min_stake_amount,
+        CarsaError::StakeBelowMinimum
+    );
 
-    /// The pool state account
-    #[account(
-        mut,
-        seeds = [POOL_STATE_SEED],
-        bump = pool_state.bump,
-        constraint = pool_delegate.key() == pool_state.pool_delegate @ CarsaError::UnauthorizedDelegate
-    )]
-    pub pool_state: Account<'info, PoolState>,
-}
+    // The insurance slice stays in the vault; the user is credited with the rest
+    let insurance_fee = pool_state
+        .config
+        .insurance_fee(amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    let staked_amount = amount
+        .checked_sub(insurance_fee)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
 
-impl RecordYield<'_> {
-    pub fn handler(ctx: Context<RecordYield>, sol_amount: u64) -> Result<()> {
-        let pool_state = &mut ctx.accounts.pool_state;
-        let clock = Clock::get()?;
+    // Check user hasn't exceeded max stake
+    let new_user_total = user_stake_record
+        .staked_amount
+        .checked_add(staked_amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    require!(
+        new_user_total <= pool_state.config.max_stake_per_user,
+        CarsaError::ExceedsMaxStake
+    );
 
-        require!(sol_amount > 0, CarsaError::InvalidAmount);
+    // Check the pool hasn't reached its TVL cap
+    let new_pool_total = pool_state
+        .total_voucher_staked
+        .checked_add(staked_amount)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    let max_total_staked = pool_state.config.max_total_staked;
+    require!(
+        max_total_staked == 0 || new_pool_total <= max_total_staked,
+        CarsaError::PoolCapReached
+    );
 
-        // Update yield tracking
-        pool_state.total_sol_staked = pool_state
-            .total_sol_staked
-            .checked_add(sol_amount)
-            .ok_or(CarsaError::Overflow)?;
+    // Gated pools only accept wallets the authority has approved
+    if pool_state.config.whitelist_enabled {
+        require!(whitelisted, CarsaError::NotWhitelisted);
+    }
 
-        pool_state.total_yield_earned = pool_state
-            .total_yield_earned
-            .checked_add(sol_amount)
-            .ok_or(CarsaError::Overflow)?;
+    require!(
+        pool_state.config.record_history == records_history,
+        CarsaError::InvalidStakeHistory
+    );
 
-        // Update reward index for proportional yield distribution
-        if pool_state.total_voucher_staked > 0 {
-            let yield_per_token = (sol_amount as u128)
-                .checked_mul(1_000_000_000_000) // Scale factor for precision
-                .ok_or(CarsaError::Overflow)?
-                .checked_div(pool_state.total_voucher_staked as u128)
-                .ok_or(CarsaError::DivisionByZero)?;
+    Ok(DepositAmounts {
+        insurance_fee,
+        staked_amount,
+        new_user_total,
+        new_pool_total,
+    })
+}
 
-            pool_state.reward_index = pool_state
-                .reward_index
-                .checked_add(yield_per_token)
-                .ok_or(CarsaError::Overflow)?;
+/// Initialize the stake record on its first stake, or settle its yield before a deposit
+/// changes its weight
+fn open_or_checkpoint(
+    pool_state: &mut PoolState,
+    user_stake_record: &mut UserStakeRecord,
+    user: Pubkey,
+    pool: Pubkey,
+    bump: u8,
+    rent_payer: Pubkey,
+    now: i64,
+) -> Result<()> {
+    // Initialize user stake record if this is their first stake
+    if user_stake_record.staked_amount == 0 {
+        user_stake_record.user = user;
+        user_stake_record.pool = pool;
+        user_stake_record.user_reward_index = pool_state.reward_index;
+        user_stake_record.user_loss_index = pool_state.loss_index;
+        user_stake_record.total_yield_claimed = 0;
+        user_stake_record.staked_at = now;
+        user_stake_record.bump = bump;
+
+        // Only a record this deposit just created has no payer yet; a record
+        // emptied and reused keeps the payer that funded it
+        if user_stake_record.rent_payer == Pubkey::default() {
+            user_stake_record.rent_payer = rent_payer;
         }
+        
+        pool_state.total_stakers = pool_state
+            .total_stakers
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+    } else {
+        // Settle yield at the current weight before the deposit changes it
+        user_stake_record
+            .checkpoint(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+    }
 
-        pool_state.last_yield_update = clock.unix_timestamp;
+    Ok(())
+}
 
-        msg!("Yield recorded successfully");
-        msg!("SOL amount: {}", sol_amount);
-        msg!("Total yield earned: {}", pool_state.total_yield_earned);
-        msg!("New reward index: {}", pool_state.reward_index);
+/// Mint sLOKAL receipts 1:1 for the credited stake
+pub(crate) fn mint_receipts<'info>(
+    pool_state: &PoolState,
+    token_program: AccountInfo<'info>,
+    receipt_mint: AccountInfo<'info>,
+    receipt_account: AccountInfo<'info>,
+    pool_vault_authority: AccountInfo<'info>,
+    vault_authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    let mint_seed = pool_state.mint_seed();
+    let pool_seed = pool_id_seed(pool_state.pool_id);
+    let vault_authority_seeds = &[
+        POOL_VAULT_AUTHORITY_SEED,
+        mint_seed.as_ref(),
+        pool_seed.as_ref(),
+        &[vault_authority_bump],
+    ];
+    let signer_seeds = &[&vault_authority_seeds[..]];
 
-        // Emit event
-        emit!(YieldRecordedEvent {
-            pool: pool_state.key(),
-            sol_amount,
-            total_yield_earned: pool_state.total_yield_earned,
-            reward_index: pool_state.reward_index,
-            timestamp: clock.unix_timestamp,
-        });
+    let mint_ctx = CpiContext::new_with_signer(
+        token_program,
+        MintTo {
+            mint: receipt_mint,
+            to: receipt_account,
+            authority: pool_vault_authority,
+        },
+        signer_seeds,
+    );
+    token_interface::mint_to(mint_ctx, amount)
+}
 
-        Ok(())
-    }
+/// Credit a checked deposit to the pool and the stake record, then re-tier and lock the stake
+fn credit_deposit(
+    pool_state: &mut PoolState,
+    user_stake_record: &mut UserStakeRecord,
+    amounts: &DepositAmounts,
+    now: i64,
+) -> Result<()> {
+    // Update pool state
+    pool_state.total_voucher_staked = amounts.new_pool_total;
+    pool_state.insurance_reserve = pool_state
+        .insurance_reserve
+        .checked_add(amounts.insurance_fee)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    // Update user stake record
+    user_stake_record.staked_amount = amounts.new_user_total;
+    user_stake_record.last_action_at = now;
+    user_stake_record.last_deposit_at = now;
+
+    // Re-tier the user's stake
+    let new_weight = pool_state
+        .config
+        .stake_weight(amounts.new_user_total)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    pool_state
+        .reweight(user_stake_record.weighted_stake, new_weight)
+        .ok_or(CarsaError::StateInconsistency)?;
+    user_stake_record.weighted_stake = new_weight;
+
+    // Lock the stake from this deposit, never shortening an existing lock
+    let lock_until = now
+        .checked_add(pool_state.config.lockup_seconds as i64)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    user_stake_record.lock_until = user_stake_record.lock_until.max(lock_until);
+
+    user_stake_record
+        .sync_view(
+            pool_state.reward_index,
+            pool_state.loss_index,
+            pool_state.weighted_total_staked,
+            now,
+        )
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    Ok(())
 }
 
 // ============================================================================
-// Redeem Voucher Instruction
+// Deposit For Beneficiary Instruction
 // ============================================================================
 
-/// Redeem staked vouchers and claim yield
-/// Allows users to unstake and withdraw their tokens plus earned yield
+/// Deposit voucher tokens into the staking pool on behalf of a beneficiary
+/// The funder signs and pays from its own token account; the stake belongs to the
+/// beneficiary, so only the beneficiary can redeem it
+#[event_cpi]
 #[derive(Accounts)]
-pub struct RedeemVoucher<'info> {
-    /// The user redeeming their stake
+pub struct DepositForBeneficiary<'info> {
+    /// The wallet funding the deposit, which also pays for any accounts it creates
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub funder: Signer<'info>,
+
+    /// The wallet the stake belongs to
+    /// CHECK: Only used as the stake owner and the receipt account's authority
+    #[account(
+        constraint = beneficiary.key() != funder.key() @ CarsaError::InvalidBeneficiary
+    )]
+    pub beneficiary: AccountInfo<'info>,
 
     /// The pool state account
     #[account(
         mut,
-        seeds = [POOL_STATE_SEED],
-        bump = pool_state.bump,
-        constraint = pool_state.config.withdrawals_enabled @ CarsaError::WithdrawalsDisabled
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_state.load()?.config.deposits_enabled @ CarsaError::DepositsDisabled,
+        constraint = !pool_state.load()?.deposits_paused @ CarsaError::DepositsPaused
     )]
-    pub pool_state: Account<'info, PoolState>,
+    pub pool_state: AccountLoader<'info, PoolState>,
 
-    /// User's stake record
+    /// Beneficiary's stake record (created if doesn't exist)
     #[account(
-        mut,
-        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
-        bump = user_stake_record.bump,
-        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+        init_if_needed,
+        payer = funder,
+        space = UserStakeRecord::LEN,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump
     )]
     pub user_stake_record: Account<'info, UserStakeRecord>,
 
-    /// User's voucher token account (destination)
+    /// The beneficiary's whitelist approval, required only when the pool is whitelist-gated
+    #[account(
+        seeds = [STAKE_WHITELIST_SEED, pool_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump = stake_whitelist_entry.bump
+    )]
+    pub stake_whitelist_entry: Option<Account<'info, StakeWhitelistEntry>>,
+
+    /// History entry for this deposit, required only when the pool records history
+    #[account(
+        init,
+        payer = funder,
+        space = StakeActionRecord::LEN,
+        seeds = [
+            STAKE_ACTION_SEED,
+            pool_state.key().as_ref(),
+            beneficiary.key().as_ref(),
+            user_stake_record.action_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_action_record: Option<Account<'info, StakeActionRecord>>,
+
+    /// Funder's voucher token account (source)
     #[account(
         mut,
-        constraint = user_voucher_ata.mint == pool_state.voucher_mint @ CarsaError::InvalidMint,
-        constraint = user_voucher_ata.owner == user.key() @ CarsaError::InvalidOwner
+        constraint = funder_voucher_ata.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint,
+        constraint = funder_voucher_ata.owner == funder.key() @ CarsaError::InvalidOwner
     )]
-    pub user_voucher_ata: Account<'info, TokenAccount>,
+    pub funder_voucher_ata: InterfaceAccount<'info, TokenAccount>,
 
-    /// Pool vault token account (source)
+    /// Pool vault token account (destination)
     #[account(
         mut,
-        constraint = pool_vault_ata.key() == pool_state.vault_ata @ CarsaError::InvalidVault
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
     )]
-    pub pool_vault_ata: Account<'info, TokenAccount>,
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
 
-    /// Pool vault authority PDA (signer for transfer)
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// Beneficiary's receipt token account (created if it doesn't exist)
+    #[account(
+        init_if_needed,
+        payer = funder,
+        associated_token::mint = receipt_mint,
+        associated_token::authority = beneficiary
+    )]
+    pub beneficiary_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault authority PDA (receipt mint authority)
     /// CHECK: PDA validation is handled by seeds constraint
     #[account(
-        seeds = [POOL_VAULT_AUTHORITY_SEED],
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
         bump
     )]
     pub pool_vault_authority: AccountInfo<'info>,
 
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+
     /// Token program for SPL token operations
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Associated token program for creating the receipt account
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
-impl RedeemVoucher<'_> {
-    pub fn handler(ctx: Context<RedeemVoucher>, amount: u64) -> Result<()> {
-        let pool_state = &mut ctx.accounts.pool_state;
+impl DepositForBeneficiary<'_> {
+    pub fn handler(ctx: Context<DepositForBeneficiary>, amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
         let user_stake_record = &mut ctx.accounts.user_stake_record;
         let clock = Clock::get()?;
 
-        // Validate amount
-        require!(amount > 0, CarsaError::InvalidAmount);
-        require!(
-            amount <= user_stake_record.staked_amount,
-            CarsaError::InsufficientBalance
-        );
-
-        // Calculate claimable yield based on reward index difference
-        let reward_index_diff = pool_state
-            .reward_index
-            .checked_sub(user_stake_record.user_reward_index)
-            .ok_or(CarsaError::Overflow)?;
-
-        let claimable_yield = (user_stake_record.staked_amount as u128)
-            .checked_mul(reward_index_diff)
-            .ok_or(CarsaError::Overflow)?
-            .checked_div(1_000_000_000_000) // Undo scale factor
-            .ok_or(CarsaError::DivisionByZero)? as u64;
+        // Limits are the beneficiary's, counting their own deposits and funded ones alike
+        let amounts = check_deposit(
+            pool_state,
+            user_stake_record,
+            amount,
+            ctx.accounts.stake_whitelist_entry.is_some(),
+            ctx.accounts.stake_action_record.is_some(),
+        )?;
 
-        // Transfer voucher tokens back to user
-        let vault_authority_bump = ctx.bumps.pool_vault_authority;
-        let vault_authority_seeds = &[POOL_VAULT_AUTHORITY_SEED, &[vault_authority_bump]];
-        let signer_seeds = &[&vault_authority_seeds[..]];
+        open_or_checkpoint(
+            pool_state,
+            user_stake_record,
+            ctx.accounts.beneficiary.key(),
+            ctx.accounts.pool_state.key(),
+            ctx.bumps.user_stake_record,
+            ctx.accounts.funder.key(),
+            clock.unix_timestamp,
+        )?;
+        user_stake_record.funded_by = ctx.accounts.funder.key();
 
-        let transfer_ctx = CpiContext::new_with_signer(
+        // Transfer tokens from the funder to the vault under the funder's own signature
+        let transfer_ctx = CpiContext::new(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.pool_vault_ata.to_account_info(),
-                to: ctx.accounts.user_voucher_ata.to_account_info(),
-                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.funder_voucher_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.pool_vault_ata.to_account_info(),
+                authority: ctx.accounts.funder.to_account_info(),
             },
-            signer_seeds,
         );
-        token::transfer(transfer_ctx, amount)?;
-
-        // Update pool state
-        pool_state.total_voucher_staked = pool_state
-            .total_voucher_staked
-            .checked_sub(amount)
-            .ok_or(CarsaError::Overflow)?;
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
 
-        // Update user stake record
-        user_stake_record.staked_amount = user_stake_record
-            .staked_amount
-            .checked_sub(amount)
-            .ok_or(CarsaError::Overflow)?;
+        mint_receipts(
+            pool_state,
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.receipt_mint.to_account_info(),
+            ctx.accounts.beneficiary_receipt_ata.to_account_info(),
+            ctx.accounts.pool_vault_authority.to_account_info(),
+            ctx.bumps.pool_vault_authority,
+            amounts.staked_amount,
+        )?;
 
-        user_stake_record.user_reward_index = pool_state.reward_index;
-        user_stake_record.total_yield_claimed = user_stake_record
-            .total_yield_claimed
-            .checked_add(claimable_yield)
-            .ok_or(CarsaError::Overflow)?;
+        credit_deposit(pool_state, user_stake_record, &amounts, clock.unix_timestamp)?;
 
-        user_stake_record.last_action_at = clock.unix_timestamp;
+        // Append to the beneficiary's audit trail
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.stake_action_record.as_mut(),
+            ctx.bumps.stake_action_record,
+        ) {
+            entry
+                .record(
+                    user_stake_record,
+                    bump,
+                    StakeActionType::Deposit,
+                    amounts.staked_amount,
+                    pool_state.reward_index,
+                    clock.unix_timestamp,
+                )
+                .ok_or(CarsaError::ArithmeticOverflow)?;
 
-        // If user has fully withdrawn, decrement staker count
-        if user_stake_record.staked_amount == 0 {
-            pool_state.total_stakers = pool_state
-                .total_stakers
-                .checked_sub(1)
-                .ok_or(CarsaError::Overflow)?;
+            emit_cpi!(StakeActionRecordedEvent {
+                sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                pool: entry.pool,
+                user: entry.user,
+                action_index: entry.action_index,
+                action_type: entry.action_type,
+                amount: entry.amount,
+                resulting_balance: entry.resulting_balance,
+                reward_index: entry.reward_index,
+                timestamp: entry.timestamp,
+            });
         }
 
-        msg!("Voucher redeemed successfully");
-        msg!("User: {}", ctx.accounts.user.key());
-        msg!("Amount redeemed: {}", amount);
-        msg!("Yield claimed: {}", claimable_yield);
-        msg!("Remaining stake: {}", user_stake_record.staked_amount);
+        log_verbose!("Voucher deposited for beneficiary");
+        log_verbose!("Funder: {}", ctx.accounts.funder.key());
+        log_verbose!("Beneficiary: {}", ctx.accounts.beneficiary.key());
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("New beneficiary total: {}", amounts.new_user_total);
 
-        // Emit event
-        emit!(VoucherRedeemedEvent {
-            user: ctx.accounts.user.key(),
-            pool: pool_state.key(),
-            amount_redeemed: amount,
-            yield_claimed: claimable_yield,
-            remaining_stake: user_stake_record.staked_amount,
+        // Emit events; the deposit event keeps stake indexers working unchanged
+        let max_total_staked = pool_state.config.max_total_staked;
+        emit_cpi!(VoucherDepositedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: ctx.accounts.beneficiary.key(),
+            pool: ctx.accounts.pool_state.key(),
+            amount,
+            insurance_fee: amounts.insurance_fee,
+            new_user_total: amounts.new_user_total,
+            pool_total_staked: pool_state.total_voucher_staked,
+            remaining_capacity: (max_total_staked > 0)
+                .then(|| max_total_staked.saturating_sub(pool_state.total_voucher_staked)),
+            timestamp: clock.unix_timestamp,
+        });
+        emit_cpi!(StakeFundedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            funder: ctx.accounts.funder.key(),
+            beneficiary: ctx.accounts.beneficiary.key(),
+            amount,
             timestamp: clock.unix_timestamp,
         });
 
@@ -470,55 +897,2771 @@ impl RedeemVoucher<'_> {
 }
 
 // ============================================================================
-// Update Pool Config Instruction
+// Record Yield Instruction
 // ============================================================================
 
-/// Update pool configuration settings
-/// Only the pool authority can perform this operation
+/// Record yield earned from staking activities
+/// Called by backend after swapping vouchers to SOL and earning yield
+#[event_cpi]
 #[derive(Accounts)]
-pub struct UpdatePoolConfig<'info> {
-    /// The pool authority (admin)
-    pub pool_authority: Signer<'info>,
+#[instruction(sol_amount: u64, yield_batch_id: [u8; 32])]
+pub struct RecordYield<'info> {
+    /// The pool delegate authority (backend service)
+    #[account(mut)]
+    pub pool_delegate: Signer<'info>,
 
     /// The pool state account
     #[account(
         mut,
-        seeds = [POOL_STATE_SEED],
-        bump = pool_state.bump,
-        constraint = pool_authority.key() == pool_state.pool_authority @ CarsaError::Unauthorized
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
     )]
-    pub pool_state: Account<'info, PoolState>,
-}
+    pub pool_state: AccountLoader<'info, PoolState>,
 
-impl UpdatePoolConfig<'_> {
-    pub fn handler(ctx: Context<UpdatePoolConfig>, new_config: PoolConfig) -> Result<()> {
-        let pool_state = &mut ctx.accounts.pool_state;
-        let clock = Clock::get()?;
+    /// Batch marker, initialization fails if this batch was already recorded
+    #[account(
+        init,
+        payer = pool_delegate,
+        space = YieldBatch::LEN,
+        seeds = [YIELD_BATCH_SEED, pool_state.key().as_ref(), yield_batch_id.as_ref()],
+        bump
+    )]
+    pub yield_batch: Account<'info, YieldBatch>,
 
-        // Validate new configuration
-        require!(
-            new_config.min_stake_amount > 0,
-            CarsaError::InvalidAmount
+    pub system_program: Program<'info, System>,
+}
+
+impl RecordYield<'_> {
+    pub fn handler(
+        ctx: Context<RecordYield>,
+        sol_amount: u64,
+        yield_batch_id: [u8; 32],
+        expected_previous_index: Option<u128>,
+        external_sequence: u64,
+    ) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let yield_batch = &mut ctx.accounts.yield_batch;
+        let clock = Clock::get()?;
+
+        require!(sol_amount > 0, CarsaError::InvalidAmount);
+
+        // Batch ids only catch a batch seen before; the journal position also catches a
+        // replay under new ids, such as after an upgrade
+        let expected_sequence = pool_state
+            .last_external_sequence
+            .checked_add(1)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            external_sequence == expected_sequence,
+            CarsaError::ExternalSequenceMismatch
+        );
+        pool_state.last_external_sequence = external_sequence;
+
+        // Lets the backend detect an interleaved update from another submitter
+        if let Some(expected_index) = expected_previous_index {
+            require!(
+                pool_state.reward_index == expected_index,
+                CarsaError::RewardIndexMismatch
+            );
+        }
+
+        yield_batch.pool = ctx.accounts.pool_state.key();
+        yield_batch.batch_id = yield_batch_id;
+        yield_batch.sol_amount = sol_amount;
+        yield_batch.recorded_at = clock.unix_timestamp;
+        yield_batch.bump = ctx.bumps.yield_batch;
+
+        // Update yield tracking
+        pool_state.total_sol_staked = pool_state
+            .total_sol_staked
+            .checked_add(sol_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        pool_state.total_yield_earned = pool_state
+            .total_yield_earned
+            .checked_add(sol_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // The protocol's cut is set aside; only the net yield reaches stakers
+        let performance_fee = pool_state
+            .config
+            .performance_fee(sol_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let net_amount = sol_amount
+            .checked_sub(performance_fee)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state.protocol_fees_accrued = pool_state
+            .protocol_fees_accrued
+            .checked_add(performance_fee)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Update reward index for proportional yield distribution
+        if pool_state.weighted_total_staked > 0 {
+            let (yield_per_token, dust_carry) = reward_index_increment(
+                net_amount,
+                pool_state.dust_carry,
+                pool_state.weighted_total_staked,
+            )
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+            pool_state.reward_index = pool_state
+                .reward_index
+                .checked_add(yield_per_token)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            pool_state.dust_carry = dust_carry;
+        }
+
+        pool_state.last_yield_update = clock.unix_timestamp;
+
+        log_verbose!("Yield recorded successfully");
+        log_verbose!("SOL amount: {}", sol_amount);
+        log_verbose!("Performance fee: {}", performance_fee);
+        log_verbose!("Total yield earned: {}", { pool_state.total_yield_earned });
+        log_verbose!("New reward index: {}", { pool_state.reward_index });
+
+        // Emit event
+        emit_cpi!(YieldRecordedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            yield_batch_id,
+            external_sequence,
+            sol_amount,
+            performance_fee,
+            net_amount,
+            total_yield_earned: pool_state.total_yield_earned,
+            reward_index: pool_state.reward_index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Reset External Sequence Instruction
+// ============================================================================
+
+/// Move the pool's yield journal position, for a backend whose journal was genuinely reset
+/// The next `record_yield` must pass `last_external_sequence + 1`
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ResetExternalSequence<'info> {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl ResetExternalSequence<'_> {
+    pub fn handler(ctx: Context<ResetExternalSequence>, last_external_sequence: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let old_sequence = pool_state.last_external_sequence;
+        pool_state.last_external_sequence = last_external_sequence;
+
+        // Logged even without verbose logs: a reset disables replay protection for
+        // whatever the journal replays next
+        msg!(
+            "WARNING: pool {} yield journal position reset from {} to {}",
+            ctx.accounts.pool_state.key(),
+            old_sequence,
+            last_external_sequence
+        );
+
+        emit_cpi!(ExternalSequenceResetEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            pool_delegate: ctx.accounts.pool_delegate.key(),
+            last_external_sequence: Changed { old: old_sequence, new: last_external_sequence },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Record Loss Instruction
+// ============================================================================
+
+/// Record a loss on deployed capital
+/// Reduces what stakers can claim in proportion to their stake
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordLoss<'info> {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl RecordLoss<'_> {
+    pub fn handler(ctx: Context<RecordLoss>, sol_amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(sol_amount > 0, CarsaError::InvalidAmount);
+
+        pool_state.total_sol_staked = pool_state
+            .total_sol_staked
+            .checked_sub(sol_amount)
+            .ok_or(CarsaError::LossExceedsPoolValue)?;
+
+        pool_state.cumulative_losses = pool_state
+            .cumulative_losses
+            .checked_add(sol_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Update loss index for proportional loss distribution
+        if pool_state.weighted_total_staked > 0 {
+            let loss_per_token =
+                loss_index_increment(sol_amount, pool_state.weighted_total_staked)
+                    .ok_or(CarsaError::ArithmeticOverflow)?;
+
+            pool_state.loss_index = pool_state
+                .loss_index
+                .checked_add(loss_per_token)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+        }
+
+        log_verbose!("Loss recorded successfully");
+        log_verbose!("SOL amount: {}", sol_amount);
+        log_verbose!("Cumulative losses: {}", { pool_state.cumulative_losses });
+        log_verbose!("New loss index: {}", { pool_state.loss_index });
+
+        // Emit event
+        emit_cpi!(LossRecordedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            sol_amount,
+            cumulative_losses: pool_state.cumulative_losses,
+            loss_index: pool_state.loss_index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Redeem Voucher Instruction
+// ============================================================================
+
+/// Redeem staked vouchers and claim yield
+/// Allows users to unstake and withdraw their tokens plus earned yield
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RedeemVoucher<'info> {
+    /// The user redeeming their stake
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_state.load()?.config.withdrawals_enabled @ CarsaError::WithdrawalsDisabled,
+        constraint = !pool_state.load()?.withdrawals_paused @ CarsaError::WithdrawalsPaused,
+        constraint = !pool_state.load()?.config.queued_withdrawals @ CarsaError::QueuedWithdrawalsOnly
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// History entry for this withdrawal, required only when the pool records history
+    #[account(
+        init,
+        payer = user,
+        space = StakeActionRecord::LEN,
+        seeds = [
+            STAKE_ACTION_SEED,
+            pool_state.key().as_ref(),
+            user.key().as_ref(),
+            user_stake_record.action_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_action_record: Option<Account<'info, StakeActionRecord>>,
+
+    /// User's voucher token account (destination)
+    #[account(
+        mut,
+        constraint = user_voucher_ata.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint,
+        constraint = user_voucher_ata.owner == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_voucher_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault token account (source)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// Pool vault authority PDA (signer for transfer)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's receipt token account (receipts burned here)
+    #[account(
+        mut,
+        constraint = user_receipt_ata.mint == pool_state.load()?.receipt_mint @ CarsaError::InvalidMint,
+        constraint = user_receipt_ata.owner == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// System program for history entry creation
+    pub system_program: Program<'info, System>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl RedeemVoucher<'_> {
+    /// Compute units a redemption may use, checked by `program-tests`
+    /// Shares `DepositVoucher`'s, since it moves the same tokens back and burns the receipts
+    /// a deposit mints
+    pub const COMPUTE_UNIT_CEILING: u64 = 59_300;
+
+    /// Redeems `amount`, or the whole stake as it stands when the instruction runs when
+    /// `amount` is `None`
+    pub fn handler(ctx: Context<RedeemVoucher>, amount: Option<u64>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        // Validate amount
+        let amount = amount.unwrap_or(user_stake_record.staked_amount);
+        require!(amount > 0, CarsaError::InvalidAmount);
+        require!(
+            amount <= user_stake_record.staked_amount,
+            CarsaError::InsufficientBalance
+        );
+        require!(
+            amount <= user_stake_record.unlocked_amount(),
+            CarsaError::StakeLockedByAdvance
+        );
+
+        // Receipts transferred away can no longer redeem the stake behind them
+        require!(
+            amount <= ctx.accounts.user_receipt_ata.amount,
+            CarsaError::InsufficientReceipts
+        );
+
+        // Deposits must sit in the pool for the cooldown before any withdrawal
+        let since_deposit = clock
+            .unix_timestamp
+            .checked_sub(user_stake_record.last_deposit_at)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            since_deposit >= pool_state.config.withdraw_cooldown_seconds as i64,
+            CarsaError::CooldownActive
+        );
+
+        require!(
+            pool_state.config.record_history == ctx.accounts.stake_action_record.is_some(),
+            CarsaError::InvalidStakeHistory
+        );
+
+        // Calculate claimable yield from the reward and loss index differences
+        let claimable_yield = user_stake_record
+            .claimable_yield(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Withdrawals before the lock expires are penalized, or rejected when there is no penalty
+        let mut penalty = 0;
+        if clock.unix_timestamp < user_stake_record.lock_until {
+            let penalty_bps = pool_state.config.early_withdraw_penalty_bps;
+            require!(penalty_bps > 0, CarsaError::StakeLocked);
+
+            // Round down so the user never pays more than the configured rate
+            penalty = (amount as u128)
+                .checked_mul(penalty_bps as u128)
+                .ok_or(CarsaError::ArithmeticOverflow)?
+                .checked_div(10_000)
+                .ok_or(CarsaError::DivisionByZero)? as u64;
+        }
+        let payout = amount
+            .checked_sub(penalty)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Part of the principal may be deployed off-chain, and the reserves are not for redemptions
+        let redeemable = pool_state
+            .redeemable_balance(ctx.accounts.pool_vault_ata.amount)
+            .ok_or(CarsaError::InsufficientVaultLiquidity)?;
+        require!(payout <= redeemable, CarsaError::InsufficientVaultLiquidity);
+
+        // Transfer voucher tokens back to user
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.pool_vault_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.user_voucher_ata.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            payout,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
+
+        // Burn the receipts for the full redeemed amount, penalty included
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                from: ctx.accounts.user_receipt_ata.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token_interface::burn(burn_ctx, amount)?;
+
+        // Update pool state
+        pool_state.total_voucher_staked = pool_state
+            .total_voucher_staked
+            .checked_sub(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Penalty tokens stay in the vault
+        pool_state.penalty_reserve = pool_state
+            .penalty_reserve
+            .checked_add(penalty)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Update user stake record
+        user_stake_record.staked_amount = user_stake_record
+            .staked_amount
+            .checked_sub(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        user_stake_record.user_reward_index = pool_state.reward_index;
+        user_stake_record.user_loss_index = pool_state.loss_index;
+        user_stake_record.pending_payout = 0;
+        user_stake_record.total_yield_claimed = user_stake_record
+            .total_yield_claimed
+            .checked_add(claimable_yield)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // Re-tier the remaining stake
+        let new_weight = pool_state
+            .config
+            .stake_weight(user_stake_record.staked_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state
+            .reweight(user_stake_record.weighted_stake, new_weight)
+            .ok_or(CarsaError::StateInconsistency)?;
+        user_stake_record.weighted_stake = new_weight;
+
+        user_stake_record.last_action_at = clock.unix_timestamp;
+        user_stake_record
+            .sync_view(
+                pool_state.reward_index,
+                pool_state.loss_index,
+                pool_state.weighted_total_staked,
+                clock.unix_timestamp,
+            )
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        // If user has fully withdrawn, decrement staker count
+        if user_stake_record.staked_amount == 0 {
+            pool_state.total_stakers = pool_state
+                .total_stakers
+                .checked_sub(1)
+                .ok_or(CarsaError::StateInconsistency)?;
+        }
+
+        // Append to the user's audit trail
+        if let (Some(entry), Some(bump)) = (
+            ctx.accounts.stake_action_record.as_mut(),
+            ctx.bumps.stake_action_record,
+        ) {
+            entry
+                .record(
+                    user_stake_record,
+                    bump,
+                    StakeActionType::Withdrawal,
+                    amount,
+                    pool_state.reward_index,
+                    clock.unix_timestamp,
+                )
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+
+            emit_cpi!(StakeActionRecordedEvent {
+                sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                pool: entry.pool,
+                user: entry.user,
+                action_index: entry.action_index,
+                action_type: entry.action_type,
+                amount: entry.amount,
+                resulting_balance: entry.resulting_balance,
+                reward_index: entry.reward_index,
+                timestamp: entry.timestamp,
+            });
+        }
+
+        log_verbose!("Voucher redeemed successfully");
+        log_verbose!("User: {}", ctx.accounts.user.key());
+        log_verbose!("Amount redeemed: {}", amount);
+        log_verbose!("Early withdrawal penalty: {}", penalty);
+        log_verbose!("Yield claimed: {}", claimable_yield);
+        log_verbose!("Remaining stake: {}", user_stake_record.staked_amount);
+
+        // Emit event
+        emit_cpi!(VoucherRedeemedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool_state.key(),
+            amount_redeemed: amount,
+            penalty,
+            yield_claimed: claimable_yield,
+            remaining_stake: user_stake_record.staked_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Update Pool Config Instruction
+// ============================================================================
+
+/// Update pool configuration settings
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdatePoolConfig<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The mint configuration, read for its parameter timelock
+    /// CHECK: Seeds are verified; may be uninitialized when no mint exists
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+impl UpdatePoolConfig<'_> {
+    pub fn handler(ctx: Context<UpdatePoolConfig>, update: PoolConfigUpdate) -> Result<()> {
+        // Under a timelock the change goes through `queue_param_change` instead
+        let timelock = mint_config(&ctx.accounts.config)?.map_or(0, |c| c.param_timelock_seconds);
+        require!(timelock == 0, CarsaError::TimelockActive);
+
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let old_config = pool_state.config;
+        let new_config = merged_pool_config(&old_config, &update)?;
+        pool_state.config = new_config;
+
+        log_verbose!("Pool configuration updated");
+        log_verbose!("Deposits enabled: {}", pool_state.config.deposits_enabled);
+        log_verbose!("Withdrawals enabled: {}", pool_state.config.withdrawals_enabled);
+
+        // Emit event
+        emit_cpi!(PoolConfigUpdatedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            config: Changed { old: old_config, new: new_config },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// The mint config, or None when the mint is not initialized
+pub(crate) fn mint_config(config: &UncheckedAccount) -> Result<Option<LokalMintConfig>> {
+    if config.data_is_empty() {
+        return Ok(None);
+    }
+    let data = config.try_borrow_data()?;
+    Ok(Some(LokalMintConfig::try_deserialize(&mut &data[..])?))
+}
+
+/// `config` with `update` merged in, failing if the result is not a valid configuration
+pub(crate) fn merged_pool_config(
+    config: &PoolConfig,
+    update: &PoolConfigUpdate,
+) -> Result<PoolConfig> {
+    let new_config = config.merge(update);
+
+    // Validate the merged configuration
+    require!(
+        new_config.min_stake_amount > 0,
+        CarsaError::InvalidPoolLimits
+    );
+    require!(
+        new_config.max_stake_per_user >= new_config.min_stake_amount,
+        CarsaError::InvalidPoolLimits
+    );
+    require!(
+        new_config.apy_basis_points <= 10000,
+        CarsaError::InvalidApyConfig
+    );
+    require!(
+        new_config.early_withdraw_penalty_bps <= 10000,
+        CarsaError::InvalidPenaltyConfig
+    );
+    require!(
+        new_config.max_deployment_bps <= 10000,
+        CarsaError::InvalidDeploymentCap
+    );
+    require!(
+        new_config.insurance_fee_bps < 10000,
+        CarsaError::InvalidInsuranceFee
+    );
+    require!(new_config.tiers_valid(), CarsaError::InvalidTierConfig);
+    require!(new_config.fee_valid(), CarsaError::InvalidFeeConfig);
+
+    Ok(new_config)
+}
+
+// ============================================================================
+// Set Deployment Destination Instruction
+// ============================================================================
+
+/// Set the token account that receives vouchers withdrawn for deployment
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetDeploymentDestination<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The token account that will receive deployed vouchers
+    #[account(
+        constraint = destination.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl SetDeploymentDestination<'_> {
+    pub fn handler(ctx: Context<SetDeploymentDestination>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let old_destination = pool_state.deployment_destination;
+        pool_state.deployment_destination = ctx.accounts.destination.key();
+
+        log_verbose!("Deployment destination set to {}", pool_state.deployment_destination);
+
+        // Emit event
+        emit_cpi!(DeploymentDestinationSetEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            destination: Changed {
+                old: old_destination,
+                new: pool_state.deployment_destination,
+            },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Withdraw For Deployment Instruction
+// ============================================================================
+
+/// Withdraw staked vouchers from the vault so the backend can deploy them
+/// The delegate can deploy at most `max_deployment_bps` of the staked principal
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawForDeployment<'info> {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Pool vault token account (source)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// Pool vault authority PDA (signer for transfer)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// The deployment destination registered by the pool authority
+    #[account(
+        mut,
+        constraint = destination.key() == pool_state.load()?.deployment_destination @ CarsaError::InvalidDeploymentDestination
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl WithdrawForDeployment<'_> {
+    pub fn handler(ctx: Context<WithdrawForDeployment>, amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, CarsaError::InvalidAmount);
+
+        // Keep deployed principal within the configured share of total stake
+        let new_deployed_amount = pool_state
+            .deployed_amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let deployment_cap = (pool_state.total_voucher_staked as u128)
+            .checked_mul(pool_state.config.max_deployment_bps as u128)
+            .ok_or(CarsaError::ArithmeticOverflow)?
+            .checked_div(10_000)
+            .ok_or(CarsaError::DivisionByZero)? as u64;
+        require!(
+            new_deployed_amount <= deployment_cap,
+            CarsaError::DeploymentCapExceeded
+        );
+        require!(
+            ctx.accounts.pool_vault_ata.amount >= amount,
+            CarsaError::InsufficientVaultLiquidity
+        );
+
+        // Transfer vouchers from the vault to the deployment destination
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.pool_vault_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
+
+        pool_state.deployed_amount = new_deployed_amount;
+
+        log_verbose!("Vault withdrawn for deployment");
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Total deployed: {}", { pool_state.deployed_amount });
+
+        // Emit event
+        emit_cpi!(VaultWithdrawnEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            deployed_amount: pool_state.deployed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Return Principal Instruction
+// ============================================================================
+
+/// Return previously deployed vouchers to the vault
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReturnPrincipal<'info> {
+    /// The pool delegate authority (backend service)
+    /// Must be the owner or approved delegate of the source account
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Token account holding the returned vouchers
+    #[account(
+        mut,
+        constraint = source.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault token account (destination)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl ReturnPrincipal<'_> {
+    pub fn handler(ctx: Context<ReturnPrincipal>, amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, CarsaError::InvalidAmount);
+        require!(
+            amount <= pool_state.deployed_amount,
+            CarsaError::ReturnExceedsDeployed
+        );
+
+        // Transfer vouchers back into the vault
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.source.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.pool_vault_ata.to_account_info(),
+                authority: ctx.accounts.pool_delegate.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
+
+        pool_state.deployed_amount = pool_state
+            .deployed_amount
+            .checked_sub(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!("Principal returned to vault");
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Total deployed: {}", { pool_state.deployed_amount });
+
+        // Emit event
+        emit_cpi!(PrincipalReturnedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            source: ctx.accounts.source.key(),
+            amount,
+            deployed_amount: pool_state.deployed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Close Stake Record Instruction
+// ============================================================================
+
+/// Close an empty user stake record and reclaim its rent
+/// Callable by the staker or the pool delegate; rent goes back to the recorded `rent_payer`
+/// whoever signs
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseStakeRecord<'info> {
+    /// The staker or the pool delegate
+    #[account(
+        constraint = authority.key() == user_stake_record.user
+            || authority.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedCloser
+    )]
+    pub authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record to close
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user_stake_record.user.as_ref()],
+        bump = user_stake_record.bump
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// The account that paid the record's rent at initialization
+    /// CHECK: Only receives lamports, validated against the record's `rent_payer`
+    #[account(
+        mut,
+        constraint = rent_receiver.key() == user_stake_record.rent_payer @ CarsaError::RentReceiverMismatch
+    )]
+    pub rent_receiver: AccountInfo<'info>,
+}
+
+impl CloseStakeRecord<'_> {
+    pub fn handler(ctx: Context<CloseStakeRecord>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        require!(
+            user_stake_record.staked_amount == 0,
+            CarsaError::StakeRecordNotEmpty
+        );
+
+        // A recreated record would restart the counter and collide with existing history
+        require!(
+            user_stake_record.action_count == 0,
+            CarsaError::StakeHistoryExists
+        );
+
+        // Make sure closing doesn't forfeit yield the user has earned
+        let claimable_yield = user_stake_record
+            .claimable_yield(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(claimable_yield == 0, CarsaError::PendingYield);
+
+        log_verbose!("Stake record closed");
+        log_verbose!("User: {}", user_stake_record.user);
+        log_verbose!("Closed by: {}", ctx.accounts.authority.key());
+
+        // Emit event
+        emit_cpi!(StakeRecordClosedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: user_stake_record.user,
+            pool: ctx.accounts.pool_state.key(),
+            closed_by: ctx.accounts.authority.key(),
+            rent_receiver: ctx.accounts.rent_receiver.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Close Stake Records Instruction
+// ============================================================================
+
+/// Close a batch of fully exited stake records and reclaim their rent
+/// Stake records are passed as writable `remaining_accounts`
+/// Only the pool delegate can perform this operation, and only for records it paid for
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseStakeRecords<'info> {
+    /// The pool delegate authority (backend service), which receives the rent
+    #[account(mut)]
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl<'info> CloseStakeRecords<'info> {
+    pub fn handler(ctx: Context<'_, '_, 'info, 'info, CloseStakeRecords<'info>>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let pool_key = ctx.accounts.pool_state.key();
+        let pool_delegate = ctx.accounts.pool_delegate.key();
+        let clock = Clock::get()?;
+
+        let records = expect_stake_records(
+            ctx.remaining_accounts,
+            pool_key,
+            MAX_CLOSE_BATCH,
+            CarsaError::CloseBatchTooLarge,
+        )?;
+
+        let mut closed: u32 = 0;
+        let mut skipped: u32 = 0;
+
+        for record in records {
+            let record = record?;
+
+            // Same rules as close_stake_record, but a record that fails them is skipped
+            // so one returning staker does not sink the whole batch
+            let claimable_yield = record
+                .claimable_yield(pool_state.reward_index, pool_state.loss_index)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            if record.staked_amount > 0
+                || record.action_count > 0
+                || claimable_yield > 0
+                || record.rent_payer != pool_delegate
+            {
+                skipped += 1;
+                continue;
+            }
+
+            emit_cpi!(StakeRecordClosedEvent {
+                sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+                user: record.user,
+                pool: pool_key,
+                closed_by: pool_delegate,
+                rent_receiver: pool_delegate,
+                timestamp: clock.unix_timestamp,
+            });
+
+            record.close(ctx.accounts.pool_delegate.to_account_info())?;
+            closed += 1;
+        }
+
+        log_verbose!("Stake records closed");
+        log_verbose!("Closed: {}", closed);
+        log_verbose!("Skipped: {}", skipped);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Repair Stake Record Instruction
+// ============================================================================
+
+/// Resync a user's reward checkpoint to the pool's current reward index
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RepairStakeRecord<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The stake record to repair
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user_stake_record.user.as_ref()],
+        bump = user_stake_record.bump
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+}
+
+impl RepairStakeRecord<'_> {
+    pub fn handler(ctx: Context<RepairStakeRecord>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        let old_index = user_stake_record.user_reward_index;
+        user_stake_record.user_reward_index = pool_state.reward_index;
+        user_stake_record.user_loss_index = pool_state.loss_index;
+
+        log_verbose!("Stake record repaired");
+        log_verbose!("User: {}", user_stake_record.user);
+        log_verbose!("Reward index: {} -> {}", old_index, { pool_state.reward_index });
+
+        // Emit event
+        emit_cpi!(StakeRecordRepairedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            user: user_stake_record.user,
+            old_reward_index: old_index,
+            new_reward_index: pool_state.reward_index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Set Pool Guardian Instruction
+// ============================================================================
+
+/// Set the guardian key that can pause the pool in an emergency
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPoolGuardian<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl SetPoolGuardian<'_> {
+    pub fn handler(ctx: Context<SetPoolGuardian>, guardian: Pubkey) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let old_guardian = pool_state.guardian;
+        pool_state.guardian = guardian;
+
+        log_verbose!("Pool guardian set to {}", guardian);
+
+        // Emit event
+        emit_cpi!(PoolGuardianUpdatedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            guardian: Changed { old: old_guardian, new: guardian },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Set Pool Pause Instruction
+// ============================================================================
+
+/// Pause or unpause pool deposits and withdrawals without touching the pool config
+/// The pool guardian and the mint guardian can only pause; unpausing requires the
+/// pool authority
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetPoolPause<'info> {
+    /// The pool authority or a guardian, checked in the handler
+    pub authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The mint configuration, read for its guardian
+    /// CHECK: Seeds are verified; may be uninitialized when no mint exists
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump,
+    )]
+    pub config: UncheckedAccount<'info>,
+}
+
+impl SetPoolPause<'_> {
+    pub fn handler(
+        ctx: Context<SetPoolPause>,
+        pause_deposits: Option<bool>,
+        pause_withdrawals: Option<bool>,
+    ) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        // A guardian can only hit the brakes
+        let signer = ctx.accounts.authority.key();
+        if signer != pool_state.pool_authority {
+            let is_mint_guardian = mint_config(&ctx.accounts.config)?
+                .is_some_and(|config| config.is_guardian(&signer));
+            require!(
+                signer == pool_state.guardian || is_mint_guardian,
+                CarsaError::UnauthorizedPauser
+            );
+            require!(
+                pause_deposits != Some(false) && pause_withdrawals != Some(false),
+                CarsaError::GuardianCannotUnpause
+            );
+        }
+
+        let old_deposits_paused = pool_state.deposits_paused;
+        let old_withdrawals_paused = pool_state.withdrawals_paused;
+        if let Some(paused) = pause_deposits {
+            pool_state.deposits_paused = paused;
+        }
+        if let Some(paused) = pause_withdrawals {
+            pool_state.withdrawals_paused = paused;
+        }
+
+        log_verbose!("Pool pause updated");
+        log_verbose!("Deposits paused: {}", pool_state.deposits_paused);
+        log_verbose!("Withdrawals paused: {}", pool_state.withdrawals_paused);
+
+        // Emit event
+        emit_cpi!(PoolPausedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            deposits_paused: Changed {
+                old: old_deposits_paused,
+                new: pool_state.deposits_paused,
+            },
+            withdrawals_paused: Changed {
+                old: old_withdrawals_paused,
+                new: pool_state.withdrawals_paused,
+            },
+            triggered_by: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Propose Pool Authority Instruction
+// ============================================================================
+
+/// Nominate a new pool authority
+/// The nominee takes over only once they accept; proposing again replaces the nominee
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProposePoolAuthority<'info> {
+    /// The current pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl ProposePoolAuthority<'_> {
+    pub fn handler(ctx: Context<ProposePoolAuthority>, new_authority: Pubkey) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        pool_state.pending_pool_authority = new_authority;
+
+        log_verbose!("Pool authority proposed: {}", new_authority);
+
+        // Emit event
+        emit_cpi!(PoolAuthorityProposedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            current_authority: pool_state.pool_authority,
+            proposed_authority: new_authority,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Accept Pool Authority Instruction
+// ============================================================================
+
+/// Accept a pending pool authority nomination
+/// Must be signed by the nominee
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptPoolAuthority<'info> {
+    /// The nominated authority
+    pub new_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_state.load()?.pending_pool_authority != Pubkey::default() @ CarsaError::NoPendingAuthority,
+        constraint = new_authority.key() == pool_state.load()?.pending_pool_authority @ CarsaError::NotPendingAuthority
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl AcceptPoolAuthority<'_> {
+    pub fn handler(ctx: Context<AcceptPoolAuthority>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let previous_authority = pool_state.pool_authority;
+        pool_state.pool_authority = ctx.accounts.new_authority.key();
+        pool_state.pending_pool_authority = Pubkey::default();
+
+        log_verbose!("Pool authority transferred");
+        log_verbose!("From: {}", previous_authority);
+        log_verbose!("To: {}", pool_state.pool_authority);
+
+        // Emit event
+        emit_cpi!(PoolAuthorityTransferredEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            authority: Changed {
+                old: previous_authority,
+                new: pool_state.pool_authority,
+            },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Set Auto Compound Instruction
+// ============================================================================
+
+/// Opt a stake in or out of delegate-driven yield compounding
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetAutoCompound<'info> {
+    /// The user who owns the stake
+    pub user: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+}
+
+impl SetAutoCompound<'_> {
+    pub fn handler(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        user_stake_record.auto_compound = enabled;
+
+        log_verbose!("Auto compound for {}: {}", user_stake_record.user, enabled);
+
+        // Emit event
+        emit_cpi!(AutoCompoundSetEvent {
+            sequence: ctx.accounts.pool_state.load_mut()?.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            user: user_stake_record.user,
+            enabled,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Compound User Instruction
+// ============================================================================
+
+/// Restake a user's claimable yield as additional vouchers
+/// The delegate buys the vouchers with the yield off-chain and supplies them
+/// from its own token account at the rate it paid
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CompoundUser<'info> {
+    /// The pool delegate authority (backend service)
+    /// Must be the owner or approved delegate of the source account
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate,
+        constraint = !pool_state.load()?.deposits_paused @ CarsaError::DepositsPaused
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The stake record being compounded
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user_stake_record.user.as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.auto_compound @ CarsaError::AutoCompoundDisabled
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// Token account holding the purchased vouchers
+    #[account(
+        mut,
+        constraint = source.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault token account (destination)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// The user's receipt token account (credited with receipts for the compounded vouchers)
+    #[account(
+        mut,
+        constraint = user_receipt_ata.mint == pool_state.load()?.receipt_mint @ CarsaError::InvalidMint,
+        constraint = user_receipt_ata.owner == user_stake_record.user @ CarsaError::InvalidOwner
+    )]
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault authority PDA (receipt mint authority)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl CompoundUser<'_> {
+    /// `rate` is the lamports paid per `COMPOUND_RATE_SCALE` voucher base units
+    pub fn handler(ctx: Context<CompoundUser>, rate: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        require!(rate > 0, CarsaError::InvalidAmount);
+        require!(user_stake_record.staked_amount > 0, CarsaError::InsufficientBalance);
+
+        // Settle accrued yield so the whole claim sits in pending_payout
+        user_stake_record
+            .checkpoint(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let (tokens, yield_consumed) = compound_split(user_stake_record.pending_payout, rate)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(tokens > 0, CarsaError::NothingToCompound);
+
+        // Conservation: the yield consumed covers the credited vouchers' cost, rounded up
+        let scaled_cost = (tokens as u128)
+            .checked_mul(rate as u128)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let scaled_consumed = (yield_consumed as u128)
+            .checked_mul(COMPOUND_RATE_SCALE)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            scaled_consumed >= scaled_cost && yield_consumed <= user_stake_record.pending_payout,
+            CarsaError::StateInconsistency
+        );
+
+        // Compounded vouchers count against the same limits as deposits
+        let new_user_total = user_stake_record
+            .staked_amount
+            .checked_add(tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            new_user_total <= pool_state.config.max_stake_per_user,
+            CarsaError::ExceedsMaxStake
+        );
+        let new_pool_total = pool_state
+            .total_voucher_staked
+            .checked_add(tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        let max_total_staked = pool_state.config.max_total_staked;
+        require!(
+            max_total_staked == 0 || new_pool_total <= max_total_staked,
+            CarsaError::PoolCapReached
+        );
+
+        // Move the purchased vouchers into the vault
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.source.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.pool_vault_ata.to_account_info(),
+                authority: ctx.accounts.pool_delegate.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            tokens,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
+
+        // Compounded vouchers are backed by receipts like any deposit
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                to: ctx.accounts.user_receipt_ata.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::mint_to(mint_ctx, tokens)?;
+
+        pool_state.total_voucher_staked = new_pool_total;
+
+        // The remainder below one voucher's cost stays claimable
+        user_stake_record.pending_payout = user_stake_record
+            .pending_payout
+            .checked_sub(yield_consumed)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        user_stake_record.total_yield_claimed = user_stake_record
+            .total_yield_claimed
+            .checked_add(yield_consumed)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        user_stake_record.total_compounded = user_stake_record
+            .total_compounded
+            .checked_add(tokens)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        user_stake_record.last_compound_rate = rate;
+        user_stake_record.staked_amount = new_user_total;
+        user_stake_record.last_action_at = clock.unix_timestamp;
+
+        // Re-tier the grown stake
+        let new_weight = pool_state
+            .config
+            .stake_weight(new_user_total)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state
+            .reweight(user_stake_record.weighted_stake, new_weight)
+            .ok_or(CarsaError::StateInconsistency)?;
+        user_stake_record.weighted_stake = new_weight;
+
+        log_verbose!("Yield compounded");
+        log_verbose!("User: {}", user_stake_record.user);
+        log_verbose!("Yield consumed: {}", yield_consumed);
+        log_verbose!("Vouchers credited: {}", tokens);
+        log_verbose!("Rate: {}", rate);
+
+        // Emit event
+        emit_cpi!(YieldCompoundedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            user: user_stake_record.user,
+            yield_consumed,
+            tokens_credited: tokens,
+            rate,
+            new_staked_amount: new_user_total,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Add To Whitelist Instruction
+// ============================================================================
+
+/// Approve a wallet to deposit into a whitelist-gated pool
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddToWhitelist<'info> {
+    /// The pool authority (admin), pays for the entry
+    #[account(mut)]
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The wallet being approved
+    /// CHECK: Only used as a seed for the whitelist entry
+    pub user: AccountInfo<'info>,
+
+    /// The whitelist entry (PDA)
+    #[account(
+        init,
+        payer = pool_authority,
+        space = StakeWhitelistEntry::LEN,
+        seeds = [STAKE_WHITELIST_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub stake_whitelist_entry: Account<'info, StakeWhitelistEntry>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl AddToWhitelist<'_> {
+    pub fn handler(ctx: Context<AddToWhitelist>) -> Result<()> {
+        let entry = &mut ctx.accounts.stake_whitelist_entry;
+        let clock = Clock::get()?;
+
+        entry.pool = ctx.accounts.pool_state.key();
+        entry.user = ctx.accounts.user.key();
+        entry.added_at = clock.unix_timestamp;
+        entry.bump = ctx.bumps.stake_whitelist_entry;
+
+        log_verbose!("Wallet whitelisted: {}", entry.user);
+
+        // Emit event
+        emit_cpi!(WhitelistUpdatedEvent {
+            sequence: ctx.accounts.pool_state.load_mut()?.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: entry.pool,
+            user: entry.user,
+            whitelisted: Changed { old: false, new: true },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Remove From Whitelist Instruction
+// ============================================================================
+
+/// Revoke a wallet's approval to deposit into a whitelist-gated pool
+/// Existing stakes are untouched; only new deposits are blocked
+/// Only the pool authority can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    /// The pool authority (admin), receives the entry's rent
+    #[account(mut)]
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The whitelist entry to close
+    #[account(
+        mut,
+        close = pool_authority,
+        seeds = [STAKE_WHITELIST_SEED, pool_state.key().as_ref(), stake_whitelist_entry.user.as_ref()],
+        bump = stake_whitelist_entry.bump
+    )]
+    pub stake_whitelist_entry: Account<'info, StakeWhitelistEntry>,
+}
+
+impl RemoveFromWhitelist<'_> {
+    pub fn handler(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+        let entry = &ctx.accounts.stake_whitelist_entry;
+        let clock = Clock::get()?;
+
+        log_verbose!("Wallet removed from whitelist: {}", entry.user);
+
+        // Emit event
+        emit_cpi!(WhitelistUpdatedEvent {
+            sequence: ctx.accounts.pool_state.load_mut()?.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: entry.pool,
+            user: entry.user,
+            whitelisted: Changed { old: true, new: false },
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Settle Users Instruction
+// ============================================================================
+
+/// Settle accrued yield for a batch of stakers
+/// Stake records are passed as writable `remaining_accounts`
+/// Only the pool delegate can perform this operation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SettleUsers<'info> {
+    /// The pool delegate authority (backend service)
+    pub pool_delegate: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_delegate.key() == pool_state.load()?.pool_delegate @ CarsaError::UnauthorizedDelegate
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl<'info> SettleUsers<'info> {
+    pub fn handler(ctx: Context<'_, '_, 'info, 'info, SettleUsers<'info>>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let pool_key = ctx.accounts.pool_state.key();
+        let clock = Clock::get()?;
+
+        let records = expect_stake_records(
+            ctx.remaining_accounts,
+            pool_key,
+            MAX_SETTLE_BATCH,
+            CarsaError::SettleBatchTooLarge,
+        )?;
+
+        let mut settled: u32 = 0;
+        let mut skipped: u32 = 0;
+        let mut yield_settled: u64 = 0;
+
+        for record in records {
+            let mut record = record?;
+
+            // Records already at the current indices have nothing to settle
+            if record.user_reward_index == pool_state.reward_index
+                && record.user_loss_index == pool_state.loss_index
+            {
+                skipped += 1;
+                continue;
+            }
+
+            let accrued = record
+                .accrued_since_checkpoint(pool_state.reward_index, pool_state.loss_index)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            record
+                .checkpoint(pool_state.reward_index, pool_state.loss_index)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            record.exit(ctx.program_id)?;
+
+            yield_settled = yield_settled
+                .checked_add(accrued)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            settled += 1;
+        }
+
+        log_verbose!("Users settled");
+        log_verbose!("Settled: {}", settled);
+        log_verbose!("Skipped: {}", skipped);
+        log_verbose!("Yield settled: {}", yield_settled);
+
+        // Emit event
+        emit_cpi!(UsersSettledEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: pool_key,
+            settled,
+            skipped,
+            yield_settled,
+            reward_index: pool_state.reward_index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Accrue Minimum Yield Instruction
+// ============================================================================
+
+/// Accrue the pool's guaranteed minimum yield since the last yield update
+/// Anyone can crank this; accrual stops once `guaranteed_yield_budget` is used up
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AccrueMinimumYield<'info> {
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl AccrueMinimumYield<'_> {
+    pub fn handler(ctx: Context<AccrueMinimumYield>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        let elapsed = clock
+            .unix_timestamp
+            .saturating_sub(pool_state.last_yield_update)
+            .max(0) as u64;
+
+        // Yield accrues in voucher terms on the principal, up to the remaining budget
+        let remaining_budget = pool_state
+            .config
+            .guaranteed_yield_budget
+            .saturating_sub(pool_state.guaranteed_yield_owed);
+        let mut amount = pool_state
+            .config
+            .minimum_yield(pool_state.total_voucher_staked, elapsed)
+            .ok_or(CarsaError::ArithmeticOverflow)?
+            .min(remaining_budget);
+
+        // Without stakers there is no one to owe the yield to
+        if pool_state.weighted_total_staked == 0 {
+            amount = 0;
+        }
+
+        if amount > 0 {
+            let (yield_per_token, dust_carry) = reward_index_increment(
+                amount,
+                pool_state.dust_carry,
+                pool_state.weighted_total_staked,
+            )
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+            pool_state.reward_index = pool_state
+                .reward_index
+                .checked_add(yield_per_token)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+            pool_state.dust_carry = dust_carry;
+            pool_state.guaranteed_yield_owed = pool_state
+                .guaranteed_yield_owed
+                .checked_add(amount)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+        }
+
+        pool_state.last_yield_update = clock.unix_timestamp;
+
+        log_verbose!("Minimum yield accrued");
+        log_verbose!("Elapsed seconds: {}", elapsed);
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Guaranteed yield owed: {}", { pool_state.guaranteed_yield_owed });
+
+        // Emit event
+        emit_cpi!(MinimumYieldAccruedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            elapsed_seconds: elapsed,
+            amount,
+            guaranteed_yield_owed: pool_state.guaranteed_yield_owed,
+            reward_index: pool_state.reward_index,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Request Unstake Instruction
+// ============================================================================
+
+/// Queue part of a stake for withdrawal in a queued-withdrawal pool
+/// The amount leaves the stake immediately, so it stops earning yield
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    /// The user requesting the withdrawal, pays for the request account
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_state.load()?.config.withdrawals_enabled @ CarsaError::WithdrawalsDisabled,
+        constraint = !pool_state.load()?.withdrawals_paused @ CarsaError::WithdrawalsPaused,
+        constraint = pool_state.load()?.config.queued_withdrawals @ CarsaError::QueuedWithdrawalsDisabled
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// The user's unstake request (PDA), topped up by repeated requests
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = UnstakeRequest::LEN,
+        seeds = [UNSTAKE_REQUEST_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub unstake_request: Account<'info, UnstakeRequest>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's receipt token account (receipts burned here)
+    #[account(
+        mut,
+        constraint = user_receipt_ata.mint == pool_state.load()?.receipt_mint @ CarsaError::InvalidMint,
+        constraint = user_receipt_ata.owner == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+impl RequestUnstake<'_> {
+    pub fn handler(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let unstake_request = &mut ctx.accounts.unstake_request;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, CarsaError::InvalidAmount);
+        require!(
+            amount <= user_stake_record.staked_amount,
+            CarsaError::InsufficientBalance
+        );
+        require!(
+            amount <= user_stake_record.unlocked_amount(),
+            CarsaError::StakeLockedByAdvance
+        );
+        require!(
+            amount <= ctx.accounts.user_receipt_ata.amount,
+            CarsaError::InsufficientReceipts
+        );
+
+        // Same timing rules as a direct withdrawal; the queue has no early-exit penalty
+        let since_deposit = clock
+            .unix_timestamp
+            .checked_sub(user_stake_record.last_deposit_at)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            since_deposit >= pool_state.config.withdraw_cooldown_seconds as i64,
+            CarsaError::CooldownActive
+        );
+        require!(
+            clock.unix_timestamp >= user_stake_record.lock_until,
+            CarsaError::StakeLocked
+        );
+
+        // Yield earned so far stays with the user; the queued amount earns nothing more
+        user_stake_record
+            .checkpoint(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        user_stake_record.staked_amount = user_stake_record
+            .staked_amount
+            .checked_sub(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let new_weight = pool_state
+            .config
+            .stake_weight(user_stake_record.staked_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state
+            .reweight(user_stake_record.weighted_stake, new_weight)
+            .ok_or(CarsaError::StateInconsistency)?;
+        user_stake_record.weighted_stake = new_weight;
+        user_stake_record.last_action_at = clock.unix_timestamp;
+
+        pool_state.total_voucher_staked = pool_state
+            .total_voucher_staked
+            .checked_sub(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state.queued_unstake_amount = pool_state
+            .queued_unstake_amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        if user_stake_record.staked_amount == 0 {
+            pool_state.total_stakers = pool_state
+                .total_stakers
+                .checked_sub(1)
+                .ok_or(CarsaError::StateInconsistency)?;
+        }
+
+        // Receipts only back active stake
+        let burn_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                from: ctx.accounts.user_receipt_ata.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        );
+        token_interface::burn(burn_ctx, amount)?;
+
+        // A top-up restarts the delay for the whole request
+        if unstake_request.amount == 0 {
+            unstake_request.pool = ctx.accounts.pool_state.key();
+            unstake_request.user = ctx.accounts.user.key();
+            unstake_request.bump = ctx.bumps.unstake_request;
+        }
+        unstake_request.amount = unstake_request
+            .amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        unstake_request.requested_at = clock.unix_timestamp;
+
+        let processable_at = clock
+            .unix_timestamp
+            .checked_add(pool_state.config.unstake_delay_seconds as i64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!("Unstake requested");
+        log_verbose!("User: {}", ctx.accounts.user.key());
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Total requested: {}", unstake_request.amount);
+        log_verbose!("Processable at: {}", processable_at);
+
+        // Emit event
+        emit_cpi!(UnstakeRequestedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool_state.key(),
+            amount,
+            total_requested: unstake_request.amount,
+            processable_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Cancel Unstake Instruction
+// ============================================================================
+
+/// Cancel a pending unstake request and return the amount to the user's stake
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelUnstake<'info> {
+    /// The user who made the request, receives the request account's rent
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// User's stake record
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = user_stake_record.bump,
+        constraint = user_stake_record.user == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+
+    /// The request being cancelled
+    #[account(
+        mut,
+        close = user,
+        seeds = [UNSTAKE_REQUEST_SEED, pool_state.key().as_ref(), user.key().as_ref()],
+        bump = unstake_request.bump
+    )]
+    pub unstake_request: Account<'info, UnstakeRequest>,
+
+    /// The pool's sLOKAL receipt mint
+    #[account(
+        mut,
+        address = pool_state.load()?.receipt_mint @ CarsaError::InvalidMint
+    )]
+    pub receipt_mint: InterfaceAccount<'info, Mint>,
+
+    /// User's receipt token account (receipts re-minted here)
+    #[account(
+        mut,
+        constraint = user_receipt_ata.mint == pool_state.load()?.receipt_mint @ CarsaError::InvalidMint,
+        constraint = user_receipt_ata.owner == user.key() @ CarsaError::InvalidOwner
+    )]
+    pub user_receipt_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault authority PDA (receipt mint authority)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl CancelUnstake<'_> {
+    pub fn handler(ctx: Context<CancelUnstake>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let amount = ctx.accounts.unstake_request.amount;
+        let clock = Clock::get()?;
+
+        // Settle under the current weight before the stake grows back
+        user_stake_record
+            .checkpoint(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        if user_stake_record.staked_amount == 0 {
+            pool_state.total_stakers = pool_state
+                .total_stakers
+                .checked_add(1)
+                .ok_or(CarsaError::ArithmeticOverflow)?;
+        }
+
+        user_stake_record.staked_amount = user_stake_record
+            .staked_amount
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        let new_weight = pool_state
+            .config
+            .stake_weight(user_stake_record.staked_amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state
+            .reweight(user_stake_record.weighted_stake, new_weight)
+            .ok_or(CarsaError::StateInconsistency)?;
+        user_stake_record.weighted_stake = new_weight;
+        user_stake_record.last_action_at = clock.unix_timestamp;
+
+        pool_state.total_voucher_staked = pool_state
+            .total_voucher_staked
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        pool_state.queued_unstake_amount = pool_state
+            .queued_unstake_amount
+            .checked_sub(amount)
+            .ok_or(CarsaError::StateInconsistency)?;
+
+        // Restore the receipts burned by the request
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let mint_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.receipt_mint.to_account_info(),
+                to: ctx.accounts.user_receipt_ata.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::mint_to(mint_ctx, amount)?;
+
+        log_verbose!("Unstake cancelled");
+        log_verbose!("User: {}", ctx.accounts.user.key());
+        log_verbose!("Amount restored: {}", amount);
+
+        // Emit event
+        emit_cpi!(UnstakeCancelledEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: ctx.accounts.user.key(),
+            pool: ctx.accounts.pool_state.key(),
+            amount,
+            new_staked_amount: user_stake_record.staked_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Process Unstake Instruction
+// ============================================================================
+
+/// Pay out an unstake request once its delay has passed
+/// Anyone can crank this; the vouchers always go to the requesting user
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ProcessUnstake<'info> {
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = !pool_state.load()?.withdrawals_paused @ CarsaError::WithdrawalsPaused
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The request being processed
+    #[account(
+        mut,
+        close = user,
+        seeds = [UNSTAKE_REQUEST_SEED, pool_state.key().as_ref(), unstake_request.user.as_ref()],
+        bump = unstake_request.bump
+    )]
+    pub unstake_request: Account<'info, UnstakeRequest>,
+
+    /// The requesting user, receives the request account's rent
+    /// CHECK: Must match the user recorded on the request
+    #[account(
+        mut,
+        address = unstake_request.user @ CarsaError::InvalidOwner
+    )]
+    pub user: AccountInfo<'info>,
+
+    /// User's voucher token account (destination)
+    #[account(
+        mut,
+        constraint = user_voucher_ata.mint == pool_state.load()?.voucher_mint @ CarsaError::InvalidMint,
+        constraint = user_voucher_ata.owner == unstake_request.user @ CarsaError::InvalidOwner
+    )]
+    pub user_voucher_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault token account (source)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// Pool vault authority PDA (signer for transfer)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl ProcessUnstake<'_> {
+    pub fn handler(ctx: Context<ProcessUnstake>) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let unstake_request = &ctx.accounts.unstake_request;
+        let amount = unstake_request.amount;
+        let clock = Clock::get()?;
+
+        let processable_at = unstake_request
+            .requested_at
+            .checked_add(pool_state.config.unstake_delay_seconds as i64)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+        require!(
+            clock.unix_timestamp >= processable_at,
+            CarsaError::UnstakeDelayActive
+        );
+
+        // Part of the principal may be deployed off-chain, and the reserves are not for redemptions
+        let redeemable = pool_state
+            .redeemable_balance(ctx.accounts.pool_vault_ata.amount)
+            .ok_or(CarsaError::InsufficientVaultLiquidity)?;
+        require!(amount <= redeemable, CarsaError::InsufficientVaultLiquidity);
+
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.pool_vault_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.user_voucher_ata.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
         );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
+
+        pool_state.queued_unstake_amount = pool_state
+            .queued_unstake_amount
+            .checked_sub(amount)
+            .ok_or(CarsaError::StateInconsistency)?;
+
+        log_verbose!("Unstake processed");
+        log_verbose!("User: {}", unstake_request.user);
+        log_verbose!("Amount paid out: {}", amount);
+
+        // Emit event
+        emit_cpi!(UnstakeProcessedEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            user: unstake_request.user,
+            pool: ctx.accounts.pool_state.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Verify Pool Invariants Instruction
+// ============================================================================
+
+/// Check the pool's accounting invariants and report the result
+/// Anyone can call this; strict mode fails the transaction on any violation
+#[event_cpi]
+#[derive(Accounts)]
+pub struct VerifyPoolInvariants<'info> {
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Pool vault token account
+    #[account(
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+}
+
+impl VerifyPoolInvariants<'_> {
+    pub fn handler(ctx: Context<VerifyPoolInvariants>, strict: bool) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let vault_balance = ctx.accounts.pool_vault_ata.amount;
+        let clock = Clock::get()?;
+
+        // Principal not deployed off-chain must still be in the vault
+        let undeployed_principal = pool_state
+            .total_voucher_staked
+            .saturating_sub(pool_state.deployed_amount);
+        let vault_covers_principal = vault_balance >= undeployed_principal;
+
+        // Stake exists exactly when someone holds it
+        let stakers_consistent =
+            (pool_state.total_stakers == 0) == (pool_state.total_voucher_staked == 0);
+
+        let reward_index_monotonic = pool_state.reward_index >= pool_state.last_verified_index;
+
+        let passed = vault_covers_principal && stakers_consistent && reward_index_monotonic;
+
+        log_verbose!("Pool invariants verified");
+        log_verbose!("Vault covers principal: {}", vault_covers_principal);
+        log_verbose!("Stakers consistent: {}", stakers_consistent);
+        log_verbose!("Reward index monotonic: {}", reward_index_monotonic);
+
+        // Emit event
+        emit_cpi!(PoolInvariantReportEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            vault_balance,
+            total_voucher_staked: pool_state.total_voucher_staked,
+            deployed_amount: pool_state.deployed_amount,
+            vault_covers_principal,
+            total_stakers: pool_state.total_stakers,
+            stakers_consistent,
+            reward_index: pool_state.reward_index,
+            last_verified_index: pool_state.last_verified_index,
+            reward_index_monotonic,
+            passed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        require!(passed || !strict, CarsaError::PoolInvariantViolated);
+
+        // Only a passing check moves the baseline, so a regression keeps being reported
+        if passed {
+            pool_state.last_verified_index = pool_state.reward_index;
+        }
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Draw Insurance Instruction
+// ============================================================================
+
+/// Move insurance reserve out of the vault to cover a recorded loss
+/// Total draws can never exceed the pool's cumulative recorded losses
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DrawInsurance<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// Pool vault token account (source)
+    #[account(
+        mut,
+        constraint = pool_vault_ata.key() == pool_state.load()?.vault_ata @ CarsaError::InvalidVault
+    )]
+    pub pool_vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The voucher token mint (LOKAL token)
+    #[account(
+        address = pool_state.load()?.voucher_mint @ CarsaError::InvalidMint
+    )]
+    pub voucher_mint: InterfaceAccount<'info, Mint>,
+
+    /// Pool vault authority PDA (signer for transfer)
+    /// CHECK: PDA validation is handled by seeds constraint
+    #[account(
+        seeds = [
+            POOL_VAULT_AUTHORITY_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump
+    )]
+    pub pool_vault_authority: AccountInfo<'info>,
+
+    /// The deployment destination registered by the pool authority
+    #[account(
+        mut,
+        constraint = destination.key() == pool_state.load()?.deployment_destination @ CarsaError::InvalidDeploymentDestination
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program for SPL token operations
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl DrawInsurance<'_> {
+    pub fn handler(ctx: Context<DrawInsurance>, amount: u64) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+        let clock = Clock::get()?;
+
+        require!(amount > 0, CarsaError::InvalidAmount);
+
+        // Draws are bounded by both the reserve and the losses recorded so far
+        let new_insurance_drawn = pool_state
+            .insurance_drawn
+            .checked_add(amount)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
         require!(
-            new_config.max_stake_per_user >= new_config.min_stake_amount,
-            CarsaError::InvalidAmount
+            amount <= pool_state.insurance_reserve
+                && new_insurance_drawn <= pool_state.cumulative_losses,
+            CarsaError::InsuranceDrawTooLarge
         );
         require!(
-            new_config.apy_basis_points <= 10000,
-            CarsaError::InvalidAmount
+            ctx.accounts.pool_vault_ata.amount >= amount,
+            CarsaError::InsufficientVaultLiquidity
         );
 
-        pool_state.config = new_config;
+        // Transfer the reserve from the vault to the deployment destination
+        let vault_authority_bump = ctx.bumps.pool_vault_authority;
+        let mint_seed = pool_state.mint_seed();
+        let pool_seed = pool_id_seed(pool_state.pool_id);
+        let vault_authority_seeds = &[
+            POOL_VAULT_AUTHORITY_SEED,
+            mint_seed.as_ref(),
+            pool_seed.as_ref(),
+            &[vault_authority_bump],
+        ];
+        let signer_seeds = &[&vault_authority_seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.pool_vault_ata.to_account_info(),
+                mint: ctx.accounts.voucher_mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.pool_vault_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(
+            transfer_ctx,
+            amount,
+            ctx.accounts.voucher_mint.decimals,
+        )?;
 
-        msg!("Pool configuration updated");
-        msg!("Deposits enabled: {}", pool_state.config.deposits_enabled);
-        msg!("Withdrawals enabled: {}", pool_state.config.withdrawals_enabled);
+        pool_state.insurance_reserve -= amount;
+        pool_state.insurance_drawn = new_insurance_drawn;
+
+        log_verbose!("Insurance drawn");
+        log_verbose!("Amount: {}", amount);
+        log_verbose!("Remaining reserve: {}", { pool_state.insurance_reserve });
+        log_verbose!("Total drawn: {}", { pool_state.insurance_drawn });
 
         // Emit event
-        emit!(PoolConfigUpdatedEvent {
-            pool: pool_state.key(),
-            config: new_config,
+        emit_cpi!(InsuranceDrawnEvent {
+            sequence: pool_state.next_event_sequence().ok_or(CarsaError::ArithmeticOverflow)?,
+            pool: ctx.accounts.pool_state.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            insurance_reserve: pool_state.insurance_reserve,
+            insurance_drawn: pool_state.insurance_drawn,
+            cumulative_losses: pool_state.cumulative_losses,
             timestamp: clock.unix_timestamp,
         });
 
@@ -526,12 +3669,161 @@ impl UpdatePoolConfig<'_> {
     }
 }
 
+// ============================================================================
+// Preview Claimable Yield Instruction
+// ============================================================================
+
+/// Preview the yield a user's stake would claim on redeem
+/// Read-only, so clients can simulate it with only a fee payer
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct PreviewClaimableYield<'info> {
+    /// The pool state account
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The user's stake record
+    #[account(
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user.as_ref()],
+        bump = user_stake_record.bump
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+}
+
+impl PreviewClaimableYield<'_> {
+    pub fn handler(ctx: Context<PreviewClaimableYield>, _user: Pubkey) -> Result<u64> {
+        let pool_state = &*ctx.accounts.pool_state.load()?;
+
+        // Same index math as redeem_voucher
+        let claimable_yield = ctx
+            .accounts
+            .user_stake_record
+            .claimable_yield(pool_state.reward_index, pool_state.loss_index)
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        Ok(claimable_yield)
+    }
+}
+
+// ============================================================================
+// Sync Stake View Instruction
+// ============================================================================
+
+/// Refresh the cached `pending_yield` and `pool_share_bps` on a user's stake record
+/// Permissionless: the values are derived from the pool, so anyone may refresh them
+#[derive(Accounts)]
+pub struct SyncStakeView<'info> {
+    /// The pool state account
+    #[account(
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    /// The stake record to refresh
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, pool_state.key().as_ref(), user_stake_record.user.as_ref()],
+        bump = user_stake_record.bump
+    )]
+    pub user_stake_record: Account<'info, UserStakeRecord>,
+}
+
+impl SyncStakeView<'_> {
+    pub fn handler(ctx: Context<SyncStakeView>) -> Result<()> {
+        let pool_state = &*ctx.accounts.pool_state.load()?;
+        let user_stake_record = &mut ctx.accounts.user_stake_record;
+        let clock = Clock::get()?;
+
+        user_stake_record
+            .sync_view(
+                pool_state.reward_index,
+                pool_state.loss_index,
+                pool_state.weighted_total_staked,
+                clock.unix_timestamp,
+            )
+            .ok_or(CarsaError::ArithmeticOverflow)?;
+
+        log_verbose!("Stake view synced");
+        log_verbose!("Pending yield: {}", user_stake_record.pending_yield);
+        log_verbose!("Pool share (bps): {}", user_stake_record.pool_share_bps);
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Corrupt Pool State Instruction (test-utils only)
+// ============================================================================
+
+/// Overwrite pool accounting fields so tests can exercise invariant checks
+/// Only compiled with the `test-utils` feature; never deploy such a build
+#[cfg(feature = "test-utils")]
+#[derive(Accounts)]
+pub struct CorruptPoolState<'info> {
+    /// The pool authority (admin)
+    pub pool_authority: Signer<'info>,
+
+    /// The pool state account
+    #[account(
+        mut,
+        seeds = [
+            POOL_STATE_SEED,
+            pool_state.load()?.mint_seed().as_ref(),
+            pool_id_seed(pool_state.load()?.pool_id).as_ref()
+        ],
+        bump = pool_state.load()?.bump,
+        constraint = pool_authority.key() == pool_state.load()?.pool_authority @ CarsaError::Unauthorized
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+#[cfg(feature = "test-utils")]
+impl CorruptPoolState<'_> {
+    pub fn handler(
+        ctx: Context<CorruptPoolState>,
+        total_voucher_staked: Option<u64>,
+        total_stakers: Option<u64>,
+        reward_index: Option<u128>,
+    ) -> Result<()> {
+        let pool_state = &mut *ctx.accounts.pool_state.load_mut()?;
+
+        if let Some(total_voucher_staked) = total_voucher_staked {
+            pool_state.total_voucher_staked = total_voucher_staked;
+        }
+        if let Some(total_stakers) = total_stakers {
+            pool_state.total_stakers = total_stakers;
+        }
+        if let Some(reward_index) = reward_index {
+            pool_state.reward_index = reward_index;
+        }
+
+        log_verbose!("Pool state overwritten for testing");
+
+        Ok(())
+    }
+}
+
 // ============================================================================
 // Events
 // ============================================================================
 
 #[event]
 pub struct PoolInitializedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub pool_id: u16,
     pub pool_authority: Pubkey,
     pub pool_delegate: Pubkey,
     pub vault_ata: Pubkey,
@@ -542,28 +3834,61 @@ pub struct PoolInitializedEvent {
 
 #[event]
 pub struct VoucherDepositedEvent {
+    pub sequence: u64,
     pub user: Pubkey,
     pub pool: Pubkey,
     pub amount: u64,
+    /// Part of `amount` kept in the insurance reserve instead of being staked
+    pub insurance_fee: u64,
     pub new_user_total: u64,
     pub pool_total_staked: u64,
+    /// Deposits the pool can still take before its cap (None when uncapped)
+    pub remaining_capacity: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeFundedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub funder: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
     pub timestamp: i64,
 }
 
 #[event]
 pub struct YieldRecordedEvent {
+    pub sequence: u64,
     pub pool: Pubkey,
+    pub yield_batch_id: [u8; 32],
+    pub external_sequence: u64,
     pub sol_amount: u64,
+    pub performance_fee: u64,
+    pub net_amount: u64,
     pub total_yield_earned: u64,
     pub reward_index: u128,
     pub timestamp: i64,
 }
 
+/// Emitted when the delegate moves a pool's yield journal position
+/// Monitoring should alert on every one: it is the only way to re-record journal entries
+#[event]
+pub struct ExternalSequenceResetEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub pool_delegate: Pubkey,
+    pub last_external_sequence: Changed<u64>,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VoucherRedeemedEvent {
+    pub sequence: u64,
     pub user: Pubkey,
     pub pool: Pubkey,
     pub amount_redeemed: u64,
+    pub penalty: u64,
     pub yield_claimed: u64,
     pub remaining_stake: u64,
     pub timestamp: i64,
@@ -571,7 +3896,225 @@ pub struct VoucherRedeemedEvent {
 
 #[event]
 pub struct PoolConfigUpdatedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub config: Changed<PoolConfig>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DeploymentDestinationSetEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub destination: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultWithdrawnEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub deployed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrincipalReturnedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub source: Pubkey,
+    pub amount: u64,
+    pub deployed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeRecordClosedEvent {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub closed_by: Pubkey,
+    pub rent_receiver: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolGuardianUpdatedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub guardian: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolPausedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub deposits_paused: Changed<bool>,
+    pub withdrawals_paused: Changed<bool>,
+    pub triggered_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeRecordRepairedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub old_reward_index: u128,
+    pub new_reward_index: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LossRecordedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub sol_amount: u64,
+    pub cumulative_losses: u64,
+    pub loss_index: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolAuthorityProposedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub current_authority: Pubkey,
+    pub proposed_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolAuthorityTransferredEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub authority: Changed<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoCompoundSetEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct YieldCompoundedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub yield_consumed: u64,
+    pub tokens_credited: u64,
+    pub rate: u64,
+    pub new_staked_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistUpdatedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub whitelisted: Changed<bool>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UsersSettledEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub settled: u32,
+    pub skipped: u32,
+    pub yield_settled: u64,
+    pub reward_index: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MinimumYieldAccruedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub elapsed_seconds: u64,
+    pub amount: u64,
+    pub guaranteed_yield_owed: u64,
+    pub reward_index: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeRequestedEvent {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub total_requested: u64,
+    pub processable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeCancelledEvent {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub new_staked_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnstakeProcessedEvent {
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeActionRecordedEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub action_index: u64,
+    pub action_type: StakeActionType,
+    pub amount: u64,
+    pub resulting_balance: u64,
+    pub reward_index: u128,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolInvariantReportEvent {
+    pub sequence: u64,
+    pub pool: Pubkey,
+    pub vault_balance: u64,
+    pub total_voucher_staked: u64,
+    pub deployed_amount: u64,
+    pub vault_covers_principal: bool,
+    pub total_stakers: u64,
+    pub stakers_consistent: bool,
+    pub reward_index: u128,
+    pub last_verified_index: u128,
+    pub reward_index_monotonic: bool,
+    pub passed: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceDrawnEvent {
+    pub sequence: u64,
     pub pool: Pubkey,
-    pub config: PoolConfig,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub insurance_reserve: u64,
+    pub insurance_drawn: u64,
+    pub cumulative_losses: u64,
     pub timestamp: i64,
 }
@@ -1,9 +1,24 @@
 use anchor_lang::prelude::*;
 
+/// `msg!` for informational logs, compiled in only with the `verbose-logs` feature
+/// Logs cost compute and are public, and events already carry the same data, so release
+/// builds leave them out. Error paths log through `msg!` directly
+macro_rules! log_verbose {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logs") {
+            anchor_lang::prelude::msg!($($arg)*);
+        }
+    };
+}
+
 // Import custom modules
 pub mod error;
 pub mod instructions;
+pub mod math;
+pub mod oracle;
+pub mod stake_pool;
 pub mod state;
+pub mod utils;
 
 // Re-export for easier access
 use error::*;
@@ -32,6 +47,7 @@ pub mod carsa {
 
     /// Mint Lokal tokens to a user's token account
     /// Used for reward distribution when users make purchases at merchants
+    /// Mints above the single-signature limit also need the configured co-signer
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
@@ -43,14 +59,30 @@ pub mod carsa {
         MintLokalTokens::handler(ctx, amount)
     }
 
+    /// Compare the Lokal mint's supply with `total_supply - total_burned` and emit the result
+    /// Anyone can check; with `force` the update authority snaps the counters to the mint
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `force` - Whether to repair the counters (update authority only)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn reconcile_supply(ctx: Context<ReconcileSupply>, force: bool) -> Result<()> {
+        ReconcileSupply::handler(ctx, force)
+    }
+
     /// Register a new merchant in the Carsa loyalty program
     /// This instruction creates a merchant account with specific cashback rates
+    /// and gives it the next `MerchantIndex` in the merchant registry
+    /// Franchise outlets pass their parent merchant, co-signed by the parent's owner
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
     /// * `name` - The merchant's display name (max 32 characters)
     /// * `category` - The merchant's business category (max 16 characters)
     /// * `cashback_rate` - The cashback percentage in basis points (e.g., 500 = 5%)
+    /// * `min_purchase_amount` - Smallest purchase value in IDR the merchant accepts (0 = none)
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
@@ -59,18 +91,80 @@ pub mod carsa {
         name: String,
         category: String,
         cashback_rate: u16,
+        min_purchase_amount: u64,
+    ) -> Result<()> {
+        RegisterMerchant::handler(ctx, name, category, cashback_rate, min_purchase_amount)
+    }
+
+    /// Register a merchant on its owner's behalf, with the update authority signing and paying
+    /// The merchant is created pending activation and takes no purchases until its owner
+    /// signs `accept_merchant_registration`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `merchant_wallet` - The wallet that will own the merchant account
+    /// * `name` - The merchant's display name (max 32 characters)
+    /// * `category` - The merchant's business category (max 16 characters)
+    /// * `cashback_rate` - The cashback percentage in basis points (e.g., 500 = 5%)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn register_merchant_by_authority(
+        ctx: Context<RegisterMerchantByAuthority>,
+        merchant_wallet: Pubkey,
+        name: String,
+        category: String,
+        cashback_rate: u16,
     ) -> Result<()> {
-        RegisterMerchant::handler(ctx, name, category, cashback_rate)
+        RegisterMerchantByAuthority::handler(ctx, merchant_wallet, name, category, cashback_rate)
+    }
+
+    /// Accept a registration made by the update authority, allowing purchases
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn accept_merchant_registration(ctx: Context<AcceptMerchantRegistration>) -> Result<()> {
+        AcceptMerchantRegistration::handler(ctx)
     }
 
     /// Process a purchase transaction and distribute reward tokens with optional token redemption
     /// This is the core instruction that implements the loyalty program logic
+    /// Customers who pass their voucher pool stake record may earn the staker cashback boost
+    /// Redeemed tokens are valued with the merchant's redemption bonus and pay the protocol
+    /// fee to the treasury; the merchant receives the rest
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `fiat_amount` - The fiat payment amount in Indonesian Rupiah (IDR)
+    /// * `redemption` - Optional tokens to redeem as payment
+    /// * `transaction_id` - Unique identifier for this transaction (32 bytes)
+    /// * `expected_nonce` - Optional POS nonce; must be one past the merchant's last
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn process_purchase_with_redemption(
+        ctx: Context<ProcessPurchase>,
+        fiat_amount: u64,
+        redemption: Option<RedemptionParams>,
+        transaction_id: [u8; 32],
+        expected_nonce: Option<u64>,
+    ) -> Result<()> {
+        ProcessPurchase::handler(ctx, fiat_amount, redemption, transaction_id, expected_nonce)
+    }
+
+    /// Legacy purchase entry point taking a bare redemption amount
+    /// Deprecated: use `process_purchase_with_redemption`; kept so existing clients don't break
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
     /// * `fiat_amount` - The fiat payment amount in Indonesian Rupiah (IDR)
     /// * `redeem_token_amount` - Optional amount of tokens to redeem as payment
     /// * `transaction_id` - Unique identifier for this transaction (32 bytes)
+    /// * `expected_nonce` - Optional POS nonce; must be one past the merchant's last
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
@@ -79,132 +173,1923 @@ pub mod carsa {
         fiat_amount: u64,
         redeem_token_amount: Option<u64>,
         transaction_id: [u8; 32],
+        expected_nonce: Option<u64>,
     ) -> Result<()> {
-        ProcessPurchase::handler(ctx, fiat_amount, redeem_token_amount, transaction_id)
+        let redemption = redeem_token_amount.map(|token_amount| RedemptionParams { token_amount });
+        ProcessPurchase::handler(ctx, fiat_amount, redemption, transaction_id, expected_nonce)
     }
 
-    /// Update merchant settings such as cashback rate and active status
+    /// Create an invoice with a fixed IDR amount for a customer to pay by reference
     /// Only the merchant owner can perform this operation
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `new_cashback_rate` - Optional new cashback rate in basis points
-    /// * `is_active` - Optional new active status for the merchant
+    /// * `reference_id` - Merchant-chosen reference, unique per merchant (32 bytes)
+    /// * `fiat_amount` - The fiat amount charged in Indonesian Rupiah (IDR)
+    /// * `max_redeem_token_amount` - Optional most tokens the customer may redeem
+    /// * `expires_at` - Timestamp from which the invoice can no longer be paid
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn update_merchant(
-        ctx: Context<UpdateMerchant>,
-        new_cashback_rate: Option<u16>,
-        is_active: Option<bool>,
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        reference_id: [u8; 32],
+        fiat_amount: u64,
+        max_redeem_token_amount: Option<u64>,
+        expires_at: i64,
     ) -> Result<()> {
-        UpdateMerchant::handler(ctx, new_cashback_rate, is_active)
+        CreateInvoice::handler(ctx, reference_id, fiat_amount, max_redeem_token_amount, expires_at)
     }
 
-    /// Transfer Lokal tokens between user accounts
-    /// Enables peer-to-peer token transfers within the ecosystem
+    /// Pay an invoice, distributing rewards as `process_purchase_with_redemption` does but
+    /// with the amount taken from the invoice
     /// 
     /// # Arguments
-    /// * `ctx` - The instruction context containing required accounts
-    /// * `amount` - The amount of tokens to transfer (in smallest unit, considering 9 decimals)
+    /// * `ctx` - The instruction context containing required accounts, including the invoice
+    /// * `invoice_amount` - The invoice amount the customer was shown; must match the invoice
+    /// * `redemption` - Optional tokens to redeem, up to the invoice's maximum
     /// * `transaction_id` - Unique identifier for this transaction (32 bytes)
-    /// * `memo` - Optional memo describing the transfer (max 64 characters)
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn transfer_tokens(
-        ctx: Context<TransferTokens>,
-        amount: u64,
+    pub fn pay_invoice(
+        ctx: Context<ProcessPurchase>,
+        invoice_amount: u64,
+        redemption: Option<RedemptionParams>,
         transaction_id: [u8; 32],
-        memo: String,
     ) -> Result<()> {
-        TransferTokens::handler(ctx, amount, transaction_id, memo)
+        ProcessPurchase::pay_invoice_handler(ctx, invoice_amount, redemption, transaction_id)
     }
 
-    // NOTE: redeem_tokens and burn_tokens functions have been integrated into process_purchase
-    // Token redemption is now handled as an optional parameter in process_purchase
-    // This provides a unified transaction experience where users can pay with tokens
-    // and still earn rewards in a single transaction
+    /// Hold customer tokens in escrow for a merchant to capture when the bill is settled
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `hold_id` - Unique identifier for this hold, reused as the capture's transaction id (32 bytes)
+    /// * `amount` - Most tokens the merchant may capture
+    /// * `expires_at` - Timestamp from which the hold can no longer be captured
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_hold(
+        ctx: Context<CreateHold>,
+        hold_id: [u8; 32],
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        CreateHold::handler(ctx, hold_id, amount, expires_at)
+    }
 
-    // ============================================================================
-    // Voucher Pool Instructions for Non-Custodial Staking
-    // ============================================================================
+    /// Capture up to the held tokens as the redemption of a purchase, distributing rewards
+    /// as `process_purchase_with_redemption` does and releasing the rest to the customer
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `fiat_amount` - The fiat part of the bill in Indonesian Rupiah (IDR)
+    /// * `capture_amount` - Held tokens to redeem towards the bill
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn capture_hold(
+        ctx: Context<CaptureHold>,
+        fiat_amount: u64,
+        capture_amount: u64,
+    ) -> Result<()> {
+        CaptureHold::handler(ctx, fiat_amount, capture_amount)
+    }
 
-    /// Initialize a new voucher staking pool for LOKAL tokens
-    /// Creates the pool state and configures staking parameters
+    /// Return an expired hold's tokens to the customer
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `config` - Pool configuration including stake limits and APY
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn initialize_pool(ctx: Context<InitializePool>, config: PoolConfig) -> Result<()> {
-        InitializePool::handler(ctx, config)
+    pub fn release_hold(ctx: Context<ReleaseHold>) -> Result<()> {
+        ReleaseHold::handler(ctx)
     }
 
-    /// Deposit voucher tokens into the staking pool using delegated authority
-    /// The user must have previously approved the pool delegate
+    /// Dispute one of the customer's purchases within the dispute window, freezing
+    /// refunds on it until the dispute is resolved
+    /// Moves the purchase's transaction stub to disputed
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `amount` - The amount of voucher tokens to stake
+    /// * `reason_code` - The customer's reason for the dispute, as an off-chain code
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn deposit_voucher(ctx: Context<DepositVoucher>, amount: u64) -> Result<()> {
-        DepositVoucher::handler(ctx, amount)
+    pub fn open_dispute(ctx: Context<OpenDispute>, reason_code: u16) -> Result<()> {
+        OpenDispute::handler(ctx, reason_code)
     }
 
-    /// Record yield earned from staking activities
-    /// Called by the backend after swapping vouchers to SOL and earning yield
+    /// Attach the merchant's response to an open dispute
+    /// Only the merchant owner can perform this operation
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `sol_amount` - The amount of SOL yield earned
+    /// * `response_code` - The merchant's response, as an off-chain code
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn record_yield(ctx: Context<RecordYield>, sol_amount: u64) -> Result<()> {
-        RecordYield::handler(ctx, sol_amount)
+    pub fn respond_dispute(ctx: Context<RespondDispute>, response_code: u16) -> Result<()> {
+        RespondDispute::handler(ctx, response_code)
     }
 
-    /// Redeem staked vouchers and claim earned yield
-    /// Allows users to unstake their tokens and withdraw
+    /// Decide a dispute, refunding the customer's redeemed tokens when it is upheld
+    /// Only the update authority can perform this operation; repeating a decision is a no-op
+    /// Moves the purchase's transaction stub to refunded or resolved
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `amount` - The amount of voucher tokens to redeem
+    /// * `uphold` - Whether to decide for the customer
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn redeem_voucher(ctx: Context<RedeemVoucher>, amount: u64) -> Result<()> {
-        RedeemVoucher::handler(ctx, amount)
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, uphold: bool) -> Result<()> {
+        ResolveDispute::handler(ctx, uphold)
     }
 
-    /// Update pool configuration settings
-    /// Only the pool authority can perform this operation
+    /// Record that a purchase's off-chain fulfillment has completed
+    /// Only the fulfillment authority can perform this operation, once per purchase and
+    /// within the fulfillment window, and not once the purchase is disputed
     /// 
     /// # Arguments
     /// * `ctx` - The instruction context containing required accounts
-    /// * `new_config` - New pool configuration parameters
     /// 
     /// # Returns
     /// * `Result<()>` - Success or error result
-    pub fn update_pool_config(ctx: Context<UpdatePoolConfig>, new_config: PoolConfig) -> Result<()> {
-        UpdatePoolConfig::handler(ctx, new_config)
+    pub fn mark_fulfilled(ctx: Context<MarkFulfilled>) -> Result<()> {
+        MarkFulfilled::handler(ctx)
     }
 
-    /// Legacy initialize function for backwards compatibility
-    /// This will be removed in future versions
-    #[deprecated(note = "Use initialize_lokal_mint instead")]
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        msg!("Legacy initialize called. Use initialize_lokal_mint instead.");
-        msg!("Program ID: {:?}", ctx.program_id);
-        Ok(())
+    /// Burn redeemed tokens the merchant holds and record the IDR owed for them, to be
+    /// paid out off-chain
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `period_id` - Merchant-chosen settlement period, unique per merchant
+    /// * `token_amount` - Tokens to settle, at most the merchant's unsettled tokens
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn settle_merchant(
+        ctx: Context<SettleMerchant>,
+        period_id: u64,
+        token_amount: u64,
+    ) -> Result<()> {
+        SettleMerchant::handler(ctx, period_id, token_amount)
+    }
+
+    /// Escrow tokens as a gift card claimable by whoever presents its secret code
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Tokens to put on the gift card
+    /// * `code_hash` - SHA-256 hash of the secret code
+    /// * `expires_at` - Timestamp from which the gift card can only be reclaimed
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_gift_card(
+        ctx: Context<CreateGiftCard>,
+        amount: u64,
+        code_hash: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        CreateGiftCard::handler(ctx, amount, code_hash, expires_at)
+    }
+
+    /// Claim a gift card's tokens by presenting its secret code, closing the gift card
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `code_preimage` - The secret code whose hash identifies the gift card
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn redeem_gift_card(ctx: Context<RedeemGiftCard>, code_preimage: Vec<u8>) -> Result<()> {
+        RedeemGiftCard::handler(ctx, code_preimage)
+    }
+
+    /// Return an expired gift card's tokens to its issuer
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn reclaim_gift_card(ctx: Context<ReclaimGiftCard>) -> Result<()> {
+        ReclaimGiftCard::handler(ctx)
+    }
+
+    /// Subscribe to a merchant, approving the subscription to charge the customer's
+    /// token account a fixed amount every period
+    /// The first period can be charged immediately
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount_per_period` - Tokens charged each period
+    /// * `period_seconds` - Seconds between charges
+    /// * `max_periods` - Number of periods the approval covers
+    /// * `earn_rewards` - Whether charges are rewarded and recorded like purchases
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        amount_per_period: u64,
+        period_seconds: i64,
+        max_periods: u32,
+        earn_rewards: bool,
+    ) -> Result<()> {
+        CreateSubscription::handler(ctx, amount_per_period, period_seconds, max_periods, earn_rewards)
+    }
+
+    /// Charge the next due period of a subscription
+    /// Permissionless, so merchants or any crank can collect
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn charge_subscription(ctx: Context<ChargeSubscription>) -> Result<()> {
+        ChargeSubscription::handler(ctx)
+    }
+
+    /// Cancel a subscription, revoking its approval so no further periods are charged
+    /// Only the subscribed customer can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        CancelSubscription::handler(ctx)
+    }
+
+    /// Create an airdrop from the merkle root of its (wallet, amount) allocations
+    /// Only the update authority can perform this operation; budgets above the
+    /// single-signature mint limit also need the mint co-signer
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `airdrop_id` - Identifier of the airdrop, unique across airdrops
+    /// * `merkle_root` - Root of the allocation merkle tree
+    /// * `budget` - Most tokens the airdrop may mint
+    /// * `expires_at` - Timestamp from which claims are refused
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_airdrop(
+        ctx: Context<CreateAirdrop>,
+        airdrop_id: u64,
+        merkle_root: [u8; 32],
+        budget: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        CreateAirdrop::handler(ctx, airdrop_id, merkle_root, budget, expires_at)
+    }
+
+    /// Claim the signer's airdrop allocation with a merkle proof, once per wallet
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The allocation committed to in the merkle tree
+    /// * `proof` - Sibling hashes from the allocation's leaf up to the root
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn claim_airdrop(ctx: Context<ClaimAirdrop>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        ClaimAirdrop::handler(ctx, amount, proof)
+    }
+
+    /// Close an expired airdrop, retiring its unclaimed budget
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn sweep_airdrop(ctx: Context<SweepAirdrop>) -> Result<()> {
+        SweepAirdrop::handler(ctx)
+    }
+
+    /// Authorize an ephemeral key to make purchases on the customer's behalf until it
+    /// expires, escrowing up to `spend_limit` tokens for it to redeem
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `session_signer` - The ephemeral public key allowed to sign session purchases
+    /// * `spend_limit` - Total tokens the session may redeem
+    /// * `expires_at` - Timestamp from which the session can no longer be used
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        session_signer: Pubkey,
+        spend_limit: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        CreateSessionKey::handler(ctx, session_signer, spend_limit, expires_at)
+    }
+
+    /// Process a purchase signed by a session key instead of the customer
+    /// Rewards are credited to the customer; redemptions draw on the session's allowance
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `fiat_amount` - The fiat payment amount in Indonesian Rupiah (IDR)
+    /// * `redemption` - Tokens to redeem from the session's allowance, if any
+    /// * `transaction_id` - Unique identifier for this transaction (32 bytes)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn process_purchase_with_session(
+        ctx: Context<ProcessPurchaseWithSession>,
+        fiat_amount: u64,
+        redemption: Option<RedemptionParams>,
+        transaction_id: [u8; 32],
+    ) -> Result<()> {
+        ProcessPurchaseWithSession::handler(ctx, fiat_amount, redemption, transaction_id)
+    }
+
+    /// Revoke a session key immediately, returning its unspent allowance
+    /// Only the customer that authorized the session can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        RevokeSessionKey::handler(ctx)
+    }
+
+    /// Update merchant settings such as cashback rate and active status
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `new_cashback_rate` - Optional new cashback rate in basis points
+    /// * `is_active` - Optional new active status for the merchant
+    /// * `min_purchase_amount` - Optional new minimum purchase value in IDR
+    /// * `redemption_bonus_bps` - Optional new premium on redeemed tokens' value in basis points
+    /// * `require_merchant_signature` - Optional new setting for requiring signed purchase
+    ///   approvals from the merchant wallet or a POS key
+    /// * `compressed_receipts` - Optional new setting for chaining purchase receipts into a
+    ///   hash on the merchant account instead of creating purchase records
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn update_merchant(
+        ctx: Context<UpdateMerchant>,
+        new_cashback_rate: Option<u16>,
+        is_active: Option<bool>,
+        min_purchase_amount: Option<u64>,
+        redemption_bonus_bps: Option<u16>,
+        require_merchant_signature: Option<bool>,
+        compressed_receipts: Option<bool>,
+    ) -> Result<()> {
+        UpdateMerchant::handler(
+            ctx,
+            new_cashback_rate,
+            is_active,
+            min_purchase_amount,
+            redemption_bonus_bps,
+            require_merchant_signature,
+            compressed_receipts,
+        )
+    }
+
+    /// Register a point-of-sale key allowed to sign purchase approvals for the merchant
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `pos_key` - The terminal's Ed25519 public key
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn register_pos_key(ctx: Context<RegisterPosKey>, pos_key: Pubkey) -> Result<()> {
+        RegisterPosKey::handler(ctx, pos_key)
     }
-}
 
-/// Legacy initialize accounts struct for backwards compatibility
-#[derive(Accounts)]
-pub struct Initialize {}
+    /// Remove a point-of-sale key so its approvals are no longer accepted
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn remove_pos_key(ctx: Context<RemovePosKey>) -> Result<()> {
+        RemovePosKey::handler(ctx)
+    }
+
+    /// Add an operator wallet that may approve purchases and create invoices for the
+    /// merchant, without access to its settings or funds
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `operator` - The operator's wallet
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn add_merchant_operator(ctx: Context<AddMerchantOperator>, operator: Pubkey) -> Result<()> {
+        AddMerchantOperator::handler(ctx, operator)
+    }
+
+    /// Remove a merchant operator so it can no longer act for the merchant
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn remove_merchant_operator(ctx: Context<RemoveMerchantOperator>) -> Result<()> {
+        RemoveMerchantOperator::handler(ctx)
+    }
+
+    /// Create the parent account of a franchise brand
+    /// Outlets register under it with the owner's co-signature and their purchases
+    /// roll up into its aggregate counters
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `name` - The brand's display name (max 32 characters)
+    /// * `default_cashback_rate` - The cashback rate in basis points cascaded to outlets
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_parent_merchant(
+        ctx: Context<CreateParentMerchant>,
+        name: String,
+        default_cashback_rate: u16,
+    ) -> Result<()> {
+        CreateParentMerchant::handler(ctx, name, default_cashback_rate)
+    }
+
+    /// Update a parent merchant's default cashback rate
+    /// Outlets are unchanged until the rate is cascaded to them
+    /// Only the parent's owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `default_cashback_rate` - The new default cashback rate in basis points
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn update_parent_merchant(
+        ctx: Context<UpdateParentMerchant>,
+        default_cashback_rate: u16,
+    ) -> Result<()> {
+        UpdateParentMerchant::handler(ctx, default_cashback_rate)
+    }
+
+    /// Set a batch of outlets' cashback rate to their parent's default
+    /// Outlet merchant accounts are passed as writable remaining accounts
+    /// Only the parent's owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn cascade_cashback_rate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CascadeCashbackRate<'info>>,
+    ) -> Result<()> {
+        CascadeCashbackRate::handler(ctx)
+    }
+
+    /// Escrow a sponsor's tokens as a cashback budget for a group of merchants
+    /// Purchases at member merchants that pass the budget are rewarded out of it
+    /// instead of with newly minted tokens, until it runs out
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `budget_id` - Sponsor-chosen identifier for the budget
+    /// * `amount` - Tokens to escrow (in smallest unit, considering 9 decimals)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_reward_budget(
+        ctx: Context<CreateRewardBudget>,
+        budget_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        CreateRewardBudget::handler(ctx, budget_id, amount)
+    }
+
+    /// Add tokens to a reward budget
+    /// Only the budget's sponsor can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Tokens to add (in smallest unit, considering 9 decimals)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn top_up_reward_budget(ctx: Context<TopUpRewardBudget>, amount: u64) -> Result<()> {
+        TopUpRewardBudget::handler(ctx, amount)
+    }
+
+    /// Return unspent tokens from a reward budget to its sponsor
+    /// Only the budget's sponsor can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Tokens to withdraw (in smallest unit, considering 9 decimals)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn withdraw_reward_budget(ctx: Context<WithdrawRewardBudget>, amount: u64) -> Result<()> {
+        WithdrawRewardBudget::handler(ctx, amount)
+    }
+
+    /// Let a reward budget pay the rewards of a merchant's purchases
+    /// Only the budget's sponsor can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn add_budget_member(ctx: Context<AddBudgetMember>) -> Result<()> {
+        AddBudgetMember::handler(ctx)
+    }
+
+    /// Stop a reward budget from paying a merchant's rewards
+    /// Only the budget's sponsor can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn remove_budget_member(ctx: Context<RemoveBudgetMember>) -> Result<()> {
+        RemoveBudgetMember::handler(ctx)
+    }
+
+    /// Create or update a protocol-funded cashback boost for every merchant in a category
+    /// Only the update authority can perform this operation
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `category` - Merchant category to boost, as given at registration
+    /// * `bonus_bps` - Extra cashback rate on top of the merchant's (in basis points)
+    /// * `start` - Timestamp the boost starts applying
+    /// * `end` - Timestamp the boost stops applying
+    /// * `budget` - Most reward tokens the boost can mint over its lifetime
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_category_boost(
+        ctx: Context<SetCategoryBoost>,
+        category: String,
+        bonus_bps: u16,
+        start: i64,
+        end: i64,
+        budget: u64,
+    ) -> Result<()> {
+        SetCategoryBoost::handler(ctx, category, bonus_bps, start, end, budget)
+    }
+
+    /// Remove a category boost
+    /// Only the update authority can perform this operation
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn close_category_boost(ctx: Context<CloseCategoryBoost>) -> Result<()> {
+        CloseCategoryBoost::handler(ctx)
+    }
+
+    /// Change the token account the merchant's redeemed tokens are paid to
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_payout_account(ctx: Context<SetPayoutAccount>) -> Result<()> {
+        SetPayoutAccount::handler(ctx)
+    }
+
+    /// Propose a new owner for the merchant, who takes over with `accept_merchant_ownership`
+    /// Only the merchant owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `new_owner` - The proposed owner's wallet (default pubkey to cancel a proposal)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn propose_merchant_owner(ctx: Context<ProposeMerchantOwner>, new_owner: Pubkey) -> Result<()> {
+        ProposeMerchantOwner::handler(ctx, new_owner)
+    }
+
+    /// Take over a merchant as its proposed owner; the merchant keeps its address
+    /// Only the proposed owner can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn accept_merchant_ownership(ctx: Context<AcceptMerchantOwnership>) -> Result<()> {
+        AcceptMerchantOwnership::handler(ctx)
+    }
+
+    /// Reward customers who visit the merchant `streak_target` times within a window
+    /// Only the merchant owner can perform this operation
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `streak_target` - Visits needed to complete a streak (0 = no streak reward)
+    /// * `streak_window_seconds` - Time from a streak's first visit to complete it
+    /// * `streak_bonus` - Reward tokens minted to a customer completing a streak
+    /// * `streak_counts_every_purchase` - Count every purchase as a visit instead of
+    ///   counting a customer's purchases on one UTC day once
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_streak_reward(
+        ctx: Context<SetStreakReward>,
+        streak_target: u16,
+        streak_window_seconds: u32,
+        streak_bonus: u64,
+        streak_counts_every_purchase: bool,
+    ) -> Result<()> {
+        SetStreakReward::handler(
+            ctx,
+            streak_target,
+            streak_window_seconds,
+            streak_bonus,
+            streak_counts_every_purchase,
+        )
+    }
+
+    /// Close a merchant account and reclaim its rent
+    /// The merchant's registry index stays in place, marked closed
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn close_merchant(ctx: Context<CloseMerchant>) -> Result<()> {
+        CloseMerchant::handler(ctx)
+    }
+
+    /// Set the extra cashback earned by customers who stake in the voucher pool
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `staker_boost_threshold` - Minimum pool stake needed for the boost
+    /// * `staker_boost_bps` - Boost added to the merchant's cashback rate in basis points (0 disables it)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_staker_boost(
+        ctx: Context<SetStakerBoost>,
+        staker_boost_threshold: u64,
+        staker_boost_bps: u16,
+    ) -> Result<()> {
+        SetStakerBoost::handler(ctx, staker_boost_threshold, staker_boost_bps)
+    }
+
+    /// Require a co-signer on administrative mints above a single-signature limit
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `mint_cosigner` - Second signer for large mints (default pubkey disables it)
+    /// * `single_sig_mint_limit` - Largest mint the update authority can sign alone
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_mint_cosigner(
+        ctx: Context<SetMintCosigner>,
+        mint_cosigner: Pubkey,
+        single_sig_mint_limit: u64,
+    ) -> Result<()> {
+        SetMintCosigner::handler(ctx, mint_cosigner, single_sig_mint_limit)
+    }
+
+    /// Set the largest transfer, mint and purchase the program accepts
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `max_transfer_amount` - Largest peer-to-peer transfer (0 = no limit)
+    /// * `max_mint_per_tx` - Largest `mint_lokal_tokens` amount (0 = no limit)
+    /// * `max_purchase_idr` - Largest purchase in IDR (0 = no limit)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_limits(
+        ctx: Context<SetLimits>,
+        max_transfer_amount: u64,
+        max_mint_per_tx: u64,
+        max_purchase_idr: u64,
+    ) -> Result<()> {
+        SetLimits::handler(ctx, max_transfer_amount, max_mint_per_tx, max_purchase_idr)
+    }
+
+    /// Cap the tokens administrative paths and purchase rewards may mint, each on its own
+    /// Only the update authority can perform this operation; under a timelock use
+    /// `queue_param_change`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `admin_mint_cap` - Largest `total_admin_minted` (0 = no cap)
+    /// * `reward_mint_cap` - Largest `total_reward_minted` (0 = no cap)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_mint_caps(
+        ctx: Context<SetMintCaps>,
+        admin_mint_cap: u64,
+        reward_mint_cap: u64,
+    ) -> Result<()> {
+        SetMintCaps::handler(ctx, admin_mint_cap, reward_mint_cap)
+    }
+
+    /// Set how often and how far merchants may change their cashback rates
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `rate_change_cooldown_seconds` - Shortest time between a merchant's rate changes
+    ///   (0 = no cooldown)
+    /// * `max_rate_increase_bps` - Largest rate increase in one change (0 = no cap)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_rate_change_policy(
+        ctx: Context<SetRateChangePolicy>,
+        rate_change_cooldown_seconds: u32,
+        max_rate_increase_bps: u16,
+    ) -> Result<()> {
+        SetRateChangePolicy::handler(ctx, rate_change_cooldown_seconds, max_rate_increase_bps)
+    }
+
+    /// Cap merchants' cashback rates and set the rate that raises a `HighCashbackAlertEvent`
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `max_cashback_bps` - Highest cashback rate a merchant may set or earn rewards at
+    /// * `alert_threshold_bps` - Rate above which setting a merchant's rate raises an alert
+    ///   (0 = no alerts)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_cashback_limits(
+        ctx: Context<SetCashbackLimits>,
+        max_cashback_bps: u16,
+        alert_threshold_bps: u16,
+    ) -> Result<()> {
+        SetCashbackLimits::handler(ctx, max_cashback_bps, alert_threshold_bps)
+    }
+
+    /// Value tokens from a Pyth price feed instead of the static token rate
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `price_feed` - Pyth price account quoting IDR per token (default pubkey disables it)
+    /// * `max_price_age_seconds` - Oldest price a purchase will accept
+    /// * `max_price_confidence_bps` - Widest confidence interval a purchase will accept
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_price_feed(
+        ctx: Context<SetPriceFeed>,
+        price_feed: Pubkey,
+        max_price_age_seconds: u32,
+        max_price_confidence_bps: u16,
+    ) -> Result<()> {
+        SetPriceFeed::handler(ctx, price_feed, max_price_age_seconds, max_price_confidence_bps)
+    }
+
+    /// Change how long after a purchase its customer may open a dispute
+    /// Only the update authority can perform this operation, and only without a timelock;
+    /// otherwise queue the change
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `dispute_window_seconds` - The new dispute window (0 disables disputes)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_dispute_window(
+        ctx: Context<SetDisputeWindow>,
+        dispute_window_seconds: u32,
+    ) -> Result<()> {
+        SetDisputeWindow::handler(ctx, dispute_window_seconds)
+    }
+
+    /// Set the backend key that records purchase fulfillment and how long after a purchase
+    /// it may do so
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `fulfillment_authority` - The new fulfillment authority (default pubkey disables it)
+    /// * `fulfillment_window_seconds` - The new fulfillment window
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_fulfillment_authority(
+        ctx: Context<SetFulfillmentAuthority>,
+        fulfillment_authority: Pubkey,
+        fulfillment_window_seconds: u32,
+    ) -> Result<()> {
+        SetFulfillmentAuthority::handler(ctx, fulfillment_authority, fulfillment_window_seconds)
+    }
+
+    /// Hold purchase rewards in escrow as expiring reward lots instead of minting them
+    /// to the customer
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `enabled` - Whether new purchase rewards go into expiring lots
+    /// * `reward_lifetime_seconds` - How long a lot can be withdrawn before it expires
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_reward_expiry(
+        ctx: Context<SetRewardExpiry>,
+        enabled: bool,
+        reward_lifetime_seconds: i64,
+    ) -> Result<()> {
+        SetRewardExpiry::handler(ctx, enabled, reward_lifetime_seconds)
+    }
+
+    /// Burn the remainder of an expired reward lot
+    /// Anyone can call this; cranking a lot twice is a no-op
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn expire_rewards(ctx: Context<ExpireRewards>) -> Result<()> {
+        ExpireRewards::handler(ctx)
+    }
+
+    /// Move an unexpired reward lot into the customer's wallet and close it
+    /// Lots already burned by `expire_rewards` can also be closed this way
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn withdraw_vested_rewards(ctx: Context<WithdrawVestedRewards>) -> Result<()> {
+        WithdrawVestedRewards::handler(ctx)
+    }
+
+    /// Mint a merchant incentive grant into escrow, vesting linearly to the beneficiary
+    /// Only the update authority can perform this operation; grants above the
+    /// single-signature mint limit also need the mint co-signer
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `beneficiary` - Wallet that can claim the vested tokens
+    /// * `total` - Tokens granted
+    /// * `start` - Timestamp vesting starts from
+    /// * `cliff` - Timestamp before which nothing can be claimed
+    /// * `duration` - Seconds from `start` until the whole grant has vested
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn create_vesting_grant(
+        ctx: Context<CreateVestingGrant>,
+        beneficiary: Pubkey,
+        total: u64,
+        start: i64,
+        cliff: i64,
+        duration: i64,
+    ) -> Result<()> {
+        CreateVestingGrant::handler(ctx, beneficiary, total, start, cliff, duration)
+    }
+
+    /// Withdraw the vested, unclaimed part of a vesting grant
+    /// Only the grant's beneficiary can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        ClaimVested::handler(ctx)
+    }
+
+    /// Stop a vesting grant and return its unvested tokens to the treasury
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn revoke_vesting_grant(ctx: Context<RevokeVestingGrant>) -> Result<()> {
+        RevokeVestingGrant::handler(ctx)
+    }
+
+    /// Turn the wallet activity feed on or off
+    /// While enabled, purchases and transfers must also write their `ActivityRecord`s
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `enabled` - Whether activity records are written
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_activity_recording(ctx: Context<SetActivityRecording>, enabled: bool) -> Result<()> {
+        SetActivityRecording::handler(ctx, enabled)
+    }
+
+    /// Choose whether `process_purchase` and `pay_invoice` write a slim
+    /// `PurchaseTransactionV2` instead of a `PurchaseTransaction`
+    /// Records already written keep their layout
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `enabled` - Whether purchases write slim records
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_slim_purchase_records(
+        ctx: Context<SetSlimPurchaseRecords>,
+        enabled: bool,
+    ) -> Result<()> {
+        SetSlimPurchaseRecords::handler(ctx, enabled)
+    }
+
+    /// Choose whether purchase records, their PDA seeds and purchase events carry
+    /// sha256(customer || salt) instead of the customer's wallet
+    /// The salt is stored on the config and can only be set once, so each customer keeps
+    /// one hash; records already written keep what they hold
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `enabled` - Whether purchase records hash the customer
+    /// * `salt` - The salt to set, or the current one (all zeros keeps it)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_customer_hashing(
+        ctx: Context<SetCustomerHashing>,
+        enabled: bool,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        SetCustomerHashing::handler(ctx, enabled, salt)
+    }
+
+    /// Set the protocol fee taken from redeemed tokens and the treasury that receives it
+    /// Only the update authority can perform this operation
+    /// While a parameter timelock is set the fee must stay the same; queue fee changes instead
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `protocol_fee_bps` - Share of redeemed tokens sent to the treasury in basis points (0 disables it)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_protocol_fee(ctx: Context<SetProtocolFee>, protocol_fee_bps: u16) -> Result<()> {
+        SetProtocolFee::handler(ctx, protocol_fee_bps)
+    }
+
+    /// Set the fee taken from peer-to-peer transfers, paid to the protocol treasury
+    /// Only the update authority can perform this operation; requires a treasury to be set
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `transfer_fee_bps` - Share of each transfer sent to the treasury in basis points (0 disables it)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_transfer_fee(ctx: Context<SetTransferFee>, transfer_fee_bps: u16) -> Result<()> {
+        SetTransferFee::handler(ctx, transfer_fee_bps)
+    }
+
+    /// Configure dual control and the per-withdrawal cap for treasury withdrawals
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `treasury_cosigner` - Second signer required on withdrawals (default pubkey disables it)
+    /// * `treasury_withdrawal_cap` - Largest single withdrawal (0 = no cap)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_treasury_controls(
+        ctx: Context<SetTreasuryControls>,
+        treasury_cosigner: Pubkey,
+        treasury_withdrawal_cap: u64,
+    ) -> Result<()> {
+        SetTreasuryControls::handler(ctx, treasury_cosigner, treasury_withdrawal_cap)
+    }
+
+    /// Withdraw collected protocol fees from the treasury token account
+    /// Requires the update authority, plus the co-signer when dual control is on
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Amount of tokens to withdraw
+    /// * `reason_code` - Caller-defined code stored in the withdrawal record
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn withdraw_treasury(
+        ctx: Context<WithdrawTreasury>,
+        amount: u64,
+        reason_code: u16,
+    ) -> Result<()> {
+        WithdrawTreasury::handler(ctx, amount, reason_code)
+    }
+
+    /// Block a Lokal token account and freeze it, starting the clawback waiting period
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `reason_code` - Caller-defined code stored in the restriction
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn restrict_account(ctx: Context<RestrictAccount>, reason_code: u16) -> Result<()> {
+        RestrictAccount::handler(ctx, reason_code)
+    }
+
+    /// Lift a restriction that was not used for a clawback and thaw the account
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn lift_account_restriction(ctx: Context<LiftAccountRestriction>) -> Result<()> {
+        LiftAccountRestriction::handler(ctx)
+    }
+
+    /// Credit the treasury with the balance of an account blocked for the waiting period
+    /// Requires the update authority and the mint co-signer; the account stays frozen
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn clawback(ctx: Context<Clawback>) -> Result<()> {
+        Clawback::handler(ctx)
+    }
+
+    /// Set the delay that queued parameter changes must wait out
+    /// Lengthening applies immediately; shortening an active timelock must be queued
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `param_timelock_seconds` - Delay in seconds (0 disables the timelock)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_param_timelock(ctx: Context<SetParamTimelock>, param_timelock_seconds: i64) -> Result<()> {
+        SetParamTimelock::handler(ctx, param_timelock_seconds)
+    }
+
+    /// Queue a change to the token rate, protocol fee, mint co-signer, timelock or a pool's
+    /// configuration
+    /// The change can be executed once the configured timelock has passed
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `change` - The parameter and its new value
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn queue_param_change(ctx: Context<QueueParamChange>, change: ParamChange) -> Result<()> {
+        QueueParamChange::handler(ctx, change)
+    }
+
+    /// Apply a queued parameter change after its timelock
+    /// Anyone can execute it; the pending change account is closed to its proposer
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn execute_param_change(ctx: Context<ExecuteParamChange>) -> Result<()> {
+        ExecuteParamChange::handler(ctx)
+    }
+
+    /// Cancel a queued parameter change
+    /// Only the authority that queued it can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn cancel_param_change(ctx: Context<CancelParamChange>) -> Result<()> {
+        CancelParamChange::handler(ctx)
+    }
+
+    /// Set the guardian, a key that can pause the program, pools and merchants
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `guardian` - The guardian key (default pubkey removes the guardian)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        SetGuardian::handler(ctx, guardian)
+    }
+
+    /// Halt or resume purchases and transfers
+    /// The guardian can only pause; unpausing requires the update authority
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `paused` - Whether purchases and transfers are halted
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_global_pause(ctx: Context<SetGlobalPause>, paused: bool) -> Result<()> {
+        SetGlobalPause::handler(ctx, paused)
+    }
+
+    /// Suspend or reinstate a merchant, blocking purchases at it while suspended
+    /// The guardian can only suspend; reinstating requires the update authority
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `suspended` - Whether the merchant is suspended
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_merchant_suspension(ctx: Context<SetMerchantSuspension>, suspended: bool) -> Result<()> {
+        SetMerchantSuspension::handler(ctx, suspended)
+    }
+
+    /// Preview the reward a purchase would earn, using the same math as `process_purchase`
+    /// Read-only, so clients can simulate it without the customer's signature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `fiat_amount` - The fiat payment amount in Indonesian Rupiah (IDR)
+    /// * `redeem_token_amount` - Optional amount of tokens that would be redeemed as payment
+    /// 
+    /// # Returns
+    /// * `Result<PurchaseRewardPreview>` - Total value, cashback rate, reward amount and the
+    ///   category boost's part of it
+    pub fn preview_purchase_reward(
+        ctx: Context<PreviewPurchaseReward>,
+        fiat_amount: u64,
+        redeem_token_amount: Option<u64>,
+    ) -> Result<PurchaseRewardPreview> {
+        PreviewPurchaseReward::handler(ctx, fiat_amount, redeem_token_amount)
+    }
+
+    /// Report the program's health: pause flags, supply and mint headroom, pending
+    /// parameter changes, and a pool's switches, vault backing and yield freshness
+    /// Read-only, so monitoring bots can simulate it
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<ProgramStatus>` - Versioned status summary
+    pub fn get_program_status(ctx: Context<GetProgramStatus>) -> Result<ProgramStatus> {
+        GetProgramStatus::handler(ctx)
+    }
+
+    /// Transfer Lokal tokens between user accounts
+    /// Enables peer-to-peer token transfers within the ecosystem
+    /// The transfer fee, unless the sender is exempt, goes to the treasury out of `amount`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of tokens to transfer (in smallest unit, considering 9 decimals)
+    /// * `transaction_id` - Unique identifier for this transaction (32 bytes)
+    /// * `memo` - Optional memo describing the transfer (max 64 characters)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn transfer_tokens(
+        ctx: Context<TransferTokens>,
+        amount: u64,
+        transaction_id: [u8; 32],
+        memo: String,
+    ) -> Result<()> {
+        TransferTokens::handler(ctx, amount, transaction_id, memo)
+    }
+
+    /// Exempt a wallet (e.g. a merchant) from the transfer fee on transfers it sends
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn add_fee_exemption(ctx: Context<AddFeeExemption>) -> Result<()> {
+        AddFeeExemption::handler(ctx)
+    }
+
+    /// Remove a wallet's transfer fee exemption
+    /// Only the update authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn remove_fee_exemption(ctx: Context<RemoveFeeExemption>) -> Result<()> {
+        RemoveFeeExemption::handler(ctx)
+    }
+
+    // NOTE: redeem_tokens and burn_tokens functions have been integrated into process_purchase
+    // Token redemption is now handled as an optional parameter in process_purchase
+    // This provides a unified transaction experience where users can pay with tokens
+    // and still earn rewards in a single transaction
+
+    // ============================================================================
+    // Voucher Pool Instructions for Non-Custodial Staking
+    // ============================================================================
+
+    /// Initialize a new voucher staking pool for LOKAL tokens
+    /// Creates the pool state and configures staking parameters
+    /// Several pools can coexist, each addressed by its voucher mint and `pool_id`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `pool_id` - Identifier of the pool among the voucher mint's pools
+    /// * `config` - Pool configuration including stake limits and APY
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        pool_id: u16,
+        config: PoolConfig,
+    ) -> Result<()> {
+        InitializePool::handler(ctx, pool_id, config)
+    }
+
+    /// Deposit voucher tokens into the staking pool using delegated authority
+    /// The user must have previously approved the pool delegate for at least `amount`
+    /// When `auto_revoke_delegation` is set, any leftover approval is revoked afterwards
+    /// Any insurance fee stays in the vault; the user is credited with the rest
+    /// Mints sLOKAL receipt tokens 1:1 for the credited stake
+    /// Whitelist-gated pools also require the user's whitelist entry
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of voucher tokens to stake
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn deposit_voucher(ctx: Context<DepositVoucher>, amount: u64) -> Result<()> {
+        DepositVoucher::handler(ctx, amount)
+    }
+
+    /// Deposit voucher tokens into the staking pool on behalf of a beneficiary
+    /// The funder signs and pays from its own token account; the stake, its receipts and the
+    /// right to redeem belong to the beneficiary, with the funder recorded as `funded_by`
+    /// The per-user cap and whitelist apply to the beneficiary
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of voucher tokens to stake
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn deposit_for_beneficiary(ctx: Context<DepositForBeneficiary>, amount: u64) -> Result<()> {
+        DepositForBeneficiary::handler(ctx, amount)
+    }
+
+    /// Record yield earned from staking activities
+    /// Called by the backend after swapping vouchers to SOL and earning yield
+    /// The pool's performance fee is set aside in `protocol_fees_accrued`; stakers get the rest
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `sol_amount` - The amount of SOL yield earned
+    /// * `yield_batch_id` - Unique batch identifier, replaying a batch fails
+    /// * `expected_previous_index` - Optional reward index the caller expects the pool to be at
+    /// * `external_sequence` - Position of this yield in the backend's journal, which must be
+    ///   the pool's `last_external_sequence + 1`
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn record_yield(
+        ctx: Context<RecordYield>,
+        sol_amount: u64,
+        yield_batch_id: [u8; 32],
+        expected_previous_index: Option<u128>,
+        external_sequence: u64,
+    ) -> Result<()> {
+        RecordYield::handler(
+            ctx,
+            sol_amount,
+            yield_batch_id,
+            expected_previous_index,
+            external_sequence,
+        )
+    }
+
+    /// Reset the pool's yield journal position after a genuine journal reset
+    /// Only the pool delegate can perform this operation; always logged and evented, since
+    /// it lets journal entries be recorded again
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `last_external_sequence` - New last position; the next yield must pass one more
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn reset_external_sequence(
+        ctx: Context<ResetExternalSequence>,
+        last_external_sequence: u64,
+    ) -> Result<()> {
+        ResetExternalSequence::handler(ctx, last_external_sequence)
+    }
+
+    /// Point the pool's deployed SOL at an SPL stake pool
+    /// Only the pool authority can perform this operation; only available in builds with the
+    /// `stake-pool` feature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    #[cfg(feature = "stake-pool")]
+    pub fn configure_stake_pool(ctx: Context<ConfigureStakePool>) -> Result<()> {
+        ConfigureStakePool::handler(ctx)
+    }
+
+    /// Deposit SOL from the pool's SOL vault into its stake pool for pool tokens
+    /// Only the pool authority can perform this operation; only available in builds with the
+    /// `stake-pool` feature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `lamports` - SOL to deposit from the vault
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    #[cfg(feature = "stake-pool")]
+    pub fn deploy_to_stake_pool(ctx: Context<DeployToStakePool>, lamports: u64) -> Result<()> {
+        DeployToStakePool::handler(ctx, lamports)
+    }
+
+    /// Redeem pool tokens for SOL paid back into the pool's SOL vault, realizing the gain
+    /// or loss against their cost basis
+    /// Only the pool authority can perform this operation; only available in builds with the
+    /// `stake-pool` feature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `pool_tokens` - Pool tokens to redeem
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    #[cfg(feature = "stake-pool")]
+    pub fn unwind_from_stake_pool(
+        ctx: Context<UnwindFromStakePool>,
+        pool_tokens: u64,
+    ) -> Result<()> {
+        UnwindFromStakePool::handler(ctx, pool_tokens)
+    }
+
+    /// Record a loss on deployed capital
+    /// Called by the backend; reduces claimable yield in proportion to stake
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `sol_amount` - The amount of SOL lost
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn record_loss(ctx: Context<RecordLoss>, sol_amount: u64) -> Result<()> {
+        RecordLoss::handler(ctx, sol_amount)
+    }
+
+    /// Redeem staked vouchers and claim earned yield
+    /// Allows users to unstake their tokens and withdraw
+    /// Withdrawals before the stake's lock expires pay the pool's early-withdrawal penalty
+    /// Burns an equal amount of sLOKAL receipts, so at most min(staked, receipts) is redeemable
+    /// Stake locked as collateral for a `TokenAdvance` cannot be redeemed until it is repaid
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of voucher tokens to redeem
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn redeem_voucher(ctx: Context<RedeemVoucher>, amount: u64) -> Result<()> {
+        RedeemVoucher::handler(ctx, Some(amount))
+    }
+
+    /// Redeem the whole stake and claim earned yield, for a full exit
+    /// Redeems `staked_amount` as it stands when the instruction runs, so yield, losses or
+    /// penalties applied since the caller last read the stake leave no dust behind
+    /// Same accounts and rules as `redeem_voucher`; follow it with `close_stake_record` in
+    /// the same transaction to also reclaim the record's rent
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn redeem_all_vouchers(ctx: Context<RedeemVoucher>) -> Result<()> {
+        RedeemVoucher::handler(ctx, None)
+    }
+
+    /// Update pool configuration settings
+    /// Only the pool authority can perform this operation
+    /// Only the provided fields change; the merged config is validated as a whole
+    /// Rejected while a parameter timelock is set; use `queue_param_change` instead
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `update` - Pool configuration fields to change
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn update_pool_config(ctx: Context<UpdatePoolConfig>, update: PoolConfigUpdate) -> Result<()> {
+        UpdatePoolConfig::handler(ctx, update)
+    }
+
+    /// Set the share of a stake its owner may borrow with `borrow_against_stake`
+    /// Only the pool authority can perform this operation; 0 disables new advances
+    /// Rejected while a parameter timelock is set; use `queue_param_change` instead
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `advance_ltv_bps` - Loan-to-value limit in basis points, at most 10000
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_advance_ltv(ctx: Context<SetAdvanceLtv>, advance_ltv_bps: u16) -> Result<()> {
+        SetAdvanceLtv::handler(ctx, advance_ltv_bps)
+    }
+
+    /// Mint an interest-free advance of Lokal tokens against the user's stake
+    /// The outstanding advance may reach the pool's `advance_ltv_bps` of the stake; the
+    /// stake securing it is locked against `redeem_voucher` and `request_unstake`
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Tokens to borrow
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn borrow_against_stake(ctx: Context<BorrowAgainstStake>, amount: u64) -> Result<()> {
+        BorrowAgainstStake::handler(ctx, amount)
+    }
+
+    /// Burn Lokal tokens to repay part or all of an advance
+    /// The stake lock shrinks with the advance; a fully repaid advance is closed
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Tokens to repay, at most the outstanding advance
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn repay_advance(ctx: Context<RepayAdvance>, amount: u64) -> Result<()> {
+        RepayAdvance::handler(ctx, amount)
+    }
+
+    /// Set the token account that receives vouchers withdrawn for deployment
+    /// Only the pool authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_deployment_destination(ctx: Context<SetDeploymentDestination>) -> Result<()> {
+        SetDeploymentDestination::handler(ctx)
+    }
+
+    /// Withdraw staked vouchers from the vault for off-chain deployment
+    /// Called by the backend before swapping vouchers to SOL
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of voucher tokens to withdraw
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn withdraw_for_deployment(ctx: Context<WithdrawForDeployment>, amount: u64) -> Result<()> {
+        WithdrawForDeployment::handler(ctx, amount)
+    }
+
+    /// Return previously deployed voucher tokens to the vault
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of voucher tokens to return
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn return_principal(ctx: Context<ReturnPrincipal>, amount: u64) -> Result<()> {
+        ReturnPrincipal::handler(ctx, amount)
+    }
+
+    /// Close an empty user stake record and reclaim its rent
+    /// Callable by the staker or the pool delegate; rent returns to whoever paid for the record
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn close_stake_record(ctx: Context<CloseStakeRecord>) -> Result<()> {
+        CloseStakeRecord::handler(ctx)
+    }
+
+    /// Close a batch of fully exited stake records, passed as writable remaining accounts
+    /// Only the pool delegate can call this; records it did not pay for, or that still
+    /// hold stake, history or yield, are skipped
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context, with the stake records as remaining accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn close_stake_records<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseStakeRecords<'info>>,
+    ) -> Result<()> {
+        CloseStakeRecords::handler(ctx)
+    }
+
+    /// Resync a user's reward checkpoint to the pool's current reward index
+    /// Only the pool authority can perform this operation; any unclaimed yield
+    /// between the two indices is forfeited
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn repair_stake_record(ctx: Context<RepairStakeRecord>) -> Result<()> {
+        RepairStakeRecord::handler(ctx)
+    }
+
+    /// Set the guardian key that can pause the pool in an emergency
+    /// Only the pool authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `guardian` - The new guardian public key
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_pool_guardian(ctx: Context<SetPoolGuardian>, guardian: Pubkey) -> Result<()> {
+        SetPoolGuardian::handler(ctx, guardian)
+    }
+
+    /// Emergency pause for pool deposits and withdrawals
+    /// Callable by the pool authority, the pool guardian or the mint guardian; only the
+    /// authority can unpause
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `pause_deposits` - Optional new paused state for deposits
+    /// * `pause_withdrawals` - Optional new paused state for withdrawals
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_pool_pause(
+        ctx: Context<SetPoolPause>,
+        pause_deposits: Option<bool>,
+        pause_withdrawals: Option<bool>,
+    ) -> Result<()> {
+        SetPoolPause::handler(ctx, pause_deposits, pause_withdrawals)
+    }
+
+    /// Nominate a new pool authority (first step of a two-step transfer)
+    /// Only the current pool authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `new_authority` - The nominated authority, replacing any earlier nomination
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn propose_pool_authority(ctx: Context<ProposePoolAuthority>, new_authority: Pubkey) -> Result<()> {
+        ProposePoolAuthority::handler(ctx, new_authority)
+    }
+
+    /// Accept a pool authority nomination (second step of a two-step transfer)
+    /// Must be signed by the nominated authority
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn accept_pool_authority(ctx: Context<AcceptPoolAuthority>) -> Result<()> {
+        AcceptPoolAuthority::handler(ctx)
+    }
+
+    /// Opt a stake in or out of automatic yield compounding
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `enabled` - Whether the delegate may compound this stake's yield
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+        SetAutoCompound::handler(ctx, enabled)
+    }
+
+    /// Restake a user's claimable yield as vouchers bought by the delegate
+    /// Only the pool delegate can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `rate` - Lamports paid per whole voucher token, recorded on the stake record
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn compound_user(ctx: Context<CompoundUser>, rate: u64) -> Result<()> {
+        CompoundUser::handler(ctx, rate)
+    }
+
+    /// Approve a wallet to deposit into a whitelist-gated pool
+    /// Only the pool authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn add_to_whitelist(ctx: Context<AddToWhitelist>) -> Result<()> {
+        AddToWhitelist::handler(ctx)
+    }
+
+    /// Revoke a wallet's whitelist approval; existing stakes can still be withdrawn
+    /// Only the pool authority can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn remove_from_whitelist(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
+        RemoveFromWhitelist::handler(ctx)
+    }
+
+    /// Settle accrued yield into `pending_payout` for a batch of stake records
+    /// Records are passed as writable remaining accounts; up-to-date records are skipped
+    /// Only the pool delegate can perform this operation
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn settle_users<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleUsers<'info>>,
+    ) -> Result<()> {
+        SettleUsers::handler(ctx)
+    }
+
+    /// Accrue the guaranteed minimum APY yield since the last yield update
+    /// Permissionless; accrual is capped by the pool's guaranteed yield budget
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn accrue_minimum_yield(ctx: Context<AccrueMinimumYield>) -> Result<()> {
+        AccrueMinimumYield::handler(ctx)
+    }
+
+    /// Queue part of a stake for withdrawal; the amount stops earning yield immediately
+    /// Only available in pools with queued withdrawals enabled
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - The amount of staked vouchers to queue
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        RequestUnstake::handler(ctx, amount)
+    }
+
+    /// Cancel a pending unstake request and restake its amount
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn cancel_unstake(ctx: Context<CancelUnstake>) -> Result<()> {
+        CancelUnstake::handler(ctx)
+    }
+
+    /// Pay out an unstake request once the pool's unstake delay has passed
+    /// Permissionless; the vouchers always go to the requesting user
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn process_unstake(ctx: Context<ProcessUnstake>) -> Result<()> {
+        ProcessUnstake::handler(ctx)
+    }
+
+    /// Check the pool's accounting invariants and emit a pass/fail report
+    /// Permissionless; in strict mode any failed invariant returns an error
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `strict` - Whether a failed invariant should fail the transaction
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn verify_pool_invariants(ctx: Context<VerifyPoolInvariants>, strict: bool) -> Result<()> {
+        VerifyPoolInvariants::handler(ctx, strict)
+    }
+
+    /// Move insurance reserve to the deployment destination to cover a recorded loss
+    /// Only callable by the pool authority; total draws are capped by cumulative losses
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `amount` - Amount of reserve vouchers to draw
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn draw_insurance(ctx: Context<DrawInsurance>, amount: u64) -> Result<()> {
+        DrawInsurance::handler(ctx, amount)
+    }
+
+    /// Preview the yield a user would claim on redeem, using the same math as `redeem_voucher`
+    /// Read-only, so clients can simulate it without the user's signature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `user` - The staker whose record is previewed
+    /// 
+    /// # Returns
+    /// * `Result<u64>` - Yield currently claimable by the user
+    pub fn preview_claimable_yield(ctx: Context<PreviewClaimableYield>, user: Pubkey) -> Result<u64> {
+        PreviewClaimableYield::handler(ctx, user)
+    }
+
+    /// Refresh the `pending_yield` and `pool_share_bps` cached on a stake record
+    /// Permissionless; deposits and redemptions refresh it too, and between those it goes
+    /// stale as the pool records yield
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn sync_stake_view(ctx: Context<SyncStakeView>) -> Result<()> {
+        SyncStakeView::handler(ctx)
+    }
+
+    /// Write today's aggregate protocol stats (supply, merchants, purchases, transfers, staking)
+    /// Permissionless; calling again on the same day refreshes the snapshot
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `day_index` - The current day (unix_timestamp / 86400)
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn write_stats_snapshot(ctx: Context<WriteStatsSnapshot>, day_index: u64) -> Result<()> {
+        WriteStatsSnapshot::handler(ctx, day_index)
+    }
+
+    // ============================================================================
+    // Account Migrations
+    // ============================================================================
+
+    /// Rewrite the original singleton pool from its first-deployment layout
+    /// Grows the account to the current `PoolState` size and creates its receipt mint
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        MigratePool::handler(ctx)
+    }
+
+    /// Rewrite a stake in the original pool from its first-deployment layout
+    /// Permissionless; also mints the staker's sLOKAL receipts, which legacy stakes lack
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn migrate_stake_record(ctx: Context<MigrateStakeRecord>) -> Result<()> {
+        MigrateStakeRecord::handler(ctx)
+    }
+
+    /// Rewrite the mint configuration from its first-deployment layout
+    /// Grows the account to the current `LokalMintConfig` size and fills the fields the
+    /// legacy layout lacks with the defaults of `initialize_lokal_mint`
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        MigrateConfig::handler(ctx)
+    }
+
+    /// Rewrite a merchant from its first-deployment layout
+    /// Permissionless; grows the account to the current `MerchantAccount` size and gives
+    /// the merchant the registry position legacy merchants lack
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn migrate_merchant(ctx: Context<MigrateMerchant>) -> Result<()> {
+        MigrateMerchant::handler(ctx)
+    }
+
+    /// Rewrite a purchase record from its 164-byte layout
+    /// Permissionless; grows the account to the current `PurchaseTransaction` size with the
+    /// fields added since unset, so disputes and fulfillment can load it again
+    ///
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    pub fn migrate_purchase_transaction(ctx: Context<MigratePurchaseTransaction>) -> Result<()> {
+        MigratePurchaseTransaction::handler(ctx)
+    }
+
+    /// Overwrite pool accounting fields to simulate state drift in tests
+    /// Only available in builds with the `test-utils` feature
+    /// 
+    /// # Arguments
+    /// * `ctx` - The instruction context containing required accounts
+    /// * `total_voucher_staked` - New total staked, if any
+    /// * `total_stakers` - New staker count, if any
+    /// * `reward_index` - New reward index, if any
+    /// 
+    /// # Returns
+    /// * `Result<()>` - Success or error result
+    #[cfg(feature = "test-utils")]
+    pub fn corrupt_pool_state(
+        ctx: Context<CorruptPoolState>,
+        total_voucher_staked: Option<u64>,
+        total_stakers: Option<u64>,
+        reward_index: Option<u128>,
+    ) -> Result<()> {
+        CorruptPoolState::handler(ctx, total_voucher_staked, total_stakers, reward_index)
+    }
+}
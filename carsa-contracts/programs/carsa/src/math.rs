@@ -0,0 +1,223 @@
+//! Pure arithmetic behind purchase rewards and pool yield
+//! Handlers call these instead of inlining the formulas, so client SDKs can mirror them exactly
+
+use anchor_lang::prelude::*;
+use crate::error::CarsaError;
+use crate::state::{BASIS_POINTS, REWARD_INDEX_SCALE};
+
+/// Decimals of the Lokal token mint
+pub const LOKAL_DECIMALS: u8 = 9;
+
+/// Base units in one whole token of a mint with `decimals`
+fn token_unit(decimals: u8) -> Result<u128> {
+    let unit = 10u128
+        .checked_pow(decimals as u32)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+    Ok(unit)
+}
+
+/// IDR value of `redeemed_tokens` base units, with one token worth `token_to_idr_rate` IDR
+/// plus the merchant's `redemption_bonus_bps`
+/// Only whole tokens count, and the bonus rounds down
+pub fn calculate_token_value(
+    redeemed_tokens: u64,
+    token_to_idr_rate: u64,
+    redemption_bonus_bps: u16,
+    decimals: u8,
+) -> Result<u64> {
+    let whole_tokens = (redeemed_tokens as u128)
+        .checked_div(token_unit(decimals)?)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    let value = whole_tokens
+        .checked_mul(token_to_idr_rate as u128)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_mul(BASIS_POINTS + redemption_bonus_bps as u128)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_div(BASIS_POINTS)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    let value = u64::try_from(value).map_err(|_| CarsaError::ArithmeticOverflow)?;
+    Ok(value)
+}
+
+/// Reward tokens, in base units, earned on `total_value` IDR at `cashback_bps`, with one
+/// token worth `token_to_idr_rate` IDR
+/// Formula: reward = total_value * cashback_bps / 10_000 / token_to_idr_rate * 10^decimals,
+/// multiplied out first so only the final result rounds down
+pub fn calculate_reward(
+    total_value: u64,
+    cashback_bps: u16,
+    token_to_idr_rate: u64,
+    decimals: u8,
+) -> Result<u64> {
+    let reward = (total_value as u128)
+        .checked_mul(cashback_bps as u128)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_mul(token_unit(decimals)?)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_div(BASIS_POINTS)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_div(token_to_idr_rate as u128)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    let reward = u64::try_from(reward).map_err(|_| CarsaError::ArithmeticOverflow)?;
+    Ok(reward)
+}
+
+/// Yield accrued by `staked_amount` over a reward index difference, rounded down
+pub fn calculate_claimable_yield(staked_amount: u64, index_delta: u128) -> Result<u64> {
+    let accrued = (staked_amount as u128)
+        .checked_mul(index_delta)
+        .ok_or(CarsaError::ArithmeticOverflow)?
+        .checked_div(REWARD_INDEX_SCALE)
+        .ok_or(CarsaError::ArithmeticOverflow)?;
+
+    let accrued = u64::try_from(accrued).map_err(|_| CarsaError::ArithmeticOverflow)?;
+    Ok(accrued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{reward_index_increment, LokalMintConfig};
+    use proptest::prelude::*;
+
+    const TOKEN: u64 = 1_000_000_000;
+    const MAX_PURCHASE_AMOUNT: u64 = LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR;
+
+    proptest! {
+        #[test]
+        fn reward_never_overflows_up_to_the_purchase_maximum(
+            total_value in 0..=MAX_PURCHASE_AMOUNT,
+            cashback_bps in 0..=10_000u16,
+            token_to_idr_rate in 1..=u64::MAX,
+        ) {
+            prop_assert!(
+                calculate_reward(total_value, cashback_bps, token_to_idr_rate, LOKAL_DECIMALS).is_ok()
+            );
+        }
+
+        #[test]
+        fn reward_rounds_down(
+            total_value in 0..=MAX_PURCHASE_AMOUNT,
+            cashback_bps in 0..=10_000u16,
+            token_to_idr_rate in 1..=1_000_000_000_000u64,
+        ) {
+            let reward =
+                calculate_reward(total_value, cashback_bps, token_to_idr_rate, LOKAL_DECIMALS).unwrap();
+
+            // reward <= exact < reward + 1, compared without dividing
+            let exact = total_value as u128 * cashback_bps as u128 * TOKEN as u128;
+            let scale = BASIS_POINTS * token_to_idr_rate as u128;
+            prop_assert!(reward as u128 * scale <= exact);
+            prop_assert!((reward as u128 + 1) * scale > exact);
+        }
+
+        #[test]
+        fn reward_grows_with_value_and_rate_and_shrinks_with_token_price(
+            values in (0..=MAX_PURCHASE_AMOUNT, 0..=MAX_PURCHASE_AMOUNT),
+            rates in (0..=10_000u16, 0..=10_000u16),
+            prices in (1..=1_000_000u64, 1..=1_000_000u64),
+        ) {
+            let (low_value, high_value) = (values.0.min(values.1), values.0.max(values.1));
+            let (low_rate, high_rate) = (rates.0.min(rates.1), rates.0.max(rates.1));
+            let (low_price, high_price) = (prices.0.min(prices.1), prices.0.max(prices.1));
+            let reward = |value, rate, price| calculate_reward(value, rate, price, LOKAL_DECIMALS).unwrap();
+
+            prop_assert!(reward(low_value, low_rate, low_price) <= reward(high_value, low_rate, low_price));
+            prop_assert!(reward(low_value, low_rate, low_price) <= reward(low_value, high_rate, low_price));
+            prop_assert!(reward(low_value, low_rate, high_price) <= reward(low_value, low_rate, low_price));
+        }
+
+        #[test]
+        fn token_value_never_overflows_for_any_balance(
+            redeemed_tokens in any::<u64>(),
+            token_to_idr_rate in 0..=100_000_000u64,
+            redemption_bonus_bps in 0..=10_000u16,
+        ) {
+            prop_assert!(calculate_token_value(
+                redeemed_tokens,
+                token_to_idr_rate,
+                redemption_bonus_bps,
+                LOKAL_DECIMALS,
+            )
+            .is_ok());
+        }
+
+        #[test]
+        fn token_value_counts_whole_tokens_and_grows_with_the_bonus(
+            whole_tokens in 0..=1_000_000u64,
+            fraction in 0..TOKEN,
+            bonuses in (0..=10_000u16, 0..=10_000u16),
+        ) {
+            let rate = LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE;
+            let (low_bonus, high_bonus) = (bonuses.0.min(bonuses.1), bonuses.0.max(bonuses.1));
+            let value = |tokens, bonus| calculate_token_value(tokens, rate, bonus, LOKAL_DECIMALS).unwrap();
+
+            prop_assert_eq!(
+                value(whole_tokens * TOKEN + fraction, low_bonus),
+                value(whole_tokens * TOKEN, low_bonus)
+            );
+            prop_assert_eq!(value(whole_tokens * TOKEN, 0), whole_tokens * rate);
+            prop_assert!(value(whole_tokens * TOKEN, low_bonus) <= value(whole_tokens * TOKEN, high_bonus));
+        }
+
+        #[test]
+        fn claimable_yield_is_monotonic(
+            stakes in (any::<u64>(), any::<u64>()),
+            deltas in (0..=REWARD_INDEX_SCALE, 0..=REWARD_INDEX_SCALE),
+        ) {
+            let (low_stake, high_stake) = (stakes.0.min(stakes.1), stakes.0.max(stakes.1));
+            let (low_delta, high_delta) = (deltas.0.min(deltas.1), deltas.0.max(deltas.1));
+
+            let base = calculate_claimable_yield(low_stake, low_delta).unwrap();
+            prop_assert!(base <= calculate_claimable_yield(high_stake, low_delta).unwrap());
+            prop_assert!(base <= calculate_claimable_yield(low_stake, high_delta).unwrap());
+        }
+
+        #[test]
+        fn claimable_yield_never_overflows_or_exceeds_the_recorded_yield(
+            staked_amount in 1..=u64::MAX,
+            other_stake in 0..=u64::MAX,
+            yield_amount in any::<u64>(),
+        ) {
+            // Any index the pool can reach from one recorded yield over a pool holding this stake
+            let total_staked = staked_amount as u128 + other_stake as u128;
+            let (index_delta, _) = reward_index_increment(yield_amount, 0, total_staked).unwrap();
+
+            let claimable = calculate_claimable_yield(staked_amount, index_delta);
+            prop_assert!(claimable.is_ok());
+            prop_assert!(claimable.unwrap() <= yield_amount);
+        }
+
+        #[test]
+        fn user_yields_sum_to_at_most_the_recorded_yield(
+            stakes in prop::collection::vec(1..=1_000_000_000_000_000_000u64, 1..20),
+            yields in prop::collection::vec(1..=10_000_000_000u64, 1..50),
+        ) {
+            let total_staked: u128 = stakes.iter().map(|stake| *stake as u128).sum();
+
+            let mut reward_index = 0u128;
+            let mut dust_carry = 0u128;
+            for yield_amount in &yields {
+                let (increment, carry) =
+                    reward_index_increment(*yield_amount, dust_carry, total_staked).unwrap();
+                reward_index += increment;
+                dust_carry = carry;
+            }
+
+            let recorded: u64 = yields.iter().sum();
+            let claimed: u64 = stakes
+                .iter()
+                .map(|stake| calculate_claimable_yield(*stake, reward_index).unwrap())
+                .sum();
+
+            // Rounding down loses at most one lamport per user; the rest is the carried
+            // dust, which later yield pays out
+            prop_assert!(claimed <= recorded);
+            let shortfall = (recorded - claimed) as u128 * REWARD_INDEX_SCALE;
+            prop_assert!(shortfall <= stakes.len() as u128 * REWARD_INDEX_SCALE + dust_carry);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Reads the aggregate price from a Pyth price account (v2 layout)
+//! The layout is decoded by hand because the Pyth SDK pins an older `solana-program`
+
+use anchor_lang::prelude::*;
+use crate::error::CarsaError;
+
+/// Pyth account magic number
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+
+/// Pyth account layout version
+const PYTH_VERSION: u32 = 2;
+
+/// Pyth account type of a price account
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+
+/// Aggregate status of a price that is currently trading
+const PYTH_STATUS_TRADING: u32 = 1;
+
+/// Byte offsets of the fields read from a price account
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_CONF_OFFSET: usize = 216;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+
+/// Bytes needed to read every field above
+pub const PYTH_PRICE_MIN_LEN: usize = 240;
+
+/// Aggregate price published by a Pyth price account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OraclePrice {
+    /// Price, scaled by `10^exponent`
+    pub price: i64,
+    /// Confidence interval around `price`, in the same units
+    pub conf: u64,
+    /// Power-of-ten exponent applied to `price` and `conf`
+    pub exponent: i32,
+    /// Unix time the aggregate was last updated
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Decode the aggregate price of a Pyth price account
+    /// Returns None unless `data` is a trading v2 price account
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() < PYTH_PRICE_MIN_LEN
+            || read_u32(data, MAGIC_OFFSET)? != PYTH_MAGIC
+            || read_u32(data, VERSION_OFFSET)? != PYTH_VERSION
+            || read_u32(data, ACCOUNT_TYPE_OFFSET)? != PYTH_ACCOUNT_TYPE_PRICE
+            || read_u32(data, AGGREGATE_STATUS_OFFSET)? != PYTH_STATUS_TRADING
+        {
+            return None;
+        }
+
+        Some(Self {
+            price: read_u64(data, AGGREGATE_PRICE_OFFSET)? as i64,
+            conf: read_u64(data, AGGREGATE_CONF_OFFSET)?,
+            exponent: read_u32(data, EXPONENT_OFFSET)? as i32,
+            publish_time: read_u64(data, TIMESTAMP_OFFSET)? as i64,
+        })
+    }
+
+    /// Whole units of the quote currency per token, rounded down
+    /// Returns None unless the price is at most `max_age_seconds` old at `now`, its
+    /// confidence interval is within `max_confidence_bps` of the price and it is at
+    /// least one unit
+    pub fn checked_rate(&self, now: i64, max_age_seconds: u32, max_confidence_bps: u16) -> Option<u64> {
+        let age = now.checked_sub(self.publish_time)?;
+        if age < 0 || age > max_age_seconds as i64 || self.price <= 0 {
+            return None;
+        }
+
+        let price = self.price as u128;
+        if (self.conf as u128).checked_mul(10_000)? > price.checked_mul(max_confidence_bps as u128)? {
+            return None;
+        }
+
+        let scale = 10u128.checked_pow(self.exponent.unsigned_abs())?;
+        let rate = if self.exponent >= 0 {
+            price.checked_mul(scale)?
+        } else {
+            price / scale
+        };
+        u64::try_from(rate).ok().filter(|rate| *rate > 0)
+    }
+}
+
+/// Value of one Lokal token in IDR from the price feed in `account`
+/// Rejects with `StalePriceFeed` rather than falling back to the static rate
+pub fn token_to_idr_rate_from_feed(
+    account: &AccountInfo,
+    now: i64,
+    max_age_seconds: u32,
+    max_confidence_bps: u16,
+) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    OraclePrice::from_account_data(&data)
+        .and_then(|price| price.checked_rate(now, max_age_seconds, max_confidence_bps))
+        .ok_or_else(|| error!(CarsaError::StalePriceFeed))
+}
+
+/// Check the staleness and confidence bounds configured alongside `price_feed`
+pub fn validate_price_feed_config(
+    price_feed: &Pubkey,
+    max_age_seconds: u32,
+    max_confidence_bps: u16,
+) -> Result<()> {
+    if *price_feed != Pubkey::default() {
+        require!(
+            max_age_seconds > 0 && max_confidence_bps <= 10_000,
+            CarsaError::InvalidPriceFeedConfig
+        );
+    }
+    Ok(())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+    const MAX_AGE: u32 = 60;
+    const MAX_CONFIDENCE_BPS: u16 = 200;
+
+    /// Mock Pyth price account quoting `price * 10^exponent` IDR per token
+    fn mock_price_account(price: i64, conf: u64, exponent: i32, publish_time: i64) -> Vec<u8> {
+        let mut data = vec![0u8; 3312];
+        data[MAGIC_OFFSET..MAGIC_OFFSET + 4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[VERSION_OFFSET..VERSION_OFFSET + 4].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[ACCOUNT_TYPE_OFFSET..ACCOUNT_TYPE_OFFSET + 4]
+            .copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[EXPONENT_OFFSET..EXPONENT_OFFSET + 4].copy_from_slice(&exponent.to_le_bytes());
+        data[TIMESTAMP_OFFSET..TIMESTAMP_OFFSET + 8].copy_from_slice(&publish_time.to_le_bytes());
+        data[AGGREGATE_PRICE_OFFSET..AGGREGATE_PRICE_OFFSET + 8]
+            .copy_from_slice(&price.to_le_bytes());
+        data[AGGREGATE_CONF_OFFSET..AGGREGATE_CONF_OFFSET + 8].copy_from_slice(&conf.to_le_bytes());
+        data[AGGREGATE_STATUS_OFFSET..AGGREGATE_STATUS_OFFSET + 4]
+            .copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data
+    }
+
+    fn rate(data: &[u8]) -> Option<u64> {
+        OraclePrice::from_account_data(data)?.checked_rate(NOW, MAX_AGE, MAX_CONFIDENCE_BPS)
+    }
+
+    #[test]
+    fn fresh_price_gives_the_scaled_rate() {
+        // Rp 1,250.5 per token with a 0.5% confidence interval
+        let data = mock_price_account(1_250_500, 6_252, -3, NOW - 10);
+        assert_eq!(rate(&data), Some(1_250));
+
+        let data = mock_price_account(12, 0, 2, NOW);
+        assert_eq!(rate(&data), Some(1_200));
+    }
+
+    #[test]
+    fn stale_price_is_rejected() {
+        let data = mock_price_account(1_000_000, 0, -3, NOW - MAX_AGE as i64);
+        assert_eq!(rate(&data), Some(1_000));
+
+        let data = mock_price_account(1_000_000, 0, -3, NOW - MAX_AGE as i64 - 1);
+        assert_eq!(rate(&data), None);
+
+        // A publish time in the future is as suspect as an old one
+        let data = mock_price_account(1_000_000, 0, -3, NOW + 1);
+        assert_eq!(rate(&data), None);
+    }
+
+    #[test]
+    fn wide_confidence_interval_is_rejected() {
+        let data = mock_price_account(1_000_000, 20_000, -3, NOW);
+        assert_eq!(rate(&data), Some(1_000));
+
+        let data = mock_price_account(1_000_000, 20_001, -3, NOW);
+        assert_eq!(rate(&data), None);
+
+        // An interval larger than the price itself
+        let data = mock_price_account(1_000_000, u64::MAX, -3, NOW);
+        assert_eq!(rate(&data), None);
+    }
+
+    #[test]
+    fn malformed_or_halted_feed_is_rejected() {
+        let mut data = mock_price_account(1_000_000, 0, -3, NOW);
+        data[AGGREGATE_STATUS_OFFSET] = 0;
+        assert_eq!(rate(&data), None);
+
+        let mut data = mock_price_account(1_000_000, 0, -3, NOW);
+        data[MAGIC_OFFSET] ^= 0xff;
+        assert_eq!(rate(&data), None);
+
+        let data = mock_price_account(1_000_000, 0, -3, NOW);
+        assert_eq!(rate(&data[..PYTH_PRICE_MIN_LEN - 1]), None);
+
+        // Non-positive prices and prices below one rupiah
+        assert_eq!(rate(&mock_price_account(-1_000_000, 0, -3, NOW)), None);
+        assert_eq!(rate(&mock_price_account(999, 0, -3, NOW)), None);
+    }
+}
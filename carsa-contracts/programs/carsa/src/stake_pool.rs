@@ -0,0 +1,258 @@
+//! Reads SPL stake-pool accounts and builds the `DepositSol` and `WithdrawSol` instructions
+//! The layout and instruction encoding are written by hand because the stake-pool crate
+//! pins an older `solana-program`
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+/// `AccountType::StakePool`, the first byte of a stake pool account
+const ACCOUNT_TYPE_STAKE_POOL: u8 = 1;
+
+/// Byte offsets of the fields read from a stake pool account
+const ACCOUNT_TYPE_OFFSET: usize = 0;
+const WITHDRAW_BUMP_OFFSET: usize = 97;
+const RESERVE_STAKE_OFFSET: usize = 130;
+const POOL_MINT_OFFSET: usize = 162;
+const MANAGER_FEE_ACCOUNT_OFFSET: usize = 194;
+const TOKEN_PROGRAM_OFFSET: usize = 226;
+const TOTAL_LAMPORTS_OFFSET: usize = 258;
+const POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+
+/// Bytes needed to read every field above
+pub const STAKE_POOL_MIN_LEN: usize = 274;
+
+/// Seed of the stake pool's withdraw authority, which mints and burns its pool tokens
+pub const AUTHORITY_WITHDRAW: &[u8] = b"withdraw";
+
+/// `StakePoolInstruction` variant tags
+const DEPOSIT_SOL_TAG: u8 = 14;
+const WITHDRAW_SOL_TAG: u8 = 16;
+
+/// The native stake program, which `WithdrawSol` uses to draw on the reserve
+pub const STAKE_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    6, 161, 216, 23, 145, 55, 84, 42, 152, 52, 55, 189, 254, 42, 122, 178, 85, 127, 83, 92, 138,
+    120, 114, 43, 104, 164, 157, 192, 0, 0, 0, 0,
+]);
+
+/// The fields of a stake pool account the program relies on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StakePoolInfo {
+    /// Bump of the withdraw authority PDA
+    pub withdraw_bump: u8,
+    /// Reserve stake account that `DepositSol` pays into and `WithdrawSol` draws from
+    pub reserve_stake: Pubkey,
+    /// Mint of the stake pool's tokens
+    pub pool_mint: Pubkey,
+    /// Token account receiving the manager's fees
+    pub manager_fee_account: Pubkey,
+    /// Token program owning `pool_mint`
+    pub token_program_id: Pubkey,
+    /// Lamports under management as of the pool's last update
+    pub total_lamports: u64,
+    /// Pool tokens in circulation as of the pool's last update
+    pub pool_token_supply: u64,
+}
+
+impl StakePoolInfo {
+    /// Decode a stake pool account
+    /// Returns None unless `data` is an initialized stake pool
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() < STAKE_POOL_MIN_LEN || data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_STAKE_POOL {
+            return None;
+        }
+
+        Some(Self {
+            withdraw_bump: data[WITHDRAW_BUMP_OFFSET],
+            reserve_stake: read_pubkey(data, RESERVE_STAKE_OFFSET)?,
+            pool_mint: read_pubkey(data, POOL_MINT_OFFSET)?,
+            manager_fee_account: read_pubkey(data, MANAGER_FEE_ACCOUNT_OFFSET)?,
+            token_program_id: read_pubkey(data, TOKEN_PROGRAM_OFFSET)?,
+            total_lamports: read_u64(data, TOTAL_LAMPORTS_OFFSET)?,
+            pool_token_supply: read_u64(data, POOL_TOKEN_SUPPLY_OFFSET)?,
+        })
+    }
+
+    /// Address of the withdraw authority of the stake pool at `stake_pool`
+    pub fn withdraw_authority(&self, program_id: &Pubkey, stake_pool: &Pubkey) -> Option<Pubkey> {
+        Pubkey::create_program_address(
+            &[stake_pool.as_ref(), AUTHORITY_WITHDRAW, &[self.withdraw_bump]],
+            program_id,
+        )
+        .ok()
+    }
+
+    /// Lamports `pool_tokens` are worth at the pool's current exchange rate, rounded down
+    pub fn lamports_for_pool_tokens(&self, pool_tokens: u64) -> Option<u64> {
+        if self.pool_token_supply == 0 {
+            return Some(0);
+        }
+        let lamports = (pool_tokens as u128)
+            .checked_mul(self.total_lamports as u128)?
+            .checked_div(self.pool_token_supply as u128)?;
+        u64::try_from(lamports).ok()
+    }
+}
+
+/// Accounts of a `DepositSol` or `WithdrawSol` instruction, in the stake pool's roles
+pub struct StakePoolAccounts {
+    pub program_id: Pubkey,
+    pub stake_pool: Pubkey,
+    pub withdraw_authority: Pubkey,
+    pub reserve_stake: Pubkey,
+    pub manager_fee_account: Pubkey,
+    pub pool_mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// `DepositSol` of `lamports` from the signer `lamports_from`, minting pool tokens to
+/// `pool_token_account`, which also takes the referral fee
+pub fn deposit_sol_instruction(
+    accounts: &StakePoolAccounts,
+    lamports_from: &Pubkey,
+    pool_token_account: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let mut data = vec![DEPOSIT_SOL_TAG];
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction {
+        program_id: accounts.program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.stake_pool, false),
+            AccountMeta::new_readonly(accounts.withdraw_authority, false),
+            AccountMeta::new(accounts.reserve_stake, false),
+            AccountMeta::new(*lamports_from, true),
+            AccountMeta::new(*pool_token_account, false),
+            AccountMeta::new(accounts.manager_fee_account, false),
+            AccountMeta::new(*pool_token_account, false),
+            AccountMeta::new(accounts.pool_mint, false),
+            AccountMeta::new_readonly(anchor_lang::system_program::ID, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data,
+    }
+}
+
+/// `WithdrawSol` burning `pool_tokens` from `pool_token_account`, signed by its owner
+/// `token_authority`, paying the lamports to `lamports_to`
+pub fn withdraw_sol_instruction(
+    accounts: &StakePoolAccounts,
+    token_authority: &Pubkey,
+    pool_token_account: &Pubkey,
+    lamports_to: &Pubkey,
+    pool_tokens: u64,
+) -> Instruction {
+    let mut data = vec![WITHDRAW_SOL_TAG];
+    data.extend_from_slice(&pool_tokens.to_le_bytes());
+    Instruction {
+        program_id: accounts.program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.stake_pool, false),
+            AccountMeta::new_readonly(accounts.withdraw_authority, false),
+            AccountMeta::new_readonly(*token_authority, true),
+            AccountMeta::new(*pool_token_account, false),
+            AccountMeta::new(accounts.reserve_stake, false),
+            AccountMeta::new(*lamports_to, false),
+            AccountMeta::new(accounts.manager_fee_account, false),
+            AccountMeta::new(accounts.pool_mint, false),
+            AccountMeta::new_readonly(anchor_lang::solana_program::sysvar::clock::ID, false),
+            AccountMeta::new_readonly(anchor_lang::solana_program::sysvar::stake_history::ID, false),
+            AccountMeta::new_readonly(STAKE_PROGRAM_ID, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data,
+    }
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Some(Pubkey::new_from_array(data.get(offset..offset + 32)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mock stake pool account with the fields the program reads
+    fn mock_stake_pool_account(info: &StakePoolInfo) -> Vec<u8> {
+        let mut data = vec![0u8; 611];
+        data[ACCOUNT_TYPE_OFFSET] = ACCOUNT_TYPE_STAKE_POOL;
+        data[WITHDRAW_BUMP_OFFSET] = info.withdraw_bump;
+        for (offset, key) in [
+            (RESERVE_STAKE_OFFSET, info.reserve_stake),
+            (POOL_MINT_OFFSET, info.pool_mint),
+            (MANAGER_FEE_ACCOUNT_OFFSET, info.manager_fee_account),
+            (TOKEN_PROGRAM_OFFSET, info.token_program_id),
+        ] {
+            data[offset..offset + 32].copy_from_slice(key.as_ref());
+        }
+        data[TOTAL_LAMPORTS_OFFSET..TOTAL_LAMPORTS_OFFSET + 8]
+            .copy_from_slice(&info.total_lamports.to_le_bytes());
+        data[POOL_TOKEN_SUPPLY_OFFSET..POOL_TOKEN_SUPPLY_OFFSET + 8]
+            .copy_from_slice(&info.pool_token_supply.to_le_bytes());
+        data
+    }
+
+    fn info() -> StakePoolInfo {
+        StakePoolInfo {
+            withdraw_bump: 254,
+            reserve_stake: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            manager_fee_account: Pubkey::new_unique(),
+            token_program_id: anchor_spl::token::ID,
+            total_lamports: 1_050,
+            pool_token_supply: 1_000,
+        }
+    }
+
+    #[test]
+    fn decodes_a_stake_pool_account() {
+        let info = info();
+        let data = mock_stake_pool_account(&info);
+        assert_eq!(StakePoolInfo::from_account_data(&data), Some(info));
+
+        let mut uninitialized = data.clone();
+        uninitialized[ACCOUNT_TYPE_OFFSET] = 0;
+        assert_eq!(StakePoolInfo::from_account_data(&uninitialized), None);
+        assert_eq!(StakePoolInfo::from_account_data(&data[..STAKE_POOL_MIN_LEN - 1]), None);
+    }
+
+    #[test]
+    fn values_pool_tokens_at_the_exchange_rate() {
+        let info = info();
+        assert_eq!(info.lamports_for_pool_tokens(1_000), Some(1_050));
+        assert_eq!(info.lamports_for_pool_tokens(3), Some(3));
+        assert_eq!(info.lamports_for_pool_tokens(u64::MAX), None);
+
+        let empty = StakePoolInfo { total_lamports: 0, pool_token_supply: 0, ..info };
+        assert_eq!(empty.lamports_for_pool_tokens(10), Some(0));
+    }
+
+    #[test]
+    fn encodes_the_stake_pool_instructions() {
+        assert_eq!(STAKE_PROGRAM_ID.to_string(), "Stake11111111111111111111111111111111111111");
+
+        let accounts = StakePoolAccounts {
+            program_id: Pubkey::new_unique(),
+            stake_pool: Pubkey::new_unique(),
+            withdraw_authority: Pubkey::new_unique(),
+            reserve_stake: Pubkey::new_unique(),
+            manager_fee_account: Pubkey::new_unique(),
+            pool_mint: Pubkey::new_unique(),
+            token_program: anchor_spl::token::ID,
+        };
+        let (vault, tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let deposit = deposit_sol_instruction(&accounts, &vault, &tokens, 5);
+        assert_eq!(deposit.data, [14, 5, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(deposit.accounts.len(), 10);
+        assert!(deposit.accounts[3].is_signer);
+
+        let withdraw = withdraw_sol_instruction(&accounts, &vault, &tokens, &vault, 7);
+        assert_eq!(withdraw.data, [16, 7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(withdraw.accounts.len(), 12);
+        assert!(withdraw.accounts[2].is_signer);
+    }
+}
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::error::CarsaError;
+use crate::math::{calculate_claimable_yield, LOKAL_DECIMALS};
 
 /// State account that stores the configuration and metadata for the Lokal token mint
 /// This account is owned by the program and stores essential mint information
@@ -21,15 +23,425 @@ pub struct LokalMintConfig {
     /// Used for tracking and analytics purposes
     pub total_supply: u64,
     
-    /// Reserved space for future upgrades (64 bytes)
-    pub reserved: [u8; 64],
+    /// Minimum voucher pool stake a customer needs for the staker cashback boost
+    pub staker_boost_threshold: u64,
+    
+    /// Extra cashback for stakers, added to the merchant's rate (in basis points)
+    pub staker_boost_bps: u16,
+    
+    /// Sequence number of the last event emitted by mint, purchase or transfer
+    /// instructions; indexers use it to detect gaps
+    pub event_sequence: u64,
+    
+    /// Whether purchases and transfers also write an `ActivityRecord`
+    pub record_activity: bool,
+    
+    /// Number of purchases processed
+    pub total_purchases: u64,
+    
+    /// Number of peer-to-peer transfers made
+    pub total_transfers: u64,
+    
+    /// Share of redeemed tokens routed to the treasury (in basis points)
+    pub protocol_fee_bps: u16,
+    
+    /// Token account that receives protocol fees
+    pub treasury_token_account: Pubkey,
+    
+    /// Share of each peer-to-peer transfer routed to the treasury (in basis points)
+    pub transfer_fee_bps: u16,
+    
+    /// Second signer required on treasury withdrawals (default pubkey = not required)
+    pub treasury_cosigner: Pubkey,
+    
+    /// Largest amount a single treasury withdrawal may move (0 = no cap)
+    pub treasury_withdrawal_cap: u64,
+    
+    /// Number of treasury withdrawals made, numbering their records
+    pub treasury_withdrawal_count: u64,
+    
+    /// Value of one Lokal token in Indonesian Rupiah (IDR)
+    pub token_to_idr_rate: u64,
+    
+    /// Delay before a queued parameter change can be executed (0 = no timelock)
+    pub param_timelock_seconds: i64,
+    
+    /// Number of parameter changes queued, numbering their PDAs
+    pub param_change_count: u64,
+    
+    /// Key that can pause the program, pools and merchants but change nothing else
+    /// (default pubkey = no guardian)
+    pub guardian: Pubkey,
+    
+    /// Whether purchases and transfers are halted
+    pub paused: bool,
+    
+    /// Second signer required on mints above `single_sig_mint_limit`
+    /// (default pubkey = not required)
+    pub mint_cosigner: Pubkey,
+    
+    /// Largest amount `mint_lokal_tokens` can mint with the update authority alone
+    pub single_sig_mint_limit: u64,
+    
+    /// Pyth price account quoting IDR per token, used instead of `token_to_idr_rate`
+    /// (default pubkey = use the static rate)
+    pub price_feed: Pubkey,
+    
+    /// Oldest price from `price_feed` a purchase will accept
+    pub max_price_age_seconds: u32,
+    
+    /// Widest confidence interval from `price_feed` a purchase will accept, relative to
+    /// the price (in basis points)
+    pub max_price_confidence_bps: u16,
+    
+    /// Whether purchase rewards are held in escrow as expiring `RewardLot`s instead of
+    /// being minted to the customer
+    pub expiring_rewards: bool,
+    
+    /// How long a reward lot can be withdrawn before it expires
+    pub reward_lifetime_seconds: i64,
+    
+    /// Number of vesting grants created, numbering their PDAs
+    pub vesting_grant_count: u64,
+    
+    /// How long after a purchase its customer may open a dispute (0 = disputes disabled)
+    pub dispute_window_seconds: u32,
+    
+    /// Decimals of `mint`, passed to every `transfer_checked`
+    /// (0 on configs created before it was recorded; read through `decimals()`)
+    pub decimals: u8,
+    
+    /// Total Lokal tokens the program has burned (settlements, expired reward lots)
+    /// `total_supply - total_burned` should equal the mint's supply; see `reconcile_supply`
+    pub total_burned: u64,
+    
+    /// Backend key that records off-chain fulfillment of purchases with `mark_fulfilled`
+    /// (default pubkey = fulfillment not recorded)
+    pub fulfillment_authority: Pubkey,
+    
+    /// How long after a purchase its fulfillment can be recorded
+    pub fulfillment_window_seconds: u32,
+    
+    /// Largest peer-to-peer transfer (0 = no limit)
+    pub max_transfer_amount: u64,
+    
+    /// Largest amount `mint_lokal_tokens` mints in one call (0 = no limit)
+    pub max_mint_per_tx: u64,
+    
+    /// Largest purchase in Indonesian Rupiah (IDR) (0 = no limit)
+    pub max_purchase_idr: u64,
+    
+    /// Shortest time between two changes of a merchant's cashback rate (0 = no cooldown)
+    pub rate_change_cooldown_seconds: u32,
+    
+    /// Largest increase of a merchant's cashback rate in one change, in basis points
+    /// (0 = no cap); decreases are never capped
+    pub max_rate_increase_bps: u16,
+    
+    /// Whether purchases record a slim `PurchaseTransactionV2` instead of a
+    /// `PurchaseTransaction`
+    pub slim_purchase_records: bool,
+    
+    /// Tokens minted by administrative paths: `mint_lokal_tokens`, vesting grants, dispute
+    /// refunds, airdrop claims and clawbacks (counted from when it was added)
+    pub total_admin_minted: u64,
+    
+    /// Largest `total_admin_minted` may grow to (0 = no cap)
+    pub admin_mint_cap: u64,
+    
+    /// Tokens minted as purchase rewards (counted from when it was added)
+    pub total_reward_minted: u64,
+    
+    /// Largest `total_reward_minted` may grow to (0 = no cap)
+    pub reward_mint_cap: u64,
+    
+    /// Number of queued parameter changes not yet executed or cancelled (counted from when
+    /// it was added)
+    pub pending_param_changes: u16,
+    
+    /// Whether purchase records, their PDA seeds and purchase events carry
+    /// `customer_hash` of the customer instead of their wallet
+    pub hash_customer_in_records: bool,
+    
+    /// Salt of `customer_hash`, set once so each customer's hash stays stable
+    pub customer_hash_salt: [u8; 32],
+    
+    /// Highest cashback rate a merchant may set or earn rewards at (in basis points)
+    /// Configs created before it hold 0, read as `DEFAULT_MAX_CASHBACK_BPS`
+    pub max_cashback_bps: u16,
+    
+    /// Cashback rate above which setting a merchant's rate emits `HighCashbackAlertEvent`
+    /// (in basis points, 0 = no alerts)
+    pub alert_threshold_bps: u16,
+    
+    /// Reserved space for future upgrades (1 byte)
+    pub reserved: [u8; 1],
 }
 
 impl LokalMintConfig {
     /// Calculate the space needed for this account
     /// 8 (discriminator) + 32 (mint) + 1 (mint_authority_bump) + 1 (config_bump) 
+    /// + 32 (update_authority) + 8 (total_supply) + 8 (staker_boost_threshold)
+    /// + 2 (staker_boost_bps) + 8 (event_sequence) + 1 (record_activity) + 8 (total_purchases)
+    /// + 8 (total_transfers) + 2 (protocol_fee_bps) + 32 (treasury_token_account)
+    /// + 2 (transfer_fee_bps) + 32 (treasury_cosigner) + 8 (treasury_withdrawal_cap)
+    /// + 8 (treasury_withdrawal_count) + 8 (token_to_idr_rate) + 8 (param_timelock_seconds)
+    /// + 8 (param_change_count) + 32 (guardian) + 1 (paused) + 32 (mint_cosigner)
+    /// + 8 (single_sig_mint_limit) + 32 (price_feed) + 4 (max_price_age_seconds)
+    /// + 2 (max_price_confidence_bps) + 1 (expiring_rewards) + 8 (reward_lifetime_seconds)
+    /// + 8 (vesting_grant_count) + 4 (dispute_window_seconds) + 1 (decimals) + 8 (total_burned)
+    /// + 32 (fulfillment_authority) + 4 (fulfillment_window_seconds) + 8 (max_transfer_amount)
+    /// + 8 (max_mint_per_tx) + 8 (max_purchase_idr) + 4 (rate_change_cooldown_seconds)
+    /// + 2 (max_rate_increase_bps) + 1 (slim_purchase_records) + 8 (total_admin_minted)
+    /// + 8 (admin_mint_cap) + 8 (total_reward_minted) + 8 (reward_mint_cap)
+    /// + 2 (pending_param_changes) + 1 (hash_customer_in_records) + 32 (customer_hash_salt)
+    /// + 2 (max_cashback_bps) + 2 (alert_threshold_bps) + 1 (reserved) = 505 bytes
+    pub const LEN: usize = 8 + 32 + 1 + 1 + 32 + 8 + 8 + 2 + 8 + 1 + 8 + 8 + 2 + 32 + 2 + 32
+        + 8 + 8 + 8 + 8 + 8 + 32 + 1 + 32 + 8 + 32 + 4 + 2 + 1 + 8 + 8 + 4 + 1 + 8 + 32 + 4 + 8 + 8 + 8
+        + 4 + 2 + 1 + 8 + 8 + 8 + 8 + 2 + 1 + 32 + 2 + 2 + 1;
+
+    /// Default value of one Lokal token (Rp 1,000)
+    pub const DEFAULT_TOKEN_TO_IDR_RATE: u64 = 1_000;
+
+    /// Default dispute window (14 days)
+    pub const DEFAULT_DISPUTE_WINDOW_SECONDS: u32 = 14 * 24 * 60 * 60;
+
+    /// Default largest transfer (10,000 tokens with 9 decimals)
+    pub const DEFAULT_MAX_TRANSFER_AMOUNT: u64 = 10_000_000_000_000;
+
+    /// Default largest single mint (10,000 tokens with 9 decimals)
+    pub const DEFAULT_MAX_MINT_PER_TX: u64 = 10_000_000_000_000;
+
+    /// Default largest purchase (Rp 1,000,000,000)
+    pub const DEFAULT_MAX_PURCHASE_IDR: u64 = 1_000_000_000;
+
+    /// Default highest cashback rate (100%)
+    pub const DEFAULT_MAX_CASHBACK_BPS: u16 = 10_000;
+
+    /// Decimals of the Lokal mint
+    /// Configs created before `decimals` was recorded hold a mint with the default decimals
+    pub fn decimals(&self) -> u8 {
+        if self.decimals == 0 {
+            LOKAL_DECIMALS
+        } else {
+            self.decimals
+        }
+    }
+
+    /// Whether a transfer of `amount` is within `max_transfer_amount`
+    pub fn allows_transfer(&self, amount: u64) -> bool {
+        within_limit(amount, self.max_transfer_amount)
+    }
+
+    /// Whether a mint of `amount` is within `max_mint_per_tx`
+    pub fn allows_mint(&self, amount: u64) -> bool {
+        within_limit(amount, self.max_mint_per_tx)
+    }
+
+    /// `total_admin_minted` after minting `amount`, or None past `admin_mint_cap`
+    pub fn admin_minted_after(&self, amount: u64) -> Option<u64> {
+        let total = self.total_admin_minted.checked_add(amount)?;
+        within_limit(total, self.admin_mint_cap).then_some(total)
+    }
+
+    /// `total_reward_minted` after minting `amount`, or None past `reward_mint_cap`
+    pub fn reward_minted_after(&self, amount: u64) -> Option<u64> {
+        let total = self.total_reward_minted.checked_add(amount)?;
+        within_limit(total, self.reward_mint_cap).then_some(total)
+    }
+
+    /// Whether a purchase of `fiat_amount` is within `max_purchase_idr`
+    pub fn allows_purchase(&self, fiat_amount: u64) -> bool {
+        within_limit(fiat_amount, self.max_purchase_idr)
+    }
+
+    /// Whether a merchant whose cashback rate last changed at `last_change_at` may change
+    /// it again at `now`; 0 means the rate has never changed
+    pub fn rate_change_cooled_down(&self, last_change_at: i64, now: i64) -> bool {
+        last_change_at == 0
+            || now.saturating_sub(last_change_at) >= self.rate_change_cooldown_seconds as i64
+    }
+
+    /// Whether a cashback rate may move from `old_rate` to `new_rate` in one change
+    pub fn allows_rate_change(&self, old_rate: u16, new_rate: u16) -> bool {
+        within_limit(
+            new_rate.saturating_sub(old_rate) as u64,
+            self.max_rate_increase_bps as u64,
+        )
+    }
+
+    /// Highest cashback rate merchants may use
+    pub fn max_cashback_bps(&self) -> u16 {
+        if self.max_cashback_bps == 0 {
+            Self::DEFAULT_MAX_CASHBACK_BPS
+        } else {
+            self.max_cashback_bps
+        }
+    }
+
+    /// Whether a merchant may use `cashback_rate` under `max_cashback_bps`
+    pub fn allows_cashback_rate(&self, cashback_rate: u16) -> bool {
+        cashback_rate <= self.max_cashback_bps()
+    }
+
+    /// Whether setting a merchant's rate to `cashback_rate` should raise an alert
+    pub fn is_high_cashback_rate(&self, cashback_rate: u16) -> bool {
+        self.alert_threshold_bps > 0 && cashback_rate > self.alert_threshold_bps
+    }
+
+    /// Advance the event sequence and return the number for the next event
+    pub fn next_event_sequence(&mut self) -> Option<u64> {
+        self.event_sequence = self.event_sequence.checked_add(1)?;
+        Some(self.event_sequence)
+    }
+
+    /// sha256(customer || customer_hash_salt), as a pubkey so it fits where the wallet goes
+    pub fn customer_hash(&self, customer: &Pubkey) -> Pubkey {
+        let hash = anchor_lang::solana_program::hash::hashv(&[
+            customer.as_ref(),
+            &self.customer_hash_salt,
+        ]);
+        Pubkey::new_from_array(hash.to_bytes())
+    }
+
+    /// What purchase records and events hold for `customer`: its hash in privacy mode,
+    /// otherwise the wallet itself
+    pub fn record_customer(&self, customer: &Pubkey) -> Pubkey {
+        if self.hash_customer_in_records {
+            self.customer_hash(customer)
+        } else {
+            *customer
+        }
+    }
+
+    /// Whether `recorded`, taken from a purchase record, stands for `customer`
+    /// Either form matches, so records written before privacy mode was switched on or off
+    /// still belong to their customer
+    pub fn is_record_customer(&self, recorded: &Pubkey, customer: &Pubkey) -> bool {
+        recorded == customer
+            || (self.customer_hash_salt != [0; 32] && *recorded == self.customer_hash(customer))
+    }
+
+    /// Whether `key` is the configured guardian
+    pub fn is_guardian(&self, key: &Pubkey) -> bool {
+        self.guardian != Pubkey::default() && *key == self.guardian
+    }
+
+    /// Cashback rate after the staker boost for a customer with `staked_amount` in the pool
+    /// Capped at 100%
+    pub fn boosted_cashback_rate(&self, cashback_rate: u16, staked_amount: u64) -> u16 {
+        if staked_amount < self.staker_boost_threshold {
+            return cashback_rate;
+        }
+        cashback_rate.saturating_add(self.staker_boost_bps).min(10_000)
+    }
+
+    /// Treasury's share of `redeemed_tokens`, rounded down in the merchant's favor
+    pub fn protocol_fee(&self, redeemed_tokens: u64) -> Option<u64> {
+        bps_share(redeemed_tokens, self.protocol_fee_bps)
+    }
+
+    /// Treasury's share of a transfer of `amount`, rounded down in the recipient's favor
+    pub fn transfer_fee(&self, amount: u64) -> Option<u64> {
+        bps_share(amount, self.transfer_fee_bps)
+    }
+}
+
+/// `LokalMintConfig` as stored by the first deployment
+/// `LokalMintConfig` cannot decode it; `migrate_config` rewrites it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LegacyLokalMintConfig {
+    pub mint: Pubkey,
+    pub mint_authority_bump: u8,
+    pub config_bump: u8,
+    pub update_authority: Pubkey,
+    pub total_supply: u64,
+    pub reserved: [u8; 64],
+}
+
+impl LegacyLokalMintConfig {
+    /// 8 (discriminator) + 32 (mint) + 1 (mint_authority_bump) + 1 (config_bump)
     /// + 32 (update_authority) + 8 (total_supply) + 64 (reserved) = 146 bytes
     pub const LEN: usize = 8 + 32 + 1 + 1 + 32 + 8 + 64;
+
+    /// Decode a config still in the legacy layout, `None` if it is not one
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN || data[..8] != *LokalMintConfig::DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The config in the current layout, with the defaults `initialize_lokal_mint` sets
+    /// `decimals` is read from the mint, which the legacy layout did not record
+    pub fn migrate(&self, decimals: u8) -> LokalMintConfig {
+        LokalMintConfig {
+            mint: self.mint,
+            mint_authority_bump: self.mint_authority_bump,
+            config_bump: self.config_bump,
+            update_authority: self.update_authority,
+            total_supply: self.total_supply,
+            staker_boost_threshold: 0,
+            staker_boost_bps: 0,
+            event_sequence: 0,
+            record_activity: false,
+            total_purchases: 0,
+            total_transfers: 0,
+            protocol_fee_bps: 0,
+            treasury_token_account: Pubkey::default(),
+            transfer_fee_bps: 0,
+            treasury_cosigner: Pubkey::default(),
+            treasury_withdrawal_cap: 0,
+            treasury_withdrawal_count: 0,
+            token_to_idr_rate: LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE,
+            param_timelock_seconds: 0,
+            param_change_count: 0,
+            guardian: Pubkey::default(),
+            paused: false,
+            mint_cosigner: Pubkey::default(),
+            single_sig_mint_limit: 0,
+            price_feed: Pubkey::default(),
+            max_price_age_seconds: 0,
+            max_price_confidence_bps: 0,
+            expiring_rewards: false,
+            reward_lifetime_seconds: 0,
+            vesting_grant_count: 0,
+            dispute_window_seconds: LokalMintConfig::DEFAULT_DISPUTE_WINDOW_SECONDS,
+            decimals,
+            total_burned: 0,
+            fulfillment_authority: Pubkey::default(),
+            fulfillment_window_seconds: 0,
+            max_transfer_amount: LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT,
+            max_mint_per_tx: LokalMintConfig::DEFAULT_MAX_MINT_PER_TX,
+            max_purchase_idr: LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR,
+            rate_change_cooldown_seconds: 0,
+            max_rate_increase_bps: 0,
+            slim_purchase_records: false,
+            total_admin_minted: 0,
+            admin_mint_cap: 0,
+            total_reward_minted: 0,
+            reward_mint_cap: 0,
+            pending_param_changes: 0,
+            hash_customer_in_records: false,
+            customer_hash_salt: [0; 32],
+            max_cashback_bps: LokalMintConfig::DEFAULT_MAX_CASHBACK_BPS,
+            alert_threshold_bps: 0,
+            reserved: [0; 1],
+        }
+    }
+}
+
+/// `amount * bps / 10_000`, rounded down
+fn bps_share(amount: u64, bps: u16) -> Option<u64> {
+    let share = (amount as u128)
+        .checked_mul(bps as u128)?
+        .checked_div(BASIS_POINTS)?;
+    u64::try_from(share).ok()
+}
+
+/// Whether `amount` is at most `limit`, where a limit of 0 means no limit
+fn within_limit(amount: u64, limit: u64) -> bool {
+    limit == 0 || amount <= limit
 }
 
 /// Merchant account that stores merchant-specific information and settings
@@ -37,6 +449,8 @@ impl LokalMintConfig {
 #[account]
 pub struct MerchantAccount {
     /// The merchant's wallet public key for receiving payments
+    /// Instructions authorize the owner by this field, not by the account's address, whose
+    /// seeds keep the wallet it was registered with
     pub merchant_wallet: Pubkey,
     
     /// The merchant's display name
@@ -66,23 +480,251 @@ pub struct MerchantAccount {
     /// The bump seed for this merchant account's PDA
     pub bump: u8,
     
-    /// Reserved space for future upgrades (32 bytes)
-    pub reserved: [u8; 32],
+    /// Position of this merchant's `MerchantIndex` in the registry
+    pub registry_index: u64,
+    
+    /// Whether the update authority or guardian has suspended this merchant
+    /// Unlike `is_active`, the merchant cannot lift it
+    pub suspended: bool,
+    
+    /// Smallest purchase (fiat + token value, in IDR) the merchant accepts (0 = no minimum)
+    pub min_purchase_amount: u64,
+    
+    /// Premium on the IDR value of tokens redeemed here (in basis points, 1000 = +10%)
+    pub redemption_bonus_bps: u16,
+    
+    /// Redeemed tokens the merchant has received but not yet settled for IDR
+    pub unsettled_tokens: u64,
+    
+    /// Whether purchases must carry an Ed25519 approval from the merchant wallet or
+    /// one of its registered POS keys
+    pub require_merchant_signature: bool,
+    
+    /// The franchise parent this outlet belongs to (default pubkey when independent)
+    pub parent_merchant: Pubkey,
+    
+    /// Whether purchases are recorded by extending `receipt_chain_hash` instead of
+    /// creating a `PurchaseTransaction` account
+    pub compressed_receipts: bool,
+    
+    /// Hash chain over the merchant's compressed receipts (all zeros before the first)
+    pub receipt_chain_hash: [u8; 32],
+    
+    /// The only token account redeemed tokens may be paid to
+    /// Default pubkey until set; the first redemption then locks in the account it pays
+    pub payout_token_account: Pubkey,
+    
+    /// Timestamp of the last change to `cashback_rate` (0 if never changed)
+    pub last_rate_change_at: i64,
+    
+    /// Visits a customer must make within `streak_window_seconds` to earn `streak_bonus`
+    /// (0 = no streak reward)
+    pub streak_target: u16,
+    
+    /// How long a customer has from their first visit to complete a streak
+    pub streak_window_seconds: u32,
+    
+    /// Reward tokens minted to a customer completing a streak
+    pub streak_bonus: u64,
+    
+    /// Whether every purchase counts as a visit; otherwise a customer's purchases on one
+    /// UTC day count once
+    pub streak_counts_every_purchase: bool,
+    
+    /// Last nonce of the merchant's POS integration, advanced by purchases that pass
+    /// `expected_nonce` (0 = none used yet)
+    pub pos_nonce: u64,
+    
+    /// Whether the update authority registered this merchant and its owner has not yet
+    /// accepted with `accept_merchant_registration`; no purchases until then
+    pub pending_activation: bool,
+    
+    /// The wallet proposed to take over the merchant with `accept_merchant_ownership`
+    /// (default pubkey when no transfer is pending)
+    pub pending_owner: Pubkey,
+    
+    /// Reserved space for future upgrades (3 bytes)
+    pub reserved: [u8; 3],
 }
 
 impl MerchantAccount {
+    /// A category as stored on the account: its UTF-8 bytes, zero-padded to 16
+    pub fn category_bytes(category: &str) -> [u8; 16] {
+        let mut category_bytes = [0u8; 16];
+        let category_len = category.len().min(16);
+        category_bytes[..category_len].copy_from_slice(&category.as_bytes()[..category_len]);
+        category_bytes
+    }
+
+    /// A name as stored on the account: its UTF-8 bytes, zero-padded to 32
+    pub fn name_bytes(name: &str) -> [u8; 32] {
+        let mut name_bytes = [0u8; 32];
+        let name_len = name.len().min(32);
+        name_bytes[..name_len].copy_from_slice(&name.as_bytes()[..name_len]);
+        name_bytes
+    }
+
     /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (merchant_wallet) + 32 (name) + 16 (category) + 2 (cashback_rate)
+    /// + 1 (is_active) + 8 (total_transactions) + 8 (total_rewards_distributed) + 8 (total_volume)
+    /// + 8 (created_at) + 1 (bump) + 8 (registry_index) + 1 (suspended)
+    /// + 8 (min_purchase_amount) + 2 (redemption_bonus_bps) + 8 (unsettled_tokens)
+    /// + 1 (require_merchant_signature) + 32 (parent_merchant) + 1 (compressed_receipts)
+    /// + 32 (receipt_chain_hash) + 32 (payout_token_account) + 8 (last_rate_change_at)
+    /// + 2 (streak_target) + 4 (streak_window_seconds) + 8 (streak_bonus)
+    /// + 1 (streak_counts_every_purchase) + 8 (pos_nonce) + 1 (pending_activation)
+    /// + 32 (pending_owner) + 3 (reserved) = 316 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 16 + 2 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 2 + 8 + 1 + 32
+        + 1 + 32 + 32 + 8 + 2 + 4 + 8 + 1 + 8 + 1 + 32 + 3;
+
+    /// Extend the receipt chain with a purchase:
+    /// hash(prev_hash || customer || fiat_amount || redeemed_token_amount || reward_amount
+    /// || transaction_id || timestamp), with amounts and timestamp as 8-byte LE
+    /// Indexers replay the receipt events in order to check they end at the stored hash
+    pub fn chain_receipt(
+        &mut self,
+        customer: &Pubkey,
+        fiat_amount: u64,
+        redeemed_token_amount: u64,
+        reward_amount: u64,
+        transaction_id: &[u8; 32],
+        timestamp: i64,
+    ) -> [u8; 32] {
+        self.receipt_chain_hash = anchor_lang::solana_program::hash::hashv(&[
+            &self.receipt_chain_hash,
+            customer.as_ref(),
+            &fiat_amount.to_le_bytes(),
+            &redeemed_token_amount.to_le_bytes(),
+            &reward_amount.to_le_bytes(),
+            transaction_id,
+            &timestamp.to_le_bytes(),
+        ])
+        .to_bytes();
+        self.receipt_chain_hash
+    }
+}
+
+/// `MerchantAccount` as stored by merchants registered with the first deployment
+/// `MerchantAccount` cannot decode these accounts; `migrate_merchant` rewrites them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LegacyMerchantAccount {
+    pub merchant_wallet: Pubkey,
+    pub name: [u8; 32],
+    pub category: [u8; 16],
+    pub cashback_rate: u16,
+    pub is_active: bool,
+    pub total_transactions: u64,
+    pub total_rewards_distributed: u64,
+    pub total_volume: u64,
+    pub created_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
+
+impl LegacyMerchantAccount {
     /// 8 (discriminator) + 32 (merchant_wallet) + 32 (name) + 16 (category) + 2 (cashback_rate)
     /// + 1 (is_active) + 8 (total_transactions) + 8 (total_rewards_distributed) + 8 (total_volume)
     /// + 8 (created_at) + 1 (bump) + 32 (reserved) = 156 bytes
     pub const LEN: usize = 8 + 32 + 32 + 16 + 2 + 1 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    /// Decode a merchant still in the legacy layout, `None` if it is not one
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN || data[..8] != *MerchantAccount::DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The merchant in the current layout, independent and with every setting added since
+    /// off, as `register_merchant` leaves them
+    /// `registry_index` is 0 until the merchant takes a registry position
+    pub fn migrate(&self) -> MerchantAccount {
+        MerchantAccount {
+            merchant_wallet: self.merchant_wallet,
+            name: self.name,
+            category: self.category,
+            cashback_rate: self.cashback_rate,
+            is_active: self.is_active,
+            total_transactions: self.total_transactions,
+            total_rewards_distributed: self.total_rewards_distributed,
+            total_volume: self.total_volume,
+            created_at: self.created_at,
+            bump: self.bump,
+            registry_index: 0,
+            suspended: false,
+            min_purchase_amount: 0,
+            redemption_bonus_bps: 0,
+            unsettled_tokens: 0,
+            require_merchant_signature: false,
+            parent_merchant: Pubkey::default(),
+            compressed_receipts: false,
+            receipt_chain_hash: [0; 32],
+            payout_token_account: Pubkey::default(),
+            last_rate_change_at: 0,
+            streak_target: 0,
+            streak_window_seconds: 0,
+            streak_bonus: 0,
+            streak_counts_every_purchase: false,
+            pos_nonce: 0,
+            pending_activation: false,
+            pending_owner: Pubkey::default(),
+            reserved: [0; 3],
+        }
+    }
+}
+
+/// Global counter of registered merchants
+/// Clients enumerate merchants by walking `MerchantIndex` PDAs 0..merchant_count
+/// Zero-copy and packed like `PoolState`, keeping the layout of the existing registry
+#[account(zero_copy)]
+#[repr(C, packed)]
+pub struct MerchantRegistry {
+    /// Number of merchants ever registered, including closed ones
+    pub merchant_count: u64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+    
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl MerchantRegistry {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (merchant_count) + 1 (bump) + 32 (reserved) = 49 bytes
+    pub const LEN: usize = 8 + 8 + 1 + 32;
+}
+
+const _: () = assert!(std::mem::size_of::<MerchantRegistry>() == MerchantRegistry::LEN - 8);
+
+/// Pointer from a registry position to a merchant account
+/// Kept as a tombstone when the merchant closes, so indexes are never renumbered
+#[account]
+pub struct MerchantIndex {
+    /// The merchant account at this position
+    pub merchant: Pubkey,
+    
+    /// Position in the registry (starts at 0)
+    pub index: u64,
+    
+    /// Whether the merchant account has been closed
+    pub closed: bool,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl MerchantIndex {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (merchant) + 8 (index) + 1 (closed) + 1 (bump) = 50 bytes
+    pub const LEN: usize = 8 + 32 + 8 + 1 + 1;
 }
 
 /// Purchase transaction record for tracking and analytics
 /// This account stores details of each purchase transaction including token redemptions
 #[account]
 pub struct PurchaseTransaction {
-    /// The customer's wallet public key
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
     pub customer: Pubkey,
     
     /// The merchant's account public key
@@ -97,10 +739,11 @@ pub struct PurchaseTransaction {
     /// Total transaction value (fiat + token value in IDR)
     pub total_value: u64,
     
-    /// Reward tokens minted for this purchase
+    /// Reward tokens minted for this purchase, including any category boost
     pub reward_amount: u64,
     
-    /// Cashback rate applied (in basis points)
+    /// Cashback rate applied, including any staker boost (in basis points)
+    /// Does not include the protocol's category boost, recorded separately below
     pub cashback_rate: u16,
     
     /// Whether tokens were used in this transaction
@@ -115,236 +758,4355 @@ pub struct PurchaseTransaction {
     /// The bump seed for this transaction account's PDA
     pub bump: u8,
     
-    /// Reserved space for future upgrades (16 bytes)
-    pub reserved: [u8; 16],
+    /// Staker boost included in `cashback_rate` (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+    
+    /// Part of `redeemed_token_amount` routed to the treasury instead of the merchant
+    pub protocol_fee: u64,
+    
+    /// Merchant's redemption bonus applied to the redeemed tokens' value (in basis points)
+    pub redemption_bonus_bps: u16,
+    
+    /// The invoice this purchase paid (default pubkey if not paid by invoice)
+    pub invoice: Pubkey,
+    
+    /// Whether the customer has disputed this purchase; refunds then only go through
+    /// the dispute's resolution
+    pub disputed: bool,
+    
+    /// Whether the redeemed tokens have been refunded to the customer
+    pub refunded: bool,
+    
+    /// When the backend recorded the purchase's off-chain fulfillment (0 = not yet)
+    pub fulfilled_at: i64,
+    
+    /// Protocol category boost on top of `cashback_rate` (in basis points, 0 if none applied)
+    pub category_boost_bps: u16,
+    
+    /// Part of `reward_amount` minted by the category boost rather than the merchant's rate
+    pub category_boost_reward: u64,
+    
+    /// Merchant's streak bonus minted because this purchase completed a streak (0 if none),
+    /// on top of `reward_amount`
+    pub streak_bonus: u64,
+    
+    /// Short code support staff look the purchase up by (see `ReferenceIndex`)
+    pub reference_code: [u8; 8],
+    
+    /// Reserved space for future upgrades (4 bytes)
+    pub reserved: [u8; 4],
 }
 
 impl PurchaseTransaction {
     /// Calculate the space needed for this account
     /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 8 (fiat_amount) + 8 (redeemed_token_amount)
     /// + 8 (total_value) + 8 (reward_amount) + 2 (cashback_rate) + 1 (used_tokens) + 8 (timestamp) 
-    /// + 32 (transaction_id) + 1 (bump) + 16 (reserved) = 164 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 8 + 32 + 1 + 16;
+    /// + 32 (transaction_id) + 1 (bump) + 2 (staker_boost_bps) + 8 (protocol_fee)
+    /// + 2 (redemption_bonus_bps) + 32 (invoice) + 1 (disputed) + 1 (refunded)
+    /// + 8 (fulfilled_at) + 2 (category_boost_bps) + 8 (category_boost_reward)
+    /// + 8 (streak_bonus) + 8 (reference_code) + 4 (reserved) = 232 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 8 + 32 + 1 + 2 + 8 + 2 + 32 + 1 + 1
+        + 8 + 2 + 8 + 8 + 8 + 4;
+
+    /// Whether `address` is this record's PDA, either merchant-scoped or under the
+    /// customer-only seeds of records created before purchases were keyed by merchant
+    pub fn is_at(&self, address: &Pubkey) -> bool {
+        let bump = [self.bump];
+        let merchant_scoped: &[&[u8]] = &[
+            TRANSACTION_SEED,
+            self.merchant.as_ref(),
+            self.customer.as_ref(),
+            &self.transaction_id,
+            &bump,
+        ];
+        let legacy: &[&[u8]] = &[
+            TRANSACTION_SEED,
+            self.customer.as_ref(),
+            &self.transaction_id,
+            &bump,
+        ];
+        [merchant_scoped, legacy].iter().any(|seeds| {
+            Pubkey::create_program_address(seeds, &crate::ID).as_ref() == Ok(address)
+        })
+    }
 }
 
-/// Token transfer record for tracking P2P transfers
-/// This account stores details of token transfers between users
+/// `PurchaseTransaction` as stored by purchases recorded before it outgrew 164 bytes
+/// The staker boost, protocol fee and redemption bonus were carved from the reserved bytes,
+/// so first-deployment records decode with them zeroed. `PurchaseTransaction` cannot
+/// decode these accounts; `migrate_purchase_transaction` rewrites them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LegacyPurchaseTransaction {
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub fiat_amount: u64,
+    pub redeemed_token_amount: u64,
+    pub total_value: u64,
+    pub reward_amount: u64,
+    pub cashback_rate: u16,
+    pub used_tokens: bool,
+    pub timestamp: i64,
+    pub transaction_id: [u8; 32],
+    pub bump: u8,
+    pub staker_boost_bps: u16,
+    pub protocol_fee: u64,
+    pub redemption_bonus_bps: u16,
+    pub reserved: [u8; 4],
+}
+
+impl LegacyPurchaseTransaction {
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 8 (fiat_amount) + 8 (redeemed_token_amount)
+    /// + 8 (total_value) + 8 (reward_amount) + 2 (cashback_rate) + 1 (used_tokens) + 8 (timestamp)
+    /// + 32 (transaction_id) + 1 (bump) + 2 (staker_boost_bps) + 8 (protocol_fee)
+    /// + 2 (redemption_bonus_bps) + 4 (reserved) = 164 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 8 + 32 + 1 + 2 + 8 + 2 + 4;
+
+    /// Decode a purchase record still in the legacy layout, `None` if it is not one
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN || data[..8] != *PurchaseTransaction::DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The record in the current layout: not paid by invoice, undisputed, unfulfilled,
+    /// and without the category boost, streak bonus or reference code added since
+    pub fn migrate(&self) -> PurchaseTransaction {
+        PurchaseTransaction {
+            customer: self.customer,
+            merchant: self.merchant,
+            fiat_amount: self.fiat_amount,
+            redeemed_token_amount: self.redeemed_token_amount,
+            total_value: self.total_value,
+            reward_amount: self.reward_amount,
+            cashback_rate: self.cashback_rate,
+            used_tokens: self.used_tokens,
+            timestamp: self.timestamp,
+            transaction_id: self.transaction_id,
+            bump: self.bump,
+            staker_boost_bps: self.staker_boost_bps,
+            protocol_fee: self.protocol_fee,
+            redemption_bonus_bps: self.redemption_bonus_bps,
+            invoice: Pubkey::default(),
+            disputed: false,
+            refunded: false,
+            fulfilled_at: 0,
+            category_boost_bps: 0,
+            category_boost_reward: 0,
+            streak_bonus: 0,
+            reference_code: [0; 8],
+            reserved: [0; 4],
+        }
+    }
+}
+
+/// Slim purchase record, written instead of `PurchaseTransaction` when the config sets
+/// `slim_purchase_records`
+/// Leaves out the fields `PurchaseProcessedEvent` already carries and that follow from the
+/// rest (`total_value`, `cashback_rate`, `used_tokens`), and keeps half the transaction id
 #[account]
-pub struct TokenTransfer {
-    /// The sender's public key
-    pub from: Pubkey,
+pub struct PurchaseTransactionV2 {
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
     
-    /// The recipient's public key
-    pub to: Pubkey,
+    /// The merchant's account public key
+    pub merchant: Pubkey,
     
-    /// Amount of tokens transferred
-    pub amount: u64,
+    /// Fiat purchase amount in Indonesian Rupiah (IDR)
+    pub fiat_amount: u64,
     
-    /// Timestamp of the transfer
+    /// Amount of tokens redeemed as payment (0 if none)
+    pub redeemed_token_amount: u64,
+    
+    /// Reward tokens minted for this purchase, including any category boost
+    pub reward_amount: u64,
+    
+    /// Part of `redeemed_token_amount` routed to the treasury instead of the merchant
+    pub protocol_fee: u64,
+    
+    /// Timestamp of the transaction
     pub timestamp: i64,
     
-    /// Transfer transaction signature for reference
-    pub transaction_id: [u8; 32],
+    /// When the backend recorded the purchase's off-chain fulfillment (0 = not yet)
+    pub fulfilled_at: i64,
     
-    /// Optional memo/note for the transfer
-    pub memo: [u8; 64],
+    /// First 16 bytes of the transaction id, which also seed this record's PDA
+    pub transaction_id: [u8; 16],
     
-    /// The bump seed for this transfer account's PDA
+    /// The invoice this purchase paid (default pubkey if not paid by invoice)
+    pub invoice: Pubkey,
+    
+    /// Staker boost included in the applied cashback rate (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+    
+    /// Merchant's redemption bonus applied to the redeemed tokens' value (in basis points)
+    pub redemption_bonus_bps: u16,
+    
+    /// The bump seed for this transaction account's PDA
     pub bump: u8,
     
-    /// Reserved space for future upgrades (16 bytes)
-    pub reserved: [u8; 16],
+    /// Whether the customer has disputed this purchase
+    pub disputed: bool,
+    
+    /// Whether the redeemed tokens have been refunded to the customer
+    pub refunded: bool,
+    
+    /// Part of `reward_amount` minted by the protocol's category boost (0 if none applied)
+    pub category_boost_reward: u64,
+    
+    /// Merchant's streak bonus minted on top of `reward_amount` (0 if no streak completed)
+    pub streak_bonus: u64,
+    
+    /// Short code support staff look the purchase up by (see `ReferenceIndex`)
+    pub reference_code: [u8; 8],
 }
 
-impl TokenTransfer {
+impl PurchaseTransactionV2 {
     /// Calculate the space needed for this account
-    /// 8 (discriminator) + 32 (from) + 32 (to) + 8 (amount) + 8 (timestamp)
-    /// + 32 (transaction_id) + 64 (memo) + 1 (bump) + 16 (reserved) = 201 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 32 + 64 + 1 + 16;
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 8 (fiat_amount)
+    /// + 8 (redeemed_token_amount) + 8 (reward_amount) + 8 (protocol_fee) + 8 (timestamp)
+    /// + 8 (fulfilled_at) + 16 (transaction_id) + 32 (invoice) + 2 (staker_boost_bps)
+    /// + 2 (redemption_bonus_bps) + 1 (bump) + 1 (disputed) + 1 (refunded)
+    /// + 8 (category_boost_reward) + 8 (streak_bonus) + 8 (reference_code) = 199 bytes
+    pub const LEN: usize =
+        8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 32 + 2 + 2 + 1 + 1 + 1 + 8 + 8 + 8;
+
+    /// The part of `transaction_id` a slim record keeps and is addressed by
+    pub fn short_id(transaction_id: &[u8; 32]) -> [u8; 16] {
+        let mut short_id = [0; 16];
+        short_id.copy_from_slice(&transaction_id[..16]);
+        short_id
+    }
 }
 
-/// Token redemption record for tracking spending at merchants
-/// This account stores details when customers spend Lokal tokens at merchants
+/// A fixed-amount bill a merchant creates for a customer to pay by reference
 #[account]
-pub struct TokenRedemption {
-    /// The customer who redeemed tokens
-    pub customer: Pubkey,
-    
-    /// The merchant where tokens were redeemed
+pub struct Invoice {
+    /// The merchant account the invoice is payable to
     pub merchant: Pubkey,
     
-    /// Amount of tokens redeemed
-    pub token_amount: u64,
+    /// Merchant-chosen reference, unique per merchant
+    pub reference_id: [u8; 32],
     
-    /// Fiat value of the redemption (in Indonesian Rupiah IDR)
-    pub fiat_value: u64,
+    /// Fiat amount charged in Indonesian Rupiah (IDR)
+    pub fiat_amount: u64,
     
-    /// Discount percentage applied (in basis points)
-    pub discount_rate: u16,
+    /// Most tokens the customer may redeem towards the invoice (0 = none)
+    pub max_redeem_token_amount: u64,
     
-    /// Timestamp of the redemption
-    pub timestamp: i64,
+    /// Timestamp from which the invoice can no longer be paid
+    pub expires_at: i64,
     
-    /// Redemption transaction signature for reference
-    pub transaction_id: [u8; 32],
+    /// Timestamp when the invoice was created
+    pub created_at: i64,
     
-    /// The bump seed for this redemption account's PDA
-    pub bump: u8,
+    /// Whether the invoice has been paid
+    pub paid: bool,
     
-    /// Reserved space for future upgrades (32 bytes)
-    pub reserved: [u8; 32],
+    /// The customer that paid the invoice
+    pub paid_by: Pubkey,
+    
+    /// The purchase transaction record of the payment (default pubkey when the merchant
+    /// uses compressed receipts)
+    pub purchase: Pubkey,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
 }
 
-impl TokenRedemption {
+impl Invoice {
     /// Calculate the space needed for this account
-    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 8 (token_amount) + 8 (fiat_value)
-    /// + 2 (discount_rate) + 8 (timestamp) + 32 (transaction_id) + 1 (bump) + 32 (reserved) = 163 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 2 + 8 + 32 + 1 + 32;
+    /// 8 (discriminator) + 32 (merchant) + 32 (reference_id) + 8 (fiat_amount)
+    /// + 8 (max_redeem_token_amount) + 8 (expires_at) + 8 (created_at) + 1 (paid)
+    /// + 32 (paid_by) + 32 (purchase) + 1 (bump) = 170 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 32 + 32 + 1;
 }
 
-/// Seeds for deriving the mint authority PDA
-pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
-
-/// Seeds for deriving the config account PDA  
-pub const CONFIG_SEED: &[u8] = b"config";
-
-/// Seeds for deriving merchant account PDAs
-pub const MERCHANT_SEED: &[u8] = b"merchant";
+/// Tokens escrowed behind a secret code, claimable by whoever presents the code
+#[account]
+pub struct GiftCard {
+    /// The wallet that bought the gift card and may reclaim it after expiry
+    pub issuer: Pubkey,
+    
+    /// SHA-256 hash of the secret code
+    pub code_hash: [u8; 32],
+    
+    /// Tokens held for the gift card (in smallest unit, considering 9 decimals)
+    pub amount: u64,
+    
+    /// Timestamp from which the gift card can no longer be redeemed and the issuer may
+    /// reclaim it
+    pub expires_at: i64,
+    
+    /// Timestamp when the gift card was created
+    pub created_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
 
-/// Seeds for deriving purchase transaction PDAs
-pub const TRANSACTION_SEED: &[u8] = b"transaction";
+impl GiftCard {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (issuer) + 32 (code_hash) + 8 (amount) + 8 (expires_at)
+    /// + 8 (created_at) + 1 (bump) = 97 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
 
-/// Seeds for deriving token transfer PDAs
-pub const TRANSFER_SEED: &[u8] = b"transfer";
+    /// Longest secret code accepted on redemption
+    pub const MAX_CODE_LEN: usize = 64;
+}
 
-/// Seeds for deriving token redemption PDAs
-pub const REDEMPTION_SEED: &[u8] = b"redemption";
+/// Redeemed tokens a merchant burned in exchange for an IDR payout made off-chain
+#[account]
+pub struct SettlementRecord {
+    /// The merchant account that settled
+    pub merchant: Pubkey,
+    
+    /// Merchant-chosen settlement period, unique per merchant
+    pub period_id: u64,
+    
+    /// Tokens burned (in smallest unit, considering 9 decimals)
+    pub token_amount: u64,
+    
+    /// IDR owed to the merchant for the burned tokens
+    pub idr_value: u64,
+    
+    /// Value of one token in IDR used for `idr_value`
+    pub token_to_idr_rate: u64,
+    
+    /// Timestamp of the settlement
+    pub timestamp: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
 
-// ============================================================================
-// Voucher Pool State Structures for Non-Custodial Staking
-// ============================================================================
+impl SettlementRecord {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (merchant) + 8 (period_id) + 8 (token_amount) + 8 (idr_value)
+    /// + 8 (token_to_idr_rate) + 8 (timestamp) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+}
 
-/// Configuration parameters for the voucher staking pool
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
-pub struct PoolConfig {
-    /// Minimum amount required to stake
-    pub min_stake_amount: u64,
+/// Recurring payment a customer authorized to a merchant, charged through an SPL
+/// delegate approval held by this account
+#[account]
+pub struct Subscription {
+    /// The paying customer
+    pub customer: Pubkey,
     
-    /// Maximum amount that can be staked per user
-    pub max_stake_per_user: u64,
+    /// The merchant account being paid
+    pub merchant: Pubkey,
     
-    /// Whether the pool accepts new deposits
-    pub deposits_enabled: bool,
+    /// The customer's token account the approval was granted on
+    pub customer_token_account: Pubkey,
     
-    /// Whether users can withdraw/redeem
-    pub withdrawals_enabled: bool,
+    /// Tokens charged each period (in smallest unit, considering 9 decimals)
+    pub amount_per_period: u64,
     
-    /// Annual percentage yield (in basis points, e.g., 1200 = 12%)
-    pub apy_basis_points: u16,
+    /// Seconds between charges
+    pub period_seconds: i64,
+    
+    /// Number of periods the customer agreed to pay for
+    pub max_periods: u32,
+    
+    /// Number of periods charged so far
+    pub periods_charged: u32,
+    
+    /// Timestamp from which the next period can be charged
+    pub next_charge_at: i64,
+    
+    /// Timestamp when the subscription was created
+    pub created_at: i64,
+    
+    /// Whether charges are rewarded and recorded like purchases
+    pub earn_rewards: bool,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
 }
 
-/// Main pool state account for voucher staking
-/// Tracks overall pool metrics and configuration
+impl Subscription {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 32 (customer_token_account)
+    /// + 8 (amount_per_period) + 8 (period_seconds) + 4 (max_periods) + 4 (periods_charged)
+    /// + 8 (next_charge_at) + 8 (created_at) + 1 (earn_rewards) + 1 (bump) = 146 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 4 + 4 + 8 + 8 + 1 + 1;
+
+    /// Whether every agreed period has been charged
+    pub fn is_exhausted(&self) -> bool {
+        self.periods_charged >= self.max_periods
+    }
+
+    /// Transaction ID identifying the charge of `period`, used for its reward lot
+    /// The creation time keeps ids distinct when a cancelled subscription is recreated
+    pub fn charge_id(&self, period: u32) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[
+            self.merchant.as_ref(),
+            &self.created_at.to_le_bytes(),
+            &period.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// A merkle-rooted token distribution whose allocations are minted as they are claimed
 #[account]
-pub struct PoolState {
-    /// The authority that can manage the pool (admin)
-    pub pool_authority: Pubkey,
+pub struct Airdrop {
+    /// Authority-chosen identifier, part of the PDA seeds
+    pub airdrop_id: u64,
     
-    /// The delegate authority that can execute deposits on behalf of users
-    pub pool_delegate: Pubkey,
+    /// Root of the merkle tree of (wallet, amount) allocations
+    pub merkle_root: [u8; 32],
     
-    /// The vault token account that holds staked voucher tokens
-    pub vault_ata: Pubkey,
+    /// Most tokens the airdrop may mint in total (in smallest unit, considering 9 decimals)
+    pub budget: u64,
     
-    /// The mint address of the voucher token (LOKAL token)
-    pub voucher_mint: Pubkey,
+    /// Tokens minted to claimants so far
+    pub claimed_amount: u64,
     
-    /// Pool configuration parameters
-    pub config: PoolConfig,
+    /// Timestamp from which claims are refused and the budget can be swept
+    pub expires_at: i64,
     
-    /// Total amount of voucher tokens currently staked in the pool
-    pub total_voucher_staked: u64,
+    /// Timestamp when the airdrop was created
+    pub created_at: i64,
     
-    /// Total SOL/WSOL staked (after swaps)
-    pub total_sol_staked: u64,
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl Airdrop {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (airdrop_id) + 32 (merkle_root) + 8 (budget)
+    /// + 8 (claimed_amount) + 8 (expires_at) + 8 (created_at) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 8 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    /// Longest merkle proof accepted on claim, enough for 2^32 allocations
+    pub const MAX_PROOF_LEN: usize = 32;
+
+    /// Leaf hash of an allocation: sha256(0x00 || wallet || amount as u64 LE)
+    pub fn leaf(wallet: &Pubkey, amount: u64) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[&[0u8], wallet.as_ref(), &amount.to_le_bytes()])
+            .to_bytes()
+    }
+
+    /// Whether `proof` links `leaf` to `merkle_root`
+    /// Each parent is sha256(0x01 || lower child || higher child), so proofs carry no
+    /// left/right flags, and the prefixes keep a leaf from passing as an inner node
+    pub fn verify_proof(merkle_root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+        let computed = proof.iter().fold(leaf, |node, sibling| {
+            let (lower, higher) = if node <= *sibling {
+                (node, *sibling)
+            } else {
+                (*sibling, node)
+            };
+            anchor_lang::solana_program::hash::hashv(&[&[1u8], &lower, &higher]).to_bytes()
+        });
+        computed == *merkle_root
+    }
+
+    /// Budget not yet minted to claimants
+    pub fn unclaimed_amount(&self) -> u64 {
+        self.budget.saturating_sub(self.claimed_amount)
+    }
+}
+
+/// Marker that a wallet has claimed its allocation from an airdrop
+#[account]
+pub struct AirdropClaim {
+    /// The airdrop claimed from
+    pub airdrop: Pubkey,
     
-    /// Total yield earned (in SOL/WSOL)
-    pub total_yield_earned: u64,
+    /// The wallet that claimed
+    pub claimant: Pubkey,
     
-    /// Number of unique stakers
-    pub total_stakers: u64,
+    /// Tokens minted for the claim
+    pub amount: u64,
     
-    /// Reward index for calculating proportional yields
-    pub reward_index: u128,
+    /// Timestamp of the claim
+    pub claimed_at: i64,
     
-    /// Timestamp when pool was created
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl AirdropClaim {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (airdrop) + 32 (claimant) + 8 (amount) + 8 (claimed_at)
+    /// + 1 (bump) = 89 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// An ephemeral key a customer lets make purchases on their behalf, spending from
+/// tokens escrowed for it
+#[account]
+pub struct SessionKey {
+    /// The customer the session purchases for
+    pub customer: Pubkey,
+    
+    /// The ephemeral key allowed to sign session purchases
+    pub session_signer: Pubkey,
+    
+    /// Tokens escrowed for the session to redeem (in smallest unit, considering 9 decimals)
+    pub spend_limit: u64,
+    
+    /// Tokens redeemed through the session so far
+    pub spent: u64,
+    
+    /// Timestamp from which the session can no longer be used
+    pub expires_at: i64,
+    
+    /// Timestamp when the session was created
     pub created_at: i64,
     
-    /// Timestamp of last yield update
-    pub last_yield_update: i64,
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl SessionKey {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (session_signer) + 8 (spend_limit)
+    /// + 8 (spent) + 8 (expires_at) + 8 (created_at) + 1 (bump) = 105 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    /// Tokens the session may still redeem, all of which are held in escrow
+    pub fn remaining_allowance(&self) -> u64 {
+        self.spend_limit.saturating_sub(self.spent)
+    }
+
+    /// Whether the session has expired at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// A point-of-sale key allowed to approve purchases for a merchant
+#[account]
+pub struct PosKey {
+    /// The merchant account the key approves purchases for
+    pub merchant: Pubkey,
     
-    /// The bump seed for this pool state PDA
+    /// The terminal's Ed25519 public key
+    pub pos_key: Pubkey,
+    
+    /// Timestamp when the key was registered
+    pub created_at: i64,
+    
+    /// The bump seed for this PDA
     pub bump: u8,
+}
+
+impl PosKey {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (merchant) + 32 (pos_key) + 8 (created_at) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// A wallet allowed to act for a merchant at the till without holding the owner key
+/// Operators may approve purchases and create invoices, but not change the merchant's
+/// settings or move its funds
+#[account]
+pub struct MerchantOperator {
+    /// The merchant account the operator acts for
+    pub merchant: Pubkey,
     
-    /// Reserved space for future upgrades (64 bytes)
-    pub reserved: [u8; 64],
+    /// The operator's wallet
+    pub operator: Pubkey,
+    
+    /// Timestamp when the operator was added
+    pub added_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
 }
 
-impl PoolState {
+impl MerchantOperator {
     /// Calculate the space needed for this account
-    /// 8 (discriminator) + 32 (pool_authority) + 32 (pool_delegate) + 32 (vault_ata)
-    /// + 32 (voucher_mint) + (8 + 8 + 1 + 1 + 2) PoolConfig + 8 (total_voucher_staked)
-    /// + 8 (total_sol_staked) + 8 (total_yield_earned) + 8 (total_stakers)
-    /// + 16 (reward_index) + 8 (created_at) + 8 (last_yield_update) + 1 (bump) + 64 (reserved)
-    /// = 284 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 20 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 1 + 64;
+    /// 8 (discriminator) + 32 (merchant) + 32 (operator) + 8 (added_at) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
 }
 
-/// Individual user stake record
-/// Tracks each user's staking position and rewards
+/// A franchise brand whose outlets are registered as child merchants
+/// Keeps aggregate counters over all of its outlets' purchases
 #[account]
-pub struct UserStakeRecord {
-    /// The user who owns this stake
-    pub user: Pubkey,
+pub struct ParentMerchant {
+    /// The brand's owner wallet, which co-signs outlet registrations
+    pub owner: Pubkey,
     
-    /// The pool this stake belongs to
-    pub pool: Pubkey,
+    /// The brand's display name
+    pub name: [u8; 32],
     
-    /// Amount of voucher tokens staked by this user
-    pub staked_amount: u64,
+    /// Cashback rate (in basis points) `cascade_cashback_rate` applies to outlets
+    pub default_cashback_rate: u16,
     
-    /// User's reward index snapshot (for yield calculations)
-    pub user_reward_index: u128,
+    /// Number of outlets ever registered under this parent, including closed ones
+    pub child_count: u32,
     
-    /// Total yield claimed by this user
-    pub total_yield_claimed: u64,
+    /// Total number of transactions processed across all outlets
+    pub total_transactions: u64,
     
-    /// Timestamp when user first staked
-    pub staked_at: i64,
+    /// Total amount of tokens distributed as rewards across all outlets
+    pub total_rewards_distributed: u64,
     
-    /// Timestamp of last stake/unstake action
-    pub last_action_at: i64,
+    /// Total purchase volume (in IDR) processed across all outlets
+    pub total_volume: u64,
     
-    /// The bump seed for this user stake record PDA
+    /// Timestamp when the parent was created
+    pub created_at: i64,
+    
+    /// The bump seed for this PDA
     pub bump: u8,
+}
+
+impl ParentMerchant {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (owner) + 32 (name) + 2 (default_cashback_rate)
+    /// + 4 (child_count) + 8 (total_transactions) + 8 (total_rewards_distributed)
+    /// + 8 (total_volume) + 8 (created_at) + 1 (bump) = 111 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 4 + 8 + 8 + 8 + 8 + 1;
+}
+
+/// Cashback a sponsor pays for purchases at its member merchants, out of escrowed tokens
+/// Purchase rewards are transferred from the escrow instead of minted while it lasts
+#[account]
+pub struct RewardBudget {
+    /// The wallet that funded the budget and may top it up or withdraw from it
+    pub sponsor: Pubkey,
     
-    /// Reserved space for future upgrades (32 bytes)
-    pub reserved: [u8; 32],
+    /// Sponsor-chosen identifier, unique per sponsor
+    pub budget_id: u64,
+    
+    /// Escrowed tokens still available for rewards
+    pub remaining_budget: u64,
+    
+    /// Tokens paid out as rewards so far
+    pub total_paid: u64,
+    
+    /// Number of merchants currently in the budget
+    pub member_count: u32,
+    
+    /// Timestamp when the budget was created
+    pub created_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
 }
 
-impl UserStakeRecord {
+impl RewardBudget {
     /// Calculate the space needed for this account
-    /// 8 (discriminator) + 32 (user) + 32 (pool) + 8 (staked_amount)
-    /// + 16 (user_reward_index) + 8 (total_yield_claimed) + 8 (staked_at)
-    /// + 8 (last_action_at) + 1 (bump) + 32 (reserved) = 153 bytes
-    pub const LEN: usize = 8 + 32 + 32 + 8 + 16 + 8 + 8 + 8 + 1 + 32;
+    /// 8 (discriminator) + 32 (sponsor) + 8 (budget_id) + 8 (remaining_budget)
+    /// + 8 (total_paid) + 4 (member_count) + 8 (created_at) + 1 (bump) = 77 bytes
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 4 + 8 + 1;
+
+    /// Take `amount` out of the budget if it covers the whole amount
+    /// Returns whether it did; an exhausted budget leaves the reward to be minted
+    pub fn try_spend(&mut self, amount: u64) -> bool {
+        if amount == 0 || amount > self.remaining_budget {
+            return false;
+        }
+        self.remaining_budget -= amount;
+        self.total_paid = self.total_paid.saturating_add(amount);
+        true
+    }
 }
 
-/// Seeds for deriving the pool state PDA
-pub const POOL_STATE_SEED: &[u8] = b"pool_state";
+/// Marks a merchant as sponsored by a reward budget
+#[account]
+pub struct BudgetMembership {
+    /// The reward budget paying the merchant's rewards
+    pub reward_budget: Pubkey,
+    
+    /// The sponsored merchant account
+    pub merchant: Pubkey,
+    
+    /// Timestamp when the merchant joined the budget
+    pub joined_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
 
-/// Seeds for deriving the pool vault authority PDA
-pub const POOL_VAULT_AUTHORITY_SEED: &[u8] = b"pool_vault_authority";
+impl BudgetMembership {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (reward_budget) + 32 (merchant) + 8 (joined_at) + 1 (bump)
+    /// = 81 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
 
-/// Seeds for deriving user stake record PDAs
-pub const USER_STAKE_SEED: &[u8] = b"user_stake";
+/// The message a merchant signs to approve a purchase:
+/// merchant account || customer || fiat_amount (u64 LE) || transaction_id
+pub fn purchase_approval_message(
+    merchant: &Pubkey,
+    customer: &Pubkey,
+    fiat_amount: u64,
+    transaction_id: &[u8; 32],
+) -> [u8; 104] {
+    let mut message = [0u8; 104];
+    message[..32].copy_from_slice(merchant.as_ref());
+    message[32..64].copy_from_slice(customer.as_ref());
+    message[64..72].copy_from_slice(&fiat_amount.to_le_bytes());
+    message[72..].copy_from_slice(transaction_id);
+    message
+}
+
+/// Stage of a purchase dispute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeStatus {
+    /// Awaiting the update authority's decision
+    Open,
+    /// Decided for the customer, whose redeemed tokens were refunded
+    Upheld,
+    /// Decided for the merchant
+    Rejected,
+}
+
+/// A customer's claim against one of their purchase records, kept as an on-chain
+/// paper trail after it is resolved
+#[account]
+pub struct Dispute {
+    /// The disputed purchase transaction record
+    pub purchase: Pubkey,
+    
+    /// The customer that opened the dispute
+    pub customer: Pubkey,
+    
+    /// The merchant account of the disputed purchase
+    pub merchant: Pubkey,
+    
+    /// Customer's reason for the dispute, as an off-chain code
+    pub reason_code: u16,
+    
+    /// Merchant's response, as an off-chain code (0 until the merchant responds)
+    pub response_code: u16,
+    
+    /// Current stage of the dispute
+    pub status: DisputeStatus,
+    
+    /// Timestamp when the dispute was opened
+    pub opened_at: i64,
+    
+    /// Timestamp of the merchant's latest response (0 if none)
+    pub responded_at: i64,
+    
+    /// Timestamp when the dispute was resolved (0 while open)
+    pub resolved_at: i64,
+    
+    /// Tokens refunded to the customer when the dispute was upheld
+    pub refund_amount: u64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl Dispute {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (purchase) + 32 (customer) + 32 (merchant) + 2 (reason_code)
+    /// + 2 (response_code) + 1 (status) + 8 (opened_at) + 8 (responded_at)
+    /// + 8 (resolved_at) + 8 (refund_amount) + 1 (bump) = 142 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 2 + 1 + 8 + 8 + 8 + 8 + 1;
+}
+
+/// Where a purchase stands after it was processed
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Processed, with nothing recorded against it since
+    Completed,
+    /// Acknowledged by the fulfillment authority
+    Fulfilled,
+    /// Under dispute by its customer
+    Disputed,
+    /// The customer's redeemed tokens were refunded
+    Refunded,
+    /// Its dispute was decided for the merchant
+    Resolved,
+}
+
+/// Durable trace of a purchase, kept at the purchase's merchant, customer and transaction
+/// id after its full record is closed, so refunds, disputes and fulfillments of the same
+/// transaction id all go through one status
+#[account]
+pub struct TransactionStub {
+    /// The customer's wallet public key, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+
+    /// The merchant's account public key
+    pub merchant: Pubkey,
+
+    /// Current status of the purchase
+    pub status: TransactionStatus,
+
+    /// Timestamp of the purchase, then of its latest status change
+    pub timestamp: i64,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl TransactionStub {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 1 (status) + 8 (timestamp)
+    /// + 1 (bump) = 82 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 8 + 1;
+
+    /// Move the purchase to `status`
+    /// Refunded and resolved purchases are final; a purchase is fulfilled or disputed
+    /// only while completed, disputed once, and resolved only out of a dispute
+    pub fn transition(&mut self, status: TransactionStatus, now: i64) -> Result<()> {
+        use TransactionStatus::*;
+        match (self.status, status) {
+            (Completed, Fulfilled | Disputed | Refunded)
+            | (Fulfilled, Disputed | Refunded)
+            | (Disputed, Refunded | Resolved) => {}
+            (Refunded | Resolved, _) => return err!(CarsaError::TransactionFinalized),
+            (Disputed, Disputed) => return err!(CarsaError::TransactionAlreadyDisputed),
+            (_, Resolved) => return err!(CarsaError::TransactionNotDisputed),
+            _ => return err!(CarsaError::InvalidTransactionTransition),
+        }
+        self.status = status;
+        self.timestamp = now;
+        Ok(())
+    }
+}
+
+/// Points a purchase's reference code at its record, so support can find a purchase from
+/// the code a customer reads out without scanning
+#[account]
+pub struct ReferenceIndex {
+    /// The reference code, as stored on the purchase record
+    pub reference_code: [u8; 8],
+
+    /// The purchase record, of either layout, the code was derived from
+    pub transaction_record: Pubkey,
+
+    /// The purchase's `TransactionStub`, kept after the record is closed
+    pub transaction_stub: Pubkey,
+
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl ReferenceIndex {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (reference_code) + 32 (transaction_record)
+    /// + 32 (transaction_stub) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 8 + 32 + 32 + 1;
+
+    /// Crockford base32, which leaves out I, L, O and U so codes survive being read aloud
+    pub const ALPHABET: &'static [u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+    /// The reference code of the purchase recorded at `transaction_record`: the first
+    /// 5 bytes of sha256 of the address, as 8 base32 characters
+    /// Shown to customers as `CRSA-` followed by the code
+    pub fn reference_code(transaction_record: &Pubkey) -> [u8; 8] {
+        let hash = anchor_lang::solana_program::hash::hash(transaction_record.as_ref());
+        let bits = hash.to_bytes()[..5]
+            .iter()
+            .fold(0u64, |bits, byte| bits << 8 | u64::from(*byte));
+        let mut code = [0; 8];
+        for (i, character) in code.iter_mut().enumerate() {
+            *character = Self::ALPHABET[(bits >> (35 - 5 * i) & 0x1f) as usize];
+        }
+        code
+    }
+}
+
+/// Tokens a customer has set aside in escrow for a merchant to capture at the end of a
+/// purchase, e.g. when the bill is settled after a meal
+#[account]
+pub struct Hold {
+    /// The customer whose tokens are held
+    pub customer: Pubkey,
+    
+    /// The merchant account that may capture the hold
+    pub merchant: Pubkey,
+    
+    /// Customer-chosen identifier, unique per customer and reused as the capture's
+    /// transaction id
+    pub hold_id: [u8; 32],
+    
+    /// Tokens held in escrow (in smallest unit, considering 9 decimals)
+    pub amount: u64,
+    
+    /// Timestamp from which the hold can no longer be captured and the customer may
+    /// release it
+    pub expires_at: i64,
+    
+    /// Timestamp when the hold was created
+    pub created_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl Hold {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 32 (hold_id) + 8 (amount)
+    /// + 8 (expires_at) + 8 (created_at) + 1 (bump) = 129 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 1;
+
+    /// Whether the hold can no longer be captured at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// A field's value before and after an instruction changed it
+/// Mutation events carry one per field they touch, so an indexer can rebuild the field's
+/// full history from the events alone
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Changed<T> {
+    pub old: T,
+    pub new: T,
+}
+
+/// Tokens a customer redeems as part of a purchase
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct RedemptionParams {
+    /// Amount of tokens to redeem (in smallest unit, considering 9 decimals)
+    pub token_amount: u64,
+}
+
+/// Reward a purchase would earn, returned by `preview_purchase_reward`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PurchaseRewardPreview {
+    /// Total transaction value (fiat + token value in IDR)
+    pub total_value: u64,
+    
+    /// Cashback rate applied, including any staker boost (in basis points)
+    pub cashback_rate: u16,
+    
+    /// Staker boost included in `cashback_rate` (in basis points, 0 if none)
+    pub staker_boost_bps: u16,
+    
+    /// Reward tokens the purchase would mint, including `category_boost_reward`
+    pub reward_amount: u64,
+    
+    /// Category boost rate on top of `cashback_rate` (in basis points, 0 if none applies)
+    pub category_boost_bps: u16,
+    
+    /// Part of `reward_amount` paid by the category boost
+    pub category_boost_reward: u64,
+}
+
+/// Health summary returned by `get_program_status`, for monitoring bots
+/// The layout only ever grows at the end, raising `version` each time, so older decoders
+/// keep reading the fields they know
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ProgramStatus {
+    /// Layout version, `ProgramStatus::VERSION` when written
+    pub version: u8,
+    
+    /// Whether purchases and transfers are halted
+    pub paused: bool,
+    
+    /// Supply reported by the Lokal mint
+    pub mint_supply: u64,
+    
+    /// Supply tracked in the config, `total_supply - total_burned`
+    pub tracked_supply: u64,
+    
+    /// Tokens administrative paths may still mint (`u64::MAX` when uncapped)
+    pub admin_mint_headroom: u64,
+    
+    /// Tokens purchase rewards may still mint (`u64::MAX` when uncapped)
+    pub reward_mint_headroom: u64,
+    
+    /// Queued parameter changes not yet executed or cancelled
+    pub pending_param_changes: u16,
+    
+    /// The voucher pool reported on
+    pub pool: Pubkey,
+    
+    /// Whether the pool's config accepts deposits
+    pub deposits_enabled: bool,
+    
+    /// Whether the pool's config allows withdrawals
+    pub withdrawals_enabled: bool,
+    
+    /// Whether the pool's guardian or authority has paused deposits
+    pub deposits_paused: bool,
+    
+    /// Whether the pool's guardian or authority has paused withdrawals
+    pub withdrawals_paused: bool,
+    
+    /// Tokens held by the pool vault
+    pub vault_balance: u64,
+    
+    /// Vouchers staked in the pool
+    pub total_staked: u64,
+    
+    /// `vault_balance + deployed_amount - total_staked`: 0 when every stake is backed,
+    /// positive while the vault holds reserves, negative on a shortfall
+    pub vault_delta: i64,
+    
+    /// Seconds since the pool last recorded yield
+    pub yield_update_age_seconds: i64,
+}
+
+impl ProgramStatus {
+    /// Current layout version
+    pub const VERSION: u8 = 1;
+}
+
+/// Token transfer record for tracking P2P transfers
+/// This account stores details of token transfers between users
+#[account]
+pub struct TokenTransfer {
+    /// The sender's public key
+    pub from: Pubkey,
+    
+    /// The recipient's public key
+    pub to: Pubkey,
+    
+    /// Amount of tokens sent, before the transfer fee
+    pub amount: u64,
+    
+    /// Timestamp of the transfer
+    pub timestamp: i64,
+    
+    /// Transfer transaction signature for reference
+    pub transaction_id: [u8; 32],
+    
+    /// Optional memo/note for the transfer
+    pub memo: [u8; 64],
+    
+    /// The bump seed for this transfer account's PDA
+    pub bump: u8,
+    
+    /// Transfer fee taken from `amount` for the treasury
+    pub fee: u64,
+    
+    /// Amount the recipient received (`amount` - `fee`)
+    pub net_amount: u64,
+}
+
+impl TokenTransfer {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (from) + 32 (to) + 8 (amount) + 8 (timestamp)
+    /// + 32 (transaction_id) + 64 (memo) + 1 (bump) + 8 (fee) + 8 (net_amount) = 201 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 32 + 64 + 1 + 8 + 8;
+}
+
+/// Audit record of a single treasury withdrawal
+#[account]
+pub struct TreasuryWithdrawal {
+    /// Position of this withdrawal (starts at 0)
+    pub index: u64,
+    
+    /// The update authority that made the withdrawal
+    pub authority: Pubkey,
+    
+    /// The co-signer, or the default pubkey when none was required
+    pub cosigner: Pubkey,
+    
+    /// Token account the fees were sent to
+    pub destination: Pubkey,
+    
+    /// Amount withdrawn
+    pub amount: u64,
+    
+    /// Caller-defined code describing why the funds were moved
+    pub reason_code: u16,
+    
+    /// Timestamp of the withdrawal
+    pub timestamp: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl TreasuryWithdrawal {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (index) + 32 (authority) + 32 (cosigner) + 32 (destination)
+    /// + 8 (amount) + 2 (reason_code) + 8 (timestamp) + 1 (bump) = 131 bytes
+    pub const LEN: usize = 8 + 8 + 32 + 32 + 32 + 8 + 2 + 8 + 1;
+}
+
+/// A parameter that can only change after the config's timelock
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub enum ParamChange {
+    /// New `LokalMintConfig::token_to_idr_rate`
+    TokenToIdrRate(u64),
+    /// New `LokalMintConfig::protocol_fee_bps`
+    ProtocolFeeBps(u16),
+    /// New `LokalMintConfig::param_timelock_seconds`
+    ParamTimelockSeconds(i64),
+    /// New `LokalMintConfig::mint_cosigner` and `single_sig_mint_limit`
+    MintCosigner { mint_cosigner: Pubkey, single_sig_mint_limit: u64 },
+    /// New `LokalMintConfig::price_feed` and its staleness and confidence bounds
+    PriceFeed { price_feed: Pubkey, max_price_age_seconds: u32, max_price_confidence_bps: u16 },
+    /// New `LokalMintConfig::dispute_window_seconds`
+    DisputeWindowSeconds(u32),
+    /// Partial update to the configuration of a voucher pool
+    PoolConfig { pool_id: u16, update: PoolConfigUpdate },
+    /// New `LokalMintConfig::max_transfer_amount`, `max_mint_per_tx` and `max_purchase_idr`
+    Limits { max_transfer_amount: u64, max_mint_per_tx: u64, max_purchase_idr: u64 },
+    /// New `LokalMintConfig::rate_change_cooldown_seconds` and `max_rate_increase_bps`
+    RateChangePolicy { rate_change_cooldown_seconds: u32, max_rate_increase_bps: u16 },
+    /// New `LokalMintConfig::admin_mint_cap` and `reward_mint_cap`
+    MintCaps { admin_mint_cap: u64, reward_mint_cap: u64 },
+    /// New `LokalMintConfig::max_cashback_bps` and `alert_threshold_bps`
+    CashbackLimits { max_cashback_bps: u16, alert_threshold_bps: u16 },
+    /// New `PoolState::advance_ltv_bps` of a voucher pool
+    AdvanceLtv { pool_id: u16, advance_ltv_bps: u16 },
+}
+
+impl ParamChange {
+    /// Largest serialized size: 1 (variant) + 2 (pool_id) + PoolConfigUpdate::MAX_LEN
+    pub const MAX_LEN: usize = 1 + 2 + PoolConfigUpdate::MAX_LEN;
+}
+
+/// A parameter change waiting out the timelock
+#[account]
+pub struct PendingParamChange {
+    /// Position of this change among all queued changes (starts at 0)
+    pub id: u64,
+    
+    /// The authority that queued the change, refunded when it is executed or cancelled
+    pub proposer: Pubkey,
+    
+    /// The parameter and its new value
+    pub change: ParamChange,
+    
+    /// Timestamp when the change was queued
+    pub queued_at: i64,
+    
+    /// Earliest timestamp at which the change can be executed
+    pub effective_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl PendingParamChange {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (id) + 32 (proposer) + ParamChange::MAX_LEN (change)
+    /// + 8 (queued_at) + 8 (effective_at) + 1 (bump) = 211 bytes
+    pub const LEN: usize = 8 + 8 + 32 + ParamChange::MAX_LEN + 8 + 8 + 1;
+}
+
+/// A purchase reward held in escrow until the customer withdraws it or it expires
+#[account]
+pub struct RewardLot {
+    /// The customer the reward was earned by, or their `customer_hash` for purchases made
+    /// in privacy mode
+    pub customer: Pubkey,
+    
+    /// Transaction ID of the purchase that earned the reward
+    pub transaction_id: [u8; 32],
+    
+    /// Reward tokens still in escrow
+    pub amount: u64,
+    
+    /// Reward tokens burned by `expire_rewards`
+    pub expired_amount: u64,
+    
+    /// Timestamp when the reward was minted into escrow
+    pub minted_at: i64,
+    
+    /// Timestamp from which the reward can no longer be withdrawn
+    pub expires_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl RewardLot {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (transaction_id) + 8 (amount)
+    /// + 8 (expired_amount) + 8 (minted_at) + 8 (expires_at) + 1 (bump) = 105 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1;
+
+    /// Whether the lot has expired at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Tokens granted to a merchant that vest linearly from `start` over `duration`,
+/// claimable from `cliff`
+#[account]
+pub struct VestingGrant {
+    /// Position of this grant among all grants (starts at 0)
+    pub id: u64,
+    
+    /// The wallet that can claim the vested tokens
+    pub beneficiary: Pubkey,
+    
+    /// Tokens granted
+    pub total: u64,
+    
+    /// Tokens claimed so far
+    pub released: u64,
+    
+    /// Timestamp vesting starts from
+    pub start: i64,
+    
+    /// Timestamp before which nothing can be claimed
+    pub cliff: i64,
+    
+    /// Seconds from `start` until the whole grant has vested
+    pub duration: i64,
+    
+    /// Timestamp the grant was revoked, freezing vesting (0 = not revoked)
+    pub revoked_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl VestingGrant {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (id) + 32 (beneficiary) + 8 (total) + 8 (released) + 8 (start)
+    /// + 8 (cliff) + 8 (duration) + 8 (revoked_at) + 1 (bump) = 105 bytes
+    pub const LEN: usize = 8 + 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Tokens vested at `now`: nothing before the cliff, then `total * elapsed / duration`
+    /// rounded down, reaching exactly `total` at the end of the schedule
+    /// Vesting stops at `revoked_at` once revoked
+    pub fn vested_amount(&self, now: i64) -> Option<u64> {
+        let now = if self.revoked_at > 0 { now.min(self.revoked_at) } else { now };
+        if now < self.cliff {
+            return Some(0);
+        }
+
+        let elapsed = now.checked_sub(self.start)?;
+        if elapsed >= self.duration {
+            return Some(self.total);
+        }
+
+        let vested = (self.total as u128)
+            .checked_mul(u128::try_from(elapsed).ok()?)?
+            .checked_div(u128::try_from(self.duration).ok()?)?;
+        u64::try_from(vested).ok()
+    }
+
+    /// Tokens vested at `now` but not yet claimed
+    pub fn claimable_amount(&self, now: i64) -> Option<u64> {
+        self.vested_amount(now)?.checked_sub(self.released)
+    }
+}
+
+/// Waives the transfer fee for transfers sent by a wallet (e.g. a merchant)
+#[account]
+pub struct FeeExemption {
+    /// The exempt wallet
+    pub wallet: Pubkey,
+    
+    /// Timestamp when the exemption was granted
+    pub added_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl FeeExemption {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (wallet) + 8 (added_at) + 1 (bump) = 49 bytes
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
+
+/// Token redemption record for tracking spending at merchants
+/// This account stores details when customers spend Lokal tokens at merchants
+#[account]
+pub struct TokenRedemption {
+    /// The customer who redeemed tokens
+    pub customer: Pubkey,
+    
+    /// The merchant where tokens were redeemed
+    pub merchant: Pubkey,
+    
+    /// Amount of tokens redeemed
+    pub token_amount: u64,
+    
+    /// Fiat value of the redemption (in Indonesian Rupiah IDR)
+    pub fiat_value: u64,
+    
+    /// Discount percentage applied (in basis points)
+    pub discount_rate: u16,
+    
+    /// Timestamp of the redemption
+    pub timestamp: i64,
+    
+    /// Redemption transaction signature for reference
+    pub transaction_id: [u8; 32],
+    
+    /// The bump seed for this redemption account's PDA
+    pub bump: u8,
+    
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl TokenRedemption {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 32 (merchant) + 8 (token_amount) + 8 (fiat_value)
+    /// + 2 (discount_rate) + 8 (timestamp) + 32 (transaction_id) + 1 (bump) + 32 (reserved) = 163 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 2 + 8 + 32 + 1 + 32;
+}
+
+/// Kind of wallet activity captured by an `ActivityRecord`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivityKind {
+    Purchase,
+    Transfer,
+    Redemption,
+}
+
+/// Compact entry in a wallet's activity feed
+/// Written alongside purchase and transfer records when `record_activity` is enabled;
+/// `wallet` comes first so one memcmp filter finds every entry for a wallet
+#[account]
+pub struct ActivityRecord {
+    /// The wallet the activity belongs to (the customer or the sender); purchases made in
+    /// privacy mode record the customer's `customer_hash` instead
+    pub wallet: Pubkey,
+    
+    /// The merchant account for purchases and redemptions, the recipient for transfers
+    pub counterparty: Pubkey,
+    
+    /// Lokal tokens involved: reward minted, tokens transferred or tokens redeemed
+    pub amount: u64,
+    
+    /// Timestamp of the activity
+    pub timestamp: i64,
+    
+    /// What kind of activity this entry records
+    pub kind: ActivityKind,
+    
+    /// Transaction identifier shared with the purchase or transfer record
+    pub transaction_id: [u8; 32],
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl ActivityRecord {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (wallet) + 32 (counterparty) + 8 (amount) + 8 (timestamp)
+    /// + 1 (kind) + 32 (transaction_id) + 1 (bump) = 122 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1 + 32 + 1;
+}
+
+/// Daily aggregate protocol stats for dashboards
+/// Rewritten by every snapshot taken during its day
+#[account]
+pub struct StatsSnapshot {
+    /// Day this snapshot covers (unix_timestamp / 86400)
+    pub day_index: u64,
+    
+    /// Total Lokal tokens ever minted
+    pub total_supply: u64,
+    
+    /// Merchants ever registered, including closed ones
+    pub merchant_count: u64,
+    
+    /// Purchases processed
+    pub total_purchases: u64,
+    
+    /// Peer-to-peer transfers made
+    pub total_transfers: u64,
+    
+    /// Vouchers staked in the original voucher pool
+    pub total_voucher_staked: u64,
+    
+    /// Stakers in the original voucher pool
+    pub total_stakers: u64,
+    
+    /// Yield earned by the original voucher pool
+    pub total_yield_earned: u64,
+    
+    /// Timestamp of the latest snapshot this day
+    pub timestamp: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+    
+    /// Reserved space for future upgrades (32 bytes)
+    pub reserved: [u8; 32],
+}
+
+impl StatsSnapshot {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 8 (day_index) + 8 (total_supply) + 8 (merchant_count)
+    /// + 8 (total_purchases) + 8 (total_transfers) + 8 (total_voucher_staked) + 8 (total_stakers)
+    /// + 8 (total_yield_earned) + 8 (timestamp) + 1 (bump) + 32 (reserved) = 113 bytes
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    /// Seconds in a snapshot day
+    pub const SECONDS_PER_DAY: i64 = 86_400;
+
+    /// Snapshot day containing `unix_timestamp`, or None before the epoch
+    pub fn day_index(unix_timestamp: i64) -> Option<u64> {
+        u64::try_from(unix_timestamp.checked_div_euclid(Self::SECONDS_PER_DAY)?).ok()
+    }
+}
+
+/// A customer's purchase totals for one calendar month (UTC), for the spending passbook
+/// Each month gets its own account, created by the first purchase made in it
+#[account]
+pub struct CustomerMonthlyStats {
+    /// The customer these totals belong to, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+    
+    /// The month covered, as YYYYMM (e.g. 202401)
+    pub year_month: u32,
+    
+    /// Purchases made this month
+    pub purchase_count: u64,
+    
+    /// Total value spent this month, fiat plus redeemed tokens (in IDR)
+    pub total_spent: u64,
+    
+    /// Reward tokens earned this month
+    pub rewards_earned: u64,
+    
+    /// Tokens redeemed toward purchases this month
+    pub tokens_redeemed: u64,
+    
+    /// Timestamp of the latest purchase this month
+    pub updated_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CustomerMonthlyStats {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (customer) + 4 (year_month) + 8 (purchase_count)
+    /// + 8 (total_spent) + 8 (rewards_earned) + 8 (tokens_redeemed) + 8 (updated_at)
+    /// + 1 (bump) = 85 bytes
+    pub const LEN: usize = 8 + 32 + 4 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    /// Calendar month (UTC) containing `unix_timestamp` as YYYYMM, or None before the epoch
+    pub fn year_month(unix_timestamp: i64) -> Option<u32> {
+        // Civil-from-days over 400-year eras of 146,097 days, with years starting in March
+        let days = StatsSnapshot::day_index(unix_timestamp)? as i64 + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        u32::try_from(year * 100 + month).ok()
+    }
+
+    /// Add a purchase made at `timestamp` to this month's totals
+    pub fn record_purchase(
+        &mut self,
+        total_value: u64,
+        redeemed_tokens: u64,
+        reward_amount: u64,
+        timestamp: i64,
+    ) -> Option<()> {
+        self.purchase_count = self.purchase_count.checked_add(1)?;
+        self.total_spent = self.total_spent.checked_add(total_value)?;
+        self.tokens_redeemed = self.tokens_redeemed.checked_add(redeemed_tokens)?;
+        self.rewards_earned = self.rewards_earned.checked_add(reward_amount)?;
+        self.updated_at = timestamp;
+        Some(())
+    }
+}
+
+/// A customer's visits to one merchant, counting toward the merchant's streak bonus
+/// Created by the customer's first purchase at the merchant
+#[account]
+pub struct CustomerMerchantStats {
+    /// The merchant visited
+    pub merchant: Pubkey,
+    
+    /// The visiting customer, or its `customer_hash` in privacy mode
+    pub customer: Pubkey,
+    
+    /// Visits counted in the current streak window
+    pub visit_count: u16,
+    
+    /// Timestamp of the first visit in the current streak window
+    pub window_start: i64,
+    
+    /// Timestamp of the last visit counted
+    pub last_visit: i64,
+    
+    /// Streaks the customer has completed at the merchant
+    pub streaks_completed: u32,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CustomerMerchantStats {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (merchant) + 32 (customer) + 2 (visit_count) + 8 (window_start)
+    /// + 8 (last_visit) + 4 (streaks_completed) + 1 (bump) = 95 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 2 + 8 + 8 + 4 + 1;
+
+    /// Count a visit at `now` toward a streak of `streak_target` visits within
+    /// `streak_window_seconds`, returning whether it completed one
+    /// A visit after the window has run out starts a new window; a completed streak resets
+    /// the count. Unless `counts_every_purchase`, visits on the day of the last one are ignored
+    pub fn record_visit(
+        &mut self,
+        streak_target: u16,
+        streak_window_seconds: u32,
+        counts_every_purchase: bool,
+        now: i64,
+    ) -> Option<bool> {
+        if !counts_every_purchase
+            && self.last_visit > 0
+            && StatsSnapshot::day_index(self.last_visit)? == StatsSnapshot::day_index(now)?
+        {
+            return Some(false);
+        }
+
+        if self.visit_count == 0
+            || now.saturating_sub(self.window_start) >= i64::from(streak_window_seconds)
+        {
+            self.visit_count = 0;
+            self.window_start = now;
+        }
+        self.visit_count = self.visit_count.checked_add(1)?;
+        self.last_visit = now;
+
+        if self.visit_count < streak_target {
+            return Some(false);
+        }
+        self.visit_count = 0;
+        self.streaks_completed = self.streaks_completed.checked_add(1)?;
+        Some(true)
+    }
+}
+
+/// Administrative block on a Lokal token account, frozen for as long as it exists
+#[account]
+pub struct AccountRestriction {
+    /// The restricted token account
+    pub token_account: Pubkey,
+    
+    /// Owner of the token account when it was restricted
+    pub owner: Pubkey,
+    
+    /// The update authority that placed the restriction
+    pub authority: Pubkey,
+    
+    /// Caller-defined code describing why the account was blocked
+    pub reason_code: u16,
+    
+    /// Timestamp the account was blocked
+    pub blocked_at: i64,
+    
+    /// Whether the balance was clawed back; the restriction can no longer be lifted
+    pub clawed_back: bool,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl AccountRestriction {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (token_account) + 32 (owner) + 32 (authority) + 2 (reason_code)
+    /// + 8 (blocked_at) + 1 (clawed_back) + 1 (bump) = 116 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 8 + 1 + 1;
+
+    /// How long an account must stay blocked before its balance can be clawed back (30 days)
+    pub const CLAWBACK_WAITING_PERIOD_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    /// Whether the waiting period has passed at `now`
+    pub fn clawback_allowed_at(&self, now: i64) -> bool {
+        now.saturating_sub(self.blocked_at) >= Self::CLAWBACK_WAITING_PERIOD_SECONDS
+    }
+}
+
+/// Audit record of a clawback from a restricted token account
+#[account]
+pub struct ClawbackRecord {
+    /// The restricted token account
+    pub token_account: Pubkey,
+    
+    /// Owner of the token account
+    pub owner: Pubkey,
+    
+    /// The restriction the clawback relied on
+    pub restriction: Pubkey,
+    
+    /// Treasury token account credited with the balance
+    pub treasury_token_account: Pubkey,
+    
+    /// The update authority that made the clawback
+    pub authority: Pubkey,
+    
+    /// The mint co-signer that approved it
+    pub cosigner: Pubkey,
+    
+    /// Balance clawed back
+    pub amount: u64,
+    
+    /// Reason code of the restriction
+    pub reason_code: u16,
+    
+    /// Timestamp the account was blocked
+    pub blocked_at: i64,
+    
+    /// Timestamp of the clawback
+    pub timestamp: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl ClawbackRecord {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (token_account) + 32 (owner) + 32 (restriction)
+    /// + 32 (treasury_token_account) + 32 (authority) + 32 (cosigner) + 8 (amount)
+    /// + 2 (reason_code) + 8 (blocked_at) + 8 (timestamp) + 1 (bump) = 226 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 32 + 8 + 2 + 8 + 8 + 1;
+}
+
+/// Extra cashback the protocol mints for purchases at every merchant in a category,
+/// on top of the merchant's own rate, between `start` and `end` and up to `budget`
+#[account]
+pub struct CategoryBoost {
+    /// The merchant category boosted, as stored on `MerchantAccount`
+    pub category: [u8; 16],
+    
+    /// Extra cashback rate (in basis points)
+    pub bonus_bps: u16,
+    
+    /// Timestamp the boost starts applying
+    pub start: i64,
+    
+    /// Timestamp the boost stops applying
+    pub end: i64,
+    
+    /// Most reward tokens the boost can mint in total
+    pub budget: u64,
+    
+    /// Reward tokens the boost has minted so far
+    pub spent: u64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl CategoryBoost {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 16 (category) + 2 (bonus_bps) + 8 (start) + 8 (end) + 8 (budget)
+    /// + 8 (spent) + 1 (bump) = 59 bytes
+    pub const LEN: usize = 8 + 16 + 2 + 8 + 8 + 8 + 8 + 1;
+
+    /// Whether the boost applies at `now`
+    pub fn is_live(&self, now: i64) -> bool {
+        self.start <= now && now < self.end
+    }
+
+    /// The part of a `reward` at the full boost rate the boost pays at `now`: nothing
+    /// outside its window, otherwise `reward` clamped to what is left of the budget
+    pub fn boost_reward(&self, reward: u64, now: i64) -> u64 {
+        if !self.is_live(now) {
+            return 0;
+        }
+        reward.min(self.budget.saturating_sub(self.spent))
+    }
+}
+
+/// Seeds for deriving the mint authority PDA
+pub const MINT_AUTHORITY_SEED: &[u8] = b"mint_authority";
+
+/// Seeds for deriving the config account PDA  
+pub const CONFIG_SEED: &[u8] = b"config";
+
+/// Seeds for deriving merchant account PDAs
+pub const MERCHANT_SEED: &[u8] = b"merchant";
+
+/// Seeds for deriving the merchant registry PDA
+pub const MERCHANT_REGISTRY_SEED: &[u8] = b"merchant_registry";
+
+/// Seeds for deriving merchant index PDAs
+pub const MERCHANT_INDEX_SEED: &[u8] = b"merchant_index";
+
+/// Seeds for deriving daily stats snapshot PDAs
+pub const SNAPSHOT_SEED: &[u8] = b"snapshot";
+
+/// Seeds for deriving customer monthly stats PDAs
+pub const CUSTOMER_MONTHLY_STATS_SEED: &[u8] = b"customer_monthly_stats";
+
+/// Seeds for deriving purchase transaction PDAs, followed by the merchant account, the
+/// customer and the transaction id (records created earlier omit the merchant)
+pub const TRANSACTION_SEED: &[u8] = b"transaction";
+
+/// Seeds for deriving token transfer PDAs
+pub const TRANSFER_SEED: &[u8] = b"transfer";
+
+/// Seeds for deriving token redemption PDAs
+pub const REDEMPTION_SEED: &[u8] = b"redemption";
+
+/// Seeds for deriving the PDA that owns the treasury token account
+pub const TREASURY_AUTHORITY_SEED: &[u8] = b"treasury_authority";
+
+/// Seeds for deriving treasury withdrawal record PDAs
+pub const TREASURY_WITHDRAWAL_SEED: &[u8] = b"treasury_withdrawal";
+
+/// Seeds for deriving transfer fee exemption PDAs
+pub const FEE_EXEMPTION_SEED: &[u8] = b"fee_exemption";
+
+/// Seeds for deriving activity record PDAs
+pub const ACTIVITY_SEED: &[u8] = b"activity";
+
+/// Seeds for deriving pending parameter change PDAs
+pub const PARAM_CHANGE_SEED: &[u8] = b"param_change";
+
+/// Seeds for deriving the token account that escrows expiring rewards
+pub const REWARD_ESCROW_SEED: &[u8] = b"reward_escrow";
+
+/// Seeds for deriving reward lot PDAs
+pub const REWARD_LOT_SEED: &[u8] = b"reward_lot";
+
+/// Seeds for deriving the token account that escrows vesting grants
+pub const VESTING_ESCROW_SEED: &[u8] = b"vesting_escrow";
+
+/// Seeds for deriving vesting grant PDAs
+pub const VESTING_GRANT_SEED: &[u8] = b"vesting_grant";
+
+/// Seeds for deriving invoice PDAs
+pub const INVOICE_SEED: &[u8] = b"invoice";
+
+/// Seeds for deriving the token account that escrows held tokens
+pub const HOLD_ESCROW_SEED: &[u8] = b"hold_escrow";
+
+/// Seeds for deriving hold PDAs
+pub const HOLD_SEED: &[u8] = b"hold";
+
+/// Seeds for deriving dispute PDAs
+pub const DISPUTE_SEED: &[u8] = b"dispute";
+
+/// Seeds for deriving settlement record PDAs
+pub const SETTLEMENT_SEED: &[u8] = b"settlement";
+
+/// Seeds for deriving the token account that escrows gift cards
+pub const GIFT_CARD_ESCROW_SEED: &[u8] = b"gift_card_escrow";
+
+/// Seeds for deriving gift card PDAs
+pub const GIFT_CARD_SEED: &[u8] = b"gift_card";
+
+/// Seeds for deriving subscription PDAs
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+
+/// Seeds for deriving airdrop PDAs
+pub const AIRDROP_SEED: &[u8] = b"airdrop";
+
+/// Seeds for deriving airdrop claim PDAs
+pub const AIRDROP_CLAIM_SEED: &[u8] = b"airdrop_claim";
+
+/// Seeds for deriving the token account that escrows session key allowances
+pub const SESSION_ESCROW_SEED: &[u8] = b"session_escrow";
+
+/// Seeds for deriving session key PDAs
+pub const SESSION_KEY_SEED: &[u8] = b"session_key";
+
+/// Seeds for deriving POS key PDAs
+pub const POS_KEY_SEED: &[u8] = b"pos_key";
+
+/// Seeds for deriving merchant operator PDAs
+pub const MERCHANT_OPERATOR_SEED: &[u8] = b"merchant_operator";
+
+/// Seeds for deriving franchise parent merchant PDAs
+pub const PARENT_MERCHANT_SEED: &[u8] = b"parent_merchant";
+
+/// Seeds for deriving the token account that escrows sponsored reward budgets
+pub const REWARD_BUDGET_ESCROW_SEED: &[u8] = b"reward_budget_escrow";
+
+/// Seeds for deriving reward budget PDAs
+pub const REWARD_BUDGET_SEED: &[u8] = b"reward_budget";
+
+/// Seeds for deriving reward budget membership PDAs
+pub const BUDGET_MEMBERSHIP_SEED: &[u8] = b"budget_membership";
+
+/// Seeds for deriving account restriction PDAs
+pub const ACCOUNT_RESTRICTION_SEED: &[u8] = b"account_restriction";
+
+/// Seeds for deriving clawback record PDAs
+pub const CLAWBACK_RECORD_SEED: &[u8] = b"clawback_record";
+
+/// Seeds for deriving category boost PDAs
+pub const CATEGORY_BOOST_SEED: &[u8] = b"category_boost";
+
+/// Seeds for deriving customer-merchant visit stats PDAs
+pub const CUSTOMER_MERCHANT_STATS_SEED: &[u8] = b"cm_stats";
+
+/// Seeds for deriving transaction stub PDAs
+pub const TRANSACTION_STUB_SEED: &[u8] = b"tx_stub";
+
+/// Seeds for deriving purchase reference index PDAs
+pub const REFERENCE_INDEX_SEED: &[u8] = b"reference";
+
+// ============================================================================
+// Voucher Pool State Structures for Non-Custodial Staking
+// ============================================================================
+
+/// Configuration parameters for the voucher staking pool
+/// Stored inline in the zero-copy `PoolState`, so it is packed like it; the Borsh impls
+/// below encode the same bytes for instruction arguments
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Debug)]
+pub struct PoolConfig {
+    /// Minimum amount required to stake
+    pub min_stake_amount: u64,
+    
+    /// Maximum amount that can be staked per user
+    pub max_stake_per_user: u64,
+    
+    /// Whether the pool accepts new deposits
+    pub deposits_enabled: bool,
+    
+    /// Whether users can withdraw/redeem
+    pub withdrawals_enabled: bool,
+    
+    /// Guaranteed minimum annual yield (in basis points, e.g., 1200 = 12%)
+    /// Accrued over time by `accrue_minimum_yield`, up to `guaranteed_yield_budget`
+    pub apy_basis_points: u16,
+    
+    /// Whether deposits revoke any approval left over after the transfer
+    /// Revoking needs the token account owner, so deposits must be co-signed by the user
+    pub auto_revoke_delegation: bool,
+    
+    /// How long each deposit locks the user's stake (0 = no lockup)
+    pub lockup_seconds: u32,
+    
+    /// Penalty on withdrawals before the lock expires (in basis points)
+    /// Zero rejects early withdrawals outright instead of penalizing them
+    pub early_withdraw_penalty_bps: u16,
+    
+    /// Maximum share of the staked principal the delegate may deploy (in basis points)
+    pub max_deployment_bps: u16,
+    
+    /// Maximum total vouchers the pool accepts (0 = unlimited)
+    pub max_total_staked: u64,
+    
+    /// Stake sizes at which each yield tier starts (0 = tier disabled)
+    /// Enabled tiers must come first and be strictly increasing
+    pub tier_thresholds: [u64; 3],
+    
+    /// Yield weight of each tier (in basis points, 10000 = 1x)
+    /// Stakes below the first threshold are weighted 1x; tier changes apply to
+    /// each user from their next deposit or withdrawal
+    pub tier_multipliers_bps: [u16; 3],
+    
+    /// Minimum time between a user's last deposit and their next withdrawal (0 = none)
+    pub withdraw_cooldown_seconds: u32,
+    
+    /// Whether only wallets with a `StakeWhitelistEntry` may deposit
+    pub whitelist_enabled: bool,
+    
+    /// Share of each recorded yield taken as a protocol fee (in basis points)
+    pub performance_fee_bps: u16,
+    
+    /// Wallet entitled to the accrued performance fees
+    pub fee_recipient: Pubkey,
+    
+    /// Total guaranteed minimum yield the pool may owe (0 = no minimum accrual)
+    pub guaranteed_yield_budget: u64,
+    
+    /// Whether withdrawals must go through `request_unstake`/`process_unstake`
+    /// Direct `redeem_voucher` is rejected while this is on
+    pub queued_withdrawals: bool,
+    
+    /// Time between an unstake request and when it can be processed
+    pub unstake_delay_seconds: u32,
+    
+    /// Whether deposits and withdrawals write a `StakeActionRecord` audit entry
+    /// Each entry costs rent, so this is off unless explicitly enabled
+    pub record_history: bool,
+    
+    /// Share of each deposit kept as insurance against deployment losses (in basis points)
+    /// The tokens stay in the vault; the depositor is credited with the remainder
+    pub insurance_fee_bps: u16,
+}
+
+// Packed fields cannot be borrowed, which the Borsh derives do, so these copy them out
+// and encode them in declaration order exactly as the derives would
+impl AnchorSerialize for PoolConfig {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let PoolConfig {
+            min_stake_amount,
+            max_stake_per_user,
+            deposits_enabled,
+            withdrawals_enabled,
+            apy_basis_points,
+            auto_revoke_delegation,
+            lockup_seconds,
+            early_withdraw_penalty_bps,
+            max_deployment_bps,
+            max_total_staked,
+            tier_thresholds,
+            tier_multipliers_bps,
+            withdraw_cooldown_seconds,
+            whitelist_enabled,
+            performance_fee_bps,
+            fee_recipient,
+            guaranteed_yield_budget,
+            queued_withdrawals,
+            unstake_delay_seconds,
+            record_history,
+            insurance_fee_bps,
+        } = *self;
+        min_stake_amount.serialize(writer)?;
+        max_stake_per_user.serialize(writer)?;
+        deposits_enabled.serialize(writer)?;
+        withdrawals_enabled.serialize(writer)?;
+        apy_basis_points.serialize(writer)?;
+        auto_revoke_delegation.serialize(writer)?;
+        lockup_seconds.serialize(writer)?;
+        early_withdraw_penalty_bps.serialize(writer)?;
+        max_deployment_bps.serialize(writer)?;
+        max_total_staked.serialize(writer)?;
+        tier_thresholds.serialize(writer)?;
+        tier_multipliers_bps.serialize(writer)?;
+        withdraw_cooldown_seconds.serialize(writer)?;
+        whitelist_enabled.serialize(writer)?;
+        performance_fee_bps.serialize(writer)?;
+        fee_recipient.serialize(writer)?;
+        guaranteed_yield_budget.serialize(writer)?;
+        queued_withdrawals.serialize(writer)?;
+        unstake_delay_seconds.serialize(writer)?;
+        record_history.serialize(writer)?;
+        insurance_fee_bps.serialize(writer)?;
+        Ok(())
+    }
+}
+
+impl AnchorDeserialize for PoolConfig {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(PoolConfig {
+            min_stake_amount: AnchorDeserialize::deserialize_reader(reader)?,
+            max_stake_per_user: AnchorDeserialize::deserialize_reader(reader)?,
+            deposits_enabled: AnchorDeserialize::deserialize_reader(reader)?,
+            withdrawals_enabled: AnchorDeserialize::deserialize_reader(reader)?,
+            apy_basis_points: AnchorDeserialize::deserialize_reader(reader)?,
+            auto_revoke_delegation: AnchorDeserialize::deserialize_reader(reader)?,
+            lockup_seconds: AnchorDeserialize::deserialize_reader(reader)?,
+            early_withdraw_penalty_bps: AnchorDeserialize::deserialize_reader(reader)?,
+            max_deployment_bps: AnchorDeserialize::deserialize_reader(reader)?,
+            max_total_staked: AnchorDeserialize::deserialize_reader(reader)?,
+            tier_thresholds: AnchorDeserialize::deserialize_reader(reader)?,
+            tier_multipliers_bps: AnchorDeserialize::deserialize_reader(reader)?,
+            withdraw_cooldown_seconds: AnchorDeserialize::deserialize_reader(reader)?,
+            whitelist_enabled: AnchorDeserialize::deserialize_reader(reader)?,
+            performance_fee_bps: AnchorDeserialize::deserialize_reader(reader)?,
+            fee_recipient: AnchorDeserialize::deserialize_reader(reader)?,
+            guaranteed_yield_budget: AnchorDeserialize::deserialize_reader(reader)?,
+            queued_withdrawals: AnchorDeserialize::deserialize_reader(reader)?,
+            unstake_delay_seconds: AnchorDeserialize::deserialize_reader(reader)?,
+            record_history: AnchorDeserialize::deserialize_reader(reader)?,
+            insurance_fee_bps: AnchorDeserialize::deserialize_reader(reader)?,
+        })
+    }
+}
+
+// SAFETY: `repr(C, packed)` leaves no padding. The `bool` fields are only ever written
+// by Borsh decoding, which rejects anything but 0 and 1, or by the program itself
+unsafe impl anchor_lang::__private::bytemuck::Pod for PoolConfig {}
+unsafe impl anchor_lang::__private::bytemuck::Zeroable for PoolConfig {}
+
+impl PoolConfig {
+    /// Returns a copy of this config with the provided fields of `update` applied
+    pub fn merge(&self, update: &PoolConfigUpdate) -> PoolConfig {
+        PoolConfig {
+            min_stake_amount: update.min_stake_amount.unwrap_or(self.min_stake_amount),
+            max_stake_per_user: update.max_stake_per_user.unwrap_or(self.max_stake_per_user),
+            deposits_enabled: update.deposits_enabled.unwrap_or(self.deposits_enabled),
+            withdrawals_enabled: update.withdrawals_enabled.unwrap_or(self.withdrawals_enabled),
+            apy_basis_points: update.apy_basis_points.unwrap_or(self.apy_basis_points),
+            auto_revoke_delegation: update
+                .auto_revoke_delegation
+                .unwrap_or(self.auto_revoke_delegation),
+            lockup_seconds: update.lockup_seconds.unwrap_or(self.lockup_seconds),
+            early_withdraw_penalty_bps: update
+                .early_withdraw_penalty_bps
+                .unwrap_or(self.early_withdraw_penalty_bps),
+            max_deployment_bps: update.max_deployment_bps.unwrap_or(self.max_deployment_bps),
+            max_total_staked: update.max_total_staked.unwrap_or(self.max_total_staked),
+            tier_thresholds: update.tier_thresholds.unwrap_or(self.tier_thresholds),
+            tier_multipliers_bps: update
+                .tier_multipliers_bps
+                .unwrap_or(self.tier_multipliers_bps),
+            withdraw_cooldown_seconds: update
+                .withdraw_cooldown_seconds
+                .unwrap_or(self.withdraw_cooldown_seconds),
+            whitelist_enabled: update.whitelist_enabled.unwrap_or(self.whitelist_enabled),
+            performance_fee_bps: update.performance_fee_bps.unwrap_or(self.performance_fee_bps),
+            fee_recipient: update.fee_recipient.unwrap_or(self.fee_recipient),
+            guaranteed_yield_budget: update
+                .guaranteed_yield_budget
+                .unwrap_or(self.guaranteed_yield_budget),
+            queued_withdrawals: update.queued_withdrawals.unwrap_or(self.queued_withdrawals),
+            unstake_delay_seconds: update
+                .unstake_delay_seconds
+                .unwrap_or(self.unstake_delay_seconds),
+            record_history: update.record_history.unwrap_or(self.record_history),
+            insurance_fee_bps: update.insurance_fee_bps.unwrap_or(self.insurance_fee_bps),
+        }
+    }
+
+    /// An update that sets every field to this config's value
+    pub fn as_update(&self) -> PoolConfigUpdate {
+        PoolConfigUpdate {
+            min_stake_amount: Some(self.min_stake_amount),
+            max_stake_per_user: Some(self.max_stake_per_user),
+            deposits_enabled: Some(self.deposits_enabled),
+            withdrawals_enabled: Some(self.withdrawals_enabled),
+            apy_basis_points: Some(self.apy_basis_points),
+            auto_revoke_delegation: Some(self.auto_revoke_delegation),
+            lockup_seconds: Some(self.lockup_seconds),
+            early_withdraw_penalty_bps: Some(self.early_withdraw_penalty_bps),
+            max_deployment_bps: Some(self.max_deployment_bps),
+            max_total_staked: Some(self.max_total_staked),
+            tier_thresholds: Some(self.tier_thresholds),
+            tier_multipliers_bps: Some(self.tier_multipliers_bps),
+            withdraw_cooldown_seconds: Some(self.withdraw_cooldown_seconds),
+            whitelist_enabled: Some(self.whitelist_enabled),
+            performance_fee_bps: Some(self.performance_fee_bps),
+            fee_recipient: Some(self.fee_recipient),
+            guaranteed_yield_budget: Some(self.guaranteed_yield_budget),
+            queued_withdrawals: Some(self.queued_withdrawals),
+            unstake_delay_seconds: Some(self.unstake_delay_seconds),
+            record_history: Some(self.record_history),
+            insurance_fee_bps: Some(self.insurance_fee_bps),
+        }
+    }
+
+    /// Whether the performance fee is well formed
+    /// A fee needs somewhere to go, so a non-zero fee requires a recipient
+    pub fn fee_valid(&self) -> bool {
+        self.performance_fee_bps as u128 <= BASIS_POINTS
+            && (self.performance_fee_bps == 0 || self.fee_recipient != Pubkey::default())
+    }
+
+    /// Performance fee taken from `yield_amount`, rounded down in the stakers' favour
+    pub fn performance_fee(&self, yield_amount: u64) -> Option<u64> {
+        let fee = (yield_amount as u128)
+            .checked_mul(self.performance_fee_bps as u128)?
+            .checked_div(BASIS_POINTS)?;
+        u64::try_from(fee).ok()
+    }
+
+    /// Minimum yield owed on `total_staked` over `elapsed_seconds` at the configured APY
+    /// Rounds down, so repeated accruals never owe more than one accrual over the whole span
+    pub fn minimum_yield(&self, total_staked: u64, elapsed_seconds: u64) -> Option<u64> {
+        let owed = (total_staked as u128)
+            .checked_mul(self.apy_basis_points as u128)?
+            .checked_mul(elapsed_seconds as u128)?
+            .checked_div(BASIS_POINTS.checked_mul(SECONDS_PER_YEAR)?)?;
+        u64::try_from(owed).ok()
+    }
+
+    /// Insurance slice of a `deposit_amount`, rounded down in the depositor's favour
+    pub fn insurance_fee(&self, deposit_amount: u64) -> Option<u64> {
+        let fee = (deposit_amount as u128)
+            .checked_mul(self.insurance_fee_bps as u128)?
+            .checked_div(BASIS_POINTS)?;
+        u64::try_from(fee).ok()
+    }
+
+    /// Whether the yield tiers are well formed
+    pub fn tiers_valid(&self) -> bool {
+        let mut previous_threshold = 0;
+        let mut tier_disabled = false;
+
+        for (threshold, multiplier) in self.tier_thresholds.into_iter().zip(self.tier_multipliers_bps) {
+            if threshold == 0 {
+                tier_disabled = true;
+                continue;
+            }
+            if tier_disabled || threshold <= previous_threshold || multiplier == 0 {
+                return false;
+            }
+            previous_threshold = threshold;
+        }
+
+        true
+    }
+
+    /// Yield weight of a stake, scaled by the multiplier of the highest tier it reaches
+    pub fn stake_weight(&self, staked_amount: u64) -> Option<u64> {
+        let mut multiplier_bps = BASIS_POINTS;
+        for (threshold, multiplier) in self.tier_thresholds.into_iter().zip(self.tier_multipliers_bps) {
+            if threshold > 0 && staked_amount >= threshold {
+                multiplier_bps = multiplier as u128;
+            }
+        }
+
+        let weight = (staked_amount as u128)
+            .checked_mul(multiplier_bps)?
+            .checked_div(BASIS_POINTS)?;
+        u64::try_from(weight).ok()
+    }
+}
+
+/// Partial update to the pool configuration
+/// Fields left as `None` keep their current value
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct PoolConfigUpdate {
+    pub min_stake_amount: Option<u64>,
+    pub max_stake_per_user: Option<u64>,
+    pub deposits_enabled: Option<bool>,
+    pub withdrawals_enabled: Option<bool>,
+    pub apy_basis_points: Option<u16>,
+    pub auto_revoke_delegation: Option<bool>,
+    pub lockup_seconds: Option<u32>,
+    pub early_withdraw_penalty_bps: Option<u16>,
+    pub max_deployment_bps: Option<u16>,
+    pub max_total_staked: Option<u64>,
+    pub tier_thresholds: Option<[u64; 3]>,
+    pub tier_multipliers_bps: Option<[u16; 3]>,
+    pub withdraw_cooldown_seconds: Option<u32>,
+    pub whitelist_enabled: Option<bool>,
+    pub performance_fee_bps: Option<u16>,
+    pub fee_recipient: Option<Pubkey>,
+    pub guaranteed_yield_budget: Option<u64>,
+    pub queued_withdrawals: Option<bool>,
+    pub unstake_delay_seconds: Option<u32>,
+    pub record_history: Option<bool>,
+    pub insurance_fee_bps: Option<u16>,
+}
+
+impl PoolConfigUpdate {
+    /// Serialized size with every field set
+    /// 4 * 9 (u64 options) + 6 * 2 (bool options) + 5 * 3 (u16 options) + 3 * 5 (u32 options)
+    /// + 25 (tier_thresholds) + 7 (tier_multipliers_bps) + 33 (fee_recipient) = 143 bytes
+    pub const MAX_LEN: usize = 4 * 9 + 6 * 2 + 5 * 3 + 3 * 5 + 25 + 7 + 33;
+}
+
+/// Main pool state account for voucher staking
+/// Tracks overall pool metrics and configuration
+/// Zero-copy, so instructions read and write fields in place instead of decoding the
+/// whole account. Packed to keep the Borsh layout of pools created before the switch;
+/// `unsafe` because `bool` is not `Pod`, though the program only ever stores 0 or 1
+#[account(zero_copy(unsafe))]
+#[repr(C, packed)]
+pub struct PoolState {
+    /// The authority that can manage the pool (admin)
+    pub pool_authority: Pubkey,
+    
+    /// The delegate authority that can execute deposits on behalf of users
+    pub pool_delegate: Pubkey,
+    
+    /// The vault token account that holds staked voucher tokens
+    pub vault_ata: Pubkey,
+    
+    /// The mint address of the voucher token (LOKAL token)
+    pub voucher_mint: Pubkey,
+    
+    /// Pool configuration parameters
+    pub config: PoolConfig,
+    
+    /// Total amount of voucher tokens currently staked in the pool
+    pub total_voucher_staked: u64,
+    
+    /// Total SOL/WSOL staked (after swaps)
+    pub total_sol_staked: u64,
+    
+    /// Total yield earned (in SOL/WSOL)
+    pub total_yield_earned: u64,
+    
+    /// Number of unique stakers
+    pub total_stakers: u64,
+    
+    /// Reward index for calculating proportional yields
+    pub reward_index: u128,
+    
+    /// Timestamp when pool was created
+    pub created_at: i64,
+    
+    /// Timestamp of last yield update
+    pub last_yield_update: i64,
+    
+    /// Identifier of this pool, part of its PDA seeds (see `pool_id_seed`)
+    pub pool_id: u16,
+    
+    /// Voucher tokens forfeited through early-withdrawal penalties
+    /// These tokens remain in the vault
+    pub penalty_reserve: u64,
+    
+    /// Token account receiving vouchers withdrawn for deployment (set by the pool authority)
+    pub deployment_destination: Pubkey,
+    
+    /// Voucher tokens currently withdrawn from the vault for deployment
+    pub deployed_amount: u64,
+    
+    /// Key that can pause (but not unpause) the pool in an emergency
+    pub guardian: Pubkey,
+    
+    /// Emergency pause on deposits, independent of `config.deposits_enabled`
+    pub deposits_paused: bool,
+    
+    /// Emergency pause on withdrawals, independent of `config.withdrawals_enabled`
+    pub withdrawals_paused: bool,
+    
+    /// Scaled yield left over from the last reward index update
+    /// Carried into the next `record_yield` so rounding never loses yield
+    pub dust_carry: u128,
+    
+    /// Total losses recorded against deployed capital
+    pub cumulative_losses: u64,
+    
+    /// Cumulative loss per staked token (scaled like `reward_index`)
+    /// Offsets the reward index when computing what users can claim
+    pub loss_index: u128,
+    
+    /// Sum of all users' tier-weighted stakes
+    /// Yield and losses are distributed against this instead of `total_voucher_staked`
+    pub weighted_total_staked: u128,
+    
+    /// The bump seed for this pool state PDA
+    pub bump: u8,
+    
+    /// Authority nominated by `propose_pool_authority`, awaiting acceptance
+    pub pending_pool_authority: Pubkey,
+    
+    /// The sLOKAL receipt mint, minted 1:1 on deposit and burned on redemption
+    pub receipt_mint: Pubkey,
+    
+    /// Performance fees taken from recorded yield and not yet collected
+    pub protocol_fees_accrued: u64,
+    
+    /// Guaranteed minimum yield added to the reward index by `accrue_minimum_yield`
+    /// A liability of the pool authority, capped by `config.guaranteed_yield_budget`
+    pub guaranteed_yield_owed: u64,
+    
+    /// Reward index seen by the last passing `verify_pool_invariants`
+    /// The reward index must never fall below it
+    pub last_verified_index: u128,
+    
+    /// Vouchers requested for unstaking and still waiting in the vault
+    pub queued_unstake_amount: u64,
+    
+    /// Deposit fees held in the vault as insurance against deployment losses
+    pub insurance_reserve: u64,
+    
+    /// Total insurance drawn so far; never exceeds `cumulative_losses`
+    pub insurance_drawn: u64,
+    
+    /// Sequence number of the last event emitted for this pool; indexers use it to detect gaps
+    pub event_sequence: u64,
+    
+    /// Share of a stake its owner may borrow with `borrow_against_stake` (in basis points,
+    /// 0 = advances disabled)
+    pub advance_ltv_bps: u16,
+    
+    /// Lokal tokens advanced against stakes in this pool and not yet repaid
+    pub total_advanced: u64,
+    
+    /// Position in the backend's yield journal of the last `record_yield`
+    /// Each call must pass the next position, so a replayed journal is rejected
+    pub last_external_sequence: u64,
+    
+    /// Whether the pool's PDAs are seeded by its voucher mint (see `PoolState::mint_seed`)
+    /// False for pools created before, which keep the addresses of their `pool_id` alone
+    pub mint_keyed: bool,
+    
+    /// Reserved space for future upgrades (13 bytes)
+    pub reserved: [u8; 13],
+}
+
+impl PoolState {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool_authority) + 32 (pool_delegate) + 32 (vault_ata)
+    /// + 32 (voucher_mint)
+    /// + (8 + 8 + 1 + 1 + 2 + 1 + 4 + 2 + 2 + 8 + 24 + 6 + 4 + 1 + 2 + 32 + 8 + 1 + 4 + 1 + 2) PoolConfig
+    /// + 8 (total_voucher_staked) + 8 (total_sol_staked) + 8 (total_yield_earned)
+    /// + 8 (total_stakers) + 16 (reward_index) + 8 (created_at) + 8 (last_yield_update)
+    /// + 2 (pool_id) + 8 (penalty_reserve) + 32 (deployment_destination)
+    /// + 8 (deployed_amount) + 32 (guardian) + 1 (deposits_paused) + 1 (withdrawals_paused)
+    /// + 16 (dust_carry) + 8 (cumulative_losses) + 16 (loss_index)
+    /// + 16 (weighted_total_staked) + 1 (bump) + 32 (pending_pool_authority)
+    /// + 32 (receipt_mint) + 8 (protocol_fees_accrued) + 8 (guaranteed_yield_owed)
+    /// + 16 (last_verified_index) + 8 (queued_unstake_amount) + 8 (insurance_reserve)
+    /// + 8 (insurance_drawn) + 8 (event_sequence) + 2 (advance_ltv_bps) + 8 (total_advanced)
+    /// + 8 (last_external_sequence) + 1 (mint_keyed) + 13 (reserved) = 623 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 122 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 2 + 8 + 32 + 8
+        + 32 + 1 + 1 + 16 + 8 + 16 + 16 + 1 + 32 + 32 + 8 + 8 + 16 + 8 + 8 + 8 + 8 + 2 + 8 + 8 + 1
+        + 13;
+
+    /// Advance the event sequence and return the number for the next event
+    pub fn next_event_sequence(&mut self) -> Option<u64> {
+        self.event_sequence = self.event_sequence.checked_add(1)?;
+        Some(self.event_sequence)
+    }
+
+    /// Voucher mint part of this pool's PDA seeds, next to `pool_id_seed(pool_id)`
+    /// Empty for pools created before PDAs were keyed by mint, so they keep their addresses
+    pub fn mint_seed(&self) -> Vec<u8> {
+        if self.mint_keyed {
+            self.voucher_mint.to_bytes().to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Swap a user's old tier-weighted stake for their new one in the weighted total
+    pub fn reweight(&mut self, old_weight: u64, new_weight: u64) -> Option<()> {
+        self.weighted_total_staked = self
+            .weighted_total_staked
+            .checked_sub(old_weight as u128)?
+            .checked_add(new_weight as u128)?;
+        Some(())
+    }
+
+    /// Part of a vault balance of `vault_amount` that redemptions may pay out: the
+    /// insurance and penalty reserves share the vault but only leave through their own
+    /// instructions. `None` when the vault holds less than the reserves
+    pub fn redeemable_balance(&self, vault_amount: u64) -> Option<u64> {
+        vault_amount
+            .checked_sub(self.insurance_reserve)?
+            .checked_sub(self.penalty_reserve)
+    }
+}
+
+const _: () = assert!(std::mem::size_of::<PoolConfig>() == 122);
+const _: () = assert!(std::mem::size_of::<PoolState>() == PoolState::LEN - 8);
+
+/// `PoolConfig` as stored by pools created before the config grew
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LegacyPoolConfig {
+    pub min_stake_amount: u64,
+    pub max_stake_per_user: u64,
+    pub deposits_enabled: bool,
+    pub withdrawals_enabled: bool,
+    pub apy_basis_points: u16,
+}
+
+/// `PoolState` as stored by the original singleton pool, before multi-pool support
+/// `PoolState` cannot load these accounts; `migrate_pool` rewrites them in place
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LegacyPoolState {
+    pub pool_authority: Pubkey,
+    pub pool_delegate: Pubkey,
+    pub vault_ata: Pubkey,
+    pub voucher_mint: Pubkey,
+    pub config: LegacyPoolConfig,
+    pub total_voucher_staked: u64,
+    pub total_sol_staked: u64,
+    pub total_yield_earned: u64,
+    pub total_stakers: u64,
+    pub reward_index: u128,
+    pub created_at: i64,
+    pub last_yield_update: i64,
+    pub bump: u8,
+    pub reserved: [u8; 64],
+}
+
+impl LegacyPoolState {
+    /// 8 (discriminator) + 4 * 32 (keys) + 20 (config) + 4 * 8 (totals) + 16 (reward_index)
+    /// + 8 (created_at) + 8 (last_yield_update) + 1 (bump) + 64 (reserved) = 285 bytes
+    pub const LEN: usize = 8 + 4 * 32 + 20 + 4 * 8 + 16 + 8 + 8 + 1 + 64;
+
+    /// Decode a pool account still in the legacy layout, `None` if it is not one
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN || data[..8] != *PoolState::DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The pool in the current layout, with every new field at its disabled default
+    /// Legacy stakes all weigh 1x, so the weighted total starts at the staked total
+    /// `None` if the reward index overflows when rescaled
+    pub fn migrate(&self, receipt_mint: Pubkey) -> Option<PoolState> {
+        let mut pool: PoolState = anchor_lang::__private::bytemuck::Zeroable::zeroed();
+        pool.pool_authority = self.pool_authority;
+        pool.pool_delegate = self.pool_delegate;
+        pool.vault_ata = self.vault_ata;
+        pool.voucher_mint = self.voucher_mint;
+        pool.config.min_stake_amount = self.config.min_stake_amount;
+        pool.config.max_stake_per_user = self.config.max_stake_per_user;
+        pool.config.deposits_enabled = self.config.deposits_enabled;
+        pool.config.withdrawals_enabled = self.config.withdrawals_enabled;
+        pool.config.apy_basis_points = self.config.apy_basis_points;
+        pool.total_voucher_staked = self.total_voucher_staked;
+        pool.total_sol_staked = self.total_sol_staked;
+        pool.total_yield_earned = self.total_yield_earned;
+        pool.total_stakers = self.total_stakers;
+        pool.reward_index = rescale_legacy_index(self.reward_index)?;
+        pool.created_at = self.created_at;
+        pool.last_yield_update = self.last_yield_update;
+        pool.weighted_total_staked = self.total_voucher_staked as u128;
+        pool.bump = self.bump;
+        pool.receipt_mint = receipt_mint;
+        Some(pool)
+    }
+}
+
+/// Individual user stake record
+/// Tracks each user's staking position and rewards
+#[account]
+pub struct UserStakeRecord {
+    /// The user who owns this stake
+    pub user: Pubkey,
+    
+    /// The pool this stake belongs to
+    pub pool: Pubkey,
+    
+    /// Amount of voucher tokens staked by this user
+    pub staked_amount: u64,
+    
+    /// User's reward index snapshot (for yield calculations)
+    pub user_reward_index: u128,
+    
+    /// Total yield claimed by this user
+    pub total_yield_claimed: u64,
+    
+    /// Timestamp when user first staked
+    pub staked_at: i64,
+    
+    /// Timestamp of last stake/unstake action
+    /// Withdrawal cooldowns run from `last_deposit_at` instead, so back-to-back
+    /// withdrawals are not throttled
+    pub last_action_at: i64,
+    
+    /// The bump seed for this user stake record PDA
+    pub bump: u8,
+    
+    /// Timestamp until which the stake is locked (extended by each deposit)
+    pub lock_until: i64,
+    
+    /// User's loss index snapshot (offsets yield accrued since the reward snapshot)
+    pub user_loss_index: u128,
+    
+    /// Tier-weighted stake this user contributes to `weighted_total_staked`
+    pub weighted_stake: u64,
+    
+    /// Yield settled at a checkpoint but not yet claimed
+    pub pending_payout: u64,
+    
+    /// Timestamp of the user's last deposit
+    pub last_deposit_at: i64,
+    
+    /// Whether the delegate may restake this user's yield via `compound_user`
+    pub auto_compound: bool,
+    
+    /// Rate used by the most recent compound (lamports per `COMPOUND_RATE_SCALE` vouchers)
+    pub last_compound_rate: u64,
+    
+    /// Total vouchers credited to this stake by compounding
+    pub total_compounded: u64,
+    
+    /// Number of `StakeActionRecord` history entries written for this stake
+    /// Also the index of the next entry, which is part of its PDA seeds
+    pub action_count: u64,
+    
+    /// Yield `redeem_voucher` would pay as of `view_synced_at`
+    /// A cache for clients; yield recorded since the last sync is not included
+    pub pending_yield: u64,
+    
+    /// Share of the pool's tier-weighted stake as of `view_synced_at`, in basis points
+    pub pool_share_bps: u16,
+    
+    /// Timestamp `pending_yield` and `pool_share_bps` were last refreshed
+    pub view_synced_at: i64,
+    
+    /// Who paid the record's rent at initialization, and so receives it back on close
+    pub rent_payer: Pubkey,
+    
+    /// Wallet behind the latest `deposit_for_beneficiary` into this stake, if any
+    /// Recorded for transparency only; it gives the funder no rights over the stake
+    pub funded_by: Pubkey,
+    
+    /// Part of `staked_amount` held as collateral for the user's `TokenAdvance`, which
+    /// cannot be withdrawn until the advance is repaid
+    pub locked_amount: u64,
+}
+
+impl UserStakeRecord {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (user) + 32 (pool) + 8 (staked_amount)
+    /// + 16 (user_reward_index) + 8 (total_yield_claimed) + 8 (staked_at)
+    /// + 8 (last_action_at) + 1 (bump) + 8 (lock_until) + 16 (user_loss_index)
+    /// + 8 (weighted_stake) + 8 (pending_payout) + 8 (last_deposit_at)
+    /// + 1 (auto_compound) + 8 (last_compound_rate) + 8 (total_compounded)
+    /// + 8 (action_count) + 8 (pending_yield) + 2 (pool_share_bps)
+    /// + 8 (view_synced_at) + 32 (rent_payer) + 32 (funded_by) + 8 (locked_amount) = 284 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 16 + 8 + 8 + 8 + 1 + 8 + 16 + 8 + 8 + 8 + 1 + 8
+        + 8 + 8 + 8 + 2 + 8 + 32 + 32 + 8;
+
+    /// Yield accrued since the last checkpoint at the given pool indices
+    /// Losses can wipe out accrued yield but never push it below zero, and a
+    /// snapshot ahead of the pool earns nothing rather than erroring
+    pub fn accrued_since_checkpoint(&self, reward_index: u128, loss_index: u128) -> Option<u64> {
+        let reward_index_diff = reward_index.saturating_sub(self.user_reward_index);
+        let loss_index_diff = loss_index.saturating_sub(self.user_loss_index);
+
+        calculate_claimable_yield(
+            self.weighted_stake,
+            reward_index_diff.saturating_sub(loss_index_diff),
+        )
+        .ok()
+    }
+
+    /// Part of the stake the user may withdraw, outside any advance's collateral
+    pub fn unlocked_amount(&self) -> u64 {
+        self.staked_amount.saturating_sub(self.locked_amount)
+    }
+
+    /// Total yield the user can claim: settled payout plus yield accrued since
+    pub fn claimable_yield(&self, reward_index: u128, loss_index: u128) -> Option<u64> {
+        self.pending_payout
+            .checked_add(self.accrued_since_checkpoint(reward_index, loss_index)?)
+    }
+
+    /// Settle accrued yield into `pending_payout` and move the snapshots to the given indices
+    /// Must run before the user's weight changes so past yield keeps its old weight
+    pub fn checkpoint(&mut self, reward_index: u128, loss_index: u128) -> Option<()> {
+        self.pending_payout = self.claimable_yield(reward_index, loss_index)?;
+        self.user_reward_index = reward_index;
+        self.user_loss_index = loss_index;
+        Some(())
+    }
+
+    /// Refresh the cached `pending_yield` and `pool_share_bps` at the given pool values
+    /// Leaves the snapshots alone, so it is safe to call at any point of a handler
+    pub fn sync_view(
+        &mut self,
+        reward_index: u128,
+        loss_index: u128,
+        weighted_total_staked: u128,
+        now: i64,
+    ) -> Option<()> {
+        let pool_share_bps = if weighted_total_staked == 0 {
+            0
+        } else {
+            (self.weighted_stake as u128)
+                .checked_mul(BASIS_POINTS)?
+                .checked_div(weighted_total_staked)?
+        };
+
+        self.pending_yield = self.claimable_yield(reward_index, loss_index)?;
+        self.pool_share_bps = u16::try_from(pool_share_bps).ok()?;
+        self.view_synced_at = now;
+        Some(())
+    }
+}
+
+/// `UserStakeRecord` as stored by stakes in the original singleton pool
+/// `UserStakeRecord` cannot decode these accounts; `migrate_stake_record` rewrites them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LegacyUserStakeRecord {
+    pub user: Pubkey,
+    pub pool: Pubkey,
+    pub staked_amount: u64,
+    pub user_reward_index: u128,
+    pub total_yield_claimed: u64,
+    pub staked_at: i64,
+    pub last_action_at: i64,
+    pub bump: u8,
+    pub reserved: [u8; 32],
+}
+
+impl LegacyUserStakeRecord {
+    /// 8 (discriminator) + 32 (user) + 32 (pool) + 8 (staked_amount) + 16 (user_reward_index)
+    /// + 8 (total_yield_claimed) + 8 (staked_at) + 8 (last_action_at) + 1 (bump)
+    /// + 32 (reserved) = 153 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 16 + 8 + 8 + 8 + 1 + 32;
+
+    /// Decode a stake record still in the legacy layout, `None` if it is not one
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN || data[..8] != *UserStakeRecord::DISCRIMINATOR {
+            return None;
+        }
+        Self::deserialize(&mut &data[8..]).ok()
+    }
+
+    /// The record in the current layout, weighted 1x and unlocked like every legacy stake
+    /// Legacy records were all paid for by the pool delegate, passed as `rent_payer`
+    /// `None` if the reward index snapshot overflows when rescaled
+    pub fn migrate(&self, rent_payer: Pubkey) -> Option<UserStakeRecord> {
+        Some(UserStakeRecord {
+            user: self.user,
+            pool: self.pool,
+            staked_amount: self.staked_amount,
+            user_reward_index: rescale_legacy_index(self.user_reward_index)?,
+            total_yield_claimed: self.total_yield_claimed,
+            staked_at: self.staked_at,
+            last_action_at: self.last_action_at,
+            bump: self.bump,
+            lock_until: 0,
+            user_loss_index: 0,
+            weighted_stake: self.staked_amount,
+            pending_payout: 0,
+            last_deposit_at: self.last_action_at,
+            auto_compound: false,
+            last_compound_rate: 0,
+            total_compounded: 0,
+            action_count: 0,
+            pending_yield: 0,
+            pool_share_bps: 0,
+            view_synced_at: 0,
+            rent_payer,
+            funded_by: Pubkey::default(),
+            locked_amount: 0,
+        })
+    }
+}
+
+/// Record of a processed `record_yield` batch
+/// Its PDA is derived from the batch id, so a replayed batch fails to initialize
+#[account]
+pub struct YieldBatch {
+    /// The pool the yield was recorded for
+    pub pool: Pubkey,
+    
+    /// Backend-supplied identifier of the batch
+    pub batch_id: [u8; 32],
+    
+    /// Amount of yield recorded
+    pub sol_amount: u64,
+    
+    /// Timestamp when the batch was recorded
+    pub recorded_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl YieldBatch {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (batch_id) + 8 (sol_amount) + 8 (recorded_at)
+    /// + 1 (bump) = 89 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Approval for a wallet to deposit into a whitelist-gated pool
+#[account]
+pub struct StakeWhitelistEntry {
+    /// The pool the approval applies to
+    pub pool: Pubkey,
+    
+    /// The approved wallet
+    pub user: Pubkey,
+    
+    /// Timestamp when the wallet was approved
+    pub added_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl StakeWhitelistEntry {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (user) + 8 (added_at) + 1 (bump) = 81 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+/// A user's pending withdrawal in a queued-withdrawal pool
+/// The amount has already left the user's stake and no longer earns yield
+#[account]
+pub struct UnstakeRequest {
+    /// The pool the request belongs to
+    pub pool: Pubkey,
+    
+    /// The user who requested the withdrawal
+    pub user: Pubkey,
+    
+    /// Vouchers waiting to be paid out
+    pub amount: u64,
+    
+    /// Timestamp of the latest request (top-ups restart the delay)
+    pub requested_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl UnstakeRequest {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (user) + 8 (amount) + 8 (requested_at)
+    /// + 1 (bump) = 89 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 1;
+}
+
+/// Lokal tokens a user borrowed against their stake, interest free
+/// The collateral is locked in the user's `UserStakeRecord` until the advance is repaid
+#[account]
+pub struct TokenAdvance {
+    /// The pool whose stake secures the advance
+    pub pool: Pubkey,
+    
+    /// The borrower
+    pub user: Pubkey,
+    
+    /// Tokens borrowed and not yet repaid
+    pub amount: u64,
+    
+    /// The pool's `advance_ltv_bps` when the advance was last drawn, used to size the lock
+    pub ltv_bps: u16,
+    
+    /// Timestamp of the latest borrow
+    pub last_borrowed_at: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl TokenAdvance {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (user) + 8 (amount) + 2 (ltv_bps)
+    /// + 8 (last_borrowed_at) + 1 (bump) = 91 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 2 + 8 + 1;
+
+    /// Largest advance a stake of `staked_amount` supports at `ltv_bps`, rounded down
+    pub fn max_advance(staked_amount: u64, ltv_bps: u16) -> Option<u64> {
+        let max = (staked_amount as u128).checked_mul(ltv_bps as u128)? / 10_000;
+        u64::try_from(max).ok()
+    }
+
+    /// Stake that must stay locked to secure `amount` at `ltv_bps`, rounded up so
+    /// the lock always covers the advance
+    pub fn collateral_for(amount: u64, ltv_bps: u16) -> Option<u64> {
+        if ltv_bps == 0 {
+            return None;
+        }
+        let collateral = (amount as u128)
+            .checked_mul(10_000)?
+            .checked_add(ltv_bps as u128 - 1)?
+            / ltv_bps as u128;
+        u64::try_from(collateral).ok()
+    }
+}
+
+/// A pool's position in an SPL stake pool, built by `deploy_to_stake_pool`
+/// SOL to deploy waits in the pool's SOL vault PDA, and unwinds pay back into it
+#[account]
+pub struct StakePoolDeployment {
+    /// The voucher pool deploying its capital
+    pub pool: Pubkey,
+    
+    /// The SPL stake-pool program CPI'd into
+    pub stake_pool_program: Pubkey,
+    
+    /// The stake pool deposited into
+    pub stake_pool: Pubkey,
+    
+    /// Token account of the stake pool's mint holding the pool tokens, owned by this PDA
+    pub pool_token_account: Pubkey,
+    
+    /// Lamports paid for the pool tokens still held (cost basis)
+    pub deployed_lamports: u64,
+    
+    /// Pool tokens held, as counted by deposits and unwinds
+    pub pool_tokens: u64,
+    
+    /// Lamports deposited into the stake pool over the deployment's lifetime
+    pub total_deposited_lamports: u64,
+    
+    /// Lamports returned by unwinds over the deployment's lifetime
+    pub total_withdrawn_lamports: u64,
+    
+    /// Lamports returned by unwinds less the cost basis of the tokens unwound
+    /// Negative when the stake pool lost value or charged more in fees than it earned
+    pub realized_pnl_lamports: i64,
+    
+    /// The bump seed of the pool's SOL vault PDA
+    pub sol_vault_bump: u8,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl StakePoolDeployment {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (stake_pool_program) + 32 (stake_pool)
+    /// + 32 (pool_token_account) + 8 (deployed_lamports) + 8 (pool_tokens)
+    /// + 8 (total_deposited_lamports) + 8 (total_withdrawn_lamports)
+    /// + 8 (realized_pnl_lamports) + 1 (sol_vault_bump) + 1 (bump) = 178 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+
+    /// Cost basis of `pool_tokens` of the held tokens, pro rata and rounded down
+    /// Unwinding every held token takes the whole basis, so none is left behind
+    pub fn cost_basis(&self, pool_tokens: u64) -> Option<u64> {
+        if pool_tokens == self.pool_tokens {
+            return Some(self.deployed_lamports);
+        }
+        let basis = (self.deployed_lamports as u128)
+            .checked_mul(pool_tokens as u128)?
+            .checked_div(self.pool_tokens as u128)?;
+        u64::try_from(basis).ok()
+    }
+}
+
+/// Kind of stake change captured by a `StakeActionRecord`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeActionType {
+    Deposit,
+    Withdrawal,
+}
+
+/// Immutable audit entry for a single deposit or withdrawal
+/// Written only when the pool has `record_history` enabled
+#[account]
+pub struct StakeActionRecord {
+    /// The pool the action happened in
+    pub pool: Pubkey,
+    
+    /// The user whose stake changed
+    pub user: Pubkey,
+    
+    /// Position of this entry in the user's history (starts at 0)
+    pub action_index: u64,
+    
+    /// Whether this was a deposit or a withdrawal
+    pub action_type: StakeActionType,
+    
+    /// Vouchers deposited or withdrawn
+    pub amount: u64,
+    
+    /// The user's staked amount after the action
+    pub resulting_balance: u64,
+    
+    /// Pool reward index at the time of the action
+    pub reward_index: u128,
+    
+    /// Timestamp of the action
+    pub timestamp: i64,
+    
+    /// The bump seed for this PDA
+    pub bump: u8,
+}
+
+impl StakeActionRecord {
+    /// Calculate the space needed for this account
+    /// 8 (discriminator) + 32 (pool) + 32 (user) + 8 (action_index) + 1 (action_type)
+    /// + 8 (amount) + 8 (resulting_balance) + 16 (reward_index) + 8 (timestamp)
+    /// + 1 (bump) = 122 bytes
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1 + 8 + 8 + 16 + 8 + 1;
+
+    /// Fill in this entry as the next action in `stake`'s history and advance its counter
+    /// Must run after the action is applied so the resulting balance is current
+    pub fn record(
+        &mut self,
+        stake: &mut UserStakeRecord,
+        bump: u8,
+        action_type: StakeActionType,
+        amount: u64,
+        reward_index: u128,
+        timestamp: i64,
+    ) -> Option<()> {
+        self.pool = stake.pool;
+        self.user = stake.user;
+        self.action_index = stake.action_count;
+        self.action_type = action_type;
+        self.amount = amount;
+        self.resulting_balance = stake.staked_amount;
+        self.reward_index = reward_index;
+        self.timestamp = timestamp;
+        self.bump = bump;
+
+        stake.action_count = stake.action_count.checked_add(1)?;
+        Some(())
+    }
+}
+
+/// Seeds for deriving the pool state PDA
+pub const POOL_STATE_SEED: &[u8] = b"pool_state";
+
+/// Seeds for deriving the pool vault authority PDA
+pub const POOL_VAULT_AUTHORITY_SEED: &[u8] = b"pool_vault_authority";
+
+/// Seeds for deriving user stake record PDAs
+pub const USER_STAKE_SEED: &[u8] = b"user_stake";
+
+/// Seeds for deriving the pool receipt mint PDA
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+
+/// Seeds for deriving stake whitelist entry PDAs
+pub const STAKE_WHITELIST_SEED: &[u8] = b"stake_whitelist";
+
+/// Seeds for deriving unstake request PDAs
+pub const UNSTAKE_REQUEST_SEED: &[u8] = b"unstake_request";
+
+/// Seeds for deriving token advance PDAs
+pub const TOKEN_ADVANCE_SEED: &[u8] = b"token_advance";
+
+/// Seeds for deriving stake pool deployment PDAs
+pub const STAKE_POOL_DEPLOYMENT_SEED: &[u8] = b"stake_pool_deployment";
+
+/// Seeds for deriving the SOL vault PDA of a pool's stake pool deployment
+pub const POOL_SOL_VAULT_SEED: &[u8] = b"pool_sol_vault";
+
+/// Seeds for deriving the pool token account PDA of a pool's stake pool deployment
+pub const STAKE_POOL_TOKENS_SEED: &[u8] = b"stake_pool_tokens";
+
+/// Seeds for deriving stake action history PDAs
+pub const STAKE_ACTION_SEED: &[u8] = b"stake_action";
+
+/// Seeds for deriving yield batch PDAs
+pub const YIELD_BATCH_SEED: &[u8] = b"yield_batch";
+
+/// Seed suffix identifying a pool of a voucher mint in the pool state, vault authority and
+/// receipt mint PDAs, after `PoolState::mint_seed`
+/// Pool 0 uses no suffix, so the pool created before multi-pool support keeps its addresses
+/// and each mint's first pool sits at `[POOL_STATE_SEED, voucher_mint]`
+pub fn pool_id_seed(pool_id: u16) -> Vec<u8> {
+    if pool_id == 0 {
+        Vec::new()
+    } else {
+        pool_id.to_le_bytes().to_vec()
+    }
+}
+
+/// Maximum stake records a single `settle_users` call accepts
+pub const MAX_SETTLE_BATCH: usize = 20;
+
+/// Maximum stake records a single `close_stake_records` call accepts
+pub const MAX_CLOSE_BATCH: usize = 20;
+
+/// Maximum outlets a single `cascade_cashback_rate` call accepts
+pub const MAX_CASCADE_BATCH: usize = 20;
+
+/// Basis point denominator (10000 = 100%)
+pub const BASIS_POINTS: u128 = 10_000;
+
+/// Seconds in a (365-day) year, the period `apy_basis_points` is quoted over
+pub const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+/// Fixed-point scale of the pool reward index
+pub const REWARD_INDEX_SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Reward index scale of the first deployment, which legacy accounts still store
+pub const LEGACY_REWARD_INDEX_SCALE: u128 = 1_000_000_000_000;
+
+/// A reward index stored under `LEGACY_REWARD_INDEX_SCALE`, rescaled to `REWARD_INDEX_SCALE`
+pub fn rescale_legacy_index(index: u128) -> Option<u128> {
+    index.checked_mul(REWARD_INDEX_SCALE / LEGACY_REWARD_INDEX_SCALE)
+}
+
+/// Reward index increment for `yield_amount` spread over `total_staked`
+/// Returns the increment and the scaled remainder to carry into the next update
+pub fn reward_index_increment(
+    yield_amount: u64,
+    dust_carry: u128,
+    total_staked: u128,
+) -> Option<(u128, u128)> {
+    let scaled_yield = (yield_amount as u128)
+        .checked_mul(REWARD_INDEX_SCALE)?
+        .checked_add(dust_carry)?;
+
+    Some((
+        scaled_yield.checked_div(total_staked)?,
+        scaled_yield.checked_rem(total_staked)?,
+    ))
+}
+
+/// Loss index increment for `loss_amount` spread over `total_staked`
+/// Rounds up so users are never credited with yield the pool no longer has
+pub fn loss_index_increment(loss_amount: u64, total_staked: u128) -> Option<u128> {
+    let scaled_loss = (loss_amount as u128).checked_mul(REWARD_INDEX_SCALE)?;
+    let increment = scaled_loss.checked_div(total_staked)?;
+
+    if scaled_loss.checked_rem(total_staked)? > 0 {
+        increment.checked_add(1)
+    } else {
+        Some(increment)
+    }
+}
+
+/// Voucher base units a compound rate is quoted per (one whole 9-decimal token)
+pub const COMPOUND_RATE_SCALE: u128 = 1_000_000_000;
+
+/// Lamports `tokens` vouchers cost at `rate`, rounded up so a fractional lamport is paid in full
+pub fn compound_cost(tokens: u64, rate: u64) -> Option<u64> {
+    let scaled_cost = (tokens as u128).checked_mul(rate as u128)?;
+    let cost = scaled_cost.checked_div(COMPOUND_RATE_SCALE)?;
+
+    if scaled_cost.checked_rem(COMPOUND_RATE_SCALE)? > 0 {
+        u64::try_from(cost.checked_add(1)?).ok()
+    } else {
+        u64::try_from(cost).ok()
+    }
+}
+
+/// Split `yield_amount` lamports into vouchers bought at `rate` and the yield they consume
+/// Vouchers round down and their cost rounds up, so the consumed yield always covers the
+/// vouchers credited and never exceeds the input
+pub fn compound_split(yield_amount: u64, rate: u64) -> Option<(u64, u64)> {
+    let tokens = (yield_amount as u128)
+        .checked_mul(COMPOUND_RATE_SCALE)?
+        .checked_div(rate as u128)?;
+    let tokens = u64::try_from(tokens).ok()?;
+    let consumed = compound_cost(tokens, rate)?;
+
+    // tokens * rate <= yield_amount * SCALE, so the rounded-up cost stays within the yield
+    (consumed <= yield_amount).then_some((tokens, consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small deterministic generator so the property checks need no extra dependency
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, low: u64, high: u64) -> u64 {
+            low + self.next() % (high - low + 1)
+        }
+    }
+
+    #[test]
+    fn claimable_yield_matches_total_within_one_lamport_per_user() {
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..500 {
+            let user_count = rng.range(1, 20) as usize;
+            // Mix pool-minimum stakes with very large ones
+            let stakes: Vec<u64> = (0..user_count)
+                .map(|_| match rng.range(0, 2) {
+                    0 => 1,
+                    1 => rng.range(1, 1_000_000_000),
+                    _ => rng.range(1_000_000_000, 1_000_000_000_000_000_000),
+                })
+                .collect();
+            let total_staked: u128 = stakes.iter().map(|stake| *stake as u128).sum();
+
+            let mut reward_index = 0u128;
+            let mut dust_carry = 0u128;
+            let mut total_yield = 0u64;
+            for _ in 0..rng.range(1, 50) {
+                let yield_amount = rng.range(1, 10_000_000_000);
+                let (increment, carry) =
+                    reward_index_increment(yield_amount, dust_carry, total_staked).unwrap();
+                reward_index += increment;
+                dust_carry = carry;
+                total_yield += yield_amount;
+            }
+
+            let claimed: u64 = stakes
+                .iter()
+                .map(|stake| calculate_claimable_yield(*stake, reward_index).unwrap())
+                .sum();
+
+            assert!(claimed <= total_yield);
+            assert!(total_yield - claimed <= user_count as u64);
+        }
+    }
+
+    #[test]
+    fn small_yield_reaches_small_stakes_in_a_large_pool() {
+        // A 1e12 scale truncated this index increment to zero
+        let total_staked = 1_000_000_000_000_000_000;
+        let (increment, _) = reward_index_increment(100_000, 0, total_staked).unwrap();
+
+        assert!(increment > 0);
+        assert_eq!(calculate_claimable_yield(10_000_000_000_000, increment).unwrap(), 1);
+    }
+
+    #[test]
+    fn loss_offsets_yield_but_never_goes_negative() {
+        // Alice stakes 100 and earns a 10 gain, then Bob stakes 100 before a 4 loss
+        let (gain, _) = reward_index_increment(10, 0, 100).unwrap();
+        let loss = loss_index_increment(4, 200).unwrap();
+
+        let alice = calculate_claimable_yield(100, gain.saturating_sub(loss)).unwrap();
+        let bob = calculate_claimable_yield(100, 0u128.saturating_sub(loss)).unwrap();
+
+        assert_eq!(alice, 8);
+        assert_eq!(bob, 0);
+    }
+
+    #[test]
+    fn dust_carry_is_paid_out_by_later_yield() {
+        // 1 lamport over 3 tokens cannot be split, the remainder rolls forward
+        let (first, carry) = reward_index_increment(1, 0, 3).unwrap();
+        assert_eq!(calculate_claimable_yield(3, first).unwrap(), 0);
+        assert!(carry > 0);
+
+        let (second, carry) = reward_index_increment(2, carry, 3).unwrap();
+        assert_eq!(calculate_claimable_yield(3, first + second).unwrap(), 3);
+        assert_eq!(carry, 0);
+    }
+
+    fn empty_record() -> UserStakeRecord {
+        UserStakeRecord {
+            user: Pubkey::default(),
+            pool: Pubkey::default(),
+            staked_amount: 0,
+            user_reward_index: 0,
+            total_yield_claimed: 0,
+            staked_at: 0,
+            last_action_at: 0,
+            bump: 0,
+            lock_until: 0,
+            user_loss_index: 0,
+            weighted_stake: 0,
+            pending_payout: 0,
+            last_deposit_at: 0,
+            auto_compound: false,
+            last_compound_rate: 0,
+            total_compounded: 0,
+            action_count: 0,
+            pending_yield: 0,
+            pool_share_bps: 0,
+            view_synced_at: 0,
+            rent_payer: Pubkey::default(),
+            funded_by: Pubkey::default(),
+            locked_amount: 0,
+        }
+    }
+
+    /// A config with every optional feature off; tests set only the fields they exercise
+    fn test_config() -> LokalMintConfig {
+        LokalMintConfig {
+            mint: Pubkey::default(),
+            mint_authority_bump: 0,
+            config_bump: 0,
+            update_authority: Pubkey::default(),
+            total_supply: 0,
+            staker_boost_threshold: 0,
+            staker_boost_bps: 0,
+            event_sequence: 0,
+            record_activity: false,
+            total_purchases: 0,
+            total_transfers: 0,
+            protocol_fee_bps: 0,
+            treasury_token_account: Pubkey::default(),
+            transfer_fee_bps: 0,
+            treasury_cosigner: Pubkey::default(),
+            treasury_withdrawal_cap: 0,
+            treasury_withdrawal_count: 0,
+            token_to_idr_rate: LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE,
+            param_timelock_seconds: 0,
+            param_change_count: 0,
+            guardian: Pubkey::default(),
+            paused: false,
+            mint_cosigner: Pubkey::default(),
+            single_sig_mint_limit: 0,
+            price_feed: Pubkey::default(),
+            max_price_age_seconds: 0,
+            max_price_confidence_bps: 0,
+            expiring_rewards: false,
+            reward_lifetime_seconds: 0,
+            vesting_grant_count: 0,
+            dispute_window_seconds: 0,
+            decimals: 0,
+            total_burned: 0,
+            fulfillment_authority: Pubkey::default(),
+            fulfillment_window_seconds: 0,
+            max_transfer_amount: 0,
+            max_mint_per_tx: 0,
+            max_purchase_idr: 0,
+            rate_change_cooldown_seconds: 0,
+            max_rate_increase_bps: 0,
+            slim_purchase_records: false,
+            total_admin_minted: 0,
+            admin_mint_cap: 0,
+            total_reward_minted: 0,
+            reward_mint_cap: 0,
+            pending_param_changes: 0,
+            hash_customer_in_records: false,
+            customer_hash_salt: [0; 32],
+            max_cashback_bps: LokalMintConfig::DEFAULT_MAX_CASHBACK_BPS,
+            alert_threshold_bps: 0,
+            reserved: [0; 1],
+        }
+    }
+
+    fn tiered_config(tier_thresholds: [u64; 3], tier_multipliers_bps: [u16; 3]) -> PoolConfig {
+        PoolConfig {
+            min_stake_amount: 1,
+            max_stake_per_user: u64::MAX,
+            deposits_enabled: true,
+            withdrawals_enabled: true,
+            apy_basis_points: 0,
+            auto_revoke_delegation: false,
+            lockup_seconds: 0,
+            early_withdraw_penalty_bps: 0,
+            max_deployment_bps: 0,
+            max_total_staked: 0,
+            tier_thresholds,
+            tier_multipliers_bps,
+            withdraw_cooldown_seconds: 0,
+            whitelist_enabled: false,
+            performance_fee_bps: 0,
+            fee_recipient: Pubkey::default(),
+            guaranteed_yield_budget: 0,
+            queued_withdrawals: false,
+            unstake_delay_seconds: 0,
+            record_history: false,
+            insurance_fee_bps: 0,
+        }
+    }
+
+    /// Mirrors the deposit, redeem and record_yield accounting of a tiered pool
+    struct TieredPool {
+        config: PoolConfig,
+        users: Vec<UserStakeRecord>,
+        reward_index: u128,
+        dust_carry: u128,
+        weighted_total_staked: u128,
+        total_yield: u64,
+        claimed: u64,
+        checkpoints: u64,
+    }
+
+    impl TieredPool {
+        fn new(config: PoolConfig, user_count: usize) -> Self {
+            TieredPool {
+                config,
+                users: (0..user_count).map(|_| empty_record()).collect(),
+                reward_index: 0,
+                dust_carry: 0,
+                weighted_total_staked: 0,
+                total_yield: 0,
+                claimed: 0,
+                checkpoints: 0,
+            }
+        }
+
+        fn set_stake(&mut self, user: usize, staked_amount: u64) {
+            let record = &mut self.users[user];
+            record.checkpoint(self.reward_index, 0).unwrap();
+            self.checkpoints += 1;
+
+            let new_weight = self.config.stake_weight(staked_amount).unwrap();
+            self.weighted_total_staked =
+                self.weighted_total_staked - record.weighted_stake as u128 + new_weight as u128;
+            record.staked_amount = staked_amount;
+            record.weighted_stake = new_weight;
+        }
+
+        fn record_yield(&mut self, yield_amount: u64) {
+            if self.weighted_total_staked == 0 {
+                return;
+            }
+            let (increment, carry) =
+                reward_index_increment(yield_amount, self.dust_carry, self.weighted_total_staked)
+                    .unwrap();
+            self.reward_index += increment;
+            self.dust_carry = carry;
+            self.total_yield += yield_amount;
+        }
+
+        fn claim(&mut self, user: usize) -> u64 {
+            let record = &mut self.users[user];
+            let claimable = record.claimable_yield(self.reward_index, 0).unwrap();
+            record.pending_payout = 0;
+            record.user_reward_index = self.reward_index;
+            self.claimed += claimable;
+            self.checkpoints += 1;
+            claimable
+        }
+
+        fn assert_conserved(&mut self) {
+            for user in 0..self.users.len() {
+                self.claim(user);
+            }
+            assert!(self.claimed <= self.total_yield);
+            assert!(self.total_yield - self.claimed <= self.checkpoints);
+        }
+    }
+
+    #[test]
+    fn tier_validation() {
+        assert!(tiered_config([0, 0, 0], [0, 0, 0]).tiers_valid());
+        assert!(tiered_config([100, 1_000, 0], [15_000, 20_000, 0]).tiers_valid());
+        assert!(!tiered_config([0, 1_000, 0], [0, 20_000, 0]).tiers_valid());
+        assert!(!tiered_config([1_000, 1_000, 0], [15_000, 20_000, 0]).tiers_valid());
+        assert!(!tiered_config([100, 0, 0], [0, 0, 0]).tiers_valid());
+    }
+
+    #[test]
+    fn as_update_restores_every_field() {
+        use anchor_lang::__private::bytemuck::bytes_of;
+
+        let mut old = tiered_config([1_000, 10_000, 0], [15_000, 20_000, 0]);
+        old.apy_basis_points = 800;
+        old.fee_recipient = Pubkey::new_unique();
+        old.record_history = true;
+        let new = tiered_config([0, 0, 0], [0, 0, 0]);
+
+        let restored = new.merge(&old.as_update());
+        assert_eq!(bytes_of(&restored), bytes_of(&old));
+    }
+
+    #[test]
+    fn advance_collateral_always_covers_the_advance() {
+        let mut rng = XorShift(0x5eed_ad7a);
+        for _ in 0..1_000 {
+            let ltv_bps = rng.range(1, 10_000) as u16;
+            let staked = rng.range(0, u64::MAX / 10_000);
+            let max = TokenAdvance::max_advance(staked, ltv_bps).unwrap();
+            let collateral = TokenAdvance::collateral_for(max, ltv_bps).unwrap();
+            assert!(collateral <= staked, "the largest advance locks at most the stake");
+            assert!(TokenAdvance::max_advance(collateral, ltv_bps).unwrap() >= max);
+        }
+        assert_eq!(TokenAdvance::max_advance(1_000, 5_000), Some(500));
+        assert_eq!(TokenAdvance::collateral_for(333, 5_000), Some(666));
+        assert_eq!(TokenAdvance::collateral_for(1, 3_000), Some(4));
+        assert_eq!(TokenAdvance::collateral_for(1, 0), None);
+    }
+
+    #[test]
+    fn unwinds_take_a_pro_rata_cost_basis() {
+        let mut deployment = StakePoolDeployment {
+            pool: Pubkey::default(),
+            stake_pool_program: Pubkey::default(),
+            stake_pool: Pubkey::default(),
+            pool_token_account: Pubkey::default(),
+            deployed_lamports: 1_000,
+            pool_tokens: 3,
+            total_deposited_lamports: 1_000,
+            total_withdrawn_lamports: 0,
+            realized_pnl_lamports: 0,
+            sol_vault_bump: 0,
+            bump: 0,
+        };
+        assert_eq!(deployment.cost_basis(1), Some(333));
+        assert_eq!(deployment.cost_basis(3), Some(1_000));
+
+        deployment.deployed_lamports -= 333;
+        deployment.pool_tokens -= 1;
+        assert_eq!(deployment.cost_basis(2), Some(667));
+    }
+
+    #[test]
+    fn mixed_tiers_split_yield_by_weight() {
+        let config = tiered_config([1_000, 10_000, 0], [15_000, 20_000, 0]);
+        let mut pool = TieredPool::new(config, 3);
+        pool.set_stake(0, 500); // 1x
+        pool.set_stake(1, 1_000); // 1.5x
+        pool.set_stake(2, 10_000); // 2x
+
+        pool.record_yield(22_000);
+
+        assert_eq!(pool.claim(0), 500);
+        assert_eq!(pool.claim(1), 1_500);
+        assert_eq!(pool.claim(2), 20_000);
+        assert_eq!(pool.claimed, pool.total_yield);
+    }
+
+    #[test]
+    fn crossing_a_threshold_between_yields_keeps_past_weight() {
+        let config = tiered_config([1_000, 0, 0], [30_000, 0, 0]);
+        let mut pool = TieredPool::new(config, 2);
+        pool.set_stake(0, 500);
+        pool.set_stake(1, 500);
+
+        // Both earn at 1x
+        pool.record_yield(1_000);
+
+        // User 1 tops up into the 3x tier before the next yield
+        pool.set_stake(1, 1_000);
+        pool.record_yield(3_500);
+
+        assert_eq!(pool.claim(0), 500 + 500);
+        assert_eq!(pool.claim(1), 500 + 3_000);
+        assert_eq!(pool.claimed, pool.total_yield);
+    }
+
+    #[test]
+    fn synced_view_matches_the_next_claim() {
+        let config = tiered_config([1_000, 0, 0], [30_000, 0, 0]);
+        let mut pool = TieredPool::new(config, 2);
+        pool.set_stake(0, 500); // 1x
+        pool.set_stake(1, 1_000); // 3x
+
+        pool.record_yield(1_000);
+        pool.set_stake(0, 700);
+        pool.record_yield(4_000);
+
+        let (reward_index, weighted_total) = (pool.reward_index, pool.weighted_total_staked);
+        for record in pool.users.iter_mut() {
+            record.sync_view(reward_index, 0, weighted_total, 42).unwrap();
+        }
+        assert_eq!(pool.users[0].pool_share_bps, 1_891); // 700 of 3_700
+        assert_eq!(pool.users[1].pool_share_bps, 8_108); // 3_000 of 3_700
+        assert_eq!(pool.users[1].view_synced_at, 42);
+
+        for user in 0..2 {
+            let cached = pool.users[user].pending_yield;
+            assert_eq!(pool.claim(user), cached);
+        }
+
+        // An empty pool has no share to report
+        let mut record = empty_record();
+        record.sync_view(0, 0, 0, 0).unwrap();
+        assert_eq!((record.pending_yield, record.pool_share_bps), (0, 0));
+    }
+
+    #[test]
+    fn tiered_yield_is_conserved_across_random_activity() {
+        let mut rng = XorShift(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..300 {
+            let first = rng.range(1, 1_000_000);
+            let second = rng.range(first + 1, 100_000_000);
+            let config = tiered_config(
+                [first, second, 0],
+                [rng.range(1, 30_000) as u16, rng.range(1, 60_000) as u16, 0],
+            );
+            let user_count = rng.range(1, 10) as usize;
+            let mut pool = TieredPool::new(config, user_count);
+
+            for _ in 0..rng.range(1, 60) {
+                let user = rng.range(0, user_count as u64 - 1) as usize;
+                match rng.range(0, 2) {
+                    // Deposits and withdrawals move users across tiers
+                    0 => {
+                        let staked = pool.users[user].staked_amount;
+                        pool.set_stake(user, staked + rng.range(1, 200_000_000));
+                    }
+                    1 => {
+                        let staked = pool.users[user].staked_amount;
+                        pool.set_stake(user, staked - rng.range(0, staked));
+                    }
+                    _ => pool.record_yield(rng.range(1, 10_000_000_000)),
+                }
+            }
+
+            pool.assert_conserved();
+        }
+    }
+
+    #[test]
+    fn compounding_consumes_exactly_the_cost_of_credited_vouchers() {
+        let mut rng = XorShift(0xc0ffee);
+
+        for _ in 0..1_000 {
+            let yield_amount = rng.range(0, 1_000_000_000_000);
+            let rate = rng.range(1, 1_000_000_000_000);
+            let (tokens, consumed) = compound_split(yield_amount, rate).unwrap();
+
+            // The consumed yield pays for every credited voucher, rounded in the pool's favour
+            assert!(consumed <= yield_amount);
+            assert!(consumed as u128 * COMPOUND_RATE_SCALE >= tokens as u128 * rate as u128);
+            // ...and by less than one lamport
+            assert!(
+                consumed == 0
+                    || (consumed as u128 - 1) * COMPOUND_RATE_SCALE < tokens as u128 * rate as u128
+            );
+            // One more voucher would cost more than the yield
+            assert!((tokens as u128 + 1) * rate as u128 > yield_amount as u128 * COMPOUND_RATE_SCALE);
+        }
+
+        assert_eq!(compound_split(5_000_000, 1_000_000_000), Some((5_000_000, 5_000_000)));
+        // 3 vouchers at 0.6 lamports each cost 1.8 lamports, which rounds up to 2
+        assert_eq!(compound_split(2, 600_000_000), Some((3, 2)));
+        assert_eq!(compound_cost(3, 600_000_000), Some(2));
+        assert_eq!(compound_split(1_000, 0), None);
+    }
+
+    #[test]
+    fn performance_fee_and_net_yield_add_up_to_the_recorded_yield() {
+        let mut rng = XorShift(0xfee);
+        let mut config = tiered_config([0; 3], [0; 3]);
+
+        for _ in 0..1_000 {
+            config.performance_fee_bps = rng.range(0, 10_000) as u16;
+            let yield_amount = rng.range(0, u64::MAX / 2);
+            let fee = config.performance_fee(yield_amount).unwrap();
+
+            assert!(fee <= yield_amount);
+            assert_eq!(
+                fee as u128,
+                yield_amount as u128 * config.performance_fee_bps as u128 / BASIS_POINTS
+            );
+        }
+
+        config.performance_fee_bps = 1_000;
+        assert_eq!(config.performance_fee(1_000_000_007), Some(100_000_000));
+        assert!(!config.fee_valid());
+        config.fee_recipient = Pubkey::new_unique();
+        assert!(config.fee_valid());
+        config.performance_fee_bps = 10_001;
+        assert!(!config.fee_valid());
+    }
+
+    #[test]
+    fn minimum_yield_splits_evenly_across_accruals() {
+        let mut config = tiered_config([0; 3], [0; 3]);
+        config.apy_basis_points = 1_200;
+        let year = SECONDS_PER_YEAR as u64;
+
+        assert_eq!(config.minimum_yield(1_000_000, year), Some(120_000));
+        assert_eq!(config.minimum_yield(1_000_000, year / 2), Some(60_000));
+        assert_eq!(config.minimum_yield(1_000_000, 0), Some(0));
+
+        // Splitting a span only ever loses rounding, never adds to it
+        let mut rng = XorShift(0xa9);
+        for _ in 0..1_000 {
+            let staked = rng.range(0, 1_000_000_000_000_000);
+            let first = rng.range(0, year);
+            let second = rng.range(0, year);
+            let whole = config.minimum_yield(staked, first + second).unwrap();
+            let split = config.minimum_yield(staked, first).unwrap()
+                + config.minimum_yield(staked, second).unwrap();
+            assert!(split <= whole && whole - split <= 1);
+        }
+
+        config.apy_basis_points = 0;
+        assert_eq!(config.minimum_yield(1_000_000, year), Some(0));
+    }
+
+    #[test]
+    fn staker_boost_applies_at_threshold_and_caps_at_full_cashback() {
+        let mut config = test_config();
+        config.staker_boost_threshold = 1_000;
+        config.staker_boost_bps = 100;
+
+        assert_eq!(config.boosted_cashback_rate(500, 999), 500);
+        assert_eq!(config.boosted_cashback_rate(500, 1_000), 600);
+        assert_eq!(config.boosted_cashback_rate(9_950, u64::MAX), 10_000);
+    }
+
+    #[test]
+    fn configs_without_recorded_decimals_read_the_default() {
+        let mut config = test_config();
+        assert_eq!(config.decimals(), LOKAL_DECIMALS);
+
+        config.decimals = 6;
+        assert_eq!(config.decimals(), 6);
+    }
+
+    #[test]
+    fn limits_apply_at_the_stored_values_and_zero_lifts_them() {
+        let mut config = test_config();
+        config.max_transfer_amount = LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT;
+        config.max_mint_per_tx = LokalMintConfig::DEFAULT_MAX_MINT_PER_TX;
+        config.max_purchase_idr = LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR;
+
+        assert!(config.allows_transfer(LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT));
+        assert!(!config.allows_transfer(LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT + 1));
+        assert!(config.allows_mint(LokalMintConfig::DEFAULT_MAX_MINT_PER_TX));
+        assert!(!config.allows_mint(LokalMintConfig::DEFAULT_MAX_MINT_PER_TX + 1));
+        assert!(config.allows_purchase(LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR));
+        assert!(!config.allows_purchase(LokalMintConfig::DEFAULT_MAX_PURCHASE_IDR + 1));
+
+        config.max_transfer_amount = 0;
+        config.max_mint_per_tx = 0;
+        config.max_purchase_idr = 0;
+        assert!(config.allows_transfer(u64::MAX));
+        assert!(config.allows_mint(u64::MAX));
+        assert!(config.allows_purchase(u64::MAX));
+
+        // Mint caps are cumulative, and each only sees its own counter
+        config.total_admin_minted = 700;
+        config.total_reward_minted = 50;
+        config.admin_mint_cap = 1_000;
+        config.reward_mint_cap = 100;
+        assert_eq!(config.admin_minted_after(300), Some(1_000));
+        assert_eq!(config.admin_minted_after(301), None);
+        assert_eq!(config.reward_minted_after(50), Some(100));
+        assert_eq!(config.reward_minted_after(51), None);
+
+        config.admin_mint_cap = 0;
+        assert_eq!(config.admin_minted_after(1_000_000), Some(1_000_700));
+        assert_eq!(config.reward_minted_after(51), None);
+    }
+
+    #[test]
+    fn rate_changes_wait_out_the_cooldown_and_cap_increases() {
+        let mut config = test_config();
+        config.rate_change_cooldown_seconds = 3_600;
+        config.max_rate_increase_bps = 500;
+
+        // A merchant that never changed its rate is not held back
+        assert!(config.rate_change_cooled_down(0, 1_000));
+        assert!(!config.rate_change_cooled_down(10_000, 13_599));
+        assert!(config.rate_change_cooled_down(10_000, 13_600));
+
+        assert!(config.allows_rate_change(100, 600));
+        assert!(!config.allows_rate_change(100, 601));
+        assert!(config.allows_rate_change(2_000, 100));
+
+        config.rate_change_cooldown_seconds = 0;
+        config.max_rate_increase_bps = 0;
+        assert!(config.rate_change_cooled_down(10_000, 10_000));
+        assert!(config.allows_rate_change(0, 10_000));
+
+        // Configs from before the cashback cap hold 0, which allows any rate
+        config.max_cashback_bps = 0;
+        assert!(config.allows_cashback_rate(10_000));
+        config.max_cashback_bps = 2_000;
+        assert!(config.allows_cashback_rate(2_000));
+        assert!(!config.allows_cashback_rate(2_001));
+
+        assert!(!config.is_high_cashback_rate(2_000));
+        config.alert_threshold_bps = 1_500;
+        assert!(!config.is_high_cashback_rate(1_500));
+        assert!(config.is_high_cashback_rate(1_501));
+    }
+    #[test]
+    fn protocol_fee_rounds_in_the_merchants_favor() {
+        let mut config = test_config();
+        assert_eq!(config.protocol_fee(1_000_000_000), Some(0));
+
+        config.protocol_fee_bps = 250;
+        assert_eq!(config.protocol_fee(1_000_000_000), Some(25_000_000));
+        assert_eq!(config.protocol_fee(39), Some(0));
+        assert_eq!(config.protocol_fee(40), Some(1));
+        assert_eq!(config.protocol_fee(1), Some(0));
+        assert_eq!(config.protocol_fee(u64::MAX), Some(461_168_601_842_738_790));
+    }
+
+    #[test]
+    fn privacy_mode_records_a_stable_customer_hash() {
+        let mut config = test_config();
+        let customer = Pubkey::new_unique();
+        assert_eq!(config.record_customer(&customer), customer);
+        assert!(!config.is_record_customer(&config.customer_hash(&customer), &customer));
+
+        config.customer_hash_salt = [9; 32];
+        config.hash_customer_in_records = true;
+        let hash = config.record_customer(&customer);
+        assert_ne!(hash, customer);
+        assert_eq!(config.record_customer(&customer), hash);
+        assert_eq!(
+            hash.to_bytes(),
+            anchor_lang::solana_program::hash::hashv(&[customer.as_ref(), &[9; 32]]).to_bytes()
+        );
+        assert_ne!(config.record_customer(&Pubkey::new_unique()), hash);
+
+        // Records written in either mode stay the customer's
+        assert!(config.is_record_customer(&hash, &customer));
+        assert!(config.is_record_customer(&customer, &customer));
+        assert!(!config.is_record_customer(&hash, &Pubkey::new_unique()));
+        config.hash_customer_in_records = false;
+        assert_eq!(config.record_customer(&customer), customer);
+        assert_eq!(config.customer_hash(&customer), hash);
+    }
+
+    #[test]
+    fn snapshot_days_start_at_midnight_utc() {
+        assert_eq!(StatsSnapshot::day_index(0), Some(0));
+        assert_eq!(StatsSnapshot::day_index(86_399), Some(0));
+        assert_eq!(StatsSnapshot::day_index(86_400), Some(1));
+        // 2024-01-01T00:00:00Z
+        assert_eq!(StatsSnapshot::day_index(1_704_067_200), Some(19_723));
+        assert_eq!(StatsSnapshot::day_index(-1), None);
+    }
+
+    #[test]
+    fn passbook_months_follow_the_utc_calendar() {
+        assert_eq!(CustomerMonthlyStats::year_month(0), Some(197_001));
+        // 2024-01-31T23:59:59Z and 2024-02-01T00:00:00Z
+        assert_eq!(CustomerMonthlyStats::year_month(1_706_745_599), Some(202_401));
+        assert_eq!(CustomerMonthlyStats::year_month(1_706_745_600), Some(202_402));
+        // 2024-02-29T12:00:00Z, a leap day, and 2024-12-31T23:59:59Z
+        assert_eq!(CustomerMonthlyStats::year_month(1_709_208_000), Some(202_402));
+        assert_eq!(CustomerMonthlyStats::year_month(1_735_689_599), Some(202_412));
+        assert_eq!(CustomerMonthlyStats::year_month(1_735_689_600), Some(202_501));
+        assert_eq!(CustomerMonthlyStats::year_month(-1), None);
+    }
+
+    #[test]
+    fn largest_pool_config_change_fits_the_pending_change_account() {
+        let update = PoolConfigUpdate {
+            min_stake_amount: Some(1),
+            max_stake_per_user: Some(1),
+            deposits_enabled: Some(true),
+            withdrawals_enabled: Some(true),
+            apy_basis_points: Some(1),
+            auto_revoke_delegation: Some(true),
+            lockup_seconds: Some(1),
+            early_withdraw_penalty_bps: Some(1),
+            max_deployment_bps: Some(1),
+            max_total_staked: Some(1),
+            tier_thresholds: Some([1; 3]),
+            tier_multipliers_bps: Some([1; 3]),
+            withdraw_cooldown_seconds: Some(1),
+            whitelist_enabled: Some(true),
+            performance_fee_bps: Some(1),
+            fee_recipient: Some(Pubkey::default()),
+            guaranteed_yield_budget: Some(1),
+            queued_withdrawals: Some(true),
+            unstake_delay_seconds: Some(1),
+            record_history: Some(true),
+            insurance_fee_bps: Some(1),
+        };
+        let mut buf = Vec::new();
+        update.serialize(&mut buf).unwrap();
+        assert_eq!(buf.len(), PoolConfigUpdate::MAX_LEN);
+
+        let mut buf = Vec::new();
+        ParamChange::PoolConfig { pool_id: 0, update }.serialize(&mut buf).unwrap();
+        assert_eq!(buf.len(), ParamChange::MAX_LEN);
+    }
+
+    fn vesting_grant(total: u64) -> VestingGrant {
+        // A year from t = 1_000 with a one-month cliff
+        VestingGrant {
+            id: 0,
+            beneficiary: Pubkey::default(),
+            total,
+            released: 0,
+            start: 1_000,
+            cliff: 1_000 + 2_592_000,
+            duration: 31_536_000,
+            revoked_at: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn vesting_is_linear_after_the_cliff_and_exact_at_the_end() {
+        let grant = vesting_grant(1_200_000_000_000);
+        let end = grant.start + grant.duration;
+
+        assert_eq!(grant.vested_amount(0), Some(0));
+        assert_eq!(grant.vested_amount(grant.cliff - 1), Some(0));
+        assert_eq!(
+            grant.vested_amount(grant.cliff),
+            Some(1_200_000_000_000 * 2_592_000 / 31_536_000)
+        );
+        assert_eq!(grant.vested_amount(grant.start + grant.duration / 2), Some(600_000_000_000));
+        assert!(grant.vested_amount(end - 1).unwrap() < grant.total);
+        assert_eq!(grant.vested_amount(end), Some(grant.total));
+        assert_eq!(grant.vested_amount(i64::MAX), Some(grant.total));
+
+        // No overflow on the largest grant
+        let grant = vesting_grant(u64::MAX);
+        assert_eq!(grant.vested_amount(grant.start + grant.duration / 2), Some(u64::MAX / 2));
+        assert_eq!(grant.vested_amount(end), Some(u64::MAX));
+    }
+
+    #[test]
+    fn claims_and_revocation_never_exceed_the_vested_amount() {
+        let mut grant = vesting_grant(1_000_000);
+        let midpoint = grant.start + grant.duration / 2;
+
+        grant.released = grant.vested_amount(midpoint).unwrap();
+        assert_eq!(grant.claimable_amount(midpoint), Some(0));
+
+        grant.revoked_at = midpoint;
+        let end = grant.start + grant.duration;
+        assert_eq!(grant.vested_amount(end), Some(500_000));
+        assert_eq!(grant.claimable_amount(end), Some(0));
+
+        // Revoked before the cliff, nothing ever vests
+        let mut grant = vesting_grant(1_000_000);
+        grant.revoked_at = grant.cliff - 1;
+        assert_eq!(grant.vested_amount(i64::MAX), Some(0));
+    }
+
+    #[test]
+    fn merkle_proofs_verify_only_the_committed_allocations() {
+        let wallets: Vec<Pubkey> = (0..3u8).map(|i| Pubkey::new_from_array([i + 1; 32])).collect();
+        let leaves: Vec<[u8; 32]> = wallets
+            .iter()
+            .zip([100, 200, 300])
+            .map(|(wallet, amount)| Airdrop::leaf(wallet, amount))
+            .collect();
+        let parent = |a: [u8; 32], b: [u8; 32]| {
+            let (lower, higher) = if a <= b { (a, b) } else { (b, a) };
+            anchor_lang::solana_program::hash::hashv(&[&[1u8], &lower, &higher]).to_bytes()
+        };
+        // An odd leaf is paired with itself
+        let left = parent(leaves[0], leaves[1]);
+        let right = parent(leaves[2], leaves[2]);
+        let root = parent(left, right);
+
+        assert!(Airdrop::verify_proof(&root, leaves[0], &[leaves[1], right]));
+        assert!(Airdrop::verify_proof(&root, leaves[1], &[leaves[0], right]));
+        assert!(Airdrop::verify_proof(&root, leaves[2], &[leaves[2], left]));
+
+        // Tampered amount, wrong wallet, truncated proof
+        assert!(!Airdrop::verify_proof(&root, Airdrop::leaf(&wallets[0], 101), &[leaves[1], right]));
+        assert!(!Airdrop::verify_proof(&root, Airdrop::leaf(&wallets[1], 100), &[leaves[1], right]));
+        assert!(!Airdrop::verify_proof(&root, leaves[0], &[leaves[1]]));
+    }
+
+    #[test]
+    fn reward_budget_only_pays_rewards_it_fully_covers() {
+        let mut budget = RewardBudget {
+            sponsor: Pubkey::default(),
+            budget_id: 0,
+            remaining_budget: 1_000,
+            total_paid: 0,
+            member_count: 1,
+            created_at: 0,
+            bump: 0,
+        };
+
+        assert!(budget.try_spend(600));
+        assert!(!budget.try_spend(0));
+        // Too little left: nothing is taken and the reward falls back to minting
+        assert!(!budget.try_spend(401));
+        assert_eq!((budget.remaining_budget, budget.total_paid), (400, 600));
+        assert!(budget.try_spend(400));
+        assert_eq!((budget.remaining_budget, budget.total_paid), (0, 1_000));
+    }
+
+    #[test]
+    fn visit_streaks_complete_within_the_window_and_count_each_day_once() {
+        let day = StatsSnapshot::SECONDS_PER_DAY;
+        let mut stats = CustomerMerchantStats {
+            merchant: Pubkey::new_unique(),
+            customer: Pubkey::new_unique(),
+            visit_count: 0,
+            window_start: 0,
+            last_visit: 0,
+            streaks_completed: 0,
+            bump: 0,
+        };
+        let start = 1_700_000_000 - 1_700_000_000 % day;
+        let visit = |stats: &mut CustomerMerchantStats, now, every_purchase| {
+            stats.record_visit(3, 7 * day as u32, every_purchase, now).unwrap()
+        };
+
+        assert!(!visit(&mut stats, start, false));
+        // A second purchase the same day is not another visit unless every purchase counts
+        assert!(!visit(&mut stats, start + 3_600, false));
+        assert_eq!(stats.visit_count, 1);
+        assert!(!visit(&mut stats, start + 7_200, true));
+        assert_eq!(stats.visit_count, 2);
+        assert!(visit(&mut stats, start + day, false));
+        assert_eq!((stats.visit_count, stats.streaks_completed), (0, 1));
+
+        // Two visits, then the window runs out and the next visit starts over
+        assert!(!visit(&mut stats, start + 2 * day, false));
+        assert!(!visit(&mut stats, start + 3 * day, false));
+        assert!(!visit(&mut stats, start + 9 * day, false));
+        assert_eq!((stats.visit_count, stats.window_start), (1, start + 9 * day));
+    }
+
+    #[test]
+    fn transaction_stubs_only_move_forward() {
+        use TransactionStatus::*;
+        let stub = |status| TransactionStub {
+            customer: Pubkey::new_unique(),
+            merchant: Pubkey::new_unique(),
+            status,
+            timestamp: 100,
+            bump: 0,
+        };
+        let moved = |from, to| {
+            let mut stub = stub(from);
+            stub.transition(to, 200).map(|()| (stub.status, stub.timestamp))
+        };
+
+        // Completed, then fulfilled, disputed and either refunded or resolved
+        assert_eq!(moved(Completed, Fulfilled), Ok((Fulfilled, 200)));
+        assert_eq!(moved(Completed, Disputed), Ok((Disputed, 200)));
+        assert_eq!(moved(Completed, Refunded), Ok((Refunded, 200)));
+        assert_eq!(moved(Fulfilled, Disputed), Ok((Disputed, 200)));
+        assert_eq!(moved(Fulfilled, Refunded), Ok((Refunded, 200)));
+        assert_eq!(moved(Disputed, Refunded), Ok((Refunded, 200)));
+        assert_eq!(moved(Disputed, Resolved), Ok((Resolved, 200)));
+
+        for from in [Refunded, Resolved] {
+            for to in [Completed, Fulfilled, Disputed, Refunded, Resolved] {
+                assert_eq!(moved(from, to), Err(CarsaError::TransactionFinalized.into()));
+            }
+        }
+        assert_eq!(
+            moved(Disputed, Disputed),
+            Err(CarsaError::TransactionAlreadyDisputed.into())
+        );
+        for from in [Completed, Fulfilled] {
+            assert_eq!(moved(from, Resolved), Err(CarsaError::TransactionNotDisputed.into()));
+        }
+        for (from, to) in [
+            (Completed, Completed),
+            (Fulfilled, Fulfilled),
+            (Fulfilled, Completed),
+            (Disputed, Fulfilled),
+            (Disputed, Completed),
+        ] {
+            assert_eq!(moved(from, to), Err(CarsaError::InvalidTransactionTransition.into()));
+        }
+    }
+
+    #[test]
+    fn category_boost_pays_only_while_live_and_within_budget() {
+        let mut boost = CategoryBoost {
+            category: MerchantAccount::category_bytes("bookstore"),
+            bonus_bps: 200,
+            start: 100,
+            end: 200,
+            budget: 1_000,
+            spent: 0,
+            bump: 0,
+        };
+
+        assert_eq!(boost.boost_reward(600, 99), 0);
+        assert_eq!(boost.boost_reward(600, 100), 600);
+        assert_eq!(boost.boost_reward(600, 200), 0);
+        // The last of the budget clamps the reward instead of skipping it
+        boost.spent = 600;
+        assert_eq!(boost.boost_reward(600, 150), 400);
+        boost.spent = 1_000;
+        assert_eq!(boost.boost_reward(600, 150), 0);
+    }
+
+    #[test]
+    fn reference_codes_are_base32_of_the_record_address_hash() {
+        // sha256 of 32 zero bytes starts 66 68 7a ad f8
+        assert_eq!(&ReferenceIndex::reference_code(&Pubkey::default()), b"CSM7NBFR");
+
+        let record = Pubkey::new_unique();
+        let code = ReferenceIndex::reference_code(&record);
+        assert_eq!(code, ReferenceIndex::reference_code(&record));
+        assert!(code.iter().all(|character| ReferenceIndex::ALPHABET.contains(character)));
+        assert_ne!(code, ReferenceIndex::reference_code(&Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn purchase_records_resolve_under_either_seed_scheme() {
+        let merchant = Pubkey::new_unique();
+        let customer = Pubkey::new_unique();
+        let transaction_id = [7; 32];
+        let (address, bump) = Pubkey::find_program_address(
+            &[TRANSACTION_SEED, merchant.as_ref(), customer.as_ref(), &transaction_id],
+            &crate::ID,
+        );
+        let (legacy_address, legacy_bump) = Pubkey::find_program_address(
+            &[TRANSACTION_SEED, customer.as_ref(), &transaction_id],
+            &crate::ID,
+        );
+
+        let record = PurchaseTransaction {
+            customer,
+            merchant,
+            fiat_amount: 50_000,
+            redeemed_token_amount: 0,
+            total_value: 50_000,
+            reward_amount: 0,
+            cashback_rate: 500,
+            used_tokens: false,
+            timestamp: 0,
+            transaction_id,
+            bump,
+            staker_boost_bps: 0,
+            protocol_fee: 0,
+            redemption_bonus_bps: 0,
+            invoice: Pubkey::default(),
+            disputed: false,
+            refunded: false,
+            fulfilled_at: 0,
+            category_boost_bps: 0,
+            category_boost_reward: 0,
+            streak_bonus: 0,
+            reference_code: [0; 8],
+            reserved: [0; 4],
+        };
+        assert!(record.is_at(&address));
+        assert!(!record.is_at(&Pubkey::new_unique()));
+
+        let legacy = PurchaseTransaction { bump: legacy_bump, ..record.clone() };
+        assert!(legacy.is_at(&legacy_address));
+
+        // Another merchant's record for the same id lives elsewhere
+        let elsewhere = PurchaseTransaction { merchant: Pubkey::new_unique(), ..record };
+        assert!(!elsewhere.is_at(&address));
+    }
+
+    /// Borsh bytes of a pool written before `PoolState` became zero-copy, field by field
+    /// in the old declaration order, with distinct values so a shifted field shows up
+    fn legacy_pool_state_snapshot() -> Vec<u8> {
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        let mut put = |bytes: &[u8]| data.extend_from_slice(bytes);
+        for key in 1..=4u8 {
+            put(&[key; 32]);
+        }
+        // config
+        put(&10u64.to_le_bytes());
+        put(&11u64.to_le_bytes());
+        put(&[1, 0]);
+        put(&12u16.to_le_bytes());
+        put(&[1]);
+        put(&13u32.to_le_bytes());
+        put(&14u16.to_le_bytes());
+        put(&15u16.to_le_bytes());
+        put(&16u64.to_le_bytes());
+        for threshold in [17u64, 18, 19] {
+            put(&threshold.to_le_bytes());
+        }
+        for multiplier in [20u16, 21, 22] {
+            put(&multiplier.to_le_bytes());
+        }
+        put(&23u32.to_le_bytes());
+        put(&[1]);
+        put(&24u16.to_le_bytes());
+        put(&[5; 32]);
+        put(&25u64.to_le_bytes());
+        put(&[0]);
+        put(&26u32.to_le_bytes());
+        put(&[1]);
+        put(&27u16.to_le_bytes());
+        // totals and indexes
+        put(&30u64.to_le_bytes());
+        put(&31u64.to_le_bytes());
+        put(&32u64.to_le_bytes());
+        put(&33u64.to_le_bytes());
+        put(&34u128.to_le_bytes());
+        put(&35i64.to_le_bytes());
+        put(&36i64.to_le_bytes());
+        put(&7u16.to_le_bytes());
+        put(&37u64.to_le_bytes());
+        put(&[6; 32]);
+        put(&38u64.to_le_bytes());
+        put(&[7; 32]);
+        put(&[0, 1]);
+        put(&39u128.to_le_bytes());
+        put(&40u64.to_le_bytes());
+        put(&41u128.to_le_bytes());
+        put(&42u128.to_le_bytes());
+        put(&[254]);
+        put(&[8; 32]);
+        put(&[9; 32]);
+        put(&43u64.to_le_bytes());
+        put(&44u64.to_le_bytes());
+        put(&45u128.to_le_bytes());
+        put(&46u64.to_le_bytes());
+        put(&47u64.to_le_bytes());
+        put(&48u64.to_le_bytes());
+        put(&49u64.to_le_bytes());
+        put(&[0; 32]);
+        data
+    }
+
+    fn assert_legacy_pool_state(pool: &PoolState) {
+        let PoolState { config, .. } = *pool;
+        assert_eq!(pool.pool_authority, Pubkey::new_from_array([1; 32]));
+        assert_eq!(pool.voucher_mint, Pubkey::new_from_array([4; 32]));
+        assert_eq!({ config.min_stake_amount }, 10);
+        assert!(config.deposits_enabled && !config.withdrawals_enabled);
+        assert_eq!({ config.tier_thresholds }, [17, 18, 19]);
+        assert_eq!({ config.tier_multipliers_bps }, [20, 21, 22]);
+        assert_eq!(config.fee_recipient, Pubkey::new_from_array([5; 32]));
+        assert!(!config.queued_withdrawals && config.record_history);
+        assert_eq!({ config.insurance_fee_bps }, 27);
+        assert_eq!({ pool.total_voucher_staked }, 30);
+        assert_eq!({ pool.reward_index }, 34);
+        assert_eq!({ pool.pool_id }, 7);
+        assert_eq!(pool.guardian, Pubkey::new_from_array([7; 32]));
+        assert!(!pool.deposits_paused && pool.withdrawals_paused);
+        assert_eq!({ pool.weighted_total_staked }, 42);
+        assert_eq!(pool.bump, 254);
+        assert_eq!(pool.receipt_mint, Pubkey::new_from_array([9; 32]));
+        assert_eq!({ pool.last_verified_index }, 45);
+        assert_eq!({ pool.event_sequence }, 49);
+        assert_eq!({ pool.advance_ltv_bps }, 0);
+        assert_eq!({ pool.total_advanced }, 0);
+        assert_eq!({ pool.last_external_sequence }, 0);
+        assert!(!pool.mint_keyed);
+        assert_eq!(pool.reserved, [0; 13]);
+    }
+
+    #[test]
+    fn legacy_pool_state_loads_through_the_zero_copy_loader() {
+        let mut data = legacy_pool_state_snapshot();
+        assert_eq!(data.len(), PoolState::LEN);
+
+        assert_legacy_pool_state(&PoolState::try_deserialize(&mut data.as_slice()).unwrap());
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        let loader = AccountLoader::<PoolState>::try_from(&info).unwrap();
+        assert_legacy_pool_state(&loader.load().unwrap());
+
+        // Writes land on the old offsets, so older clients keep decoding the account
+        loader.load_mut().unwrap().next_event_sequence().unwrap();
+        let config_bytes = &data[8 + 4 * 32..8 + 4 * 32 + 122];
+        let config = PoolConfig::deserialize(&mut &config_bytes[..]).unwrap();
+        assert_eq!(config.try_to_vec().unwrap(), config_bytes);
+        assert_eq!(data[PoolState::LEN - 40..PoolState::LEN - 32], 50u64.to_le_bytes());
+    }
+
+    /// Borsh bytes of the original singleton pool as first deployed, before the config
+    /// grew and multi-pool support was added
+    fn baseline_pool_state_snapshot() -> Vec<u8> {
+        let mut data = PoolState::DISCRIMINATOR.to_vec();
+        let mut put = |bytes: &[u8]| data.extend_from_slice(bytes);
+        for key in 1..=4u8 {
+            put(&[key; 32]);
+        }
+        put(&10u64.to_le_bytes());
+        put(&11u64.to_le_bytes());
+        put(&[1, 0]);
+        put(&12u16.to_le_bytes());
+        put(&30u64.to_le_bytes());
+        put(&31u64.to_le_bytes());
+        put(&32u64.to_le_bytes());
+        put(&33u64.to_le_bytes());
+        put(&34u128.to_le_bytes());
+        put(&35i64.to_le_bytes());
+        put(&36i64.to_le_bytes());
+        put(&[254]);
+        put(&[0; 64]);
+        data
+    }
+
+    #[test]
+    fn baseline_pool_state_migrates_to_the_current_layout() {
+        let data = baseline_pool_state_snapshot();
+        assert_eq!(data.len(), LegacyPoolState::LEN);
+        assert_eq!(LegacyPoolState::LEN, 285);
+        assert!(data.len() < PoolState::LEN);
+
+        let legacy = LegacyPoolState::from_account_data(&data).unwrap();
+        let receipt_mint = Pubkey::new_unique();
+        let pool = legacy.migrate(receipt_mint).unwrap();
+        let PoolState { config, .. } = pool;
+        assert_eq!(pool.pool_authority, Pubkey::new_from_array([1; 32]));
+        assert_eq!(pool.voucher_mint, Pubkey::new_from_array([4; 32]));
+        assert_eq!(({ config.min_stake_amount }, { config.max_stake_per_user }), (10, 11));
+        assert!(config.deposits_enabled && !config.withdrawals_enabled);
+        assert_eq!({ config.apy_basis_points }, 12);
+        assert!(config.tiers_valid() && config.fee_valid());
+        assert_eq!(({ pool.total_voucher_staked }, { pool.total_stakers }), (30, 33));
+        assert_eq!({ pool.weighted_total_staked }, 30);
+        assert_eq!({ pool.reward_index }, 34_000_000);
+        assert_eq!(({ pool.created_at }, { pool.last_yield_update }), (35, 36));
+        assert_eq!(({ pool.pool_id }, pool.bump), (0, 254));
+        assert_eq!(pool.receipt_mint, receipt_mint);
+        assert_eq!({ pool.event_sequence }, 0);
+        // The migrated pool keeps the addresses it was created at
+        assert!(!pool.mint_keyed && pool.mint_seed().is_empty());
+
+        // The rewritten account loads through the zero-copy loader
+        let mut migrated = PoolState::DISCRIMINATOR.to_vec();
+        migrated.extend_from_slice(anchor_lang::__private::bytemuck::bytes_of(&pool));
+        assert_eq!(migrated.len(), PoolState::LEN);
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut migrated, &crate::ID, false, 0);
+        let loader = AccountLoader::<PoolState>::try_from(&info).unwrap();
+        assert_eq!({ loader.load().unwrap().total_voucher_staked }, 30);
+
+        // Pools already in the current layout are not taken for legacy ones
+        assert!(LegacyPoolState::from_account_data(&legacy_pool_state_snapshot()).is_none());
+    }
+
+    #[test]
+    fn baseline_config_migrates_to_the_current_layout() {
+        let mint = Pubkey::new_unique();
+        let update_authority = Pubkey::new_unique();
+        let mut data = LokalMintConfig::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(mint.as_ref());
+        data.push(254);
+        data.push(253);
+        data.extend_from_slice(update_authority.as_ref());
+        data.extend_from_slice(&42_000u64.to_le_bytes());
+        data.extend_from_slice(&[0; 64]);
+        assert_eq!(data.len(), LegacyLokalMintConfig::LEN);
+        assert_eq!(LegacyLokalMintConfig::LEN, 146);
+
+        // The current layout cannot read the legacy account at all
+        assert!(LokalMintConfig::try_deserialize(&mut data.as_slice()).is_err());
+
+        let legacy = LegacyLokalMintConfig::from_account_data(&data).unwrap();
+        let config = legacy.migrate(6);
+        assert_eq!((config.mint, config.update_authority), (mint, update_authority));
+        assert_eq!((config.mint_authority_bump, config.config_bump), (254, 253));
+        assert_eq!((config.total_supply, config.decimals()), (42_000, 6));
+        assert_eq!(config.token_to_idr_rate, LokalMintConfig::DEFAULT_TOKEN_TO_IDR_RATE);
+        assert_eq!(config.dispute_window_seconds, LokalMintConfig::DEFAULT_DISPUTE_WINDOW_SECONDS);
+        assert_eq!(config.max_cashback_bps, LokalMintConfig::DEFAULT_MAX_CASHBACK_BPS);
+        assert!(config.allows_transfer(LokalMintConfig::DEFAULT_MAX_TRANSFER_AMOUNT));
+        assert!(!config.allows_mint(LokalMintConfig::DEFAULT_MAX_MINT_PER_TX + 1));
+        assert!(!config.paused);
+
+        let mut migrated = Vec::new();
+        config.try_serialize(&mut migrated).unwrap();
+        assert_eq!(migrated.len(), LokalMintConfig::LEN);
+        assert!(LegacyLokalMintConfig::from_account_data(&migrated).is_none());
+        let decoded = LokalMintConfig::try_deserialize(&mut migrated.as_slice()).unwrap();
+        assert_eq!(decoded.total_supply, 42_000);
+    }
+
+    #[test]
+    fn baseline_merchant_migrates_to_the_current_layout() {
+        let wallet = Pubkey::new_unique();
+        let mut data = MerchantAccount::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(wallet.as_ref());
+        data.extend_from_slice(&MerchantAccount::name_bytes("Warung Bu Sri"));
+        data.extend_from_slice(&MerchantAccount::category_bytes("restaurant"));
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&12u64.to_le_bytes());
+        data.extend_from_slice(&3_000u64.to_le_bytes());
+        data.extend_from_slice(&600_000u64.to_le_bytes());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.push(251);
+        data.extend_from_slice(&[0; 32]);
+        assert_eq!(data.len(), LegacyMerchantAccount::LEN);
+        assert_eq!(LegacyMerchantAccount::LEN, 156);
+
+        let merchant = LegacyMerchantAccount::from_account_data(&data).unwrap().migrate();
+        assert_eq!(merchant.merchant_wallet, wallet);
+        assert_eq!(merchant.name, MerchantAccount::name_bytes("Warung Bu Sri"));
+        assert_eq!(merchant.category, MerchantAccount::category_bytes("restaurant"));
+        assert_eq!((merchant.cashback_rate, merchant.is_active), (500, true));
+        assert_eq!(
+            (merchant.total_transactions, merchant.total_rewards_distributed, merchant.total_volume),
+            (12, 3_000, 600_000)
+        );
+        assert_eq!((merchant.created_at, merchant.bump), (1_700_000_000, 251));
+        assert!(!merchant.suspended && !merchant.pending_activation);
+        assert_eq!(merchant.parent_merchant, Pubkey::default());
+
+        let mut migrated = Vec::new();
+        merchant.try_serialize(&mut migrated).unwrap();
+        assert_eq!(migrated.len(), MerchantAccount::LEN);
+        assert!(LegacyMerchantAccount::from_account_data(&migrated).is_none());
+        let decoded = MerchantAccount::try_deserialize(&mut migrated.as_slice()).unwrap();
+        assert_eq!(decoded.total_volume, 600_000);
+    }
+
+    #[test]
+    fn legacy_purchase_record_migrates_to_the_current_layout() {
+        let customer = Pubkey::new_unique();
+        let merchant = Pubkey::new_unique();
+        let transaction_id = [7; 32];
+        let mut data = PurchaseTransaction::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(customer.as_ref());
+        data.extend_from_slice(merchant.as_ref());
+        data.extend_from_slice(&100_000u64.to_le_bytes());
+        data.extend_from_slice(&20u64.to_le_bytes());
+        data.extend_from_slice(&120_000u64.to_le_bytes());
+        data.extend_from_slice(&5u64.to_le_bytes());
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        data.extend_from_slice(&transaction_id);
+        data.push(254);
+        data.extend_from_slice(&[0; 16]);
+        assert_eq!(data.len(), LegacyPurchaseTransaction::LEN);
+        assert_eq!(LegacyPurchaseTransaction::LEN, 164);
+        assert!(PurchaseTransaction::try_deserialize(&mut data.as_slice()).is_err());
+
+        let record = LegacyPurchaseTransaction::from_account_data(&data).unwrap().migrate();
+        assert_eq!((record.customer, record.merchant), (customer, merchant));
+        assert_eq!(
+            (record.fiat_amount, record.redeemed_token_amount, record.total_value),
+            (100_000, 20, 120_000)
+        );
+        assert_eq!((record.reward_amount, record.cashback_rate), (5, 500));
+        assert!(record.used_tokens);
+        assert_eq!(
+            (record.timestamp, record.transaction_id, record.bump),
+            (1_700_000_000, transaction_id, 254)
+        );
+        assert_eq!((record.staker_boost_bps, record.protocol_fee), (0, 0));
+        assert!(!record.disputed && !record.refunded);
+        assert_eq!((record.invoice, record.fulfilled_at), (Pubkey::default(), 0));
+
+        let mut migrated = Vec::new();
+        record.try_serialize(&mut migrated).unwrap();
+        assert_eq!(migrated.len(), PurchaseTransaction::LEN);
+        assert!(LegacyPurchaseTransaction::from_account_data(&migrated).is_none());
+        let decoded = PurchaseTransaction::try_deserialize(&mut migrated.as_slice()).unwrap();
+        assert_eq!(decoded.total_value, 120_000);
+    }
+
+    #[test]
+    fn baseline_stake_record_migrates_to_the_current_layout() {
+        let user = Pubkey::new_unique();
+        let pool = Pubkey::new_unique();
+        let mut data = UserStakeRecord::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(user.as_ref());
+        data.extend_from_slice(pool.as_ref());
+        data.extend_from_slice(&500u64.to_le_bytes());
+        data.extend_from_slice(&7_000_000_000_000u128.to_le_bytes());
+        data.extend_from_slice(&20u64.to_le_bytes());
+        data.extend_from_slice(&100i64.to_le_bytes());
+        data.extend_from_slice(&200i64.to_le_bytes());
+        data.push(252);
+        data.extend_from_slice(&[0; 32]);
+        assert_eq!(data.len(), LegacyUserStakeRecord::LEN);
+        assert_eq!(LegacyUserStakeRecord::LEN, 153);
+
+        let delegate = Pubkey::new_unique();
+        let legacy = LegacyUserStakeRecord::from_account_data(&data).unwrap();
+        let record = legacy.migrate(delegate).unwrap();
+        assert_eq!((record.user, record.pool), (user, pool));
+        assert_eq!((record.staked_amount, record.weighted_stake), (500, 500));
+        assert_eq!(record.user_reward_index, 7 * REWARD_INDEX_SCALE);
+        assert_eq!(record.total_yield_claimed, 20);
+        assert_eq!((record.staked_at, record.last_deposit_at), (100, 200));
+        assert_eq!((record.lock_until, record.locked_amount), (0, 0));
+        assert_eq!((record.bump, record.rent_payer), (252, delegate));
+
+        // Yield accrued under the old scale is still owed after both indices are rescaled
+        let legacy_pool_index = 9 * LEGACY_REWARD_INDEX_SCALE;
+        let legacy_yield =
+            500 * (legacy_pool_index - legacy.user_reward_index) / LEGACY_REWARD_INDEX_SCALE;
+        let pool_index = rescale_legacy_index(legacy_pool_index).unwrap();
+        assert_eq!(record.accrued_since_checkpoint(pool_index, 0), Some(legacy_yield as u64));
+        assert_eq!(legacy_yield, 1000);
+
+        let mut migrated = Vec::new();
+        record.try_serialize(&mut migrated).unwrap();
+        assert_eq!(migrated.len(), UserStakeRecord::LEN);
+        assert!(LegacyUserStakeRecord::from_account_data(&migrated).is_none());
+        let decoded = UserStakeRecord::try_deserialize(&mut migrated.as_slice()).unwrap();
+        assert_eq!(decoded.staked_amount, 500);
+    }
+
+    #[test]
+    fn slim_purchase_records_take_less_rent() {
+        let record = PurchaseTransactionV2 {
+            customer: Pubkey::new_unique(),
+            merchant: Pubkey::new_unique(),
+            fiat_amount: 100_000,
+            redeemed_token_amount: 0,
+            reward_amount: 5_000_000_000,
+            protocol_fee: 0,
+            timestamp: 1_700_000_000,
+            fulfilled_at: 0,
+            transaction_id: PurchaseTransactionV2::short_id(&[7; 32]),
+            invoice: Pubkey::default(),
+            staker_boost_bps: 0,
+            redemption_bonus_bps: 0,
+            bump: 255,
+            disputed: false,
+            refunded: false,
+            category_boost_reward: 0,
+            streak_bonus: 0,
+            reference_code: [0; 8],
+        };
+        let mut data = Vec::new();
+        record.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PurchaseTransactionV2::LEN);
+        assert_eq!(PurchaseTransactionV2::LEN, 199);
+        assert_eq!(PurchaseTransaction::LEN, 232);
+
+        let rent = Rent::default();
+        let v1 = rent.minimum_balance(PurchaseTransaction::LEN);
+        let v2 = rent.minimum_balance(PurchaseTransactionV2::LEN);
+        assert!(v2 < v1);
+        println!(
+            "PurchaseTransaction: {} bytes, {} lamports; PurchaseTransactionV2: {} bytes, {} lamports; \
+             {} lamports saved per purchase",
+            PurchaseTransaction::LEN,
+            v1,
+            PurchaseTransactionV2::LEN,
+            v2,
+            v1 - v2,
+        );
+    }
+
+    #[test]
+    fn legacy_merchant_registry_loads_through_the_zero_copy_loader() {
+        let mut data = MerchantRegistry::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&12u64.to_le_bytes());
+        data.push(253);
+        data.extend_from_slice(&[0; 32]);
+        assert_eq!(data.len(), MerchantRegistry::LEN);
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &crate::ID, false, 0);
+        let registry = AccountLoader::<MerchantRegistry>::try_from(&info).unwrap();
+        let registry = registry.load().unwrap();
+        assert_eq!(({ registry.merchant_count }, registry.bump), (12, 253));
+    }
+}
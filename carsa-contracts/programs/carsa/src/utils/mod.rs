@@ -0,0 +1 @@
+pub mod remaining_accounts;
@@ -0,0 +1,280 @@
+//! Checked, typed access to `ctx.remaining_accounts` for batch instructions
+//! Every account must be writable, owned by the expected program and of the expected type,
+//! and a batch may hold at most its instruction's limit
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use anchor_spl::{token, token_2022};
+use crate::error::CarsaError;
+use crate::state::*;
+
+/// Require at most `max` accounts, failing with the batch's own `too_large` error
+pub fn check_batch_len(accounts: &[AccountInfo], max: usize, too_large: CarsaError) -> Result<()> {
+    if accounts.len() > max {
+        return Err(too_large.into());
+    }
+    Ok(())
+}
+
+/// Require a writable account owned by one of `owners`
+fn check_writable_owned_by(account_info: &AccountInfo, owners: &[Pubkey]) -> Result<()> {
+    require!(account_info.is_writable, CarsaError::RemainingAccountNotWritable);
+    require!(
+        owners.contains(account_info.owner),
+        CarsaError::RemainingAccountOwnerMismatch
+    );
+    Ok(())
+}
+
+/// Writable program accounts of type `T`, at most `max` of them
+/// Relationships between the accounts and the instruction are left to the caller
+pub fn expect_program_accounts<'info, T>(
+    accounts: &'info [AccountInfo<'info>],
+    max: usize,
+    too_large: CarsaError,
+) -> Result<impl Iterator<Item = Result<Account<'info, T>>>>
+where
+    T: AccountSerialize + AccountDeserialize + Owner + Clone,
+{
+    check_batch_len(accounts, max, too_large)?;
+    Ok(accounts.iter().map(|account_info| {
+        check_writable_owned_by(account_info, &[T::owner()])?;
+        Account::try_from(account_info).map_err(|_| error!(CarsaError::InvalidRemainingAccount))
+    }))
+}
+
+/// Writable token accounts of `mint` under either token program, at most `max` of them
+pub fn expect_token_accounts<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    mint: Pubkey,
+    max: usize,
+    too_large: CarsaError,
+) -> Result<impl Iterator<Item = Result<InterfaceAccount<'info, TokenAccount>>>> {
+    check_batch_len(accounts, max, too_large)?;
+    Ok(accounts.iter().map(move |account_info| {
+        check_writable_owned_by(account_info, &[token::ID, token_2022::ID])?;
+        let token_account: InterfaceAccount<TokenAccount> = InterfaceAccount::try_from(account_info)
+            .map_err(|_| error!(CarsaError::InvalidRemainingAccount))?;
+        require_keys_eq!(token_account.mint, mint, CarsaError::MintMismatch);
+        Ok(token_account)
+    }))
+}
+
+/// Writable stake records of `pool`, each at its stake PDA, at most `max` of them
+pub fn expect_stake_records<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    pool: Pubkey,
+    max: usize,
+    too_large: CarsaError,
+) -> Result<impl Iterator<Item = Result<Account<'info, UserStakeRecord>>>> {
+    Ok(expect_program_accounts::<UserStakeRecord>(accounts, max, too_large)?.map(move |record| {
+        let record = record?;
+        let expected = Pubkey::create_program_address(
+            &[USER_STAKE_SEED, pool.as_ref(), record.user.as_ref(), &[record.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| error!(CarsaError::InvalidStakeRecord))?;
+        require!(
+            record.pool == pool && expected == record.key(),
+            CarsaError::InvalidStakeRecord
+        );
+        Ok(record)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_option::COption;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+    /// Backing storage for an `AccountInfo` passed as a remaining account
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        is_writable: bool,
+    }
+
+    impl TestAccount {
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                self.is_writable,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+
+        fn read_only(mut self) -> Self {
+            self.is_writable = false;
+            self
+        }
+    }
+
+    /// A stake record of `pool` at its stake PDA
+    fn stake_record(pool: Pubkey) -> TestAccount {
+        let user = Pubkey::new_unique();
+        let (key, bump) = Pubkey::find_program_address(
+            &[USER_STAKE_SEED, pool.as_ref(), user.as_ref()],
+            &crate::ID,
+        );
+        let record = UserStakeRecord {
+            user,
+            pool,
+            staked_amount: 1_000,
+            user_reward_index: 0,
+            total_yield_claimed: 0,
+            staked_at: 0,
+            last_action_at: 0,
+            bump,
+            lock_until: 0,
+            user_loss_index: 0,
+            weighted_stake: 1_000,
+            pending_payout: 0,
+            last_deposit_at: 0,
+            auto_compound: false,
+            last_compound_rate: 0,
+            total_compounded: 0,
+            action_count: 0,
+            pending_yield: 0,
+            pool_share_bps: 0,
+            view_synced_at: 0,
+            rent_payer: Pubkey::default(),
+            funded_by: Pubkey::default(),
+            locked_amount: 0,
+        };
+        let mut data = Vec::new();
+        record.try_serialize(&mut data).unwrap();
+        TestAccount { key, owner: crate::ID, lamports: 0, data, is_writable: true }
+    }
+
+    /// A classic SPL token account of `mint`
+    fn token_account(mint: Pubkey) -> TestAccount {
+        let account = SplAccount {
+            mint,
+            owner: Pubkey::new_unique(),
+            amount: 0,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        let mut data = vec![0; SplAccount::LEN];
+        SplAccount::pack(account, &mut data).unwrap();
+        TestAccount { key: Pubkey::new_unique(), owner: token::ID, lamports: 0, data, is_writable: true }
+    }
+
+    fn error_code<T>(result: Result<T>) -> u32 {
+        match result {
+            Err(Error::AnchorError(error)) => error.error_code_number,
+            Err(error) => panic!("unexpected error {error:?}"),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    fn stake_records_error(accounts: &mut [TestAccount], pool: Pubkey, max: usize) -> u32 {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        error_code(
+            expect_stake_records(&infos, pool, max, CarsaError::SettleBatchTooLarge)
+                .and_then(|records| records.collect::<Result<Vec<_>>>()),
+        )
+    }
+
+    #[test]
+    fn stake_records_of_the_pool_load() {
+        let pool = Pubkey::new_unique();
+        let mut accounts = [stake_record(pool), stake_record(pool)];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let records = expect_stake_records(&infos, pool, 2, CarsaError::SettleBatchTooLarge)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|record| record.pool == pool));
+    }
+
+    #[test]
+    fn rejects_batches_over_the_limit() {
+        let pool = Pubkey::new_unique();
+        let mut accounts = [stake_record(pool), stake_record(pool), stake_record(pool)];
+        assert_eq!(
+            stake_records_error(&mut accounts, pool, 2),
+            u32::from(CarsaError::SettleBatchTooLarge)
+        );
+    }
+
+    #[test]
+    fn rejects_read_only_accounts() {
+        let pool = Pubkey::new_unique();
+        let mut accounts = [stake_record(pool), stake_record(pool).read_only()];
+        assert_eq!(
+            stake_records_error(&mut accounts, pool, 2),
+            u32::from(CarsaError::RemainingAccountNotWritable)
+        );
+    }
+
+    #[test]
+    fn rejects_accounts_owned_by_another_program() {
+        let pool = Pubkey::new_unique();
+        let mut accounts = [stake_record(pool), token_account(Pubkey::new_unique())];
+        assert_eq!(
+            stake_records_error(&mut accounts, pool, 2),
+            u32::from(CarsaError::RemainingAccountOwnerMismatch)
+        );
+
+        let mint = Pubkey::new_unique();
+        let mut accounts = [token_account(mint), stake_record(Pubkey::new_unique())];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        assert_eq!(
+            error_code(
+                expect_token_accounts(&infos, mint, 2, CarsaError::SettleBatchTooLarge)
+                    .and_then(|accounts| accounts.collect::<Result<Vec<_>>>()),
+            ),
+            u32::from(CarsaError::RemainingAccountOwnerMismatch)
+        );
+    }
+
+    #[test]
+    fn rejects_program_accounts_of_another_type_or_pool() {
+        let pool = Pubkey::new_unique();
+
+        // Owned by the program, but not a stake record
+        let mut other_type = stake_record(pool);
+        other_type.data[..8].copy_from_slice(MerchantAccount::DISCRIMINATOR);
+        let mut accounts = [other_type];
+        assert_eq!(
+            stake_records_error(&mut accounts, pool, 2),
+            u32::from(CarsaError::InvalidRemainingAccount)
+        );
+
+        let mut accounts = [stake_record(pool), stake_record(Pubkey::new_unique())];
+        assert_eq!(
+            stake_records_error(&mut accounts, pool, 2),
+            u32::from(CarsaError::InvalidStakeRecord)
+        );
+    }
+
+    #[test]
+    fn token_accounts_must_hold_the_mint() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = [token_account(mint), token_account(Pubkey::new_unique())];
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+
+        let mut token_accounts =
+            expect_token_accounts(&infos, mint, 2, CarsaError::SettleBatchTooLarge).unwrap();
+        assert_eq!(token_accounts.next().unwrap().unwrap().mint, mint);
+        assert_eq!(
+            error_code(token_accounts.next().unwrap()),
+            u32::from(CarsaError::MintMismatch)
+        );
+    }
+}
@@ -35,6 +35,7 @@ import {
 } from "@solana/spl-token";
 import { Carsa } from "./target/types/carsa";
 import * as fs from "fs";
+import { createHash } from "crypto";
 
 // ============================================================================
 // Configuration
@@ -107,29 +108,6 @@ function calculateYield(
   return numerator / denominator;
 }
 
-/**
- * Read u64 from buffer (little-endian)
- */
-function readU64LE(buffer: Buffer, offset: number): bigint {
-  let value = BigInt(0);
-  for (let i = 0; i < 8; i++) {
-    value += BigInt(buffer[offset + i]) << BigInt(8 * i);
-  }
-  return value;
-}
-
-/**
- * Read i64 from buffer (little-endian)
- */
-function readI64LE(buffer: Buffer, offset: number): bigint {
-  const unsigned = readU64LE(buffer, offset);
-  // Convert to signed if MSB is set
-  if (unsigned >= BigInt(1) << BigInt(63)) {
-    return unsigned - (BigInt(1) << BigInt(64));
-  }
-  return unsigned;
-}
-
 // ============================================================================
 // Yield Recording Service
 // ============================================================================
@@ -190,23 +168,16 @@ class YieldRecordingService {
       throw new Error("Pool state account not found");
     }
 
-    const data = accountInfo.data;
+    // Decode through the IDL so the service keeps up with PoolState layout changes
+    const poolState = this.program.coder.accounts.decode(
+      "poolState",
+      accountInfo.data
+    );
 
-    // PoolState layout:
-    // discriminator(8) + pool_authority(32) + pool_delegate(32) + vault_ata(32) + 
-    // voucher_mint(32) + config(20) + total_voucher_staked(8) + total_sol_staked(8) +
-    // total_yield_earned(8) + reward_index(16) + last_yield_update(8) + total_stakers(8) + bump(1)
-
-    const totalStaked = readU64LE(data, 8 + 32 + 32 + 32 + 32 + 20); // offset 156
-    const totalSolStaked = readU64LE(data, 8 + 32 + 32 + 32 + 32 + 20 + 8); // offset 164
-    const totalYieldEarned = readU64LE(data, 8 + 32 + 32 + 32 + 32 + 20 + 8 + 8); // offset 172
-    
-    let rewardIndexValue = BigInt(0);
-    for (let i = 0; i < 16; i++) {
-      rewardIndexValue += BigInt(data[180 + i]) << BigInt(8 * i);
-    }
-    
-    const lastYieldUpdate = readI64LE(data, 196); // offset 196
+    const totalStaked = BigInt(poolState.totalVoucherStaked.toString());
+    const totalYieldEarned = BigInt(poolState.totalYieldEarned.toString());
+    const rewardIndexValue = BigInt(poolState.rewardIndex.toString());
+    const lastYieldUpdate = BigInt(poolState.lastYieldUpdate.toString());
 
     return {
       totalStaked,
@@ -269,12 +240,28 @@ class YieldRecordingService {
 
       // Execute record_yield instruction
       const poolStatePDA = getPoolStatePDA(this.program.programId);
+
+      // The batch id is derived from the period start, so a retry of the same
+      // period hits the same batch marker and cannot double count
+      const batchId = createHash("sha256")
+        .update(`${poolStatePDA.toBase58()}:${poolState.lastYieldUpdate}`)
+        .digest();
+      const [yieldBatchPDA] = PublicKey.findProgramAddressSync(
+        [Buffer.from("yield_batch"), poolStatePDA.toBuffer(), batchId],
+        this.program.programId
+      );
       
       console.log("\n🚀 Executing record_yield instruction...");
       const tx = await this.program.methods
-        .recordYield(new anchor.BN(yieldAmount.toString()))
+        .recordYield(
+          new anchor.BN(yieldAmount.toString()),
+          Array.from(batchId),
+          new anchor.BN(poolState.rewardIndex.toString())
+        )
         .accounts({
           poolDelegate: this.poolDelegate.publicKey,
+          poolState: poolStatePDA,
+          yieldBatch: yieldBatchPDA,
         })
         .signers([this.poolDelegate])
         .rpc();
@@ -0,0 +1,326 @@
+/**
+ * Activity Feed Test
+ *
+ * Covers the shared activity records written for purchases, redemptions and transfers
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountInstruction,
+  createAccount,
+  getAssociatedTokenAddress,
+} from "@solana/spl-token";
+
+describe("Activity Feed", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  // Matches the ActivityKind variant order, used as the last PDA seed byte
+  const PURCHASE = 0;
+  const TRANSFER = 1;
+  const REDEMPTION = 2;
+
+  const FIAT_AMOUNT = new anchor.BN(50_000); // Rp 50,000
+  const REDEEM_AMOUNT = new anchor.BN(5 * 1e9);
+  const TRANSFER_AMOUNT = new anchor.BN(10 * 1e9);
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+  let friend: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let friendTokenAccount: PublicKey;
+
+  const airdrop = async (key: PublicKey) =>
+    provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(key, 5 * anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+  const activityPDA = (wallet: PublicKey, transactionId: number[], kind: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("activity"),
+        wallet.toBuffer(),
+        Buffer.from(transactionId),
+        Buffer.from([kind]),
+      ],
+      program.programId
+    )[0];
+
+  const setRecording = (enabled: boolean) =>
+    program.methods
+      .setActivityRecording(enabled)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  const purchase = async (redeemAmount: anchor.BN | null, withActivity = true) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(FIAT_AMOUNT, redeemAmount, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: withActivity
+          ? activityPDA(customer.publicKey, transactionId, PURCHASE)
+          : null,
+        redemptionActivityRecord:
+          withActivity && redeemAmount
+            ? activityPDA(customer.publicKey, transactionId, REDEMPTION)
+            : null,
+      })
+      .signers([customer])
+      .rpc();
+
+    return { transactionId, transactionRecord };
+  };
+
+  const transfer = async (withActivity = true) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transferRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transfer"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .transferTokens(TRANSFER_AMOUNT, transactionId, "lunch")
+      .accounts({
+        sender: customer.publicKey,
+        senderTokenAccount: customerTokenAccount,
+        recipientTokenAccount: friendTokenAccount,
+        config: configPda,
+        transferRecord,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        activityRecord: withActivity
+          ? activityPDA(customer.publicKey, transactionId, TRANSFER)
+          : null,
+      })
+      .signers([customer])
+      .rpc();
+
+    return { transactionId };
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+    friend = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer, friend]) {
+      await airdrop(keypair.publicKey);
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    await program.methods
+      .registerMerchant("Warung Feed", "restaurant", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+
+    customerTokenAccount = await getAssociatedTokenAddress(
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    friendTokenAccount = await getAssociatedTokenAddress(
+      mintKeypair.publicKey,
+      friend.publicKey
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          customer.publicKey,
+          customerTokenAccount,
+          customer.publicKey,
+          mintKeypair.publicKey
+        ),
+        createAssociatedTokenAccountInstruction(
+          customer.publicKey,
+          friendTokenAccount,
+          friend.publicKey,
+          mintKeypair.publicKey
+        )
+      ),
+      [customer]
+    );
+
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * 1e9))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Rejects activity records while recording is disabled", async () => {
+    try {
+      await purchase(null);
+      expect.fail("Should have failed with recording disabled");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidActivityRecord");
+    }
+
+    await purchase(null, false);
+  });
+
+  it("Only the update authority can enable recording", async () => {
+    try {
+      await program.methods
+        .setActivityRecording(true)
+        .accounts({
+          authority: merchantOwner.publicKey,
+          config: configPda,
+        })
+        .signers([merchantOwner])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    await setRecording(true);
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.recordActivity).to.be.true;
+  });
+
+  it("Requires activity records while recording is enabled", async () => {
+    try {
+      await purchase(null, false);
+      expect.fail("Should have failed without the activity record");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidActivityRecord");
+    }
+
+    try {
+      await transfer(false);
+      expect.fail("Should have failed without the activity record");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidActivityRecord");
+    }
+  });
+
+  it("Returns purchases, redemptions and transfers from one filtered scan", async () => {
+    const plain = await purchase(null);
+    const redeeming = await purchase(REDEEM_AMOUNT);
+    const sent = await transfer();
+
+    // One getProgramAccounts call: ActivityRecord discriminator + wallet at offset 8
+    const entries = await program.account.activityRecord.all([
+      { memcmp: { offset: 8, bytes: customer.publicKey.toBase58() } },
+    ]);
+
+    expect(entries).to.have.length(4);
+    for (const { account } of entries) {
+      expect(account.wallet.toBase58()).to.equal(customer.publicKey.toBase58());
+    }
+
+    const find = (transactionId: number[], kind: string) =>
+      entries.find(
+        ({ account }) =>
+          Buffer.from(account.transactionId).equals(Buffer.from(transactionId)) &&
+          Object.keys(account.kind)[0] === kind
+      )?.account;
+
+    const plainRecord = await program.account.purchaseTransaction.fetch(
+      plain.transactionRecord
+    );
+    const plainEntry = find(plain.transactionId, "purchase");
+    expect(plainEntry).to.not.be.undefined;
+    expect(plainEntry.counterparty.toBase58()).to.equal(merchantAccountPda.toBase58());
+    expect(plainEntry.amount.toString()).to.equal(plainRecord.rewardAmount.toString());
+
+    expect(find(redeeming.transactionId, "purchase")).to.not.be.undefined;
+    const redemptionEntry = find(redeeming.transactionId, "redemption");
+    expect(redemptionEntry).to.not.be.undefined;
+    expect(redemptionEntry.counterparty.toBase58()).to.equal(merchantAccountPda.toBase58());
+    expect(redemptionEntry.amount.toString()).to.equal(REDEEM_AMOUNT.toString());
+
+    const transferEntry = find(sent.transactionId, "transfer");
+    expect(transferEntry).to.not.be.undefined;
+    expect(transferEntry.counterparty.toBase58()).to.equal(friend.publicKey.toBase58());
+    expect(transferEntry.amount.toString()).to.equal(TRANSFER_AMOUNT.toString());
+  });
+});
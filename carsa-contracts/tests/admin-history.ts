@@ -0,0 +1,311 @@
+/**
+ * Admin Mutation History Test
+ *
+ * Covers rebuilding a merchant's and a pool's settings from their mutation events alone,
+ * using the old and new value each event carries per field
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createMint,
+} from "@solana/spl-token";
+import { defaultPoolConfig, parseCpiEvents, toConfigUpdate } from "../voucher-pool-client";
+
+describe("Admin Mutation History", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 26;
+
+  const MERCHANT_FIELDS = [
+    "cashbackRate",
+    "isActive",
+    "minPurchaseAmount",
+    "redemptionBonusBps",
+    "requireMerchantSignature",
+    "compressedReceipts",
+  ];
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let poolAuthority: Keypair;
+  let guardian: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let merchantAccount: PublicKey;
+  let poolState: PublicKey;
+
+  const poolIdSeed = Buffer.alloc(2);
+  poolIdSeed.writeUInt16LE(POOL_ID);
+
+  // BNs and public keys compare by their JSON form
+  const normalize = (value: unknown) => JSON.parse(JSON.stringify(value));
+
+  const eventsOf = async (signature: string, name: string) => {
+    await provider.connection.confirmTransaction(signature, "confirmed");
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return parseCpiEvents(program, tx).filter((event) => event.name.toLowerCase() === name);
+  };
+
+  /**
+   * Folds `Changed` fields into a running state, checking each old value continues the
+   * history so far, and returns the state after every event
+   */
+  const replay = (events: anchor.Event[], fields: string[]) => {
+    let state: Record<string, unknown> | null = null;
+    const history: Record<string, unknown>[] = [];
+    for (const event of events) {
+      const old = Object.fromEntries(fields.map((field) => [field, event.data[field].old]));
+      if (state) {
+        expect(normalize(old)).to.deep.equal(normalize(state));
+      }
+      state = Object.fromEntries(fields.map((field) => [field, event.data[field].new]));
+      history.push(state);
+    }
+    return history;
+  };
+
+  const updateMerchant = (
+    cashbackRate: number | null,
+    isActive: boolean | null,
+    minPurchaseAmount: number | null,
+    redemptionBonusBps: number | null
+  ) =>
+    program.methods
+      .updateMerchant(
+        cashbackRate,
+        isActive,
+        minPurchaseAmount === null ? null : new anchor.BN(minPurchaseAmount),
+        redemptionBonusBps,
+        null,
+        null
+      )
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount,
+        config: configPda,
+      })
+      .signers([merchantOwner])
+      .rpc({ commitment: "confirmed" });
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    poolAuthority = Keypair.generate();
+    guardian = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, poolAuthority]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    [merchantAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Riwayat", "retail", 300, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    const voucherMint = await createMint(
+      provider.connection,
+      poolAuthority,
+      poolAuthority.publicKey,
+      null,
+      9
+    );
+    [poolState] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_state"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    const [poolVaultAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_vault_authority"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    const [receiptMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt_mint"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    await program.methods
+      .initializePool(POOL_ID, defaultPoolConfig())
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolDelegate: poolAuthority.publicKey,
+        poolState,
+        vaultAta: getAssociatedTokenAddressSync(voucherMint, poolVaultAuthority, true),
+        poolVaultAuthority,
+        voucherMint,
+        receiptMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolAuthority])
+      .rpc();
+  });
+
+  it("Rebuilds a merchant's settings history from its update events", async () => {
+    const initial = await program.account.merchantAccount.fetch(merchantAccount);
+    const signatures: string[] = [];
+    const snapshots: Record<string, unknown>[] = [];
+    const record = async (signature: string) => {
+      signatures.push(signature);
+      const merchant = await program.account.merchantAccount.fetch(merchantAccount);
+      snapshots.push(Object.fromEntries(MERCHANT_FIELDS.map((field) => [field, merchant[field]])));
+    };
+
+    await record(await updateMerchant(450, null, null, null));
+    await record(await updateMerchant(null, false, 10_000, null));
+    await record(await updateMerchant(450, true, null, 500));
+    await record(await updateMerchant(200, null, 0, 0));
+
+    const events = [];
+    for (const signature of signatures) {
+      events.push(...(await eventsOf(signature, "merchantupdatedevent")));
+    }
+    expect(events).to.have.length(signatures.length);
+    for (const event of events) {
+      expect(event.data.merchant.toBase58()).to.equal(merchantAccount.toBase58());
+    }
+
+    // The first event's old values are the settings at registration
+    const registered = Object.fromEntries(
+      MERCHANT_FIELDS.map((field) => [field, events[0].data[field].old])
+    );
+    expect(normalize(registered)).to.deep.equal(
+      normalize(Object.fromEntries(MERCHANT_FIELDS.map((field) => [field, initial[field]])))
+    );
+    expect(normalize(replay(events, MERCHANT_FIELDS))).to.deep.equal(normalize(snapshots));
+  });
+
+  it("Rebuilds a pool's configuration, guardian and pause history from its events", async () => {
+    const snapshots: Record<string, unknown>[] = [];
+    const events: anchor.Event[] = [];
+    const history: Record<string, unknown>[] = [];
+    let state: Record<string, unknown> = {};
+
+    // Each pool event changes a subset of the fields; the rest carry over
+    const apply = async (signature: string, name: string, fields: string[]) => {
+      const [event] = await eventsOf(signature, name);
+      expect(event.data.pool.toBase58()).to.equal(poolState.toBase58());
+      events.push(event);
+
+      for (const field of fields) {
+        if (field in state) {
+          expect(normalize(event.data[field].old)).to.deep.equal(normalize(state[field]));
+        }
+      }
+      state = {
+        ...state,
+        ...Object.fromEntries(fields.map((field) => [field, event.data[field].new])),
+      };
+      history.push(state);
+
+      const pool = await program.account.poolState.fetch(poolState);
+      snapshots.push(
+        Object.fromEntries(Object.keys(state).map((field) => [field, pool[field]]))
+      );
+    };
+
+    const updateConfig = (update: Parameters<typeof toConfigUpdate>[0]) =>
+      program.methods
+        .updatePoolConfig(toConfigUpdate(update))
+        .accounts({ poolAuthority: poolAuthority.publicKey, poolState })
+        .signers([poolAuthority])
+        .rpc({ commitment: "confirmed" });
+
+    const setPause = (pauseDeposits: boolean | null, pauseWithdrawals: boolean | null) =>
+      program.methods
+        .setPoolPause(pauseDeposits, pauseWithdrawals)
+        .accounts({ authority: poolAuthority.publicKey, poolState })
+        .signers([poolAuthority])
+        .rpc({ commitment: "confirmed" });
+
+    const pauseFields = ["depositsPaused", "withdrawalsPaused"];
+    await apply(await updateConfig({ apyBasisPoints: 800 }), "poolconfigupdatedevent", ["config"]);
+    await apply(
+      await program.methods
+        .setPoolGuardian(guardian.publicKey)
+        .accounts({ poolAuthority: poolAuthority.publicKey, poolState })
+        .signers([poolAuthority])
+        .rpc({ commitment: "confirmed" }),
+      "poolguardianupdatedevent",
+      ["guardian"]
+    );
+    await apply(await setPause(true, null), "poolpausedevent", pauseFields);
+    await apply(
+      await updateConfig({ lockupSeconds: 3_600, depositsEnabled: false }),
+      "poolconfigupdatedevent",
+      ["config"]
+    );
+    await apply(await setPause(false, true), "poolpausedevent", pauseFields);
+
+    // Per-pool event sequence numbers are gapless, so nothing was missed in between
+    const sequences = events.map((event) => event.data.sequence.toNumber());
+    for (let i = 1; i < sequences.length; i++) {
+      expect(sequences[i]).to.equal(sequences[i - 1] + 1);
+    }
+    expect(normalize(history)).to.deep.equal(normalize(snapshots));
+  });
+});
@@ -0,0 +1,278 @@
+/**
+ * Airdrop Test
+ *
+ * Covers merkle airdrops: allocations are minted when a wallet proves its leaf against
+ * the published root, once per wallet and within the budget, until the airdrop expires
+ * and the authority sweeps it
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { createHash } from "crypto";
+
+describe("Airdrops", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let alice: Keypair;
+  let bob: Keypair;
+  let carol: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  const tokenAccounts = new Map<string, PublicKey>();
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  // Mirrors `Airdrop::leaf` and `Airdrop::verify_proof`
+  const sha256 = (...parts: Buffer[]) =>
+    createHash("sha256").update(Buffer.concat(parts)).digest();
+
+  const leaf = (wallet: PublicKey, amount: number) =>
+    sha256(Buffer.from([0]), wallet.toBuffer(), new anchor.BN(amount).toArrayLike(Buffer, "le", 8));
+
+  const parent = (a: Buffer, b: Buffer) =>
+    Buffer.compare(a, b) <= 0
+      ? sha256(Buffer.from([1]), a, b)
+      : sha256(Buffer.from([1]), b, a);
+
+  // Builds the tree bottom-up, pairing an odd node with itself, and returns the root
+  // with a proof per allocation
+  const merkleTree = (allocations: [PublicKey, number][]) => {
+    let level = allocations.map(([wallet, amount]) => leaf(wallet, amount));
+    const proofs: Buffer[][] = level.map(() => []);
+    let positions = level.map((_, index) => index);
+
+    while (level.length > 1) {
+      const next: Buffer[] = [];
+      for (let index = 0; index < level.length; index += 2) {
+        next.push(parent(level[index], level[index + 1] ?? level[index]));
+      }
+      positions = positions.map((position, index) => {
+        const sibling = position ^ 1;
+        proofs[index].push(level[sibling] ?? level[position]);
+        return position >> 1;
+      });
+      level = next;
+    }
+
+    return { root: level[0], proofs: proofs.map((proof) => proof.map((node) => Array.from(node))) };
+  };
+
+  const airdropPda = (airdropId: number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop"), new anchor.BN(airdropId).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const createAirdrop = async (
+    airdropId: number,
+    root: Buffer,
+    budget: number,
+    lifetimeSeconds: number
+  ) =>
+    program.methods
+      .createAirdrop(
+        new anchor.BN(airdropId),
+        Array.from(root),
+        new anchor.BN(budget),
+        new anchor.BN((await clusterTime()) + lifetimeSeconds)
+      )
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        airdrop: airdropPda(airdropId),
+        systemProgram: SystemProgram.programId,
+        cosigner: null,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  const claim = (airdropId: number, claimant: Keypair, amount: number, proof: number[][]) => {
+    const airdrop = airdropPda(airdropId);
+    const [airdropClaim] = PublicKey.findProgramAddressSync(
+      [Buffer.from("airdrop_claim"), airdrop.toBuffer(), claimant.publicKey.toBuffer()],
+      program.programId
+    );
+    return program.methods
+      .claimAirdrop(new anchor.BN(amount), proof)
+      .accounts({
+        claimant: claimant.publicKey,
+        claimantTokenAccount: tokenAccounts.get(claimant.publicKey.toBase58()),
+        airdrop,
+        airdropClaim,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([claimant])
+      .rpc();
+  };
+
+  const sweep = (airdropId: number) =>
+    program.methods
+      .sweepAirdrop()
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        airdrop: airdropPda(airdropId),
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    alice = Keypair.generate();
+    bob = Keypair.generate();
+    carol = Keypair.generate();
+
+    for (const keypair of [updateAuthority, alice, bob, carol]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    for (const keypair of [alice, bob, carol]) {
+      tokenAccounts.set(
+        keypair.publicKey.toBase58(),
+        await createAccount(provider.connection, keypair, mintKeypair.publicKey, keypair.publicKey)
+      );
+    }
+  });
+
+  it("Mints proven allocations once each, within the budget", async () => {
+    const allocations: [PublicKey, number][] = [
+      [alice.publicKey, 100 * TOKEN],
+      [bob.publicKey, 200 * TOKEN],
+      [carol.publicKey, 300 * TOKEN],
+    ];
+    const { root, proofs } = merkleTree(allocations);
+    // Deliberately short of the 600 tokens allocated
+    await createAirdrop(1, root, 500 * TOKEN, 600);
+
+    const aliceTokens = tokenAccounts.get(alice.publicKey.toBase58());
+    await claim(1, alice, 100 * TOKEN, proofs[0]);
+    expect(await balance(aliceTokens)).to.equal(100 * TOKEN);
+
+    try {
+      await claim(1, alice, 100 * TOKEN, proofs[0]);
+      expect.fail("Should have failed for a second claim");
+    } catch (error) {
+      expect(error.toString()).to.include("already in use");
+    }
+
+    try {
+      await claim(1, bob, 250 * TOKEN, proofs[1]);
+      expect.fail("Should have failed for a tampered amount");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidMerkleProof");
+    }
+
+    try {
+      await claim(1, carol, 200 * TOKEN, proofs[1]);
+      expect.fail("Should have failed for someone else's proof");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidMerkleProof");
+    }
+
+    await claim(1, bob, 200 * TOKEN, proofs[1]);
+    const airdrop = await program.account.airdrop.fetch(airdropPda(1));
+    expect(airdrop.claimedAmount.toNumber()).to.equal(300 * TOKEN);
+
+    try {
+      await claim(1, carol, 300 * TOKEN, proofs[2]);
+      expect.fail("Should have failed beyond the budget");
+    } catch (error) {
+      expect(error.toString()).to.include("AirdropBudgetExceeded");
+    }
+  });
+
+  it("Refuses claims after expiry and lets the authority sweep the rest", async () => {
+    const { root, proofs } = merkleTree([[carol.publicKey, 10 * TOKEN]]);
+    await createAirdrop(2, root, 10 * TOKEN, 3);
+
+    try {
+      await sweep(2);
+      expect.fail("Should have failed before expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("AirdropNotExpired");
+    }
+
+    const airdrop = await program.account.airdrop.fetch(airdropPda(2));
+    await waitForClusterTime(airdrop.expiresAt.toNumber());
+
+    try {
+      await claim(2, carol, 10 * TOKEN, proofs[0]);
+      expect.fail("Should have failed after expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("AirdropExpired");
+    }
+
+    const carolTokens = tokenAccounts.get(carol.publicKey.toBase58());
+    const supplyBefore = (await program.account.lokalMintConfig.fetch(configPda)).totalSupply;
+    await sweep(2);
+    expect(await program.account.airdrop.fetchNullable(airdropPda(2))).to.be.null;
+    expect(await balance(carolTokens)).to.equal(0);
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.totalSupply.toString()).to.equal(supplyBefore.toString());
+  });
+});
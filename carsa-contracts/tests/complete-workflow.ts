@@ -105,7 +105,7 @@ describe("Carsa Comprehensive Client Tests", () => {
 
     // Register first merchant (Coffee Shop with 5% cashback)
     const registerMerchant1Tx = await program.methods
-      .registerMerchant("Coffee Shop", "restaurant", 500)
+      .registerMerchant("Coffee Shop", "restaurant", 500, new anchor.BN(0))
       .accounts({
         merchantOwner: merchant1.publicKey,
         merchantAccount: merchant1AccountPda,
@@ -125,7 +125,7 @@ describe("Carsa Comprehensive Client Tests", () => {
 
     // Register second merchant (Book Store with 3% cashback)
     const registerMerchant2Tx = await program.methods
-      .registerMerchant("Book Store", "retail", 300)
+      .registerMerchant("Book Store", "retail", 300, new anchor.BN(0))
       .accounts({
         merchantOwner: merchant2.publicKey,
         merchantAccount: merchant2AccountPda,
@@ -218,7 +218,7 @@ describe("Carsa Comprehensive Client Tests", () => {
     const transaction1Id = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transaction1RecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer1.publicKey.toBuffer(), Buffer.from(transaction1Id)],
+      [Buffer.from("transaction"), merchant1AccountPda.toBuffer(), customer1.publicKey.toBuffer(), Buffer.from(transaction1Id)],
       program.programId
     );
 
@@ -230,7 +230,7 @@ describe("Carsa Comprehensive Client Tests", () => {
     const customer1BalanceBefore = await getAccount(provider.connection, customer1TokenAccount);
 
     const purchase1Tx = await program.methods
-      .processPurchase(purchase1Amount, null, transaction1Id)
+      .processPurchase(purchase1Amount, null, transaction1Id, null)
       .accounts({
         customer: customer1.publicKey,
         merchantAccount: merchant1AccountPda,
@@ -278,7 +278,7 @@ describe("Carsa Comprehensive Client Tests", () => {
     const transaction2Id = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transaction2RecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer1.publicKey.toBuffer(), Buffer.from(transaction2Id)],
+      [Buffer.from("transaction"), merchant1AccountPda.toBuffer(), customer1.publicKey.toBuffer(), Buffer.from(transaction2Id)],
       program.programId
     );
 
@@ -286,7 +286,7 @@ describe("Carsa Comprehensive Client Tests", () => {
     const merchant1BalanceBeforeRedemption = await getAccount(provider.connection, merchant1TokenAccount);
 
     const purchase2Tx = await program.methods
-      .processPurchase(purchase2Amount, redeemAmount, transaction2Id)
+      .processPurchase(purchase2Amount, redeemAmount, transaction2Id, null)
       .accounts({
         customer: customer1.publicKey,
         merchantAccount: merchant1AccountPda,
@@ -345,6 +345,7 @@ describe("Carsa Comprehensive Client Tests", () => {
         recipientTokenAccount: customer2TokenAccount,
         transferRecord: transferRecordPda,
         config: configPda,
+        mint: mintKeypair.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -377,7 +378,7 @@ describe("Carsa Comprehensive Client Tests", () => {
     const newCashbackRate = 750; // 7.5%
     
     const updateMerchantTx = await program.methods
-      .updateMerchant(newCashbackRate, null)
+      .updateMerchant(newCashbackRate, null, null, null, null, null)
       .accounts({
         merchantOwner: merchant1.publicKey,
         merchantAccount: merchant1AccountPda,
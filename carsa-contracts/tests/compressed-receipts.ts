@@ -0,0 +1,219 @@
+/**
+ * Compressed Receipts Test
+ *
+ * Covers the compressed receipt mode: purchases at a merchant that enables it create no
+ * purchase record, and instead extend a hash chain on the merchant account whose links
+ * are emitted as receipt events
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { createHash } from "crypto";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Compressed Receipts", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const u64 = (value: number | anchor.BN) => new anchor.BN(value).toArrayLike(Buffer, "le", 8);
+
+  // Mirrors `MerchantAccount::chain_receipt`
+  const chainReceipt = (previous: Buffer, receipt: any) =>
+    createHash("sha256")
+      .update(
+        Buffer.concat([
+          previous,
+          receipt.customer.toBuffer(),
+          u64(receipt.fiatAmount),
+          u64(receipt.redeemedTokenAmount),
+          u64(receipt.rewardAmount),
+          Buffer.from(receipt.transactionId),
+          u64(receipt.timestamp),
+        ])
+      )
+      .digest();
+
+  // Receipt events are emitted through a self-CPI, so they are read back from the
+  // transaction's inner instructions
+  const receiptEvents = async (signature: string) => {
+    const transaction = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const events = [];
+    for (const inner of transaction.meta.innerInstructions) {
+      for (const instruction of inner.instructions) {
+        const data = anchor.utils.bytes.bs58.decode(instruction.data);
+        const event = program.coder.events.decode(
+          anchor.utils.bytes.base64.encode(Buffer.from(data.subarray(8)))
+        );
+        if (event?.name === "purchaseReceiptEvent") {
+          events.push(event.data);
+        }
+      }
+    }
+    return events;
+  };
+
+  const purchase = async (fiatAmount: number, withRecord = false) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    return program.methods
+      .processPurchase(new anchor.BN(fiatAmount), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount: customerTokenAccount,
+        transactionRecord: withRecord ? transactionRecord : null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc({ commitment: "confirmed" });
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Warung Ramai", "restaurant", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    await program.methods
+      .updateMerchant(null, null, null, null, null, true)
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+  });
+
+  it("Rejects a purchase record for a merchant with compressed receipts", async () => {
+    try {
+      await purchase(10_000, true);
+      expect.fail("Should have failed with a purchase record");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTransactionRecord");
+    }
+  });
+
+  it("Chains every receipt into the merchant's hash", async () => {
+    let chain = Buffer.alloc(32);
+    for (const fiatAmount of [10_000, 25_000, 40_000, 5_000]) {
+      const events = await receiptEvents(await purchase(fiatAmount));
+      expect(events).to.have.length(1);
+      expect(events[0].fiatAmount.toNumber()).to.equal(fiatAmount);
+
+      chain = chainReceipt(chain, events[0]);
+      expect(Buffer.from(events[0].receiptChainHash)).to.deep.equal(chain);
+    }
+
+    const merchant = await program.account.merchantAccount.fetch(merchantAccountPda);
+    expect(Buffer.from(merchant.receiptChainHash)).to.deep.equal(chain);
+    expect(merchant.totalTransactions.toNumber()).to.equal(4);
+  });
+
+  it("Rejects signed approvals together with compressed receipts", async () => {
+    try {
+      await program.methods
+        .updateMerchant(null, null, null, null, true, null)
+        .accounts({
+          merchantOwner: merchantOwner.publicKey,
+          merchantAccount: merchantAccountPda,
+        })
+        .signers([merchantOwner])
+        .rpc();
+      expect.fail("Should have failed for signed approvals");
+    } catch (error) {
+      expect(error.toString()).to.include("CompressedReceiptsSignatureConflict");
+    }
+  });
+});
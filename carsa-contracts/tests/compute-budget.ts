@@ -0,0 +1,288 @@
+/**
+ * Compute Budget Test
+ *
+ * Guards the compute cost of the two hottest instructions, process_purchase and
+ * deposit_voucher: each must stay under a fixed ceiling and, without the
+ * `verbose-logs` feature, write no informational logs of its own
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY, Transaction } from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createAccount,
+  createApproveInstruction,
+  createMint,
+  mintTo,
+} from "@solana/spl-token";
+import { defaultPoolConfig } from "../voucher-pool-client";
+
+// Every `msg!` costs at least one 100 CU `sol_log_` syscall, before formatting. These are
+// the informational lines each instruction wrote before `verbose-logs` gated them
+const LOG_SYSCALL_CU = 100;
+const GATED_LOG_LINES = { processPurchase: 1, depositVoucher: 7 };
+
+// Compute units each instruction may use: the ceiling it had while it still logged, less
+// the logging it no longer does, so bringing the logs back fails the test. Lower the
+// ceilings as the instructions get cheaper; raising one needs a profile showing why.
+// Keep them in step with `COMPUTE_UNIT_CEILING` next to the Rust handlers
+const PROCESS_PURCHASE_CU_CEILING = 45_000 - GATED_LOG_LINES.processPurchase * LOG_SYSCALL_CU;
+const DEPOSIT_VOUCHER_CU_CEILING = 60_000 - GATED_LOG_LINES.depositVoucher * LOG_SYSCALL_CU;
+
+const POOL_ID = 25;
+
+/** Log lines the carsa program wrote itself, leaving out CPIs and Anchor's instruction name */
+const carsaLogs = (logs: string[], programId: PublicKey): string[] => {
+  const stack: string[] = [];
+  const own: string[] = [];
+  for (const line of logs) {
+    const invoke = line.match(/^Program (\w+) invoke/);
+    if (invoke) {
+      stack.push(invoke[1]);
+    } else if (/^Program \w+ (success|failed)/.test(line)) {
+      stack.pop();
+    } else if (
+      stack[stack.length - 1] === programId.toBase58() &&
+      line.startsWith("Program log: ") &&
+      !line.startsWith("Program log: Instruction: ")
+    ) {
+      own.push(line);
+    }
+  }
+  return own;
+};
+
+describe("Compute Budget", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Hemat", "grocery", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+  });
+
+  it("Keeps a fiat-only purchase under the compute ceiling", async () => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    const transaction = await program.methods
+      .processPurchase(new anchor.BN(150_000), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount: customerTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .transaction();
+    transaction.feePayer = customer.publicKey;
+    transaction.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+
+    const simulation = await provider.connection.simulateTransaction(transaction, [customer]);
+    expect(simulation.value.err).to.be.null;
+    expect(simulation.value.unitsConsumed).to.be.lessThan(PROCESS_PURCHASE_CU_CEILING);
+
+    // Detailed purchase logs are only built with the `verbose-logs` feature
+    expect(carsaLogs(simulation.value.logs, program.programId)).to.deep.equal([]);
+  });
+
+  it("Keeps a first deposit under the compute ceiling", async () => {
+    const poolAuthority = Keypair.generate();
+    const poolDelegate = Keypair.generate();
+    for (const keypair of [poolAuthority, poolDelegate]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    const voucherMint = await createMint(
+      provider.connection,
+      poolAuthority,
+      poolAuthority.publicKey,
+      null,
+      9
+    );
+    const userVoucherAta = await createAccount(
+      provider.connection,
+      customer,
+      voucherMint,
+      customer.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      poolAuthority,
+      voucherMint,
+      userVoucherAta,
+      poolAuthority,
+      100 * 1e9
+    );
+
+    const poolIdSeed = Buffer.alloc(2);
+    poolIdSeed.writeUInt16LE(POOL_ID);
+    const [poolState] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_state"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    const [poolVaultAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool_vault_authority"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    const [receiptMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("receipt_mint"), voucherMint.toBuffer(), poolIdSeed],
+      program.programId
+    );
+    const [userStakeRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("user_stake"), poolState.toBuffer(), customer.publicKey.toBuffer()],
+      program.programId
+    );
+    const poolVaultAta = getAssociatedTokenAddressSync(voucherMint, poolVaultAuthority, true);
+
+    await program.methods
+      .initializePool(POOL_ID, defaultPoolConfig({ apyBasisPoints: 0 }))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        vaultAta: poolVaultAta,
+        poolVaultAuthority,
+        voucherMint,
+        receiptMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createApproveInstruction(userVoucherAta, poolDelegate.publicKey, customer.publicKey, 10 * 1e9)
+      ),
+      [customer]
+    );
+
+    const transaction = await program.methods
+      .depositVoucher(new anchor.BN(10 * 1e9))
+      .accounts({
+        user: customer.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta: getAssociatedTokenAddressSync(receiptMint, customer.publicKey),
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .transaction();
+    transaction.feePayer = poolDelegate.publicKey;
+    transaction.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+
+    // The first deposit also creates the stake record and receipt account, the costliest path
+    const simulation = await provider.connection.simulateTransaction(transaction, [poolDelegate]);
+    expect(simulation.value.err).to.be.null;
+    expect(simulation.value.unitsConsumed).to.be.lessThan(DEPOSIT_VOUCHER_CU_CEILING);
+
+    // Informational logs are only built with the `verbose-logs` feature
+    expect(carsaLogs(simulation.value.logs, program.programId)).to.deep.equal([]);
+  });
+});
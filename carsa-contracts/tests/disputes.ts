@@ -0,0 +1,331 @@
+/**
+ * Dispute Test
+ *
+ * Covers the purchase dispute lifecycle: the customer opens a dispute within the
+ * window, the merchant responds and the update authority upholds or rejects it
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Disputes", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const REDEEMED_TOKENS = 10;
+  const REASON_NOT_MINE = 1;
+  const RESPONSE_RECEIPT_ON_FILE = 7;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const setDisputeWindow = (seconds: number) =>
+    program.methods
+      .setDisputeWindow(seconds)
+      .accounts({ authority: updateAuthority.publicKey, config: configPda })
+      .signers([updateAuthority])
+      .rpc();
+
+  const purchase = async () => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchaseWithRedemption(
+        new anchor.BN(50_000),
+        { tokenAmount: new anchor.BN(REDEEMED_TOKENS * TOKEN) },
+        transactionId,
+        null
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+        invoice: null,
+      })
+      .signers([customer])
+      .rpc();
+
+    const [dispute] = PublicKey.findProgramAddressSync(
+      [Buffer.from("dispute"), transactionRecord.toBuffer()],
+      program.programId
+    );
+    return { transactionRecord, dispute };
+  };
+
+  const openDispute = (transactionRecord: PublicKey, dispute: PublicKey) =>
+    program.methods
+      .openDispute(REASON_NOT_MINE)
+      .accounts({
+        customer: customer.publicKey,
+        config: configPda,
+        transactionRecord,
+        dispute,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc();
+
+  const respondDispute = (dispute: PublicKey) =>
+    program.methods
+      .respondDispute(RESPONSE_RECEIPT_ON_FILE)
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        config: configPda,
+        dispute,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+  const resolveDispute = (
+    transactionRecord: PublicKey,
+    dispute: PublicKey,
+    uphold: boolean,
+    signer: Keypair = updateAuthority
+  ) =>
+    program.methods
+      .resolveDispute(uphold)
+      .accounts({
+        authority: signer.publicKey,
+        config: configPda,
+        dispute,
+        transactionRecord,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        cosigner: null,
+      })
+      .signers([signer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Sengketa", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Opens with the default dispute window", async () => {
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.disputeWindowSeconds).to.equal(14 * 24 * 60 * 60);
+  });
+
+  it("Upholds a dispute, refunding the redeemed tokens exactly once", async () => {
+    const { transactionRecord, dispute } = await purchase();
+    await openDispute(transactionRecord, dispute);
+
+    let record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.disputed).to.be.true;
+    let opened = await program.account.dispute.fetch(dispute);
+    expect(opened.reasonCode).to.equal(REASON_NOT_MINE);
+    expect(opened.status).to.deep.equal({ open: {} });
+
+    // One dispute per purchase
+    try {
+      await openDispute(transactionRecord, dispute);
+      expect.fail("Should have failed for a second dispute on the purchase");
+    } catch (error) {
+      expect(error.toString()).to.include("already in use");
+    }
+
+    await respondDispute(dispute);
+    opened = await program.account.dispute.fetch(dispute);
+    expect(opened.responseCode).to.equal(RESPONSE_RECEIPT_ON_FILE);
+
+    try {
+      await resolveDispute(transactionRecord, dispute, true, merchantOwner);
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    const walletBefore = await balance(customerTokenAccount);
+    await resolveDispute(transactionRecord, dispute, true);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore + REDEEMED_TOKENS * TOKEN);
+
+    const resolved = await program.account.dispute.fetch(dispute);
+    expect(resolved.status).to.deep.equal({ upheld: {} });
+    expect(resolved.refundAmount.toNumber()).to.equal(REDEEMED_TOKENS * TOKEN);
+    record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.refunded).to.be.true;
+
+    // Repeating the decision changes nothing; reversing it is refused
+    await resolveDispute(transactionRecord, dispute, true);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore + REDEEMED_TOKENS * TOKEN);
+    try {
+      await resolveDispute(transactionRecord, dispute, false);
+      expect.fail("Should have failed for reversing the decision");
+    } catch (error) {
+      expect(error.toString()).to.include("DisputeAlreadyResolved");
+    }
+
+    try {
+      await respondDispute(dispute);
+      expect.fail("Should have failed for a resolved dispute");
+    } catch (error) {
+      expect(error.toString()).to.include("DisputeNotOpen");
+    }
+  });
+
+  it("Rejects a dispute without a refund", async () => {
+    const { transactionRecord, dispute } = await purchase();
+    await openDispute(transactionRecord, dispute);
+
+    const walletBefore = await balance(customerTokenAccount);
+    await resolveDispute(transactionRecord, dispute, false);
+    await resolveDispute(transactionRecord, dispute, false);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore);
+
+    const resolved = await program.account.dispute.fetch(dispute);
+    expect(resolved.status).to.deep.equal({ rejected: {} });
+    expect(resolved.refundAmount.toNumber()).to.equal(0);
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.refunded).to.be.false;
+  });
+
+  it("Cannot dispute a purchase outside the window", async () => {
+    await setDisputeWindow(2);
+    const { transactionRecord, dispute } = await purchase();
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    await waitForClusterTime(record.timestamp.toNumber() + 3);
+
+    try {
+      await openDispute(transactionRecord, dispute);
+      expect.fail("Should have failed outside the dispute window");
+    } catch (error) {
+      expect(error.toString()).to.include("DisputeWindowClosed");
+    }
+  });
+});
@@ -156,7 +156,7 @@ describe("Carsa Essential Client Tests", () => {
       const cashbackRate = 500; // 5%
 
       const tx = await program.methods
-        .registerMerchant(merchantName, merchantCategory, cashbackRate)
+        .registerMerchant(merchantName, merchantCategory, cashbackRate, new anchor.BN(0))
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -195,7 +195,7 @@ describe("Carsa Essential Client Tests", () => {
 
       // First register the merchant
       await program.methods
-        .registerMerchant("Update Test Shop", "service", 250) // 2.5%
+        .registerMerchant("Update Test Shop", "service", 250, new anchor.BN(0)) // 2.5%
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -207,7 +207,7 @@ describe("Carsa Essential Client Tests", () => {
       // Update cashback rate and status
       const newCashbackRate = 750; // 7.5%
       await program.methods
-        .updateMerchant(newCashbackRate, false) // Also deactivate
+        .updateMerchant(newCashbackRate, false, null, null, null, null) // Also deactivate
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -258,7 +258,7 @@ describe("Carsa Essential Client Tests", () => {
       );
 
       await program.methods
-        .registerMerchant("Reward Test Store", "retail", 300) // 3% cashback
+        .registerMerchant("Reward Test Store", "retail", 300, new anchor.BN(0)) // 3% cashback
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -272,7 +272,7 @@ describe("Carsa Essential Client Tests", () => {
       const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
       const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+        [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
         program.programId
       );
 
@@ -285,7 +285,7 @@ describe("Carsa Essential Client Tests", () => {
       const initialBalance = await getAccount(provider.connection, customerTokenAccount);
       
       const tx = await program.methods
-        .processPurchase(fiatAmount, null, transactionId)
+        .processPurchase(fiatAmount, null, transactionId, null)
         .accounts({
           customer: customer.publicKey,
           merchantAccount: merchantAccountPda,
@@ -392,7 +392,7 @@ describe("Carsa Essential Client Tests", () => {
       );
 
       await program.methods
-        .registerMerchant("Token Redemption Store", "retail", 400) // 4% cashback
+        .registerMerchant("Token Redemption Store", "retail", 400, new anchor.BN(0)) // 4% cashback
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -407,7 +407,7 @@ describe("Carsa Essential Client Tests", () => {
       const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
       const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+        [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
         program.programId
       );
 
@@ -415,7 +415,7 @@ describe("Carsa Essential Client Tests", () => {
       const initialMerchantBalance = await getAccount(provider.connection, merchantTokenAccount);
       
       const tx = await program.methods
-        .processPurchase(fiatAmount, redeemTokens, transactionId)
+        .processPurchase(fiatAmount, redeemTokens, transactionId, null)
         .accounts({
           customer: customer.publicKey,
           merchantAccount: merchantAccountPda,
@@ -540,6 +540,7 @@ describe("Carsa Essential Client Tests", () => {
           recipientTokenAccount: receiverTokenAccount,
           transferRecord: transferRecordPda,
           config: configPda,
+          mint: mintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -0,0 +1,232 @@
+/**
+ * Franchise Test
+ *
+ * Covers parent/child merchants: outlets register under a parent with its owner's
+ * co-signature, the parent cascades its default cashback rate to them, and their
+ * purchases roll up into the parent's aggregate counters
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Franchise", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let parentOwner: Keypair;
+  let customer: Keypair;
+  let outletOwners: Keypair[];
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let parentMerchantPda: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let outletAccounts: PublicKey[];
+
+  const merchantPda = (owner: Keypair) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), owner.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+  const registerOutlet = async (owner: Keypair, coSigner: Keypair) => {
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Outlet", "coffee_shop", 300, new anchor.BN(0))
+      .accounts({
+        merchantOwner: owner.publicKey,
+        merchantAccount: merchantPda(owner),
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+        parentMerchant: parentMerchantPda,
+        parentOwner: coSigner.publicKey,
+      })
+      .signers([owner, coSigner])
+      .rpc();
+  };
+
+  const purchase = async (merchantAccount: PublicKey, fiatAmount: number, withParent = true) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccount.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(new anchor.BN(fiatAmount), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount: customerTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        parentMerchant: withParent ? parentMerchantPda : null,
+      })
+      .signers([customer])
+      .rpc();
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    parentOwner = Keypair.generate();
+    customer = Keypair.generate();
+    outletOwners = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+
+    for (const keypair of [updateAuthority, parentOwner, customer, ...outletOwners]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [parentMerchantPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("parent_merchant"), parentOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    outletAccounts = outletOwners.map(merchantPda);
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    await program.methods
+      .createParentMerchant("Kopi Nusantara", 500)
+      .accounts({
+        owner: parentOwner.publicKey,
+        parentMerchant: parentMerchantPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([parentOwner])
+      .rpc();
+
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+  });
+
+  it("Rejects an outlet registration without the parent owner's signature", async () => {
+    try {
+      await registerOutlet(outletOwners[0], Keypair.generate());
+      expect.fail("Should have failed for a forged parent signature");
+    } catch (error) {
+      expect(error.toString()).to.include("ParentOwnerMismatch");
+    }
+  });
+
+  it("Registers outlets co-signed by the parent owner", async () => {
+    for (const owner of outletOwners) {
+      await registerOutlet(owner, parentOwner);
+    }
+
+    const parent = await program.account.parentMerchant.fetch(parentMerchantPda);
+    expect(parent.childCount).to.equal(3);
+    for (const outlet of outletAccounts) {
+      const merchant = await program.account.merchantAccount.fetch(outlet);
+      expect(merchant.parentMerchant.toString()).to.equal(parentMerchantPda.toString());
+      expect(merchant.cashbackRate).to.equal(300);
+    }
+  });
+
+  it("Cascades the parent's cashback rate to three outlets", async () => {
+    await program.methods
+      .updateParentMerchant(750)
+      .accounts({
+        owner: parentOwner.publicKey,
+        parentMerchant: parentMerchantPda,
+      })
+      .signers([parentOwner])
+      .rpc();
+
+    await program.methods
+      .cascadeCashbackRate()
+      .accounts({
+        owner: parentOwner.publicKey,
+        parentMerchant: parentMerchantPda,
+      })
+      .remainingAccounts(
+        outletAccounts.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+      )
+      .signers([parentOwner])
+      .rpc();
+
+    for (const outlet of outletAccounts) {
+      const merchant = await program.account.merchantAccount.fetch(outlet);
+      expect(merchant.cashbackRate).to.equal(750);
+    }
+  });
+
+  it("Rolls outlet purchases up into the parent's counters", async () => {
+    await purchase(outletAccounts[0], 40_000);
+    await purchase(outletAccounts[1], 25_000);
+    await purchase(outletAccounts[1], 10_000);
+    await purchase(outletAccounts[2], 60_000);
+
+    // An outlet's purchase cannot skip its parent
+    try {
+      await purchase(outletAccounts[2], 5_000, false);
+      expect.fail("Should have failed without the parent merchant");
+    } catch (error) {
+      expect(error.toString()).to.include("ParentMerchantMismatch");
+    }
+
+    const outlets = await Promise.all(
+      outletAccounts.map((outlet) => program.account.merchantAccount.fetch(outlet))
+    );
+    const parent = await program.account.parentMerchant.fetch(parentMerchantPda);
+    const sum = (field: string) =>
+      outlets.reduce((total, outlet) => total + outlet[field].toNumber(), 0);
+
+    expect(parent.totalTransactions.toNumber()).to.equal(sum("totalTransactions"));
+    expect(parent.totalVolume.toNumber()).to.equal(sum("totalVolume"));
+    expect(parent.totalRewardsDistributed.toNumber()).to.equal(sum("totalRewardsDistributed"));
+    expect(parent.totalVolume.toNumber()).to.equal(135_000);
+  });
+});
@@ -0,0 +1,242 @@
+/**
+ * Gift Card Test
+ *
+ * Covers gift cards: tokens escrowed behind the hash of a secret code, claimable once
+ * by whoever presents the code and reclaimable by the issuer after expiry
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { createHash, randomBytes } from "crypto";
+
+describe("Gift Cards", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const GIFT_TOKENS = 25;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let issuer: Keypair;
+  let claimer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let giftCardEscrow: PublicKey;
+  let issuerTokenAccount: PublicKey;
+  let claimerTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const createGiftCard = async (lifetimeSeconds = 600) => {
+    const code = randomBytes(16);
+    const codeHash = Array.from(createHash("sha256").update(code).digest());
+    const [giftCard] = PublicKey.findProgramAddressSync(
+      [Buffer.from("gift_card"), Buffer.from(codeHash)],
+      program.programId
+    );
+
+    await program.methods
+      .createGiftCard(
+        new anchor.BN(GIFT_TOKENS * TOKEN),
+        codeHash,
+        new anchor.BN((await clusterTime()) + lifetimeSeconds)
+      )
+      .accounts({
+        issuer: issuer.publicKey,
+        issuerTokenAccount,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        giftCardEscrow,
+        giftCard,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([issuer])
+      .rpc();
+
+    return { code, giftCard };
+  };
+
+  const redeem = (giftCard: PublicKey, code: Buffer) =>
+    program.methods
+      .redeemGiftCard(code)
+      .accounts({
+        claimer: claimer.publicKey,
+        claimerTokenAccount,
+        issuer: issuer.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        giftCardEscrow,
+        giftCard,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([claimer])
+      .rpc();
+
+  const reclaim = (giftCard: PublicKey) =>
+    program.methods
+      .reclaimGiftCard()
+      .accounts({
+        issuer: issuer.publicKey,
+        issuerTokenAccount,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        giftCardEscrow,
+        giftCard,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([issuer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    issuer = Keypair.generate();
+    claimer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, issuer, claimer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [giftCardEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("gift_card_escrow")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    issuerTokenAccount = await createAccount(
+      provider.connection,
+      issuer,
+      mintKeypair.publicKey,
+      issuer.publicKey
+    );
+    claimerTokenAccount = await createAccount(
+      provider.connection,
+      claimer,
+      mintKeypair.publicKey,
+      claimer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: issuerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Pays out to whoever presents the code, exactly once", async () => {
+    const issuerBefore = await balance(issuerTokenAccount);
+    const { code, giftCard } = await createGiftCard();
+    expect(await balance(issuerTokenAccount)).to.equal(issuerBefore - GIFT_TOKENS * TOKEN);
+    expect(await balance(giftCardEscrow)).to.equal(GIFT_TOKENS * TOKEN);
+
+    try {
+      await redeem(giftCard, randomBytes(16));
+      expect.fail("Should have failed for the wrong code");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidGiftCardCode");
+    }
+
+    await redeem(giftCard, code);
+    expect(await balance(claimerTokenAccount)).to.equal(GIFT_TOKENS * TOKEN);
+    expect(await balance(giftCardEscrow)).to.equal(0);
+    expect(await provider.connection.getAccountInfo(giftCard)).to.be.null;
+
+    try {
+      await redeem(giftCard, code);
+      expect.fail("Should have failed for a redeemed gift card");
+    } catch (error) {
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+    expect(await balance(claimerTokenAccount)).to.equal(GIFT_TOKENS * TOKEN);
+  });
+
+  it("Returns to the issuer only after expiry", async () => {
+    const issuerBefore = await balance(issuerTokenAccount);
+    const { code, giftCard } = await createGiftCard(3);
+
+    try {
+      await reclaim(giftCard);
+      expect.fail("Should have failed before expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("GiftCardNotExpired");
+    }
+
+    const { expiresAt } = await program.account.giftCard.fetch(giftCard);
+    await waitForClusterTime(expiresAt.toNumber());
+
+    try {
+      await redeem(giftCard, code);
+      expect.fail("Should have failed for an expired gift card");
+    } catch (error) {
+      expect(error.toString()).to.include("GiftCardExpired");
+    }
+
+    await reclaim(giftCard);
+    expect(await balance(issuerTokenAccount)).to.equal(issuerBefore);
+    expect(await provider.connection.getAccountInfo(giftCard)).to.be.null;
+  });
+});
@@ -0,0 +1,355 @@
+/**
+ * Guardian Test
+ *
+ * Covers the guardian key that can pause the program, pools and merchants but
+ * cannot unpause or change parameters
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, createMint } from "@solana/spl-token";
+import {
+  defaultPoolConfig,
+  getPoolStatePDA,
+  initializePool,
+  parseCpiEvents,
+} from "../voucher-pool-client";
+
+describe("Guardian", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let guardian: Keypair;
+  let poolAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+  let outsider: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let poolState: PublicKey;
+
+  const poolConfig = defaultPoolConfig();
+
+  const setGlobalPause = (paused: boolean, signer: Keypair) =>
+    program.methods
+      .setGlobalPause(paused)
+      .accounts({
+        authority: signer.publicKey,
+        config: configPda,
+      })
+      .signers([signer])
+      .rpc({ commitment: "confirmed" });
+
+  const setMerchantSuspension = (suspended: boolean, signer: Keypair) =>
+    program.methods
+      .setMerchantSuspension(suspended)
+      .accounts({
+        authority: signer.publicKey,
+        config: configPda,
+        merchantAccount: merchantAccountPda,
+      })
+      .signers([signer])
+      .rpc();
+
+  const setPoolPause = (paused: boolean, signer: Keypair) =>
+    program.methods
+      .setPoolPause(paused, paused)
+      .accounts({
+        authority: signer.publicKey,
+        poolState,
+        config: configPda,
+      })
+      .signers([signer])
+      .rpc();
+
+  const purchase = async () => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(new anchor.BN(10_000), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+      })
+      .signers([customer])
+      .rpc();
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    guardian = Keypair.generate();
+    poolAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+    outsider = Keypair.generate();
+
+    for (const keypair of [
+      updateAuthority,
+      guardian,
+      poolAuthority,
+      merchantOwner,
+      customer,
+      outsider,
+    ]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Guardian", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+
+    const voucherMint = await createMint(
+      provider.connection,
+      poolAuthority,
+      poolAuthority.publicKey,
+      null,
+      9
+    );
+    await initializePool(
+      program,
+      poolAuthority,
+      Keypair.generate().publicKey,
+      voucherMint,
+      poolConfig
+    );
+    [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  });
+
+  it("Only the update authority can set the guardian", async () => {
+    try {
+      await program.methods
+        .setGuardian(outsider.publicKey)
+        .accounts({ authority: outsider.publicKey, config: configPda })
+        .signers([outsider])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ authority: updateAuthority.publicKey, config: configPda })
+      .signers([updateAuthority])
+      .rpc();
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.guardian.toBase58()).to.equal(guardian.publicKey.toBase58());
+  });
+
+  it("Guardian pauses purchases and transfers but cannot unpause", async () => {
+    try {
+      await setGlobalPause(true, outsider);
+      expect.fail("Should have failed for a non-guardian signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedPauser");
+    }
+
+    const tx = await setGlobalPause(true, guardian);
+    const txDetails = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const event = parseCpiEvents(program, txDetails).find(
+      (event) => event.name.toLowerCase() === "globalpauseevent"
+    );
+    expect(event).to.not.be.undefined;
+    expect(event.data.paused.old).to.be.false;
+    expect(event.data.paused.new).to.be.true;
+    expect(event.data.triggeredBy.toBase58()).to.equal(guardian.publicKey.toBase58());
+
+    try {
+      await purchase();
+      expect.fail("Should have failed while paused");
+    } catch (error) {
+      expect(error.toString()).to.include("ProgramPaused");
+    }
+
+    try {
+      await setGlobalPause(false, guardian);
+      expect.fail("Should have failed for the guardian unpausing");
+    } catch (error) {
+      expect(error.toString()).to.include("GuardianCannotUnpause");
+    }
+
+    await setGlobalPause(false, updateAuthority);
+    await purchase();
+  });
+
+  it("Guardian suspends a merchant but cannot reinstate it", async () => {
+    await setMerchantSuspension(true, guardian);
+
+    try {
+      await purchase();
+      expect.fail("Should have failed at a suspended merchant");
+    } catch (error) {
+      expect(error.toString()).to.include("MerchantSuspended");
+    }
+
+    try {
+      await setMerchantSuspension(false, guardian);
+      expect.fail("Should have failed for the guardian reinstating");
+    } catch (error) {
+      expect(error.toString()).to.include("GuardianCannotUnpause");
+    }
+
+    await setMerchantSuspension(false, updateAuthority);
+    await purchase();
+  });
+
+  it("Guardian pauses a pool but cannot unpause it", async () => {
+    await setPoolPause(true, guardian);
+
+    let pool = await program.account.poolState.fetch(poolState);
+    expect(pool.depositsPaused).to.be.true;
+    expect(pool.withdrawalsPaused).to.be.true;
+
+    try {
+      await setPoolPause(false, guardian);
+      expect.fail("Should have failed for the guardian unpausing");
+    } catch (error) {
+      expect(error.toString()).to.include("GuardianCannotUnpause");
+    }
+
+    await setPoolPause(false, poolAuthority);
+    pool = await program.account.poolState.fetch(poolState);
+    expect(pool.depositsPaused).to.be.false;
+  });
+
+  it("Guardian cannot change parameters", async () => {
+    const [treasuryAuthority] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+    const treasuryTokenAccount = await createAccount(
+      provider.connection,
+      guardian,
+      mintKeypair.publicKey,
+      treasuryAuthority,
+      Keypair.generate()
+    );
+
+    try {
+      await program.methods
+        .setProtocolFee(500)
+        .accounts({
+          authority: guardian.publicKey,
+          config: configPda,
+          treasuryAuthority,
+          treasuryTokenAccount,
+        })
+        .signers([guardian])
+        .rpc();
+      expect.fail("Should have failed for the guardian");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    try {
+      await program.methods
+        .setGuardian(outsider.publicKey)
+        .accounts({ authority: guardian.publicKey, config: configPda })
+        .signers([guardian])
+        .rpc();
+      expect.fail("Should have failed for the guardian");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.protocolFeeBps).to.equal(0);
+    expect(config.guardian.toBase58()).to.equal(guardian.publicKey.toBase58());
+  });
+});
@@ -0,0 +1,293 @@
+/**
+ * Hold Test
+ *
+ * Covers pre-authorization holds: the customer escrows tokens when sitting down, the
+ * merchant captures the actual amount at the end and the rest goes back to the customer
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Holds", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const HELD_TOKENS = 20;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let holdEscrow: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const createHold = async (lifetimeSeconds = 600) => {
+    const holdId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [hold] = PublicKey.findProgramAddressSync(
+      [Buffer.from("hold"), customer.publicKey.toBuffer(), Buffer.from(holdId)],
+      program.programId
+    );
+
+    await program.methods
+      .createHold(
+        holdId,
+        new anchor.BN(HELD_TOKENS * TOKEN),
+        new anchor.BN((await clusterTime()) + lifetimeSeconds)
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        holdEscrow,
+        hold,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc();
+
+    return { hold, holdId };
+  };
+
+  const capture = async (hold: PublicKey, holdId: number[], captureTokens: number) => {
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(holdId)],
+      program.programId
+    );
+
+    await program.methods
+      .captureHold(new anchor.BN(40_000), new anchor.BN(captureTokens * TOKEN))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        hold,
+        customer: customer.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        holdEscrow,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    return transactionRecord;
+  };
+
+  const release = (hold: PublicKey) =>
+    program.methods
+      .releaseHold()
+      .accounts({
+        customer: customer.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        holdEscrow,
+        customerTokenAccount,
+        hold,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([customer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [holdEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("hold_escrow")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Rumah Makan Tahan", "restaurant", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Captures part of a hold as a purchase and releases the rest", async () => {
+    const walletBefore = await balance(customerTokenAccount);
+    const { hold, holdId } = await createHold();
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore - HELD_TOKENS * TOKEN);
+    expect(await balance(holdEscrow)).to.equal(HELD_TOKENS * TOKEN);
+
+    try {
+      await capture(hold, holdId, HELD_TOKENS + 1);
+      expect.fail("Should have failed for capturing more than held");
+    } catch (error) {
+      expect(error.toString()).to.include("HoldCaptureExceeded");
+    }
+
+    const transactionRecord = await capture(hold, holdId, 15);
+
+    // Same accounting as process_purchase: Rp 40,000 fiat plus 15 tokens at Rp 1,000
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.redeemedTokenAmount.toNumber()).to.equal(15 * TOKEN);
+    expect(record.totalValue.toNumber()).to.equal(40_000 + 15 * 1_000);
+    expect(record.usedTokens).to.be.true;
+
+    expect(await balance(merchantTokenAccount)).to.equal(15 * TOKEN);
+    expect(await balance(holdEscrow)).to.equal(0);
+    expect(await balance(customerTokenAccount)).to.equal(
+      walletBefore - 15 * TOKEN + record.rewardAmount.toNumber()
+    );
+    expect(await provider.connection.getAccountInfo(hold)).to.be.null;
+  });
+
+  it("Releases a hold to the customer only after expiry", async () => {
+    const walletBefore = await balance(customerTokenAccount);
+    const { hold } = await createHold(3);
+
+    try {
+      await release(hold);
+      expect.fail("Should have failed before expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("HoldNotExpired");
+    }
+
+    const { expiresAt } = await program.account.hold.fetch(hold);
+    await waitForClusterTime(expiresAt.toNumber());
+    await release(hold);
+
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore);
+    expect(await provider.connection.getAccountInfo(hold)).to.be.null;
+  });
+
+  it("Cannot be captured after expiry", async () => {
+    const { hold, holdId } = await createHold(3);
+    const { expiresAt } = await program.account.hold.fetch(hold);
+    await waitForClusterTime(expiresAt.toNumber());
+
+    try {
+      await capture(hold, holdId, 5);
+      expect.fail("Should have failed for an expired hold");
+    } catch (error) {
+      expect(error.toString()).to.include("HoldExpired");
+    }
+
+    await release(hold);
+  });
+});
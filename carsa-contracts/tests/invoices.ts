@@ -0,0 +1,305 @@
+/**
+ * Invoice Test
+ *
+ * Covers merchant invoices: a fixed amount the customer pays by reference, at most
+ * once and only before the invoice expires
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Invoices", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const INVOICE_AMOUNT = 75_000;
+  const TOKEN = 1e9;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const createInvoice = async (
+    maxRedeemTokens: number | null = null,
+    lifetimeSeconds = 600
+  ) => {
+    const referenceId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [invoice] = PublicKey.findProgramAddressSync(
+      [Buffer.from("invoice"), merchantAccountPda.toBuffer(), Buffer.from(referenceId)],
+      program.programId
+    );
+
+    await program.methods
+      .createInvoice(
+        referenceId,
+        new anchor.BN(INVOICE_AMOUNT),
+        maxRedeemTokens === null ? null : new anchor.BN(maxRedeemTokens * TOKEN),
+        new anchor.BN((await clusterTime()) + lifetimeSeconds)
+      )
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        invoice,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    return invoice;
+  };
+
+  const purchaseAccounts = (transactionRecord: PublicKey, invoice: PublicKey | null) => ({
+    customer: customer.publicKey,
+    merchantAccount: merchantAccountPda,
+    mint: mintKeypair.publicKey,
+    mintAuthority: mintAuthorityPda,
+    config: configPda,
+    customerTokenAccount,
+    merchantTokenAccount,
+    transactionRecord,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+    stakePool: null,
+    customerStakeRecord: null,
+    activityRecord: null,
+    redemptionActivityRecord: null,
+    treasuryTokenAccount: null,
+    priceFeed: null,
+    rewardEscrow: null,
+    rewardLot: null,
+    invoice,
+  });
+
+  const newTransaction = () => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+    return { transactionId, transactionRecord };
+  };
+
+  const payInvoice = async (
+    invoice: PublicKey,
+    amount = INVOICE_AMOUNT,
+    redeemTokens: number | null = null
+  ) => {
+    const { transactionId, transactionRecord } = newTransaction();
+
+    await program.methods
+      .payInvoice(
+        new anchor.BN(amount),
+        redeemTokens === null ? null : { tokenAmount: new anchor.BN(redeemTokens * TOKEN) },
+        transactionId
+      )
+      .accounts(purchaseAccounts(transactionRecord, invoice))
+      .signers([customer])
+      .rpc();
+
+    return transactionRecord;
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Faktur", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Rejects an invoice that is already expired", async () => {
+    try {
+      await createInvoice(null, -1);
+      expect.fail("Should have failed for an expiry in the past");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidInvoiceExpiry");
+    }
+  });
+
+  it("Settles an invoice for exactly its amount", async () => {
+    const invoice = await createInvoice();
+
+    try {
+      await payInvoice(invoice, INVOICE_AMOUNT - 1);
+      expect.fail("Should have failed for an amount other than the invoice's");
+    } catch (error) {
+      expect(error.toString()).to.include("InvoiceMismatch");
+    }
+
+    const transactionRecord = await payInvoice(invoice);
+
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.fiatAmount.toNumber()).to.equal(INVOICE_AMOUNT);
+    expect(record.invoice.toBase58()).to.equal(invoice.toBase58());
+
+    const paid = await program.account.invoice.fetch(invoice);
+    expect(paid.paid).to.be.true;
+    expect(paid.paidBy.toBase58()).to.equal(customer.publicKey.toBase58());
+    expect(paid.purchase.toBase58()).to.equal(transactionRecord.toBase58());
+  });
+
+  it("Cannot be paid twice", async () => {
+    const invoice = await createInvoice();
+    await payInvoice(invoice);
+
+    try {
+      await payInvoice(invoice);
+      expect.fail("Should have failed for an invoice already paid");
+    } catch (error) {
+      expect(error.toString()).to.include("InvoiceAlreadyPaid");
+    }
+  });
+
+  it("Cannot be paid after it expires", async () => {
+    const invoice = await createInvoice(null, 3);
+    const { expiresAt } = await program.account.invoice.fetch(invoice);
+    await waitForClusterTime(expiresAt.toNumber());
+
+    try {
+      await payInvoice(invoice);
+      expect.fail("Should have failed for an expired invoice");
+    } catch (error) {
+      expect(error.toString()).to.include("InvoiceExpired");
+    }
+  });
+
+  it("Caps the tokens redeemed against the invoice", async () => {
+    const invoice = await createInvoice(5);
+
+    try {
+      await payInvoice(invoice, INVOICE_AMOUNT, 6);
+      expect.fail("Should have failed for redeeming more than the invoice allows");
+    } catch (error) {
+      expect(error.toString()).to.include("InvoiceRedemptionExceeded");
+    }
+
+    const transactionRecord = await payInvoice(invoice, INVOICE_AMOUNT, 5);
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.redeemedTokenAmount.toNumber()).to.equal(5 * TOKEN);
+  });
+
+  it("Only pay_invoice accepts an invoice", async () => {
+    const invoice = await createInvoice();
+    const { transactionId, transactionRecord } = newTransaction();
+
+    try {
+      await program.methods
+        .processPurchaseWithRedemption(new anchor.BN(INVOICE_AMOUNT), null, transactionId, null)
+        .accounts(purchaseAccounts(transactionRecord, invoice))
+        .signers([customer])
+        .rpc();
+      expect.fail("Should have failed for a purchase carrying an invoice");
+    } catch (error) {
+      expect(error.toString()).to.include("InvoiceMismatch");
+    }
+  });
+});
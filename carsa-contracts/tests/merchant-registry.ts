@@ -0,0 +1,188 @@
+/**
+ * Merchant Registry Test
+ *
+ * Covers enumerating merchants through the registry's index PDAs
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+
+describe("Merchant Registry", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  let updateAuthority: Keypair;
+  let registryPda: PublicKey;
+  const owners: Keypair[] = [];
+  const merchants: PublicKey[] = [];
+
+  const merchantIndexPDA = (index: anchor.BN | number) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), new anchor.BN(index).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const merchantPDA = (owner: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), owner.toBuffer()],
+      program.programId
+    )[0];
+
+  // Walk every index 0..merchant_count, as a client without getProgramAccounts would
+  const walkRegistry = async () => {
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const entries = [];
+    for (let i = 0; i < registry.merchantCount.toNumber(); i++) {
+      entries.push(await program.account.merchantIndex.fetch(merchantIndexPDA(i)));
+    }
+    return entries;
+  };
+
+  before(async () => {
+    updateAuthority = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        updateAuthority.publicKey,
+        5 * anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    const mintKeypair = Keypair.generate();
+    const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    const [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+  });
+
+  it("Numbers merchants in registration order", async () => {
+    const before = await program.account.merchantRegistry.fetch(registryPda);
+
+    for (const name of ["Kopi Satu", "Toko Dua", "Warung Tiga"]) {
+      const owner = Keypair.generate();
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(owner.publicKey, 2 * anchor.web3.LAMPORTS_PER_SOL)
+      );
+      const registry = await program.account.merchantRegistry.fetch(registryPda);
+
+      await program.methods
+        .registerMerchant(name, "retail", 300, new anchor.BN(0))
+        .accounts({
+          merchantOwner: owner.publicKey,
+          merchantAccount: merchantPDA(owner.publicKey),
+          merchantRegistry: registryPda,
+          merchantIndex: merchantIndexPDA(registry.merchantCount),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+
+      owners.push(owner);
+      merchants.push(merchantPDA(owner.publicKey));
+    }
+
+    const after = await program.account.merchantRegistry.fetch(registryPda);
+    expect(after.merchantCount.sub(before.merchantCount).toNumber()).to.equal(3);
+
+    const merchant = await program.account.merchantAccount.fetch(merchants[1]);
+    const entry = await program.account.merchantIndex.fetch(
+      merchantIndexPDA(merchant.registryIndex)
+    );
+    expect(entry.merchant.toBase58()).to.equal(merchants[1].toBase58());
+    expect(entry.index.toString()).to.equal(merchant.registryIndex.toString());
+  });
+
+  it("Finds every merchant by walking the index PDAs", async () => {
+    const found = (await walkRegistry())
+      .filter((entry) => !entry.closed)
+      .map((entry) => entry.merchant.toBase58());
+
+    for (const merchant of merchants) {
+      expect(found).to.include(merchant.toBase58());
+    }
+  });
+
+  it("Tombstones a closed merchant's index without renumbering", async () => {
+    const before = await program.account.merchantRegistry.fetch(registryPda);
+    const closing = await program.account.merchantAccount.fetch(merchants[1]);
+
+    await program.methods
+      .closeMerchant()
+      .accounts({
+        merchantOwner: owners[1].publicKey,
+        merchantAccount: merchants[1],
+        merchantIndex: merchantIndexPDA(closing.registryIndex),
+      })
+      .signers([owners[1]])
+      .rpc();
+
+    expect(await provider.connection.getAccountInfo(merchants[1])).to.be.null;
+
+    const after = await program.account.merchantRegistry.fetch(registryPda);
+    expect(after.merchantCount.toString()).to.equal(before.merchantCount.toString());
+
+    const entries = await walkRegistry();
+    const tombstone = entries[closing.registryIndex.toNumber()];
+    expect(tombstone.merchant.toBase58()).to.equal(merchants[1].toBase58());
+    expect(tombstone.closed).to.be.true;
+
+    const open = entries.filter((entry) => !entry.closed).map((entry) => entry.merchant.toBase58());
+    expect(open).to.include(merchants[0].toBase58());
+    expect(open).to.include(merchants[2].toBase58());
+    expect(open).to.not.include(merchants[1].toBase58());
+
+    // Surviving merchants keep their positions
+    for (const i of [0, 2]) {
+      const merchant = await program.account.merchantAccount.fetch(merchants[i]);
+      expect(entries[merchant.registryIndex.toNumber()].merchant.toBase58()).to.equal(
+        merchants[i].toBase58()
+      );
+    }
+  });
+
+  it("Rejects closing with another merchant's index", async () => {
+    const other = await program.account.merchantAccount.fetch(merchants[2]);
+    try {
+      await program.methods
+        .closeMerchant()
+        .accounts({
+          merchantOwner: owners[0].publicKey,
+          merchantAccount: merchants[0],
+          merchantIndex: merchantIndexPDA(other.registryIndex),
+        })
+        .signers([owners[0]])
+        .rpc();
+      expect.fail("Should have failed with a mismatched index");
+    } catch (error) {
+      expect(error.toString()).to.match(/ConstraintSeeds|MerchantIndexMismatch/);
+    }
+  });
+});
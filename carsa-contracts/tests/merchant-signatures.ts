@@ -0,0 +1,291 @@
+/**
+ * Merchant Signature Test
+ *
+ * Covers signed purchase approvals: a merchant requiring them only accepts purchases
+ * whose transaction carries an Ed25519 signature by the merchant wallet or a registered
+ * POS key over (merchant, customer, fiat amount, transaction id)
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Merchant Signatures", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const FIAT_AMOUNT = 75_000;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+  let posKey: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let posKeyPda: PublicKey;
+
+  // Mirrors `purchase_approval_message`
+  const approvalMessage = (fiatAmount: number, transactionId: number[]) =>
+    Buffer.concat([
+      merchantAccountPda.toBuffer(),
+      customer.publicKey.toBuffer(),
+      new anchor.BN(fiatAmount).toArrayLike(Buffer, "le", 8),
+      Buffer.from(transactionId),
+    ]);
+
+  const approval = (signer: Keypair, message: Buffer) =>
+    Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message,
+    });
+
+  const purchase = async (
+    transactionId: number[],
+    preInstructions: TransactionInstruction[],
+    approvedByPosKey = false,
+    withSysvar = true
+  ) => {
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(new anchor.BN(FIAT_AMOUNT), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+        invoice: null,
+        instructionsSysvar: withSysvar ? SYSVAR_INSTRUCTIONS_PUBKEY : null,
+        posKey: approvedByPosKey ? posKeyPda : null,
+      })
+      .preInstructions(preInstructions)
+      .signers([customer])
+      .rpc();
+
+    return transactionRecord;
+  };
+
+  const newTransactionId = () => Array.from(Keypair.generate().publicKey.toBytes());
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+    posKey = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Kasir Aman", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    await program.methods
+      .updateMerchant(null, null, null, null, true, null)
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    [posKeyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pos_key"), merchantAccountPda.toBuffer(), posKey.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .registerPosKey(posKey.publicKey)
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        posKeyAccount: posKeyPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Rejects a purchase without an approval", async () => {
+    for (const withSysvar of [true, false]) {
+      try {
+        await purchase(newTransactionId(), [], false, withSysvar);
+        expect.fail("Should have failed without a signed approval");
+      } catch (error) {
+        expect(error.toString()).to.include("MerchantSignatureMissing");
+      }
+    }
+  });
+
+  it("Rejects an approval over a different amount", async () => {
+    const transactionId = newTransactionId();
+    try {
+      await purchase(transactionId, [
+        approval(merchantOwner, approvalMessage(FIAT_AMOUNT * 10, transactionId)),
+      ]);
+      expect.fail("Should have failed for a tampered amount");
+    } catch (error) {
+      expect(error.toString()).to.include("MerchantSignatureMissing");
+    }
+  });
+
+  it("Rejects an approval from an unregistered key", async () => {
+    const transactionId = newTransactionId();
+    try {
+      await purchase(transactionId, [
+        approval(Keypair.generate(), approvalMessage(FIAT_AMOUNT, transactionId)),
+      ]);
+      expect.fail("Should have failed for an unknown signer");
+    } catch (error) {
+      expect(error.toString()).to.include("MerchantSignatureMissing");
+    }
+
+    // A POS key only counts when its registration is passed in
+    try {
+      await purchase(transactionId, [approval(posKey, approvalMessage(FIAT_AMOUNT, transactionId))]);
+      expect.fail("Should have failed without the POS key account");
+    } catch (error) {
+      expect(error.toString()).to.include("MerchantSignatureMissing");
+    }
+  });
+
+  it("Accepts approvals from the merchant wallet and a POS key", async () => {
+    let transactionId = newTransactionId();
+    let record = await purchase(transactionId, [
+      approval(merchantOwner, approvalMessage(FIAT_AMOUNT, transactionId)),
+    ]);
+    expect(
+      (await program.account.purchaseTransaction.fetch(record)).fiatAmount.toNumber()
+    ).to.equal(FIAT_AMOUNT);
+
+    transactionId = newTransactionId();
+    record = await purchase(
+      transactionId,
+      [approval(posKey, approvalMessage(FIAT_AMOUNT, transactionId))],
+      true
+    );
+    expect(
+      (await program.account.purchaseTransaction.fetch(record)).fiatAmount.toNumber()
+    ).to.equal(FIAT_AMOUNT);
+
+    // The approval names the transaction id, whose record already exists
+    try {
+      await purchase(transactionId, [approval(posKey, approvalMessage(FIAT_AMOUNT, transactionId))], true);
+      expect.fail("Should have failed for a reused approval");
+    } catch (error) {
+      expect(error.toString()).to.include("already in use");
+    }
+  });
+});
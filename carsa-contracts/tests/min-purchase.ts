@@ -0,0 +1,224 @@
+/**
+ * Minimum Purchase Test
+ *
+ * Covers the per-merchant minimum purchase value, counted over fiat and redeemed tokens
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Minimum Purchase", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const MIN_PURCHASE = 20_000; // Rp 20,000
+  const TOKEN = 1e9; // 1 token = Rp 1,000 at the default rate
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  type Merchant = { owner: Keypair; account: PublicKey; tokenAccount: PublicKey };
+  let strictMerchant: Merchant;
+  let legacyMerchant: Merchant;
+
+  const registerMerchant = async (name: string, minPurchase: number): Promise<Merchant> => {
+    const owner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(owner.publicKey, 5 * anchor.web3.LAMPORTS_PER_SOL)
+    );
+    const [account] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), owner.publicKey.toBuffer()],
+      program.programId
+    );
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .registerMerchant(name, "retail", 300, new anchor.BN(minPurchase))
+      .accounts({
+        merchantOwner: owner.publicKey,
+        merchantAccount: account,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const tokenAccount = await createAccount(
+      provider.connection,
+      owner,
+      mintKeypair.publicKey,
+      owner.publicKey
+    );
+    return { owner, account, tokenAccount };
+  };
+
+  const purchase = async (merchant: Merchant, fiatAmount: number, redeemTokens = 0) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchant.account.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(
+        new anchor.BN(fiatAmount),
+        redeemTokens > 0 ? new anchor.BN(redeemTokens * TOKEN) : null,
+        transactionId,
+        null
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchant.account,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount: merchant.tokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+      })
+      .signers([customer])
+      .rpc();
+
+    return program.account.purchaseTransaction.fetch(transactionRecord);
+  };
+
+  const expectBelowMinimum = async (promise: Promise<unknown>) => {
+    try {
+      await promise;
+      expect.fail("Should have failed below the minimum");
+    } catch (error) {
+      expect(error.toString()).to.include("PurchaseBelowMinimum");
+    }
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    strictMerchant = await registerMerchant("Toko Minimum", MIN_PURCHASE);
+    legacyMerchant = await registerMerchant("Toko Lama", 0);
+  });
+
+  it("Stores the minimum set at registration", async () => {
+    const merchant = await program.account.merchantAccount.fetch(strictMerchant.account);
+    expect(merchant.minPurchaseAmount.toNumber()).to.equal(MIN_PURCHASE);
+  });
+
+  it("Accepts a fiat purchase exactly at the minimum and rejects one below it", async () => {
+    await expectBelowMinimum(purchase(strictMerchant, MIN_PURCHASE - 1));
+
+    const record = await purchase(strictMerchant, MIN_PURCHASE);
+    expect(record.totalValue.toNumber()).to.equal(MIN_PURCHASE);
+  });
+
+  it("Counts redeemed tokens toward the minimum", async () => {
+    // Rp 14,999 + 5 tokens (Rp 5,000) is one rupiah short
+    await expectBelowMinimum(purchase(strictMerchant, MIN_PURCHASE - 5_000 - 1, 5));
+
+    const record = await purchase(strictMerchant, MIN_PURCHASE - 5_000, 5);
+    expect(record.totalValue.toNumber()).to.equal(MIN_PURCHASE);
+  });
+
+  it("Places no minimum on merchants left at the default of zero", async () => {
+    const merchant = await program.account.merchantAccount.fetch(legacyMerchant.account);
+    expect(merchant.minPurchaseAmount.toNumber()).to.equal(0);
+
+    const record = await purchase(legacyMerchant, 1);
+    expect(record.totalValue.toNumber()).to.equal(1);
+  });
+
+  it("Lets the merchant change its minimum", async () => {
+    await program.methods
+      .updateMerchant(null, null, new anchor.BN(500), null, null, null)
+      .accounts({
+        merchantOwner: legacyMerchant.owner.publicKey,
+        merchantAccount: legacyMerchant.account,
+      })
+      .signers([legacyMerchant.owner])
+      .rpc();
+
+    await expectBelowMinimum(purchase(legacyMerchant, 499));
+    await purchase(legacyMerchant, 500);
+  });
+});
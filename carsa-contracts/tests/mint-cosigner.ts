@@ -0,0 +1,154 @@
+/**
+ * Mint Co-signer Test
+ *
+ * Covers the second signature required on administrative mints above the
+ * single-signature limit
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Mint Co-signer", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const SINGLE_SIG_LIMIT = new anchor.BN(1_000 * 1e9);
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let cosigner: Keypair;
+  let outsider: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let destination: PublicKey;
+
+  const mint = (amount: anchor.BN, signer: Keypair | null = null) =>
+    program.methods
+      .mintLokalTokens(amount)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        cosigner: signer ? signer.publicKey : null,
+      })
+      .signers(signer ? [updateAuthority, signer] : [updateAuthority])
+      .rpc();
+
+  const balance = async () =>
+    new anchor.BN((await getAccount(provider.connection, destination)).amount.toString());
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    cosigner = Keypair.generate();
+    outsider = Keypair.generate();
+
+    for (const keypair of [updateAuthority, outsider]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    destination = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      updateAuthority.publicKey
+    );
+  });
+
+  it("Only the update authority can set the co-signer", async () => {
+    try {
+      await program.methods
+        .setMintCosigner(outsider.publicKey, new anchor.BN(0))
+        .accounts({ authority: outsider.publicKey, config: configPda })
+        .signers([outsider])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    await program.methods
+      .setMintCosigner(cosigner.publicKey, SINGLE_SIG_LIMIT)
+      .accounts({ authority: updateAuthority.publicKey, config: configPda })
+      .signers([updateAuthority])
+      .rpc();
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.mintCosigner.toBase58()).to.equal(cosigner.publicKey.toBase58());
+    expect(config.singleSigMintLimit.toString()).to.equal(SINGLE_SIG_LIMIT.toString());
+  });
+
+  it("Mints up to the limit with a single signature", async () => {
+    const before = await balance();
+    await mint(SINGLE_SIG_LIMIT);
+    expect((await balance()).sub(before).toString()).to.equal(SINGLE_SIG_LIMIT.toString());
+  });
+
+  it("Rejects a large mint without the co-signer", async () => {
+    const amount = SINGLE_SIG_LIMIT.addn(1);
+
+    try {
+      await mint(amount);
+      expect.fail("Should have failed without the co-signer");
+    } catch (error) {
+      expect(error.toString()).to.include("MintCosignerRequired");
+    }
+
+    try {
+      await mint(amount, outsider);
+      expect.fail("Should have failed with the wrong co-signer");
+    } catch (error) {
+      expect(error.toString()).to.include("MintCosignerRequired");
+    }
+  });
+
+  it("Mints a large amount with the co-signer", async () => {
+    const amount = new anchor.BN(5_000 * 1e9);
+    const before = await balance();
+    await mint(amount, cosigner);
+    expect((await balance()).sub(before).toString()).to.equal(amount.toString());
+  });
+});
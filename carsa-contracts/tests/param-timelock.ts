@@ -0,0 +1,278 @@
+/**
+ * Parameter Timelock Test
+ *
+ * Covers queueing, executing and cancelling timelocked parameter changes
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createMint } from "@solana/spl-token";
+import {
+  defaultPoolConfig,
+  getPoolStatePDA,
+  initializePool,
+  toConfigUpdate,
+} from "../voucher-pool-client";
+
+describe("Parameter Timelock", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TIMELOCK_SECONDS = 3;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let poolAuthority: Keypair;
+  let outsider: Keypair;
+
+  let configPda: PublicKey;
+  let poolState: PublicKey;
+
+  const poolConfig = defaultPoolConfig();
+
+  const pendingChangePDA = (id: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("param_change"), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const queue = async (change: any, proposer: Keypair, withPool = false) => {
+    const { paramChangeCount } = await program.account.lokalMintConfig.fetch(configPda);
+    const pendingChange = pendingChangePDA(paramChangeCount);
+
+    await program.methods
+      .queueParamChange(change)
+      .accounts({
+        proposer: proposer.publicKey,
+        config: configPda,
+        poolState: withPool ? poolState : null,
+        pendingChange,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([proposer])
+      .rpc();
+
+    return pendingChange;
+  };
+
+  const execute = (pendingChange: PublicKey, proposer: PublicKey, withPool = false) =>
+    program.methods
+      .executeParamChange()
+      .accounts({
+        proposer,
+        config: configPda,
+        poolState: withPool ? poolState : null,
+        pendingChange,
+      })
+      .rpc();
+
+  const cancel = (pendingChange: PublicKey, proposer: Keypair) =>
+    program.methods
+      .cancelParamChange()
+      .accounts({
+        proposer: proposer.publicKey,
+        config: configPda,
+        pendingChange,
+      })
+      .signers([proposer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    poolAuthority = Keypair.generate();
+    outsider = Keypair.generate();
+
+    for (const keypair of [updateAuthority, poolAuthority, outsider]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    const [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const voucherMint = await createMint(
+      provider.connection,
+      poolAuthority,
+      poolAuthority.publicKey,
+      null,
+      9
+    );
+    await initializePool(
+      program,
+      poolAuthority,
+      Keypair.generate().publicKey,
+      voucherMint,
+      poolConfig
+    );
+    [poolState] = getPoolStatePDA(program.programId, voucherMint);
+
+    await program.methods
+      .setParamTimelock(new anchor.BN(TIMELOCK_SECONDS))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Blocks direct changes to timelocked parameters", async () => {
+    try {
+      await program.methods
+        .updatePoolConfig(toConfigUpdate({ apyBasisPoints: 900 }))
+        .accounts({
+          poolAuthority: poolAuthority.publicKey,
+          poolState,
+          config: configPda,
+        })
+        .signers([poolAuthority])
+        .rpc();
+      expect.fail("Should have failed under the timelock");
+    } catch (error) {
+      expect(error.toString()).to.include("TimelockActive");
+    }
+
+    try {
+      await program.methods
+        .setParamTimelock(new anchor.BN(0))
+        .accounts({
+          authority: updateAuthority.publicKey,
+          config: configPda,
+        })
+        .signers([updateAuthority])
+        .rpc();
+      expect.fail("Should have failed to shorten the timelock directly");
+    } catch (error) {
+      expect(error.toString()).to.include("TimelockActive");
+    }
+  });
+
+  it("Only the update authority can queue mint parameter changes", async () => {
+    try {
+      await queue({ tokenToIdrRate: { 0: new anchor.BN(2_000) } }, outsider);
+      expect.fail("Should have failed for a non-authority proposer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+  });
+
+  it("Refuses to execute before the timelock passes, then applies the queued value", async () => {
+    const pendingChange = await queue(
+      { tokenToIdrRate: { 0: new anchor.BN(1_250) } },
+      updateAuthority
+    );
+    const pending = await program.account.pendingParamChange.fetch(pendingChange);
+    expect(pending.effectiveAt.sub(pending.queuedAt).toNumber()).to.equal(TIMELOCK_SECONDS);
+
+    try {
+      await execute(pendingChange, updateAuthority.publicKey);
+      expect.fail("Should have failed before the effective time");
+    } catch (error) {
+      expect(error.toString()).to.include("ParamChangeNotReady");
+    }
+    let config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.tokenToIdrRate.toNumber()).to.equal(1_000);
+
+    await waitForClusterTime(pending.effectiveAt.toNumber() + 1);
+    await execute(pendingChange, updateAuthority.publicKey);
+
+    config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.tokenToIdrRate.toNumber()).to.equal(1_250);
+    expect(await provider.connection.getAccountInfo(pendingChange)).to.be.null;
+  });
+
+  it("Lets the proposer cancel a queued change", async () => {
+    const pendingChange = await queue(
+      { poolConfig: { poolId: 0, update: toConfigUpdate({ apyBasisPoints: 1 }) } },
+      poolAuthority,
+      true
+    );
+
+    try {
+      await cancel(pendingChange, outsider);
+      expect.fail("Should have failed for someone other than the proposer");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+
+    await cancel(pendingChange, poolAuthority);
+    expect(await provider.connection.getAccountInfo(pendingChange)).to.be.null;
+
+    const pool = await program.account.poolState.fetch(poolState);
+    expect(pool.config.apyBasisPoints).to.equal(poolConfig.apyBasisPoints);
+  });
+
+  it("Applies exactly the queued pool configuration change", async () => {
+    const pendingChange = await queue(
+      {
+        poolConfig: {
+          poolId: 0,
+          update: toConfigUpdate({ apyBasisPoints: 900, lockupSeconds: 60 }),
+        },
+      },
+      poolAuthority,
+      true
+    );
+    const pending = await program.account.pendingParamChange.fetch(pendingChange);
+    const before = await program.account.poolState.fetch(poolState);
+
+    await waitForClusterTime(pending.effectiveAt.toNumber() + 1);
+    // Anyone can execute once the delay is over
+    await execute(pendingChange, poolAuthority.publicKey, true);
+
+    const after = await program.account.poolState.fetch(poolState);
+    expect(after.config.apyBasisPoints).to.equal(900);
+    expect(after.config.lockupSeconds).to.equal(60);
+    expect(after.config.minStakeAmount.toString()).to.equal(
+      before.config.minStakeAmount.toString()
+    );
+    expect(after.config.depositsEnabled).to.equal(before.config.depositsEnabled);
+    expect(after.config.insuranceFeeBps).to.equal(before.config.insuranceFeeBps);
+  });
+});
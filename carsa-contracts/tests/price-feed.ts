@@ -0,0 +1,232 @@
+/**
+ * Price Feed Test
+ *
+ * Covers valuing redeemed tokens from a Pyth price feed, using the mock price accounts
+ * loaded from tests/fixtures (Rp 1,250 per token, published at unix time 1_700_000_000)
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Price Feed", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  // 0.1% confidence interval
+  const PRICE_FEED = new PublicKey("Fz56rmkNtcXm38tZjRsxKTYhqmSk83Raok7BNofMYiyq");
+  // 40% confidence interval
+  const WIDE_PRICE_FEED = new PublicKey("7ZYbMWr1Hy1CX2Un8YjxvVYNM3h8PFCCY7Hhz5PCuL6S");
+  const FEED_RATE = 1_250;
+
+  // The mock prices are old, so "fresh" means an age limit reaching back to them
+  const NO_AGE_LIMIT = 4_294_967_295;
+  const MAX_CONFIDENCE_BPS = 200;
+
+  const TOKEN = 1e9;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const setPriceFeed = (priceFeed: PublicKey, maxAgeSeconds: number, maxConfidenceBps: number) =>
+    program.methods
+      .setPriceFeed(priceFeed, maxAgeSeconds, maxConfidenceBps)
+      .accounts({ authority: updateAuthority.publicKey, config: configPda })
+      .signers([updateAuthority])
+      .rpc();
+
+  const purchase = async (redeemTokens: number, priceFeed: PublicKey | null) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchaseWithRedemption(
+        new anchor.BN(50_000),
+        { tokenAmount: new anchor.BN(redeemTokens * TOKEN) },
+        transactionId,
+        null
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed,
+      })
+      .signers([customer])
+      .rpc();
+
+    return program.account.purchaseTransaction.fetch(transactionRecord);
+  };
+
+  const expectStale = async (promise: Promise<unknown>) => {
+    try {
+      await promise;
+      expect.fail("Should have rejected the price feed");
+    } catch (error) {
+      expect(error.toString()).to.include("StalePriceFeed");
+    }
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Oracle", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Uses the static rate while no feed is set", async () => {
+    const record = await purchase(10, null);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 10 * 1_000);
+  });
+
+  it("Rejects a feed without a maximum age", async () => {
+    try {
+      await setPriceFeed(PRICE_FEED, 0, MAX_CONFIDENCE_BPS);
+      expect.fail("Should have failed without a maximum age");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidPriceFeedConfig");
+    }
+  });
+
+  it("Values redeemed tokens at a fresh oracle price", async () => {
+    await setPriceFeed(PRICE_FEED, NO_AGE_LIMIT, MAX_CONFIDENCE_BPS);
+
+    const record = await purchase(10, PRICE_FEED);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 10 * FEED_RATE);
+  });
+
+  it("Requires the configured price account", async () => {
+    await expectStale(purchase(10, null));
+    await expectStale(purchase(10, WIDE_PRICE_FEED));
+    // Not a price account at all
+    await expectStale(purchase(10, customer.publicKey));
+  });
+
+  it("Rejects a stale price instead of falling back", async () => {
+    await setPriceFeed(PRICE_FEED, 60, MAX_CONFIDENCE_BPS);
+    await expectStale(purchase(10, PRICE_FEED));
+  });
+
+  it("Rejects a price with an absurd confidence interval", async () => {
+    await setPriceFeed(WIDE_PRICE_FEED, NO_AGE_LIMIT, MAX_CONFIDENCE_BPS);
+    await expectStale(purchase(10, WIDE_PRICE_FEED));
+  });
+
+  it("Returns to the static rate once the feed is unset", async () => {
+    await setPriceFeed(PublicKey.default, 0, 0);
+
+    const record = await purchase(10, null);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 10 * 1_000);
+  });
+});
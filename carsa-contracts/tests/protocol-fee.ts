@@ -0,0 +1,274 @@
+/**
+ * Protocol Fee Test
+ *
+ * Covers routing a share of redeemed tokens to the treasury
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Protocol Fee", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const FEE_BPS = 250; // 2.5%
+  const FIAT_AMOUNT = new anchor.BN(40_000); // Rp 40,000
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let treasuryAuthorityPda: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+
+  const balance = async (account: PublicKey) =>
+    new anchor.BN((await getAccount(provider.connection, account)).amount.toString());
+
+  const setFee = (feeBps: number, authority = updateAuthority, treasury = treasuryTokenAccount) =>
+    program.methods
+      .setProtocolFee(feeBps)
+      .accounts({
+        authority: authority.publicKey,
+        config: configPda,
+        treasuryAuthority: treasuryAuthorityPda,
+        treasuryTokenAccount: treasury,
+      })
+      .signers([authority])
+      .rpc();
+
+  const purchase = async (redeemAmount: anchor.BN, treasury: PublicKey | null) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(FIAT_AMOUNT, redeemAmount, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: treasury,
+      })
+      .signers([customer])
+      .rpc();
+
+    return program.account.purchaseTransaction.fetch(transactionRecord);
+  };
+
+  // Redeems from the customer and reports who received what
+  const redeem = async (redeemAmount: anchor.BN, treasury: PublicKey | null) => {
+    const merchantBefore = await balance(merchantTokenAccount);
+    const treasuryBefore = await balance(treasuryTokenAccount);
+    const record = await purchase(redeemAmount, treasury);
+    return {
+      record,
+      toMerchant: (await balance(merchantTokenAccount)).sub(merchantBefore),
+      toTreasury: (await balance(treasuryTokenAccount)).sub(treasuryBefore),
+    };
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Fee", "retail", 400, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    // The treasury is owned by the program's treasury authority PDA
+    [treasuryAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+    treasuryTokenAccount = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      treasuryAuthorityPda,
+      Keypair.generate()
+    );
+
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * 1e9))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Sends the full redemption to the merchant with no fee", async () => {
+    const redeemAmount = new anchor.BN(10 * 1e9);
+    const { record, toMerchant } = await redeem(redeemAmount, null);
+
+    expect(toMerchant.toString()).to.equal(redeemAmount.toString());
+    expect(record.protocolFee.toString()).to.equal("0");
+  });
+
+  it("Only the update authority can set a valid fee", async () => {
+    try {
+      await setFee(FEE_BPS, merchantOwner);
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    try {
+      await setFee(10_001);
+      expect.fail("Should have failed above 100%");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidProtocolFee");
+    }
+
+    try {
+      await setFee(FEE_BPS, updateAuthority, merchantTokenAccount);
+      expect.fail("Should have failed for a treasury the program does not own");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTreasuryAccount");
+    }
+
+    await setFee(FEE_BPS);
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.protocolFeeBps).to.equal(FEE_BPS);
+    expect(config.treasuryTokenAccount.toBase58()).to.equal(treasuryTokenAccount.toBase58());
+  });
+
+  it("Splits a redemption between treasury and merchant", async () => {
+    const redeemAmount = new anchor.BN(10 * 1e9 + 39);
+    const expectedFee = redeemAmount.muln(FEE_BPS).divn(10_000);
+
+    const { record, toMerchant, toTreasury } = await redeem(redeemAmount, treasuryTokenAccount);
+
+    expect(toTreasury.toString()).to.equal(expectedFee.toString());
+    expect(toMerchant.toString()).to.equal(redeemAmount.sub(expectedFee).toString());
+    expect(record.protocolFee.toString()).to.equal(expectedFee.toString());
+
+    // Value and cashback use the gross redemption
+    expect(record.redeemedTokenAmount.toString()).to.equal(redeemAmount.toString());
+    expect(record.totalValue.toString()).to.equal(FIAT_AMOUNT.addn(10_000).toString());
+  });
+
+  it("Rounds a 1-unit redemption's fee down to zero", async () => {
+    const { record, toMerchant, toTreasury } = await redeem(new anchor.BN(1), null);
+
+    expect(toTreasury.toString()).to.equal("0");
+    expect(toMerchant.toString()).to.equal("1");
+    expect(record.protocolFee.toString()).to.equal("0");
+  });
+
+  it("Requires the configured treasury when a fee is owed", async () => {
+    try {
+      await purchase(new anchor.BN(1e9), null);
+      expect.fail("Should have failed without the treasury");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTreasuryAccount");
+    }
+
+    try {
+      await purchase(new anchor.BN(1e9), merchantTokenAccount);
+      expect.fail("Should have failed with another token account");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTreasuryAccount");
+    }
+  });
+});
@@ -0,0 +1,235 @@
+/**
+ * Redemption Bonus Test
+ *
+ * Covers the per-merchant bonus applied to the value of redeemed tokens
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount } from "@solana/spl-token";
+
+describe("Redemption Bonus", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const BONUS_BPS = 1_000; // 10%
+  const TOKEN = 1e9; // 1 token = Rp 1,000 at the default rate
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  type Merchant = { owner: Keypair; account: PublicKey; tokenAccount: PublicKey };
+  let bonusMerchant: Merchant;
+  let plainMerchant: Merchant;
+
+  const registerMerchant = async (name: string): Promise<Merchant> => {
+    const owner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(owner.publicKey, 5 * anchor.web3.LAMPORTS_PER_SOL)
+    );
+    const [account] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), owner.publicKey.toBuffer()],
+      program.programId
+    );
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
+    await program.methods
+      .registerMerchant(name, "retail", 300, new anchor.BN(0))
+      .accounts({
+        merchantOwner: owner.publicKey,
+        merchantAccount: account,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+
+    const tokenAccount = await createAccount(
+      provider.connection,
+      owner,
+      mintKeypair.publicKey,
+      owner.publicKey
+    );
+    return { owner, account, tokenAccount };
+  };
+
+  const setBonus = (merchant: Merchant, bonusBps: number) =>
+    program.methods
+      .updateMerchant(null, null, null, bonusBps, null, null)
+      .accounts({
+        merchantOwner: merchant.owner.publicKey,
+        merchantAccount: merchant.account,
+      })
+      .signers([merchant.owner])
+      .rpc();
+
+  const purchaseAccounts = (merchant: Merchant, transactionRecord: PublicKey) => ({
+    customer: customer.publicKey,
+    merchantAccount: merchant.account,
+    mint: mintKeypair.publicKey,
+    mintAuthority: mintAuthorityPda,
+    config: configPda,
+    customerTokenAccount,
+    merchantTokenAccount: merchant.tokenAccount,
+    transactionRecord,
+    tokenProgram: TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+    stakePool: null,
+    customerStakeRecord: null,
+    activityRecord: null,
+    redemptionActivityRecord: null,
+    treasuryTokenAccount: null,
+  });
+
+  const newTransaction = (merchant: Merchant) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchant.account.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+    return { transactionId, transactionRecord };
+  };
+
+  const purchase = async (merchant: Merchant, fiatAmount: number, redeemTokens: number) => {
+    const { transactionId, transactionRecord } = newTransaction(merchant);
+
+    await program.methods
+      .processPurchaseWithRedemption(
+        new anchor.BN(fiatAmount),
+        redeemTokens > 0 ? { tokenAmount: new anchor.BN(redeemTokens * TOKEN) } : null,
+        transactionId,
+        null
+      )
+      .accounts(purchaseAccounts(merchant, transactionRecord))
+      .signers([customer])
+      .rpc();
+
+    return program.account.purchaseTransaction.fetch(transactionRecord);
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    bonusMerchant = await registerMerchant("Toko Bonus");
+    plainMerchant = await registerMerchant("Toko Biasa");
+    await setBonus(bonusMerchant, BONUS_BPS);
+  });
+
+  it("Rejects a bonus above 100%", async () => {
+    try {
+      await setBonus(plainMerchant, 10_001);
+      expect.fail("Should have failed for a bonus above 10000 bps");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidRedemptionBonus");
+    }
+  });
+
+  it("Values redeemed tokens with the merchant bonus", async () => {
+    const merchant = await program.account.merchantAccount.fetch(bonusMerchant.account);
+    expect(merchant.redemptionBonusBps).to.equal(BONUS_BPS);
+
+    // 10 tokens are worth Rp 10,000, plus 10% at this merchant
+    const record = await purchase(bonusMerchant, 50_000, 10);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 11_000);
+    expect(record.redemptionBonusBps).to.equal(BONUS_BPS);
+  });
+
+  it("Applies no bonus to fiat-only purchases", async () => {
+    const record = await purchase(bonusMerchant, 50_000, 0);
+    expect(record.totalValue.toNumber()).to.equal(50_000);
+    expect(record.redemptionBonusBps).to.equal(0);
+  });
+
+  it("Values redeemed tokens at face value for merchants without a bonus", async () => {
+    const record = await purchase(plainMerchant, 50_000, 10);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 10_000);
+    expect(record.redemptionBonusBps).to.equal(0);
+  });
+
+  it("Keeps the legacy process_purchase instruction working", async () => {
+    const { transactionId, transactionRecord } = newTransaction(bonusMerchant);
+
+    await program.methods
+      .processPurchase(new anchor.BN(50_000), new anchor.BN(10 * TOKEN), transactionId, null)
+      .accounts(purchaseAccounts(bonusMerchant, transactionRecord))
+      .signers([customer])
+      .rpc();
+
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.totalValue.toNumber()).to.equal(50_000 + 11_000);
+  });
+});
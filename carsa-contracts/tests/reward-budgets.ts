@@ -0,0 +1,302 @@
+/**
+ * Reward Budget Test
+ *
+ * Covers sponsored cashback: a sponsor escrows a budget that pays the rewards of
+ * purchases at its member merchants instead of minting them, falls back to minting
+ * once exhausted, and can be withdrawn by the sponsor
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount, getMint } from "@solana/spl-token";
+
+describe("Reward Budgets", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const BUDGET_ID = new anchor.BN(1);
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let sponsor: Keypair;
+  let tenantOwner: Keypair;
+  let outsiderOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let registryPda: PublicKey;
+  let rewardBudgetPda: PublicKey;
+  let rewardBudgetEscrow: PublicKey;
+  let tenantMembership: PublicKey;
+  let outsiderMembership: PublicKey;
+  let sponsorTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const merchantPda = (owner: Keypair) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), owner.publicKey.toBuffer()],
+      program.programId
+    )[0];
+
+  const membershipPda = (owner: Keypair) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("budget_membership"), rewardBudgetPda.toBuffer(), merchantPda(owner).toBuffer()],
+      program.programId
+    )[0];
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const supply = async () =>
+    Number((await getMint(provider.connection, mintKeypair.publicKey)).supply);
+
+  const registerMerchant = async (owner: Keypair) => {
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Mall Tenant", "retail", 1_000, new anchor.BN(0))
+      .accounts({
+        merchantOwner: owner.publicKey,
+        merchantAccount: merchantPda(owner),
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  const purchase = async (owner: Keypair, fiatAmount: number, membership: PublicKey | null) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantPda(owner).toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchase(new anchor.BN(fiatAmount), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantPda(owner),
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount: customerTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rewardBudget: rewardBudgetPda,
+        budgetMembership: membership,
+        rewardBudgetEscrow,
+      })
+      .signers([customer])
+      .rpc();
+
+    return (await program.account.purchaseTransaction.fetch(transactionRecord)).rewardAmount.toNumber();
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    sponsor = Keypair.generate();
+    tenantOwner = Keypair.generate();
+    outsiderOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, sponsor, tenantOwner, outsiderOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [rewardBudgetPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_budget"), sponsor.publicKey.toBuffer(), BUDGET_ID.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    [rewardBudgetEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_budget_escrow")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    await registerMerchant(tenantOwner);
+    await registerMerchant(outsiderOwner);
+
+    sponsorTokenAccount = await createAccount(
+      provider.connection,
+      sponsor,
+      mintKeypair.publicKey,
+      sponsor.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: sponsorTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    await program.methods
+      .createRewardBudget(BUDGET_ID, new anchor.BN(50 * TOKEN))
+      .accounts({
+        sponsor: sponsor.publicKey,
+        sponsorTokenAccount,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        rewardBudgetEscrow,
+        rewardBudget: rewardBudgetPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([sponsor])
+      .rpc();
+
+    tenantMembership = membershipPda(tenantOwner);
+    outsiderMembership = membershipPda(outsiderOwner);
+    await program.methods
+      .addBudgetMember()
+      .accounts({
+        sponsor: sponsor.publicKey,
+        rewardBudget: rewardBudgetPda,
+        merchantAccount: merchantPda(tenantOwner),
+        budgetMembership: tenantMembership,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([sponsor])
+      .rpc();
+  });
+
+  it("Pays a member merchant's reward from the budget", async () => {
+    const supplyBefore = await supply();
+    const escrowBefore = await balance(rewardBudgetEscrow);
+
+    const reward = await purchase(tenantOwner, 100_000, tenantMembership);
+    expect(reward).to.be.greaterThan(0);
+
+    expect(await supply()).to.equal(supplyBefore);
+    expect(await balance(rewardBudgetEscrow)).to.equal(escrowBefore - reward);
+    expect(await balance(customerTokenAccount)).to.equal(reward);
+
+    const budget = await program.account.rewardBudget.fetch(rewardBudgetPda);
+    expect(budget.totalPaid.toNumber()).to.equal(reward);
+    expect(budget.remainingBudget.toNumber()).to.equal(50 * TOKEN - reward);
+  });
+
+  it("Rejects a merchant outside the budget", async () => {
+    for (const membership of [null, tenantMembership]) {
+      try {
+        await purchase(outsiderOwner, 100_000, membership);
+        expect.fail("Should have failed for a non-member merchant");
+      } catch (error) {
+        expect(error.toString()).to.include("NotBudgetMember");
+      }
+    }
+  });
+
+  it("Lets the sponsor withdraw unspent budget", async () => {
+    const budget = await program.account.rewardBudget.fetch(rewardBudgetPda);
+    const remaining = budget.remainingBudget.toNumber();
+    const withdraw = async (amount: number, signer: Keypair) =>
+      program.methods
+        .withdrawRewardBudget(new anchor.BN(amount))
+        .accounts({
+          sponsor: signer.publicKey,
+          sponsorTokenAccount,
+          mintAuthority: mintAuthorityPda,
+          config: configPda,
+          rewardBudgetEscrow,
+          rewardBudget: rewardBudgetPda,
+          mint: mintKeypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([signer])
+        .rpc();
+
+    try {
+      await withdraw(remaining + 1, sponsor);
+      expect.fail("Should have failed for more than the remaining budget");
+    } catch (error) {
+      expect(error.toString()).to.include("RewardBudgetInsufficient");
+    }
+
+    try {
+      await withdraw(1, customer);
+      expect.fail("Should have failed for a wallet other than the sponsor");
+    } catch (error) {
+      expect(error.toString()).to.include("ConstraintSeeds");
+    }
+
+    // Leave a single token, less than a purchase reward
+    const sponsorBefore = await balance(sponsorTokenAccount);
+    await withdraw(remaining - 1, sponsor);
+    expect(await balance(sponsorTokenAccount)).to.equal(sponsorBefore + remaining - 1);
+    expect(
+      (await program.account.rewardBudget.fetch(rewardBudgetPda)).remainingBudget.toNumber()
+    ).to.equal(1);
+  });
+
+  it("Falls back to minting once the budget is exhausted", async () => {
+    const supplyBefore = await supply();
+    const escrowBefore = await balance(rewardBudgetEscrow);
+
+    const reward = await purchase(tenantOwner, 100_000, tenantMembership);
+
+    expect(await supply()).to.equal(supplyBefore + reward);
+    expect(await balance(rewardBudgetEscrow)).to.equal(escrowBefore);
+    expect(
+      (await program.account.rewardBudget.fetch(rewardBudgetPda)).remainingBudget.toNumber()
+    ).to.equal(1);
+  });
+});
@@ -0,0 +1,341 @@
+/**
+ * Reward Expiry Test
+ *
+ * Covers expiring rewards: purchase rewards held in escrow as reward lots that the
+ * customer withdraws or a permissionless crank burns once they expire
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount, getMint } from "@solana/spl-token";
+
+describe("Reward Expiry", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const LIFETIME_SECONDS = 6;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+  let cranker: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let rewardEscrow: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const supply = async () =>
+    Number((await getMint(provider.connection, mintKeypair.publicKey)).supply);
+
+  const purchase = async (withLot = true) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+    const [rewardLot] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_lot"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchaseWithRedemption(new anchor.BN(100_000), null, transactionId, null)
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: withLot ? rewardEscrow : null,
+        rewardLot: withLot ? rewardLot : null,
+      })
+      .signers([customer])
+      .rpc();
+
+    return rewardLot;
+  };
+
+  // Sent by the provider wallet, neither the customer nor the authority
+  const expire = (rewardLot: PublicKey) =>
+    program.methods
+      .expireRewards()
+      .accounts({
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        rewardEscrow,
+        rewardLot,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+  const withdraw = (rewardLot: PublicKey) =>
+    program.methods
+      .withdrawVestedRewards()
+      .accounts({
+        customer: customer.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        rewardEscrow,
+        customerTokenAccount,
+        rewardLot,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([customer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+    cranker = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer, cranker]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [rewardEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("reward_escrow")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Kedaluwarsa", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+  });
+
+  it("Mints rewards straight to the customer by default", async () => {
+    const before = await balance(customerTokenAccount);
+    await purchase(false);
+    expect(await balance(customerTokenAccount)).to.be.greaterThan(before);
+  });
+
+  it("Only the update authority can enable expiring rewards", async () => {
+    const setRewardExpiry = (signer: Keypair, lifetime: number) =>
+      program.methods
+        .setRewardExpiry(true, new anchor.BN(lifetime))
+        .accounts({
+          authority: signer.publicKey,
+          config: configPda,
+          mint: mintKeypair.publicKey,
+          mintAuthority: mintAuthorityPda,
+          rewardEscrow,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([signer])
+        .rpc();
+
+    try {
+      await setRewardExpiry(cranker, LIFETIME_SECONDS);
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    try {
+      await setRewardExpiry(updateAuthority, 0);
+      expect.fail("Should have failed without a lifetime");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidRewardLifetime");
+    }
+
+    await setRewardExpiry(updateAuthority, LIFETIME_SECONDS);
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.expiringRewards).to.be.true;
+    expect(config.rewardLifetimeSeconds.toNumber()).to.equal(LIFETIME_SECONDS);
+  });
+
+  it("Holds rewards in escrow and lets the customer claim just before expiry", async () => {
+    try {
+      await purchase(false);
+      expect.fail("Should have failed without reward lot accounts");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidRewardLot");
+    }
+
+    const walletBefore = await balance(customerTokenAccount);
+    const rewardLot = await purchase();
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore);
+
+    const lot = await program.account.rewardLot.fetch(rewardLot);
+    expect(lot.customer.toBase58()).to.equal(customer.publicKey.toBase58());
+    expect(lot.amount.toNumber()).to.be.greaterThan(0);
+    expect(lot.expiresAt.sub(lot.mintedAt).toNumber()).to.equal(LIFETIME_SECONDS);
+    expect(await balance(rewardEscrow)).to.equal(lot.amount.toNumber());
+
+    try {
+      await expire(rewardLot);
+      expect.fail("Should have failed before expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("RewardLotNotExpired");
+    }
+
+    await waitForClusterTime(lot.expiresAt.toNumber() - 2);
+    await withdraw(rewardLot);
+
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore + lot.amount.toNumber());
+    expect(await balance(rewardEscrow)).to.equal(0);
+    expect(await provider.connection.getAccountInfo(rewardLot)).to.be.null;
+  });
+
+  it("Expires only the lots past their expiry", async () => {
+    const oldLot = await purchase();
+    const old = await program.account.rewardLot.fetch(oldLot);
+    await waitForClusterTime(old.expiresAt.toNumber());
+
+    const newLot = await purchase();
+    const fresh = await program.account.rewardLot.fetch(newLot);
+    const escrowBefore = await balance(rewardEscrow);
+    const supplyBefore = await supply();
+
+    // The customer can no longer claim the expired lot
+    try {
+      await withdraw(oldLot);
+      expect.fail("Should have failed for an expired lot");
+    } catch (error) {
+      expect(error.toString()).to.include("RewardLotExpired");
+    }
+
+    await expire(oldLot);
+    try {
+      await expire(newLot);
+      expect.fail("Should have failed for an unexpired lot");
+    } catch (error) {
+      expect(error.toString()).to.include("RewardLotNotExpired");
+    }
+
+    const expired = await program.account.rewardLot.fetch(oldLot);
+    expect(expired.amount.toNumber()).to.equal(0);
+    expect(expired.expiredAmount.toNumber()).to.equal(old.amount.toNumber());
+    expect(await balance(rewardEscrow)).to.equal(escrowBefore - old.amount.toNumber());
+    expect(await supply()).to.equal(supplyBefore - old.amount.toNumber());
+
+    // The unexpired lot is untouched and still claimable
+    const walletBefore = await balance(customerTokenAccount);
+    await withdraw(newLot);
+    expect(await balance(customerTokenAccount)).to.equal(
+      walletBefore + fresh.amount.toNumber()
+    );
+  });
+
+  it("Cranking an expired lot again burns nothing", async () => {
+    const rewardLot = await purchase();
+    const lot = await program.account.rewardLot.fetch(rewardLot);
+    await waitForClusterTime(lot.expiresAt.toNumber());
+
+    await expire(rewardLot);
+    const supplyAfterFirst = await supply();
+    const escrowAfterFirst = await balance(rewardEscrow);
+
+    await expire(rewardLot);
+    expect(await supply()).to.equal(supplyAfterFirst);
+    expect(await balance(rewardEscrow)).to.equal(escrowAfterFirst);
+    const expired = await program.account.rewardLot.fetch(rewardLot);
+    expect(expired.expiredAmount.toNumber()).to.equal(lot.amount.toNumber());
+
+    // The customer closes the spent lot to recover its rent
+    const walletBefore = await balance(customerTokenAccount);
+    await withdraw(rewardLot);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore);
+    expect(await provider.connection.getAccountInfo(rewardLot)).to.be.null;
+  });
+});
@@ -88,7 +88,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const cashbackRate = 500; // 5%
 
     const tx = await program.methods
-      .registerMerchant(merchantName, merchantCategory, cashbackRate)
+      .registerMerchant(merchantName, merchantCategory, cashbackRate, new anchor.BN(0))
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -129,7 +129,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     try {
       await program.methods
-        .registerMerchant("Bad Merchant", "retail", 10001) // > 100%
+        .registerMerchant("Bad Merchant", "retail", 10001, new anchor.BN(0)) // > 100%
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -158,7 +158,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     try {
       await program.methods
-        .registerMerchant("", "retail", 300)
+        .registerMerchant("", "retail", 300, new anchor.BN(0))
         .accounts({
           merchantOwner: merchantOwner.publicKey,
           merchantAccount: merchantAccountPda,
@@ -209,7 +209,7 @@ describe("Carsa Reward Distribution Tests", () => {
     );
 
     await program.methods
-      .registerMerchant("Bookstore", "retail", 300) // 3% cashback
+      .registerMerchant("Bookstore", "retail", 300, new anchor.BN(0)) // 3% cashback
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -223,7 +223,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
       program.programId
     );
 
@@ -236,7 +236,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const initialBalance = await getAccount(provider.connection, customerTokenAccount);
     
     const tx = await program.methods
-      .processPurchase(fiatAmount, null, transactionId)
+      .processPurchase(fiatAmount, null, transactionId, null)
       .accounts({
         customer: customer.publicKey,
         merchantAccount: merchantAccountPda,
@@ -344,7 +344,7 @@ describe("Carsa Reward Distribution Tests", () => {
     );
 
     await program.methods
-      .registerMerchant("Token Store", "retail", 400) // 4% cashback
+      .registerMerchant("Token Store", "retail", 400, new anchor.BN(0)) // 4% cashback
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -359,7 +359,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
       program.programId
     );
 
@@ -367,7 +367,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const initialMerchantBalance = await getAccount(provider.connection, merchantTokenAccount);
     
     const tx = await program.methods
-      .processPurchase(fiatAmount, redeemTokens, transactionId)
+      .processPurchase(fiatAmount, redeemTokens, transactionId, null)
       .accounts({
         customer: customer.publicKey,
         merchantAccount: merchantAccountPda,
@@ -425,7 +425,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     // First register the merchant
     await program.methods
-      .registerMerchant("Update Test", "service", 250) // 2.5%
+      .registerMerchant("Update Test", "service", 250, new anchor.BN(0)) // 2.5%
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -437,7 +437,7 @@ describe("Carsa Reward Distribution Tests", () => {
     // Update cashback rate
     const newCashbackRate = 750; // 7.5%
     await program.methods
-      .updateMerchant(newCashbackRate, null)
+      .updateMerchant(newCashbackRate, null, null, null, null, null)
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -451,7 +451,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     // Update active status
     await program.methods
-      .updateMerchant(null, false)
+      .updateMerchant(null, false, null, null, null, null)
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -500,7 +500,7 @@ describe("Carsa Reward Distribution Tests", () => {
     );
 
     await program.methods
-      .registerMerchant("Inactive Store", "retail", 200)
+      .registerMerchant("Inactive Store", "retail", 200, new anchor.BN(0))
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -511,7 +511,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     // Deactivate merchant
     await program.methods
-      .updateMerchant(null, false)
+      .updateMerchant(null, false, null, null, null, null)
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -524,7 +524,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
       program.programId
     );
 
@@ -535,7 +535,7 @@ describe("Carsa Reward Distribution Tests", () => {
 
     try {
       await program.methods
-        .processPurchase(fiatAmount, null, transactionId)
+        .processPurchase(fiatAmount, null, transactionId, null)
         .accounts({
           customer: customer.publicKey,
           merchantAccount: merchantAccountPda,
@@ -608,7 +608,7 @@ describe("Carsa Reward Distribution Tests", () => {
     );
 
     await program.methods
-      .registerMerchant("Test Store", "retail", 300)
+      .registerMerchant("Test Store", "retail", 300, new anchor.BN(0))
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -623,13 +623,13 @@ describe("Carsa Reward Distribution Tests", () => {
     const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
       program.programId
     );
 
     try {
       await program.methods
-        .processPurchase(fiatAmount, redeemTokens, transactionId)
+        .processPurchase(fiatAmount, redeemTokens, transactionId, null)
         .accounts({
           customer: customer.publicKey,
           merchantAccount: merchantAccountPda,
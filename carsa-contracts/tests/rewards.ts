@@ -91,7 +91,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const cashbackRate = 500; // 5%
 
     const tx = await program.methods
-      .registerMerchant(merchantName, merchantCategory, cashbackRate)
+      .registerMerchant(merchantName, merchantCategory, cashbackRate, new anchor.BN(0))
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -145,7 +145,7 @@ describe("Carsa Reward Distribution Tests", () => {
     );
 
     await program.methods
-      .registerMerchant("Bookstore", "retail", 300) // 3% cashback
+      .registerMerchant("Bookstore", "retail", 300, new anchor.BN(0)) // 3% cashback
       .accounts({
         merchantOwner: merchantOwner.publicKey,
         merchantAccount: merchantAccountPda,
@@ -159,7 +159,7 @@ describe("Carsa Reward Distribution Tests", () => {
     const transactionId = Array.from(crypto.getRandomValues(new Uint8Array(32)));
 
     const [transactionRecordPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("transaction"), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
       program.programId
     );
 
@@ -0,0 +1,333 @@
+/**
+ * Session Key Test
+ *
+ * Covers session keys: an ephemeral key signs purchases for the customer, redeeming
+ * only from its escrowed allowance until it expires or is revoked, while rewards still
+ * go to the customer
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Session Keys", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const SPEND_LIMIT = 10 * TOKEN;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let sessionEscrow: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const sessionKeyPda = (sessionSigner: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("session_key"), customer.publicKey.toBuffer(), sessionSigner.toBuffer()],
+      program.programId
+    )[0];
+
+  // The ephemeral key pays for its purchase records, so the customer funds it with SOL
+  const createSessionKey = async (lifetimeSeconds: number) => {
+    const sessionSigner = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        sessionSigner.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    await program.methods
+      .createSessionKey(
+        sessionSigner.publicKey,
+        new anchor.BN(SPEND_LIMIT),
+        new anchor.BN((await clusterTime()) + lifetimeSeconds)
+      )
+      .accounts({
+        customer: customer.publicKey,
+        customerTokenAccount,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        sessionEscrow,
+        sessionKey: sessionKeyPda(sessionSigner.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc();
+
+    return sessionSigner;
+  };
+
+  const sessionPurchase = async (sessionSigner: Keypair, redeemedTokens: number) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchaseWithSession(
+        new anchor.BN(50_000),
+        { tokenAmount: new anchor.BN(redeemedTokens) },
+        transactionId
+      )
+      .accounts({
+        sessionSigner: sessionSigner.publicKey,
+        sessionKey: sessionKeyPda(sessionSigner.publicKey),
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        sessionEscrow,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+      })
+      .signers([sessionSigner])
+      .rpc();
+
+    return transactionRecord;
+  };
+
+  const revokeSessionKey = (sessionSigner: Keypair) =>
+    program.methods
+      .revokeSessionKey()
+      .accounts({
+        customer: customer.publicKey,
+        customerTokenAccount,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        sessionEscrow,
+        sessionKey: sessionKeyPda(sessionSigner.publicKey),
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([customer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    [sessionEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("session_escrow")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Warung Sesi", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Spends within the allowance and credits rewards to the customer", async () => {
+    const customerBefore = await balance(customerTokenAccount);
+    const sessionSigner = await createSessionKey(600);
+    expect(await balance(customerTokenAccount)).to.equal(customerBefore - SPEND_LIMIT);
+
+    const transactionRecord = await sessionPurchase(sessionSigner, 6 * TOKEN);
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.customer.toBase58()).to.equal(customer.publicKey.toBase58());
+    expect(record.redeemedTokenAmount.toNumber()).to.equal(6 * TOKEN);
+    expect(record.rewardAmount.toNumber()).to.be.greaterThan(0);
+    expect(await balance(merchantTokenAccount)).to.equal(6 * TOKEN);
+    expect(await balance(customerTokenAccount)).to.equal(
+      customerBefore - SPEND_LIMIT + record.rewardAmount.toNumber()
+    );
+
+    const session = await program.account.sessionKey.fetch(sessionKeyPda(sessionSigner.publicKey));
+    expect(session.spent.toNumber()).to.equal(6 * TOKEN);
+
+    try {
+      await sessionPurchase(sessionSigner, 5 * TOKEN);
+      expect.fail("Should have failed past the allowance");
+    } catch (error) {
+      expect(error.toString()).to.include("SessionAllowanceExceeded");
+    }
+
+    // The unspent allowance comes back and the key stops working at once
+    const walletBefore = await balance(customerTokenAccount);
+    await revokeSessionKey(sessionSigner);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore + 4 * TOKEN);
+
+    try {
+      await sessionPurchase(sessionSigner, 0);
+      expect.fail("Should have failed after revocation");
+    } catch (error) {
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+  });
+
+  it("Cannot move the customer's tokens with transfer_tokens", async () => {
+    const sessionSigner = await createSessionKey(600);
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transferRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transfer"), sessionSigner.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .transferTokens(new anchor.BN(TOKEN), transactionId, "")
+        .accounts({
+          sender: sessionSigner.publicKey,
+          senderTokenAccount: customerTokenAccount,
+          recipientTokenAccount: merchantTokenAccount,
+          config: configPda,
+          transferRecord,
+          mint: mintKeypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          activityRecord: null,
+          feeExemption: null,
+          treasuryTokenAccount: null,
+        })
+        .signers([sessionSigner])
+        .rpc();
+      expect.fail("Should have failed for a session key as sender");
+    } catch (error) {
+      expect(error.toString()).to.include("ConstraintRaw");
+    }
+
+    await revokeSessionKey(sessionSigner);
+  });
+
+  it("Cannot purchase with an expired session key", async () => {
+    const sessionSigner = await createSessionKey(3);
+    const session = await program.account.sessionKey.fetch(sessionKeyPda(sessionSigner.publicKey));
+    await waitForClusterTime(session.expiresAt.toNumber());
+
+    try {
+      await sessionPurchase(sessionSigner, TOKEN);
+      expect.fail("Should have failed after expiry");
+    } catch (error) {
+      expect(error.toString()).to.include("SessionKeyExpired");
+    }
+
+    // The customer can still take back an expired session's allowance
+    const walletBefore = await balance(customerTokenAccount);
+    await revokeSessionKey(sessionSigner);
+    expect(await balance(customerTokenAccount)).to.equal(walletBefore + SPEND_LIMIT);
+  });
+});
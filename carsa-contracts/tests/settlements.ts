@@ -0,0 +1,243 @@
+/**
+ * Settlement Test
+ *
+ * Covers merchant settlement: redeemed tokens the merchant receives are tracked as
+ * unsettled, then burned in exchange for an IDR payable record
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount, getMint } from "@solana/spl-token";
+
+describe("Merchant Settlement", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const supply = async () =>
+    Number((await getMint(provider.connection, mintKeypair.publicKey)).supply);
+
+  const unsettledTokens = async () =>
+    (await program.account.merchantAccount.fetch(merchantAccountPda)).unsettledTokens.toNumber();
+
+  const purchase = async (redeemTokens: number) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transaction"), merchantAccountPda.toBuffer(), customer.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    await program.methods
+      .processPurchaseWithRedemption(
+        new anchor.BN(50_000),
+        { tokenAmount: new anchor.BN(redeemTokens * TOKEN) },
+        transactionId,
+        null
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool: null,
+        customerStakeRecord: null,
+        activityRecord: null,
+        redemptionActivityRecord: null,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+        invoice: null,
+      })
+      .signers([customer])
+      .rpc();
+  };
+
+  const settle = async (periodId: number, settleTokens: number) => {
+    const [settlementRecord] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("settlement"),
+        merchantAccountPda.toBuffer(),
+        new anchor.BN(periodId).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .settleMerchant(new anchor.BN(periodId), new anchor.BN(settleTokens * TOKEN))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantTokenAccount,
+        mint: mintKeypair.publicKey,
+        config: configPda,
+        settlementRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        priceFeed: null,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    return program.account.settlementRecord.fetch(settlementRecord);
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Toko Setor", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Burns and records the redeemed tokens over two settlement cycles", async () => {
+    for (const [periodId, redeemTokens] of [[1, 10], [2, 4]]) {
+      await purchase(redeemTokens);
+      expect(await unsettledTokens()).to.equal(redeemTokens * TOKEN);
+      expect(await balance(merchantTokenAccount)).to.equal(redeemTokens * TOKEN);
+
+      const supplyBefore = await supply();
+      const record = await settle(periodId, redeemTokens);
+
+      expect(record.periodId.toNumber()).to.equal(periodId);
+      expect(record.tokenAmount.toNumber()).to.equal(redeemTokens * TOKEN);
+      expect(record.tokenToIdrRate.toNumber()).to.equal(1_000);
+      expect(record.idrValue.toNumber()).to.equal(redeemTokens * 1_000);
+
+      expect(await unsettledTokens()).to.equal(0);
+      expect(await balance(merchantTokenAccount)).to.equal(0);
+      expect(await supply()).to.equal(supplyBefore - redeemTokens * TOKEN);
+    }
+  });
+
+  it("Rejects settling more than is unsettled", async () => {
+    await purchase(3);
+
+    try {
+      await settle(3, 4);
+      expect.fail("Should have failed for more than the unsettled tokens");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidSettlementAmount");
+    }
+  });
+
+  it("Rejects a duplicate period", async () => {
+    try {
+      await settle(2, 1);
+      expect.fail("Should have failed for an already settled period");
+    } catch (error) {
+      expect(error.toString()).to.include("already in use");
+    }
+
+    await settle(3, 3);
+    expect(await unsettledTokens()).to.equal(0);
+  });
+});
@@ -0,0 +1,406 @@
+/**
+ * Staker Cashback Boost Test
+ *
+ * Covers the extra purchase cashback for customers staking in the voucher pool
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountInstruction,
+  createApproveInstruction,
+  createAccount,
+  getAssociatedTokenAddress,
+  getAccount,
+} from "@solana/spl-token";
+import {
+  defaultPoolConfig,
+  depositVoucher,
+  getPoolStatePDA,
+  getUserStakePDA,
+  initializePool,
+  parseCpiEvents,
+} from "../voucher-pool-client";
+
+describe("Staker Cashback Boost", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const MERCHANT_CASHBACK_BPS = 300; // 3%
+  const STAKER_BOOST_BPS = 100; // +1%
+  const BOOST_THRESHOLD = new anchor.BN(100 * 1e9);
+  const FIAT_AMOUNT = new anchor.BN(100_000); // Rp 100,000
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let merchantOwner: Keypair;
+  let voucherMint: PublicKey;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let poolState: PublicKey;
+
+  interface Customer {
+    keypair: Keypair;
+    tokenAccount: PublicKey;
+    stakeRecord: PublicKey;
+  }
+
+  let staker: Customer;
+  let smallStaker: Customer;
+  let spoofer: Customer;
+
+  const poolConfig = defaultPoolConfig();
+
+  const airdrop = async (key: PublicKey) =>
+    provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(key, 5 * anchor.web3.LAMPORTS_PER_SOL)
+    );
+
+  const setupCustomer = async (stake: number): Promise<Customer> => {
+    const keypair = Keypair.generate();
+    await airdrop(keypair.publicKey);
+
+    const tokenAccount = await getAssociatedTokenAddress(
+      mintKeypair.publicKey,
+      keypair.publicKey
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountInstruction(
+          keypair.publicKey,
+          tokenAccount,
+          keypair.publicKey,
+          mintKeypair.publicKey
+        )
+      ),
+      [keypair]
+    );
+
+    const [stakeRecord] = getUserStakePDA(program.programId, poolState, keypair.publicKey);
+
+    if (stake > 0) {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          createApproveInstruction(tokenAccount, poolDelegate.publicKey, keypair.publicKey, stake)
+        ),
+        [keypair]
+      );
+      await program.methods
+        .mintLokalTokens(new anchor.BN(stake))
+        .accounts({
+          authority: updateAuthority.publicKey,
+          mint: mintKeypair.publicKey,
+          mintAuthority: mintAuthorityPda,
+          config: configPda,
+          destination: tokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([updateAuthority])
+        .rpc();
+      await depositVoucher(
+        program,
+        poolDelegate,
+        keypair.publicKey,
+        voucherMint,
+        new anchor.BN(stake)
+      );
+    }
+
+    return { keypair, tokenAccount, stakeRecord };
+  };
+
+  const purchase = async (
+    customer: Customer,
+    stakeRecord: PublicKey | null,
+    stakePool: PublicKey | null = stakeRecord ? poolState : null
+  ) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transactionRecord] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("transaction"),
+        merchantAccountPda.toBuffer(),
+        customer.keypair.publicKey.toBuffer(),
+        Buffer.from(transactionId),
+      ],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .processPurchase(FIAT_AMOUNT, null, transactionId, null)
+      .accounts({
+        customer: customer.keypair.publicKey,
+        merchantAccount: merchantAccountPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        customerTokenAccount: customer.tokenAccount,
+        merchantTokenAccount: merchantTokenAccount,
+        transactionRecord,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        stakePool,
+        customerStakeRecord: stakeRecord,
+      })
+      .signers([customer.keypair])
+      .rpc({ commitment: "confirmed" });
+
+    return { tx, transactionRecord };
+  };
+
+  // Simulated by the provider wallet; the customer does not sign
+  const preview = (
+    customer: Customer,
+    stakeRecord: PublicKey | null,
+    redeemTokenAmount: anchor.BN | null = null
+  ) =>
+    program.methods
+      .previewPurchaseReward(FIAT_AMOUNT, redeemTokenAmount)
+      .accounts({
+        customer: customer.keypair.publicKey,
+        merchantAccount: merchantAccountPda,
+        config: configPda,
+        stakePool: stakeRecord ? poolState : null,
+        customerStakeRecord: stakeRecord,
+      })
+      .view();
+
+  // reward = fiat * rate / 10_000 / 1_000 tokens
+  const expectedReward = (rateBps: number) =>
+    FIAT_AMOUNT.muln(rateBps).mul(new anchor.BN(1e9)).divn(10_000).divn(1_000);
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    poolAuthority = Keypair.generate();
+    poolDelegate = Keypair.generate();
+    merchantOwner = Keypair.generate();
+
+    for (const keypair of [updateAuthority, poolAuthority, poolDelegate, merchantOwner]) {
+      await airdrop(keypair.publicKey);
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    await program.methods
+      .registerMerchant("Warung Boost", "restaurant", MERCHANT_CASHBACK_BPS, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+
+    // Stakes in pool 0 of the Lokal mint earn the boost
+    voucherMint = mintKeypair.publicKey;
+    await initializePool(
+      program,
+      poolAuthority,
+      poolDelegate.publicKey,
+      voucherMint,
+      poolConfig
+    );
+    [poolState] = getPoolStatePDA(program.programId, voucherMint);
+
+    staker = await setupCustomer(150 * 1e9);
+    smallStaker = await setupCustomer(10 * 1e9);
+    spoofer = await setupCustomer(0);
+  });
+
+  it("Only the update authority can configure a valid boost", async () => {
+    try {
+      await program.methods
+        .setStakerBoost(BOOST_THRESHOLD, STAKER_BOOST_BPS)
+        .accounts({
+          authority: merchantOwner.publicKey,
+          config: configPda,
+        })
+        .signers([merchantOwner])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+
+    try {
+      await program.methods
+        .setStakerBoost(BOOST_THRESHOLD, 10_001)
+        .accounts({
+          authority: updateAuthority.publicKey,
+          config: configPda,
+        })
+        .signers([updateAuthority])
+        .rpc();
+      expect.fail("Should have failed above 100%");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidStakerBoost");
+    }
+
+    await program.methods
+      .setStakerBoost(BOOST_THRESHOLD, STAKER_BOOST_BPS)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.stakerBoostThreshold.toString()).to.equal(BOOST_THRESHOLD.toString());
+    expect(config.stakerBoostBps).to.equal(STAKER_BOOST_BPS);
+  });
+
+  it("Pays the merchant rate when no stake record is supplied", async () => {
+    const before = await getAccount(provider.connection, staker.tokenAccount);
+    const { transactionRecord } = await purchase(staker, null);
+    const after = await getAccount(provider.connection, staker.tokenAccount);
+
+    expect((after.amount - before.amount).toString()).to.equal(
+      expectedReward(MERCHANT_CASHBACK_BPS).toString()
+    );
+
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.cashbackRate).to.equal(MERCHANT_CASHBACK_BPS);
+    expect(record.stakerBoostBps).to.equal(0);
+  });
+
+  it("Boosts the cashback of stakers above the threshold", async () => {
+    const before = await getAccount(provider.connection, staker.tokenAccount);
+    const { tx, transactionRecord } = await purchase(staker, staker.stakeRecord);
+    const after = await getAccount(provider.connection, staker.tokenAccount);
+
+    const boostedRate = MERCHANT_CASHBACK_BPS + STAKER_BOOST_BPS;
+    expect((after.amount - before.amount).toString()).to.equal(
+      expectedReward(boostedRate).toString()
+    );
+
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.cashbackRate).to.equal(boostedRate);
+    expect(record.stakerBoostBps).to.equal(STAKER_BOOST_BPS);
+
+    const txDetails = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const event = parseCpiEvents(program, txDetails).find(
+      (event) => event.name.toLowerCase() === "purchaseprocessedevent"
+    );
+    expect(event).to.not.be.undefined;
+    expect(event.data.stakerBoostBps).to.equal(STAKER_BOOST_BPS);
+    expect(event.data.cashbackRate).to.equal(boostedRate);
+  });
+
+  it("Previews the same reward a purchase then mints", async () => {
+    for (const stakeRecord of [null, staker.stakeRecord]) {
+      const expected = await preview(staker, stakeRecord);
+
+      const before = await getAccount(provider.connection, staker.tokenAccount);
+      const { transactionRecord } = await purchase(staker, stakeRecord);
+      const after = await getAccount(provider.connection, staker.tokenAccount);
+
+      expect((after.amount - before.amount).toString()).to.equal(
+        expected.rewardAmount.toString()
+      );
+
+      const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+      expect(record.totalValue.toString()).to.equal(expected.totalValue.toString());
+      expect(record.rewardAmount.toString()).to.equal(expected.rewardAmount.toString());
+      expect(record.cashbackRate).to.equal(expected.cashbackRate);
+      expect(record.stakerBoostBps).to.equal(expected.stakerBoostBps);
+    }
+  });
+
+  it("Previews token redemption without touching balances", async () => {
+    const redeemAmount = new anchor.BN(2_500_000_000); // 2.5 tokens, valued at Rp 2,000
+    const before = await getAccount(provider.connection, staker.tokenAccount);
+    const expected = await preview(staker, staker.stakeRecord, redeemAmount);
+    const after = await getAccount(provider.connection, staker.tokenAccount);
+
+    expect(after.amount.toString()).to.equal(before.amount.toString());
+    expect(expected.totalValue.toString()).to.equal(FIAT_AMOUNT.addn(2_000).toString());
+    expect(expected.stakerBoostBps).to.equal(STAKER_BOOST_BPS);
+  });
+
+  it("Rejects previews with another wallet's stake record", async () => {
+    try {
+      await preview(spoofer, staker.stakeRecord);
+      expect.fail("Should have failed with another wallet's stake record");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidStakeRecord");
+    }
+  });
+
+  it("Leaves the rate unchanged below the threshold", async () => {
+    const { transactionRecord } = await purchase(smallStaker, smallStaker.stakeRecord);
+
+    const record = await program.account.purchaseTransaction.fetch(transactionRecord);
+    expect(record.cashbackRate).to.equal(MERCHANT_CASHBACK_BPS);
+    expect(record.stakerBoostBps).to.equal(0);
+  });
+
+  it("Rejects a stake record owned by a different wallet", async () => {
+    try {
+      await purchase(spoofer, staker.stakeRecord);
+      expect.fail("Should have failed with another wallet's stake record");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidStakeRecord");
+    }
+  });
+
+  it("Rejects a stake record passed without its pool", async () => {
+    try {
+      await purchase(staker, staker.stakeRecord, null);
+      expect.fail("Should have failed without the stake pool");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidStakeRecord");
+    }
+  });
+});
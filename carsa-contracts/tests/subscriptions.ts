@@ -0,0 +1,266 @@
+/**
+ * Subscription Test
+ *
+ * Covers recurring merchant charges: the customer approves the subscription PDA as
+ * delegate, a crank charges once per elapsed period up to the agreed number of
+ * periods, and cancelling revokes the approval
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Subscriptions", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const TOKEN = 1e9;
+  const MONTHLY_FEE = 5 * TOKEN;
+  const PERIOD_SECONDS = 2;
+  const MAX_PERIODS = 2;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let merchantOwner: Keypair;
+  let customer: Keypair;
+  let cranker: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let merchantAccountPda: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let customerTokenAccount: PublicKey;
+  let subscriptionPda: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const createSubscription = () =>
+    program.methods
+      .createSubscription(
+        new anchor.BN(MONTHLY_FEE),
+        new anchor.BN(PERIOD_SECONDS),
+        MAX_PERIODS,
+        false
+      )
+      .accounts({
+        customer: customer.publicKey,
+        merchantAccount: merchantAccountPda,
+        config: configPda,
+        customerTokenAccount,
+        subscription: subscriptionPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc();
+
+  const chargeSubscription = () =>
+    program.methods
+      .chargeSubscription()
+      .accounts({
+        cranker: cranker.publicKey,
+        subscription: subscriptionPda,
+        merchantAccount: merchantAccountPda,
+        customerTokenAccount,
+        merchantTokenAccount,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        treasuryTokenAccount: null,
+        priceFeed: null,
+        rewardEscrow: null,
+        rewardLot: null,
+      })
+      .signers([cranker])
+      .rpc();
+
+  const cancelSubscription = () =>
+    program.methods
+      .cancelSubscription()
+      .accounts({
+        customer: customer.publicKey,
+        subscription: subscriptionPda,
+        customerTokenAccount,
+        config: configPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([customer])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    merchantOwner = Keypair.generate();
+    customer = Keypair.generate();
+    cranker = Keypair.generate();
+
+    for (const keypair of [updateAuthority, merchantOwner, customer, cranker]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    [merchantAccountPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant"), merchantOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    [subscriptionPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("subscription"),
+        customer.publicKey.toBuffer(),
+        merchantAccountPda.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const registry = await program.account.merchantRegistry.fetch(registryPda);
+    const [merchantIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_index"), registry.merchantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    await program.methods
+      .registerMerchant("Gym Sehat", "retail", 500, new anchor.BN(0))
+      .accounts({
+        merchantOwner: merchantOwner.publicKey,
+        merchantAccount: merchantAccountPda,
+        merchantRegistry: registryPda,
+        merchantIndex,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchantOwner])
+      .rpc();
+
+    merchantTokenAccount = await createAccount(
+      provider.connection,
+      merchantOwner,
+      mintKeypair.publicKey,
+      merchantOwner.publicKey
+    );
+    customerTokenAccount = await createAccount(
+      provider.connection,
+      customer,
+      mintKeypair.publicKey,
+      customer.publicKey
+    );
+    await program.methods
+      .mintLokalTokens(new anchor.BN(100 * TOKEN))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: customerTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Charges each elapsed period until exhausted", async () => {
+    await createSubscription();
+
+    const approved = await getAccount(provider.connection, customerTokenAccount);
+    expect(approved.delegate.toBase58()).to.equal(subscriptionPda.toBase58());
+    expect(Number(approved.delegatedAmount)).to.equal(MONTHLY_FEE * MAX_PERIODS);
+
+    // The first period is due on creation
+    const customerBefore = await balance(customerTokenAccount);
+    await chargeSubscription();
+    expect(await balance(customerTokenAccount)).to.equal(customerBefore - MONTHLY_FEE);
+    expect(await balance(merchantTokenAccount)).to.equal(MONTHLY_FEE);
+
+    let subscription = await program.account.subscription.fetch(subscriptionPda);
+    expect(subscription.periodsCharged).to.equal(1);
+
+    try {
+      await chargeSubscription();
+      expect.fail("Should have failed before the period elapsed");
+    } catch (error) {
+      expect(error.toString()).to.include("SubscriptionPeriodNotElapsed");
+    }
+
+    await waitForClusterTime(subscription.nextChargeAt.toNumber());
+    await chargeSubscription();
+    subscription = await program.account.subscription.fetch(subscriptionPda);
+    expect(subscription.periodsCharged).to.equal(MAX_PERIODS);
+    expect(await balance(merchantTokenAccount)).to.equal(MONTHLY_FEE * MAX_PERIODS);
+
+    await waitForClusterTime(subscription.nextChargeAt.toNumber());
+    try {
+      await chargeSubscription();
+      expect.fail("Should have failed after the last period");
+    } catch (error) {
+      expect(error.toString()).to.include("SubscriptionExhausted");
+    }
+
+    await cancelSubscription();
+  });
+
+  it("Cannot charge a cancelled subscription", async () => {
+    await createSubscription();
+    await chargeSubscription();
+    await cancelSubscription();
+
+    const revoked = await getAccount(provider.connection, customerTokenAccount);
+    expect(revoked.delegate).to.be.null;
+
+    const subscription = await program.account.subscription.fetchNullable(subscriptionPda);
+    expect(subscription).to.be.null;
+
+    try {
+      await chargeSubscription();
+      expect.fail("Should have failed for a cancelled subscription");
+    } catch (error) {
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+  });
+});
@@ -0,0 +1,297 @@
+/**
+ * Transfer Fee Test
+ *
+ * Covers the treasury fee on peer-to-peer transfers and per-wallet exemptions
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { parseCpiEvents } from "../voucher-pool-client";
+
+describe("Transfer Fee", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const FEE_BPS = 100; // 1%
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let sender: Keypair;
+  let merchant: Keypair;
+  let recipient: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let senderTokenAccount: PublicKey;
+  let merchantTokenAccount: PublicKey;
+  let recipientTokenAccount: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+
+  const balance = async (account: PublicKey) =>
+    new anchor.BN((await getAccount(provider.connection, account)).amount.toString());
+
+  const feeExemptionPDA = (wallet: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("fee_exemption"), wallet.toBuffer()],
+      program.programId
+    )[0];
+
+  const setTransferFee = (feeBps: number) =>
+    program.methods
+      .setTransferFee(feeBps)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  const transfer = async (
+    from: Keypair,
+    fromTokenAccount: PublicKey,
+    amount: anchor.BN,
+    exempt = false
+  ) => {
+    const transactionId = Array.from(Keypair.generate().publicKey.toBytes());
+    const [transferRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("transfer"), from.publicKey.toBuffer(), Buffer.from(transactionId)],
+      program.programId
+    );
+
+    const recipientBefore = await balance(recipientTokenAccount);
+    const treasuryBefore = await balance(treasuryTokenAccount);
+
+    const tx = await program.methods
+      .transferTokens(amount, transactionId, "")
+      .accounts({
+        sender: from.publicKey,
+        senderTokenAccount: fromTokenAccount,
+        recipientTokenAccount,
+        config: configPda,
+        transferRecord,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        activityRecord: null,
+        feeExemption: exempt ? feeExemptionPDA(from.publicKey) : null,
+        treasuryTokenAccount,
+      })
+      .signers([from])
+      .rpc({ commitment: "confirmed" });
+
+    return {
+      tx,
+      record: await program.account.tokenTransfer.fetch(transferRecord),
+      toRecipient: (await balance(recipientTokenAccount)).sub(recipientBefore),
+      toTreasury: (await balance(treasuryTokenAccount)).sub(treasuryBefore),
+    };
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    sender = Keypair.generate();
+    merchant = Keypair.generate();
+    recipient = Keypair.generate();
+
+    for (const keypair of [updateAuthority, sender, merchant, recipient]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    const tokenAccount = (owner: Keypair) =>
+      createAccount(provider.connection, owner, mintKeypair.publicKey, owner.publicKey);
+    senderTokenAccount = await tokenAccount(sender);
+    merchantTokenAccount = await tokenAccount(merchant);
+    recipientTokenAccount = await tokenAccount(recipient);
+    const [treasuryAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+    treasuryTokenAccount = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      treasuryAuthorityPda,
+      Keypair.generate()
+    );
+
+    for (const destination of [senderTokenAccount, merchantTokenAccount]) {
+      await program.methods
+        .mintLokalTokens(new anchor.BN(100 * 1e9))
+        .accounts({
+          authority: updateAuthority.publicKey,
+          mint: mintKeypair.publicKey,
+          mintAuthority: mintAuthorityPda,
+          config: configPda,
+          destination,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([updateAuthority])
+        .rpc();
+    }
+  });
+
+  it("Charges nothing while the fee is zero", async () => {
+    const amount = new anchor.BN(5 * 1e9);
+    const { record, toRecipient, toTreasury } = await transfer(
+      sender,
+      senderTokenAccount,
+      amount
+    );
+
+    expect(toRecipient.toString()).to.equal(amount.toString());
+    expect(toTreasury.toString()).to.equal("0");
+    expect(record.fee.toString()).to.equal("0");
+    expect(record.netAmount.toString()).to.equal(amount.toString());
+  });
+
+  it("Needs a treasury before a fee can be set", async () => {
+    try {
+      await setTransferFee(FEE_BPS);
+      expect.fail("Should have failed without a treasury");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidTransferFee");
+    }
+
+    await program.methods
+      .setProtocolFee(0)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        treasuryTokenAccount,
+      })
+      .signers([updateAuthority])
+      .rpc();
+    await setTransferFee(FEE_BPS);
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.transferFeeBps).to.equal(FEE_BPS);
+  });
+
+  it("Deducts the fee from the amount and records gross, fee and net", async () => {
+    const amount = new anchor.BN(10 * 1e9);
+    const fee = amount.muln(FEE_BPS).divn(10_000);
+
+    const { tx, record, toRecipient, toTreasury } = await transfer(
+      sender,
+      senderTokenAccount,
+      amount
+    );
+
+    expect(toTreasury.toString()).to.equal(fee.toString());
+    expect(toRecipient.toString()).to.equal(amount.sub(fee).toString());
+    expect(record.amount.toString()).to.equal(amount.toString());
+    expect(record.fee.toString()).to.equal(fee.toString());
+    expect(record.netAmount.toString()).to.equal(amount.sub(fee).toString());
+
+    const txDetails = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const event = parseCpiEvents(program, txDetails).find(
+      (event) => event.name.toLowerCase() === "tokenstransferredevent"
+    );
+    expect(event).to.not.be.undefined;
+    expect(event.data.amount.toString()).to.equal(amount.toString());
+    expect(event.data.fee.toString()).to.equal(fee.toString());
+    expect(event.data.netAmount.toString()).to.equal(amount.sub(fee).toString());
+  });
+
+  it("Rounds the fee on tiny transfers down in the recipient's favor", async () => {
+    const justBelow = await transfer(sender, senderTokenAccount, new anchor.BN(99));
+    expect(justBelow.toTreasury.toString()).to.equal("0");
+    expect(justBelow.toRecipient.toString()).to.equal("99");
+
+    const atOne = await transfer(sender, senderTokenAccount, new anchor.BN(100));
+    expect(atOne.toTreasury.toString()).to.equal("1");
+    expect(atOne.toRecipient.toString()).to.equal("99");
+  });
+
+  it("Waives the fee for exempt wallets", async () => {
+    await program.methods
+      .addFeeExemption()
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        wallet: merchant.publicKey,
+        feeExemption: feeExemptionPDA(merchant.publicKey),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    const amount = new anchor.BN(10 * 1e9);
+    const { record, toRecipient, toTreasury } = await transfer(
+      merchant,
+      merchantTokenAccount,
+      amount,
+      true
+    );
+    expect(toTreasury.toString()).to.equal("0");
+    expect(toRecipient.toString()).to.equal(amount.toString());
+    expect(record.fee.toString()).to.equal("0");
+
+    // Another wallet's exemption does not apply
+    try {
+      await transfer(sender, senderTokenAccount, amount, true);
+      expect.fail("Should have failed with a missing exemption");
+    } catch (error) {
+      expect(error.toString()).to.match(/AccountNotInitialized|ConstraintSeeds/);
+    }
+  });
+
+  it("Charges the fee again once an exemption is removed", async () => {
+    await program.methods
+      .removeFeeExemption()
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        feeExemption: feeExemptionPDA(merchant.publicKey),
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    const amount = new anchor.BN(1e9);
+    const { toTreasury } = await transfer(merchant, merchantTokenAccount, amount);
+    expect(toTreasury.toString()).to.equal(amount.muln(FEE_BPS).divn(10_000).toString());
+  });
+});
@@ -151,6 +151,7 @@ describe("Carsa Token Transfer Tests", () => {
         toTokenAccount: receiverTokenAccount,
         transferRecord: transferRecordPda,
         config: configPda,
+        mint: mintKeypair.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -234,6 +235,7 @@ describe("Carsa Token Transfer Tests", () => {
           toTokenAccount: receiverTokenAccount,
           transferRecord: transferRecordPda,
           config: configPda,
+          mint: mintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -317,6 +319,7 @@ describe("Carsa Token Transfer Tests", () => {
           toTokenAccount: receiverTokenAccount,
           transferRecord: transferRecordPda,
           config: configPda,
+          mint: mintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -366,6 +369,7 @@ describe("Carsa Token Transfer Tests", () => {
           toTokenAccount: userTokenAccount, // Same account
           transferRecord: transferRecordPda,
           config: configPda,
+          mint: mintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -435,6 +439,7 @@ describe("Carsa Token Transfer Tests", () => {
           toTokenAccount: receiverTokenAccount,
           transferRecord: transferRecordPda,
           config: configPda,
+          mint: mintKeypair.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
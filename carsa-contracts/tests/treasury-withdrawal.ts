@@ -0,0 +1,255 @@
+/**
+ * Treasury Withdrawal Test
+ *
+ * Covers withdrawing protocol fees under the per-withdrawal cap and dual control
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+import { parseCpiEvents } from "../voucher-pool-client";
+
+describe("Treasury Withdrawal", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const REASON_OPERATIONS = 1;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let cosigner: Keypair;
+  let outsider: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let treasuryAuthorityPda: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+  let destination: PublicKey;
+
+  const balance = async (account: PublicKey) =>
+    new anchor.BN((await getAccount(provider.connection, account)).amount.toString());
+
+  const withdrawalPDA = (index: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_withdrawal"), index.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+
+  const setControls = (cosignerKey: PublicKey, cap: anchor.BN) =>
+    program.methods
+      .setTreasuryControls(cosignerKey, cap)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  const withdraw = async (
+    amount: anchor.BN,
+    signer: Keypair | null = null,
+    authority: Keypair = updateAuthority
+  ) => {
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    const withdrawalRecord = withdrawalPDA(config.treasuryWithdrawalCount);
+
+    const tx = await program.methods
+      .withdrawTreasury(amount, REASON_OPERATIONS)
+      .accounts({
+        authority: authority.publicKey,
+        cosigner: signer ? signer.publicKey : null,
+        config: configPda,
+        treasuryAuthority: treasuryAuthorityPda,
+        treasuryTokenAccount,
+        destination,
+        withdrawalRecord,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signer ? [authority, signer] : [authority])
+      .rpc({ commitment: "confirmed" });
+
+    return { tx, withdrawalRecord };
+  };
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    cosigner = Keypair.generate();
+    outsider = Keypair.generate();
+
+    for (const keypair of [updateAuthority, outsider]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [treasuryAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    treasuryTokenAccount = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      treasuryAuthorityPda,
+      Keypair.generate()
+    );
+    destination = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      updateAuthority.publicKey
+    );
+
+    await program.methods
+      .setProtocolFee(100)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        treasuryAuthority: treasuryAuthorityPda,
+        treasuryTokenAccount,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    // Stand in for fees collected from redemptions and transfers
+    await program.methods
+      .mintLokalTokens(new anchor.BN(50 * 1e9))
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        destination: treasuryTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+  });
+
+  it("Withdraws with the update authority alone and records it", async () => {
+    const amount = new anchor.BN(5 * 1e9);
+    const before = await balance(destination);
+
+    const { tx, withdrawalRecord } = await withdraw(amount);
+
+    expect((await balance(destination)).sub(before).toString()).to.equal(amount.toString());
+
+    const record = await program.account.treasuryWithdrawal.fetch(withdrawalRecord);
+    expect(record.amount.toString()).to.equal(amount.toString());
+    expect(record.destination.toBase58()).to.equal(destination.toBase58());
+    expect(record.authority.toBase58()).to.equal(updateAuthority.publicKey.toBase58());
+    expect(record.cosigner.toBase58()).to.equal(PublicKey.default.toBase58());
+    expect(record.reasonCode).to.equal(REASON_OPERATIONS);
+
+    const txDetails = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const event = parseCpiEvents(program, txDetails).find(
+      (event) => event.name.toLowerCase() === "treasurywithdrawnevent"
+    );
+    expect(event).to.not.be.undefined;
+    expect(event.data.amount.toString()).to.equal(amount.toString());
+    expect(event.data.index.toString()).to.equal(record.index.toString());
+  });
+
+  it("Rejects withdrawals by anyone but the update authority", async () => {
+    try {
+      await withdraw(new anchor.BN(1e9), null, outsider);
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+  });
+
+  it("Enforces the per-withdrawal cap", async () => {
+    const cap = new anchor.BN(2 * 1e9);
+    await setControls(PublicKey.default, cap);
+
+    try {
+      await withdraw(cap.addn(1));
+      expect.fail("Should have failed above the cap");
+    } catch (error) {
+      expect(error.toString()).to.include("TreasuryWithdrawalCapExceeded");
+    }
+
+    await withdraw(cap);
+  });
+
+  it("Rejects withdrawals larger than the treasury balance", async () => {
+    await setControls(PublicKey.default, new anchor.BN(0));
+    const available = await balance(treasuryTokenAccount);
+
+    try {
+      await withdraw(available.addn(1));
+      expect.fail("Should have failed above the balance");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientBalance");
+    }
+  });
+
+  it("Requires the co-signer in dual-control mode", async () => {
+    await setControls(cosigner.publicKey, new anchor.BN(0));
+    const amount = new anchor.BN(1e9);
+
+    try {
+      await withdraw(amount);
+      expect.fail("Should have failed without the co-signer");
+    } catch (error) {
+      expect(error.toString()).to.include("TreasuryCosignerRequired");
+    }
+
+    try {
+      await withdraw(amount, outsider);
+      expect.fail("Should have failed with the wrong co-signer");
+    } catch (error) {
+      expect(error.toString()).to.include("TreasuryCosignerRequired");
+    }
+
+    const { withdrawalRecord } = await withdraw(amount, cosigner);
+    const record = await program.account.treasuryWithdrawal.fetch(withdrawalRecord);
+    expect(record.cosigner.toBase58()).to.equal(cosigner.publicKey.toBase58());
+
+    const config = await program.account.lokalMintConfig.fetch(configPda);
+    expect(config.treasuryWithdrawalCount.toNumber()).to.equal(3);
+  });
+});
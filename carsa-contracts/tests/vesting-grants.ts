@@ -0,0 +1,284 @@
+/**
+ * Vesting Grant Test
+ *
+ * Covers merchant incentive grants minted into escrow that vest linearly to the
+ * beneficiary, and revocation returning the unvested part to the treasury
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import { expect } from "chai";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
+import { TOKEN_PROGRAM_ID, createAccount, getAccount } from "@solana/spl-token";
+
+describe("Vesting Grants", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const GRANT_AMOUNT = 1_200 * 1e9;
+  const CLIFF_SECONDS = 3;
+  const DURATION_SECONDS = 8;
+
+  let mintKeypair: Keypair;
+  let updateAuthority: Keypair;
+  let beneficiary: Keypair;
+
+  let mintAuthorityPda: PublicKey;
+  let configPda: PublicKey;
+  let vestingEscrow: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+  let beneficiaryTokenAccount: PublicKey;
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const clusterTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  const balance = async (tokenAccount: PublicKey) =>
+    Number((await getAccount(provider.connection, tokenAccount)).amount);
+
+  const createGrant = async (signer: Keypair = updateAuthority) => {
+    const { vestingGrantCount } = await program.account.lokalMintConfig.fetch(configPda);
+    const [vestingGrant] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vesting_grant"), vestingGrantCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const start = await clusterTime();
+
+    await program.methods
+      .createVestingGrant(
+        beneficiary.publicKey,
+        new anchor.BN(GRANT_AMOUNT),
+        new anchor.BN(start),
+        new anchor.BN(start + CLIFF_SECONDS),
+        new anchor.BN(DURATION_SECONDS)
+      )
+      .accounts({
+        authority: signer.publicKey,
+        config: configPda,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        vestingEscrow,
+        vestingGrant,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        cosigner: null,
+      })
+      .signers([signer])
+      .rpc();
+
+    return vestingGrant;
+  };
+
+  const claim = (vestingGrant: PublicKey, signer: Keypair = beneficiary) =>
+    program.methods
+      .claimVested()
+      .accounts({
+        beneficiary: signer.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        vestingEscrow,
+        vestingGrant,
+        beneficiaryTokenAccount,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([signer])
+      .rpc();
+
+  const revoke = (vestingGrant: PublicKey) =>
+    program.methods
+      .revokeVestingGrant()
+      .accounts({
+        authority: updateAuthority.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        vestingEscrow,
+        vestingGrant,
+        treasuryTokenAccount,
+        mint: mintKeypair.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+  before(async () => {
+    mintKeypair = Keypair.generate();
+    updateAuthority = Keypair.generate();
+    beneficiary = Keypair.generate();
+
+    for (const keypair of [updateAuthority, beneficiary]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          5 * anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    [mintAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_authority")],
+      program.programId
+    );
+    [configPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("config")],
+      program.programId
+    );
+    [vestingEscrow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vesting_escrow")],
+      program.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_registry")],
+      program.programId
+    );
+    const [treasuryAuthorityPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("treasury_authority")],
+      program.programId
+    );
+
+    await program.methods
+      .initializeLokalMint()
+      .accounts({
+        updateAuthority: updateAuthority.publicKey,
+        mint: mintKeypair.publicKey,
+        mintAuthority: mintAuthorityPda,
+        config: configPda,
+        merchantRegistry: registryPda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([updateAuthority, mintKeypair])
+      .rpc();
+
+    treasuryTokenAccount = await createAccount(
+      provider.connection,
+      updateAuthority,
+      mintKeypair.publicKey,
+      treasuryAuthorityPda,
+      Keypair.generate()
+    );
+    await program.methods
+      .setProtocolFee(0)
+      .accounts({
+        authority: updateAuthority.publicKey,
+        config: configPda,
+        treasuryAuthority: treasuryAuthorityPda,
+        treasuryTokenAccount,
+      })
+      .signers([updateAuthority])
+      .rpc();
+
+    beneficiaryTokenAccount = await createAccount(
+      provider.connection,
+      beneficiary,
+      mintKeypair.publicKey,
+      beneficiary.publicKey
+    );
+  });
+
+  it("Only the update authority can create grants", async () => {
+    try {
+      await createGrant(beneficiary);
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UpdateAuthorityMismatch");
+    }
+  });
+
+  it("Vests linearly after the cliff and releases exactly the grant at the end", async () => {
+    const vestingGrant = await createGrant();
+    const grant = await program.account.vestingGrant.fetch(vestingGrant);
+    expect(grant.total.toNumber()).to.equal(GRANT_AMOUNT);
+    expect(await balance(vestingEscrow)).to.equal(GRANT_AMOUNT);
+
+    try {
+      await claim(vestingGrant);
+      expect.fail("Should have failed before the cliff");
+    } catch (error) {
+      expect(error.toString()).to.include("VestingCliffNotReached");
+    }
+
+    await waitForClusterTime(grant.cliff.toNumber());
+    await claim(vestingGrant);
+
+    // Partway through: something vested, but not the whole grant
+    const partial = await balance(beneficiaryTokenAccount);
+    expect(partial).to.be.greaterThan(0);
+    expect(partial).to.be.lessThan(GRANT_AMOUNT);
+    let updated = await program.account.vestingGrant.fetch(vestingGrant);
+    expect(updated.released.toNumber()).to.equal(partial);
+
+    await waitForClusterTime(grant.start.add(grant.duration).toNumber());
+    await claim(vestingGrant);
+
+    updated = await program.account.vestingGrant.fetch(vestingGrant);
+    expect(updated.released.toNumber()).to.equal(GRANT_AMOUNT);
+    expect(await balance(beneficiaryTokenAccount)).to.equal(GRANT_AMOUNT);
+    expect(await balance(vestingEscrow)).to.equal(0);
+
+    try {
+      await claim(vestingGrant);
+      expect.fail("Should have failed with nothing left to claim");
+    } catch (error) {
+      expect(error.toString()).to.include("NothingVested");
+    }
+  });
+
+  it("Only the beneficiary can claim", async () => {
+    const vestingGrant = await createGrant();
+    const grant = await program.account.vestingGrant.fetch(vestingGrant);
+    await waitForClusterTime(grant.cliff.toNumber());
+
+    try {
+      await claim(vestingGrant, updateAuthority);
+      expect.fail("Should have failed for someone other than the beneficiary");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+
+    // Leave nothing behind for the next test
+    await revoke(vestingGrant);
+    await claim(vestingGrant);
+  });
+
+  it("Revocation returns the unvested tokens to the treasury", async () => {
+    const vestingGrant = await createGrant();
+    const treasuryBefore = await balance(treasuryTokenAccount);
+    const walletBefore = await balance(beneficiaryTokenAccount);
+
+    // Revoked before the cliff, nothing vests
+    await revoke(vestingGrant);
+    expect(await balance(treasuryTokenAccount)).to.equal(treasuryBefore + GRANT_AMOUNT);
+
+    try {
+      await revoke(vestingGrant);
+      expect.fail("Should have failed for an already revoked grant");
+    } catch (error) {
+      expect(error.toString()).to.include("VestingGrantRevoked");
+    }
+
+    const grant = await program.account.vestingGrant.fetch(vestingGrant);
+    await waitForClusterTime(grant.start.add(grant.duration).toNumber());
+    try {
+      await claim(vestingGrant);
+      expect.fail("Should have failed for a grant revoked before its cliff");
+    } catch (error) {
+      expect(error.toString()).to.include("NothingVested");
+    }
+    expect(await balance(beneficiaryTokenAccount)).to.equal(walletBefore);
+  });
+});
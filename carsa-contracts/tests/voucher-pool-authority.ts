@@ -0,0 +1,174 @@
+/**
+ * Voucher Pool Authority Transfer Test
+ *
+ * Covers the two-step pool authority handover: proposing, replacing a
+ * stale proposal, and accepting as the nominee
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+} from "@solana/web3.js";
+import { assert, expect } from "chai";
+import { toConfigUpdate, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Authority Transfer", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 11;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let nominee: Keypair;
+  let staleNominee: Keypair;
+
+  const propose = (authority: Keypair, newAuthority: PublicKey) =>
+    program.methods
+      .proposePoolAuthority(newAuthority)
+      .accounts({
+        poolAuthority: authority.publicKey,
+        poolState,
+      })
+      .signers([authority])
+      .rpc();
+
+  const accept = (newAuthority: Keypair) =>
+    program.methods
+      .acceptPoolAuthority()
+      .accounts({
+        newAuthority: newAuthority.publicKey,
+        poolState,
+      })
+      .signers([newAuthority])
+      .rpc();
+
+  const updateApy = (authority: Keypair, apyBasisPoints: number) =>
+    program.methods
+      .updatePoolConfig(toConfigUpdate({ apyBasisPoints }))
+      .accounts({
+        poolAuthority: authority.publicKey,
+        poolState,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Rejects acceptance when nothing is pending", async () => {
+    nominee = Keypair.generate();
+    staleNominee = Keypair.generate();
+    for (const keypair of [nominee, staleNominee]) {
+      await provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          keypair.publicKey,
+          anchor.web3.LAMPORTS_PER_SOL
+        )
+      );
+    }
+
+    try {
+      await accept(nominee);
+      expect.fail("Should have failed without a pending transfer");
+    } catch (error) {
+      expect(error.toString()).to.include("NoPendingAuthority");
+    }
+  });
+
+  it("Only the current authority can propose", async () => {
+    try {
+      await propose(nominee, nominee.publicKey);
+      expect.fail("Should have failed for a non-authority proposer");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("A newer proposal overwrites a stale one", async () => {
+    await propose(poolAuthority, staleNominee.publicKey);
+    await propose(poolAuthority, nominee.publicKey);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.pendingPoolAuthority.toBase58(), nominee.publicKey.toBase58());
+    assert.equal(poolData.poolAuthority.toBase58(), poolAuthority.publicKey.toBase58());
+
+    try {
+      await accept(staleNominee);
+      expect.fail("Should have failed for the replaced nominee");
+    } catch (error) {
+      expect(error.toString()).to.include("NotPendingAuthority");
+    }
+  });
+
+  it("Nominee accepts and takes over the pool", async () => {
+    await accept(nominee);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.poolAuthority.toBase58(), nominee.publicKey.toBase58());
+    assert.ok(poolData.pendingPoolAuthority.equals(PublicKey.default));
+  });
+
+  it("Admin instructions honor the new authority and reject the old one", async () => {
+    try {
+      await updateApy(poolAuthority, 1500);
+      expect.fail("Should have failed for the previous authority");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+
+    await updateApy(nominee, 1500);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.config.apyBasisPoints, 1500);
+
+    await program.methods
+      .setPoolPause(true, null)
+      .accounts({
+        authority: nominee.publicKey,
+        poolState,
+      })
+      .signers([nominee])
+      .rpc();
+
+    try {
+      await program.methods
+        .setPoolPause(false, null)
+        .accounts({
+          authority: poolAuthority.publicKey,
+          poolState,
+        })
+        .signers([poolAuthority])
+        .rpc();
+      expect.fail("Should have failed for the previous authority");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedPauser");
+    }
+  });
+});
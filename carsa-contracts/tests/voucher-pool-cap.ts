@@ -0,0 +1,153 @@
+/**
+ * Voucher Pool TVL Cap Test
+ *
+ * Covers the pool-level cap on total staked vouchers
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { toConfigUpdate, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - TVL Cap", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 10;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const updateCap = (maxTotalStaked: number) =>
+    program.methods
+      .updatePoolConfig(toConfigUpdate({ maxTotalStaked: new anchor.BN(maxTotalStaked) }))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, { maxTotalStaked: new anchor.BN(100 * 1e9) }));
+  });
+
+  it("Accepts deposits up to exactly the cap", async () => {
+    await deposit(60 * 1e9);
+    await deposit(40 * 1e9);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.totalVoucherStaked.toString(), (100 * 1e9).toString());
+
+    try {
+      await deposit(1);
+      expect.fail("Should have failed above the pool cap");
+    } catch (error) {
+      expect(error.toString()).to.include("PoolCapReached");
+    }
+  });
+
+  it("Allows lowering the cap below the current TVL", async () => {
+    await updateCap(50 * 1e9);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.config.maxTotalStaked.toString(), (50 * 1e9).toString());
+    assert.equal(poolData.totalVoucherStaked.toString(), (100 * 1e9).toString());
+
+    try {
+      await deposit(1);
+      expect.fail("Should have failed while above the lowered cap");
+    } catch (error) {
+      expect(error.toString()).to.include("PoolCapReached");
+    }
+
+    // Withdrawals still work and free up capacity under the new cap
+    await redeem(60 * 1e9);
+    await deposit(10 * 1e9);
+
+    try {
+      await deposit(1);
+      expect.fail("Should have failed at the lowered cap");
+    } catch (error) {
+      expect(error.toString()).to.include("PoolCapReached");
+    }
+  });
+
+  it("Zero removes the cap", async () => {
+    await updateCap(0);
+    await deposit(100 * 1e9);
+  });
+});
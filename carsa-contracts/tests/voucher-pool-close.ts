@@ -0,0 +1,275 @@
+/**
+ * Voucher Pool Stake Record Closing Test
+ *
+ * Covers closing empty stake records and re-staking afterwards
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import {
+  addFixturePool,
+  getUnstakeRequestPDA,
+  getYieldBatchPDA,
+  setupPoolFixture,
+  setupStaker,
+} from "../voucher-pool-client";
+
+describe("Voucher Pool - Close Stake Record", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 6;
+  // Queued withdrawals leave an empty stake with its yield still owed
+  const QUEUED_POOL_ID = 27;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const closeRecord = (authority: Keypair) =>
+    program.methods
+      .closeStakeRecord()
+      .accounts({
+        authority: authority.publicKey,
+        poolState,
+        userStakeRecord,
+        rentReceiver: poolDelegate.publicKey,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Fails to close a record with remaining stake", async () => {
+    await deposit(20 * 1e9);
+
+    try {
+      await closeRecord(user);
+      expect.fail("Should have failed with remaining stake");
+    } catch (error) {
+      expect(error.toString()).to.include("StakeRecordNotEmpty");
+    }
+  });
+
+  it("Fails to close while earned yield is unsettled", async () => {
+    const base = { poolAuthority, poolDelegate, lokalMint };
+    const pool = await addFixturePool(program, base, QUEUED_POOL_ID, {
+      queuedWithdrawals: true,
+    });
+    const staker = await setupStaker(program, { ...base, ...pool });
+    const [unstakeRequest] = getUnstakeRequestPDA(
+      program.programId,
+      pool.poolState,
+      staker.user.publicKey
+    );
+
+    await program.methods
+      .depositVoucher(new anchor.BN(20 * 1e9))
+      .accounts({
+        user: staker.user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState: pool.poolState,
+        userStakeRecord: staker.userStakeRecord,
+        userVoucherAta: staker.userTokenAccount,
+        poolVaultAta: pool.poolVaultAta,
+        receiptMint: pool.receiptMint,
+        userReceiptAta: staker.userReceiptAta,
+        poolVaultAuthority: pool.poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+    const batchId = randomBytes(32);
+    await program.methods
+      .recordYield(new anchor.BN(1e9), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState: pool.poolState,
+        yieldBatch: getYieldBatchPDA(program.programId, pool.poolState, batchId)[0],
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+    // Queuing the whole stake settles its yield into the record's pending payout
+    await program.methods
+      .requestUnstake(new anchor.BN(20 * 1e9))
+      .accounts({
+        user: staker.user.publicKey,
+        poolState: pool.poolState,
+        userStakeRecord: staker.userStakeRecord,
+        unstakeRequest,
+        receiptMint: pool.receiptMint,
+        userReceiptAta: staker.userReceiptAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([staker.user])
+      .rpc();
+
+    const emptied = await program.account.userStakeRecord.fetch(staker.userStakeRecord);
+    assert.equal(emptied.stakedAmount.toNumber(), 0);
+    assert.ok(emptied.pendingPayout.toNumber() > 0);
+
+    try {
+      await program.methods
+        .closeStakeRecord()
+        .accounts({
+          authority: poolDelegate.publicKey,
+          poolState: pool.poolState,
+          userStakeRecord: staker.userStakeRecord,
+          rentReceiver: poolDelegate.publicKey,
+        })
+        .signers([poolDelegate])
+        .rpc();
+      expect.fail("Should have failed with unsettled yield");
+    } catch (error) {
+      expect(error.toString()).to.include("PendingYield");
+    }
+  });
+
+  it("Redeeming pays out the yield and empties the record", async () => {
+    await recordYield(1e9);
+    await redeem(20 * 1e9);
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(record.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.ok(record.totalYieldClaimed.toNumber() > 0);
+  });
+
+  it("Rejects a closer that is neither the staker nor the delegate", async () => {
+    const stranger = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        stranger.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    try {
+      await closeRecord(stranger);
+      expect.fail("Should have failed with an unauthorized closer");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedCloser");
+    }
+  });
+
+  it("Closes the empty record and returns rent to the delegate", async () => {
+    const delegateBefore = await provider.connection.getBalance(poolDelegate.publicKey);
+    const recordRent = await provider.connection.getBalance(userStakeRecord);
+
+    await closeRecord(user);
+
+    const delegateAfter = await provider.connection.getBalance(poolDelegate.publicKey);
+    assert.equal(delegateAfter - delegateBefore, recordRent);
+    assert.isNull(await provider.connection.getAccountInfo(userStakeRecord));
+  });
+
+  it("Re-staking after close starts from a fresh record", async () => {
+    await recordYield(1e9); // Recorded while the user has no record
+
+    await deposit(10 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(record.stakedAmount.toString(), (10 * 1e9).toString());
+    assert.equal(record.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.equal(record.totalYieldClaimed.toNumber(), 0);
+    assert.equal(poolData.totalStakers.toNumber(), 1);
+  });
+});
@@ -0,0 +1,271 @@
+/**
+ * Voucher Pool Auto Compound Test
+ *
+ * Covers restaking recorded yield as vouchers supplied by the delegate,
+ * alongside manual claims
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  mintTo,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Auto Compound", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 13;
+  const RATE = 500_000_000; // 0.5 SOL per LOKAL
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let delegateTokenAccount: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const setAutoCompound = (enabled: boolean) =>
+    program.methods
+      .setAutoCompound(enabled)
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+      })
+      .signers([user])
+      .rpc();
+
+  const compound = (rate: number) =>
+    program.methods
+      .compoundUser(new anchor.BN(rate))
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        source: delegateTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  // Yield the user could claim right now (no losses are recorded in this pool)
+  const claimable = async () => {
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    return record.pendingPayout.add(
+      record.weightedStake
+        .mul(poolData.rewardIndex.sub(record.userRewardIndex))
+        .div(new anchor.BN("1000000000000000000"))
+    );
+  };
+
+  // Compounds and checks the yield consumed covers the vouchers credited
+  const compoundAndCheck = async (rate: number) => {
+    const yieldBefore = await claimable();
+    const before = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const receiptsBefore = (await getAccount(provider.connection, userReceiptAta)).amount;
+
+    await compound(rate);
+
+    const after = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const credited = after.stakedAmount.sub(before.stakedAmount);
+    const consumed = yieldBefore.sub(after.pendingPayout);
+    const scale = new anchor.BN(1e9);
+
+    assert.ok(credited.gtn(0));
+    // The consumed yield pays for the vouchers in full, rounded up to the next lamport
+    const cost = credited.mul(new anchor.BN(rate));
+    assert.ok(consumed.mul(scale).gte(cost));
+    assert.ok(consumed.subn(1).mul(scale).lt(cost));
+    assert.ok(consumed.lte(yieldBefore));
+    // Whatever stays claimable could not buy one more voucher unit
+    assert.ok(credited.addn(1).mul(new anchor.BN(rate)).gt(yieldBefore.mul(scale)));
+    assert.equal(after.lastCompoundRate.toNumber(), rate);
+    assert.equal(
+      after.totalCompounded.sub(before.totalCompounded).toString(),
+      credited.toString()
+    );
+
+    // Compounded vouchers come with receipts like a deposit
+    const receiptsAfter = (await getAccount(provider.connection, userReceiptAta)).amount;
+    assert.equal((receiptsAfter - receiptsBefore).toString(), credited.toString());
+    return credited;
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Rejects compounding a stake that has not opted in", async () => {
+    delegateTokenAccount = await createAccount(
+      provider.connection,
+      poolDelegate,
+      lokalMint,
+      poolDelegate.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      poolAuthority,
+      lokalMint,
+      delegateTokenAccount,
+      poolAuthority,
+      100 * 1e9
+    );
+
+    await deposit(100 * 1e9);
+    await recordYield(1e9);
+
+    try {
+      await compound(RATE);
+      expect.fail("Should have failed without auto compound enabled");
+    } catch (error) {
+      expect(error.toString()).to.include("AutoCompoundDisabled");
+    }
+  });
+
+  it("Compounds recorded yield into staked vouchers", async () => {
+    await setAutoCompound(true);
+
+    const credited = await compoundAndCheck(RATE);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(
+      poolData.totalVoucherStaked.toString(),
+      new anchor.BN(100 * 1e9).add(credited).toString()
+    );
+    assert.equal(record.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.equal(
+      (await getAccount(provider.connection, poolVaultAta)).amount.toString(),
+      poolData.totalVoucherStaked.toString()
+    );
+  });
+
+  it("Compounds a second time at a different rate", async () => {
+    await recordYield(3e8);
+
+    await compoundAndCheck(700_000_000);
+  });
+
+  it("Interleaves with a manual claim", async () => {
+    await recordYield(2e8);
+
+    // Redeeming claims every bit of yield, leaving nothing to compound
+    await redeem(10 * 1e9);
+    try {
+      await compound(RATE);
+      expect.fail("Should have failed with no yield left to compound");
+    } catch (error) {
+      expect(error.toString()).to.include("NothingToCompound");
+    }
+
+    await recordYield(4e8);
+    await compoundAndCheck(RATE);
+  });
+
+  it("Stops compounding once the user opts out", async () => {
+    await setAutoCompound(false);
+    await recordYield(1e8);
+
+    try {
+      await compound(RATE);
+      expect.fail("Should have failed after opting out");
+    } catch (error) {
+      expect(error.toString()).to.include("AutoCompoundDisabled");
+    }
+  });
+});
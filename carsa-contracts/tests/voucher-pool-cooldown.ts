@@ -0,0 +1,161 @@
+/**
+ * Voucher Pool Withdraw Cooldown Test
+ *
+ * Covers the per-user cooldown between a deposit and the next withdrawal
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Withdraw Cooldown", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 12;
+  const COOLDOWN_SECONDS = 3;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const cooldownEnd = async () => {
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    return record.lastDepositAt.toNumber() + COOLDOWN_SECONDS;
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, {
+      withdrawCooldownSeconds: COOLDOWN_SECONDS,
+    }));
+  });
+
+  it("Rejects a withdrawal right after a deposit", async () => {
+    await deposit(20 * 1e9);
+
+    try {
+      await redeem(5 * 1e9);
+      expect.fail("Should have failed inside the cooldown");
+    } catch (error) {
+      expect(error.toString()).to.include("CooldownActive");
+    }
+  });
+
+  it("Allows withdrawal once the cooldown has elapsed", async () => {
+    await waitForClusterTime(await cooldownEnd());
+
+    await redeem(5 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toString(), (15 * 1e9).toString());
+  });
+
+  it("Does not throttle back-to-back withdrawals", async () => {
+    await redeem(5 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toString(), (10 * 1e9).toString());
+    assert.ok(record.lastActionAt.toNumber() >= record.lastDepositAt.toNumber());
+  });
+
+  it("A new deposit restarts the cooldown", async () => {
+    await deposit(5 * 1e9);
+
+    try {
+      await redeem(1e9);
+      expect.fail("Should have failed inside the restarted cooldown");
+    } catch (error) {
+      expect(error.toString()).to.include("CooldownActive");
+    }
+
+    await waitForClusterTime(await cooldownEnd());
+    await redeem(15 * 1e9);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.totalVoucherStaked.toNumber(), 0);
+  });
+});
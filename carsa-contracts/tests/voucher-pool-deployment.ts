@@ -0,0 +1,207 @@
+/**
+ * Voucher Pool Deployment Test
+ *
+ * Covers the delegate moving principal out of the vault for deployment
+ * and returning it
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Deployment", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 5;
+  const MAX_DEPLOYMENT_BPS = 5000; // 50% of principal
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let deploymentAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const withdrawForDeployment = (amount: number) =>
+    program.methods
+      .withdrawForDeployment(new anchor.BN(amount))
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        poolVaultAta,
+        poolVaultAuthority,
+        destination: deploymentAccount,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, {
+      maxDeploymentBps: MAX_DEPLOYMENT_BPS,
+    }, { approved: 100 * 1e9 }));
+
+    deploymentAccount = await createAccount(
+      provider.connection,
+      poolDelegate,
+      lokalMint,
+      poolDelegate.publicKey
+    );
+
+    await program.methods
+      .depositVoucher(new anchor.BN(100 * 1e9))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  });
+
+  it("Rejects withdrawal before a destination is set", async () => {
+    try {
+      await withdrawForDeployment(10 * 1e9);
+      expect.fail("Should have failed without a registered destination");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidDeploymentDestination");
+    }
+  });
+
+  it("Pool authority registers the deployment destination", async () => {
+    await program.methods
+      .setDeploymentDestination()
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+        destination: deploymentAccount,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(
+      poolData.deploymentDestination.toBase58(),
+      deploymentAccount.toBase58()
+    );
+  });
+
+  it("Withdraws up to the deployment cap", async () => {
+    await withdrawForDeployment(30 * 1e9);
+    await withdrawForDeployment(20 * 1e9);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.deployedAmount.toString(), (50 * 1e9).toString());
+
+    const deployed = await getAccount(provider.connection, deploymentAccount);
+    assert.equal(deployed.amount.toString(), (50 * 1e9).toString());
+
+    try {
+      await withdrawForDeployment(1);
+      expect.fail("Should have failed above the deployment cap");
+    } catch (error) {
+      expect(error.toString()).to.include("DeploymentCapExceeded");
+    }
+  });
+
+  it("Fails redemption gracefully when the vault is short", async () => {
+    try {
+      await redeem(100 * 1e9);
+      expect.fail("Should have failed with insufficient vault liquidity");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientVaultLiquidity");
+    }
+
+    // Whatever is still in the vault can be redeemed
+    await redeem(40 * 1e9);
+  });
+
+  it("Round-trips principal back into the vault", async () => {
+    await program.methods
+      .returnPrincipal(new anchor.BN(50 * 1e9))
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        source: deploymentAccount,
+        poolVaultAta,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+    let poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.deployedAmount.toNumber(), 0);
+
+    await redeem(60 * 1e9);
+
+    poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.totalVoucherStaked.toNumber(), 0);
+    const vault = await getAccount(provider.connection, poolVaultAta);
+    assert.equal(vault.amount.toString(), "0");
+  });
+});
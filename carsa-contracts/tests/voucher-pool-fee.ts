@@ -0,0 +1,198 @@
+/**
+ * Voucher Pool Performance Fee Test
+ *
+ * Covers the protocol's performance fee on recorded yield
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, toConfigUpdate, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Performance Fee", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 16;
+  const FEE_BPS = 1000; // 10% of yield
+  const INDEX_SCALE = new anchor.BN("1000000000000000000");
+
+  const feeRecipient = Keypair.generate().publicKey;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const updateFee = (performanceFeeBps: number, feeRecipient: PublicKey) =>
+    program.methods
+      .updatePoolConfig(toConfigUpdate({ performanceFeeBps, feeRecipient }))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, { performanceFeeBps: FEE_BPS, feeRecipient }));
+  });
+
+  it("Takes the fee before the yield reaches the reward index", async () => {
+    await deposit(100 * 1e9);
+
+    const yieldAmount = new anchor.BN(1_000_000_007);
+    await recordYield(yieldAmount.toNumber());
+
+    const fee = yieldAmount.muln(FEE_BPS).divn(10_000);
+    const net = yieldAmount.sub(fee);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.protocolFeesAccrued.toString(), fee.toString());
+    assert.equal(poolData.totalYieldEarned.toString(), yieldAmount.toString());
+    assert.equal(
+      poolData.rewardIndex.toString(),
+      net.mul(INDEX_SCALE).div(poolData.weightedTotalStaked).toString()
+    );
+  });
+
+  it("Users claim yield computed on the net amount", async () => {
+    const poolData = await program.account.poolState.fetch(poolState);
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const expected = record.weightedStake.mul(poolData.rewardIndex).div(INDEX_SCALE);
+
+    await redeem(100 * 1e9);
+
+    const after = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(after.totalYieldClaimed.toString(), expected.toString());
+
+    // Only index rounding separates the claim from the net yield
+    const net = new anchor.BN(1_000_000_007 - 100_000_000);
+    assert.ok(after.totalYieldClaimed.lte(net));
+    assert.ok(net.sub(after.totalYieldClaimed).lten(1));
+  });
+
+  it("Accumulates fees exactly across batches", async () => {
+    await deposit(40 * 1e9);
+
+    let expectedFees = (await program.account.poolState.fetch(poolState))
+      .protocolFeesAccrued;
+    for (const amount of [999, 123_456_789, 5_000_000_001]) {
+      await recordYield(amount);
+      expectedFees = expectedFees.add(new anchor.BN(amount).muln(FEE_BPS).divn(10_000));
+    }
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.protocolFeesAccrued.toString(), expectedFees.toString());
+  });
+
+  it("Rejects invalid fee settings", async () => {
+    try {
+      await updateFee(10_001, feeRecipient);
+      expect.fail("Should have failed above 100%");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidFeeConfig");
+    }
+
+    try {
+      await updateFee(FEE_BPS, PublicKey.default);
+      expect.fail("Should have failed without a fee recipient");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidFeeConfig");
+    }
+
+    // Dropping the fee needs no recipient and stops accrual
+    await updateFee(0, PublicKey.default);
+    const before = (await program.account.poolState.fetch(poolState)).protocolFeesAccrued;
+    await recordYield(1e9);
+    const after = (await program.account.poolState.fetch(poolState)).protocolFeesAccrued;
+    assert.equal(after.toString(), before.toString());
+  });
+});
@@ -0,0 +1,239 @@
+/**
+ * Voucher Pool Stake History Test
+ *
+ * Covers the per-user deposit and withdrawal audit trail
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, parseCpiEvents, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Stake History", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const STAKE_ACTION_SEED = "stake_action";
+
+  const POOL_ID = 21;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const nextEntry = async () => {
+    const record = await program.account.userStakeRecord.fetchNullable(userStakeRecord);
+    const actionIndex = record ? record.actionCount : new anchor.BN(0);
+    const [entry] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from(STAKE_ACTION_SEED),
+        poolState.toBuffer(),
+        user.publicKey.toBuffer(),
+        actionIndex.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    return entry;
+  };
+
+  const recordedEvent = async (signature: string) => {
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    for (const event of parseCpiEvents(program, tx)) {
+      if (event.name.toLowerCase() === "stakeactionrecordedevent") {
+        return event.data;
+      }
+    }
+    throw new Error("No stake action event emitted");
+  };
+
+  const depositWithHistory = async (amount: number) => {
+    const stakeActionRecord = await nextEntry();
+    const signature = await program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        stakeActionRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc({ commitment: "confirmed" });
+    return { stakeActionRecord, signature };
+  };
+
+  const redeemWithHistory = async (amount: number) => {
+    const stakeActionRecord = await nextEntry();
+    const signature = await program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        stakeActionRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc({ commitment: "confirmed" });
+    return { stakeActionRecord, signature };
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, { recordHistory: true }));
+  });
+
+  it("Writes one history entry per action and advances the counter", async () => {
+    const actions = [
+      { action: await depositWithHistory(50 * 1e9), type: "deposit", amount: 50, balance: 50 },
+      { action: await depositWithHistory(20 * 1e9), type: "deposit", amount: 20, balance: 70 },
+      { action: await redeemWithHistory(30 * 1e9), type: "withdrawal", amount: 30, balance: 40 },
+    ];
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.actionCount.toNumber(), actions.length);
+
+    for (const [index, { action, type, amount, balance }] of actions.entries()) {
+      const entry = await program.account.stakeActionRecord.fetch(action.stakeActionRecord);
+      assert.equal(entry.pool.toBase58(), poolState.toBase58());
+      assert.equal(entry.user.toBase58(), user.publicKey.toBase58());
+      assert.equal(entry.actionIndex.toNumber(), index);
+      assert.property(entry.actionType, type);
+      assert.equal(entry.amount.toString(), (amount * 1e9).toString());
+      assert.equal(entry.resultingBalance.toString(), (balance * 1e9).toString());
+    }
+  });
+
+  it("Entries can be rebuilt from the emitted events", async () => {
+    await recordYield(1e9);
+    const { stakeActionRecord, signature } = await depositWithHistory(5 * 1e9);
+
+    const entry = await program.account.stakeActionRecord.fetch(stakeActionRecord);
+    const event = await recordedEvent(signature);
+    const poolData = await program.account.poolState.fetch(poolState);
+
+    assert.equal(event.actionIndex.toString(), entry.actionIndex.toString());
+    assert.deepEqual(event.actionType, entry.actionType);
+    assert.equal(event.amount.toString(), entry.amount.toString());
+    assert.equal(event.resultingBalance.toString(), entry.resultingBalance.toString());
+    assert.equal(event.rewardIndex.toString(), entry.rewardIndex.toString());
+    assert.equal(event.timestamp.toString(), entry.timestamp.toString());
+    assert.equal(entry.rewardIndex.toString(), poolData.rewardIndex.toString());
+  });
+
+  it("Requires the history entry while history is on", async () => {
+    try {
+      await deposit(1e9);
+      expect.fail("Should have failed without a history entry");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidStakeHistory");
+    }
+  });
+
+  it("Keeps records with history from being closed", async () => {
+    await redeemWithHistory(45 * 1e9);
+
+    try {
+      await program.methods
+        .closeStakeRecord()
+        .accounts({
+          authority: user.publicKey,
+          poolState,
+          userStakeRecord,
+          rentReceiver: poolDelegate.publicKey,
+        })
+        .signers([user])
+        .rpc();
+      expect.fail("Should have failed with history entries");
+    } catch (error) {
+      expect(error.toString()).to.include("StakeHistoryExists");
+    }
+  });
+});
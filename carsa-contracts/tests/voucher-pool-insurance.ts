@@ -0,0 +1,259 @@
+/**
+ * Voucher Pool Insurance Test
+ *
+ * Covers the insurance reserve funded by deposits and draws against losses
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, parseCpiEvents, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Insurance", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 22;
+  const INSURANCE_FEE_BPS = 100; // 1% of each deposit
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let deploymentAccount: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const recordLoss = (amount: number) =>
+    program.methods
+      .recordLoss(new anchor.BN(amount))
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const drawInsurance = (amount: number) =>
+    program.methods
+      .drawInsurance(new anchor.BN(amount))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+        poolVaultAta,
+        poolVaultAuthority,
+        destination: deploymentAccount,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, { insuranceFeeBps: INSURANCE_FEE_BPS }));
+
+    deploymentAccount = await createAccount(
+      provider.connection,
+      poolAuthority,
+      lokalMint,
+      poolAuthority.publicKey
+    );
+  });
+
+  it("Keeps the insurance fee in the vault and stakes the remainder", async () => {
+    const vaultBefore = (await getAccount(provider.connection, poolVaultAta)).amount;
+    const signature = await deposit(100 * 1e9);
+    const vaultAfter = (await getAccount(provider.connection, poolVaultAta)).amount;
+
+    const fee = (100 * 1e9 * INSURANCE_FEE_BPS) / 10_000;
+    const net = 100 * 1e9 - fee;
+
+    assert.equal((vaultAfter - vaultBefore).toString(), (100 * 1e9).toString());
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toString(), net.toString());
+
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), net.toString());
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.totalVoucherStaked.toString(), net.toString());
+    assert.equal(poolData.insuranceReserve.toString(), fee.toString());
+    assert.equal(poolData.insuranceDrawn.toNumber(), 0);
+
+    // The deposit event is read from the inner self-CPI, not the logs
+    await provider.connection.confirmTransaction(signature, "confirmed");
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const event = parseCpiEvents(program, tx).find(
+      (event) => event.name.toLowerCase() === "voucherdepositedevent"
+    );
+    assert.ok(event);
+    assert.equal(event.data.amount.toString(), (100 * 1e9).toString());
+    assert.equal(event.data.insuranceFee.toString(), fee.toString());
+    assert.equal(event.data.newUserTotal.toString(), net.toString());
+  });
+
+  it("Rounds the fee down on tiny deposits", async () => {
+    const before = await program.account.poolState.fetch(poolState);
+
+    // 1% of 99 base units rounds down to 0
+    await deposit(99);
+
+    const after = await program.account.poolState.fetch(poolState);
+    assert.equal(after.insuranceReserve.toString(), before.insuranceReserve.toString());
+    assert.equal(
+      after.totalVoucherStaked.sub(before.totalVoucherStaked).toNumber(),
+      99
+    );
+  });
+
+  it("Rejects draws beyond the recorded losses", async () => {
+    await program.methods
+      .setDeploymentDestination()
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+        destination: deploymentAccount,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    // No loss has been recorded yet
+    try {
+      await drawInsurance(1);
+      expect.fail("Should have failed without a recorded loss");
+    } catch (error) {
+      expect(error.toString()).to.include("InsuranceDrawTooLarge");
+    }
+
+    await recordYield(1e9);
+    await recordLoss(3e8);
+
+    try {
+      await drawInsurance(3e8 + 1);
+      expect.fail("Should have failed above cumulative losses");
+    } catch (error) {
+      expect(error.toString()).to.include("InsuranceDrawTooLarge");
+    }
+  });
+
+  it("Draws the reserve up to the recorded losses", async () => {
+    const reserveBefore = (await program.account.poolState.fetch(poolState))
+      .insuranceReserve;
+
+    await drawInsurance(2e8);
+    await drawInsurance(1e8);
+
+    const destination = await getAccount(provider.connection, deploymentAccount);
+    assert.equal(destination.amount.toString(), (3e8).toString());
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.insuranceDrawn.toString(), (3e8).toString());
+    assert.equal(
+      reserveBefore.sub(poolData.insuranceReserve).toString(),
+      (3e8).toString()
+    );
+
+    // The loss is fully covered, so nothing more can be drawn
+    try {
+      await drawInsurance(1);
+      expect.fail("Should have failed once the losses are covered");
+    } catch (error) {
+      expect(error.toString()).to.include("InsuranceDrawTooLarge");
+    }
+  });
+
+  it("Rejects draws from anyone but the pool authority", async () => {
+    await recordLoss(1e8);
+
+    try {
+      await program.methods
+        .drawInsurance(new anchor.BN(1))
+        .accounts({
+          poolAuthority: poolDelegate.publicKey,
+          poolState,
+          poolVaultAta,
+          poolVaultAuthority,
+          destination: deploymentAccount,
+          voucherMint: lokalMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([poolDelegate])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+  });
+});
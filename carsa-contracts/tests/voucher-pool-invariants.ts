@@ -0,0 +1,210 @@
+/**
+ * Voucher Pool Invariants Test
+ *
+ * Covers the pool invariant report and its strict mode
+ * Corruption cases need a build with the `test-utils` feature and are skipped otherwise
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, parseCpiEvents, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Invariants", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 19;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  // The corruption hook only exists in builds with the `test-utils` feature
+  const hasTestUtils = program.idl.instructions.some(
+    (ix) => ix.name === "corruptPoolState"
+  );
+
+  const verify = (strict: boolean) =>
+    program.methods
+      .verifyPoolInvariants(strict)
+      .accounts({ poolState, poolVaultAta })
+      .rpc({ commitment: "confirmed" });
+
+  const report = async () => {
+    const signature = await verify(false);
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    for (const event of parseCpiEvents(program, tx)) {
+      if (event.name.toLowerCase() === "poolinvariantreportevent") {
+        return event.data;
+      }
+    }
+    throw new Error("No invariant report emitted");
+  };
+
+  const corrupt = (fields: {
+    totalVoucherStaked?: anchor.BN;
+    totalStakers?: anchor.BN;
+    rewardIndex?: anchor.BN;
+  }) =>
+    (program.methods as any)
+      .corruptPoolState(
+        fields.totalVoucherStaked ?? null,
+        fields.totalStakers ?? null,
+        fields.rewardIndex ?? null
+      )
+      .accounts({ poolAuthority: poolAuthority.publicKey, poolState })
+      .signers([poolAuthority])
+      .rpc();
+
+  const expectStrictFailure = async () => {
+    try {
+      await verify(true);
+      expect.fail("Strict verification should have failed");
+    } catch (error) {
+      expect(error.toString()).to.include("PoolInvariantViolated");
+    }
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Reports a healthy pool and advances the verified index", async () => {
+    await deposit(50 * 1e9);
+    await recordYield(1e9);
+
+    const result = await report();
+    assert.isTrue(result.vaultCoversPrincipal);
+    assert.isTrue(result.stakersConsistent);
+    assert.isTrue(result.rewardIndexMonotonic);
+    assert.isTrue(result.passed);
+    assert.equal(result.vaultBalance.toString(), (50 * 1e9).toString());
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.lastVerifiedIndex.toString(), poolData.rewardIndex.toString());
+
+    // Strict mode passes silently on a healthy pool
+    await verify(true);
+  });
+
+  it("Flags a vault that no longer covers the principal", async function () {
+    if (!hasTestUtils) this.skip();
+
+    const staked = (await program.account.poolState.fetch(poolState)).totalVoucherStaked;
+    await corrupt({ totalVoucherStaked: staked.addn(1) });
+
+    const result = await report();
+    assert.isFalse(result.vaultCoversPrincipal);
+    assert.isTrue(result.stakersConsistent);
+    assert.isFalse(result.passed);
+    await expectStrictFailure();
+
+    await corrupt({ totalVoucherStaked: staked });
+  });
+
+  it("Flags a staker count that disagrees with the stake", async function () {
+    if (!hasTestUtils) this.skip();
+
+    await corrupt({ totalStakers: new anchor.BN(0) });
+
+    const result = await report();
+    assert.isFalse(result.stakersConsistent);
+    assert.isTrue(result.vaultCoversPrincipal);
+    assert.isFalse(result.passed);
+    await expectStrictFailure();
+
+    await corrupt({ totalStakers: new anchor.BN(1) });
+  });
+
+  it("Flags a reward index that went backwards", async function () {
+    if (!hasTestUtils) this.skip();
+
+    const before = await program.account.poolState.fetch(poolState);
+    await corrupt({ rewardIndex: before.lastVerifiedIndex.subn(1) });
+
+    const result = await report();
+    assert.isFalse(result.rewardIndexMonotonic);
+    assert.isFalse(result.passed);
+    await expectStrictFailure();
+
+    // A failed check keeps the old baseline
+    const after = await program.account.poolState.fetch(poolState);
+    assert.equal(after.lastVerifiedIndex.toString(), before.lastVerifiedIndex.toString());
+
+    await corrupt({ rewardIndex: before.rewardIndex });
+    assert.isTrue((await report()).passed);
+  });
+});
@@ -0,0 +1,237 @@
+/**
+ * Voucher Pool Lockup Test
+ *
+ * Covers lockup enforcement and the early-withdrawal penalty
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import {
+  FixturePool,
+  addFixturePool,
+  getUserStakePDA,
+  setupPoolFixture,
+  toConfigUpdate,
+} from "../voucher-pool-client";
+
+describe("Voucher Pool - Lockup", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const STRICT_POOL_ID = 3;
+  const PENALTY_POOL_ID = 4;
+  const LOCKUP_SECONDS = 4;
+  const PENALTY_BPS = 1000; // 10%
+
+  interface TestPool {
+    id: number;
+    state: PublicKey;
+    vaultAuthority: PublicKey;
+    vaultAta: PublicKey;
+    receiptMint: PublicKey;
+    userReceiptAta: PublicKey;
+    userStakeRecord: PublicKey;
+  }
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let strictPool: TestPool;
+  let penaltyPool: TestPool;
+
+  const toTestPool = (id: number, pool: FixturePool): TestPool => ({
+    id,
+    state: pool.poolState,
+    vaultAuthority: pool.poolVaultAuthority,
+    vaultAta: pool.poolVaultAta,
+    receiptMint: pool.receiptMint,
+    userReceiptAta: getAssociatedTokenAddressSync(pool.receiptMint, user.publicKey),
+    userStakeRecord: getUserStakePDA(program.programId, pool.poolState, user.publicKey)[0],
+  });
+
+  const deposit = async (pool: TestPool, amount: number) => {
+    await program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState: pool.state,
+        userStakeRecord: pool.userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta: pool.vaultAta,
+        receiptMint: pool.receiptMint,
+        userReceiptAta: pool.userReceiptAta,
+        poolVaultAuthority: pool.vaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const redeem = async (pool: TestPool, amount: number) => {
+    await program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState: pool.state,
+        userStakeRecord: pool.userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta: pool.vaultAta,
+        poolVaultAuthority: pool.vaultAuthority,
+        receiptMint: pool.receiptMint,
+        userReceiptAta: pool.userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+  };
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  before(async () => {
+    const fixture = await setupPoolFixture(
+      program,
+      STRICT_POOL_ID,
+      { lockupSeconds: LOCKUP_SECONDS },
+      { approved: 1000 * 1e9 }
+    );
+    ({ poolAuthority, poolDelegate, user, lokalMint, userTokenAccount } = fixture);
+
+    strictPool = toTestPool(STRICT_POOL_ID, fixture);
+    penaltyPool = toTestPool(
+      PENALTY_POOL_ID,
+      await addFixturePool(program, fixture, PENALTY_POOL_ID, {
+        lockupSeconds: LOCKUP_SECONDS,
+        earlyWithdrawPenaltyBps: PENALTY_BPS,
+      })
+    );
+  });
+
+  it("Rejects withdrawal during the lock when there is no penalty", async () => {
+    await deposit(strictPool, 10 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(
+      strictPool.userStakeRecord
+    );
+    assert.ok(record.lockUntil.toNumber() > 0);
+
+    try {
+      await redeem(strictPool, 10 * 1e9);
+      expect.fail("Should have failed while the stake is locked");
+    } catch (error) {
+      expect(error.toString()).to.include("StakeLocked");
+    }
+  });
+
+  it("Allows withdrawal once the lock expires", async () => {
+    const record = await program.account.userStakeRecord.fetch(
+      strictPool.userStakeRecord
+    );
+    await waitForClusterTime(record.lockUntil.toNumber());
+
+    const balanceBefore = (await getAccount(provider.connection, userTokenAccount)).amount;
+    await redeem(strictPool, 10 * 1e9);
+    const balanceAfter = (await getAccount(provider.connection, userTokenAccount)).amount;
+
+    assert.equal((balanceAfter - balanceBefore).toString(), (10 * 1e9).toString());
+    const poolData = await program.account.poolState.fetch(strictPool.state);
+    assert.equal(poolData.penaltyReserve.toNumber(), 0);
+  });
+
+  it("Top-ups extend the lock but never shorten it", async () => {
+    await deposit(strictPool, 1e9);
+    const first = await program.account.userStakeRecord.fetch(
+      strictPool.userStakeRecord
+    );
+
+    await deposit(strictPool, 1e9);
+    const second = await program.account.userStakeRecord.fetch(
+      strictPool.userStakeRecord
+    );
+    assert.ok(second.lockUntil.toNumber() >= first.lockUntil.toNumber());
+
+    // Raising the lockup later must not move the existing lock
+    await program.methods
+      .updatePoolConfig(toConfigUpdate({ lockupSeconds: 86_400 }))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState: strictPool.state,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    const third = await program.account.userStakeRecord.fetch(
+      strictPool.userStakeRecord
+    );
+    assert.equal(third.lockUntil.toString(), second.lockUntil.toString());
+  });
+
+  it("Charges the early-withdrawal penalty and keeps it in the vault", async () => {
+    await deposit(penaltyPool, 100 * 1e9);
+
+    const vaultBefore = (await getAccount(provider.connection, penaltyPool.vaultAta)).amount;
+    const balanceBefore = (await getAccount(provider.connection, userTokenAccount)).amount;
+    await redeem(penaltyPool, 50 * 1e9);
+    const vaultAfter = (await getAccount(provider.connection, penaltyPool.vaultAta)).amount;
+    const balanceAfter = (await getAccount(provider.connection, userTokenAccount)).amount;
+
+    const expectedPenalty = (50 * 1e9 * PENALTY_BPS) / 10_000;
+    assert.equal(
+      (balanceAfter - balanceBefore).toString(),
+      (50 * 1e9 - expectedPenalty).toString()
+    );
+    assert.equal(
+      (vaultBefore - vaultAfter).toString(),
+      (50 * 1e9 - expectedPenalty).toString()
+    );
+
+    const poolData = await program.account.poolState.fetch(penaltyPool.state);
+    assert.equal(poolData.penaltyReserve.toString(), expectedPenalty.toString());
+    assert.equal(poolData.totalVoucherStaked.toString(), (50 * 1e9).toString());
+  });
+
+  it("Rounds the penalty down on tiny withdrawals", async () => {
+    const reserveBefore = (await program.account.poolState.fetch(penaltyPool.state))
+      .penaltyReserve;
+
+    // 10% of 9 base units rounds down to 0, 10% of 19 rounds down to 1
+    await redeem(penaltyPool, 9);
+    let poolData = await program.account.poolState.fetch(penaltyPool.state);
+    assert.equal(poolData.penaltyReserve.sub(reserveBefore).toNumber(), 0);
+
+    await redeem(penaltyPool, 19);
+    poolData = await program.account.poolState.fetch(penaltyPool.state);
+    assert.equal(poolData.penaltyReserve.sub(reserveBefore).toNumber(), 1);
+  });
+});
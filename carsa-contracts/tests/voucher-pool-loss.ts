@@ -0,0 +1,157 @@
+/**
+ * Voucher Pool Loss Recording Test
+ *
+ * Covers losses on deployed capital offsetting the yield stakers can claim
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import {
+  StakerFixture,
+  getYieldBatchPDA,
+  setupPoolFixture,
+  setupStaker,
+} from "../voucher-pool-client";
+
+describe("Voucher Pool - Losses", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 9;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let lokalMint: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let alice: StakerFixture;
+  let bob: StakerFixture;
+
+  const deposit = (staker: StakerFixture, amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: staker.user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord: staker.userStakeRecord,
+        userVoucherAta: staker.userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta: staker.userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (staker: StakerFixture, amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: staker.user.publicKey,
+        poolState,
+        userStakeRecord: staker.userStakeRecord,
+        userVoucherAta: staker.userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta: staker.userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([staker.user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const recordLoss = (amount: number) =>
+    program.methods
+      .recordLoss(new anchor.BN(amount))
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  before(async () => {
+    const fixture = await setupPoolFixture(program, POOL_ID, {}, { approved: 1000 * 1e9 });
+    ({
+      poolAuthority,
+      poolDelegate,
+      lokalMint,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+    } = fixture);
+
+    alice = fixture;
+    bob = await setupStaker(program, fixture, { approved: 1000 * 1e9 });
+  });
+
+  it("Rejects a loss larger than the recorded pool value", async () => {
+    try {
+      await recordLoss(1);
+      expect.fail("Should have failed with nothing to lose");
+    } catch (error) {
+      expect(error.toString()).to.include("LossExceedsPoolValue");
+    }
+  });
+
+  it("Nets a gain and a later loss for stakers with different entry points", async () => {
+    // Alice is in for the gain, Bob only joins before the loss
+    await deposit(alice, 100 * 1e9);
+    await recordYield(10 * 1e9);
+    await deposit(bob, 100 * 1e9);
+    await recordLoss(4 * 1e9);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.cumulativeLosses.toString(), (4 * 1e9).toString());
+    assert.equal(poolData.totalSolStaked.toString(), (6 * 1e9).toString());
+
+    await redeem(alice, 100 * 1e9);
+    await redeem(bob, 100 * 1e9);
+
+    // Alice earned 10 and bears half of the 4 loss; Bob earned nothing and
+    // cannot go below zero
+    const aliceRecord = await program.account.userStakeRecord.fetch(alice.userStakeRecord);
+    const bobRecord = await program.account.userStakeRecord.fetch(bob.userStakeRecord);
+    assert.equal(aliceRecord.totalYieldClaimed.toString(), (8 * 1e9).toString());
+    assert.equal(bobRecord.totalYieldClaimed.toNumber(), 0);
+  });
+});
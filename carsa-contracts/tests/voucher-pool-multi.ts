@@ -0,0 +1,253 @@
+/**
+ * Multiple Voucher Pools Test
+ *
+ * Runs two pools side by side (flexible and locked) and verifies their
+ * accounting stays isolated
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createAssociatedTokenAccountIdempotent,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import {
+  defaultPoolConfig,
+  fixturePoolAddresses,
+  getUserStakePDA,
+  getYieldBatchPDA,
+  setupFixtureBase,
+  setupStaker,
+} from "../voucher-pool-client";
+
+describe("Voucher Pool - Multiple Pools", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  // Pool 0 is the singleton pool used by the workflow test
+  const FLEXIBLE_POOL_ID = 1;
+  const LOCKED_POOL_ID = 2;
+
+  interface TestPool {
+    id: number;
+    state: PublicKey;
+    vaultAuthority: PublicKey;
+    vaultAta: PublicKey;
+    receiptMint: PublicKey;
+    userReceiptAta: PublicKey;
+    userStakeRecord: PublicKey;
+  }
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let flexiblePool: TestPool;
+  let lockedPool: TestPool;
+
+  const derivePool = (poolId: number): TestPool => {
+    const pool = fixturePoolAddresses(program.programId, lokalMint, poolId);
+    return {
+      id: poolId,
+      state: pool.poolState,
+      vaultAuthority: pool.poolVaultAuthority,
+      vaultAta: pool.poolVaultAta,
+      receiptMint: pool.receiptMint,
+      userReceiptAta: getAssociatedTokenAddressSync(pool.receiptMint, user.publicKey),
+      userStakeRecord: getUserStakePDA(program.programId, pool.poolState, user.publicKey)[0],
+    };
+  };
+
+  const deposit = async (pool: TestPool, amount: number) => {
+    await program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState: pool.state,
+        userStakeRecord: pool.userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta: pool.vaultAta,
+        receiptMint: pool.receiptMint,
+        userReceiptAta: pool.userReceiptAta,
+        poolVaultAuthority: pool.vaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  before(async () => {
+    const base = await setupFixtureBase(program);
+    ({ poolAuthority, poolDelegate, lokalMint } = base);
+    ({ user, userTokenAccount } = await setupStaker(program, {
+      ...base,
+      ...fixturePoolAddresses(program.programId, lokalMint, FLEXIBLE_POOL_ID),
+    }));
+
+    flexiblePool = derivePool(FLEXIBLE_POOL_ID);
+    lockedPool = derivePool(LOCKED_POOL_ID);
+
+    // A vault ATA left over from an earlier attempt must not block initialization
+    await createAssociatedTokenAccountIdempotent(
+      provider.connection,
+      poolAuthority,
+      lokalMint,
+      lockedPool.vaultAuthority,
+      {},
+      TOKEN_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      true
+    );
+  });
+
+  it("Initializes two pools with different configs", async () => {
+    const configs = [
+      {
+        pool: flexiblePool,
+        config: defaultPoolConfig({
+          minStakeAmount: new anchor.BN(1_000_000),
+          apyBasisPoints: 500, // 5% APY
+        }),
+      },
+      {
+        pool: lockedPool,
+        config: defaultPoolConfig({
+          minStakeAmount: new anchor.BN(10_000_000_000),
+          maxStakePerUser: new anchor.BN(100_000_000_000),
+          apyBasisPoints: 1500, // 15% APY
+        }),
+      },
+    ];
+
+    for (const { pool, config } of configs) {
+      await program.methods
+        .initializePool(pool.id, config)
+        .accounts({
+          poolAuthority: poolAuthority.publicKey,
+          poolDelegate: poolDelegate.publicKey,
+          poolState: pool.state,
+          vaultAta: pool.vaultAta,
+          poolVaultAuthority: pool.vaultAuthority,
+          voucherMint: lokalMint,
+          receiptMint: pool.receiptMint,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([poolAuthority])
+        .rpc();
+
+      const poolData = await program.account.poolState.fetch(pool.state);
+      assert.equal(poolData.poolId, pool.id);
+      assert.equal(poolData.config.apyBasisPoints, config.apyBasisPoints);
+    }
+
+    assert.notEqual(flexiblePool.state.toBase58(), lockedPool.state.toBase58());
+  });
+
+  it("Keeps deposits isolated between pools", async () => {
+    await deposit(flexiblePool, 30 * 1e9);
+    await deposit(lockedPool, 50 * 1e9);
+    await deposit(flexiblePool, 5 * 1e9);
+
+    const flexibleData = await program.account.poolState.fetch(flexiblePool.state);
+    const lockedData = await program.account.poolState.fetch(lockedPool.state);
+    assert.equal(flexibleData.totalVoucherStaked.toString(), (35 * 1e9).toString());
+    assert.equal(lockedData.totalVoucherStaked.toString(), (50 * 1e9).toString());
+    assert.equal(flexibleData.totalStakers.toNumber(), 1);
+    assert.equal(lockedData.totalStakers.toNumber(), 1);
+
+    const flexibleRecord = await program.account.userStakeRecord.fetch(
+      flexiblePool.userStakeRecord
+    );
+    const lockedRecord = await program.account.userStakeRecord.fetch(
+      lockedPool.userStakeRecord
+    );
+    assert.equal(flexibleRecord.stakedAmount.toString(), (35 * 1e9).toString());
+    assert.equal(lockedRecord.stakedAmount.toString(), (50 * 1e9).toString());
+
+    const flexibleVault = await getAccount(provider.connection, flexiblePool.vaultAta);
+    const lockedVault = await getAccount(provider.connection, lockedPool.vaultAta);
+    assert.equal(flexibleVault.amount.toString(), (35 * 1e9).toString());
+    assert.equal(lockedVault.amount.toString(), (50 * 1e9).toString());
+  });
+
+  it("Enforces each pool's own limits", async () => {
+    // 1 LOKAL is fine for the flexible pool but below the locked pool minimum
+    await deposit(flexiblePool, 1e9);
+
+    try {
+      await deposit(lockedPool, 1e9);
+      expect.fail("Should have failed below the locked pool minimum");
+    } catch (error) {
+      expect(error.toString()).to.include("StakeBelowMinimum");
+    }
+  });
+
+  it("Rejects a vault belonging to another pool", async () => {
+    try {
+      await program.methods
+        .depositVoucher(new anchor.BN(1e9))
+        .accounts({
+          user: user.publicKey,
+          poolDelegate: poolDelegate.publicKey,
+          poolState: flexiblePool.state,
+          userStakeRecord: flexiblePool.userStakeRecord,
+          userVoucherAta: userTokenAccount,
+          poolVaultAta: lockedPool.vaultAta,
+          receiptMint: flexiblePool.receiptMint,
+          userReceiptAta: flexiblePool.userReceiptAta,
+          poolVaultAuthority: flexiblePool.vaultAuthority,
+          systemProgram: SystemProgram.programId,
+          voucherMint: lokalMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([poolDelegate])
+        .rpc();
+
+      expect.fail("Should have failed with the wrong vault");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidVault");
+    }
+  });
+
+  it("Records yield per pool", async () => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, lockedPool.state, batchId);
+    await program.methods
+      .recordYield(new anchor.BN(2 * 1e9), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState: lockedPool.state,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+    const flexibleData = await program.account.poolState.fetch(flexiblePool.state);
+    const lockedData = await program.account.poolState.fetch(lockedPool.state);
+    assert.equal(flexibleData.totalYieldEarned.toNumber(), 0);
+    assert.equal(flexibleData.rewardIndex.toString(), "0");
+    assert.equal(lockedData.totalYieldEarned.toString(), (2 * 1e9).toString());
+  });
+});
@@ -0,0 +1,184 @@
+/**
+ * Voucher Pool Emergency Pause Test
+ *
+ * Covers the guardian pause and its separation from the pool config flags
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { fundKeypairs, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Emergency Pause", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 7;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let guardian: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const setPause = (
+    authority: Keypair,
+    pauseDeposits: boolean | null,
+    pauseWithdrawals: boolean | null
+  ) =>
+    program.methods
+      .setPoolPause(pauseDeposits, pauseWithdrawals)
+      .accounts({
+        authority: authority.publicKey,
+        poolState,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+
+    guardian = Keypair.generate();
+    await fundKeypairs(provider, [guardian]);
+
+    await program.methods
+      .setPoolGuardian(guardian.publicKey)
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+      })
+      .signers([poolAuthority])
+      .rpc();
+  });
+
+  it("Guardian pauses deposits without touching the config", async () => {
+    await deposit(10 * 1e9);
+    await setPause(guardian, true, null);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.isTrue(poolData.depositsPaused);
+    assert.isFalse(poolData.withdrawalsPaused);
+    assert.isTrue(poolData.config.depositsEnabled);
+
+    try {
+      await deposit(1e9);
+      expect.fail("Should have failed while deposits are paused");
+    } catch (error) {
+      expect(error.toString()).to.include("DepositsPaused");
+    }
+
+    // Withdrawals are still open
+    await redeem(1e9);
+  });
+
+  it("Guardian cannot unpause", async () => {
+    try {
+      await setPause(guardian, false, null);
+      expect.fail("Should have failed for a guardian unpause");
+    } catch (error) {
+      expect(error.toString()).to.include("GuardianCannotUnpause");
+    }
+  });
+
+  it("Rejects pausing by an unrelated signer", async () => {
+    try {
+      await setPause(user, true, true);
+      expect.fail("Should have failed for an unauthorized pauser");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedPauser");
+    }
+  });
+
+  it("Guardian pauses withdrawals", async () => {
+    await setPause(guardian, null, true);
+
+    try {
+      await redeem(1e9);
+      expect.fail("Should have failed while withdrawals are paused");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalsPaused");
+    }
+  });
+
+  it("Pool authority unpauses", async () => {
+    await setPause(poolAuthority, false, false);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.isFalse(poolData.depositsPaused);
+    assert.isFalse(poolData.withdrawalsPaused);
+
+    await deposit(1e9);
+    await redeem(10 * 1e9);
+  });
+});
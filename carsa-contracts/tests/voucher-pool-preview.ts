@@ -0,0 +1,148 @@
+/**
+ * Voucher Pool Yield Preview Test
+ *
+ * Covers previewing claimable yield by simulation and matching it to redeem
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, previewClaimableYield, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Yield Preview", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 24;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Previews the yield a later redeem claims", async () => {
+    await deposit(100 * 1e9);
+    await recordYield(3 * 1e9 + 7);
+
+    const preview = await previewClaimableYield(program, user.publicKey, lokalMint, POOL_ID);
+    expect(preview.gtn(0)).to.be.true;
+
+    const before = await program.account.userStakeRecord.fetch(userStakeRecord);
+    await redeem(40 * 1e9);
+    const after = await program.account.userStakeRecord.fetch(userStakeRecord);
+
+    expect(after.totalYieldClaimed.sub(before.totalYieldClaimed).toString()).to.equal(
+      preview.toString()
+    );
+  });
+
+  it("Previews zero once the yield has been claimed", async () => {
+    const preview = await previewClaimableYield(program, user.publicKey, lokalMint, POOL_ID);
+    expect(preview.toString()).to.equal("0");
+  });
+
+  it("Tracks yield recorded after a partial redeem", async () => {
+    await recordYield(2 * 1e9 + 3);
+
+    const preview = await previewClaimableYield(program, user.publicKey, lokalMint, POOL_ID);
+
+    const before = await program.account.userStakeRecord.fetch(userStakeRecord);
+    await redeem(10 * 1e9);
+    const after = await program.account.userStakeRecord.fetch(userStakeRecord);
+
+    expect(after.totalYieldClaimed.sub(before.totalYieldClaimed).toString()).to.equal(
+      preview.toString()
+    );
+  });
+});
@@ -0,0 +1,241 @@
+/**
+ * Voucher Pool Unstake Queue Test
+ *
+ * Covers queued withdrawals through request, cancel and process
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAccount,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Unstake Queue", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const UNSTAKE_REQUEST_SEED = "unstake_request";
+
+  const POOL_ID = 20;
+  const UNSTAKE_DELAY_SECONDS = 3;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let unstakeRequest: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const requestUnstake = (amount: number) =>
+    program.methods
+      .requestUnstake(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        unstakeRequest,
+        receiptMint,
+        userReceiptAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user])
+      .rpc();
+
+  const cancelUnstake = () =>
+    program.methods
+      .cancelUnstake()
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        unstakeRequest,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const processUnstake = () =>
+    program.methods
+      .processUnstake()
+      .accounts({
+        poolState,
+        unstakeRequest,
+        user: user.publicKey,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+  const waitForClusterTime = async (timestamp: number) => {
+    for (;;) {
+      const slot = await provider.connection.getSlot();
+      const blockTime = await provider.connection.getBlockTime(slot);
+      if (blockTime !== null && blockTime >= timestamp) {
+        return;
+      }
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, {
+      queuedWithdrawals: true,
+      unstakeDelaySeconds: UNSTAKE_DELAY_SECONDS,
+    }));
+
+    [unstakeRequest] = PublicKey.findProgramAddressSync(
+      [Buffer.from(UNSTAKE_REQUEST_SEED), poolState.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+  });
+
+  it("Rejects direct redemption in a queued pool", async () => {
+    await deposit(100 * 1e9);
+
+    try {
+      await redeem(10 * 1e9);
+      expect.fail("Should have required the unstake queue");
+    } catch (error) {
+      expect(error.toString()).to.include("QueuedWithdrawalsOnly");
+    }
+  });
+
+  it("Takes requested vouchers out of the stake immediately", async () => {
+    await requestUnstake(40 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    const request = await program.account.unstakeRequest.fetch(unstakeRequest);
+    assert.equal(record.stakedAmount.toString(), (60 * 1e9).toString());
+    assert.equal(poolData.totalVoucherStaked.toString(), (60 * 1e9).toString());
+    assert.equal(poolData.weightedTotalStaked.toString(), (60 * 1e9).toString());
+    assert.equal(poolData.queuedUnstakeAmount.toString(), (40 * 1e9).toString());
+    assert.equal(request.amount.toString(), (40 * 1e9).toString());
+
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), (60 * 1e9).toString());
+  });
+
+  it("Fails to process before the delay", async () => {
+    try {
+      await processUnstake();
+      expect.fail("Should have failed before the unstake delay");
+    } catch (error) {
+      expect(error.toString()).to.include("UnstakeDelayActive");
+    }
+  });
+
+  it("Cancelling restores the stake and receipts", async () => {
+    await cancelUnstake();
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(record.stakedAmount.toString(), (100 * 1e9).toString());
+    assert.equal(poolData.totalVoucherStaked.toString(), (100 * 1e9).toString());
+    assert.equal(poolData.weightedTotalStaked.toString(), (100 * 1e9).toString());
+    assert.equal(poolData.queuedUnstakeAmount.toNumber(), 0);
+    assert.isNull(await provider.connection.getAccountInfo(unstakeRequest));
+
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), (100 * 1e9).toString());
+  });
+
+  it("Pays out after the delay exactly once", async () => {
+    await requestUnstake(30 * 1e9);
+    const request = await program.account.unstakeRequest.fetch(unstakeRequest);
+    await waitForClusterTime(request.requestedAt.toNumber() + UNSTAKE_DELAY_SECONDS);
+
+    const balanceBefore = (await getAccount(provider.connection, userTokenAccount)).amount;
+    await processUnstake();
+    const balanceAfter = (await getAccount(provider.connection, userTokenAccount)).amount;
+
+    assert.equal((balanceAfter - balanceBefore).toString(), (30 * 1e9).toString());
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.queuedUnstakeAmount.toNumber(), 0);
+    assert.equal(poolData.totalVoucherStaked.toString(), (70 * 1e9).toString());
+
+    try {
+      await processUnstake();
+      expect.fail("Should have failed on a processed request");
+    } catch (error) {
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+  });
+});
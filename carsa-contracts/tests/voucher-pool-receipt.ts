@@ -0,0 +1,157 @@
+/**
+ * Voucher Pool Stake Receipts Test
+ *
+ * Covers the sLOKAL receipt tokens minted on deposit and burned on redemption
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createAccount,
+  transfer,
+  getAccount,
+  getMint,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Stake Receipts", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 14;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Deposit mints receipts 1:1", async () => {
+    await deposit(30 * 1e9);
+
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), (30 * 1e9).toString());
+    assert.equal(receipts.mint.toBase58(), receiptMint.toBase58());
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.receiptMint.toBase58(), receiptMint.toBase58());
+    const mint = await getMint(provider.connection, receiptMint);
+    assert.equal(mint.mintAuthority?.toBase58(), poolVaultAuthority.toBase58());
+    assert.equal(mint.supply.toString(), poolData.totalVoucherStaked.toString());
+  });
+
+  it("Redeem burns the redeemed receipts", async () => {
+    await redeem(10 * 1e9);
+
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), (20 * 1e9).toString());
+    const mint = await getMint(provider.connection, receiptMint);
+    assert.equal(mint.supply.toString(), (20 * 1e9).toString());
+  });
+
+  it("Caps redemption at the receipts still held", async () => {
+    const holder = Keypair.generate();
+    const holderReceiptAta = await createAccount(
+      provider.connection,
+      user,
+      receiptMint,
+      holder.publicKey
+    );
+    await transfer(
+      provider.connection,
+      user,
+      userReceiptAta,
+      holderReceiptAta,
+      user,
+      15 * 1e9
+    );
+
+    // 20 staked but only 5 receipts left
+    try {
+      await redeem(6 * 1e9);
+      expect.fail("Should have failed with insufficient receipts");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientReceipts");
+    }
+
+    await redeem(5 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toString(), (15 * 1e9).toString());
+    const receipts = await getAccount(provider.connection, userReceiptAta);
+    assert.equal(receipts.amount.toString(), "0");
+  });
+});
@@ -0,0 +1,161 @@
+/**
+ * Voucher Pool Stake Record Repair Test
+ *
+ * Covers the authority resyncing a user's reward checkpoint. A checkpoint
+ * ahead of the pool index cannot be produced through the program, so these
+ * tests exercise the repair itself and the user's exit afterwards
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Repair Stake Record", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 8;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const repair = (authority: Keypair) =>
+    program.methods
+      .repairStakeRecord()
+      .accounts({
+        poolAuthority: authority.publicKey,
+        poolState,
+        userStakeRecord,
+      })
+      .signers([authority])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Rejects repair from anyone but the pool authority", async () => {
+    await deposit(20 * 1e9);
+    await recordYield(1e9);
+
+    try {
+      await repair(poolDelegate);
+      expect.fail("Should have failed for a non-authority");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Resyncs the user's reward index to the pool index", async () => {
+    const before = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.notEqual(before.userRewardIndex.toString(), poolData.rewardIndex.toString());
+
+    await repair(poolAuthority);
+
+    const after = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(after.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.equal(after.stakedAmount.toString(), before.stakedAmount.toString());
+  });
+
+  it("User can fully exit after the repair", async () => {
+    await redeem(20 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(record.stakedAmount.toNumber(), 0);
+    assert.equal(record.totalYieldClaimed.toNumber(), 0); // Forfeited by the repair
+    assert.equal(poolData.totalStakers.toNumber(), 0);
+    assert.equal(poolData.totalVoucherStaked.toNumber(), 0);
+  });
+});
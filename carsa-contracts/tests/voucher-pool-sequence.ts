@@ -0,0 +1,185 @@
+/**
+ * Voucher Pool Event Sequence Test
+ *
+ * Covers gapless event sequence numbers across pool instructions
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import {
+  defaultPoolConfig,
+  fixturePoolAddresses,
+  getYieldBatchPDA,
+  parseCpiEvents,
+  setupFixtureBase,
+  setupStaker,
+  toConfigUpdate,
+} from "../voucher-pool-client";
+
+describe("Voucher Pool - Event Sequence", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const POOL_ID = 23;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let initSignature: string;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  const eventsOf = async (signature: string) => {
+    await provider.connection.confirmTransaction(signature, "confirmed");
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    return parseCpiEvents(program, tx);
+  };
+
+  before(async () => {
+    const base = await setupFixtureBase(program);
+    ({ poolAuthority, poolDelegate, lokalMint } = base);
+    const pool = fixturePoolAddresses(program.programId, lokalMint, POOL_ID);
+    ({ poolState, poolVaultAuthority, poolVaultAta, receiptMint } = pool);
+    ({ user, userTokenAccount, userReceiptAta, userStakeRecord } = await setupStaker(program, {
+      ...base,
+      ...pool,
+    }));
+
+    // Initialized here rather than through the fixture, since its event is sequence 1
+    initSignature = await program.methods
+      .initializePool(POOL_ID, defaultPoolConfig())
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        vaultAta: poolVaultAta,
+        poolVaultAuthority,
+        voucherMint: lokalMint,
+        receiptMint,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolAuthority])
+      .rpc();
+  });
+
+  it("Numbers pool events 1..=5 across mixed operations", async () => {
+    const signatures = [initSignature];
+    signatures.push(await deposit(10 * 1e9));
+    signatures.push(await recordYield(1e9));
+    signatures.push(
+      await program.methods
+        .updatePoolConfig(toConfigUpdate({ apyBasisPoints: 800 }))
+        .accounts({
+          poolAuthority: poolAuthority.publicKey,
+          poolState,
+        })
+        .signers([poolAuthority])
+        .rpc()
+    );
+    signatures.push(await redeem(5 * 1e9));
+
+    const sequences: number[] = [];
+    for (const signature of signatures) {
+      for (const event of await eventsOf(signature)) {
+        sequences.push(event.data.sequence.toNumber());
+      }
+    }
+    assert.deepEqual(sequences, [1, 2, 3, 4, 5]);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.eventSequence.toNumber(), 5);
+  });
+
+  it("Does not advance the sequence when an instruction fails", async () => {
+    try {
+      await redeem(1_000 * 1e9);
+      expect.fail("Should have failed redeeming more than staked");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientBalance");
+    }
+
+    const events = await eventsOf(await deposit(1e9));
+    assert.equal(events.length, 1);
+    assert.equal(events[0].data.sequence.toNumber(), 6);
+  });
+});
@@ -0,0 +1,285 @@
+/**
+ * Voucher Pool Settle Users Test
+ *
+ * Covers the settle_users crank over batches of stake records
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createAccount,
+  mintTo,
+  createApproveInstruction,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import { getYieldBatchPDA, setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Settle Users", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const USER_STAKE_SEED = "user_stake";
+
+  const POOL_ID = 17;
+  const INDEX_SCALE = new anchor.BN("1000000000000000000");
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let bob: Staker;
+
+  const deposit = (amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const recordYield = (amount: number) => {
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+    return program.methods
+      .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolDelegate])
+      .rpc();
+  };
+
+  interface Staker {
+    keypair: Keypair;
+    tokenAccount: PublicKey;
+    receiptAta: PublicKey;
+    stakeRecord: PublicKey;
+  }
+
+  const setupStaker = async (): Promise<Staker> => {
+    const keypair = Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        keypair.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+
+    const tokenAccount = await createAccount(
+      provider.connection,
+      keypair,
+      lokalMint,
+      keypair.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      poolAuthority,
+      lokalMint,
+      tokenAccount,
+      poolAuthority,
+      1000 * 1e9
+    );
+    const approveIx = createApproveInstruction(
+      tokenAccount,
+      poolDelegate.publicKey,
+      keypair.publicKey,
+      1000 * 1e9
+    );
+    await provider.sendAndConfirm(new Transaction().add(approveIx), [keypair]);
+
+    const [stakeRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from(USER_STAKE_SEED), poolState.toBuffer(), keypair.publicKey.toBuffer()],
+      program.programId
+    );
+    const receiptAta = getAssociatedTokenAddressSync(receiptMint, keypair.publicKey);
+
+    return { keypair, tokenAccount, receiptAta, stakeRecord };
+  };
+
+  const depositFor = (staker: Staker, amount: number) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: staker.keypair.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord: staker.stakeRecord,
+        userVoucherAta: staker.tokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta: staker.receiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  const settle = (records: PublicKey[]) =>
+    program.methods
+      .settleUsers()
+      .accounts({
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+      })
+      .remainingAccounts(
+        records.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      )
+      .signers([poolDelegate])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID));
+  });
+
+  it("Settles stale records and skips up-to-date ones", async () => {
+    bob = await setupStaker();
+    await deposit(60 * 1e9);
+    await depositFor(bob, 40 * 1e9);
+    await recordYield(1e9);
+
+    await settle([userStakeRecord]);
+
+    const poolData = await program.account.poolState.fetch(poolState);
+    const first = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(first.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.equal(
+      first.pendingPayout.toString(),
+      first.weightedStake.mul(poolData.rewardIndex).div(INDEX_SCALE).toString()
+    );
+
+    // The first staker is already settled, so only Bob moves
+    await settle([userStakeRecord, bob.stakeRecord]);
+
+    const firstAfter = await program.account.userStakeRecord.fetch(userStakeRecord);
+    const bobAfter = await program.account.userStakeRecord.fetch(bob.stakeRecord);
+    assert.equal(firstAfter.pendingPayout.toString(), first.pendingPayout.toString());
+    assert.equal(bobAfter.userRewardIndex.toString(), poolData.rewardIndex.toString());
+    assert.equal(
+      bobAfter.pendingPayout.toString(),
+      bobAfter.weightedStake.mul(poolData.rewardIndex).div(INDEX_SCALE).toString()
+    );
+  });
+
+  it("Pays settled yield out on withdrawal", async () => {
+    const settled = (await program.account.userStakeRecord.fetch(userStakeRecord))
+      .pendingPayout;
+
+    await redeem(60 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.pendingPayout.toNumber(), 0);
+    assert.equal(record.totalYieldClaimed.toString(), settled.toString());
+  });
+
+  it("Rejects accounts that are not stake records of this pool", async () => {
+    await recordYield(1e9);
+    const before = await program.account.userStakeRecord.fetch(bob.stakeRecord);
+
+    // A token account, owned by another program, and the pool's own state account
+    for (const [intruder, expected] of [
+      [userTokenAccount, "RemainingAccountOwnerMismatch"],
+      [poolState, "InvalidRemainingAccount"],
+    ] as const) {
+      try {
+        await settle([bob.stakeRecord, intruder]);
+        expect.fail("Should have failed with an unrelated account");
+      } catch (error) {
+        expect(error.toString()).to.include(expected);
+      }
+    }
+
+    // The whole batch is rolled back
+    const after = await program.account.userStakeRecord.fetch(bob.stakeRecord);
+    assert.equal(after.userRewardIndex.toString(), before.userRewardIndex.toString());
+    assert.equal(after.pendingPayout.toString(), before.pendingPayout.toString());
+  });
+
+  it("Only the pool delegate can settle", async () => {
+    try {
+      await program.methods
+        .settleUsers()
+        .accounts({
+          poolDelegate: poolAuthority.publicKey,
+          poolState,
+        })
+        .remainingAccounts([
+          { pubkey: bob.stakeRecord, isSigner: false, isWritable: true },
+        ])
+        .signers([poolAuthority])
+        .rpc();
+      expect.fail("Should have failed for a non-delegate signer");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedDelegate");
+    }
+  });
+});
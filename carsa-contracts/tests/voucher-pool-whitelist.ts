@@ -0,0 +1,186 @@
+/**
+ * Voucher Pool Whitelist Test
+ *
+ * Covers whitelist-gated deposits and removal of approved wallets
+ */
+
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { Carsa } from "../target/types/carsa";
+import {
+  Keypair,
+  PublicKey,
+  SystemProgram,
+} from "@solana/web3.js";
+import {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { assert, expect } from "chai";
+import { setupPoolFixture } from "../voucher-pool-client";
+
+describe("Voucher Pool - Whitelist", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Carsa as Program<Carsa>;
+
+  const STAKE_WHITELIST_SEED = "stake_whitelist";
+
+  const POOL_ID = 15;
+
+  let poolAuthority: Keypair;
+  let poolDelegate: Keypair;
+  let user: Keypair;
+  let lokalMint: PublicKey;
+  let userTokenAccount: PublicKey;
+  let poolState: PublicKey;
+  let poolVaultAuthority: PublicKey;
+  let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
+  let userStakeRecord: PublicKey;
+  let stakeWhitelistEntry: PublicKey;
+
+  const redeem = (amount: number) =>
+    program.methods
+      .redeemVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolState,
+        userStakeRecord,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        poolVaultAuthority,
+        receiptMint,
+        userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+  const deposit = (amount: number, whitelistEntry: PublicKey | null) =>
+    program.methods
+      .depositVoucher(new anchor.BN(amount))
+      .accounts({
+        user: user.publicKey,
+        poolDelegate: poolDelegate.publicKey,
+        poolState,
+        userStakeRecord,
+        stakeWhitelistEntry: whitelistEntry,
+        userVoucherAta: userTokenAccount,
+        poolVaultAta,
+        receiptMint,
+        userReceiptAta,
+        poolVaultAuthority,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      })
+      .signers([poolDelegate])
+      .rpc();
+
+  before(async () => {
+    ({
+      poolAuthority,
+      poolDelegate,
+      user,
+      lokalMint,
+      userTokenAccount,
+      poolState,
+      poolVaultAuthority,
+      poolVaultAta,
+      receiptMint,
+      userReceiptAta,
+      userStakeRecord,
+    } = await setupPoolFixture(program, POOL_ID, { whitelistEnabled: true }));
+  });
+
+  it("Rejects deposits from a wallet that is not whitelisted", async () => {
+    [stakeWhitelistEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from(STAKE_WHITELIST_SEED), poolState.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await deposit(10 * 1e9, null);
+      expect.fail("Should have failed for a wallet that is not whitelisted");
+    } catch (error) {
+      expect(error.toString()).to.include("NotWhitelisted");
+    }
+
+    // An entry that was never created cannot stand in for one
+    try {
+      await deposit(10 * 1e9, stakeWhitelistEntry);
+      expect.fail("Should have failed without an initialized entry");
+    } catch (error) {
+      expect(error.toString()).to.include("AccountNotInitialized");
+    }
+  });
+
+  it("Only the pool authority can whitelist", async () => {
+    try {
+      await program.methods
+        .addToWhitelist()
+        .accounts({
+          poolAuthority: user.publicKey,
+          poolState,
+          user: user.publicKey,
+          stakeWhitelistEntry,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      expect.fail("Should have failed for a non-authority signer");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+  });
+
+  it("Accepts deposits once whitelisted", async () => {
+    await program.methods
+      .addToWhitelist()
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+        user: user.publicKey,
+        stakeWhitelistEntry,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    await deposit(10 * 1e9, stakeWhitelistEntry);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toString(), (10 * 1e9).toString());
+  });
+
+  it("Removal blocks top-ups but still allows withdrawal", async () => {
+    await program.methods
+      .removeFromWhitelist()
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState,
+        stakeWhitelistEntry,
+      })
+      .signers([poolAuthority])
+      .rpc();
+    assert.isNull(await provider.connection.getAccountInfo(stakeWhitelistEntry));
+
+    try {
+      await deposit(5 * 1e9, null);
+      expect.fail("Should have failed after removal from the whitelist");
+    } catch (error) {
+      expect(error.toString()).to.include("NotWhitelisted");
+    }
+
+    await redeem(10 * 1e9);
+
+    const record = await program.account.userStakeRecord.fetch(userStakeRecord);
+    assert.equal(record.stakedAmount.toNumber(), 0);
+  });
+});
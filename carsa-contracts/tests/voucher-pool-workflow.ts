@@ -15,14 +15,21 @@ import {
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
-  createMint,
-  createAccount,
-  mintTo,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
   getAccount,
   createApproveInstruction,
   createRevokeInstruction,
 } from "@solana/spl-token";
-import { assert } from "chai";
+import { assert, expect } from "chai";
+import { randomBytes } from "crypto";
+import {
+  defaultPoolConfig,
+  fixturePoolAddresses,
+  getYieldBatchPDA,
+  setupFixtureBase,
+  setupStaker,
+  toConfigUpdate,
+} from "../voucher-pool-client";
 
 describe("Voucher Pool - Complete Workflow", () => {
   // Configure the client to use the local cluster
@@ -40,91 +47,23 @@ describe("Voucher Pool - Complete Workflow", () => {
   let poolState: PublicKey;
   let poolVaultAuthority: PublicKey;
   let poolVaultAta: PublicKey;
+  let receiptMint: PublicKey;
+  let userReceiptAta: PublicKey;
   let userStakeRecord: PublicKey;
 
-  const POOL_STATE_SEED = "pool_state";
-  const POOL_VAULT_AUTHORITY_SEED = "pool_vault_authority";
-  const USER_STAKE_SEED = "user_stake";
-
   before(async () => {
-    // Generate keypairs
-    poolAuthority = Keypair.generate();
-    poolDelegate = Keypair.generate();
-    user = Keypair.generate();
-
-    // Airdrop SOL
-    await provider.connection.requestAirdrop(
-      poolAuthority.publicKey,
-      10 * anchor.web3.LAMPORTS_PER_SOL
-    );
-    await provider.connection.requestAirdrop(
-      poolDelegate.publicKey,
-      10 * anchor.web3.LAMPORTS_PER_SOL
-    );
-    await provider.connection.requestAirdrop(
-      user.publicKey,
-      10 * anchor.web3.LAMPORTS_PER_SOL
-    );
-
-    // Wait for airdrops
-    await new Promise((resolve) => setTimeout(resolve, 2000));
-
-    // Create LOKAL token mint
-    lokalMint = await createMint(
-      provider.connection,
-      poolAuthority,
-      poolAuthority.publicKey,
-      null,
-      9 // 9 decimals
-    );
-
+    // The workflow initializes pool 0 and approves the delegate itself
+    const base = await setupFixtureBase(program);
+    ({ poolAuthority, poolDelegate, lokalMint } = base);
     console.log("LOKAL Mint:", lokalMint.toBase58());
 
-    // Derive PDAs
-    [poolState] = PublicKey.findProgramAddressSync(
-      [Buffer.from(POOL_STATE_SEED)],
-      program.programId
-    );
-
-    [poolVaultAuthority] = PublicKey.findProgramAddressSync(
-      [Buffer.from(POOL_VAULT_AUTHORITY_SEED)],
-      program.programId
-    );
-
-    [userStakeRecord] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from(USER_STAKE_SEED),
-        poolState.toBuffer(),
-        user.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
-
-    // Create vault ATA
-    poolVaultAta = await createAccount(
-      provider.connection,
-      poolAuthority,
-      lokalMint,
-      poolVaultAuthority,
-      undefined
-    );
-
-    // Create user token account and mint tokens
-    userTokenAccount = await createAccount(
-      provider.connection,
-      user,
-      lokalMint,
-      user.publicKey
-    );
-
-    await mintTo(
-      provider.connection,
-      poolAuthority,
-      lokalMint,
-      userTokenAccount,
-      poolAuthority,
-      1000 * 1e9 // Mint 1000 LOKAL tokens
-    );
+    const pool = fixturePoolAddresses(program.programId, lokalMint, 0);
+    ({ poolState, poolVaultAuthority, poolVaultAta, receiptMint } = pool);
+    ({ user, userTokenAccount, userReceiptAta, userStakeRecord } = await setupStaker(
+      program,
+      { ...base, ...pool },
+      { approved: 0 }
+    ));
 
     console.log("Setup complete!");
     console.log("Pool State:", poolState.toBase58());
@@ -135,16 +74,12 @@ describe("Voucher Pool - Complete Workflow", () => {
   });
 
   it("1. Initialize Pool", async () => {
-    const config = {
+    const config = defaultPoolConfig({
       minStakeAmount: new anchor.BN(1_000_000), // 0.001 LOKAL
-      maxStakePerUser: new anchor.BN(1_000_000_000_000), // 1,000 LOKAL
-      depositsEnabled: true,
-      withdrawalsEnabled: true,
-      apyBasisPoints: 1200, // 12% APY
-    };
+    });
 
     await program.methods
-      .initializePool(config)
+      .initializePool(0, config)
       .accounts({
         poolAuthority: poolAuthority.publicKey,
         poolDelegate: poolDelegate.publicKey,
@@ -152,8 +87,10 @@ describe("Voucher Pool - Complete Workflow", () => {
         vaultAta: poolVaultAta,
         poolVaultAuthority: poolVaultAuthority,
         voucherMint: lokalMint,
+        receiptMint: receiptMint,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
       })
       .signers([poolAuthority])
       .rpc();
@@ -171,6 +108,12 @@ describe("Voucher Pool - Complete Workflow", () => {
     assert.equal(poolData.config.depositsEnabled, true);
     assert.equal(poolData.totalVoucherStaked.toNumber(), 0);
 
+    // The vault was created from scratch as the vault authority's ATA
+    assert.equal(poolData.vaultAta.toBase58(), poolVaultAta.toBase58());
+    const vault = await getAccount(provider.connection, poolVaultAta);
+    assert.equal(vault.owner.toBase58(), poolVaultAuthority.toBase58());
+    assert.equal(vault.mint.toBase58(), lokalMint.toBase58());
+
     console.log("✅ Pool initialized successfully");
   });
 
@@ -216,8 +159,13 @@ describe("Voucher Pool - Complete Workflow", () => {
         userStakeRecord: userStakeRecord,
         userVoucherAta: userTokenAccount,
         poolVaultAta: poolVaultAta,
+        receiptMint: receiptMint,
+        userReceiptAta: userReceiptAta,
+        poolVaultAuthority: poolVaultAuthority,
         systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
         tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
       })
       .signers([poolDelegate])
       .rpc();
@@ -251,12 +199,16 @@ describe("Voucher Pool - Complete Workflow", () => {
 
   it("4. Backend Records Yield", async () => {
     const yieldAmount = new anchor.BN(5 * 1e9); // 5 SOL equivalent yield
+    const batchId = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
 
     await program.methods
-      .recordYield(yieldAmount)
+      .recordYield(yieldAmount, Array.from(batchId), new anchor.BN(0))
       .accounts({
         poolDelegate: poolDelegate.publicKey,
         poolState: poolState,
+        yieldBatch,
+        systemProgram: SystemProgram.programId,
       })
       .signers([poolDelegate])
       .rpc();
@@ -270,6 +222,65 @@ describe("Voucher Pool - Complete Workflow", () => {
     console.log("   Reward Index:", poolData.rewardIndex.toString());
   });
 
+  it("4b. Yield Batches Are Idempotent", async () => {
+    const recordBatch = async (batchId: Buffer, amount: number) => {
+      const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
+      await program.methods
+        .recordYield(new anchor.BN(amount), Array.from(batchId), null)
+        .accounts({
+          poolDelegate: poolDelegate.publicKey,
+          poolState: poolState,
+          yieldBatch,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([poolDelegate])
+        .rpc();
+    };
+
+    const before = await program.account.poolState.fetch(poolState);
+    const firstBatch = randomBytes(32);
+    const secondBatch = randomBytes(32);
+
+    await recordBatch(firstBatch, 1e9);
+
+    // A retried submission of the same batch must not count twice
+    try {
+      await recordBatch(firstBatch, 1e9);
+      expect.fail("Should have failed for a replayed batch");
+    } catch (error) {
+      expect(error.toString()).to.include("already in use");
+    }
+
+    await recordBatch(secondBatch, 2 * 1e9);
+
+    const after = await program.account.poolState.fetch(poolState);
+    assert.equal(
+      after.totalYieldEarned.sub(before.totalYieldEarned).toString(),
+      (3 * 1e9).toString()
+    );
+
+    // A stale expected index is rejected
+    const staleBatch = randomBytes(32);
+    const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, staleBatch);
+    try {
+      await program.methods
+        .recordYield(new anchor.BN(1e9), Array.from(staleBatch), before.rewardIndex)
+        .accounts({
+          poolDelegate: poolDelegate.publicKey,
+          poolState: poolState,
+          yieldBatch,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([poolDelegate])
+        .rpc();
+      expect.fail("Should have failed with a stale reward index");
+    } catch (error) {
+      expect(error.toString()).to.include("RewardIndexMismatch");
+    }
+
+    console.log("✅ Replayed yield batch rejected, distinct batches accumulate");
+  });
+
   it("5. User Redeems Stake and Claims Yield", async () => {
     const redeemAmount = new anchor.BN(50 * 1e9); // Redeem all 50 LOKAL
 
@@ -290,6 +301,10 @@ describe("Voucher Pool - Complete Workflow", () => {
         userVoucherAta: userTokenAccount,
         poolVaultAta: poolVaultAta,
         poolVaultAuthority: poolVaultAuthority,
+        receiptMint: receiptMint,
+        userReceiptAta: userReceiptAta,
+        systemProgram: SystemProgram.programId,
+        voucherMint: lokalMint,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .signers([user])
@@ -348,16 +363,11 @@ describe("Voucher Pool - Complete Workflow", () => {
   });
 
   it("7. Pool Authority Updates Configuration", async () => {
-    const newConfig = {
-      minStakeAmount: new anchor.BN(2_000_000), // 0.002 LOKAL
-      maxStakePerUser: new anchor.BN(2_000_000_000_000), // 2,000 LOKAL
-      depositsEnabled: true,
-      withdrawalsEnabled: true,
-      apyBasisPoints: 1500, // 15% APY
-    };
+    const before = await program.account.poolState.fetch(poolState);
 
+    // Only the APY is sent; everything else must survive untouched
     await program.methods
-      .updatePoolConfig(newConfig)
+      .updatePoolConfig(toConfigUpdate({ apyBasisPoints: 1500 })) // 15% APY
       .accounts({
         poolAuthority: poolAuthority.publicKey,
         poolState: poolState,
@@ -365,15 +375,197 @@ describe("Voucher Pool - Complete Workflow", () => {
       .signers([poolAuthority])
       .rpc();
 
-    // Verify config updated
-    const poolData = await program.account.poolState.fetch(poolState);
+    let poolData = await program.account.poolState.fetch(poolState);
     assert.equal(poolData.config.apyBasisPoints, 1500);
-    assert.equal(poolData.config.minStakeAmount.toString(), newConfig.minStakeAmount.toString());
+    assert.equal(
+      poolData.config.minStakeAmount.toString(),
+      before.config.minStakeAmount.toString()
+    );
+    assert.equal(
+      poolData.config.maxStakePerUser.toString(),
+      before.config.maxStakePerUser.toString()
+    );
+    assert.equal(poolData.config.depositsEnabled, before.config.depositsEnabled);
+    assert.equal(poolData.config.withdrawalsEnabled, before.config.withdrawalsEnabled);
+
+    // The merged config is validated, so a min above the existing max is rejected
+    try {
+      await program.methods
+        .updatePoolConfig(
+          toConfigUpdate({
+            minStakeAmount: before.config.maxStakePerUser.add(new anchor.BN(1)),
+          })
+        )
+        .accounts({
+          poolAuthority: poolAuthority.publicKey,
+          poolState: poolState,
+        })
+        .signers([poolAuthority])
+        .rpc();
+
+      expect.fail("Should have failed with min above max");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidPoolLimits");
+    }
+
+    await program.methods
+      .updatePoolConfig(
+        toConfigUpdate({
+          minStakeAmount: new anchor.BN(2_000_000), // 0.002 LOKAL
+          maxStakePerUser: new anchor.BN(2_000_000_000_000), // 2,000 LOKAL
+        })
+      )
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState: poolState,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    poolData = await program.account.poolState.fetch(poolState);
+    assert.equal(poolData.config.apyBasisPoints, 1500);
+    assert.equal(poolData.config.minStakeAmount.toString(), "2000000");
 
     console.log("✅ Pool configuration updated");
   });
 
-  it("8. Summary - Complete Workflow Verified", async () => {
+  it("8. Deposit Without Approval Fails", async () => {
+    // Delegation was revoked in step 6
+    try {
+      await program.methods
+        .depositVoucher(new anchor.BN(10 * 1e9))
+        .accounts({
+          user: user.publicKey,
+          poolDelegate: poolDelegate.publicKey,
+          poolState: poolState,
+          userStakeRecord: userStakeRecord,
+          userVoucherAta: userTokenAccount,
+          poolVaultAta: poolVaultAta,
+          receiptMint: receiptMint,
+          userReceiptAta: userReceiptAta,
+          poolVaultAuthority: poolVaultAuthority,
+          systemProgram: SystemProgram.programId,
+          voucherMint: lokalMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([poolDelegate])
+        .rpc();
+
+      expect.fail("Should have failed with missing delegation");
+    } catch (error) {
+      expect(error.toString()).to.include("MissingDelegation");
+    }
+
+    console.log("✅ Deposit without approval rejected");
+  });
+
+  it("9. Deposit Above Partial Approval Fails", async () => {
+    const approveIx = createApproveInstruction(
+      userTokenAccount,
+      poolDelegate.publicKey,
+      user.publicKey,
+      10 * 1e9 // Approve only 10 LOKAL
+    );
+    await provider.sendAndConfirm(new Transaction().add(approveIx), [user]);
+
+    try {
+      await program.methods
+        .depositVoucher(new anchor.BN(20 * 1e9))
+        .accounts({
+          user: user.publicKey,
+          poolDelegate: poolDelegate.publicKey,
+          poolState: poolState,
+          userStakeRecord: userStakeRecord,
+          userVoucherAta: userTokenAccount,
+          poolVaultAta: poolVaultAta,
+          receiptMint: receiptMint,
+          userReceiptAta: userReceiptAta,
+          poolVaultAuthority: poolVaultAuthority,
+          systemProgram: SystemProgram.programId,
+          voucherMint: lokalMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([poolDelegate])
+        .rpc();
+
+      expect.fail("Should have failed with insufficient delegation");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientDelegation");
+    }
+
+    console.log("✅ Deposit above approved amount rejected");
+  });
+
+  it("10. Auto-Revoke Clears Leftover Approval", async () => {
+    await program.methods
+      .updatePoolConfig(toConfigUpdate({ autoRevokeDelegation: true }))
+      .accounts({
+        poolAuthority: poolAuthority.publicKey,
+        poolState: poolState,
+      })
+      .signers([poolAuthority])
+      .rpc();
+
+    const depositAccounts = {
+      user: user.publicKey,
+      poolDelegate: poolDelegate.publicKey,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+      userVoucherAta: userTokenAccount,
+      poolVaultAta: poolVaultAta,
+      voucherMint: lokalMint,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      poolVaultAuthority: poolVaultAuthority,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    };
+
+    // Without the user's signature the leftover approval cannot be revoked
+    try {
+      await program.methods
+        .depositVoucher(new anchor.BN(4 * 1e9))
+        .accounts(depositAccounts)
+        .signers([poolDelegate])
+        .rpc();
+
+      expect.fail("Should have failed without the user's signature");
+    } catch (error) {
+      expect(error.toString()).to.include("UserSignatureRequired");
+    }
+
+    // Deposit 4 of the 10 approved LOKAL, co-signed by the user
+    const depositIx = await program.methods
+      .depositVoucher(new anchor.BN(4 * 1e9))
+      .accounts(depositAccounts)
+      .instruction();
+    depositIx.keys
+      .filter((key) => key.pubkey.equals(user.publicKey))
+      .forEach((key) => (key.isSigner = true));
+    await provider.sendAndConfirm(new Transaction().add(depositIx), [
+      poolDelegate,
+      user,
+    ]);
+
+    const tokenAccountInfo = await getAccount(
+      provider.connection,
+      userTokenAccount
+    );
+    assert.equal(tokenAccountInfo.delegate, null);
+    assert.equal(tokenAccountInfo.delegatedAmount.toString(), "0");
+
+    const stakeRecord = await program.account.userStakeRecord.fetch(
+      userStakeRecord
+    );
+    assert.equal(stakeRecord.stakedAmount.toString(), (4 * 1e9).toString());
+
+    console.log("✅ Leftover 6 LOKAL approval revoked after deposit");
+  });
+
+  it("11. Summary - Complete Workflow Verified", async () => {
     console.log("\n" + "=".repeat(60));
     console.log("🎉 Complete Workflow Test Summary");
     console.log("=".repeat(60));
@@ -384,6 +576,8 @@ describe("Voucher Pool - Complete Workflow", () => {
     console.log("✅ User redeemed stake + yield");
     console.log("✅ User revoked delegation");
     console.log("✅ Admin updated pool configuration");
+    console.log("✅ Missing and insufficient delegation rejected");
+    console.log("✅ Leftover delegation auto-revoked");
     console.log("=".repeat(60));
     console.log("\n💡 Non-custodial staking system working perfectly!");
   });
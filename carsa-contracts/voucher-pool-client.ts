@@ -27,10 +27,12 @@ import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
-  createAssociatedTokenAccountInstruction,
+  getAssociatedTokenAddressSync,
   createApproveInstruction,
   createRevokeInstruction,
-  getAccount,
+  createMint,
+  createAccount,
+  mintTo,
 } from "@solana/spl-token";
 import { Carsa } from "./target/types/carsa";
 
@@ -41,6 +43,11 @@ import { Carsa } from "./target/types/carsa";
 const POOL_STATE_SEED = "pool_state";
 const POOL_VAULT_AUTHORITY_SEED = "pool_vault_authority";
 const USER_STAKE_SEED = "user_stake";
+const YIELD_BATCH_SEED = "yield_batch";
+const RECEIPT_MINT_SEED = "receipt_mint";
+const STAKE_WHITELIST_SEED = "stake_whitelist";
+const UNSTAKE_REQUEST_SEED = "unstake_request";
+const STAKE_ACTION_SEED = "stake_action";
 
 // Use Devnet by default
 const CLUSTER = "devnet";
@@ -51,21 +58,95 @@ const connection = new Connection(clusterApiUrl(CLUSTER), "confirmed");
 // ============================================================================
 
 /**
- * Derive the pool state PDA
+ * Seed suffix for a pool id, after the voucher mint (pool 0 uses no suffix)
  */
-export function getPoolStatePDA(programId: PublicKey): [PublicKey, number] {
+export function poolIdSeed(poolId: number): Buffer {
+  if (poolId === 0) {
+    return Buffer.alloc(0);
+  }
+  const seed = Buffer.alloc(2);
+  seed.writeUInt16LE(poolId);
+  return seed;
+}
+
+/**
+ * Derive the state PDA of pool `poolId` of `voucherMint`
+ */
+export function getPoolStatePDA(
+  programId: PublicKey,
+  voucherMint: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(POOL_STATE_SEED), voucherMint.toBuffer(), poolIdSeed(poolId)],
+    programId
+  );
+}
+
+/**
+ * Derive the vault authority PDA of pool `poolId` of `voucherMint`
+ */
+export function getPoolVaultAuthorityPDA(
+  programId: PublicKey,
+  voucherMint: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(POOL_VAULT_AUTHORITY_SEED), voucherMint.toBuffer(), poolIdSeed(poolId)],
+    programId
+  );
+}
+
+/**
+ * Derive the sLOKAL receipt mint PDA of pool `poolId` of `voucherMint`
+ */
+export function getReceiptMintPDA(
+  programId: PublicKey,
+  voucherMint: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(RECEIPT_MINT_SEED), voucherMint.toBuffer(), poolIdSeed(poolId)],
+    programId
+  );
+}
+
+/**
+ * Derive the state PDA of a pool created before pool PDAs were keyed by mint,
+ * such as the original pool 0
+ */
+export function getLegacyPoolStatePDA(
+  programId: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(POOL_STATE_SEED), poolIdSeed(poolId)],
+    programId
+  );
+}
+
+/**
+ * Derive the vault authority PDA of a pool created before pool PDAs were keyed by mint
+ */
+export function getLegacyPoolVaultAuthorityPDA(
+  programId: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from(POOL_STATE_SEED)],
+    [Buffer.from(POOL_VAULT_AUTHORITY_SEED), poolIdSeed(poolId)],
     programId
   );
 }
 
 /**
- * Derive the pool vault authority PDA
+ * Derive the sLOKAL receipt mint PDA of a pool created before pool PDAs were keyed by mint
  */
-export function getPoolVaultAuthorityPDA(programId: PublicKey): [PublicKey, number] {
+export function getLegacyReceiptMintPDA(
+  programId: PublicKey,
+  poolId: number = 0
+): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from(POOL_VAULT_AUTHORITY_SEED)],
+    [Buffer.from(RECEIPT_MINT_SEED), poolIdSeed(poolId)],
     programId
   );
 }
@@ -88,6 +169,87 @@ export function getUserStakePDA(
   );
 }
 
+/**
+ * Derive a user's whitelist entry PDA
+ */
+export function getStakeWhitelistPDA(
+  programId: PublicKey,
+  poolState: PublicKey,
+  user: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(STAKE_WHITELIST_SEED), poolState.toBuffer(), user.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Derive a user's unstake request PDA
+ */
+export function getUnstakeRequestPDA(
+  programId: PublicKey,
+  poolState: PublicKey,
+  user: PublicKey
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(UNSTAKE_REQUEST_SEED), poolState.toBuffer(), user.toBuffer()],
+    programId
+  );
+}
+
+/**
+ * Derive the PDA of a user's stake history entry
+ */
+export function getStakeActionPDA(
+  programId: PublicKey,
+  poolState: PublicKey,
+  user: PublicKey,
+  actionIndex: anchor.BN
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [
+      Buffer.from(STAKE_ACTION_SEED),
+      poolState.toBuffer(),
+      user.toBuffer(),
+      actionIndex.toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  );
+}
+
+/**
+ * Next stake history entry for a user, or null when the pool doesn't record history
+ */
+async function getNextStakeActionRecord(
+  program: Program<Carsa>,
+  poolState: PublicKey,
+  userStakeRecord: PublicKey,
+  user: PublicKey
+): Promise<PublicKey | null> {
+  const poolData = await program.account.poolState.fetch(poolState);
+  if (!poolData.config.recordHistory) {
+    return null;
+  }
+
+  const record = await program.account.userStakeRecord.fetchNullable(userStakeRecord);
+  const actionIndex = record ? record.actionCount : new anchor.BN(0);
+  return getStakeActionPDA(program.programId, poolState, user, actionIndex)[0];
+}
+
+/**
+ * Derive the marker PDA for a yield batch
+ */
+export function getYieldBatchPDA(
+  programId: PublicKey,
+  poolState: PublicKey,
+  batchId: Buffer
+): [PublicKey, number] {
+  return PublicKey.findProgramAddressSync(
+    [Buffer.from(YIELD_BATCH_SEED), poolState.toBuffer(), batchId],
+    programId
+  );
+}
+
 // ============================================================================
 // Pool Configuration Type
 // ============================================================================
@@ -98,6 +260,300 @@ export interface PoolConfig {
   depositsEnabled: boolean;
   withdrawalsEnabled: boolean;
   apyBasisPoints: number;
+  autoRevokeDelegation: boolean;
+  lockupSeconds: number;
+  earlyWithdrawPenaltyBps: number;
+  maxDeploymentBps: number;
+  maxTotalStaked: anchor.BN; // 0 = unlimited
+  tierThresholds: anchor.BN[]; // 3 stake sizes, 0 = tier disabled
+  tierMultipliersBps: number[]; // 3 yield weights, 10000 = 1x
+  withdrawCooldownSeconds: number; // since the user's last deposit, 0 = none
+  whitelistEnabled: boolean; // only whitelisted wallets may deposit
+  performanceFeeBps: number; // share of recorded yield kept by the protocol
+  feeRecipient: PublicKey; // required when the fee is non-zero
+  guaranteedYieldBudget: anchor.BN; // cap on minimum APY accrual, 0 = none
+  queuedWithdrawals: boolean; // withdraw via request/process_unstake only
+  unstakeDelaySeconds: number; // wait between request and processing
+  recordHistory: boolean; // write a StakeActionRecord per deposit/withdrawal
+  insuranceFeeBps: number; // share of each deposit kept as insurance reserve
+}
+
+/**
+ * Partial pool configuration update; omitted fields keep their current value
+ */
+export interface PoolConfigUpdate {
+  minStakeAmount?: anchor.BN | null;
+  maxStakePerUser?: anchor.BN | null;
+  depositsEnabled?: boolean | null;
+  withdrawalsEnabled?: boolean | null;
+  apyBasisPoints?: number | null;
+  autoRevokeDelegation?: boolean | null;
+  lockupSeconds?: number | null;
+  earlyWithdrawPenaltyBps?: number | null;
+  maxDeploymentBps?: number | null;
+  maxTotalStaked?: anchor.BN | null;
+  tierThresholds?: anchor.BN[] | null;
+  tierMultipliersBps?: number[] | null;
+  withdrawCooldownSeconds?: number | null;
+  whitelistEnabled?: boolean | null;
+  performanceFeeBps?: number | null;
+  feeRecipient?: PublicKey | null;
+  guaranteedYieldBudget?: anchor.BN | null;
+  queuedWithdrawals?: boolean | null;
+  unstakeDelaySeconds?: number | null;
+  recordHistory?: boolean | null;
+  insuranceFeeBps?: number | null;
+}
+
+/**
+ * Fill omitted fields of a config update with null so they are left unchanged
+ */
+export function toConfigUpdate(update: PoolConfigUpdate): Required<PoolConfigUpdate> {
+  return {
+    minStakeAmount: null,
+    maxStakePerUser: null,
+    depositsEnabled: null,
+    withdrawalsEnabled: null,
+    apyBasisPoints: null,
+    autoRevokeDelegation: null,
+    lockupSeconds: null,
+    earlyWithdrawPenaltyBps: null,
+    maxDeploymentBps: null,
+    maxTotalStaked: null,
+    tierThresholds: null,
+    tierMultipliersBps: null,
+    withdrawCooldownSeconds: null,
+    whitelistEnabled: null,
+    performanceFeeBps: null,
+    feeRecipient: null,
+    guaranteedYieldBudget: null,
+    queuedWithdrawals: null,
+    unstakeDelaySeconds: null,
+    recordHistory: null,
+    insuranceFeeBps: null,
+    ...update,
+  };
+}
+
+/**
+ * Pool configuration with every optional feature off; pass only the fields that differ
+ */
+export function defaultPoolConfig(overrides: Partial<PoolConfig> = {}): PoolConfig {
+  return {
+    minStakeAmount: new anchor.BN(1),
+    maxStakePerUser: new anchor.BN(1_000_000_000_000),
+    depositsEnabled: true,
+    withdrawalsEnabled: true,
+    apyBasisPoints: 1200,
+    autoRevokeDelegation: false,
+    lockupSeconds: 0,
+    earlyWithdrawPenaltyBps: 0,
+    maxDeploymentBps: 0,
+    maxTotalStaked: new anchor.BN(0),
+    tierThresholds: [new anchor.BN(0), new anchor.BN(0), new anchor.BN(0)],
+    tierMultipliersBps: [0, 0, 0],
+    withdrawCooldownSeconds: 0,
+    whitelistEnabled: false,
+    performanceFeeBps: 0,
+    feeRecipient: PublicKey.default,
+    guaranteedYieldBudget: new anchor.BN(0),
+    queuedWithdrawals: false,
+    unstakeDelaySeconds: 0,
+    recordHistory: false,
+    insuranceFeeBps: 0,
+    ...overrides,
+  };
+}
+
+// ============================================================================
+// Test Fixture
+// ============================================================================
+
+/**
+ * LOKAL minted to a fixture staker and approved for the pool delegate (default 1000 and 500)
+ */
+export interface StakerOptions {
+  balance?: number;
+  approved?: number;
+}
+
+/**
+ * Keys and mint shared by every pool a spec initializes
+ */
+export interface FixtureBase {
+  poolAuthority: Keypair;
+  poolDelegate: Keypair;
+  lokalMint: PublicKey;
+}
+
+/**
+ * Addresses of one pool, as derived by `fixturePoolAddresses`
+ */
+export interface FixturePool {
+  poolState: PublicKey;
+  poolVaultAuthority: PublicKey;
+  poolVaultAta: PublicKey;
+  receiptMint: PublicKey;
+}
+
+/**
+ * A funded staker with a LOKAL balance, as set up by `setupStaker`
+ */
+export interface StakerFixture {
+  user: Keypair;
+  userTokenAccount: PublicKey;
+  userReceiptAta: PublicKey;
+  userStakeRecord: PublicKey;
+}
+
+/**
+ * A freshly initialized pool over a new mint, with one staker, as set up by `setupPoolFixture`
+ */
+export interface PoolFixture extends FixtureBase, FixturePool, StakerFixture {}
+
+/**
+ * Airdrop SOL to each keypair and wait for the airdrops to confirm
+ */
+export async function fundKeypairs(
+  provider: anchor.AnchorProvider,
+  keypairs: Keypair[],
+  sol: number = 10
+): Promise<void> {
+  for (const keypair of keypairs) {
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        keypair.publicKey,
+        sol * anchor.web3.LAMPORTS_PER_SOL
+      )
+    );
+  }
+}
+
+/**
+ * Fund a new pool authority and delegate and create a LOKAL mint owned by the authority
+ */
+export async function setupFixtureBase(program: Program<Carsa>): Promise<FixtureBase> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const poolAuthority = Keypair.generate();
+  const poolDelegate = Keypair.generate();
+  await fundKeypairs(provider, [poolAuthority, poolDelegate]);
+
+  const lokalMint = await createMint(
+    provider.connection,
+    poolAuthority,
+    poolAuthority.publicKey,
+    null,
+    9
+  );
+
+  return { poolAuthority, poolDelegate, lokalMint };
+}
+
+/**
+ * Addresses pool `poolId` over `lokalMint` has, whether or not it is initialized yet
+ */
+export function fixturePoolAddresses(
+  programId: PublicKey,
+  lokalMint: PublicKey,
+  poolId: number
+): FixturePool {
+  const [poolState] = getPoolStatePDA(programId, lokalMint, poolId);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(programId, lokalMint, poolId);
+  const [receiptMint] = getReceiptMintPDA(programId, lokalMint, poolId);
+  const poolVaultAta = getAssociatedTokenAddressSync(lokalMint, poolVaultAuthority, true);
+  return { poolState, poolVaultAuthority, poolVaultAta, receiptMint };
+}
+
+/**
+ * Initialize pool `poolId` over the fixture's mint with `defaultPoolConfig(config)`
+ */
+export async function addFixturePool(
+  program: Program<Carsa>,
+  base: FixtureBase,
+  poolId: number,
+  config: Partial<PoolConfig> = {}
+): Promise<FixturePool> {
+  const pool = fixturePoolAddresses(program.programId, base.lokalMint, poolId);
+  const { poolState, poolVaultAuthority, poolVaultAta, receiptMint } = pool;
+
+  await program.methods
+    .initializePool(poolId, defaultPoolConfig(config))
+    .accounts({
+      poolAuthority: base.poolAuthority.publicKey,
+      poolDelegate: base.poolDelegate.publicKey,
+      poolState,
+      vaultAta: poolVaultAta,
+      poolVaultAuthority,
+      voucherMint: base.lokalMint,
+      receiptMint,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    })
+    .signers([base.poolAuthority])
+    .rpc();
+
+  return pool;
+}
+
+/**
+ * Fund a new staker in `pool`, mint them LOKAL and approve the pool delegate to deposit it
+ */
+export async function setupStaker(
+  program: Program<Carsa>,
+  pool: FixtureBase & FixturePool,
+  { balance = 1000 * 1e9, approved = 500 * 1e9 }: StakerOptions = {}
+): Promise<StakerFixture> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const user = Keypair.generate();
+  await fundKeypairs(provider, [user]);
+
+  const userTokenAccount = await createAccount(
+    provider.connection,
+    user,
+    pool.lokalMint,
+    user.publicKey
+  );
+  await mintTo(
+    provider.connection,
+    pool.poolAuthority,
+    pool.lokalMint,
+    userTokenAccount,
+    pool.poolAuthority,
+    balance
+  );
+
+  if (approved > 0) {
+    const approveIx = createApproveInstruction(
+      userTokenAccount,
+      pool.poolDelegate.publicKey,
+      user.publicKey,
+      approved
+    );
+    await provider.sendAndConfirm(new Transaction().add(approveIx), [user]);
+  }
+
+  return {
+    user,
+    userTokenAccount,
+    userReceiptAta: getAssociatedTokenAddressSync(pool.receiptMint, user.publicKey),
+    userStakeRecord: getUserStakePDA(program.programId, pool.poolState, user.publicKey)[0],
+  };
+}
+
+/**
+ * Create a LOKAL mint, initialize pool `poolId` over it with `defaultPoolConfig(config)`,
+ * and set up one staker; specs override only the config fields they test
+ */
+export async function setupPoolFixture(
+  program: Program<Carsa>,
+  poolId: number,
+  config: Partial<PoolConfig> = {},
+  staker: StakerOptions = {}
+): Promise<PoolFixture> {
+  const base = await setupFixtureBase(program);
+  const pool = { ...base, ...(await addFixturePool(program, base, poolId, config)) };
+  return { ...pool, ...(await setupStaker(program, pool, staker)) };
 }
 
 // ============================================================================
@@ -112,6 +568,7 @@ export interface PoolConfig {
  * @param poolDelegate - The public key that can execute deposits on behalf of users
  * @param voucherMint - The LOKAL token mint address
  * @param config - Pool configuration parameters
+ * @param poolId - Identifier of the pool (0 is the original singleton pool)
  * @returns Transaction signature
  */
 export async function initializePool(
@@ -119,7 +576,8 @@ export async function initializePool(
   poolAuthority: Keypair,
   poolDelegate: PublicKey,
   voucherMint: PublicKey,
-  config: PoolConfig
+  config: PoolConfig,
+  poolId: number = 0
 ): Promise<string> {
   console.log("\n🚀 Initializing Voucher Pool...");
   console.log("Pool Authority:", poolAuthority.publicKey.toBase58());
@@ -127,13 +585,15 @@ export async function initializePool(
   console.log("Voucher Mint:", voucherMint.toBase58());
 
   // Derive PDAs
-  const [poolState] = getPoolStatePDA(program.programId);
-  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint, poolId);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint, poolId);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint, poolId);
 
   console.log("Pool State PDA:", poolState.toBase58());
   console.log("Pool Vault Authority PDA:", poolVaultAuthority.toBase58());
+  console.log("Receipt Mint PDA:", receiptMint.toBase58());
 
-  // Get the vault ATA (must be created beforehand)
+  // The vault ATA is created by initialize_pool if it doesn't exist yet
   const vaultAta = await getAssociatedTokenAddress(
     voucherMint,
     poolVaultAuthority,
@@ -142,27 +602,9 @@ export async function initializePool(
 
   console.log("Vault ATA:", vaultAta.toBase58());
 
-  // Check if vault ATA exists, if not create it
-  try {
-    await getAccount(connection, vaultAta);
-    console.log("✓ Vault ATA already exists");
-  } catch (error) {
-    console.log("Creating vault ATA...");
-    const createAtaIx = createAssociatedTokenAccountInstruction(
-      poolAuthority.publicKey,
-      vaultAta,
-      poolVaultAuthority,
-      voucherMint
-    );
-    
-    const createAtaTx = new Transaction().add(createAtaIx);
-    const createAtaSig = await program.provider.sendAndConfirm(createAtaTx, [poolAuthority]);
-    console.log("✓ Vault ATA created:", createAtaSig);
-  }
-
   // Initialize the pool
   const tx = await program.methods
-    .initializePool(config)
+    .initializePool(poolId, config)
     .accounts({
       poolAuthority: poolAuthority.publicKey,
       poolDelegate: poolDelegate,
@@ -170,8 +612,10 @@ export async function initializePool(
       vaultAta: vaultAta,
       poolVaultAuthority: poolVaultAuthority,
       voucherMint: voucherMint,
+      receiptMint: receiptMint,
       systemProgram: SystemProgram.programId,
       tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
     })
     .signers([poolAuthority])
     .rpc();
@@ -210,17 +654,33 @@ export async function depositVoucher(
   console.log("Amount:", amount.toString());
 
   // Derive PDAs
-  const [poolState] = getPoolStatePDA(program.programId);
-  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
   const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
 
-  // Get ATAs
+  // Whitelist-gated pools need the user's entry; open pools ignore it
+  const [whitelistEntry] = getStakeWhitelistPDA(program.programId, poolState, user);
+  const stakeWhitelistEntry = (await program.provider.connection.getAccountInfo(whitelistEntry))
+    ? whitelistEntry
+    : null;
+
+  // History-recording pools need the user's next audit entry
+  const stakeActionRecord = await getNextStakeActionRecord(
+    program,
+    poolState,
+    userStakeRecord,
+    user
+  );
+
+  // Get ATAs (the receipt ATA is created by the deposit if needed)
   const userVoucherAta = await getAssociatedTokenAddress(voucherMint, user);
   const poolVaultAta = await getAssociatedTokenAddress(
     voucherMint,
     poolVaultAuthority,
     true
   );
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, user);
 
   console.log("User ATA:", userVoucherAta.toBase58());
   console.log("Pool Vault ATA:", poolVaultAta.toBase58());
@@ -234,10 +694,16 @@ export async function depositVoucher(
       poolDelegate: poolDelegate.publicKey,
       poolState: poolState,
       userStakeRecord: userStakeRecord,
+      stakeWhitelistEntry: stakeWhitelistEntry,
+      stakeActionRecord: stakeActionRecord,
       userVoucherAta: userVoucherAta,
       poolVaultAta: poolVaultAta,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      poolVaultAuthority: poolVaultAuthority,
       systemProgram: SystemProgram.programId,
       tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
     })
     .signers([poolDelegate])
     .rpc();
@@ -248,6 +714,64 @@ export async function depositVoucher(
   return tx;
 }
 
+/**
+ * Stake vouchers for a beneficiary, e.g. a partner staking for an employee
+ * The funder signs and pays from its own token account; only the beneficiary can redeem
+ *
+ * @param program - The Carsa Anchor program instance
+ * @param funder - The keypair paying the vouchers and any account rent
+ * @param beneficiary - The wallet the stake belongs to
+ * @param voucherMint - The LOKAL token mint address
+ * @param amount - The amount of tokens to deposit
+ * @returns Transaction signature
+ */
+export async function depositForBeneficiary(
+  program: Program<Carsa>,
+  funder: Keypair,
+  beneficiary: PublicKey,
+  voucherMint: PublicKey,
+  amount: anchor.BN
+): Promise<string> {
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, beneficiary);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
+
+  // Whitelist and history apply to the beneficiary, as for their own deposits
+  const [whitelistEntry] = getStakeWhitelistPDA(program.programId, poolState, beneficiary);
+  const stakeWhitelistEntry = (await program.provider.connection.getAccountInfo(whitelistEntry))
+    ? whitelistEntry
+    : null;
+  const stakeActionRecord = await getNextStakeActionRecord(
+    program,
+    poolState,
+    userStakeRecord,
+    beneficiary
+  );
+
+  return program.methods
+    .depositForBeneficiary(amount)
+    .accounts({
+      funder: funder.publicKey,
+      beneficiary: beneficiary,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+      stakeWhitelistEntry: stakeWhitelistEntry,
+      stakeActionRecord: stakeActionRecord,
+      funderVoucherAta: await getAssociatedTokenAddress(voucherMint, funder.publicKey),
+      poolVaultAta: await getAssociatedTokenAddress(voucherMint, poolVaultAuthority, true),
+      voucherMint: voucherMint,
+      receiptMint: receiptMint,
+      beneficiaryReceiptAta: await getAssociatedTokenAddress(receiptMint, beneficiary),
+      poolVaultAuthority: poolVaultAuthority,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    })
+    .signers([funder])
+    .rpc();
+}
+
 // ============================================================================
 // Record Yield
 // ============================================================================
@@ -258,24 +782,33 @@ export async function depositVoucher(
  * 
  * @param program - The Carsa Anchor program instance
  * @param poolDelegate - The delegate keypair (backend service)
+ * @param voucherMint - The LOKAL token mint address
  * @param solAmount - The amount of SOL yield earned
+ * @param batchId - 32-byte batch identifier; reuse it when retrying so the yield is not counted twice
+ * @param expectedPreviousIndex - Reward index the pool is expected to be at, or null to skip the check
  * @returns Transaction signature
  */
 export async function recordYield(
   program: Program<Carsa>,
   poolDelegate: Keypair,
-  solAmount: anchor.BN
+  voucherMint: PublicKey,
+  solAmount: anchor.BN,
+  batchId: Buffer,
+  expectedPreviousIndex: anchor.BN | null = null
 ): Promise<string> {
   console.log("\n📈 Recording Yield...");
   console.log("SOL Amount:", solAmount.toString());
 
-  const [poolState] = getPoolStatePDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [yieldBatch] = getYieldBatchPDA(program.programId, poolState, batchId);
 
   const tx = await program.methods
-    .recordYield(solAmount)
+    .recordYield(solAmount, Array.from(batchId), expectedPreviousIndex)
     .accounts({
       poolDelegate: poolDelegate.publicKey,
       poolState: poolState,
+      yieldBatch,
+      systemProgram: SystemProgram.programId,
     })
     .signers([poolDelegate])
     .rpc();
@@ -296,23 +829,32 @@ export async function recordYield(
  * @param program - The Carsa Anchor program instance
  * @param user - The user's keypair
  * @param voucherMint - The LOKAL token mint address
- * @param amount - The amount of vouchers to redeem
+ * @param amount - The amount of vouchers to redeem, or null to redeem the whole stake
  * @returns Transaction signature
  */
 export async function redeemVoucher(
   program: Program<Carsa>,
   user: Keypair,
   voucherMint: PublicKey,
-  amount: anchor.BN
+  amount: anchor.BN | null
 ): Promise<string> {
   console.log("\n💸 Redeeming Vouchers...");
   console.log("User:", user.publicKey.toBase58());
-  console.log("Amount:", amount.toString());
+  console.log("Amount:", amount === null ? "entire stake" : amount.toString());
 
   // Derive PDAs
-  const [poolState] = getPoolStatePDA(program.programId);
-  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
   const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user.publicKey);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
+
+  // History-recording pools need the user's next audit entry
+  const stakeActionRecord = await getNextStakeActionRecord(
+    program,
+    poolState,
+    userStakeRecord,
+    user.publicKey
+  );
 
   // Get ATAs
   const userVoucherAta = await getAssociatedTokenAddress(voucherMint, user.publicKey);
@@ -321,20 +863,27 @@ export async function redeemVoucher(
     poolVaultAuthority,
     true
   );
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, user.publicKey);
 
-  const tx = await program.methods
-    .redeemVoucher(amount)
-    .accounts({
-      user: user.publicKey,
-      poolState: poolState,
-      userStakeRecord: userStakeRecord,
-      userVoucherAta: userVoucherAta,
-      poolVaultAta: poolVaultAta,
-      poolVaultAuthority: poolVaultAuthority,
-      tokenProgram: TOKEN_PROGRAM_ID,
-    })
-    .signers([user])
-    .rpc();
+  const accounts = {
+    user: user.publicKey,
+    poolState: poolState,
+    userStakeRecord: userStakeRecord,
+    stakeActionRecord: stakeActionRecord,
+    userVoucherAta: userVoucherAta,
+    poolVaultAta: poolVaultAta,
+    poolVaultAuthority: poolVaultAuthority,
+    receiptMint: receiptMint,
+    userReceiptAta: userReceiptAta,
+    systemProgram: SystemProgram.programId,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  };
+
+  // Redeeming everything reads the stake on-chain, so nothing changed since is left behind
+  const tx =
+    amount === null
+      ? await program.methods.redeemAllVouchers().accounts(accounts).signers([user]).rpc()
+      : await program.methods.redeemVoucher(amount).accounts(accounts).signers([user]).rpc();
 
   console.log("✅ Vouchers redeemed successfully!");
   console.log("Transaction signature:", tx);
@@ -342,6 +891,143 @@ export async function redeemVoucher(
   return tx;
 }
 
+/**
+ * Queue staked vouchers for withdrawal in a queued-withdrawal pool
+ * The amount stops earning yield immediately
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param user - The user's keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @param amount - The amount of staked vouchers to queue
+ * @returns Transaction signature
+ */
+export async function requestUnstake(
+  program: Program<Carsa>,
+  user: Keypair,
+  voucherMint: PublicKey,
+  amount: anchor.BN
+): Promise<string> {
+  console.log("\n⏳ Requesting Unstake...");
+  console.log("User:", user.publicKey.toBase58());
+  console.log("Amount:", amount.toString());
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user.publicKey);
+  const [unstakeRequest] = getUnstakeRequestPDA(program.programId, poolState, user.publicKey);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, user.publicKey);
+
+  const tx = await program.methods
+    .requestUnstake(amount)
+    .accounts({
+      user: user.publicKey,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+      unstakeRequest: unstakeRequest,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([user])
+    .rpc();
+
+  console.log("✅ Unstake requested successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Cancel a pending unstake request and restake its amount
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param user - The user's keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @returns Transaction signature
+ */
+export async function cancelUnstake(
+  program: Program<Carsa>,
+  user: Keypair,
+  voucherMint: PublicKey
+): Promise<string> {
+  console.log("\n↩️ Cancelling Unstake...");
+  console.log("User:", user.publicKey.toBase58());
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user.publicKey);
+  const [unstakeRequest] = getUnstakeRequestPDA(program.programId, poolState, user.publicKey);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, user.publicKey);
+
+  const tx = await program.methods
+    .cancelUnstake()
+    .accounts({
+      user: user.publicKey,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+      unstakeRequest: unstakeRequest,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      poolVaultAuthority: poolVaultAuthority,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([user])
+    .rpc();
+
+  console.log("✅ Unstake cancelled successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Pay out a user's unstake request once the unstake delay has passed
+ * Permissionless; the provider wallet pays the transaction fee
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param voucherMint - The LOKAL token mint address
+ * @param user - The requesting user's public key
+ * @returns Transaction signature
+ */
+export async function processUnstake(
+  program: Program<Carsa>,
+  voucherMint: PublicKey,
+  user: PublicKey
+): Promise<string> {
+  console.log("\n📤 Processing Unstake...");
+  console.log("User:", user.toBase58());
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const [unstakeRequest] = getUnstakeRequestPDA(program.programId, poolState, user);
+  const userVoucherAta = await getAssociatedTokenAddress(voucherMint, user);
+  const poolVaultAta = await getAssociatedTokenAddress(
+    voucherMint,
+    poolVaultAuthority,
+    true
+  );
+
+  const tx = await program.methods
+    .processUnstake()
+    .accounts({
+      poolState: poolState,
+      unstakeRequest: unstakeRequest,
+      user: user,
+      userVoucherAta: userVoucherAta,
+      poolVaultAta: poolVaultAta,
+      poolVaultAuthority: poolVaultAuthority,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+  console.log("✅ Unstake processed successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
 // ============================================================================
 // Update Pool Configuration
 // ============================================================================
@@ -352,20 +1038,22 @@ export async function redeemVoucher(
  * 
  * @param program - The Carsa Anchor program instance
  * @param poolAuthority - The pool authority keypair
- * @param newConfig - New pool configuration
+ * @param voucherMint - The LOKAL token mint address
+ * @param update - Pool configuration fields to change
  * @returns Transaction signature
  */
 export async function updatePoolConfig(
   program: Program<Carsa>,
   poolAuthority: Keypair,
-  newConfig: PoolConfig
+  voucherMint: PublicKey,
+  update: PoolConfigUpdate
 ): Promise<string> {
   console.log("\n⚙️ Updating Pool Configuration...");
 
-  const [poolState] = getPoolStatePDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
 
   const tx = await program.methods
-    .updatePoolConfig(newConfig)
+    .updatePoolConfig(toConfigUpdate(update))
     .accounts({
       poolAuthority: poolAuthority.publicKey,
       poolState: poolState,
@@ -379,6 +1067,309 @@ export async function updatePoolConfig(
   return tx;
 }
 
+// ============================================================================
+// Auto Compounding
+// ============================================================================
+
+/**
+ * Opt the user's stake in or out of automatic yield compounding
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param user - The staking user's keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @param enabled - Whether the delegate may compound the stake's yield
+ * @returns Transaction signature
+ */
+export async function setAutoCompound(
+  program: Program<Carsa>,
+  user: Keypair,
+  voucherMint: PublicKey,
+  enabled: boolean
+): Promise<string> {
+  console.log("\n🔁 Setting Auto Compound...");
+  console.log("User:", user.publicKey.toBase58());
+  console.log("Enabled:", enabled);
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user.publicKey);
+
+  const tx = await program.methods
+    .setAutoCompound(enabled)
+    .accounts({
+      user: user.publicKey,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+    })
+    .signers([user])
+    .rpc();
+
+  console.log("✅ Auto compound updated successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Restake a user's claimable yield as vouchers bought by the delegate
+ * Only the pool delegate can perform this operation
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param poolDelegate - The pool delegate keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @param user - The staking user's public key
+ * @param rate - Lamports paid per whole LOKAL token
+ * @returns Transaction signature
+ */
+export async function compoundUser(
+  program: Program<Carsa>,
+  poolDelegate: Keypair,
+  voucherMint: PublicKey,
+  user: PublicKey,
+  rate: anchor.BN
+): Promise<string> {
+  console.log("\n🔁 Compounding Yield...");
+  console.log("User:", user.toBase58());
+  console.log("Rate:", rate.toString());
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user);
+  const [receiptMint] = getReceiptMintPDA(program.programId, voucherMint);
+
+  // The delegate supplies the purchased vouchers from its own ATA
+  const source = await getAssociatedTokenAddress(voucherMint, poolDelegate.publicKey);
+  const poolVaultAta = await getAssociatedTokenAddress(
+    voucherMint,
+    poolVaultAuthority,
+    true
+  );
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, user);
+
+  const tx = await program.methods
+    .compoundUser(rate)
+    .accounts({
+      poolDelegate: poolDelegate.publicKey,
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+      source: source,
+      poolVaultAta: poolVaultAta,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      poolVaultAuthority: poolVaultAuthority,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([poolDelegate])
+    .rpc();
+
+  console.log("✅ Yield compounded successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Settle accrued yield into pending payouts for a batch of stakers
+ * Only the pool delegate can perform this operation
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param poolDelegate - The pool delegate keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @param users - Public keys of the stakers to settle
+ * @returns Transaction signature
+ */
+export async function settleUsers(
+  program: Program<Carsa>,
+  poolDelegate: Keypair,
+  voucherMint: PublicKey,
+  users: PublicKey[]
+): Promise<string> {
+  console.log("\n🧾 Settling Users...");
+  console.log("Users:", users.length);
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const remainingAccounts = users.map((user) => ({
+    pubkey: getUserStakePDA(program.programId, poolState, user)[0],
+    isSigner: false,
+    isWritable: true,
+  }));
+
+  const tx = await program.methods
+    .settleUsers()
+    .accounts({
+      poolDelegate: poolDelegate.publicKey,
+      poolState: poolState,
+    })
+    .remainingAccounts(remainingAccounts)
+    .signers([poolDelegate])
+    .rpc();
+
+  console.log("✅ Users settled successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Accrue the pool's guaranteed minimum yield since the last yield update
+ * Permissionless; the provider wallet pays the transaction fee
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param voucherMint - The LOKAL token mint address
+ * @returns Transaction signature
+ */
+export async function accrueMinimumYield(
+  program: Program<Carsa>,
+  voucherMint: PublicKey
+): Promise<string> {
+  console.log("\n⏱️ Accruing Minimum Yield...");
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+
+  const tx = await program.methods
+    .accrueMinimumYield()
+    .accounts({
+      poolState: poolState,
+    })
+    .rpc();
+
+  console.log("✅ Minimum yield accrued successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Check the pool's accounting invariants and emit a report event
+ * Permissionless; in strict mode the transaction fails if any invariant is violated
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param voucherMint - The LOKAL token mint address
+ * @param strict - Whether a failed invariant should fail the transaction
+ * @returns Transaction signature
+ */
+export async function verifyPoolInvariants(
+  program: Program<Carsa>,
+  voucherMint: PublicKey,
+  strict: boolean
+): Promise<string> {
+  console.log("\n🩺 Verifying Pool Invariants...");
+  console.log("Strict:", strict);
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const poolVaultAta = await getAssociatedTokenAddress(
+    voucherMint,
+    poolVaultAuthority,
+    true
+  );
+
+  const tx = await program.methods
+    .verifyPoolInvariants(strict)
+    .accounts({
+      poolState: poolState,
+      poolVaultAta: poolVaultAta,
+    })
+    .rpc();
+
+  console.log("✅ Pool invariants verified!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+/**
+ * Draw from the insurance reserve to cover recorded losses
+ * Only the pool authority can perform this operation; the vouchers go to the
+ * pool's registered deployment destination
+ *
+ * @param program - The Carsa Anchor program instance
+ * @param poolAuthority - The pool authority keypair
+ * @param voucherMint - The LOKAL token mint address
+ * @param amount - Amount of reserve vouchers to draw
+ * @returns Transaction signature
+ */
+export async function drawInsurance(
+  program: Program<Carsa>,
+  poolAuthority: Keypair,
+  voucherMint: PublicKey,
+  amount: anchor.BN
+): Promise<string> {
+  console.log("\n🛟 Drawing Insurance Reserve...");
+  console.log("Amount:", amount.toString());
+
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
+  const [poolVaultAuthority] = getPoolVaultAuthorityPDA(program.programId, voucherMint);
+  const poolVaultAta = await getAssociatedTokenAddress(
+    voucherMint,
+    poolVaultAuthority,
+    true
+  );
+  const poolData = await program.account.poolState.fetch(poolState);
+
+  const tx = await program.methods
+    .drawInsurance(amount)
+    .accounts({
+      poolAuthority: poolAuthority.publicKey,
+      poolState: poolState,
+      poolVaultAta: poolVaultAta,
+      poolVaultAuthority: poolVaultAuthority,
+      destination: poolData.deploymentDestination,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    })
+    .signers([poolAuthority])
+    .rpc();
+
+  console.log("✅ Insurance drawn successfully!");
+  console.log("Transaction signature:", tx);
+
+  return tx;
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+// Instruction tag Anchor prefixes to self-CPI event data (EVENT_IX_TAG_LE)
+const EVENT_IX_TAG = Buffer.from([0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d]);
+
+/**
+ * Decode the program's events from a confirmed transaction
+ * Events are emitted with `emit_cpi!`, so they are read from the inner
+ * instructions, which survive log truncation
+ * 
+ * @param program - The Carsa Anchor program instance
+ * @param tx - The transaction fetched with `getTransaction`
+ * @returns The decoded events in emission order
+ */
+export function parseCpiEvents(
+  program: Program<Carsa>,
+  tx: anchor.web3.VersionedTransactionResponse
+): anchor.Event[] {
+  const accountKeys = tx.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta.loadedAddresses,
+  });
+
+  const events: anchor.Event[] = [];
+  for (const inner of tx.meta.innerInstructions ?? []) {
+    for (const ix of inner.instructions) {
+      if (!accountKeys.get(ix.programIdIndex).equals(program.programId)) {
+        continue;
+      }
+      const data = Buffer.from(anchor.utils.bytes.bs58.decode(ix.data));
+      if (!data.subarray(0, 8).equals(EVENT_IX_TAG)) {
+        continue;
+      }
+      const event = program.coder.events.decode(
+        anchor.utils.bytes.base64.encode(data.subarray(8))
+      );
+      if (event) {
+        events.push(event);
+      }
+    }
+  }
+  return events;
+}
+
 // ============================================================================
 // Query Functions
 // ============================================================================
@@ -387,9 +1378,10 @@ export async function updatePoolConfig(
  * Fetch pool state data
  */
 export async function getPoolState(
-  program: Program<Carsa>
+  program: Program<Carsa>,
+  voucherMint: PublicKey
 ): Promise<any> {
-  const [poolState] = getPoolStatePDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
   const poolData = await program.account.poolState.fetch(poolState);
   
   console.log("\n📊 Pool State:");
@@ -405,14 +1397,32 @@ export async function getPoolState(
   return poolData;
 }
 
+/**
+ * List the pools staking `voucherMint`, e.g. the pools of a regional token
+ * Also finds pools created before pool PDAs were keyed by mint, which sit at
+ * `getLegacyPoolStatePDA` addresses
+ */
+export async function findPoolsForMint(
+  program: Program<Carsa>,
+  voucherMint: PublicKey
+): Promise<{ poolId: number; poolState: PublicKey }[]> {
+  // Discriminator, then pool authority, pool delegate and vault before the mint
+  const VOUCHER_MINT_OFFSET = 8 + 32 + 32 + 32;
+  const pools = await program.account.poolState.all([
+    { memcmp: { offset: VOUCHER_MINT_OFFSET, bytes: voucherMint.toBase58() } },
+  ]);
+  return pools.map((pool) => ({ poolId: pool.account.poolId, poolState: pool.publicKey }));
+}
+
 /**
  * Fetch user stake record data
  */
 export async function getUserStakeRecord(
   program: Program<Carsa>,
+  voucherMint: PublicKey,
   user: PublicKey
 ): Promise<any> {
-  const [poolState] = getPoolStatePDA(program.programId);
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint);
   const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user);
   
   try {
@@ -431,6 +1441,53 @@ export async function getUserStakeRecord(
   }
 }
 
+/**
+ * Preview the yield a user would claim on redeem, by simulating the read-only
+ * preview instruction (no signatures beyond the fee payer)
+ */
+export async function previewClaimableYield(
+  program: Program<Carsa>,
+  user: PublicKey,
+  voucherMint: PublicKey,
+  poolId: number = 0
+): Promise<anchor.BN> {
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint, poolId);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user);
+
+  return program.methods
+    .previewClaimableYield(user)
+    .accounts({
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+    })
+    .view();
+}
+
+/**
+ * Refresh the pending yield and pool share cached on a user's stake record, then fetch it
+ * Anyone can pay for the refresh; the cache is otherwise only updated by deposits and
+ * redemptions
+ */
+export async function syncStakeView(
+  program: Program<Carsa>,
+  user: PublicKey,
+  voucherMint: PublicKey,
+  poolId: number = 0
+): Promise<any> {
+  const [poolState] = getPoolStatePDA(program.programId, voucherMint, poolId);
+  const [userStakeRecord] = getUserStakePDA(program.programId, poolState, user);
+
+  await program.methods
+    .syncStakeView()
+    .accounts({
+      poolState: poolState,
+      userStakeRecord: userStakeRecord,
+    })
+    .rpc({ commitment: "confirmed" });
+
+  return program.account.userStakeRecord.fetch(userStakeRecord, "confirmed");
+}
+
 // ============================================================================
 // Example Usage / Demo
 // ============================================================================
@@ -464,13 +1521,10 @@ export async function runDemo() {
   await new Promise(resolve => setTimeout(resolve, 2000)); // Wait for airdrops
 
   // 1. Initialize Pool
-  const poolConfig: PoolConfig = {
-    minStakeAmount: new anchor.BN(1_000_000), // 0.001 LOKAL (assuming 9 decimals)
-    maxStakePerUser: new anchor.BN(1_000_000_000_000), // 1,000 LOKAL
-    depositsEnabled: true,
-    withdrawalsEnabled: true,
-    apyBasisPoints: 1200, // 12% APY
-  };
+  // 12% APY, stakes between 0.001 and 1,000 LOKAL (9 decimals)
+  const poolConfig = defaultPoolConfig({
+    minStakeAmount: new anchor.BN(1_000_000),
+  });
 
   await initializePool(
     program,
@@ -481,7 +1535,7 @@ export async function runDemo() {
   );
 
   // 2. Query Pool State
-  await getPoolState(program);
+  await getPoolState(program, voucherMint);
 
   console.log("\n" + "=".repeat(60));
   console.log("✅ Demo completed successfully!");
@@ -38,6 +38,14 @@ function getPoolVaultAuthorityPDA(programId: PublicKey): PublicKey {
   return pda;
 }
 
+function getReceiptMintPDA(programId: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("receipt_mint")],
+    programId
+  );
+  return pda;
+}
+
 function getUserStakePDA(
   programId: PublicKey,
   poolState: PublicKey,
@@ -109,6 +117,8 @@ export async function processDeposit(userPubkey: PublicKey): Promise<string> {
     poolVaultAuthority,
     true
   );
+  const receiptMint = getReceiptMintPDA(program.programId);
+  const userReceiptAta = await getAssociatedTokenAddress(receiptMint, userPubkey);
 
   // Execute deposit
   const tx = await program.methods
@@ -118,6 +128,9 @@ export async function processDeposit(userPubkey: PublicKey): Promise<string> {
       poolDelegate: poolDelegate.publicKey,
       userVoucherAta: userAta,
       poolVaultAta: poolVaultAta,
+      receiptMint: receiptMint,
+      userReceiptAta: userReceiptAta,
+      poolVaultAuthority: poolVaultAuthority,
     })
     .signers([poolDelegate])
     .rpc();